@@ -2,6 +2,36 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
 mod m20251216_182846_create_users_table;
+mod m20261019_090000_create_lti_tables;
+mod m20261019_100000_create_xapi_tables;
+mod m20261019_110000_create_oidc_login_states_table;
+mod m20261019_120000_add_auth_source_to_users;
+mod m20261019_130000_create_two_factor_tables;
+mod m20261019_140000_create_password_tables;
+mod m20261019_150000_create_api_keys_table;
+mod m20261019_160000_create_impersonation_tables;
+mod m20261019_170000_create_audit_log_table;
+mod m20261019_180000_add_deleted_at_to_users;
+mod m20261019_190000_create_erasure_tables;
+mod m20261019_200000_create_user_profiles_table;
+mod m20261019_210000_create_tenants_table;
+mod m20261019_220000_create_fee_tables;
+mod m20261019_230000_create_payment_gateway_tables;
+mod m20261020_000000_create_library_tables;
+mod m20261020_010000_create_transport_tables;
+mod m20261020_020000_create_hostel_tables;
+mod m20261020_030000_create_admissions_tables;
+mod m20261020_040000_create_announcement_tables;
+mod m20261020_050000_create_messaging_tables;
+mod m20261020_060000_add_announcement_notified_at;
+mod m20261020_070000_create_email_outbox;
+mod m20261020_080000_create_lti_user_links;
+mod m20261020_090000_scope_xapi_attachments;
+mod m20261020_100000_add_audit_log_redaction;
+mod m20261020_110000_blank_sent_reset_emails;
+mod m20261020_120000_normalize_user_emails;
+mod m20261020_130000_add_directory_deactivated_at_to_users;
+mod m20261020_140000_add_password_changed_at_to_users;
 
 pub struct Migrator;
 
@@ -10,6 +40,36 @@ impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20251216_182846_create_users_table::Migration),
+            Box::new(m20261019_090000_create_lti_tables::Migration),
+            Box::new(m20261019_100000_create_xapi_tables::Migration),
+            Box::new(m20261019_110000_create_oidc_login_states_table::Migration),
+            Box::new(m20261019_120000_add_auth_source_to_users::Migration),
+            Box::new(m20261019_130000_create_two_factor_tables::Migration),
+            Box::new(m20261019_140000_create_password_tables::Migration),
+            Box::new(m20261019_150000_create_api_keys_table::Migration),
+            Box::new(m20261019_160000_create_impersonation_tables::Migration),
+            Box::new(m20261019_170000_create_audit_log_table::Migration),
+            Box::new(m20261019_180000_add_deleted_at_to_users::Migration),
+            Box::new(m20261019_190000_create_erasure_tables::Migration),
+            Box::new(m20261019_200000_create_user_profiles_table::Migration),
+            Box::new(m20261019_210000_create_tenants_table::Migration),
+            Box::new(m20261019_220000_create_fee_tables::Migration),
+            Box::new(m20261019_230000_create_payment_gateway_tables::Migration),
+            Box::new(m20261020_000000_create_library_tables::Migration),
+            Box::new(m20261020_010000_create_transport_tables::Migration),
+            Box::new(m20261020_020000_create_hostel_tables::Migration),
+            Box::new(m20261020_030000_create_admissions_tables::Migration),
+            Box::new(m20261020_040000_create_announcement_tables::Migration),
+            Box::new(m20261020_050000_create_messaging_tables::Migration),
+            Box::new(m20261020_060000_add_announcement_notified_at::Migration),
+            Box::new(m20261020_070000_create_email_outbox::Migration),
+            Box::new(m20261020_080000_create_lti_user_links::Migration),
+            Box::new(m20261020_090000_scope_xapi_attachments::Migration),
+            Box::new(m20261020_100000_add_audit_log_redaction::Migration),
+            Box::new(m20261020_110000_blank_sent_reset_emails::Migration),
+            Box::new(m20261020_120000_normalize_user_emails::Migration),
+            Box::new(m20261020_130000_add_directory_deactivated_at_to_users::Migration),
+            Box::new(m20261020_140000_add_password_changed_at_to_users::Migration),
         ]
     }
 }
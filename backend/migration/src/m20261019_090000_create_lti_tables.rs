@@ -0,0 +1,191 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LtiPlatforms::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LtiPlatforms::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LtiPlatforms::Name).string().not_null())
+                    .col(ColumnDef::new(LtiPlatforms::Issuer).string().not_null())
+                    .col(ColumnDef::new(LtiPlatforms::ClientId).string().not_null())
+                    .col(ColumnDef::new(LtiPlatforms::DeploymentId).string())
+                    .col(ColumnDef::new(LtiPlatforms::AuthLoginUrl).string().not_null())
+                    .col(ColumnDef::new(LtiPlatforms::AuthTokenUrl).string().not_null())
+                    .col(ColumnDef::new(LtiPlatforms::JwksUrl).string().not_null())
+                    .col(
+                        ColumnDef::new(LtiPlatforms::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(LtiPlatforms::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(LtiPlatforms::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_lti_platforms_issuer_client_id")
+                    .table(LtiPlatforms::Table)
+                    .col(LtiPlatforms::Issuer)
+                    .col(LtiPlatforms::ClientId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LtiLoginStates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LtiLoginStates::State)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LtiLoginStates::Nonce).string().not_null())
+                    .col(ColumnDef::new(LtiLoginStates::PlatformId).integer().not_null())
+                    .col(ColumnDef::new(LtiLoginStates::TargetLinkUri).string().not_null())
+                    .col(ColumnDef::new(LtiLoginStates::ExpiresAt).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(LtiLoginStates::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_lti_login_states_platform_id")
+                            .from(LtiLoginStates::Table, LtiLoginStates::PlatformId)
+                            .to(LtiPlatforms::Table, LtiPlatforms::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LtiLaunches::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LtiLaunches::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LtiLaunches::PlatformId).integer().not_null())
+                    .col(ColumnDef::new(LtiLaunches::UserId).integer())
+                    .col(ColumnDef::new(LtiLaunches::Subject).string().not_null())
+                    .col(ColumnDef::new(LtiLaunches::MessageType).string().not_null())
+                    .col(ColumnDef::new(LtiLaunches::Claims).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(LtiLaunches::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_lti_launches_platform_id")
+                            .from(LtiLaunches::Table, LtiLaunches::PlatformId)
+                            .to(LtiPlatforms::Table, LtiPlatforms::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_lti_launches_user_id")
+                            .from(LtiLaunches::Table, LtiLaunches::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LtiLaunches::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LtiLoginStates::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LtiPlatforms::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LtiPlatforms {
+    Table,
+    Id,
+    Name,
+    Issuer,
+    ClientId,
+    DeploymentId,
+    AuthLoginUrl,
+    AuthTokenUrl,
+    JwksUrl,
+    IsActive,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LtiLoginStates {
+    Table,
+    State,
+    Nonce,
+    PlatformId,
+    TargetLinkUri,
+    ExpiresAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LtiLaunches {
+    Table,
+    Id,
+    PlatformId,
+    UserId,
+    Subject,
+    MessageType,
+    Claims,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
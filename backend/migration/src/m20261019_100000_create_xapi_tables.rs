@@ -0,0 +1,133 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(XapiStatements::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(XapiStatements::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(XapiStatements::ActorIfi).string().not_null())
+                    .col(ColumnDef::new(XapiStatements::UserId).integer())
+                    .col(ColumnDef::new(XapiStatements::VerbId).string().not_null())
+                    .col(ColumnDef::new(XapiStatements::ObjectType).string().not_null())
+                    .col(ColumnDef::new(XapiStatements::ObjectId).string())
+                    .col(ColumnDef::new(XapiStatements::Registration).string())
+                    .col(
+                        ColumnDef::new(XapiStatements::Voided)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(XapiStatements::Statement).json_binary().not_null())
+                    .col(ColumnDef::new(XapiStatements::Timestamp).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(XapiStatements::Stored)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_xapi_statements_user_id")
+                            .from(XapiStatements::Table, XapiStatements::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        for (name, column) in [
+            ("idx_xapi_statements_actor_ifi", XapiStatements::ActorIfi),
+            ("idx_xapi_statements_verb_id", XapiStatements::VerbId),
+            ("idx_xapi_statements_object_id", XapiStatements::ObjectId),
+            ("idx_xapi_statements_stored", XapiStatements::Stored),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(XapiStatements::Table)
+                        .col(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(XapiAttachments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(XapiAttachments::Sha2)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(XapiAttachments::ContentType).string().not_null())
+                    .col(ColumnDef::new(XapiAttachments::Length).big_integer().not_null())
+                    .col(ColumnDef::new(XapiAttachments::Content).binary().not_null())
+                    .col(
+                        ColumnDef::new(XapiAttachments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(XapiAttachments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(XapiStatements::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum XapiStatements {
+    Table,
+    Id,
+    ActorIfi,
+    UserId,
+    VerbId,
+    ObjectType,
+    ObjectId,
+    Registration,
+    Voided,
+    Statement,
+    Timestamp,
+    Stored,
+}
+
+#[derive(DeriveIden)]
+enum XapiAttachments {
+    Table,
+    Sha2,
+    ContentType,
+    Length,
+    Content,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OidcLoginStates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OidcLoginStates::State)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OidcLoginStates::Nonce).string().not_null())
+                    .col(ColumnDef::new(OidcLoginStates::CodeVerifier).string().not_null())
+                    .col(ColumnDef::new(OidcLoginStates::ExpiresAt).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(OidcLoginStates::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OidcLoginStates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OidcLoginStates {
+    Table,
+    State,
+    Nonce,
+    CodeVerifier,
+    ExpiresAt,
+    CreatedAt,
+}
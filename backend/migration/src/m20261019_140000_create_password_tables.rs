@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PasswordHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PasswordHistory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PasswordHistory::UserId).integer().not_null())
+                    .col(ColumnDef::new(PasswordHistory::PasswordHash).string().not_null())
+                    .col(
+                        ColumnDef::new(PasswordHistory::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_password_history_user_id")
+                            .from(PasswordHistory::Table, PasswordHistory::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_password_history_user_id")
+                    .table(PasswordHistory::Table)
+                    .col(PasswordHistory::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PasswordResetTokens::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PasswordResetTokens::TokenHash)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PasswordResetTokens::UserId).integer().not_null())
+                    .col(ColumnDef::new(PasswordResetTokens::ExpiresAt).timestamp().not_null())
+                    .col(ColumnDef::new(PasswordResetTokens::UsedAt).timestamp())
+                    .col(
+                        ColumnDef::new(PasswordResetTokens::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_password_reset_tokens_user_id")
+                            .from(PasswordResetTokens::Table, PasswordResetTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PasswordResetTokens::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(PasswordHistory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PasswordHistory {
+    Table,
+    Id,
+    UserId,
+    PasswordHash,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum PasswordResetTokens {
+    Table,
+    TokenHash,
+    UserId,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,134 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImpersonationSessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImpersonationSessions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ImpersonationSessions::AdminId).integer().not_null())
+                    .col(ColumnDef::new(ImpersonationSessions::TargetUserId).integer().not_null())
+                    .col(ColumnDef::new(ImpersonationSessions::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(ImpersonationSessions::StartedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ImpersonationSessions::ExpiresAt).timestamp().not_null())
+                    .col(ColumnDef::new(ImpersonationSessions::EndedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_impersonation_sessions_admin_id")
+                            .from(ImpersonationSessions::Table, ImpersonationSessions::AdminId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_impersonation_sessions_target_user_id")
+                            .from(ImpersonationSessions::Table, ImpersonationSessions::TargetUserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One row per request made while impersonating
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImpersonationActions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImpersonationActions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ImpersonationActions::SessionId).integer().not_null())
+                    .col(ColumnDef::new(ImpersonationActions::Method).string().not_null())
+                    .col(ColumnDef::new(ImpersonationActions::Path).text().not_null())
+                    .col(ColumnDef::new(ImpersonationActions::Status).integer().not_null())
+                    .col(ColumnDef::new(ImpersonationActions::IpAddress).string())
+                    .col(
+                        ColumnDef::new(ImpersonationActions::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_impersonation_actions_session_id")
+                            .from(ImpersonationActions::Table, ImpersonationActions::SessionId)
+                            .to(ImpersonationSessions::Table, ImpersonationSessions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_impersonation_actions_session_id")
+                    .table(ImpersonationActions::Table)
+                    .col(ImpersonationActions::SessionId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImpersonationActions::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ImpersonationSessions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ImpersonationSessions {
+    Table,
+    Id,
+    AdminId,
+    TargetUserId,
+    Reason,
+    StartedAt,
+    ExpiresAt,
+    EndedAt,
+}
+
+#[derive(DeriveIden)]
+enum ImpersonationActions {
+    Table,
+    Id,
+    SessionId,
+    Method,
+    Path,
+    Status,
+    IpAddress,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
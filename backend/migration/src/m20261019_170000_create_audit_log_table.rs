@@ -0,0 +1,119 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // No foreign keys: entries must outlive the users and records they mention
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::ActorId).integer())
+                    .col(ColumnDef::new(AuditLog::ActorEmail).string())
+                    .col(ColumnDef::new(AuditLog::ImpersonatorId).integer())
+                    .col(ColumnDef::new(AuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Entity).string().not_null())
+                    .col(ColumnDef::new(AuditLog::EntityId).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Before).json_binary())
+                    .col(ColumnDef::new(AuditLog::After).json_binary())
+                    .col(ColumnDef::new(AuditLog::IpAddress).string())
+                    .col(ColumnDef::new(AuditLog::RequestId).string())
+                    .col(ColumnDef::new(AuditLog::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(AuditLog::PrevHash).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Hash).string().not_null().unique_key())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_entity")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::Entity)
+                    .col(AuditLog::EntityId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_actor_id")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::ActorId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Append-only at the database level too; the hash chain catches
+        // anyone who gets around this (e.g. a superuser dropping the trigger)
+        if manager.get_database_backend() == DbBackend::Postgres {
+            let db = manager.get_connection();
+            db.execute_unprepared(
+                "CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS trigger AS $$ \
+                 BEGIN RAISE EXCEPTION 'audit_log is append-only'; END; \
+                 $$ LANGUAGE plpgsql",
+            )
+            .await?;
+            db.execute_unprepared(
+                "CREATE TRIGGER audit_log_no_change BEFORE UPDATE OR DELETE ON audit_log \
+                 FOR EACH ROW EXECUTE FUNCTION audit_log_append_only()",
+            )
+            .await?;
+            db.execute_unprepared(
+                "CREATE TRIGGER audit_log_no_truncate BEFORE TRUNCATE ON audit_log \
+                 FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only()",
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await?;
+
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared("DROP FUNCTION IF EXISTS audit_log_append_only()")
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditLog {
+    Table,
+    Id,
+    ActorId,
+    ActorEmail,
+    ImpersonatorId,
+    Action,
+    Entity,
+    EntityId,
+    Before,
+    After,
+    IpAddress,
+    RequestId,
+    CreatedAt,
+    PrevHash,
+    Hash,
+}
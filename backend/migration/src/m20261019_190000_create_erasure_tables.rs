@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // No foreign key to users: the record of an erasure has to outlive a purge
+        manager
+            .create_table(
+                Table::create()
+                    .table(ErasureRequests::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ErasureRequests::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ErasureRequests::UserId).integer().not_null())
+                    .col(ColumnDef::new(ErasureRequests::RequestedBy).integer().not_null())
+                    .col(ColumnDef::new(ErasureRequests::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(ErasureRequests::Status)
+                            .string()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(ErasureRequests::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ErasureRequests::CompletedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // What happened to each request and who did it: requested, approved,
+        // rejected, completed
+        manager
+            .create_table(
+                Table::create()
+                    .table(ErasureRequestEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ErasureRequestEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ErasureRequestEvents::RequestId).integer().not_null())
+                    .col(ColumnDef::new(ErasureRequestEvents::ActorId).integer())
+                    .col(ColumnDef::new(ErasureRequestEvents::Event).string().not_null())
+                    .col(ColumnDef::new(ErasureRequestEvents::Comment).text())
+                    .col(
+                        ColumnDef::new(ErasureRequestEvents::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_erasure_request_events_request_id")
+                            .from(ErasureRequestEvents::Table, ErasureRequestEvents::RequestId)
+                            .to(ErasureRequests::Table, ErasureRequests::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ErasureRequestEvents::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ErasureRequests::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ErasureRequests {
+    Table,
+    Id,
+    UserId,
+    RequestedBy,
+    Reason,
+    Status,
+    CreatedAt,
+    CompletedAt,
+}
+
+#[derive(DeriveIden)]
+enum ErasureRequestEvents {
+    Table,
+    Id,
+    RequestId,
+    ActorId,
+    Event,
+    Comment,
+    CreatedAt,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Sensitive profile fields, stored encrypted. `key_id` is the master
+        // key the row was last sealed with, so rotation can find stale rows.
+        // `national_id_index` is a keyed hash for exact-match lookups.
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserProfiles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserProfiles::UserId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserProfiles::KeyId).string().not_null())
+                    .col(ColumnDef::new(UserProfiles::NationalId).text())
+                    .col(ColumnDef::new(UserProfiles::NationalIdIndex).string())
+                    .col(ColumnDef::new(UserProfiles::Address).text())
+                    .col(ColumnDef::new(UserProfiles::MedicalNotes).text())
+                    .col(
+                        ColumnDef::new(UserProfiles::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_profiles_user_id")
+                            .from(UserProfiles::Table, UserProfiles::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_profiles_national_id_index")
+                    .table(UserProfiles::Table)
+                    .col(UserProfiles::NationalIdIndex)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_profiles_key_id")
+                    .table(UserProfiles::Table)
+                    .col(UserProfiles::KeyId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserProfiles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProfiles {
+    Table,
+    UserId,
+    KeyId,
+    NationalId,
+    NationalIdIndex,
+    Address,
+    MedicalNotes,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,216 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tables whose rows belong to a school directly. Everything else hangs off one
+// of these (a user's keys and profile, a platform's launches, a request's
+// events) and is scoped through it.
+const SCOPED_TABLES: &[&str] = &[
+    "users",
+    "lti_platforms",
+    "xapi_statements",
+    "oidc_login_states",
+    "impersonation_sessions",
+    "erasure_requests",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A school (or campus) sharing this deployment, with its own settings.
+        // An empty grading scale means the built-in A-F scale.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tenants::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tenants::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tenants::Slug).string().not_null().unique_key())
+                    .col(ColumnDef::new(Tenants::Name).string().not_null())
+                    .col(ColumnDef::new(Tenants::LogoUrl).string())
+                    .col(
+                        ColumnDef::new(Tenants::GradingScale)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'[]'")),
+                    )
+                    .col(
+                        ColumnDef::new(Tenants::Timezone)
+                            .string()
+                            .not_null()
+                            .default("UTC"),
+                    )
+                    .col(
+                        ColumnDef::new(Tenants::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(Tenants::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Tenants::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The school everything so far belongs to; it gets id 1
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Tenants::Table)
+                    .columns([Tenants::Slug, Tenants::Name])
+                    .values_panic(["default".into(), "rsEdu".into()])
+                    .to_owned(),
+            )
+            .await?;
+
+        for table in SCOPED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(*table))
+                        .add_column(ColumnDef::new(Tenants::TenantId).integer().not_null().default(1))
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name(format!("fk_{}_tenant_id", table))
+                                .from_tbl(Alias::new(*table))
+                                .from_col(Tenants::TenantId)
+                                .to_tbl(Tenants::Table)
+                                .to_col(Tenants::Id)
+                                .on_delete(ForeignKeyAction::Restrict),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("idx_{}_tenant_id", table))
+                        .table(Alias::new(*table))
+                        .col(Tenants::TenantId)
+                        .to_owned(),
+                )
+                .await?;
+            // Existing rows went to the default school; new rows must say where they belong
+            if manager.get_database_backend() == DbBackend::Postgres {
+                manager
+                    .get_connection()
+                    .execute_unprepared(&format!("ALTER TABLE {} ALTER COLUMN tenant_id DROP DEFAULT", table))
+                    .await?;
+            }
+        }
+
+        // Emails are unique per school, not across the deployment
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared("ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key")
+                .await?;
+        }
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_users_tenant_id_email")
+                    .table(Alias::new("users"))
+                    .col(Tenants::TenantId)
+                    .col(Alias::new("email"))
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Audit entries cannot be updated, so entries from before tenancy keep
+        // a NULL school (and their hashes); no foreign key, like the rest of the log
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new("audit_log"))
+                    .add_column(ColumnDef::new(Tenants::TenantId).integer())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_tenant_id")
+                    .table(Alias::new("audit_log"))
+                    .col(Tenants::TenantId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new("audit_log"))
+                    .drop_column(Tenants::TenantId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_users_tenant_id_email")
+                    .table(Alias::new("users"))
+                    .to_owned(),
+            )
+            .await?;
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared("ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email)")
+                .await?;
+        }
+
+        for table in SCOPED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(*table))
+                        .drop_foreign_key(Alias::new(format!("fk_{}_tenant_id", table)))
+                        .drop_column(Tenants::TenantId)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(Tenants::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+    Slug,
+    Name,
+    LogoUrl,
+    GradingScale,
+    Timezone,
+    IsActive,
+    CreatedAt,
+    UpdatedAt,
+    TenantId,
+}
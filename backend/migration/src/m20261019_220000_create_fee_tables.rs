@@ -0,0 +1,383 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Money columns: exact decimals with two places, never floats
+fn money(column: impl IntoIden) -> ColumnDef {
+    ColumnDef::new(column).decimal_len(12, 2).not_null().to_owned()
+}
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // What a grade pays for a term, e.g. "Grade 5" in "2026 Term 1"
+        manager
+            .create_table(
+                Table::create()
+                    .table(FeeSchedules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FeeSchedules::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FeeSchedules::TenantId).integer().not_null())
+                    .col(ColumnDef::new(FeeSchedules::Name).string().not_null())
+                    .col(ColumnDef::new(FeeSchedules::Grade).string().not_null())
+                    .col(ColumnDef::new(FeeSchedules::Term).string().not_null())
+                    .col(ColumnDef::new(FeeSchedules::DueDate).date().not_null())
+                    .col(
+                        ColumnDef::new(FeeSchedules::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(FeeSchedules::Table, "fk_fee_schedules_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // One fee of a schedule: tuition, transport, lab, exam or other
+        manager
+            .create_table(
+                Table::create()
+                    .table(FeeItems::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FeeItems::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FeeItems::ScheduleId).integer().not_null())
+                    .col(ColumnDef::new(FeeItems::Category).string().not_null())
+                    .col(ColumnDef::new(FeeItems::Description).string().not_null())
+                    .col(money(FeeItems::Amount))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_fee_items_schedule_id")
+                            .from(FeeItems::Table, FeeItems::ScheduleId)
+                            .to(FeeSchedules::Table, FeeSchedules::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A student's discount or scholarship: a percentage or a fixed amount,
+        // off one category or all fees, for one term or every term
+        manager
+            .create_table(
+                Table::create()
+                    .table(FeeDiscounts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FeeDiscounts::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FeeDiscounts::TenantId).integer().not_null())
+                    .col(ColumnDef::new(FeeDiscounts::StudentId).integer().not_null())
+                    .col(ColumnDef::new(FeeDiscounts::Kind).string().not_null())
+                    .col(ColumnDef::new(FeeDiscounts::Name).string().not_null())
+                    .col(ColumnDef::new(FeeDiscounts::Percent).decimal_len(5, 2))
+                    .col(ColumnDef::new(FeeDiscounts::Amount).decimal_len(12, 2))
+                    .col(ColumnDef::new(FeeDiscounts::Category).string())
+                    .col(ColumnDef::new(FeeDiscounts::Term).string())
+                    .col(
+                        ColumnDef::new(FeeDiscounts::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(FeeDiscounts::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(FeeDiscounts::Table, "fk_fee_discounts_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_fee_discounts_student_id")
+                            .from(FeeDiscounts::Table, FeeDiscounts::StudentId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Invoices and payments are financial records: no foreign key to
+        // users, so they outlive a purge. Totals are kept on the invoice so
+        // balances and aging do not have to add up lines.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Invoices::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Invoices::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Invoices::TenantId).integer().not_null())
+                    .col(ColumnDef::new(Invoices::StudentId).integer().not_null())
+                    .col(ColumnDef::new(Invoices::ScheduleId).integer().not_null())
+                    .col(ColumnDef::new(Invoices::Term).string().not_null())
+                    .col(ColumnDef::new(Invoices::DueDate).date().not_null())
+                    .col(money(Invoices::Subtotal))
+                    .col(money(Invoices::DiscountTotal))
+                    .col(money(Invoices::Total))
+                    .col(money(Invoices::AmountPaid).default(0).to_owned())
+                    .col(
+                        ColumnDef::new(Invoices::Status)
+                            .string()
+                            .not_null()
+                            .default("open"),
+                    )
+                    .col(
+                        ColumnDef::new(Invoices::IssuedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(Invoices::Table, "fk_invoices_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invoices_schedule_id")
+                            .from(Invoices::Table, Invoices::ScheduleId)
+                            .to(FeeSchedules::Table, FeeSchedules::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A student is invoiced once per schedule, so generation can be re-run
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invoices_student_id_schedule_id")
+                    .table(Invoices::Table)
+                    .col(Invoices::StudentId)
+                    .col(Invoices::ScheduleId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invoices_tenant_id_status")
+                    .table(Invoices::Table)
+                    .col(Invoices::TenantId)
+                    .col(Invoices::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Fees and discounts as billed; discounts are negative
+        manager
+            .create_table(
+                Table::create()
+                    .table(InvoiceLines::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InvoiceLines::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(InvoiceLines::InvoiceId).integer().not_null())
+                    .col(ColumnDef::new(InvoiceLines::Category).string().not_null())
+                    .col(ColumnDef::new(InvoiceLines::Description).string().not_null())
+                    .col(money(InvoiceLines::Amount))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invoice_lines_invoice_id")
+                            .from(InvoiceLines::Table, InvoiceLines::InvoiceId)
+                            .to(Invoices::Table, Invoices::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Payments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Payments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Payments::TenantId).integer().not_null())
+                    .col(ColumnDef::new(Payments::InvoiceId).integer().not_null())
+                    .col(money(Payments::Amount))
+                    .col(ColumnDef::new(Payments::Method).string().not_null())
+                    .col(ColumnDef::new(Payments::Reference).string())
+                    .col(ColumnDef::new(Payments::RecordedBy).integer())
+                    .col(
+                        ColumnDef::new(Payments::ReceivedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(Payments::Table, "fk_payments_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_payments_invoice_id")
+                            .from(Payments::Table, Payments::InvoiceId)
+                            .to(Invoices::Table, Invoices::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_payments_invoice_id")
+                    .table(Payments::Table)
+                    .col(Payments::InvoiceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Payments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(InvoiceLines::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Invoices::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(FeeDiscounts::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(FeeItems::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(FeeSchedules::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum FeeSchedules {
+    Table,
+    Id,
+    TenantId,
+    Name,
+    Grade,
+    Term,
+    DueDate,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum FeeItems {
+    Table,
+    Id,
+    ScheduleId,
+    Category,
+    Description,
+    Amount,
+}
+
+#[derive(DeriveIden)]
+enum FeeDiscounts {
+    Table,
+    Id,
+    TenantId,
+    StudentId,
+    Kind,
+    Name,
+    Percent,
+    Amount,
+    Category,
+    Term,
+    IsActive,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Invoices {
+    Table,
+    Id,
+    TenantId,
+    StudentId,
+    ScheduleId,
+    Term,
+    DueDate,
+    Subtotal,
+    DiscountTotal,
+    Total,
+    AmountPaid,
+    Status,
+    IssuedAt,
+}
+
+#[derive(DeriveIden)]
+enum InvoiceLines {
+    Table,
+    Id,
+    InvoiceId,
+    Category,
+    Description,
+    Amount,
+}
+
+#[derive(DeriveIden)]
+enum Payments {
+    Table,
+    Id,
+    TenantId,
+    InvoiceId,
+    Amount,
+    Method,
+    Reference,
+    RecordedBy,
+    ReceivedAt,
+}
@@ -0,0 +1,292 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Online payments carry the provider's id, which is what refunds and
+        // reconciliation refer to; one provider payment is recorded only once
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Payments::Table)
+                    .add_column(ColumnDef::new(Payments::ProviderPaymentId).string())
+                    .add_column(
+                        ColumnDef::new(Payments::RefundedAmount)
+                            .decimal_len(12, 2)
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_payments_provider_payment_id")
+                    .table(Payments::Table)
+                    .col(Payments::ProviderPaymentId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // A hosted checkout started for (part of) an invoice's balance
+        manager
+            .create_table(
+                Table::create()
+                    .table(CheckoutSessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CheckoutSessions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CheckoutSessions::TenantId).integer().not_null())
+                    .col(ColumnDef::new(CheckoutSessions::InvoiceId).integer().not_null())
+                    .col(ColumnDef::new(CheckoutSessions::Provider).string().not_null())
+                    .col(ColumnDef::new(CheckoutSessions::ProviderSessionId).string().not_null())
+                    .col(ColumnDef::new(CheckoutSessions::Amount).decimal_len(12, 2).not_null())
+                    .col(ColumnDef::new(CheckoutSessions::Currency).string().not_null())
+                    .col(ColumnDef::new(CheckoutSessions::CheckoutUrl).string().not_null())
+                    .col(
+                        ColumnDef::new(CheckoutSessions::Status)
+                            .string()
+                            .not_null()
+                            .default("open"),
+                    )
+                    .col(ColumnDef::new(CheckoutSessions::PaymentId).integer())
+                    .col(ColumnDef::new(CheckoutSessions::CreatedBy).integer())
+                    .col(
+                        ColumnDef::new(CheckoutSessions::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(CheckoutSessions::CompletedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_checkout_sessions_tenant_id")
+                            .from(CheckoutSessions::Table, CheckoutSessions::TenantId)
+                            .to(Tenants::Table, Tenants::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_checkout_sessions_invoice_id")
+                            .from(CheckoutSessions::Table, CheckoutSessions::InvoiceId)
+                            .to(Invoices::Table, Invoices::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_checkout_sessions_provider_session")
+                    .table(CheckoutSessions::Table)
+                    .col(CheckoutSessions::Provider)
+                    .col(CheckoutSessions::ProviderSessionId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every webhook event handled, so a redelivered event is a no-op
+        manager
+            .create_table(
+                Table::create()
+                    .table(PaymentWebhookEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PaymentWebhookEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PaymentWebhookEvents::Provider).string().not_null())
+                    .col(ColumnDef::new(PaymentWebhookEvents::EventId).string().not_null())
+                    .col(ColumnDef::new(PaymentWebhookEvents::EventType).string().not_null())
+                    .col(
+                        ColumnDef::new(PaymentWebhookEvents::ReceivedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_payment_webhook_events_provider_event_id")
+                    .table(PaymentWebhookEvents::Table)
+                    .col(PaymentWebhookEvents::Provider)
+                    .col(PaymentWebhookEvents::EventId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Money given back on a payment; online refunds may settle later
+        manager
+            .create_table(
+                Table::create()
+                    .table(Refunds::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Refunds::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Refunds::TenantId).integer().not_null())
+                    .col(ColumnDef::new(Refunds::PaymentId).integer().not_null())
+                    .col(ColumnDef::new(Refunds::Amount).decimal_len(12, 2).not_null())
+                    .col(ColumnDef::new(Refunds::Reason).string().not_null())
+                    .col(
+                        ColumnDef::new(Refunds::Status)
+                            .string()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(ColumnDef::new(Refunds::ProviderRefundId).string())
+                    .col(ColumnDef::new(Refunds::RequestedBy).integer())
+                    .col(
+                        ColumnDef::new(Refunds::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Refunds::SettledAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_refunds_tenant_id")
+                            .from(Refunds::Table, Refunds::TenantId)
+                            .to(Tenants::Table, Tenants::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_refunds_payment_id")
+                            .from(Refunds::Table, Refunds::PaymentId)
+                            .to(Payments::Table, Payments::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_refunds_provider_refund_id")
+                    .table(Refunds::Table)
+                    .col(Refunds::ProviderRefundId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Refunds::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(PaymentWebhookEvents::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(CheckoutSessions::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_payments_provider_payment_id")
+                    .table(Payments::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Payments::Table)
+                    .drop_column(Payments::RefundedAmount)
+                    .drop_column(Payments::ProviderPaymentId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Invoices {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Payments {
+    Table,
+    Id,
+    ProviderPaymentId,
+    RefundedAmount,
+}
+
+#[derive(DeriveIden)]
+enum CheckoutSessions {
+    Table,
+    Id,
+    TenantId,
+    InvoiceId,
+    Provider,
+    ProviderSessionId,
+    Amount,
+    Currency,
+    CheckoutUrl,
+    Status,
+    PaymentId,
+    CreatedBy,
+    CreatedAt,
+    CompletedAt,
+}
+
+#[derive(DeriveIden)]
+enum PaymentWebhookEvents {
+    Table,
+    Id,
+    Provider,
+    EventId,
+    EventType,
+    ReceivedAt,
+}
+
+#[derive(DeriveIden)]
+enum Refunds {
+    Table,
+    Id,
+    TenantId,
+    PaymentId,
+    Amount,
+    Reason,
+    Status,
+    ProviderRefundId,
+    RequestedBy,
+    CreatedAt,
+    SettledAt,
+}
@@ -0,0 +1,329 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A book (or other item) in the catalog; copies are what get lent
+        manager
+            .create_table(
+                Table::create()
+                    .table(LibraryTitles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LibraryTitles::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LibraryTitles::TenantId).integer().not_null())
+                    .col(ColumnDef::new(LibraryTitles::Isbn).string())
+                    .col(ColumnDef::new(LibraryTitles::Title).string().not_null())
+                    .col(ColumnDef::new(LibraryTitles::Author).string())
+                    .col(ColumnDef::new(LibraryTitles::Publisher).string())
+                    .col(ColumnDef::new(LibraryTitles::PublishedYear).integer())
+                    .col(
+                        ColumnDef::new(LibraryTitles::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(LibraryTitles::Table, "fk_library_titles_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_library_titles_tenant_id_isbn")
+                    .table(LibraryTitles::Table)
+                    .col(LibraryTitles::TenantId)
+                    .col(LibraryTitles::Isbn)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LibraryCopies::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LibraryCopies::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LibraryCopies::TenantId).integer().not_null())
+                    .col(ColumnDef::new(LibraryCopies::TitleId).integer().not_null())
+                    .col(ColumnDef::new(LibraryCopies::Barcode).string().not_null())
+                    .col(ColumnDef::new(LibraryCopies::Location).string())
+                    .col(
+                        ColumnDef::new(LibraryCopies::Status)
+                            .string()
+                            .not_null()
+                            .default("available"),
+                    )
+                    .col(
+                        ColumnDef::new(LibraryCopies::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(LibraryCopies::Table, "fk_library_copies_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_library_copies_title_id")
+                            .from(LibraryCopies::Table, LibraryCopies::TitleId)
+                            .to(LibraryTitles::Table, LibraryTitles::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A barcode identifies one copy within a school
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_library_copies_tenant_id_barcode")
+                    .table(LibraryCopies::Table)
+                    .col(LibraryCopies::TenantId)
+                    .col(LibraryCopies::Barcode)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Loans carry their fine, so like invoices they have no foreign key
+        // to users and outlive a purge
+        manager
+            .create_table(
+                Table::create()
+                    .table(LibraryLoans::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LibraryLoans::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LibraryLoans::TenantId).integer().not_null())
+                    .col(ColumnDef::new(LibraryLoans::CopyId).integer().not_null())
+                    .col(ColumnDef::new(LibraryLoans::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(LibraryLoans::CheckedOutAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(LibraryLoans::DueDate).date().not_null())
+                    .col(ColumnDef::new(LibraryLoans::ReturnedAt).timestamp())
+                    .col(
+                        ColumnDef::new(LibraryLoans::Renewals)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(LibraryLoans::FineAmount)
+                            .decimal_len(12, 2)
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(LibraryLoans::FineStatus)
+                            .string()
+                            .not_null()
+                            .default("none"),
+                    )
+                    .col(ColumnDef::new(LibraryLoans::CheckedOutBy).integer())
+                    .foreign_key(&mut tenant_key(LibraryLoans::Table, "fk_library_loans_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_library_loans_copy_id")
+                            .from(LibraryLoans::Table, LibraryLoans::CopyId)
+                            .to(LibraryCopies::Table, LibraryCopies::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_library_loans_user_id")
+                    .table(LibraryLoans::Table)
+                    .col(LibraryLoans::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user's place in the queue for a title. When a copy comes back it
+        // is set aside for the first waiting hold until the hold expires.
+        manager
+            .create_table(
+                Table::create()
+                    .table(LibraryHolds::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LibraryHolds::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LibraryHolds::TenantId).integer().not_null())
+                    .col(ColumnDef::new(LibraryHolds::TitleId).integer().not_null())
+                    .col(ColumnDef::new(LibraryHolds::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(LibraryHolds::Status)
+                            .string()
+                            .not_null()
+                            .default("waiting"),
+                    )
+                    .col(ColumnDef::new(LibraryHolds::CopyId).integer())
+                    .col(
+                        ColumnDef::new(LibraryHolds::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(LibraryHolds::ReadyAt).timestamp())
+                    .col(ColumnDef::new(LibraryHolds::ExpiresOn).date())
+                    .foreign_key(&mut tenant_key(LibraryHolds::Table, "fk_library_holds_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_library_holds_title_id")
+                            .from(LibraryHolds::Table, LibraryHolds::TitleId)
+                            .to(LibraryTitles::Table, LibraryTitles::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_library_holds_user_id")
+                            .from(LibraryHolds::Table, LibraryHolds::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_library_holds_copy_id")
+                            .from(LibraryHolds::Table, LibraryHolds::CopyId)
+                            .to(LibraryCopies::Table, LibraryCopies::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_library_holds_title_id_status")
+                    .table(LibraryHolds::Table)
+                    .col(LibraryHolds::TitleId)
+                    .col(LibraryHolds::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LibraryHolds::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LibraryLoans::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LibraryCopies::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LibraryTitles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum LibraryTitles {
+    Table,
+    Id,
+    TenantId,
+    Isbn,
+    Title,
+    Author,
+    Publisher,
+    PublishedYear,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LibraryCopies {
+    Table,
+    Id,
+    TenantId,
+    TitleId,
+    Barcode,
+    Location,
+    Status,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LibraryLoans {
+    Table,
+    Id,
+    TenantId,
+    CopyId,
+    UserId,
+    CheckedOutAt,
+    DueDate,
+    ReturnedAt,
+    Renewals,
+    FineAmount,
+    FineStatus,
+    CheckedOutBy,
+}
+
+#[derive(DeriveIden)]
+enum LibraryHolds {
+    Table,
+    Id,
+    TenantId,
+    TitleId,
+    UserId,
+    Status,
+    CopyId,
+    CreatedAt,
+    ReadyAt,
+    ExpiresOn,
+}
@@ -0,0 +1,348 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A bus or van; its capacity caps the students assigned to a route
+        manager
+            .create_table(
+                Table::create()
+                    .table(TransportVehicles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TransportVehicles::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TransportVehicles::TenantId).integer().not_null())
+                    .col(ColumnDef::new(TransportVehicles::Registration).string().not_null())
+                    .col(ColumnDef::new(TransportVehicles::Description).string())
+                    .col(ColumnDef::new(TransportVehicles::Capacity).integer().not_null())
+                    .col(
+                        ColumnDef::new(TransportVehicles::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(TransportVehicles::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(TransportVehicles::Table, "fk_transport_vehicles_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transport_vehicles_tenant_id_registration")
+                    .table(TransportVehicles::Table)
+                    .col(TransportVehicles::TenantId)
+                    .col(TransportVehicles::Registration)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Drivers are often contractors, so they are records of their own
+        // rather than users
+        manager
+            .create_table(
+                Table::create()
+                    .table(TransportDrivers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TransportDrivers::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TransportDrivers::TenantId).integer().not_null())
+                    .col(ColumnDef::new(TransportDrivers::FullName).string().not_null())
+                    .col(ColumnDef::new(TransportDrivers::Phone).string())
+                    .col(ColumnDef::new(TransportDrivers::LicenseNumber).string().not_null())
+                    .col(ColumnDef::new(TransportDrivers::LicenseExpiresOn).date())
+                    .col(
+                        ColumnDef::new(TransportDrivers::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(TransportDrivers::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(TransportDrivers::Table, "fk_transport_drivers_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TransportRoutes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TransportRoutes::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TransportRoutes::TenantId).integer().not_null())
+                    .col(ColumnDef::new(TransportRoutes::Name).string().not_null())
+                    .col(ColumnDef::new(TransportRoutes::VehicleId).integer())
+                    .col(ColumnDef::new(TransportRoutes::DriverId).integer())
+                    .col(
+                        ColumnDef::new(TransportRoutes::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(TransportRoutes::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(TransportRoutes::Table, "fk_transport_routes_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_transport_routes_vehicle_id")
+                            .from(TransportRoutes::Table, TransportRoutes::VehicleId)
+                            .to(TransportVehicles::Table, TransportVehicles::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_transport_routes_driver_id")
+                            .from(TransportRoutes::Table, TransportRoutes::DriverId)
+                            .to(TransportDrivers::Table, TransportDrivers::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transport_routes_tenant_id_name")
+                    .table(TransportRoutes::Table)
+                    .col(TransportRoutes::TenantId)
+                    .col(TransportRoutes::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Stops in the order the vehicle calls at them
+        manager
+            .create_table(
+                Table::create()
+                    .table(TransportStops::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TransportStops::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TransportStops::RouteId).integer().not_null())
+                    .col(ColumnDef::new(TransportStops::Position).integer().not_null())
+                    .col(ColumnDef::new(TransportStops::Name).string().not_null())
+                    .col(ColumnDef::new(TransportStops::ScheduledTime).time().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_transport_stops_route_id")
+                            .from(TransportStops::Table, TransportStops::RouteId)
+                            .to(TransportRoutes::Table, TransportRoutes::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transport_stops_route_id_position")
+                    .table(TransportStops::Table)
+                    .col(TransportStops::RouteId)
+                    .col(TransportStops::Position)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Where a student boards a route; a student rides a route once
+        manager
+            .create_table(
+                Table::create()
+                    .table(TransportAssignments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TransportAssignments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TransportAssignments::TenantId).integer().not_null())
+                    .col(ColumnDef::new(TransportAssignments::RouteId).integer().not_null())
+                    .col(ColumnDef::new(TransportAssignments::StopId).integer().not_null())
+                    .col(ColumnDef::new(TransportAssignments::StudentId).integer().not_null())
+                    .col(
+                        ColumnDef::new(TransportAssignments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(TransportAssignments::Table, "fk_transport_assignments_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_transport_assignments_route_id")
+                            .from(TransportAssignments::Table, TransportAssignments::RouteId)
+                            .to(TransportRoutes::Table, TransportRoutes::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_transport_assignments_stop_id")
+                            .from(TransportAssignments::Table, TransportAssignments::StopId)
+                            .to(TransportStops::Table, TransportStops::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_transport_assignments_student_id")
+                            .from(TransportAssignments::Table, TransportAssignments::StudentId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transport_assignments_route_id_student_id")
+                    .table(TransportAssignments::Table)
+                    .col(TransportAssignments::RouteId)
+                    .col(TransportAssignments::StudentId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TransportAssignments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TransportStops::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TransportRoutes::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TransportDrivers::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TransportVehicles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum TransportVehicles {
+    Table,
+    Id,
+    TenantId,
+    Registration,
+    Description,
+    Capacity,
+    IsActive,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TransportDrivers {
+    Table,
+    Id,
+    TenantId,
+    FullName,
+    Phone,
+    LicenseNumber,
+    LicenseExpiresOn,
+    IsActive,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TransportRoutes {
+    Table,
+    Id,
+    TenantId,
+    Name,
+    VehicleId,
+    DriverId,
+    IsActive,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TransportStops {
+    Table,
+    Id,
+    RouteId,
+    Position,
+    Name,
+    ScheduledTime,
+}
+
+#[derive(DeriveIden)]
+enum TransportAssignments {
+    Table,
+    Id,
+    TenantId,
+    RouteId,
+    StopId,
+    StudentId,
+    CreatedAt,
+}
@@ -0,0 +1,295 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A boarding room houses one gender and, optionally, one grade
+        manager
+            .create_table(
+                Table::create()
+                    .table(HostelRooms::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HostelRooms::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(HostelRooms::TenantId).integer().not_null())
+                    .col(ColumnDef::new(HostelRooms::Name).string().not_null())
+                    .col(ColumnDef::new(HostelRooms::Building).string())
+                    .col(ColumnDef::new(HostelRooms::Gender).string().not_null())
+                    .col(ColumnDef::new(HostelRooms::Grade).string())
+                    .col(
+                        ColumnDef::new(HostelRooms::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(HostelRooms::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(HostelRooms::Table, "fk_hostel_rooms_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_hostel_rooms_tenant_id_name")
+                    .table(HostelRooms::Table)
+                    .col(HostelRooms::TenantId)
+                    .col(HostelRooms::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(HostelBeds::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HostelBeds::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(HostelBeds::RoomId).integer().not_null())
+                    .col(ColumnDef::new(HostelBeds::Label).string().not_null())
+                    .col(
+                        ColumnDef::new(HostelBeds::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_hostel_beds_room_id")
+                            .from(HostelBeds::Table, HostelBeds::RoomId)
+                            .to(HostelRooms::Table, HostelRooms::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_hostel_beds_room_id_label")
+                    .table(HostelBeds::Table)
+                    .col(HostelBeds::RoomId)
+                    .col(HostelBeds::Label)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // A student's bed for a term. Gender and grade are recorded as given
+        // at allocation, since users carry neither.
+        manager
+            .create_table(
+                Table::create()
+                    .table(HostelAllocations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HostelAllocations::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(HostelAllocations::TenantId).integer().not_null())
+                    .col(ColumnDef::new(HostelAllocations::BedId).integer().not_null())
+                    .col(ColumnDef::new(HostelAllocations::StudentId).integer().not_null())
+                    .col(ColumnDef::new(HostelAllocations::Term).string().not_null())
+                    .col(ColumnDef::new(HostelAllocations::Gender).string().not_null())
+                    .col(ColumnDef::new(HostelAllocations::Grade).string().not_null())
+                    .col(
+                        ColumnDef::new(HostelAllocations::Status)
+                            .string()
+                            .not_null()
+                            .default("allocated"),
+                    )
+                    .col(ColumnDef::new(HostelAllocations::AllocatedBy).integer())
+                    .col(
+                        ColumnDef::new(HostelAllocations::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(HostelAllocations::Table, "fk_hostel_allocations_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_hostel_allocations_bed_id")
+                            .from(HostelAllocations::Table, HostelAllocations::BedId)
+                            .to(HostelBeds::Table, HostelBeds::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_hostel_allocations_student_id")
+                            .from(HostelAllocations::Table, HostelAllocations::StudentId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One bed per student and one student per bed each term
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_hostel_allocations_term_student_id")
+                    .table(HostelAllocations::Table)
+                    .col(HostelAllocations::TenantId)
+                    .col(HostelAllocations::Term)
+                    .col(HostelAllocations::StudentId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_hostel_allocations_term_bed_id")
+                    .table(HostelAllocations::Table)
+                    .col(HostelAllocations::Term)
+                    .col(HostelAllocations::BedId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every arrival and departure, including weekend exeats
+        manager
+            .create_table(
+                Table::create()
+                    .table(HostelStayLogs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HostelStayLogs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(HostelStayLogs::AllocationId).integer().not_null())
+                    .col(ColumnDef::new(HostelStayLogs::Event).string().not_null())
+                    .col(ColumnDef::new(HostelStayLogs::Notes).string())
+                    .col(ColumnDef::new(HostelStayLogs::RecordedBy).integer())
+                    .col(
+                        ColumnDef::new(HostelStayLogs::OccurredAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_hostel_stay_logs_allocation_id")
+                            .from(HostelStayLogs::Table, HostelStayLogs::AllocationId)
+                            .to(HostelAllocations::Table, HostelAllocations::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(HostelStayLogs::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(HostelAllocations::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(HostelBeds::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(HostelRooms::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum HostelRooms {
+    Table,
+    Id,
+    TenantId,
+    Name,
+    Building,
+    Gender,
+    Grade,
+    IsActive,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HostelBeds {
+    Table,
+    Id,
+    RoomId,
+    Label,
+    IsActive,
+}
+
+#[derive(DeriveIden)]
+enum HostelAllocations {
+    Table,
+    Id,
+    TenantId,
+    BedId,
+    StudentId,
+    Term,
+    Gender,
+    Grade,
+    Status,
+    AllocatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HostelStayLogs {
+    Table,
+    Id,
+    AllocationId,
+    Event,
+    Notes,
+    RecordedBy,
+    OccurredAt,
+}
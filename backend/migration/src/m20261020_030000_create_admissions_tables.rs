@@ -0,0 +1,343 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Each school's pipeline, in order (inquiry, application, ...)
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionStages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionStages::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionStages::TenantId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionStages::Name).string().not_null())
+                    .col(ColumnDef::new(AdmissionStages::Position).integer().not_null())
+                    .foreign_key(&mut tenant_key(AdmissionStages::Table, "fk_admission_stages_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admission_stages_tenant_id_name")
+                    .table(AdmissionStages::Table)
+                    .col(AdmissionStages::TenantId)
+                    .col(AdmissionStages::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // A prospective student. Families applying online get an upload token
+        // (stored hashed) for their documents.
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionApplications::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionApplications::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionApplications::TenantId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionApplications::StageId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionApplications::FullName).string().not_null())
+                    .col(ColumnDef::new(AdmissionApplications::DateOfBirth).date())
+                    .col(ColumnDef::new(AdmissionApplications::Grade).string().not_null())
+                    .col(ColumnDef::new(AdmissionApplications::EntryTerm).string())
+                    .col(ColumnDef::new(AdmissionApplications::StudentEmail).string())
+                    .col(ColumnDef::new(AdmissionApplications::GuardianName).string().not_null())
+                    .col(ColumnDef::new(AdmissionApplications::GuardianEmail).string().not_null())
+                    .col(ColumnDef::new(AdmissionApplications::GuardianPhone).string())
+                    .col(ColumnDef::new(AdmissionApplications::Notes).text())
+                    .col(
+                        ColumnDef::new(AdmissionApplications::Decision)
+                            .string()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(ColumnDef::new(AdmissionApplications::DecidedAt).timestamp())
+                    .col(ColumnDef::new(AdmissionApplications::UploadTokenHash).string().unique_key())
+                    .col(ColumnDef::new(AdmissionApplications::StudentId).integer())
+                    .col(
+                        ColumnDef::new(AdmissionApplications::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(AdmissionApplications::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(AdmissionApplications::Table, "fk_admission_applications_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admission_applications_stage_id")
+                            .from(AdmissionApplications::Table, AdmissionApplications::StageId)
+                            .to(AdmissionStages::Table, AdmissionStages::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admission_applications_student_id")
+                            .from(AdmissionApplications::Table, AdmissionApplications::StudentId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionDocuments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionDocuments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionDocuments::ApplicationId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionDocuments::Kind).string().not_null())
+                    .col(ColumnDef::new(AdmissionDocuments::Filename).string().not_null())
+                    .col(ColumnDef::new(AdmissionDocuments::ContentType).string().not_null())
+                    .col(ColumnDef::new(AdmissionDocuments::Length).big_integer().not_null())
+                    .col(ColumnDef::new(AdmissionDocuments::Content).binary().not_null())
+                    .col(ColumnDef::new(AdmissionDocuments::UploadedBy).integer())
+                    .col(
+                        ColumnDef::new(AdmissionDocuments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admission_documents_application_id")
+                            .from(AdmissionDocuments::Table, AdmissionDocuments::ApplicationId)
+                            .to(AdmissionApplications::Table, AdmissionApplications::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One score per reviewer per application; reviewing again replaces it
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionReviews::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionReviews::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionReviews::ApplicationId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionReviews::ReviewerId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionReviews::Score).integer().not_null())
+                    .col(ColumnDef::new(AdmissionReviews::Comments).text())
+                    .col(
+                        ColumnDef::new(AdmissionReviews::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admission_reviews_application_id")
+                            .from(AdmissionReviews::Table, AdmissionReviews::ApplicationId)
+                            .to(AdmissionApplications::Table, AdmissionApplications::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admission_reviews_reviewer_id")
+                            .from(AdmissionReviews::Table, AdmissionReviews::ReviewerId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admission_reviews_application_id_reviewer_id")
+                    .table(AdmissionReviews::Table)
+                    .col(AdmissionReviews::ApplicationId)
+                    .col(AdmissionReviews::ReviewerId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every decision letter as sent, for the applicant's file
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionLetters::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionLetters::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionLetters::ApplicationId).integer().not_null())
+                    .col(ColumnDef::new(AdmissionLetters::Decision).string().not_null())
+                    .col(ColumnDef::new(AdmissionLetters::SentTo).string().not_null())
+                    .col(ColumnDef::new(AdmissionLetters::Subject).string().not_null())
+                    .col(ColumnDef::new(AdmissionLetters::Body).text().not_null())
+                    .col(ColumnDef::new(AdmissionLetters::SentBy).integer())
+                    .col(ColumnDef::new(AdmissionLetters::SentAt).timestamp())
+                    .col(
+                        ColumnDef::new(AdmissionLetters::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admission_letters_application_id")
+                            .from(AdmissionLetters::Table, AdmissionLetters::ApplicationId)
+                            .to(AdmissionApplications::Table, AdmissionApplications::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AdmissionLetters::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AdmissionReviews::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AdmissionDocuments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AdmissionApplications::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AdmissionStages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum AdmissionStages {
+    Table,
+    Id,
+    TenantId,
+    Name,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum AdmissionApplications {
+    Table,
+    Id,
+    TenantId,
+    StageId,
+    FullName,
+    DateOfBirth,
+    Grade,
+    EntryTerm,
+    StudentEmail,
+    GuardianName,
+    GuardianEmail,
+    GuardianPhone,
+    Notes,
+    Decision,
+    DecidedAt,
+    UploadTokenHash,
+    StudentId,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AdmissionDocuments {
+    Table,
+    Id,
+    ApplicationId,
+    Kind,
+    Filename,
+    ContentType,
+    Length,
+    Content,
+    UploadedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AdmissionReviews {
+    Table,
+    Id,
+    ApplicationId,
+    ReviewerId,
+    Score,
+    Comments,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AdmissionLetters {
+    Table,
+    Id,
+    ApplicationId,
+    Decision,
+    SentTo,
+    Subject,
+    Body,
+    SentBy,
+    SentAt,
+    CreatedAt,
+}
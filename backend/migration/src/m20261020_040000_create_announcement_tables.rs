@@ -0,0 +1,286 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The grade and section a user belongs to, for students and their
+        // class teachers. Used to target notices at a grade or section.
+        manager
+            .create_table(
+                Table::create()
+                    .table(ClassMemberships::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ClassMemberships::UserId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ClassMemberships::Grade).string().not_null())
+                    .col(ColumnDef::new(ClassMemberships::Section).string())
+                    .col(
+                        ColumnDef::new(ClassMemberships::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_class_memberships_user_id")
+                            .from(ClassMemberships::Table, ClassMemberships::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_class_memberships_grade_section")
+                    .table(ClassMemberships::Table)
+                    .col(ClassMemberships::Grade)
+                    .col(ClassMemberships::Section)
+                    .to_owned(),
+            )
+            .await?;
+
+        // A notice. `audience` is school, grade, section, guardians or staff;
+        // grade and section narrow the first two. It shows from `publish_at`
+        // until `expires_at`.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Announcements::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Announcements::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Announcements::TenantId).integer().not_null())
+                    .col(ColumnDef::new(Announcements::Title).string().not_null())
+                    .col(ColumnDef::new(Announcements::Body).text().not_null())
+                    .col(ColumnDef::new(Announcements::Audience).string().not_null())
+                    .col(ColumnDef::new(Announcements::Grade).string())
+                    .col(ColumnDef::new(Announcements::Section).string())
+                    .col(
+                        ColumnDef::new(Announcements::IsPinned)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Announcements::PublishAt).timestamp().not_null())
+                    .col(ColumnDef::new(Announcements::ExpiresAt).timestamp())
+                    .col(ColumnDef::new(Announcements::CreatedBy).integer())
+                    .col(
+                        ColumnDef::new(Announcements::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Announcements::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(Announcements::Table, "fk_announcements_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_announcements_created_by")
+                            .from(Announcements::Table, Announcements::CreatedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_announcements_tenant_id_publish_at")
+                    .table(Announcements::Table)
+                    .col(Announcements::TenantId)
+                    .col(Announcements::PublishAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AnnouncementAttachments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AnnouncementAttachments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AnnouncementAttachments::AnnouncementId).integer().not_null())
+                    .col(ColumnDef::new(AnnouncementAttachments::Filename).string().not_null())
+                    .col(ColumnDef::new(AnnouncementAttachments::ContentType).string().not_null())
+                    .col(ColumnDef::new(AnnouncementAttachments::Length).big_integer().not_null())
+                    .col(ColumnDef::new(AnnouncementAttachments::Content).binary().not_null())
+                    .col(
+                        ColumnDef::new(AnnouncementAttachments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_announcement_attachments_announcement_id")
+                            .from(AnnouncementAttachments::Table, AnnouncementAttachments::AnnouncementId)
+                            .to(Announcements::Table, Announcements::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Read receipts, one per reader
+        manager
+            .create_table(
+                Table::create()
+                    .table(AnnouncementReads::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AnnouncementReads::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AnnouncementReads::AnnouncementId).integer().not_null())
+                    .col(ColumnDef::new(AnnouncementReads::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(AnnouncementReads::ReadAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_announcement_reads_announcement_id")
+                            .from(AnnouncementReads::Table, AnnouncementReads::AnnouncementId)
+                            .to(Announcements::Table, Announcements::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_announcement_reads_user_id")
+                            .from(AnnouncementReads::Table, AnnouncementReads::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_announcement_reads_announcement_id_user_id")
+                    .table(AnnouncementReads::Table)
+                    .col(AnnouncementReads::AnnouncementId)
+                    .col(AnnouncementReads::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AnnouncementReads::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AnnouncementAttachments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Announcements::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ClassMemberships::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum ClassMemberships {
+    Table,
+    UserId,
+    Grade,
+    Section,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Announcements {
+    Table,
+    Id,
+    TenantId,
+    Title,
+    Body,
+    Audience,
+    Grade,
+    Section,
+    IsPinned,
+    PublishAt,
+    ExpiresAt,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AnnouncementAttachments {
+    Table,
+    Id,
+    AnnouncementId,
+    Filename,
+    ContentType,
+    Length,
+    Content,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AnnouncementReads {
+    Table,
+    Id,
+    AnnouncementId,
+    UserId,
+    ReadAt,
+}
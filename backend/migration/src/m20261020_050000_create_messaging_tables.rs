@@ -0,0 +1,564 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn tenant_key(table: impl IntoIden + 'static, name: &str) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(name)
+        .from(table, Alias::new("tenant_id"))
+        .to(Tenants::Table, Tenants::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Which guardians look after which students
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuardianLinks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuardianLinks::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(GuardianLinks::GuardianId).integer().not_null())
+                    .col(ColumnDef::new(GuardianLinks::StudentId).integer().not_null())
+                    .col(ColumnDef::new(GuardianLinks::Relationship).string())
+                    .col(
+                        ColumnDef::new(GuardianLinks::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_guardian_links_guardian_id")
+                            .from(GuardianLinks::Table, GuardianLinks::GuardianId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_guardian_links_student_id")
+                            .from(GuardianLinks::Table, GuardianLinks::StudentId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_guardian_links_guardian_id_student_id")
+                    .table(GuardianLinks::Table)
+                    .col(GuardianLinks::GuardianId)
+                    .col(GuardianLinks::StudentId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_guardian_links_student_id")
+                    .table(GuardianLinks::Table)
+                    .col(GuardianLinks::StudentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // A school's safeguarding rules; schools without a row use the
+        // defaults below
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessagingSettings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessagingSettings::TenantId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MessagingSettings::ArchiveStaffStudent)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(MessagingSettings::ArchiveStudentStudent)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(MessagingSettings::StudentsMayMessageStudents)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(MessagingSettings::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(MessagingSettings::Table, "fk_messaging_settings_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageThreads::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageThreads::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageThreads::TenantId).integer().not_null())
+                    .col(ColumnDef::new(MessageThreads::Subject).string().not_null())
+                    .col(ColumnDef::new(MessageThreads::CreatedBy).integer())
+                    .col(
+                        ColumnDef::new(MessageThreads::IsLocked)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(MessageThreads::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(MessageThreads::LastMessageAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(MessageThreads::Table, "fk_message_threads_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_threads_created_by")
+                            .from(MessageThreads::Table, MessageThreads::CreatedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Who is in a thread, and up to when they have read it
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageParticipants::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageParticipants::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageParticipants::ThreadId).integer().not_null())
+                    .col(ColumnDef::new(MessageParticipants::UserId).integer().not_null())
+                    .col(ColumnDef::new(MessageParticipants::LastReadAt).timestamp())
+                    .col(
+                        ColumnDef::new(MessageParticipants::JoinedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_participants_thread_id")
+                            .from(MessageParticipants::Table, MessageParticipants::ThreadId)
+                            .to(MessageThreads::Table, MessageThreads::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_participants_user_id")
+                            .from(MessageParticipants::Table, MessageParticipants::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_message_participants_thread_id_user_id")
+                    .table(MessageParticipants::Table)
+                    .col(MessageParticipants::ThreadId)
+                    .col(MessageParticipants::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_message_participants_user_id")
+                    .table(MessageParticipants::Table)
+                    .col(MessageParticipants::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Messages are never deleted; moderators hide them instead
+        manager
+            .create_table(
+                Table::create()
+                    .table(Messages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Messages::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Messages::ThreadId).integer().not_null())
+                    .col(ColumnDef::new(Messages::SenderId).integer())
+                    .col(ColumnDef::new(Messages::Body).text().not_null())
+                    .col(ColumnDef::new(Messages::HiddenAt).timestamp())
+                    .col(ColumnDef::new(Messages::HiddenBy).integer())
+                    .col(
+                        ColumnDef::new(Messages::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_messages_thread_id")
+                            .from(Messages::Table, Messages::ThreadId)
+                            .to(MessageThreads::Table, MessageThreads::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_messages_sender_id")
+                            .from(Messages::Table, Messages::SenderId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_messages_thread_id_created_at")
+                    .table(Messages::Table)
+                    .col(Messages::ThreadId)
+                    .col(Messages::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageAttachments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageAttachments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageAttachments::MessageId).integer().not_null())
+                    .col(ColumnDef::new(MessageAttachments::Filename).string().not_null())
+                    .col(ColumnDef::new(MessageAttachments::ContentType).string().not_null())
+                    .col(ColumnDef::new(MessageAttachments::Length).big_integer().not_null())
+                    .col(ColumnDef::new(MessageAttachments::Content).binary().not_null())
+                    .col(
+                        ColumnDef::new(MessageAttachments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_attachments_message_id")
+                            .from(MessageAttachments::Table, MessageAttachments::MessageId)
+                            .to(Messages::Table, Messages::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A participant flagging a message for the moderators
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageReports::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageReports::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageReports::TenantId).integer().not_null())
+                    .col(ColumnDef::new(MessageReports::MessageId).integer().not_null())
+                    .col(ColumnDef::new(MessageReports::ReporterId).integer())
+                    .col(ColumnDef::new(MessageReports::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(MessageReports::Status)
+                            .string()
+                            .not_null()
+                            .default("open"),
+                    )
+                    .col(ColumnDef::new(MessageReports::Resolution).text())
+                    .col(ColumnDef::new(MessageReports::ResolvedBy).integer())
+                    .col(ColumnDef::new(MessageReports::ResolvedAt).timestamp())
+                    .col(
+                        ColumnDef::new(MessageReports::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(MessageReports::Table, "fk_message_reports_tenant_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_reports_message_id")
+                            .from(MessageReports::Table, MessageReports::MessageId)
+                            .to(Messages::Table, Messages::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_reports_reporter_id")
+                            .from(MessageReports::Table, MessageReports::ReporterId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_message_reports_message_id_reporter_id")
+                    .table(MessageReports::Table)
+                    .col(MessageReports::MessageId)
+                    .col(MessageReports::ReporterId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Safeguarding copies of messages, kept apart from the threads so
+        // they outlive the users and threads they came from
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageArchive::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageArchive::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageArchive::TenantId).integer().not_null())
+                    .col(ColumnDef::new(MessageArchive::ThreadId).integer().not_null())
+                    .col(ColumnDef::new(MessageArchive::MessageId).integer().not_null())
+                    .col(ColumnDef::new(MessageArchive::Subject).string().not_null())
+                    .col(ColumnDef::new(MessageArchive::SenderId).integer())
+                    .col(ColumnDef::new(MessageArchive::SenderName).string().not_null())
+                    .col(ColumnDef::new(MessageArchive::SenderRole).string().not_null())
+                    .col(ColumnDef::new(MessageArchive::Participants).json_binary().not_null())
+                    .col(ColumnDef::new(MessageArchive::Body).text().not_null())
+                    .col(ColumnDef::new(MessageArchive::Reason).string().not_null())
+                    .col(ColumnDef::new(MessageArchive::SentAt).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(MessageArchive::ArchivedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(&mut tenant_key(MessageArchive::Table, "fk_message_archive_tenant_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_message_archive_tenant_id_sent_at")
+                    .table(MessageArchive::Table)
+                    .col(MessageArchive::TenantId)
+                    .col(MessageArchive::SentAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageArchive::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MessageReports::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MessageAttachments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Messages::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MessageParticipants::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MessageThreads::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MessagingSettings::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GuardianLinks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum GuardianLinks {
+    Table,
+    Id,
+    GuardianId,
+    StudentId,
+    Relationship,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MessagingSettings {
+    Table,
+    TenantId,
+    ArchiveStaffStudent,
+    ArchiveStudentStudent,
+    StudentsMayMessageStudents,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MessageThreads {
+    Table,
+    Id,
+    TenantId,
+    Subject,
+    CreatedBy,
+    IsLocked,
+    CreatedAt,
+    LastMessageAt,
+}
+
+#[derive(DeriveIden)]
+enum MessageParticipants {
+    Table,
+    Id,
+    ThreadId,
+    UserId,
+    LastReadAt,
+    JoinedAt,
+}
+
+#[derive(DeriveIden)]
+enum Messages {
+    Table,
+    Id,
+    ThreadId,
+    SenderId,
+    Body,
+    HiddenAt,
+    HiddenBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MessageAttachments {
+    Table,
+    Id,
+    MessageId,
+    Filename,
+    ContentType,
+    Length,
+    Content,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MessageReports {
+    Table,
+    Id,
+    TenantId,
+    MessageId,
+    ReporterId,
+    Reason,
+    Status,
+    Resolution,
+    ResolvedBy,
+    ResolvedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MessageArchive {
+    Table,
+    Id,
+    TenantId,
+    ThreadId,
+    MessageId,
+    Subject,
+    SenderId,
+    SenderName,
+    SenderRole,
+    Participants,
+    Body,
+    Reason,
+    SentAt,
+    ArchivedAt,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // When readers were told a notice had gone up. Notices scheduled for
+        // later are picked up by the notification sweep once they go live.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Announcements::Table)
+                    .add_column(ColumnDef::new(Announcements::NotifiedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Notices already up were seen on the board; only scheduled ones are news
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Announcements::Table)
+                    .value(Announcements::NotifiedAt, Expr::col(Announcements::PublishAt))
+                    .and_where(Expr::col(Announcements::PublishAt).lte(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_announcements_unnotified")
+                    .table(Announcements::Table)
+                    .col(Announcements::NotifiedAt)
+                    .col(Announcements::PublishAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_announcements_unnotified")
+                    .table(Announcements::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Announcements::Table)
+                    .drop_column(Announcements::NotifiedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Announcements {
+    Table,
+    PublishAt,
+    NotifiedAt,
+}
@@ -0,0 +1,168 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The language a school's emails are written in, and the colour they
+        // are branded with (a "#rrggbb" hex code; None for the default)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tenants::Table)
+                    .add_column(ColumnDef::new(Tenants::Locale).string().not_null().default("en"))
+                    .add_column(ColumnDef::new(Tenants::BrandColor).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // When the family was reminded that an invoice is nearly due
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoices::Table)
+                    .add_column(ColumnDef::new(Invoices::RemindedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every email the system sends, queued when whatever caused it happens
+        // and delivered by a worker. Failed deliveries are retried with
+        // backoff until they run out of attempts.
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmailOutbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EmailOutbox::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(EmailOutbox::TenantId).integer().not_null())
+                    .col(ColumnDef::new(EmailOutbox::Template).string().not_null())
+                    .col(ColumnDef::new(EmailOutbox::ToAddress).string().not_null())
+                    .col(ColumnDef::new(EmailOutbox::FromName).string())
+                    .col(ColumnDef::new(EmailOutbox::Subject).string().not_null())
+                    .col(ColumnDef::new(EmailOutbox::Body).text().not_null())
+                    .col(ColumnDef::new(EmailOutbox::Html).text())
+                    .col(
+                        ColumnDef::new(EmailOutbox::Status)
+                            .string()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::NextAttemptAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(EmailOutbox::LastError).text())
+                    .col(
+                        ColumnDef::new(EmailOutbox::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(EmailOutbox::SentAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_email_outbox_tenant_id")
+                            .from(EmailOutbox::Table, EmailOutbox::TenantId)
+                            .to(Tenants::Table, Tenants::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_email_outbox_status_next_attempt_at")
+                    .table(EmailOutbox::Table)
+                    .col(EmailOutbox::Status)
+                    .col(EmailOutbox::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_email_outbox_tenant_id_created_at")
+                    .table(EmailOutbox::Table)
+                    .col(EmailOutbox::TenantId)
+                    .col(EmailOutbox::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EmailOutbox::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoices::Table)
+                    .drop_column(Invoices::RemindedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tenants::Table)
+                    .drop_column(Tenants::BrandColor)
+                    .drop_column(Tenants::Locale)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+    Locale,
+    BrandColor,
+}
+
+#[derive(DeriveIden)]
+enum Invoices {
+    Table,
+    RemindedAt,
+}
+
+#[derive(DeriveIden)]
+enum EmailOutbox {
+    Table,
+    Id,
+    TenantId,
+    Template,
+    ToAddress,
+    FromName,
+    Subject,
+    Body,
+    Html,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    CreatedAt,
+    SentAt,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Which rsEdu account a platform user (`sub` at the platform's issuer)
+        // signs in as. Launches only sign in through a link, never by email.
+        manager
+            .create_table(
+                Table::create()
+                    .table(LtiUserLinks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LtiUserLinks::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LtiUserLinks::PlatformId).integer().not_null())
+                    .col(ColumnDef::new(LtiUserLinks::Subject).string().not_null())
+                    .col(ColumnDef::new(LtiUserLinks::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(LtiUserLinks::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_lti_user_links_platform_id")
+                            .from(LtiUserLinks::Table, LtiUserLinks::PlatformId)
+                            .to(LtiPlatforms::Table, LtiPlatforms::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_lti_user_links_user_id")
+                            .from(LtiUserLinks::Table, LtiUserLinks::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_lti_user_links_platform_id_subject")
+                    .table(LtiUserLinks::Table)
+                    .col(LtiUserLinks::PlatformId)
+                    .col(LtiUserLinks::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_lti_user_links_user_id")
+                    .table(LtiUserLinks::Table)
+                    .col(LtiUserLinks::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LtiUserLinks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LtiUserLinks {
+    Table,
+    Id,
+    PlatformId,
+    Subject,
+    UserId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LtiPlatforms {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Attachments were shared across schools by hash, so any school could
+        // fetch another's file by referencing its sha2. Each school now keeps
+        // its own copy, keyed by (tenant_id, sha2).
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(XapiAttachments::Table)
+                    .add_column(ColumnDef::new(XapiAttachments::TenantId).integer().not_null().default(1))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_xapi_attachments_tenant_id")
+                            .from_tbl(XapiAttachments::Table)
+                            .from_col(XapiAttachments::TenantId)
+                            .to_tbl(Tenants::Table)
+                            .to_col(Tenants::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        if manager.get_database_backend() == DbBackend::Postgres {
+            let db = manager.get_connection();
+            db.execute_unprepared("ALTER TABLE xapi_attachments ALTER COLUMN tenant_id DROP DEFAULT")
+                .await?;
+            db.execute_unprepared("ALTER TABLE xapi_attachments DROP CONSTRAINT xapi_attachments_pkey")
+                .await?;
+            db.execute_unprepared("ALTER TABLE xapi_attachments ADD PRIMARY KEY (tenant_id, sha2)")
+                .await?;
+            // Existing files went to the default school; copy them to every
+            // other school whose statements reference them
+            db.execute_unprepared(
+                "INSERT INTO xapi_attachments (tenant_id, sha2, content_type, length, content, created_at)
+                 SELECT DISTINCT s.tenant_id, a.sha2, a.content_type, a.length, a.content, a.created_at
+                 FROM xapi_attachments a
+                 JOIN xapi_statements s
+                   ON s.statement::jsonb -> 'attachments' @> jsonb_build_array(jsonb_build_object('sha2', a.sha2))
+                 WHERE s.tenant_id <> a.tenant_id
+                 ON CONFLICT DO NOTHING",
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Postgres {
+            let db = manager.get_connection();
+            db.execute_unprepared(
+                "DELETE FROM xapi_attachments a USING xapi_attachments b
+                 WHERE a.sha2 = b.sha2 AND a.tenant_id > b.tenant_id",
+            )
+            .await?;
+            db.execute_unprepared("ALTER TABLE xapi_attachments DROP CONSTRAINT xapi_attachments_pkey")
+                .await?;
+            db.execute_unprepared("ALTER TABLE xapi_attachments ADD PRIMARY KEY (sha2)")
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(XapiAttachments::Table)
+                    .drop_foreign_key(Alias::new("fk_xapi_attachments_tenant_id"))
+                    .drop_column(XapiAttachments::TenantId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum XapiAttachments {
+    Table,
+    TenantId,
+}
+
+#[derive(DeriveIden)]
+enum Tenants {
+    Table,
+    Id,
+}
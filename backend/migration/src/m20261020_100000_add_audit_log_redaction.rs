@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Erasing a user must also blank the before/after snapshots that hold
+        // their data. New entries hash a digest of their snapshots instead of
+        // the snapshots themselves, so the chain still verifies once they are
+        // blanked.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditLog::Table)
+                    .add_column(ColumnDef::new(AuditLog::SnapshotHash).string())
+                    .add_column(ColumnDef::new(AuditLog::RedactedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Still append-only, except for that one change: snapshots set to
+        // NULL and redacted_at set, with every other column as it was
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared(
+                    "CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS trigger AS $$ \
+                     BEGIN \
+                       IF TG_OP = 'UPDATE' \
+                          AND OLD.redacted_at IS NULL AND NEW.redacted_at IS NOT NULL \
+                          AND NEW.before IS NULL AND NEW.after IS NULL \
+                          AND (NEW.id, NEW.actor_id, NEW.actor_email, NEW.impersonator_id, NEW.action, \
+                               NEW.entity, NEW.entity_id, NEW.ip_address, NEW.request_id, NEW.created_at, \
+                               NEW.prev_hash, NEW.hash, NEW.tenant_id, NEW.snapshot_hash) \
+                              IS NOT DISTINCT FROM \
+                              (OLD.id, OLD.actor_id, OLD.actor_email, OLD.impersonator_id, OLD.action, \
+                               OLD.entity, OLD.entity_id, OLD.ip_address, OLD.request_id, OLD.created_at, \
+                               OLD.prev_hash, OLD.hash, OLD.tenant_id, OLD.snapshot_hash) \
+                       THEN RETURN NEW; END IF; \
+                       RAISE EXCEPTION 'audit_log is append-only'; \
+                     END; \
+                     $$ LANGUAGE plpgsql",
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared(
+                    "CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS trigger AS $$ \
+                     BEGIN RAISE EXCEPTION 'audit_log is append-only'; END; \
+                     $$ LANGUAGE plpgsql",
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditLog::Table)
+                    .drop_column(AuditLog::SnapshotHash)
+                    .drop_column(AuditLog::RedactedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditLog {
+    Table,
+    SnapshotHash,
+    RedactedAt,
+}
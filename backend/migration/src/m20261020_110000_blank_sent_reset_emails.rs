@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Password reset emails carry a live reset link. The outbox now drops
+        // it once an email is sent or given up on; do the same for old ones.
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(EmailOutbox::Table)
+                    .value(EmailOutbox::Body, "")
+                    .value(EmailOutbox::Html, Option::<String>::None)
+                    .and_where(Expr::col(EmailOutbox::Template).eq("password_reset"))
+                    .and_where(Expr::col(EmailOutbox::Status).is_in(["sent", "failed"]))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // The links are gone for good
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmailOutbox {
+    Table,
+    Template,
+    Status,
+    Body,
+    Html,
+}
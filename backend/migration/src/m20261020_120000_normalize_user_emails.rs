@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{DbBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Emails are now stored lowercased and looked up with LOWER(email),
+        // which is unique among a school's accounts outside the trash.
+        // Existing addresses are lowercased unless that would clash with
+        // another account in the same school; clashes outside the trash must
+        // be resolved by an admin before this migration can run.
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE users u SET email = LOWER(TRIM(u.email))
+             WHERE u.email <> LOWER(TRIM(u.email))
+               AND NOT EXISTS (
+                 SELECT 1 FROM users o
+                 WHERE o.tenant_id = u.tenant_id AND o.id <> u.id AND LOWER(TRIM(o.email)) = LOWER(TRIM(u.email))
+               )",
+        )
+        .await?;
+        let clashes: Vec<String> = db
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT tenant_id, LOWER(TRIM(email)) AS email FROM users
+                 WHERE deleted_at IS NULL
+                 GROUP BY tenant_id, LOWER(TRIM(email))
+                 HAVING COUNT(*) > 1
+                 ORDER BY tenant_id, email",
+            ))
+            .await?
+            .iter()
+            .map(|row| {
+                let email: String = row.try_get("", "email")?;
+                let tenant_id: i32 = row.try_get("", "tenant_id")?;
+                Ok(format!("{} (school {})", email, tenant_id))
+            })
+            .collect::<Result<_, DbErr>>()?;
+        if !clashes.is_empty() {
+            return Err(DbErr::Migration(format!(
+                "Accounts differ only in the case of their email; rename or delete all but one of each first: {}",
+                clashes.join(", ")
+            )));
+        }
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_users_tenant_id_lower_email ON users (tenant_id, LOWER(email))
+             WHERE deleted_at IS NULL",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared("DROP INDEX IF EXISTS idx_users_tenant_id_lower_email")
+                .await?;
+        }
+        Ok(())
+    }
+}
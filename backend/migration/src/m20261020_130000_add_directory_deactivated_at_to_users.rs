@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Set when the directory sync deactivates a user, who has left every
+        // mapped group. Only those users are brought back when they reappear;
+        // anyone deactivated another way stays out.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::DirectoryDeactivatedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Until now only the sync deactivated directory users
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Users::Table)
+                    .value(Users::DirectoryDeactivatedAt, Expr::col(Users::UpdatedAt))
+                    .and_where(Expr::col(Users::AuthSource).eq("ldap"))
+                    .and_where(Expr::col(Users::IsActive).eq(false))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::DirectoryDeactivatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    AuthSource,
+    IsActive,
+    UpdatedAt,
+    DirectoryDeactivatedAt,
+}
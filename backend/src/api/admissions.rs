@@ -0,0 +1,420 @@
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::admissions::{self, AdmissionsError, DocumentUpload, ADMISSIONS_ROLES, REVIEWER_ROLES};
+use crate::application::audit::AuditContext;
+use crate::dto::admissions::{
+    ApplicationDetail, ApplicationQuery, ApplicationRequest, ApplicationResponse, ApplyResponse, DecisionRequest,
+    DocumentQuery, DocumentResponse, EnrolRequest, EnrolResponse, LetterResponse, MoveStageRequest,
+    ReplaceStagesRequest, ReviewRequest, ReviewResponse, StageResponse,
+};
+use crate::infrastructure::mailer::{Email, SharedMailer};
+use crate::repositories::admissions_repository::AdmissionsRepository;
+
+type ApiError = (StatusCode, String);
+
+// Header carrying the upload token a family got when applying online
+const UPLOAD_TOKEN_HEADER: &str = "x-application-token";
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn admissions_error(e: AdmissionsError) -> ApiError {
+    match e {
+        AdmissionsError::ApplicationNotFound => (StatusCode::NOT_FOUND, "Application not found".to_string()),
+        AdmissionsError::StageNotFound => (StatusCode::NOT_FOUND, "Stage not found".to_string()),
+        AdmissionsError::DocumentNotFound => (StatusCode::NOT_FOUND, "Document not found".to_string()),
+        AdmissionsError::UnsupportedDocument => (StatusCode::UNSUPPORTED_MEDIA_TYPE, capitalize(&e.to_string())),
+        AdmissionsError::NoStages
+        | AdmissionsError::DuplicateStage(_)
+        | AdmissionsError::InvalidDecision
+        | AdmissionsError::EmailRequired
+        | AdmissionsError::EmptyDocument => (StatusCode::BAD_REQUEST, capitalize(&e.to_string())),
+        AdmissionsError::Database(e) => db_error(e),
+        e => (StatusCode::CONFLICT, capitalize(&e.to_string())),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn require_admissions_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !ADMISSIONS_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and admissions staff can manage admissions".to_string()));
+    }
+    Ok(())
+}
+
+fn require_reviewer(user: &AuthUser) -> Result<(), ApiError> {
+    if !REVIEWER_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admissions staff and teachers can review applications".to_string()));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+// A document sent as the raw request body, named by `?kind=&filename=`; its
+// type comes from Content-Type
+pub struct UploadedDocument(DocumentUpload);
+
+impl<S> FromRequest<S> for UploadedDocument
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = request.into_parts();
+        let Query(query) = Query::<DocumentQuery>::from_request_parts(&mut parts, state)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.body_text()))?;
+        let content_type = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let content = Bytes::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?;
+        Ok(UploadedDocument(DocumentUpload {
+            kind: query.kind,
+            filename: query.filename,
+            content_type,
+            content: content.to_vec(),
+        }))
+    }
+}
+
+impl ExportRow for ApplicationResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "stage_id", "full_name", "date_of_birth", "grade", "entry_term", "student_email", "guardian_name",
+        "guardian_email", "guardian_phone", "notes", "decision", "decided_at", "student_id", "created_at",
+    ];
+}
+
+impl ExportRow for StageResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "position"];
+}
+
+// GET /api/v1/admissions/stages - The pipeline, in order
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_stages(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_reviewer(&user)?;
+
+    let stages = AdmissionsRepository::list_stages(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "admission-stages", stages.into_iter().map(StageResponse::from).collect()).await)
+}
+
+// PUT /api/v1/admissions/stages - Rename, reorder, add or remove stages
+pub async fn replace_stages(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<ReplaceStagesRequest>,
+) -> Result<Json<Vec<StageResponse>>, ApiError> {
+    require_admissions_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let stages = AdmissionsRepository::replace_stages(&db, tenant.id(), &payload.stages, &context)
+        .await
+        .map_err(admissions_error)?;
+    tracing::info!("{} admission stages set by {}", stages.len(), user.0.email);
+    Ok(Json(stages.into_iter().map(StageResponse::from).collect()))
+}
+
+// POST /api/v1/admissions/apply - Apply online. No sign-in; the response
+// carries the token for uploading documents.
+pub async fn apply(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    context: AuditContext,
+    Json(payload): Json<ApplicationRequest>,
+) -> Result<(StatusCode, Json<ApplyResponse>), ApiError> {
+    payload.validate().map_err(validation_error)?;
+
+    let (application, token) = AdmissionsRepository::create_application(&db, tenant.id(), &payload, true, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Application {} received online from {}", application.id, application.guardian_email);
+    Ok((
+        StatusCode::CREATED,
+        Json(ApplyResponse {
+            application_id: application.id,
+            upload_token: token.unwrap_or_default(),
+        }),
+    ))
+}
+
+// POST /api/v1/admissions/apply/documents?kind=&filename= - Upload a
+// document with the token from applying (X-Application-Token)
+pub async fn upload_with_token(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    context: AuditContext,
+    headers: HeaderMap,
+    UploadedDocument(upload): UploadedDocument,
+) -> Result<(StatusCode, Json<DocumentResponse>), ApiError> {
+    let unauthorized = (StatusCode::UNAUTHORIZED, "Invalid or expired upload token".to_string());
+    let token = headers
+        .get(UPLOAD_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(unauthorized.clone())?;
+    let application = AdmissionsRepository::find_by_upload_token(&db, tenant.id(), token)
+        .await
+        .map_err(db_error)?
+        .ok_or(unauthorized)?;
+
+    let document = AdmissionsRepository::add_document(&db, tenant.id(), application.id, upload, &context)
+        .await
+        .map_err(admissions_error)?;
+    tracing::info!("Document {} uploaded online to application {}", document.filename, application.id);
+    Ok((StatusCode::CREATED, Json(document.into())))
+}
+
+// GET /api/v1/admissions/applications - Applications, by stage, decision or grade
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_applications(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<ApplicationQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_reviewer(&user)?;
+
+    let applications = AdmissionsRepository::list_applications(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    let applications: Vec<_> = applications.into_iter().map(ApplicationResponse::from).collect();
+    Ok(list_response(format, "admission-applications", applications).await)
+}
+
+// POST /api/v1/admissions/applications - Enter an application (e.g. taken by phone)
+pub async fn create_application(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<ApplicationRequest>,
+) -> Result<(StatusCode, Json<ApplicationResponse>), ApiError> {
+    require_admissions_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let (application, _) = AdmissionsRepository::create_application(&db, tenant.id(), &payload, false, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Application {} entered by {}", application.id, user.0.email);
+    Ok((StatusCode::CREATED, Json(application.into())))
+}
+
+// GET /api/v1/admissions/applications/:id - An application with documents,
+// reviews and letters
+pub async fn get_application(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<ApplicationDetail>, ApiError> {
+    require_reviewer(&user)?;
+
+    let application = AdmissionsRepository::application_detail(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or(admissions_error(AdmissionsError::ApplicationNotFound))?;
+    Ok(Json(application))
+}
+
+// PUT /api/v1/admissions/applications/:id/stage - Move an application along
+pub async fn move_stage(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<MoveStageRequest>,
+) -> Result<Json<ApplicationResponse>, ApiError> {
+    require_admissions_staff(&user)?;
+
+    let application = AdmissionsRepository::move_stage(&db, tenant.id(), id, payload.stage_id, &context)
+        .await
+        .map_err(admissions_error)?;
+    tracing::info!("Application {} moved to stage {} by {}", id, application.stage_id, user.0.email);
+    Ok(Json(application.into()))
+}
+
+// POST /api/v1/admissions/applications/:id/documents?kind=&filename= - Add
+// a document on the family's behalf
+pub async fn upload_document(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    UploadedDocument(upload): UploadedDocument,
+) -> Result<(StatusCode, Json<DocumentResponse>), ApiError> {
+    require_admissions_staff(&user)?;
+
+    let document = AdmissionsRepository::add_document(&db, tenant.id(), id, upload, &context)
+        .await
+        .map_err(admissions_error)?;
+    tracing::info!("Document {} added to application {} by {}", document.filename, id, user.0.email);
+    Ok((StatusCode::CREATED, Json(document.into())))
+}
+
+// GET /api/v1/admissions/documents/:id - Download a document
+pub async fn download_document(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Response, ApiError> {
+    require_reviewer(&user)?;
+
+    let document = AdmissionsRepository::document(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or(admissions_error(AdmissionsError::DocumentNotFound))?;
+    let disposition = format!("attachment; filename=\"{}\"", document.filename);
+    Ok((
+        [(header::CONTENT_TYPE, document.content_type), (header::CONTENT_DISPOSITION, disposition)],
+        document.content,
+    )
+        .into_response())
+}
+
+// POST /api/v1/admissions/applications/:id/reviews - Score an application
+pub async fn review(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<ReviewRequest>,
+) -> Result<Json<ReviewResponse>, ApiError> {
+    require_reviewer(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let review = AdmissionsRepository::review(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(admissions_error)?;
+    tracing::info!("Application {} scored {} by {}", id, review.score, user.0.email);
+    Ok(Json(review.into()))
+}
+
+// POST /api/v1/admissions/decisions - Decide a batch of applications and
+// email each family its letter. Letters that fail to send stay unsent on
+// the application for staff to follow up.
+pub async fn decide(
+    State(db): State<DatabaseConnection>,
+    State(mailer): State<SharedMailer>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<DecisionRequest>,
+) -> Result<Json<Vec<LetterResponse>>, ApiError> {
+    require_admissions_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let school = tenant.0.name.clone();
+    let message = payload.message.as_deref();
+    let letters = AdmissionsRepository::decide(
+        &db,
+        tenant.id(),
+        &payload.application_ids,
+        &payload.decision,
+        |application| admissions::decision_letter(&school, application, &payload.decision, message),
+        &context,
+    )
+    .await
+    .map_err(admissions_error)?;
+
+    let mut sent = Vec::with_capacity(letters.len());
+    for letter in letters {
+        let email = Email {
+            to: letter.sent_to.clone(),
+            subject: letter.subject.clone(),
+            body: letter.body.clone(),
+            html: None,
+            from_name: Some(school.clone()),
+        };
+        match mailer.send(&email).await {
+            Ok(()) => {
+                AdmissionsRepository::mark_letter_sent(&db, letter.id)
+                    .await
+                    .map_err(db_error)?;
+                sent.push(LetterResponse {
+                    sent_at: Some(chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+                    ..letter.into()
+                });
+            }
+            Err(e) => {
+                tracing::error!("Failed to send admission letter {} to {}: {}", letter.id, letter.sent_to, e);
+                sent.push(letter.into());
+            }
+        }
+    }
+    tracing::info!(
+        "{} applications marked {} by {}",
+        payload.application_ids.len(),
+        payload.decision,
+        user.0.email
+    );
+    Ok(Json(sent))
+}
+
+// POST /api/v1/admissions/applications/:id/enrol - Create the student
+// account for an accepted applicant
+pub async fn enrol(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    payload: Option<Json<EnrolRequest>>,
+) -> Result<(StatusCode, Json<EnrolResponse>), ApiError> {
+    require_admissions_staff(&user)?;
+    let Json(payload) = payload.unwrap_or_default();
+    payload.validate().map_err(validation_error)?;
+
+    let (application, student) = AdmissionsRepository::enrol(&db, tenant.id(), id, payload.email.as_deref(), &context)
+        .await
+        .map_err(admissions_error)?;
+    tracing::info!("Application {} enrolled as {} by {}", id, student.email, user.0.email);
+    Ok((
+        StatusCode::CREATED,
+        Json(EnrolResponse {
+            application: application.into(),
+            student: student.into(),
+        }),
+    ))
+}
@@ -0,0 +1,372 @@
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::announcements::{AnnouncementError, AttachmentUpload, Reader, ANNOUNCEMENT_ROLES};
+use crate::application::audit::AuditContext;
+use crate::application::notifications;
+use crate::dto::announcements::{
+    AnnouncementQuery, AnnouncementRequest, AnnouncementResponse, AttachmentQuery, AttachmentResponse, ClassRequest,
+    ClassResponse, NoticeResponse, ReceiptResponse, UnreadCountResponse,
+};
+use crate::infrastructure::notifications::NotificationHub;
+use crate::repositories::announcement_repository::AnnouncementRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn announcement_error(e: AnnouncementError) -> ApiError {
+    match e {
+        AnnouncementError::NotFound => (StatusCode::NOT_FOUND, "Announcement not found".to_string()),
+        AnnouncementError::AttachmentNotFound => (StatusCode::NOT_FOUND, "Attachment not found".to_string()),
+        AnnouncementError::UserNotFound => (StatusCode::NOT_FOUND, "User not found".to_string()),
+        AnnouncementError::UnsupportedAttachment => (StatusCode::UNSUPPORTED_MEDIA_TYPE, capitalize(&e.to_string())),
+        AnnouncementError::Database(e) => db_error(e),
+        e => (StatusCode::BAD_REQUEST, capitalize(&e.to_string())),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn require_announcement_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !ANNOUNCEMENT_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and principals can manage announcements".to_string()));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+async fn reader(db: &DatabaseConnection, user: &AuthUser) -> Result<Reader, ApiError> {
+    AnnouncementRepository::reader(db, user.id()?, &user.0.role)
+        .await
+        .map_err(db_error)
+}
+
+// Tell readers about notices that have just gone live; those scheduled for
+// later are announced by the sweep. Best effort: the notice is on the board
+// either way.
+async fn announce(db: &DatabaseConnection, hub: &NotificationHub) {
+    if let Err(e) = notifications::announce_due(db, hub).await {
+        tracing::error!("Failed to announce notices: {:?}", e);
+    }
+}
+
+// An attachment sent as the raw request body, named by `?filename=`; its
+// type comes from Content-Type
+pub struct UploadedAttachment(AttachmentUpload);
+
+impl<S> FromRequest<S> for UploadedAttachment
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = request.into_parts();
+        let Query(query) = Query::<AttachmentQuery>::from_request_parts(&mut parts, state)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.body_text()))?;
+        let content_type = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let content = Bytes::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?;
+        Ok(UploadedAttachment(AttachmentUpload {
+            filename: query.filename,
+            content_type,
+            content: content.to_vec(),
+        }))
+    }
+}
+
+impl ExportRow for NoticeResponse {
+    const COLUMNS: &'static [&'static str] = &["announcement", "attachments", "read_at"];
+}
+
+impl ExportRow for ReceiptResponse {
+    const COLUMNS: &'static [&'static str] = &["user_id", "full_name", "role", "read_at"];
+}
+
+// GET /api/v1/announcements - The notice board: live notices addressed to
+// the caller, pinned first
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_announcements(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<AnnouncementQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    let reader = reader(&db, &user).await?;
+
+    let notices = AnnouncementRepository::board(&db, tenant.id(), &reader, query.all)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "announcements", notices).await)
+}
+
+// POST /api/v1/announcements - Post or schedule a notice
+pub async fn create_announcement(
+    State(db): State<DatabaseConnection>,
+    State(hub): State<NotificationHub>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<AnnouncementRequest>,
+) -> Result<(StatusCode, Json<AnnouncementResponse>), ApiError> {
+    require_announcement_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let announcement = AnnouncementRepository::create(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(announcement_error)?;
+    announce(&db, &hub).await;
+    tracing::info!(
+        "Announcement {} posted to {} by {}",
+        announcement.id,
+        announcement.audience,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(announcement.into())))
+}
+
+// GET /api/v1/announcements/unread-count - How many live notices addressed
+// to the caller they have not read
+pub async fn unread_count(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+) -> Result<Json<UnreadCountResponse>, ApiError> {
+    let reader = reader(&db, &user).await?;
+
+    let unread = AnnouncementRepository::unread_count(&db, tenant.id(), &reader)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(UnreadCountResponse { unread }))
+}
+
+// GET /api/v1/announcements/:id - One notice with its attachments
+pub async fn get_announcement(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<NoticeResponse>, ApiError> {
+    let reader = reader(&db, &user).await?;
+
+    let notice = AnnouncementRepository::notice(&db, tenant.id(), id, &reader)
+        .await
+        .map_err(db_error)?
+        .ok_or(announcement_error(AnnouncementError::NotFound))?;
+    Ok(Json(notice))
+}
+
+// PUT /api/v1/announcements/:id - Edit, reschedule, retarget or pin a notice
+pub async fn update_announcement(
+    State(db): State<DatabaseConnection>,
+    State(hub): State<NotificationHub>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<AnnouncementRequest>,
+) -> Result<Json<AnnouncementResponse>, ApiError> {
+    require_announcement_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let announcement = AnnouncementRepository::update(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(announcement_error)?;
+    announce(&db, &hub).await;
+    tracing::info!("Announcement {} updated by {}", announcement.id, user.0.email);
+    Ok(Json(announcement.into()))
+}
+
+// DELETE /api/v1/announcements/:id - Take a notice down
+pub async fn delete_announcement(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_announcement_staff(&user)?;
+
+    if !AnnouncementRepository::delete(&db, tenant.id(), id, &context)
+        .await
+        .map_err(db_error)?
+    {
+        return Err(announcement_error(AnnouncementError::NotFound));
+    }
+    tracing::info!("Announcement {} deleted by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/v1/announcements/:id/read - Mark a notice as read
+pub async fn mark_read(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<NoticeResponse>, ApiError> {
+    let reader = reader(&db, &user).await?;
+
+    let notice = AnnouncementRepository::mark_read(&db, tenant.id(), id, &reader)
+        .await
+        .map_err(announcement_error)?;
+    Ok(Json(notice))
+}
+
+// GET /api/v1/announcements/:id/receipts - Who has read a notice, out of
+// everyone it is addressed to
+// Exports the readers as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn receipts(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_announcement_staff(&user)?;
+
+    let receipts = AnnouncementRepository::receipts(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or(announcement_error(AnnouncementError::NotFound))?;
+    if format.is_some() {
+        return Ok(list_response(format, "announcement-receipts", receipts.readers).await);
+    }
+    Ok(Json(receipts).into_response())
+}
+
+// POST /api/v1/announcements/:id/attachments?filename= - Attach a file to a
+// notice (raw body; Content-Type gives the file type)
+pub async fn add_attachment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    UploadedAttachment(upload): UploadedAttachment,
+) -> Result<(StatusCode, Json<AttachmentResponse>), ApiError> {
+    require_announcement_staff(&user)?;
+
+    let attachment = AnnouncementRepository::add_attachment(&db, tenant.id(), id, upload, &context)
+        .await
+        .map_err(announcement_error)?;
+    tracing::info!(
+        "Attachment {} added to announcement {} by {}",
+        attachment.filename,
+        id,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(attachment.into())))
+}
+
+// GET /api/v1/announcements/attachments/:id - Download an attachment
+pub async fn download_attachment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Response, ApiError> {
+    let reader = reader(&db, &user).await?;
+
+    let attachment = AnnouncementRepository::attachment(&db, tenant.id(), id, &reader)
+        .await
+        .map_err(db_error)?
+        .ok_or(announcement_error(AnnouncementError::AttachmentNotFound))?;
+    let disposition = format!("attachment; filename=\"{}\"", attachment.filename);
+    Ok((
+        [(header::CONTENT_TYPE, attachment.content_type), (header::CONTENT_DISPOSITION, disposition)],
+        attachment.content,
+    )
+        .into_response())
+}
+
+// DELETE /api/v1/announcements/attachments/:id - Remove an attachment
+pub async fn remove_attachment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_announcement_staff(&user)?;
+
+    if !AnnouncementRepository::remove_attachment(&db, tenant.id(), id, &context)
+        .await
+        .map_err(db_error)?
+    {
+        return Err(announcement_error(AnnouncementError::AttachmentNotFound));
+    }
+    tracing::info!("Announcement attachment {} removed by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// PUT /api/v1/users/:id/class - Place a user in a grade and section, so
+// grade and section notices reach them
+pub async fn set_class(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<ClassRequest>,
+) -> Result<Json<ClassResponse>, ApiError> {
+    require_announcement_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let membership = AnnouncementRepository::set_class(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(announcement_error)?;
+    tracing::info!("User {} placed in {} by {}", id, membership.grade, user.0.email);
+    Ok(Json(membership.into()))
+}
+
+// DELETE /api/v1/users/:id/class - Take a user out of their class
+pub async fn clear_class(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_announcement_staff(&user)?;
+
+    if !AnnouncementRepository::clear_class(&db, tenant.id(), id, &context)
+        .await
+        .map_err(db_error)?
+    {
+        return Err((StatusCode::NOT_FOUND, "User is not in a class".to_string()));
+    }
+    tracing::info!("User {} taken out of their class by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::api_keys::{self, AUTH_SOURCE_SERVICE};
+use crate::application::audit::AuditContext;
+use crate::dto::api_key::{
+    ApiKeyResponse, CreateApiKeyRequest, CreateServiceAccountRequest, CreatedApiKeyResponse,
+    ServiceAccountsListResponse,
+};
+use crate::dto::user::UserResponse;
+use crate::repositories::api_key_repository::ApiKeyRepository;
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn require_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can manage service accounts".to_string()));
+    }
+    Ok(())
+}
+
+impl ExportRow for ApiKeyResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "user_id", "name", "prefix", "scopes", "expires_at", "last_used_at", "revoked_at", "created_at"];
+}
+
+// GET /api/v1/api-keys - List the caller's API keys
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list(
+    State(db): State<DatabaseConnection>,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    let keys = ApiKeyRepository::list_for_user(&db, user.id()?)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "api-keys", keys.into_iter().map(ApiKeyResponse::from).collect()).await)
+}
+
+// POST /api/v1/api-keys - Create a key for yourself; admins may create one
+// for a service account
+pub async fn create(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+    if let Some(scope) = payload.scopes.iter().find(|scope| !api_keys::valid_scope(scope)) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid scope: {}", scope)));
+    }
+
+    let caller_id = user.id()?;
+    let owner_id = payload.user_id.unwrap_or(caller_id);
+    // Keys act as their owner, so the only other accounts an admin may mint
+    // them for are service accounts, never a person's account
+    if owner_id != caller_id {
+        require_admin(&user)?;
+        let is_service_account = UserRepository::find_by_auth_source(&db, tenant.id(), AUTH_SOURCE_SERVICE)
+            .await
+            .map_err(db_error)?
+            .iter()
+            .any(|account| account.id == owner_id);
+        if !is_service_account {
+            return Err((StatusCode::NOT_FOUND, "Service account not found".to_string()));
+        }
+    }
+
+    let new_key = api_keys::generate();
+    let api_key = ApiKeyRepository::create(&db, owner_id, caller_id, &payload, &new_key, &context)
+        .await
+        .map_err(db_error)?;
+
+    tracing::info!("API key {} created for user {} by {}", api_key.prefix, owner_id, user.0.email);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse {
+            key: new_key.key,
+            api_key: api_key.into(),
+        }),
+    ))
+}
+
+// DELETE /api/v1/api-keys/:id - Revoke a key (its owner or an admin)
+pub async fn revoke(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let api_key = ApiKeyRepository::find_by_id(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "API key not found".to_string()))?;
+    if api_key.user_id != user.id()? && user.0.role != "admin" {
+        return Err((StatusCode::NOT_FOUND, "API key not found".to_string()));
+    }
+
+    if ApiKeyRepository::revoke(&db, id, &context).await.map_err(db_error)? {
+        tracing::info!("API key {} revoked by {}", api_key.prefix, user.0.email);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/v1/service-accounts - List service accounts (admin)
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_service_accounts(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_admin(&admin)?;
+
+    let accounts: Vec<UserResponse> = UserRepository::find_by_auth_source(&db, tenant.id(), AUTH_SOURCE_SERVICE)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(UserResponse::from)
+        .collect();
+    if format.is_some() {
+        return Ok(list_response(format, "service-accounts", accounts).await);
+    }
+    let total = accounts.len();
+    Ok(Json(ServiceAccountsListResponse {
+        service_accounts: accounts,
+        total,
+    })
+    .into_response())
+}
+
+// POST /api/v1/service-accounts - Create a service account (admin). It has no
+// usable password; give it API keys through POST /api-keys with its user_id.
+pub async fn create_service_account(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CreateServiceAccountRequest>,
+) -> Result<(StatusCode, Json<UserResponse>), ApiError> {
+    require_admin(&admin)?;
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let email = format!("svc-{}@service.invalid", &id[..12]);
+    let account = UserRepository::provision(&db, tenant.id(), &email, &payload.name, &payload.role, AUTH_SOURCE_SERVICE, &context)
+        .await
+        .map_err(db_error)?;
+
+    tracing::info!("Service account {} created by {}", account.email, admin.0.email);
+    Ok((StatusCode::CREATED, Json(account)))
+}
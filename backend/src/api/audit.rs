@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+
+use crate::api::export::{export_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::dto::audit::{AuditEntryResponse, AuditListResponse, AuditQuery, AuditVerifyResponse};
+use crate::repositories::audit_repository::{AuditFilter, AuditRepository};
+
+type ApiError = (StatusCode, String);
+
+const DEFAULT_LIMIT: u64 = 50;
+const MAX_LIMIT: u64 = 500;
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn require_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can read the audit log".to_string()));
+    }
+    Ok(())
+}
+
+impl ExportRow for AuditEntryResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "actor_id", "actor_email", "impersonator_id", "action", "entity", "entity_id", "before", "after",
+        "ip_address", "request_id", "created_at", "hash", "redacted_at",
+    ];
+}
+
+// GET /api/v1/audit - Search the school's audit log, newest first (admin)
+// Also exports every match as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Query(query): Query<AuditQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_admin(&admin)?;
+
+    let filter = AuditFilter {
+        actor_id: query.actor_id,
+        action: query.action,
+        entity: query.entity,
+        entity_id: query.entity_id,
+    };
+    // Exports hold every match, not one page
+    if let Some(format) = format {
+        return Ok(export_response(format, "audit-log", AuditRepository::stream(db, tenant.id(), filter)).await);
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let (entries, total) = AuditRepository::find(&db, tenant.id(), &filter, query.offset.unwrap_or(0), limit)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(AuditListResponse {
+        entries: entries.into_iter().map(AuditEntryResponse::from).collect(),
+        total,
+    })
+    .into_response())
+}
+
+// GET /api/v1/audit/verify - Check the hash chain for tampering (platform admin).
+// There is one chain across all schools.
+pub async fn verify(
+    State(db): State<DatabaseConnection>,
+    admin: AuthUser,
+) -> Result<Json<AuditVerifyResponse>, ApiError> {
+    if !admin.is_platform_admin() {
+        return Err((StatusCode::FORBIDDEN, "Only platform admins can verify the audit log".to_string()));
+    }
+
+    let (entries_checked, first_invalid_id) = AuditRepository::verify(&db).await.map_err(db_error)?;
+    if let Some(id) = first_invalid_id {
+        tracing::error!("Audit log hash chain broken at entry {}", id);
+    }
+    Ok(Json(AuditVerifyResponse {
+        valid: first_invalid_id.is_none(),
+        entries_checked,
+        first_invalid_id,
+    }))
+}
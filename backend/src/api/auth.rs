@@ -0,0 +1,199 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Redirect,
+    Json,
+};
+use sea_orm::{DatabaseConnection, DbErr};
+use validator::Validate;
+
+use crate::application::audit::AuditContext;
+use crate::application::auth;
+use crate::application::directory::{self, LdapDirectory, DIRECTORY_TENANT_ID};
+use crate::application::login_guard::LoginGuard;
+use crate::application::oidc::{self, OidcClient, OidcError};
+use crate::config::Config;
+use crate::api::extractors::{ClientIp, CurrentTenant};
+use crate::api::two_factor;
+use crate::dto::auth::{LoginOutcome, LoginRequest, LoginResponse, OidcCallbackQuery};
+use crate::dto::user::UserResponse;
+use crate::infrastructure::jwks::JwksCache;
+use crate::repositories::oidc_repository::OidcRepository;
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: DbErr) -> ApiError {
+    tracing::error!("Database error in auth: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
+
+fn oidc_error(e: OidcError) -> ApiError {
+    tracing::warn!("OIDC login failed: {}", e);
+    let status = match e {
+        OidcError::Provider(_) | OidcError::Jwks(_) => StatusCode::BAD_GATEWAY,
+        OidcError::InvalidToken(_) | OidcError::MissingEmail => StatusCode::UNAUTHORIZED,
+    };
+    (status, e.to_string())
+}
+
+fn login_response(config: &Config, user: UserResponse) -> Result<Json<LoginResponse>, ApiError> {
+    let token = auth::issue_token(config, &user).map_err(|e| {
+        tracing::error!("Failed to issue token: {:?}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to issue token".to_string())
+    })?;
+    Ok(Json(LoginResponse { token, user }))
+}
+
+// POST /api/v1/auth/login - Sign in with email and password
+pub async fn login(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    State(ldap): State<Option<LdapDirectory>>,
+    State(guard): State<LoginGuard>,
+    tenant: CurrentTenant,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Json<LoginOutcome>, ApiError> {
+    if let Err(e) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+    guard
+        .check(tenant.id(), &payload.email, ip)
+        .await
+        .map_err(|blocked| (StatusCode::TOO_MANY_REQUESTS, blocked.message()))?;
+
+    let mut user = UserRepository::authenticate(&db, tenant.id(), &payload.email, &payload.password)
+        .await
+        .map_err(db_error)?;
+
+    // Not a local password: ask the directory, if there is one and it serves this school
+    if let (None, Some(ldap), DIRECTORY_TENANT_ID) = (&user, &ldap, tenant.id()) {
+        // Account changes pulled from the directory are audited as system changes
+        let context = AuditContext {
+            tenant_id: Some(DIRECTORY_TENANT_ID),
+            ip_address: ip.map(|ip| ip.to_string()),
+            ..Default::default()
+        };
+        user = match directory::login(&db, ldap, &config.ldap_group_role_map, &payload.email, &payload.password, &context).await {
+            Ok(user) => user,
+            Err(e) => {
+                // Counted like a wrong password, so guesses cannot go unthrottled
+                // while the directory is failing
+                guard.record_failure(tenant.id(), &payload.email, ip).await;
+                tracing::error!("Directory login failed: {}", e);
+                return Err((StatusCode::BAD_GATEWAY, "Directory is unavailable".to_string()));
+            }
+        };
+    }
+    // Roles that must use single sign-on are refused like a wrong password,
+    // so the answer never tells that the password was right
+    if let Some(refused) = user.as_ref().filter(|user| config.local_login_disabled_roles.contains(&user.role)) {
+        tracing::info!("Password login refused for {} account {}", refused.role, refused.email);
+        user = None;
+    }
+    let Some(user) = user else {
+        guard.record_failure(tenant.id(), &payload.email, ip).await;
+        return Err((StatusCode::UNAUTHORIZED, "Invalid email or password".to_string()));
+    };
+    guard.record_success(tenant.id(), &payload.email).await;
+
+    tracing::info!("User logged in: {}", user.email);
+    two_factor::login_outcome(&db, &config, user).await
+}
+
+// GET /api/v1/auth/oidc/login - Start single sign-on at the identity provider
+pub async fn oidc_login(
+    State(db): State<DatabaseConnection>,
+    State(http): State<reqwest::Client>,
+    State(oidc): State<Option<OidcClient>>,
+    tenant: CurrentTenant,
+) -> Result<Redirect, ApiError> {
+    let oidc = oidc.ok_or((StatusCode::NOT_FOUND, "Single sign-on is not configured".to_string()))?;
+    let metadata = oidc.metadata(&http).await.map_err(oidc_error)?;
+
+    let state = uuid::Uuid::new_v4().simple().to_string();
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let (verifier, challenge) = oidc::pkce_pair();
+    let url = oidc
+        .authorization_url(&metadata, &state, &nonce, &challenge)
+        .map_err(oidc_error)?;
+
+    OidcRepository::save_login_state(&db, tenant.id(), state, nonce, verifier)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Redirect::to(&url))
+}
+
+// GET /api/v1/auth/oidc/callback - Finish single sign-on and issue an rsEdu token
+pub async fn oidc_callback(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    State(http): State<reqwest::Client>,
+    State(jwks): State<JwksCache>,
+    State(oidc): State<Option<OidcClient>>,
+    ClientIp(ip): ClientIp,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let oidc = oidc.ok_or((StatusCode::NOT_FOUND, "Single sign-on is not configured".to_string()))?;
+
+    let login_state = OidcRepository::take_login_state(&db, &query.state)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid or expired login state".to_string()))?;
+
+    if let Some(error) = query.error {
+        let description = query.error_description.unwrap_or_default();
+        return Err((StatusCode::UNAUTHORIZED, format!("Identity provider error: {} {}", error, description)));
+    }
+    let code = query
+        .code
+        .ok_or((StatusCode::BAD_REQUEST, "Missing authorization code".to_string()))?;
+
+    let identity = oidc
+        .exchange_code(&http, &jwks, &code, &login_state.code_verifier, &login_state.nonce)
+        .await
+        .map_err(oidc_error)?;
+
+    // The school the login was started for; the callback URL is shared
+    let tenant_id = login_state.tenant_id;
+    // Changes made from the identity provider's claims are audited as system changes
+    let context = AuditContext {
+        tenant_id: Some(tenant_id),
+        ip_address: ip.map(|ip| ip.to_string()),
+        ..Default::default()
+    };
+    let user = match UserRepository::find_by_email(&db, tenant_id, &identity.email).await.map_err(db_error)? {
+        Some(user) if !user.is_active => {
+            return Err((StatusCode::FORBIDDEN, "Account is deactivated".to_string()));
+        }
+        // Keep the rsEdu role in line with the identity provider
+        Some(user) => match &identity.role {
+            Some(role) if *role != user.role => UserRepository::update_role(&db, tenant_id, user.id, role, &context)
+                .await
+                .map_err(db_error)?
+                .unwrap_or(user),
+            _ => user,
+        },
+        None if UserRepository::email_in_trash(&db, tenant_id, &identity.email).await.map_err(db_error)? => {
+            return Err((StatusCode::FORBIDDEN, "Account has been deleted".to_string()));
+        }
+        None if config.oidc_jit_provisioning => {
+            let role = identity.role.as_deref().unwrap_or(&config.oidc_default_role);
+            let full_name = identity.name.as_deref().unwrap_or(&identity.email);
+            let user = UserRepository::provision(&db, tenant_id, &identity.email, full_name, role, "oidc", &context)
+                .await
+                .map_err(db_error)?;
+            tracing::info!("Provisioned user {} from single sign-on", user.email);
+            user
+        }
+        None => {
+            return Err((StatusCode::FORBIDDEN, "No rsEdu account for this identity".to_string()));
+        }
+    };
+
+    // The identity provider is responsible for the second factor of SSO logins
+    tracing::info!("User logged in via SSO: {} (subject {})", user.email, identity.subject);
+    login_response(&config, user)
+}
@@ -0,0 +1,215 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::data_subject::{self, STATUS_PENDING};
+use crate::application::encryption::Keyring;
+use crate::config::Config;
+use crate::dto::data_subject::{
+    CreateErasureRequest, ErasureDecisionRequest, ErasureRequestDetail, ErasureRequestResponse,
+};
+use crate::entities::erasure_requests;
+use crate::repositories::data_subject_repository::DataSubjectRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn require_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can handle erasure requests".to_string()));
+    }
+    Ok(())
+}
+
+// Users may act on their own data, admins on anyone's
+fn require_self_or_admin(user: &AuthUser, id: i32) -> Result<(), ApiError> {
+    if user.id()? != id && user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "You can only access your own data".to_string()));
+    }
+    Ok(())
+}
+
+async fn find_pending(db: &DatabaseConnection, tenant: &CurrentTenant, id: i32) -> Result<erasure_requests::Model, ApiError> {
+    let request = DataSubjectRepository::find_request(db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Erasure request not found".to_string()))?;
+    if request.status != STATUS_PENDING {
+        return Err((StatusCode::CONFLICT, format!("Erasure request is already {}", request.status)));
+    }
+    Ok(request)
+}
+
+impl ExportRow for ErasureRequestResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "user_id", "requested_by", "reason", "status", "created_at", "completed_at"];
+}
+
+// GET /api/v1/users/:id/data-export - Everything held about a user as a ZIP
+// of data.json and attachments
+pub async fn export(
+    State(db): State<DatabaseConnection>,
+    State(keyring): State<Keyring>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<Response, ApiError> {
+    require_self_or_admin(&user, id)?;
+
+    let (data, attachments) = DataSubjectRepository::collect(&db, &keyring, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    let archive = data_subject::build_archive(&data, &attachments).map_err(|e| {
+        tracing::error!("Failed to build data export for user {}: {}", id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build export".to_string())
+    })?;
+    DataSubjectRepository::log_export(&db, id, &context)
+        .await
+        .map_err(db_error)?;
+
+    tracing::info!("Data export of user {} by {}", id, user.0.email);
+    let disposition = format!("attachment; filename=\"rsedu-user-{}-export.zip\"", id);
+    Ok((
+        [(header::CONTENT_TYPE, "application/zip".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        archive,
+    )
+        .into_response())
+}
+
+// POST /api/v1/users/:id/erasure-requests - Ask for a user's data to be erased
+pub async fn request_erasure(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<CreateErasureRequest>,
+) -> Result<(StatusCode, Json<ErasureRequestResponse>), ApiError> {
+    require_self_or_admin(&user, id)?;
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+
+    let request = DataSubjectRepository::create_request(&db, tenant.id(), id, user.id()?, &payload.reason)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    tracing::info!("Erasure of user {} requested by {}", id, user.0.email);
+    Ok((StatusCode::CREATED, Json(request.into())))
+}
+
+// GET /api/v1/erasure-requests - All erasure requests, newest first (admin)
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_requests(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_admin(&admin)?;
+
+    let requests = DataSubjectRepository::list_requests(&db, tenant.id()).await.map_err(db_error)?;
+    let requests: Vec<_> = requests.into_iter().map(ErasureRequestResponse::from).collect();
+    Ok(list_response(format, "erasure-requests", requests).await)
+}
+
+// GET /api/v1/erasure-requests/:id - A request and its approval log (admin)
+pub async fn get_request(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<ErasureRequestDetail>, ApiError> {
+    require_admin(&admin)?;
+
+    let request = DataSubjectRepository::find_request(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Erasure request not found".to_string()))?;
+    let events = DataSubjectRepository::events(&db, id).await.map_err(db_error)?;
+    Ok(Json(ErasureRequestDetail {
+        request: request.into(),
+        events: events.into_iter().map(Into::into).collect(),
+    }))
+}
+
+// POST /api/v1/erasure-requests/:id/approve - Approve; the last required
+// approval carries out the erasure (admin, not the requester)
+pub async fn approve(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<ErasureDecisionRequest>,
+) -> Result<Json<ErasureRequestResponse>, ApiError> {
+    require_admin(&admin)?;
+    let admin_id = admin.id()?;
+
+    let request = find_pending(&db, &tenant, id).await?;
+    if request.requested_by == admin_id {
+        return Err((StatusCode::FORBIDDEN, "Someone other than the requester must approve".to_string()));
+    }
+    let events = DataSubjectRepository::events(&db, id).await.map_err(db_error)?;
+    let mut approvers: Vec<i32> = events
+        .iter()
+        .filter(|event| event.event == "approved")
+        .filter_map(|event| event.actor_id)
+        .collect();
+    if approvers.contains(&admin_id) {
+        return Err((StatusCode::CONFLICT, "You already approved this request".to_string()));
+    }
+
+    DataSubjectRepository::add_event(&db, id, Some(admin_id), "approved", payload.comment.as_deref())
+        .await
+        .map_err(db_error)?;
+    approvers.push(admin_id);
+
+    if data_subject::approved(request.requested_by, &approvers, config.erasure_required_approvals) {
+        if !DataSubjectRepository::erase(&db, &request, &context).await.map_err(db_error)? {
+            return Err((StatusCode::NOT_FOUND, "User no longer exists".to_string()));
+        }
+        tracing::warn!("User {} erased (request {}, last approval by {})", request.user_id, id, admin.0.email);
+    }
+
+    let request = DataSubjectRepository::find_request(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Erasure request not found".to_string()))?;
+    Ok(Json(request.into()))
+}
+
+// POST /api/v1/erasure-requests/:id/reject - Close a request without erasing (admin)
+pub async fn reject(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<ErasureDecisionRequest>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&admin)?;
+
+    find_pending(&db, &tenant, id).await?;
+    if !DataSubjectRepository::reject(&db, id, admin.id()?, payload.comment.as_deref())
+        .await
+        .map_err(db_error)?
+    {
+        return Err((StatusCode::CONFLICT, "Erasure request is no longer pending".to_string()));
+    }
+    tracing::info!("Erasure request {} rejected by {}", id, admin.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,37 @@
+use axum::{extract::State, http::StatusCode, Json};
+use sea_orm::DatabaseConnection;
+
+use crate::api::extractors::AuthUser;
+use crate::application::audit::AuditContext;
+use crate::application::directory::{self, DirectoryError, LdapDirectory, SyncReport};
+use crate::config::Config;
+
+type ApiError = (StatusCode, String);
+
+fn directory_error(e: DirectoryError) -> ApiError {
+    tracing::error!("Directory sync failed: {:?}", e);
+    match e {
+        DirectoryError::Ldap(e) => (StatusCode::BAD_GATEWAY, format!("Directory error: {}", e)),
+        DirectoryError::Database(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)),
+    }
+}
+
+// POST /api/v1/admin/ldap/sync - Run the directory sync now instead of waiting for the schedule (platform admin)
+pub async fn sync(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    State(ldap): State<Option<LdapDirectory>>,
+    admin: AuthUser,
+    context: AuditContext,
+) -> Result<Json<SyncReport>, ApiError> {
+    if !admin.is_platform_admin() {
+        return Err((StatusCode::FORBIDDEN, "Only platform admins can run the directory sync".to_string()));
+    }
+    let ldap = ldap.ok_or((StatusCode::NOT_FOUND, "LDAP is not configured".to_string()))?;
+
+    let report = directory::sync(&db, &ldap, &config.ldap_group_role_map, &context)
+        .await
+        .map_err(directory_error)?;
+    tracing::info!("Directory sync run by {}", admin.0.email);
+    Ok(Json(report))
+}
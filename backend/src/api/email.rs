@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::email::{self, Branding, EmailError, ABSENCE_ALERT_ROLES, OUTBOX_ROLES, STATUSES};
+use crate::dto::email::{AbsenceAlertRequest, AbsenceAlertResponse, OutboxEmailResponse, OutboxQuery};
+use crate::repositories::email_repository::EmailRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn email_error(e: EmailError) -> ApiError {
+    match e {
+        EmailError::NotFound => (StatusCode::NOT_FOUND, "Email not found".to_string()),
+        EmailError::StudentNotFound => (StatusCode::NOT_FOUND, "Student not found".to_string()),
+        EmailError::NotFailed | EmailError::NotRetriable | EmailError::NoGuardians => (StatusCode::CONFLICT, capitalize(&e.to_string())),
+        EmailError::Template(e) => {
+            tracing::error!("Email template error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render email".to_string())
+        }
+        EmailError::Database(e) => db_error(e),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn require_outbox_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !OUTBOX_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins can manage the email outbox".to_string()));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+impl ExportRow for OutboxEmailResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "template", "to_address", "subject", "status", "attempts", "next_attempt_at", "last_error", "created_at",
+        "sent_at",
+    ];
+}
+
+// GET /api/v1/email/outbox?status= - The school's most recent emails and how
+// their delivery went
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_outbox(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<OutboxQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_outbox_staff(&user)?;
+    if let Some(status) = query.status.as_deref().filter(|status| !STATUSES.contains(status)) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown status {}; expected one of: {}", status, STATUSES.join(", ")),
+        ));
+    }
+
+    let emails = EmailRepository::outbox(&db, tenant.id(), query.status.as_deref())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "email-outbox", emails.into_iter().map(OutboxEmailResponse::from).collect()).await)
+}
+
+// POST /api/v1/email/outbox/:id/retry - Send a failed email again
+pub async fn retry_email(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<Json<OutboxEmailResponse>, ApiError> {
+    require_outbox_staff(&user)?;
+    let email = EmailRepository::retry(&db, tenant.id(), id, &context)
+        .await
+        .map_err(email_error)?;
+    tracing::info!("Email {} queued for retry by {}", id, user.0.email);
+    Ok(Json(email.into()))
+}
+
+// POST /api/v1/users/:id/absence-alerts - Email a student's guardians that
+// they were absent
+pub async fn send_absence_alert(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(student_id): Path<i32>,
+    Json(payload): Json<AbsenceAlertRequest>,
+) -> Result<(StatusCode, Json<AbsenceAlertResponse>), ApiError> {
+    if !ABSENCE_ALERT_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only teachers and school leaders can send absence alerts".to_string()));
+    }
+    payload.validate().map_err(validation_error)?;
+
+    let (student, guardians) = EmailRepository::guardians(&db, tenant.id(), student_id)
+        .await
+        .map_err(email_error)?;
+    if guardians.is_empty() {
+        return Err(email_error(EmailError::NoGuardians));
+    }
+    let branding = Branding::from(&tenant.0);
+    let alerts = guardians
+        .iter()
+        .map(|guardian| email::absence_alert(&branding, guardian, &student, payload.date, payload.note.as_deref()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| email_error(e.into()))?;
+
+    let queued = EmailRepository::enqueue(&db, tenant.id(), email::ABSENCE_ALERT, &alerts)
+        .await
+        .map_err(db_error)?;
+    tracing::info!(
+        "Absence alert for student {} on {} queued for {} guardians by {}",
+        student_id,
+        payload.date,
+        queued.len(),
+        user.0.email
+    );
+    Ok((StatusCode::ACCEPTED, Json(AbsenceAlertResponse { queued: queued.len() })))
+}
@@ -0,0 +1,467 @@
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{Stream, StreamExt, TryStreamExt};
+use rust_xlsxwriter::Workbook;
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// Query string accepted by every exportable list endpoint (?format=csv|xlsx|jsonl)
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+}
+
+// A type that can be exported. COLUMNS names its serialized fields in the
+// order files show them; the header is written from it even when there
+// are no rows.
+pub trait ExportRow: Serialize {
+    const COLUMNS: &'static [&'static str];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    JsonLines,
+}
+
+impl ExportFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "xlsx" => Some(Self::Xlsx),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "text/csv" => Some(Self::Csv),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => Some(Self::Xlsx),
+            "application/jsonl" | "application/x-ndjson" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Self::JsonLines => "application/jsonl",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+            Self::JsonLines => "jsonl",
+        }
+    }
+
+    // Pick the export format for a request. `?format=` wins over the Accept
+    // header, where the first type we know decides. Ok(None) means the caller
+    // should answer with its normal JSON body; an Accept header naming only
+    // types we cannot produce is 406.
+    pub fn negotiate(query: &ExportQuery, headers: &HeaderMap) -> Result<Option<Self>, StatusCode> {
+        if let Some(name) = query.format.as_deref() {
+            return match name {
+                "json" => Ok(None),
+                _ => Self::from_name(name).map(Some).ok_or(StatusCode::BAD_REQUEST),
+            };
+        }
+
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if accept.trim().is_empty() {
+            return Ok(None);
+        }
+
+        for part in accept.split(',') {
+            let mime = part.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+            if let Some(format) = Self::from_mime(&mime) {
+                return Ok(Some(format));
+            }
+            if matches!(mime.as_str(), "application/json" | "application/*" | "*/*") {
+                return Ok(None);
+            }
+        }
+        Err(StatusCode::NOT_ACCEPTABLE)
+    }
+}
+
+// Error body for a failed negotiation, for handlers that answer with text
+pub fn format_error(status: StatusCode) -> (StatusCode, String) {
+    let message = match status {
+        StatusCode::NOT_ACCEPTABLE => "Accept must allow one of: application/json, text/csv, xlsx, application/jsonl",
+        _ => "Format must be one of: json, csv, xlsx, jsonl",
+    };
+    (status, message.to_string())
+}
+
+// The export format a list request asked for, if any (see `ExportFormat::negotiate`)
+pub struct Export(pub Option<ExportFormat>);
+
+impl<S: Send + Sync> FromRequestParts<S> for Export {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = Query::<ExportQuery>::try_from_uri(&parts.uri)
+            .map(|Query(query)| query)
+            .unwrap_or_default();
+        ExportFormat::negotiate(&query, &parts.headers)
+            .map(Export)
+            .map_err(format_error)
+    }
+}
+
+// A list answered as JSON, or as a download when an export was asked for
+pub async fn list_response<T>(format: Option<ExportFormat>, name: &str, rows: Vec<T>) -> Response
+where
+    T: ExportRow + Send + 'static,
+{
+    match format {
+        Some(format) => export_response(format, name, futures::stream::iter(rows.into_iter().map(Ok))).await,
+        None => Json(rows).into_response(),
+    }
+}
+
+// Turn a stream of rows into a downloadable response.
+// CSV and JSON Lines are written to the socket row by row; XLSX has to be
+// assembled in memory because the file is a zip archive.
+pub async fn export_response<S, T>(format: ExportFormat, name: &str, rows: S) -> Response
+where
+    S: Stream<Item = Result<T, DbErr>> + Send + 'static,
+    T: ExportRow + Send + 'static,
+{
+    let body = match format {
+        ExportFormat::Csv => Body::from_stream(csv_stream(rows)),
+        ExportFormat::JsonLines => Body::from_stream(jsonl_stream(rows)),
+        ExportFormat::Xlsx => match xlsx_bytes(rows).await {
+            Ok(bytes) => Body::from(bytes),
+            Err(e) => {
+                tracing::error!("Failed to build XLSX export: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+    };
+
+    let disposition = format!("attachment; filename=\"{}.{}\"", name, format.extension());
+
+    let mut response = Response::new(body);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+// A row as a map of its serialized fields
+fn fields<T: Serialize>(row: &T) -> Result<Map<String, Value>, DbErr> {
+    match serde_json::to_value(row) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err(DbErr::Custom("Export needs struct rows".to_string())),
+        Err(e) => Err(DbErr::Custom(format!("Export failed: {}", e))),
+    }
+}
+
+// A cell's text; missing and null values are empty
+fn text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => defuse(value),
+        Some(other) => other.to_string(),
+    }
+}
+
+// Spreadsheets run text starting with = + - @ (or a tab or carriage return
+// ahead of one) as a formula; a leading ' keeps user input as plain text
+fn defuse(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line<I>(cells: I) -> Result<Vec<u8>, DbErr>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let csv_err = |e: String| DbErr::Custom(format!("CSV export failed: {}", e));
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+    writer.write_record(cells).map_err(|e| csv_err(e.to_string()))?;
+    writer.into_inner().map_err(|e| csv_err(e.to_string()))
+}
+
+fn csv_stream<S, T>(rows: S) -> impl Stream<Item = Result<Vec<u8>, DbErr>> + Send
+where
+    S: Stream<Item = Result<T, DbErr>> + Send,
+    T: ExportRow,
+{
+    // The header goes first, so an empty export or one failing on its first
+    // row still says what its columns are
+    futures::stream::once(async { csv_line(T::COLUMNS) }).chain(rows.map(|row| {
+        let fields = fields(&row?)?;
+        csv_line(T::COLUMNS.iter().map(|column| text(fields.get(*column))))
+    }))
+}
+
+fn jsonl_stream<S, T>(rows: S) -> impl Stream<Item = Result<Vec<u8>, DbErr>> + Send
+where
+    S: Stream<Item = Result<T, DbErr>> + Send,
+    T: Serialize,
+{
+    rows.map(|row| {
+        let mut line = serde_json::to_vec(&row?)
+            .map_err(|e| DbErr::Custom(format!("JSON Lines export failed: {}", e)))?;
+        line.push(b'\n');
+        Ok(line)
+    })
+}
+
+async fn xlsx_bytes<S, T>(rows: S) -> Result<Vec<u8>, DbErr>
+where
+    S: Stream<Item = Result<T, DbErr>>,
+    T: ExportRow,
+{
+    let xlsx_err = |e: rust_xlsxwriter::XlsxError| DbErr::Custom(format!("XLSX export failed: {}", e));
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    for (col, title) in T::COLUMNS.iter().enumerate() {
+        sheet.write_string(0, col as u16, *title).map_err(xlsx_err)?;
+    }
+
+    let mut row_index: u32 = 1;
+    futures::pin_mut!(rows);
+    while let Some(row) = rows.try_next().await? {
+        let fields = fields(&row)?;
+        for (col, key) in T::COLUMNS.iter().enumerate() {
+            let col = col as u16;
+            match fields.get(*key) {
+                Some(Value::Bool(value)) => sheet.write_boolean(row_index, col, *value),
+                Some(Value::Number(value)) => match value.as_f64() {
+                    Some(number) => sheet.write_number(row_index, col, number),
+                    None => sheet.write_string(row_index, col, value.to_string()),
+                },
+                Some(Value::String(value)) => sheet.write_string(row_index, col, defuse(value)),
+                Some(Value::Null) | None => continue,
+                Some(other) => sheet.write_string(row_index, col, other.to_string()),
+            }
+            .map_err(xlsx_err)?;
+        }
+        row_index += 1;
+    }
+
+    workbook.save_to_buffer().map_err(xlsx_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::dto::audit::AuditEntryResponse;
+    use crate::dto::hostel::RoomOccupancy;
+    use crate::dto::transport::ManifestRow;
+    use crate::dto::user::{TrashedUserResponse, UserResponse};
+
+    #[derive(Serialize)]
+    struct Row {
+        // Declared out of column order on purpose
+        note: Option<String>,
+        name: String,
+        count: i32,
+    }
+
+    impl ExportRow for Row {
+        const COLUMNS: &'static [&'static str] = &["name", "count", "note"];
+    }
+
+    fn negotiate(format: Option<&str>, accept: Option<&str>) -> Result<Option<ExportFormat>, StatusCode> {
+        let mut headers = HeaderMap::new();
+        if let Some(accept) = accept {
+            headers.insert(header::ACCEPT, HeaderValue::from_str(accept).unwrap());
+        }
+        ExportFormat::negotiate(&ExportQuery { format: format.map(str::to_string) }, &headers)
+    }
+
+    async fn collect<S: Stream<Item = Result<Vec<u8>, DbErr>>>(stream: S) -> (String, Option<DbErr>) {
+        let mut text = Vec::new();
+        futures::pin_mut!(stream);
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => text.extend(bytes),
+                Err(e) => return (String::from_utf8(text).unwrap(), Some(e)),
+            }
+        }
+        (String::from_utf8(text).unwrap(), None)
+    }
+
+    #[test]
+    fn format_parameter_wins_over_accept_and_unknown_types_are_refused() {
+        assert_eq!(negotiate(Some("csv"), Some("application/jsonl")), Ok(Some(ExportFormat::Csv)));
+        assert_eq!(negotiate(Some("XLSX"), None), Ok(Some(ExportFormat::Xlsx)));
+        assert_eq!(negotiate(Some("json"), Some("text/csv")), Ok(None));
+        assert_eq!(negotiate(Some("pdf"), None), Err(StatusCode::BAD_REQUEST));
+
+        assert_eq!(negotiate(None, None), Ok(None));
+        assert_eq!(negotiate(None, Some("text/csv; charset=utf-8")), Ok(Some(ExportFormat::Csv)));
+        assert_eq!(negotiate(None, Some("text/html, application/x-ndjson")), Ok(Some(ExportFormat::JsonLines)));
+        assert_eq!(negotiate(None, Some("application/json, text/csv")), Ok(None));
+        assert_eq!(negotiate(None, Some("text/html,application/xhtml+xml,*/*;q=0.8")), Ok(None));
+        assert_eq!(negotiate(None, Some("text/html, application/pdf")), Err(StatusCode::NOT_ACCEPTABLE));
+    }
+
+    #[tokio::test]
+    async fn csv_has_its_header_first_and_quotes_cells() {
+        let rows = futures::stream::iter(vec![
+            Ok(Row { note: Some("said \"hi\", left".to_string()), name: "Okafor, Ada".to_string(), count: 2 }),
+            Ok(Row { note: None, name: "line\nbreak".to_string(), count: 0 }),
+        ]);
+        let (text, error) = collect(csv_stream(rows)).await;
+        assert!(error.is_none());
+        assert_eq!(text, "name,count,note\n\"Okafor, Ada\",2,\"said \"\"hi\"\", left\"\n\"line\nbreak\",0,\n");
+
+        let (text, error) = collect(csv_stream(futures::stream::iter(Vec::<Result<Row, DbErr>>::new()))).await;
+        assert!(error.is_none());
+        assert_eq!(text, "name,count,note\n");
+
+        let failing = futures::stream::iter(vec![Err::<Row, _>(DbErr::Custom("gone".to_string()))]);
+        let (text, error) = collect(csv_stream(failing)).await;
+        assert_eq!(text, "name,count,note\n");
+        assert!(error.is_some());
+    }
+
+    #[tokio::test]
+    async fn formulas_in_text_cells_are_defused() {
+        let rows = futures::stream::iter(vec![
+            Ok(Row { note: Some("@SUM(A1)".to_string()), name: "=HYPERLINK(\"http://x\")".to_string(), count: -4 }),
+            Ok(Row { note: Some("-2+3".to_string()), name: "+1 555".to_string(), count: 0 }),
+        ]);
+        let (text, _) = collect(csv_stream(rows)).await;
+        assert_eq!(
+            text,
+            "name,count,note\n\"'=HYPERLINK(\"\"http://x\"\")\",-4,'@SUM(A1)\n'+1 555,0,'-2+3\n"
+        );
+        assert_eq!(defuse("\t=1"), "'\t=1");
+        assert_eq!(defuse("Ada"), "Ada");
+
+        let rows = futures::stream::iter(vec![Ok(Row { note: None, name: "=1+1".to_string(), count: 1 })]);
+        let mut archive = zip::ZipArchive::new(Cursor::new(xlsx_bytes(rows).await.unwrap())).unwrap();
+        let mut strings = String::new();
+        archive.by_name("xl/sharedStrings.xml").unwrap().read_to_string(&mut strings).unwrap();
+        assert!(strings.contains(">'=1+1<"), "{}", strings);
+    }
+
+    #[tokio::test]
+    async fn xlsx_is_a_workbook_with_the_columns_in_order() {
+        let rows = futures::stream::iter(vec![Ok(Row { note: None, name: "Ada".to_string(), count: 3 })]);
+        let bytes = xlsx_bytes(rows).await.unwrap();
+        assert!(bytes.starts_with(b"PK"));
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut strings = String::new();
+        archive.by_name("xl/sharedStrings.xml").unwrap().read_to_string(&mut strings).unwrap();
+        let (name, count, note) = (strings.find(">name<"), strings.find(">count<"), strings.find(">note<"));
+        assert!(name < count && count < note && name.is_some());
+        assert!(strings.contains(">Ada<"));
+
+        let mut sheet = String::new();
+        archive.by_name("xl/worksheets/sheet1.xml").unwrap().read_to_string(&mut sheet).unwrap();
+        assert!(sheet.contains(r#"<c r="B2"><v>3</v></c>"#));
+        assert!(!sheet.contains(r#"r="C2""#));
+    }
+
+    // COLUMNS must name every field of the row and nothing else
+    fn user() -> UserResponse {
+        UserResponse {
+            id: 1,
+            tenant_id: 1,
+            email: "ada@example.com".to_string(),
+            full_name: "Ada".to_string(),
+            role: "student".to_string(),
+            is_active: true,
+            created_at: String::new(),
+        }
+    }
+
+    fn covers<T: ExportRow>(row: T) {
+        let mut fields: Vec<String> = fields(&row).unwrap().keys().cloned().collect();
+        let mut columns: Vec<String> = T::COLUMNS.iter().map(|column| column.to_string()).collect();
+        fields.sort();
+        columns.sort();
+        assert_eq!(fields, columns);
+    }
+
+    #[test]
+    fn exported_rows_list_all_their_columns() {
+        covers(user());
+        covers(RoomOccupancy {
+            room_id: 1,
+            room: "A1".to_string(),
+            building: None,
+            gender: "mixed".to_string(),
+            grade: None,
+            beds: 4,
+            allocated: 2,
+            checked_in: 1,
+            free: 2,
+        });
+        covers(ManifestRow {
+            stop_position: 1,
+            stop: "Gate".to_string(),
+            time: NaiveTime::from_hms_opt(7, 30, 0).unwrap(),
+            student_id: 3,
+            student_name: "Ada".to_string(),
+        });
+        covers(TrashedUserResponse { user: user(), deleted_at: String::new(), purge_after: String::new() });
+        covers(AuditEntryResponse {
+            id: 1,
+            actor_id: None,
+            actor_email: None,
+            impersonator_id: None,
+            action: "create".to_string(),
+            entity: "users".to_string(),
+            entity_id: "1".to_string(),
+            before: None,
+            after: None,
+            ip_address: None,
+            request_id: None,
+            created_at: String::new(),
+            hash: String::new(),
+            redacted_at: None,
+        });
+    }
+
+    #[tokio::test]
+    async fn lists_answer_json_unless_an_export_is_asked_for() {
+        async fn body(response: Response) -> String {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        }
+        let rows = || vec![Row { note: None, name: "=1+1".to_string(), count: 2 }];
+
+        let json = list_response(None, "rows", rows()).await;
+        assert_eq!(json.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body(json).await, r#"[{"note":null,"name":"=1+1","count":2}]"#);
+
+        let csv = list_response(Some(ExportFormat::Csv), "rows", rows()).await;
+        assert!(csv.headers()[header::CONTENT_DISPOSITION].to_str().unwrap().contains("rows"));
+        assert_eq!(body(csv).await, "name,count,note\n'=1+1,2,\n");
+    }
+}
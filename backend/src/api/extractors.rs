@@ -0,0 +1,294 @@
+use std::convert::Infallible;
+use std::net::IpAddr;
+
+use axum::{
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts, OriginalUri},
+    http::{header, request::Parts, StatusCode},
+};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+
+use crate::application::api_keys;
+use crate::application::audit::AuditContext;
+use crate::application::auth::{self, Claims};
+use crate::application::tenancy::{self, DEFAULT_TENANT_ID, TENANT_HEADER};
+use crate::config::Config;
+use crate::entities::tenants;
+use crate::infrastructure::rate_limit;
+use crate::repositories::api_key_repository::ApiKeyRepository;
+use crate::repositories::tenant_repository::TenantRepository;
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+// The school a request is for: the one named by the X-Tenant header, else the
+// subdomain of the Host under TENANT_BASE_DOMAIN, else the school of the
+// caller's token. Requests with none of these go to the default school.
+// The header and subdomain are the caller's to choose, so a request that
+// also carries a token must name the token's school; only the routes anyone
+// may call (signing in, applying, the school's branding) go by them alone.
+#[derive(Clone)]
+pub struct CurrentTenant(pub tenants::Model);
+
+impl CurrentTenant {
+    pub fn id(&self) -> i32 {
+        self.0.id
+    }
+}
+
+fn requested_slug(parts: &Parts, config: &Config) -> Option<String> {
+    let header_value = |name| parts.headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(slug) = header_value(TENANT_HEADER) {
+        return Some(slug.trim().to_lowercase());
+    }
+    let base_domain = config.tenant_base_domain.as_deref()?;
+    tenancy::subdomain(header_value(header::HOST.as_str())?, base_domain).map(str::to_lowercase)
+}
+
+// The school of the bearer token, if there is a valid one; a bad token is
+// left for `AuthUser` to reject
+async fn token_tenant(db: &DatabaseConnection, config: &Config, parts: &Parts) -> Result<Option<i32>, ApiError> {
+    let Some(token) = bearer_token(parts) else {
+        return Ok(None);
+    };
+    match api_keys::prefix_of(token) {
+        Some(prefix) => ApiKeyRepository::tenant_of(db, prefix).await.map_err(db_error),
+        None => Ok(auth::verify_token(config, token).ok().map(|claims| claims.tid)),
+    }
+}
+
+impl<S> FromRequestParts<S> for CurrentTenant
+where
+    Config: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(tenant) = parts.extensions.get::<CurrentTenant>() {
+            return Ok(tenant.clone());
+        }
+
+        let config = Config::from_ref(state);
+        let db = DatabaseConnection::from_ref(state);
+        let token_tenant = token_tenant(&db, &config, parts).await?;
+        let tenant = match requested_slug(parts, &config) {
+            Some(slug) => TenantRepository::find_by_slug(&db, &slug).await,
+            None => TenantRepository::find_by_id(&db, token_tenant.unwrap_or(DEFAULT_TENANT_ID)).await,
+        }
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Unknown school".to_string()))?;
+        if token_tenant.is_some_and(|id| id != tenant.id) {
+            return Err((StatusCode::UNAUTHORIZED, "Token belongs to another school".to_string()));
+        }
+        if !tenant.is_active {
+            return Err((StatusCode::FORBIDDEN, "This school is not active".to_string()));
+        }
+
+        let tenant = CurrentTenant(tenant);
+        parts.extensions.insert(tenant.clone());
+        Ok(tenant)
+    }
+}
+
+// The signed-in user, from an `Authorization: Bearer <access token or API key>`
+// header. API keys only get through on routes their scopes cover, and
+// neither gets through on another school's requests or for a user in the
+// trash or deactivated.
+pub struct AuthUser(pub Claims);
+
+impl AuthUser {
+    pub fn id(&self) -> Result<i32, ApiError> {
+        self.0
+            .user_id()
+            .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))
+    }
+
+    // Admins of the default school also run the deployment: they add schools
+    // and see what spans all of them
+    pub fn is_platform_admin(&self) -> bool {
+        self.0.role == "admin" && self.0.tid == DEFAULT_TENANT_ID
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+impl<S> FromRequestParts<S> for AuthUser
+where
+    Config: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        <Self as OptionalFromRequestParts<S>>::from_request_parts(parts, state)
+            .await?
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing bearer token".to_string()))
+    }
+}
+
+// No Authorization header at all is `None`; a bad token is still rejected
+impl<S> OptionalFromRequestParts<S> for AuthUser
+where
+    Config: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if bearer_token(parts).is_none() {
+            return Ok(None);
+        }
+        let tenant = CurrentTenant::from_request_parts(parts, state).await?;
+        let token = bearer_token(parts).unwrap_or_default();
+        if let Some(prefix) = api_keys::prefix_of(token) {
+            let db = DatabaseConnection::from_ref(state);
+            return api_key_user(&db, parts, tenant.id(), prefix, token).await.map(Some);
+        }
+        let config = Config::from_ref(state);
+        let claims = auth::verify_token(&config, token)
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string()))?;
+        if claims.tid != tenant.id() {
+            return Err((StatusCode::UNAUTHORIZED, "Token belongs to another school".to_string()));
+        }
+        // A user moved to the trash or deactivated (by an admin or the directory
+        // sync), or whose password changed since the token was issued, loses
+        // access at once, not when their token expires
+        let db = DatabaseConnection::from_ref(state);
+        let issued_at = DateTime::from_timestamp(claims.iat, 0).map(|at| at.naive_utc());
+        let accepted = match (claims.user_id(), issued_at) {
+            (Some(id), Some(issued_at)) => {
+                UserRepository::accepts_token(&db, tenant.id(), id, issued_at).await.map_err(db_error)?
+            }
+            _ => false,
+        };
+        if !accepted {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "Account no longer exists or is deactivated, or its password has changed".to_string(),
+            ));
+        }
+        Ok(Some(AuthUser(claims)))
+    }
+}
+
+async fn api_key_user(
+    db: &DatabaseConnection,
+    parts: &Parts,
+    tenant_id: i32,
+    prefix: &str,
+    key: &str,
+) -> Result<AuthUser, ApiError> {
+    let invalid = || (StatusCode::UNAUTHORIZED, "Invalid, expired or revoked API key".to_string());
+
+    let (api_key, user) = ApiKeyRepository::find_by_prefix(db, tenant_id, prefix)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(invalid)?;
+
+    let now = Utc::now().naive_utc();
+    if !api_keys::hashes_match(&api_keys::hash(key), &api_key.key_hash)
+        || api_key.revoked_at.is_some()
+        || api_key.expires_at.is_some_and(|expires| expires <= now)
+        || !user.is_active
+    {
+        return Err(invalid());
+    }
+
+    // Nested routers see a shortened path; scopes are named after the full one
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map(|OriginalUri(uri)| uri.path())
+        .unwrap_or(parts.uri.path());
+    let required = api_keys::required_scope(&parts.method, path);
+    let scopes: Vec<String> = serde_json::from_value(api_key.scopes.clone()).unwrap_or_default();
+    if !api_keys::allows(&scopes, &required) {
+        return Err((StatusCode::FORBIDDEN, format!("API key lacks the {} scope", required)));
+    }
+
+    if let Err(e) = ApiKeyRepository::touch(db, &api_key).await {
+        tracing::warn!("Failed to record use of API key {}: {:?}", api_key.prefix, e);
+    }
+
+    let expires_at = api_key.expires_at.unwrap_or(now).and_utc().timestamp();
+    Ok(AuthUser(Claims {
+        sub: user.id.to_string(),
+        email: user.email,
+        role: user.role,
+        iat: api_key.created_at.and_utc().timestamp(),
+        exp: expires_at,
+        act: None,
+        tid: user.tenant_id,
+    }))
+}
+
+// Address of the client, if known (see `rate_limit::client_ip`)
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    Config: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = Config::from_ref(state);
+        Ok(ClientIp(rate_limit::client_ip(
+            &parts.headers,
+            &parts.extensions,
+            config.trusted_proxies,
+        )))
+    }
+}
+
+// Who is making the request and from where, for the audit log. Requests
+// without a token are audited without an actor; a bad token is rejected.
+impl<S> FromRequestParts<S> for AuditContext
+where
+    Config: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let tenant = CurrentTenant::from_request_parts(parts, state).await?;
+        let user = <AuthUser as OptionalFromRequestParts<S>>::from_request_parts(parts, state).await?;
+        let Ok(ClientIp(ip)) = ClientIp::from_request_parts(parts, state).await;
+        let request_id = parts
+            .headers
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let claims = user.map(|AuthUser(claims)| claims);
+        Ok(AuditContext {
+            tenant_id: Some(tenant.id()),
+            actor_id: claims.as_ref().and_then(Claims::user_id),
+            actor_email: claims.as_ref().map(|claims| claims.email.clone()),
+            impersonator_id: claims
+                .as_ref()
+                .and_then(|claims| claims.act.as_ref())
+                .and_then(|actor| actor.sub.parse().ok()),
+            ip_address: ip.map(|ip| ip.to_string()),
+            request_id,
+        })
+    }
+}
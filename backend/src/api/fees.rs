@@ -0,0 +1,416 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::fees::{self, FeeError, CATEGORIES, DISCOUNT_KINDS, FEE_ROLES, PAYMENT_METHODS};
+use crate::application::tenancy;
+use crate::dto::fees::{
+    AgingBucketResponse, AgingQuery, CreateDiscountRequest, CreateFeeScheduleRequest, DiscountQuery,
+    DiscountResponse, FeeScheduleQuery, FeeScheduleResponse, GenerateInvoicesRequest, GenerateInvoicesResponse,
+    InvoiceDetail, InvoiceLineResponse, InvoiceQuery, InvoiceResponse, PaymentResponse, ReceiptResponse,
+    RecordPaymentRequest, StudentBalance,
+};
+use crate::entities::invoices;
+use crate::repositories::fee_repository::FeeRepository;
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn fee_error(e: FeeError) -> ApiError {
+    match e {
+        FeeError::InvoiceNotFound => (StatusCode::NOT_FOUND, "Invoice not found".to_string()),
+        FeeError::PaymentNotFound => (StatusCode::NOT_FOUND, "Payment not found".to_string()),
+        FeeError::Overpayment { .. } | FeeError::AlreadyPaid | FeeError::RefundTooLarge { .. } => {
+            (StatusCode::CONFLICT, e.to_string())
+        }
+        FeeError::Database(e) => db_error(e),
+    }
+}
+
+fn require_fee_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !FEE_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and bursars can manage fees".to_string()));
+    }
+    Ok(())
+}
+
+// Students see their own invoices, fee staff everyone's
+fn require_own_invoice(user: &AuthUser, invoice: &invoices::Model) -> Result<(), ApiError> {
+    if FEE_ROLES.contains(&user.0.role.as_str()) || user.id()? == invoice.student_id {
+        return Ok(());
+    }
+    Err((StatusCode::NOT_FOUND, "Invoice not found".to_string()))
+}
+
+fn require_one_of(field: &str, value: &str, allowed: &[&str]) -> Result<(), ApiError> {
+    if !allowed.contains(&value) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} must be one of: {}", field, allowed.join(", ")),
+        ));
+    }
+    Ok(())
+}
+
+fn require_positive(field: &str, amount: Decimal) -> Result<(), ApiError> {
+    if fees::round_money(amount) <= Decimal::ZERO {
+        return Err((StatusCode::BAD_REQUEST, format!("{} must be more than zero", field)));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+impl ExportRow for AgingBucketResponse {
+    const COLUMNS: &'static [&'static str] = &["bucket", "invoices", "outstanding"];
+}
+
+impl ExportRow for DiscountResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "student_id", "kind", "name", "percent", "amount", "category", "term", "is_active"];
+}
+
+impl ExportRow for FeeScheduleResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "grade", "term", "due_date", "total", "items"];
+}
+
+impl ExportRow for InvoiceResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "number", "student_id", "schedule_id", "term", "due_date", "subtotal", "discount_total", "total",
+        "amount_paid", "balance", "status", "issued_at",
+    ];
+}
+
+impl ExportRow for StudentBalance {
+    const COLUMNS: &'static [&'static str] = &["student_id", "invoiced", "paid", "outstanding"];
+}
+
+// GET /api/v1/fees/schedules - Fee schedules, optionally for one grade or term
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_schedules(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<FeeScheduleQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_fee_staff(&user)?;
+
+    let schedules = FeeRepository::list_schedules(&db, tenant.id(), query.grade.as_deref(), query.term.as_deref())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "fee-schedules", schedules.into_iter().map(FeeScheduleResponse::from).collect()).await)
+}
+
+// POST /api/v1/fees/schedules - Set what a grade pays for a term
+pub async fn create_schedule(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CreateFeeScheduleRequest>,
+) -> Result<(StatusCode, Json<FeeScheduleResponse>), ApiError> {
+    require_fee_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+    for item in &payload.items {
+        require_one_of("Category", &item.category, CATEGORIES)?;
+        require_positive("Fee amount", item.amount)?;
+    }
+
+    let schedule = FeeRepository::create_schedule(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Fee schedule {} created by {}", schedule.0.name, user.0.email);
+    Ok((StatusCode::CREATED, Json(schedule.into())))
+}
+
+// GET /api/v1/fees/schedules/:id - A schedule with its fees
+pub async fn get_schedule(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<FeeScheduleResponse>, ApiError> {
+    require_fee_staff(&user)?;
+
+    let schedule = FeeRepository::find_schedule(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Fee schedule not found".to_string()))?;
+    Ok(Json(schedule.into()))
+}
+
+// POST /api/v1/fees/schedules/:id/invoices - Invoice students for a schedule
+pub async fn generate_invoices(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<GenerateInvoicesRequest>,
+) -> Result<Json<GenerateInvoicesResponse>, ApiError> {
+    require_fee_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let (schedule, items) = FeeRepository::find_schedule(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Fee schedule not found".to_string()))?;
+    let generated = FeeRepository::generate_invoices(&db, tenant.id(), &schedule, &items, &payload.student_ids, &context)
+        .await
+        .map_err(db_error)?;
+
+    tracing::info!(
+        "{} invoices for fee schedule {} generated by {}",
+        generated.created.len(),
+        schedule.id,
+        user.0.email
+    );
+    Ok(Json(GenerateInvoicesResponse {
+        created: generated.created.into_iter().map(InvoiceResponse::from).collect(),
+        already_invoiced: generated.already_invoiced,
+        not_students: generated.not_students,
+    }))
+}
+
+// GET /api/v1/fees/discounts - Discounts and scholarships, optionally of one student
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_discounts(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<DiscountQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_fee_staff(&user)?;
+
+    let discounts = FeeRepository::list_discounts(&db, tenant.id(), query.student_id)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "fee-discounts", discounts.into_iter().map(DiscountResponse::from).collect()).await)
+}
+
+// POST /api/v1/fees/discounts - Give a student a discount or scholarship
+pub async fn create_discount(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CreateDiscountRequest>,
+) -> Result<(StatusCode, Json<DiscountResponse>), ApiError> {
+    require_fee_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+    require_one_of("Kind", &payload.kind, DISCOUNT_KINDS)?;
+    if let Some(category) = &payload.category {
+        require_one_of("Category", category, CATEGORIES)?;
+    }
+    match (payload.percent, payload.amount) {
+        (Some(percent), None) => {
+            if percent <= Decimal::ZERO || percent > Decimal::ONE_HUNDRED {
+                return Err((StatusCode::BAD_REQUEST, "Percent must be more than 0 and at most 100".to_string()));
+            }
+        }
+        (None, Some(amount)) => require_positive("Discount amount", amount)?,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Give either a percent or an amount, not both".to_string(),
+            ));
+        }
+    }
+
+    let discount = FeeRepository::create_discount(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Student not found".to_string()))?;
+    tracing::info!(
+        "{} {} for student {} added by {}",
+        discount.kind,
+        discount.name,
+        discount.student_id,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(discount.into())))
+}
+
+// DELETE /api/v1/fees/discounts/:id - Stop a discount applying to new invoices
+pub async fn deactivate_discount(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_fee_staff(&user)?;
+
+    let deactivated = FeeRepository::deactivate_discount(&db, tenant.id(), id, &context)
+        .await
+        .map_err(db_error)?;
+    if !deactivated {
+        return Err((StatusCode::NOT_FOUND, "Active discount not found".to_string()));
+    }
+    tracing::info!("Discount {} deactivated by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/v1/fees/invoices - Invoices by student, status or term; students get their own
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_invoices(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(mut query): Query<InvoiceQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    if !FEE_ROLES.contains(&user.0.role.as_str()) {
+        query.student_id = Some(user.id()?);
+    }
+
+    let invoices = FeeRepository::list_invoices(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "invoices", invoices.into_iter().map(InvoiceResponse::from).collect()).await)
+}
+
+// GET /api/v1/fees/invoices/:id - An invoice with its lines and payments
+pub async fn get_invoice(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<InvoiceDetail>, ApiError> {
+    let invoice = FeeRepository::find_invoice(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+    require_own_invoice(&user, &invoice)?;
+
+    let (lines, payments) = FeeRepository::invoice_details(&db, &invoice)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(InvoiceDetail {
+        invoice: invoice.into(),
+        lines: lines.into_iter().map(InvoiceLineResponse::from).collect(),
+        payments: payments.into_iter().map(PaymentResponse::from).collect(),
+    }))
+}
+
+// POST /api/v1/fees/invoices/:id/payments - Record a full or partial payment
+pub async fn record_payment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<RecordPaymentRequest>,
+) -> Result<(StatusCode, Json<PaymentResponse>), ApiError> {
+    require_fee_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+    require_one_of("Method", &payload.method, PAYMENT_METHODS)?;
+    require_positive("Payment amount", payload.amount)?;
+
+    let (payment, invoice) = FeeRepository::record_payment(&db, tenant.id(), id, &payload, Some(user.id()?), &context)
+        .await
+        .map_err(fee_error)?;
+    tracing::info!(
+        "Payment of {} on {} recorded by {}, invoice is {}",
+        payment.amount,
+        fees::invoice_number(invoice.id),
+        user.0.email,
+        invoice.status
+    );
+    Ok((StatusCode::CREATED, Json(payment.into())))
+}
+
+// GET /api/v1/fees/payments/:id/receipt - What to print for a payment
+pub async fn receipt(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<ReceiptResponse>, ApiError> {
+    let (payment, invoice) = FeeRepository::find_payment(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Payment not found".to_string()))?;
+    require_own_invoice(&user, &invoice)
+        .map_err(|_| (StatusCode::NOT_FOUND, "Payment not found".to_string()))?;
+
+    let paid_to_date = FeeRepository::paid_through(&db, invoice.id, payment.id)
+        .await
+        .map_err(db_error)?;
+    // A purged student's receipt still prints, without the name
+    let student = UserRepository::find_by_id(&db, tenant.id(), invoice.student_id)
+        .await
+        .map_err(db_error)?;
+    let payment = PaymentResponse::from(payment);
+    Ok(Json(ReceiptResponse {
+        receipt_number: payment.receipt_number,
+        school: tenant.0.name.clone(),
+        student_id: invoice.student_id,
+        student_name: student.map(|student| student.full_name),
+        invoice_number: fees::invoice_number(invoice.id),
+        term: invoice.term,
+        amount: payment.amount,
+        method: payment.method,
+        reference: payment.reference,
+        received_at: payment.received_at,
+        invoice_total: invoice.total,
+        paid_to_date,
+        balance_after: invoice.total - paid_to_date,
+    }))
+}
+
+// GET /api/v1/fees/reports/balances - Outstanding balance per student
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn balances(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<DiscountQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_fee_staff(&user)?;
+
+    let balances = FeeRepository::balances(&db, tenant.id(), query.student_id)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "balances", balances).await)
+}
+
+// GET /api/v1/fees/reports/aging - Unpaid balances by days past due, as of today at the school
+// Exports its buckets as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn aging(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<AgingQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_fee_staff(&user)?;
+
+    let as_of = query.as_of.unwrap_or_else(|| tenancy::local_today(&tenant.0.timezone));
+    let report = FeeRepository::aging(&db, tenant.id(), as_of)
+        .await
+        .map_err(db_error)?;
+    if format.is_some() {
+        return Ok(list_response(format, "fee-aging", report.buckets).await);
+    }
+    Ok(Json(report).into_response())
+}
@@ -0,0 +1,380 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{export_response, format_error, list_response, Export, ExportFormat, ExportQuery, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::hostel::{self, HostelError, HOSTEL_ROLES};
+use crate::dto::hostel::{
+    AllocateRequest, AllocationQuery, AllocationResponse, AutoAllocateRequest, AutoAllocateResponse, BedRequest,
+    BedResponse, OccupancyQuery, OccupancyResponse, RoomDetail, RoomOccupancy, RoomRequest, RoomResponse,
+    StayLogResponse, StayRequest, UnplacedStudent,
+};
+use crate::repositories::hostel_repository::HostelRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn hostel_error(e: HostelError) -> ApiError {
+    match e {
+        HostelError::RoomNotFound => (StatusCode::NOT_FOUND, "Room not found".to_string()),
+        HostelError::BedNotFound => (StatusCode::NOT_FOUND, "Bed not found".to_string()),
+        HostelError::StudentNotFound => (StatusCode::NOT_FOUND, "Student not found".to_string()),
+        HostelError::AllocationNotFound => (StatusCode::NOT_FOUND, "Allocation not found".to_string()),
+        HostelError::InvalidGender => (StatusCode::BAD_REQUEST, capitalize(&e.to_string())),
+        HostelError::Database(e) => db_error(e),
+        e => (StatusCode::CONFLICT, capitalize(&e.to_string())),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_hostel_staff(user: &AuthUser) -> bool {
+    HOSTEL_ROLES.contains(&user.0.role.as_str())
+}
+
+fn require_hostel_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !is_hostel_staff(user) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and wardens can manage the hostel".to_string()));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+// GET /api/v1/hostel/rooms - All rooms
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_rooms(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_hostel_staff(&user)?;
+
+    let rooms = HostelRepository::list_rooms(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "hostel-rooms", rooms.into_iter().map(RoomResponse::from).collect()).await)
+}
+
+// POST /api/v1/hostel/rooms - Add a room with its beds
+pub async fn create_room(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<RoomRequest>,
+) -> Result<(StatusCode, Json<RoomDetail>), ApiError> {
+    require_hostel_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let room = HostelRepository::create_room(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!("Room {} with {} beds added by {}", room.room.name, room.beds.len(), user.0.email);
+    Ok((StatusCode::CREATED, Json(room)))
+}
+
+// GET /api/v1/hostel/rooms/:id - A room and its beds
+pub async fn get_room(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<RoomDetail>, ApiError> {
+    require_hostel_staff(&user)?;
+
+    let room = HostelRepository::room_detail(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or(hostel_error(HostelError::RoomNotFound))?;
+    Ok(Json(room))
+}
+
+// PUT /api/v1/hostel/rooms/:id - Update a room
+pub async fn update_room(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<RoomRequest>,
+) -> Result<Json<RoomResponse>, ApiError> {
+    require_hostel_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let room = HostelRepository::update_room(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!("Room {} updated by {}", room.name, user.0.email);
+    Ok(Json(room.into()))
+}
+
+// POST /api/v1/hostel/rooms/:id/beds - Add a bed to a room
+pub async fn add_bed(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<BedRequest>,
+) -> Result<(StatusCode, Json<BedResponse>), ApiError> {
+    require_hostel_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let bed = HostelRepository::add_bed(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!("Bed {} added to room {} by {}", bed.label, id, user.0.email);
+    Ok((StatusCode::CREATED, Json(bed.into())))
+}
+
+// PUT /api/v1/hostel/beds/:id - Relabel a bed or take it out of use
+pub async fn update_bed(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<BedRequest>,
+) -> Result<Json<BedResponse>, ApiError> {
+    require_hostel_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let bed = HostelRepository::update_bed(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!("Bed {} updated by {}", bed.id, user.0.email);
+    Ok(Json(bed.into()))
+}
+
+// POST /api/v1/hostel/allocations - Put a student in a chosen bed for a term
+pub async fn allocate(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<AllocateRequest>,
+) -> Result<(StatusCode, Json<AllocationResponse>), ApiError> {
+    require_hostel_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let allocation = HostelRepository::allocate(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!(
+        "Student {} allocated bed {} for {} by {}",
+        allocation.student_id,
+        allocation.bed_id,
+        allocation.term,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(allocation.into())))
+}
+
+// POST /api/v1/hostel/allocations/auto - Place students into free beds by
+// gender, grade and capacity
+pub async fn auto_allocate(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<AutoAllocateRequest>,
+) -> Result<Json<AutoAllocateResponse>, ApiError> {
+    require_hostel_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let (allocated, unplaced) = HostelRepository::auto_allocate(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!(
+        "{} students allocated and {} unplaced for {} by {}",
+        allocated.len(),
+        unplaced.len(),
+        payload.term,
+        user.0.email
+    );
+    Ok(Json(AutoAllocateResponse {
+        allocated: allocated.into_iter().map(AllocationResponse::from).collect(),
+        unplaced: unplaced
+            .into_iter()
+            .map(|(student_id, reason)| UnplacedStudent {
+                student_id,
+                reason: capitalize(&reason),
+            })
+            .collect(),
+    }))
+}
+
+// GET /api/v1/hostel/allocations - Who sleeps where; students only see their own
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_allocations(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(mut query): Query<AllocationQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    if !is_hostel_staff(&user) {
+        query.student_id = Some(user.id()?);
+    }
+
+    let allocations = HostelRepository::list_allocations(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    let allocations: Vec<_> = allocations.into_iter().map(AllocationResponse::from).collect();
+    Ok(list_response(format, "hostel-allocations", allocations).await)
+}
+
+// DELETE /api/v1/hostel/allocations/:id - Free a bed
+pub async fn remove_allocation(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_hostel_staff(&user)?;
+
+    HostelRepository::remove_allocation(&db, tenant.id(), id, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!("Hostel allocation {} removed by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/v1/hostel/allocations/:id/check-in - Log a student arriving
+pub async fn check_in(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    payload: Option<Json<StayRequest>>,
+) -> Result<Json<StayLogResponse>, ApiError> {
+    record_stay(db, tenant, user, context, id, hostel::CHECK_IN, payload).await
+}
+
+// POST /api/v1/hostel/allocations/:id/check-out - Log a student leaving
+pub async fn check_out(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    payload: Option<Json<StayRequest>>,
+) -> Result<Json<StayLogResponse>, ApiError> {
+    record_stay(db, tenant, user, context, id, hostel::CHECK_OUT, payload).await
+}
+
+async fn record_stay(
+    db: DatabaseConnection,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    id: i32,
+    event: &str,
+    payload: Option<Json<StayRequest>>,
+) -> Result<Json<StayLogResponse>, ApiError> {
+    require_hostel_staff(&user)?;
+    let Json(payload) = payload.unwrap_or_default();
+
+    let (allocation, log) = HostelRepository::record_stay(&db, tenant.id(), id, event, payload.notes, &context)
+        .await
+        .map_err(hostel_error)?;
+    tracing::info!("Student {} {} by {}", allocation.student_id, event, user.0.email);
+    Ok(Json(log.into()))
+}
+
+// GET /api/v1/hostel/allocations/:id/logs - Arrivals and departures
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn stay_logs(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    let (allocation, logs) = HostelRepository::stay_logs(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or(hostel_error(HostelError::AllocationNotFound))?;
+    if !is_hostel_staff(&user) && allocation.student_id != user.id()? {
+        return Err(hostel_error(HostelError::AllocationNotFound));
+    }
+    Ok(list_response(format, "hostel-stay-logs", logs.into_iter().map(StayLogResponse::from).collect()).await)
+}
+
+impl ExportRow for AllocationResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "term", "bed_id", "student_id", "gender", "grade", "status", "created_at"];
+}
+
+impl ExportRow for RoomResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "building", "gender", "grade", "is_active"];
+}
+
+impl ExportRow for StayLogResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "allocation_id", "event", "notes", "recorded_by", "occurred_at"];
+}
+
+impl ExportRow for RoomOccupancy {
+    const COLUMNS: &'static [&'static str] = &[
+        "room_id", "room", "building", "gender", "grade", "beds", "allocated", "checked_in", "free",
+    ];
+}
+
+// GET /api/v1/hostel/reports/occupancy?term= - Beds, allocations and
+// check-ins per room. Also exports as CSV/XLSX via `Accept` or `?format=`.
+pub async fn occupancy(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<OccupancyQuery>,
+    Query(export): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    require_hostel_staff(&user)?;
+    let format = ExportFormat::negotiate(&export, &headers)
+        .map_err(format_error)?;
+
+    let rooms = HostelRepository::occupancy(&db, tenant.id(), &query.term)
+        .await
+        .map_err(db_error)?;
+
+    if let Some(format) = format {
+        let rows: Vec<Result<RoomOccupancy, sea_orm::DbErr>> = rooms.into_iter().map(Ok).collect();
+        return Ok(export_response(format, "hostel-occupancy", futures::stream::iter(rows)).await);
+    }
+
+    let beds = rooms.iter().map(|room| room.beds).sum();
+    let allocated = rooms.iter().map(|room| room.allocated).sum();
+    Ok(Json(OccupancyResponse {
+        term: query.term,
+        beds,
+        allocated,
+        checked_in: rooms.iter().map(|room| room.checked_in).sum(),
+        free: rooms.iter().map(|room| room.free).sum(),
+        rooms,
+    })
+    .into_response())
+}
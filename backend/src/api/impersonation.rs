@@ -0,0 +1,207 @@
+use axum::{
+    extract::{OriginalUri, Path, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::auth::{self, Actor};
+use crate::application::impersonation::{self, BANNER_HEADER};
+use crate::config::Config;
+use crate::dto::impersonation::{
+    ImpersonationResponse, ImpersonationSessionDetail, ImpersonationSessionResponse,
+    StartImpersonationRequest,
+};
+use crate::infrastructure::rate_limit;
+use crate::repositories::impersonation_repository::ImpersonationRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::state::AppState;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+impl ExportRow for ImpersonationSessionResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "admin_id", "target_user_id", "reason", "started_at", "expires_at", "ended_at"];
+}
+
+// POST /api/v1/admin/impersonate/:id - Start viewing rsEdu as another user
+pub async fn start(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<StartImpersonationRequest>,
+) -> Result<(StatusCode, Json<ImpersonationResponse>), ApiError> {
+    if admin.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can impersonate users".to_string()));
+    }
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+
+    let target = UserRepository::find_by_id(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    if let Some(refusal) = impersonation::refusal(&admin.0, &target) {
+        return Err((StatusCode::FORBIDDEN, refusal.to_string()));
+    }
+
+    // The session never outlives the admin's own login
+    let admin_expiry = DateTime::from_timestamp(admin.0.exp, 0).unwrap_or_else(Utc::now);
+    let expires_at = (Utc::now() + Duration::minutes(config.impersonation_minutes)).min(admin_expiry);
+
+    let session = ImpersonationRepository::start(&db, tenant.id(), admin.id()?, target.id, &payload.reason, expires_at.naive_utc())
+        .await
+        .map_err(db_error)?;
+    let actor = Actor {
+        sub: admin.0.sub.clone(),
+        email: admin.0.email.clone(),
+        session_id: session.id,
+    };
+    let token = auth::issue_impersonation_token(&config, &target, actor, expires_at).map_err(|e| {
+        tracing::error!("Failed to issue impersonation token: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to issue token".to_string())
+    })?;
+
+    tracing::warn!(
+        "{} started impersonating {} (session {}): {}",
+        admin.0.email, target.email, session.id, payload.reason
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(ImpersonationResponse {
+            token,
+            user: target,
+            impersonated_by: admin.0.email,
+            session_id: session.id,
+            expires_at: expires_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }),
+    ))
+}
+
+// POST /api/v1/auth/impersonation/stop - End the session the token belongs to
+pub async fn stop(
+    State(db): State<DatabaseConnection>,
+    user: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let Some(actor) = &user.0.act else {
+        return Err((StatusCode::BAD_REQUEST, "Not an impersonation session".to_string()));
+    };
+
+    ImpersonationRepository::end(&db, actor.session_id)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("{} stopped impersonating {}", actor.email, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/v1/admin/impersonations - Recent impersonation sessions (admin)
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_sessions(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    if admin.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can view impersonations".to_string()));
+    }
+
+    let sessions = ImpersonationRepository::list_sessions(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    let sessions: Vec<_> = sessions.into_iter().map(ImpersonationSessionResponse::from).collect();
+    Ok(list_response(format, "impersonation-sessions", sessions).await)
+}
+
+// GET /api/v1/admin/impersonations/:id - A session and every request made in it (admin)
+pub async fn get_session(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<ImpersonationSessionDetail>, ApiError> {
+    if admin.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can view impersonations".to_string()));
+    }
+
+    let session = ImpersonationRepository::find_session(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Impersonation session not found".to_string()))?;
+    let actions = ImpersonationRepository::actions(&db, id)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(ImpersonationSessionDetail {
+        session: session.into(),
+        actions: actions.into_iter().map(Into::into).collect(),
+    }))
+}
+
+// Middleware for every API request made with an impersonated token: the
+// session must still be open, writes are refused, the response carries the
+// banner header, and the request is recorded against the session
+pub async fn track_impersonation(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let actor = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| auth::verify_token(&state.config, token.trim()).ok())
+        .and_then(|claims| claims.act);
+    let Some(actor) = actor else {
+        return next.run(request).await;
+    };
+
+    match ImpersonationRepository::find_active(&state.db, actor.session_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::UNAUTHORIZED, "Impersonation session has ended".to_string()).into_response();
+        }
+        Err(e) => return db_error(e).into_response(),
+    }
+
+    let method = request.method().clone();
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|OriginalUri(uri)| uri.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let ip = rate_limit::client_ip(request.headers(), request.extensions(), state.config.trusted_proxies);
+
+    let mut response = if impersonation::allows(&method, &path) {
+        next.run(request).await
+    } else {
+        (StatusCode::FORBIDDEN, "Changes are not allowed while impersonating".to_string()).into_response()
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&actor.email) {
+        response.headers_mut().insert(BANNER_HEADER, value);
+    }
+    if let Err(e) = ImpersonationRepository::record_action(
+        &state.db,
+        actor.session_id,
+        method.as_str(),
+        &path,
+        response.status().as_u16(),
+        ip.map(|ip| ip.to_string()),
+    )
+    .await
+    {
+        tracing::error!("Failed to record impersonated request {} {}: {:?}", method, path, e);
+    }
+    response
+}
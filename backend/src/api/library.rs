@@ -0,0 +1,420 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::library::{
+    self, LibraryError, LibraryPolicy, FINE_PAID, FINE_WAIVED, LIBRARY_ROLES, SHELF_STATUSES,
+};
+use crate::application::tenancy;
+use crate::dto::library::{
+    CheckoutRequest, CopyResponse, CreateCopyRequest, CreateTitleRequest, HoldQuery, HoldResponse,
+    ImportErrorResponse, ImportQuery, ImportResponse, LoanQuery, LoanResponse, PlaceHoldRequest, ReturnRequest,
+    ReturnResponse, SettleFineRequest, TitleQuery, TitleResponse, UpdateCopyStatusRequest,
+};
+use crate::repositories::library_repository::LibraryRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn library_error(e: LibraryError) -> ApiError {
+    match e {
+        LibraryError::CopyNotFound
+        | LibraryError::BorrowerNotFound
+        | LibraryError::LoanNotFound
+        | LibraryError::TitleNotFound
+        | LibraryError::HoldNotFound => (StatusCode::NOT_FOUND, capitalize(&e.to_string())),
+        LibraryError::Database(e) => db_error(e),
+        e => (StatusCode::CONFLICT, capitalize(&e.to_string())),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_librarian(user: &AuthUser) -> bool {
+    LIBRARY_ROLES.contains(&user.0.role.as_str())
+}
+
+fn require_librarian(user: &AuthUser) -> Result<(), ApiError> {
+    if !is_librarian(user) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and librarians can manage the library".to_string()));
+    }
+    Ok(())
+}
+
+// Readers see their own loans and holds, library staff everyone's
+fn require_own(user: &AuthUser, user_id: i32, not_found: &str) -> Result<(), ApiError> {
+    if is_librarian(user) || user.id()? == user_id {
+        return Ok(());
+    }
+    Err((StatusCode::NOT_FOUND, not_found.to_string()))
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+impl ExportRow for HoldResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "title_id", "user_id", "status", "copy_id", "created_at", "ready_at", "expires_on"];
+}
+
+impl ExportRow for LoanResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "copy_id", "user_id", "checked_out_at", "due_date", "returned_at", "renewals", "fine_amount",
+        "fine_status",
+    ];
+}
+
+impl ExportRow for TitleResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "isbn", "title", "author", "publisher", "published_year", "available", "copies"];
+}
+
+// GET /api/v1/library/titles - Search the catalog by title, author or ISBN
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_titles(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    _user: AuthUser,
+    Query(query): Query<TitleQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    let search = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let isbn = search.and_then(library::normalize_isbn);
+
+    let titles = LibraryRepository::list_titles(&db, tenant.id(), search, isbn.as_deref())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "library-titles", titles.into_iter().map(TitleResponse::from).collect()).await)
+}
+
+// POST /api/v1/library/titles - Add a title to the catalog
+pub async fn create_title(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(mut payload): Json<CreateTitleRequest>,
+) -> Result<(StatusCode, Json<TitleResponse>), ApiError> {
+    require_librarian(&user)?;
+    payload.validate().map_err(validation_error)?;
+    if let Some(isbn) = payload.isbn.as_deref().map(str::trim).filter(|isbn| !isbn.is_empty()) {
+        let normalized = library::normalize_isbn(isbn)
+            .ok_or((StatusCode::BAD_REQUEST, format!("{} is not a valid ISBN", isbn)))?;
+        payload.isbn = Some(normalized);
+    } else {
+        payload.isbn = None;
+    }
+
+    let title = LibraryRepository::create_title(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Library title {} added by {}", title.title, user.0.email);
+    Ok((StatusCode::CREATED, Json((title, Vec::new()).into())))
+}
+
+// GET /api/v1/library/titles/:id - A title with its copies
+pub async fn get_title(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    _user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<TitleResponse>, ApiError> {
+    let title = LibraryRepository::find_title(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Title not found".to_string()))?;
+    Ok(Json(title.into()))
+}
+
+// POST /api/v1/library/titles/:id/copies - Add a barcoded copy of a title
+pub async fn add_copy(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<CreateCopyRequest>,
+) -> Result<(StatusCode, Json<CopyResponse>), ApiError> {
+    require_librarian(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let copy = LibraryRepository::add_copy(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!("Library copy {} added by {}", copy.barcode, user.0.email);
+    Ok((StatusCode::CREATED, Json(copy.into())))
+}
+
+// PUT /api/v1/library/copies/:id/status - Mark a copy lost or withdrawn, or
+// return it to the shelf
+pub async fn update_copy_status(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateCopyStatusRequest>,
+) -> Result<Json<CopyResponse>, ApiError> {
+    require_librarian(&user)?;
+    if !SHELF_STATUSES.contains(&payload.status.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Status must be one of: {}", SHELF_STATUSES.join(", ")),
+        ));
+    }
+
+    let copy = LibraryRepository::set_shelf_status(&db, tenant.id(), id, &payload.status, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!("Library copy {} marked {} by {}", copy.barcode, copy.status, user.0.email);
+    Ok(Json(copy.into()))
+}
+
+// GET /api/v1/library/loans - Loans; readers only see their own
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_loans(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(mut query): Query<LoanQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    if !is_librarian(&user) {
+        query.user_id = Some(user.id()?);
+    }
+
+    let loans = LibraryRepository::list_loans(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "library-loans", loans.into_iter().map(LoanResponse::from).collect()).await)
+}
+
+// POST /api/v1/library/loans - Check a copy out to a user
+pub async fn checkout(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<LibraryPolicy>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CheckoutRequest>,
+) -> Result<(StatusCode, Json<LoanResponse>), ApiError> {
+    require_librarian(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let today = tenancy::local_today(&tenant.0.timezone);
+    let loan = LibraryRepository::checkout(&db, tenant.id(), &policy, today, &payload, Some(user.id()?), &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!(
+        "Library copy {} checked out to user {} by {}",
+        payload.barcode,
+        loan.user_id,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(loan.into())))
+}
+
+// POST /api/v1/library/loans/return - Check a copy back in by barcode
+pub async fn return_copy(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<LibraryPolicy>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<ReturnRequest>,
+) -> Result<Json<ReturnResponse>, ApiError> {
+    require_librarian(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let today = tenancy::local_today(&tenant.0.timezone);
+    let (loan, hold) = LibraryRepository::return_copy(&db, tenant.id(), &policy, today, &payload.barcode, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!("Library copy {} returned by {}", payload.barcode, user.0.email);
+    Ok(Json(ReturnResponse {
+        loan: loan.into(),
+        ready_hold: hold.map(HoldResponse::from),
+    }))
+}
+
+// POST /api/v1/library/loans/:id/renew - Extend a loan; borrowers may renew their own
+pub async fn renew(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<LibraryPolicy>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<Json<LoanResponse>, ApiError> {
+    let loan = LibraryRepository::find_loan(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Loan not found".to_string()))?;
+    require_own(&user, loan.user_id, "Loan not found")?;
+
+    let today = tenancy::local_today(&tenant.0.timezone);
+    let loan = LibraryRepository::renew(&db, tenant.id(), &policy, today, id, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!("Library loan {} renewed to {} by {}", loan.id, loan.due_date, user.0.email);
+    Ok(Json(loan.into()))
+}
+
+// POST /api/v1/library/loans/:id/fine - Mark an overdue fine paid or waived
+pub async fn settle_fine(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<SettleFineRequest>,
+) -> Result<Json<LoanResponse>, ApiError> {
+    require_librarian(&user)?;
+    if ![FINE_PAID, FINE_WAIVED].contains(&payload.status.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Status must be one of: {}, {}", FINE_PAID, FINE_WAIVED),
+        ));
+    }
+
+    let loan = LibraryRepository::settle_fine(&db, tenant.id(), id, &payload.status, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!(
+        "Library fine of {} on loan {} {} by {}",
+        loan.fine_amount,
+        loan.id,
+        loan.fine_status,
+        user.0.email
+    );
+    Ok(Json(loan.into()))
+}
+
+// GET /api/v1/library/holds - Holds in queue order; readers only see their own
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_holds(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(mut query): Query<HoldQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    if !is_librarian(&user) {
+        query.user_id = Some(user.id()?);
+    }
+
+    let holds = LibraryRepository::list_holds(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "library-holds", holds.into_iter().map(HoldResponse::from).collect()).await)
+}
+
+// POST /api/v1/library/titles/:id/holds - Join the queue for a title with
+// no copy on the shelf; staff may place holds for other readers
+pub async fn place_hold(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<LibraryPolicy>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<PlaceHoldRequest>,
+) -> Result<(StatusCode, Json<HoldResponse>), ApiError> {
+    let reader = match payload.user_id {
+        Some(user_id) if user_id != user.id()? => {
+            require_librarian(&user)?;
+            user_id
+        }
+        _ => user.id()?,
+    };
+
+    let today = tenancy::local_today(&tenant.0.timezone);
+    let hold = LibraryRepository::place_hold(&db, tenant.id(), &policy, today, id, reader, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!("Library hold on title {} for user {} placed by {}", id, reader, user.0.email);
+    Ok((StatusCode::CREATED, Json(hold.into())))
+}
+
+// DELETE /api/v1/library/holds/:id - Cancel a waiting or ready hold
+pub async fn cancel_hold(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<LibraryPolicy>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<Json<HoldResponse>, ApiError> {
+    let hold = LibraryRepository::find_hold(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Hold not found".to_string()))?;
+    require_own(&user, hold.user_id, "Hold not found")?;
+
+    let today = tenancy::local_today(&tenant.0.timezone);
+    let hold = LibraryRepository::cancel_hold(&db, tenant.id(), &policy, today, id, &context)
+        .await
+        .map_err(library_error)?;
+    tracing::info!("Library hold {} cancelled by {}", hold.id, user.0.email);
+    Ok(Json(hold.into()))
+}
+
+// POST /api/v1/library/import?format=csv|marc - Load titles and copies from a
+// CSV or MARC21 file sent as the request body
+pub async fn import(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportResponse>, ApiError> {
+    require_librarian(&user)?;
+
+    let parsed = match query.format.as_str() {
+        "csv" => library::parse_csv(&body),
+        "marc" => library::parse_marc(&body),
+        _ => return Err((StatusCode::BAD_REQUEST, "Format must be one of: csv, marc".to_string())),
+    };
+    let imported = LibraryRepository::import(&db, tenant.id(), parsed.records, &context)
+        .await
+        .map_err(db_error)?;
+
+    tracing::info!(
+        "Library import of {} titles and {} copies by {}",
+        imported.titles_created,
+        imported.copies_created,
+        user.0.email
+    );
+    Ok(Json(ImportResponse {
+        titles_created: imported.titles_created,
+        titles_matched: imported.titles_matched,
+        copies_created: imported.copies_created,
+        duplicate_barcodes: imported.duplicate_barcodes,
+        errors: parsed
+            .errors
+            .into_iter()
+            .map(|(position, message)| ImportErrorResponse { position, message })
+            .collect(),
+    }))
+}
@@ -0,0 +1,384 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Redirect,
+    Form, response::Response,
+    Json,
+};
+use chrono::Utc;
+use jsonwebtoken::jwk::JwkSet;
+use sea_orm::{DatabaseConnection, DbErr};
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::api::two_factor;
+use crate::application::audit::AuditContext;
+use crate::application::lti::{self, LaunchClaims, LtiError, Score, ToolKey, DEEP_LINKING_REQUEST, PLATFORM_ROLES};
+use crate::config::Config;
+use crate::dto::auth::LoginOutcome;
+use crate::dto::lti::{
+    CreatePlatformRequest, DeepLinkingRequest, DeepLinkingResponse, LaunchForm, LaunchResponse,
+    LoginInitiationRequest, PlatformResponse, ScoreRequest, UserLinkResponse,
+};
+use crate::dto::user::UserResponse;
+use crate::entities::{lti_launches, lti_platforms};
+use crate::infrastructure::jwks::JwksCache;
+use crate::repositories::lti_repository::LtiRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: DbErr) -> ApiError {
+    tracing::error!("Database error in LTI: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
+
+fn lti_error(e: LtiError) -> ApiError {
+    if let LtiError::Database(e) = e {
+        return db_error(e);
+    }
+    tracing::warn!("LTI error: {}", e);
+    let status = match e {
+        LtiError::UnknownPlatform => StatusCode::NOT_FOUND,
+        LtiError::InvalidState | LtiError::InvalidToken(_) | LtiError::Jwt(_) => StatusCode::UNAUTHORIZED,
+        LtiError::Unsupported(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        LtiError::MissingToolKey => StatusCode::SERVICE_UNAVAILABLE,
+        LtiError::Jwks(_) | LtiError::Platform(_) => StatusCode::BAD_GATEWAY,
+        LtiError::AlreadyLinked | LtiError::AmbiguousPlatform => StatusCode::CONFLICT,
+        LtiError::LinkExpired => StatusCode::GONE,
+        LtiError::AccountInactive | LtiError::NotGrader => StatusCode::FORBIDDEN,
+        LtiError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+// A platform can sign people in to the school, so only admins manage them
+fn require_platform_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if !PLATFORM_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins can manage LTI platforms".to_string()));
+    }
+    Ok(())
+}
+
+impl ExportRow for PlatformResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "name", "issuer", "client_id", "deployment_id", "auth_login_url", "auth_token_url", "jwks_url",
+        "is_active",
+    ];
+}
+
+impl ExportRow for UserLinkResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "platform_id", "subject", "user_id", "created_at"];
+}
+
+// GET /api/v1/lti/platforms - List the school's registered LMS platforms
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_platforms(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_platform_admin(&user)?;
+    let platforms = LtiRepository::find_all_platforms(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "lti-platforms", platforms).await)
+}
+
+// POST /api/v1/lti/platforms - Register an LMS platform for the school
+pub async fn create_platform(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CreatePlatformRequest>,
+) -> Result<(StatusCode, Json<PlatformResponse>), ApiError> {
+    require_platform_admin(&user)?;
+    if let Err(e) = payload.validate() {
+        tracing::error!("Validation error: {:?}", e);
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+
+    let platform = LtiRepository::create_platform(&db, tenant.id(), payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("LTI platform registered: {} by {}", platform.issuer, user.0.email);
+    Ok((StatusCode::CREATED, Json(platform)))
+}
+
+// GET /api/v1/lti/jwks - Public keys of the rsEdu tool
+pub async fn jwks(State(tool_key): State<Option<ToolKey>>) -> Result<Json<JwkSet>, StatusCode> {
+    tool_key
+        .map(|key| Json(key.jwks()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// GET /api/v1/lti/login - OIDC third-party login initiation
+pub async fn login_get(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    Query(login): Query<LoginInitiationRequest>,
+) -> Result<Redirect, ApiError> {
+    start_login(&db, &config, login).await
+}
+
+// POST /api/v1/lti/login - OIDC third-party login initiation
+pub async fn login_post(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    Form(login): Form<LoginInitiationRequest>,
+) -> Result<Redirect, ApiError> {
+    start_login(&db, &config, login).await
+}
+
+async fn start_login(
+    db: &DatabaseConnection,
+    config: &Config,
+    login: LoginInitiationRequest,
+) -> Result<Redirect, ApiError> {
+    let platform = LtiRepository::find_platform(
+        db,
+        &login.iss,
+        login.client_id.as_deref(),
+        login.lti_deployment_id.as_deref(),
+    )
+    .await
+    .map_err(lti_error)?
+    .ok_or_else(|| lti_error(LtiError::UnknownPlatform))?;
+
+    // Only ever send users back into rsEdu itself
+    if !lti::is_app_url(&login.target_link_uri, &config.app_url) {
+        return Err((StatusCode::BAD_REQUEST, "target_link_uri is not an rsEdu URL".to_string()));
+    }
+
+    let state = lti::random_token();
+    let nonce = lti::random_token();
+    let redirect_uri = format!("{}/api/v1/lti/launch", config.app_url);
+    let url = lti::authorization_url(&platform, &login, &redirect_uri, &state, &nonce)
+        .map_err(lti_error)?;
+
+    LtiRepository::save_login_state(db, state, nonce, platform.id, login.target_link_uri)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Redirect::to(&url))
+}
+
+// POST /api/v1/lti/launch - Validate the id_token posted by the platform
+pub async fn launch(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    State(http): State<reqwest::Client>,
+    State(jwks): State<JwksCache>,
+    Form(form): Form<LaunchForm>,
+) -> Result<Json<LaunchResponse>, ApiError> {
+    let login_state = LtiRepository::take_login_state(&db, &form.state)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| lti_error(LtiError::InvalidState))?;
+
+    let platform = LtiRepository::find_platform_by_id(&db, login_state.platform_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| lti_error(LtiError::UnknownPlatform))?;
+
+    let (claims, raw) = lti::verify_launch(&http, &jwks, &platform, &form.id_token, &login_state.nonce)
+        .await
+        .map_err(lti_error)?;
+
+    // Platform users sign in only as the account they have been linked to;
+    // the email claim is the platform's word and proves nothing here
+    let user = LtiRepository::linked_user(&db, &platform, &claims.sub)
+        .await
+        .map_err(db_error)?;
+    if user.as_ref().is_some_and(|user| !user.is_active) {
+        return Err(lti_error(LtiError::AccountInactive));
+    }
+    let user = user.map(UserResponse::from);
+    let (token, two_factor) = match &user {
+        Some(user) => match two_factor::login_outcome(&db, &config, user.clone()).await?.0 {
+            LoginOutcome::SignedIn(signed_in) => (Some(signed_in.token), None),
+            LoginOutcome::TwoFactor(challenge) => (None, Some(challenge)),
+        },
+        None => (None, None),
+    };
+
+    let launch = LtiRepository::create_launch(
+        &db,
+        lti::random_token(),
+        platform.id,
+        user.as_ref().map(|user| user.id),
+        claims.sub.clone(),
+        claims.message_type.clone(),
+        raw,
+    )
+    .await
+    .map_err(db_error)?;
+
+    tracing::info!("LTI launch {} from {}", launch.id, platform.issuer);
+
+    Ok(Json(LaunchResponse {
+        launch_id: launch.id,
+        target_link_uri: claims.target_link_uri.unwrap_or(login_state.target_link_uri),
+        user_id: user.map(|user| user.id),
+        token,
+        two_factor,
+        roles: claims.roles,
+        context: claims.context,
+        resource_link: claims.resource_link,
+        deep_linking: claims.message_type == DEEP_LINKING_REQUEST,
+        can_post_scores: claims
+            .ags_endpoint
+            .is_some_and(|endpoint| endpoint.scope.iter().any(|scope| scope == lti::AGS_SCOPE_SCORE)),
+        message_type: claims.message_type,
+    }))
+}
+
+async fn load_launch(
+    db: &DatabaseConnection,
+    tenant: &CurrentTenant,
+    id: &str,
+) -> Result<(lti_launches::Model, lti_platforms::Model, LaunchClaims), ApiError> {
+    let launch = LtiRepository::find_launch(db, id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Launch not found".to_string()))?;
+
+    let platform = LtiRepository::find_platform_by_id(db, launch.platform_id)
+        .await
+        .map_err(db_error)?
+        .filter(|platform| platform.tenant_id == tenant.id())
+        .ok_or((StatusCode::NOT_FOUND, "Launch not found".to_string()))?;
+
+    let claims = serde_json::from_value(launch.claims.clone())
+        .map_err(|e| lti_error(LtiError::InvalidToken(e.to_string())))?;
+
+    Ok((launch, platform, claims))
+}
+
+// A launch the signed-in user was signed in by; anyone else's launch is not found
+async fn load_own_launch(
+    db: &DatabaseConnection,
+    tenant: &CurrentTenant,
+    user: &AuthUser,
+    id: &str,
+) -> Result<(lti_launches::Model, lti_platforms::Model, LaunchClaims), ApiError> {
+    let (launch, platform, claims) = load_launch(db, tenant, id).await?;
+    if launch.user_id != Some(user.id()?) {
+        return Err((StatusCode::NOT_FOUND, "Launch not found".to_string()));
+    }
+    Ok((launch, platform, claims))
+}
+
+// POST /api/v1/lti/launches/:id/link - Link the platform user of a recent
+// launch to the signed-in account, so later launches sign them in
+pub async fn link_launch(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, Json<UserLinkResponse>), ApiError> {
+    let (launch, platform, _) = load_launch(&db, &tenant, &id).await?;
+    let link = LtiRepository::link_launch(&db, &launch, user.id()?, &context)
+        .await
+        .map_err(lti_error)?;
+    tracing::info!("Platform user {} of {} linked to {}", launch.subject, platform.issuer, user.0.email);
+    Ok((StatusCode::CREATED, Json(link.into())))
+}
+
+// GET /api/v1/lti/platforms/:id/links - Platform users linked to accounts
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_user_links(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(platform_id): Path<i32>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_platform_admin(&user)?;
+    let links = LtiRepository::user_links(&db, tenant.id(), platform_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| lti_error(LtiError::UnknownPlatform))?;
+    Ok(list_response(format, "lti-user-links", links.into_iter().map(UserLinkResponse::from).collect()).await)
+}
+
+// DELETE /api/v1/lti/platforms/:id/links/:link_id - Stop a platform user
+// signing in as an account
+pub async fn delete_user_link(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path((platform_id, link_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    require_platform_admin(&user)?;
+    if !LtiRepository::unlink_user(&db, tenant.id(), platform_id, link_id, &context)
+        .await
+        .map_err(db_error)?
+    {
+        return Err((StatusCode::NOT_FOUND, "Link not found".to_string()));
+    }
+    tracing::info!("LTI user link {} removed by {}", link_id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/v1/lti/launches/:id/deep-linking - Sign the content items picked by the user
+pub async fn deep_linking(
+    State(db): State<DatabaseConnection>,
+    State(tool_key): State<Option<ToolKey>>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(payload): Json<DeepLinkingRequest>,
+) -> Result<Json<DeepLinkingResponse>, ApiError> {
+    let tool_key = tool_key.ok_or_else(|| lti_error(LtiError::MissingToolKey))?;
+    let (_, platform, claims) = load_own_launch(&db, &tenant, &user, &id).await?;
+
+    let (return_url, jwt) =
+        lti::deep_linking_response(&tool_key, &platform, &claims, payload.items, payload.message)
+            .map_err(lti_error)?;
+
+    Ok(Json(DeepLinkingResponse { return_url, jwt }))
+}
+
+// POST /api/v1/lti/launches/:id/scores - Pass a score back to the platform gradebook
+pub async fn post_score(
+    State(db): State<DatabaseConnection>,
+    State(http): State<reqwest::Client>,
+    State(tool_key): State<Option<ToolKey>>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(payload): Json<ScoreRequest>,
+) -> Result<StatusCode, ApiError> {
+    if let Err(e) = payload.validate() {
+        tracing::error!("Validation error: {:?}", e);
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+
+    lti::require_grader(&user.0.role).map_err(lti_error)?;
+    let tool_key = tool_key.ok_or_else(|| lti_error(LtiError::MissingToolKey))?;
+    let (launch, platform, claims) = load_own_launch(&db, &tenant, &user, &id).await?;
+    let endpoint = claims
+        .ags_endpoint
+        .ok_or_else(|| lti_error(LtiError::Unsupported("score passback")))?;
+
+    let score = Score {
+        user_id: payload.user_id.unwrap_or(launch.subject),
+        score_given: payload.score_given,
+        score_maximum: payload.score_maximum,
+        comment: payload.comment,
+        timestamp: Utc::now().to_rfc3339(),
+        activity_progress: payload.activity_progress.unwrap_or_else(|| "Completed".to_string()),
+        grading_progress: payload.grading_progress.unwrap_or_else(|| "FullyGraded".to_string()),
+    };
+
+    lti::post_score(&http, &tool_key, &platform, &endpoint, &score)
+        .await
+        .map_err(lti_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,441 @@
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::messaging::{AttachmentUpload, MessagingError, GUARDIAN_LINK_ROLES, SAFEGUARDING_ROLES};
+use crate::application::notifications;
+use crate::dto::messaging::{
+    ArchiveQuery, ArchivedMessageResponse, AttachmentQuery, AttachmentResponse, GuardianLinkRequest,
+    GuardianLinkResponse, LockRequest, MessageRequest, ReportQuery, ReportRequest, ReportResponse, ResolveRequest,
+    SafeguardingSettings, ThreadDetail, ThreadRequest, ThreadResponse, ThreadSummary,
+};
+use crate::entities::{message_threads, messages};
+use crate::infrastructure::notifications::NotificationHub;
+use crate::repositories::messaging_repository::MessagingRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn messaging_error(e: MessagingError) -> ApiError {
+    match e {
+        MessagingError::ThreadNotFound => (StatusCode::NOT_FOUND, "Thread not found".to_string()),
+        MessagingError::MessageNotFound => (StatusCode::NOT_FOUND, "Message not found".to_string()),
+        MessagingError::AttachmentNotFound => (StatusCode::NOT_FOUND, "Attachment not found".to_string()),
+        MessagingError::ReportNotFound => (StatusCode::NOT_FOUND, "Report not found".to_string()),
+        MessagingError::UserNotFound => (StatusCode::NOT_FOUND, "User not found".to_string()),
+        MessagingError::NotAllowed(_) | MessagingError::NotTogether(..) | MessagingError::NotSender => (StatusCode::FORBIDDEN, capitalize(&e.to_string())),
+        MessagingError::ThreadLocked
+        | MessagingError::AlreadyReported
+        | MessagingError::AlreadyResolved
+        | MessagingError::AlreadyLinked => (StatusCode::CONFLICT, capitalize(&e.to_string())),
+        MessagingError::UnsupportedAttachment => (StatusCode::UNSUPPORTED_MEDIA_TYPE, capitalize(&e.to_string())),
+        MessagingError::Database(e) => db_error(e),
+        e => (StatusCode::BAD_REQUEST, capitalize(&e.to_string())),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_moderator(user: &AuthUser) -> bool {
+    SAFEGUARDING_ROLES.contains(&user.0.role.as_str())
+}
+
+fn require_moderator(user: &AuthUser) -> Result<(), ApiError> {
+    if !is_moderator(user) {
+        return Err((StatusCode::FORBIDDEN, "Only safeguarding staff can moderate messages".to_string()));
+    }
+    Ok(())
+}
+
+fn require_guardian_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !GUARDIAN_LINK_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and principals can manage guardians".to_string()));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+// Tell the rest of the thread about a new message. Best effort: the message
+// is sent either way.
+async fn notify(
+    db: &DatabaseConnection,
+    hub: &NotificationHub,
+    thread: &message_threads::Model,
+    message: &messages::Model,
+) {
+    match MessagingRepository::participant_ids(db, thread.id).await {
+        Ok(participants) => hub.publish(notifications::for_message(thread, message, &participants)).await,
+        Err(e) => tracing::error!("Failed to notify thread {} of message {}: {:?}", thread.id, message.id, e),
+    }
+}
+
+// An attachment sent as the raw request body, named by `?filename=`; its
+// type comes from Content-Type
+pub struct UploadedAttachment(AttachmentUpload);
+
+impl<S> FromRequest<S> for UploadedAttachment
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = request.into_parts();
+        let Query(query) = Query::<AttachmentQuery>::from_request_parts(&mut parts, state)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.body_text()))?;
+        let content_type = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let content = Bytes::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?;
+        Ok(UploadedAttachment(AttachmentUpload {
+            filename: query.filename,
+            content_type,
+            content: content.to_vec(),
+        }))
+    }
+}
+
+impl ExportRow for ArchivedMessageResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "thread_id", "message_id", "subject", "sender_id", "sender_name", "sender_role", "participants", "body",
+        "reason", "sent_at", "archived_at",
+    ];
+}
+
+impl ExportRow for GuardianLinkResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "guardian_id", "student_id", "relationship", "created_at"];
+}
+
+impl ExportRow for ReportResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "message_id", "thread_id", "sender_id", "message", "message_hidden", "reporter_id", "reason", "status",
+        "resolution", "resolved_by", "resolved_at", "created_at",
+    ];
+}
+
+impl ExportRow for ThreadSummary {
+    const COLUMNS: &'static [&'static str] = &["thread", "participants", "unread"];
+}
+
+// GET /api/v1/messages/threads - The caller's inbox, latest first, with
+// unread counts
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_threads(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    let threads = MessagingRepository::threads(&db, tenant.id(), user.id()?)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "message-threads", threads).await)
+}
+
+// POST /api/v1/messages/threads - Start a conversation
+pub async fn create_thread(
+    State(db): State<DatabaseConnection>,
+    State(hub): State<NotificationHub>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Json(payload): Json<ThreadRequest>,
+) -> Result<(StatusCode, Json<ThreadResponse>), ApiError> {
+    payload.validate().map_err(validation_error)?;
+
+    let (thread, message) = MessagingRepository::create_thread(&db, tenant.id(), user.id()?, &payload)
+        .await
+        .map_err(messaging_error)?;
+    notify(&db, &hub, &thread, &message).await;
+    tracing::info!("Message thread {} started by {}", thread.id, user.0.email);
+    Ok((StatusCode::CREATED, Json(thread.into())))
+}
+
+// GET /api/v1/messages/threads/:id - A thread with its messages and who has
+// read them
+pub async fn get_thread(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<ThreadDetail>, ApiError> {
+    let thread = MessagingRepository::thread(&db, tenant.id(), id, user.id()?, is_moderator(&user))
+        .await
+        .map_err(db_error)?
+        .ok_or(messaging_error(MessagingError::ThreadNotFound))?;
+    Ok(Json(thread))
+}
+
+// POST /api/v1/messages/threads/:id/messages - Reply in a thread
+pub async fn reply(
+    State(db): State<DatabaseConnection>,
+    State(hub): State<NotificationHub>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<MessageRequest>,
+) -> Result<StatusCode, ApiError> {
+    payload.validate().map_err(validation_error)?;
+
+    let (thread, message) = MessagingRepository::reply(&db, tenant.id(), id, user.id()?, &payload.body)
+        .await
+        .map_err(messaging_error)?;
+    notify(&db, &hub, &thread, &message).await;
+    tracing::info!("Message {} sent in thread {} by {}", message.id, id, user.0.email);
+    Ok(StatusCode::CREATED)
+}
+
+// POST /api/v1/messages/threads/:id/read - Mark a thread as read
+pub async fn mark_read(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    MessagingRepository::mark_read(&db, tenant.id(), id, user.id()?)
+        .await
+        .map_err(messaging_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// PUT /api/v1/messages/threads/:id/lock - Stop or allow replies in a thread
+pub async fn set_locked(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<LockRequest>,
+) -> Result<Json<ThreadResponse>, ApiError> {
+    require_moderator(&user)?;
+
+    let thread = MessagingRepository::set_locked(&db, tenant.id(), id, payload.locked, &context)
+        .await
+        .map_err(messaging_error)?;
+    let action = if thread.is_locked { "locked" } else { "unlocked" };
+    tracing::info!("Message thread {} {} by {}", id, action, user.0.email);
+    Ok(Json(thread.into()))
+}
+
+// POST /api/v1/messages/:id/attachments?filename= - Attach a file to a
+// message the caller sent (raw body; Content-Type gives the file type)
+pub async fn add_attachment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    UploadedAttachment(upload): UploadedAttachment,
+) -> Result<(StatusCode, Json<AttachmentResponse>), ApiError> {
+    let attachment = MessagingRepository::add_attachment(&db, tenant.id(), id, user.id()?, upload)
+        .await
+        .map_err(messaging_error)?;
+    tracing::info!("Attachment {} added to message {} by {}", attachment.filename, id, user.0.email);
+    Ok((StatusCode::CREATED, Json(attachment.into())))
+}
+
+// GET /api/v1/messages/attachments/:id - Download an attachment
+pub async fn download_attachment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Response, ApiError> {
+    let attachment = MessagingRepository::attachment(&db, tenant.id(), id, user.id()?, is_moderator(&user))
+        .await
+        .map_err(messaging_error)?
+        .ok_or(messaging_error(MessagingError::AttachmentNotFound))?;
+    let disposition = format!("attachment; filename=\"{}\"", attachment.filename);
+    Ok((
+        [(header::CONTENT_TYPE, attachment.content_type), (header::CONTENT_DISPOSITION, disposition)],
+        attachment.content,
+    )
+        .into_response())
+}
+
+// POST /api/v1/messages/:id/reports - Report a message to the safeguarding
+// team
+pub async fn report_message(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<ReportRequest>,
+) -> Result<(StatusCode, Json<ReportResponse>), ApiError> {
+    payload.validate().map_err(validation_error)?;
+
+    let report = MessagingRepository::report(&db, tenant.id(), id, user.id()?, &payload.reason)
+        .await
+        .map_err(messaging_error)?;
+    tracing::info!("Message {} reported by {}", id, user.0.email);
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+// GET /api/v1/messages/reports - Reported messages, newest first
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_reports(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<ReportQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_moderator(&user)?;
+
+    let reports = MessagingRepository::reports(&db, tenant.id(), query.status.as_deref())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "message-reports", reports).await)
+}
+
+// POST /api/v1/messages/reports/:id/resolve - Dismiss a report, or hide the
+// message it is about
+pub async fn resolve_report(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<ResolveRequest>,
+) -> Result<Json<ReportResponse>, ApiError> {
+    require_moderator(&user)?;
+
+    let report = MessagingRepository::resolve(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(messaging_error)?;
+    tracing::info!("Message report {} {} by {}", id, report.status, user.0.email);
+    Ok(Json(report))
+}
+
+// GET /api/v1/messages/settings - The school's safeguarding rules
+pub async fn get_settings(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+) -> Result<Json<SafeguardingSettings>, ApiError> {
+    require_moderator(&user)?;
+
+    let rules = MessagingRepository::settings(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(Json(rules.into()))
+}
+
+// PUT /api/v1/messages/settings - Change the school's safeguarding rules
+pub async fn update_settings(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<SafeguardingSettings>,
+) -> Result<Json<SafeguardingSettings>, ApiError> {
+    require_moderator(&user)?;
+
+    let settings = MessagingRepository::update_settings(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Messaging safeguarding rules updated by {}", user.0.email);
+    Ok(Json(settings.into()))
+}
+
+// GET /api/v1/messages/archive - Archived messages, by participant, thread
+// or date
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_archive(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<ArchiveQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_moderator(&user)?;
+
+    let archived = MessagingRepository::archive(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    let archived: Vec<_> = archived.into_iter().map(ArchivedMessageResponse::from).collect();
+    Ok(list_response(format, "message-archive", archived).await)
+}
+
+// GET /api/v1/users/:id/guardians - A student's guardians
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_guardians(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_guardian_staff(&user)?;
+
+    let links = MessagingRepository::guardians(&db, tenant.id(), id)
+        .await
+        .map_err(messaging_error)?;
+    Ok(list_response(format, "guardians", links.into_iter().map(GuardianLinkResponse::from).collect()).await)
+}
+
+// POST /api/v1/users/:id/guardians - Link a guardian to a student
+pub async fn link_guardian(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<GuardianLinkRequest>,
+) -> Result<(StatusCode, Json<GuardianLinkResponse>), ApiError> {
+    require_guardian_staff(&user)?;
+
+    let link = MessagingRepository::link_guardian(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(messaging_error)?;
+    tracing::info!("Guardian {} linked to student {} by {}", link.guardian_id, id, user.0.email);
+    Ok((StatusCode::CREATED, Json(link.into())))
+}
+
+// DELETE /api/v1/users/:id/guardians/:guardian_id - Unlink a guardian
+pub async fn unlink_guardian(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path((id, guardian_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    require_guardian_staff(&user)?;
+
+    if !MessagingRepository::unlink_guardian(&db, tenant.id(), id, guardian_id, &context)
+        .await
+        .map_err(db_error)?
+    {
+        return Err((StatusCode::NOT_FOUND, "Guardian is not linked to this student".to_string()));
+    }
+    tracing::info!("Guardian {} unlinked from student {} by {}", guardian_id, id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,8 +1,41 @@
-use axum::{routing::get, Router, Json, extract::State};
+use axum::{routing::{delete, get, post, put}, Router, Json, extract::{DefaultBodyLimit, State}};
 use sea_orm::DatabaseConnection;
 use serde::Serialize;
 
+use crate::application::admissions::MAX_DOCUMENT_BYTES;
+use crate::application::announcements::MAX_ATTACHMENT_BYTES;
+use crate::application::messaging::MAX_ATTACHMENT_BYTES as MAX_MESSAGE_ATTACHMENT_BYTES;
+use crate::state::AppState;
+
+mod admissions;
+mod announcements;
+mod api_keys;
+mod audit;
+mod auth;
+mod data_subject;
+mod directory;
+mod email;
+mod export;
+mod extractors;
+mod fees;
+mod hostel;
+mod impersonation;
+mod library;
+mod lti;
+mod messaging;
+mod notifications;
+mod password;
+mod payments;
+mod profiles;
+mod tenants;
+mod transport;
+mod trash;
+mod two_factor;
 mod users;
+mod xapi;
+
+pub use impersonation::track_impersonation;
+pub use notifications::trace_span;
 
 #[derive(Serialize)]
 struct ApiInfo {
@@ -24,10 +57,164 @@ async fn api_info(State(db): State<DatabaseConnection>) -> Json<ApiInfo> {
     })
 }
 
-pub fn routes() -> Router<DatabaseConnection> {
+pub fn routes() -> Router<AppState> {
     tracing::info!("📋 Registering API routes");
     Router::new()
         .route("/info", get(api_info))
+        .route("/tenant", get(tenants::current).put(tenants::update_current))
+        .route("/auth/login", post(auth::login))
+        .route("/auth/oidc/login", get(auth::oidc_login))
+        .route("/auth/oidc/callback", get(auth::oidc_callback))
+        .route("/auth/password/change", post(password::change))
+        .route("/auth/password/forgot", post(password::forgot))
+        .route("/auth/password/reset", post(password::reset))
+        .route("/auth/2fa", get(two_factor::status))
+        .route("/auth/2fa/setup", post(two_factor::setup))
+        .route("/auth/2fa/enable", post(two_factor::enable))
+        .route("/auth/2fa/verify", post(two_factor::verify))
+        .route("/auth/2fa/recovery-codes", post(two_factor::regenerate_recovery_codes))
+        .route("/auth/2fa/disable", post(two_factor::disable))
+        .route("/auth/impersonation/stop", post(impersonation::stop))
+        .route("/admin/tenants", get(tenants::list).post(tenants::create))
+        .route("/admin/ldap/sync", post(directory::sync))
+        .route("/admin/trash/users", get(trash::list_users))
+        .route("/admin/trash/users/{id}", delete(trash::purge_user))
+        .route("/admin/trash/users/{id}/restore", post(trash::restore_user))
+        .route("/admin/profiles/search", get(profiles::search))
+        .route("/admin/encryption/reencrypt", post(profiles::reencrypt))
+        .route("/admin/impersonate/{id}", post(impersonation::start))
+        .route("/admin/impersonations", get(impersonation::list_sessions))
+        .route("/admin/impersonations/{id}", get(impersonation::get_session))
+        .route("/audit", get(audit::list))
+        .route("/audit/verify", get(audit::verify))
+        .route("/api-keys", get(api_keys::list).post(api_keys::create))
+        .route("/api-keys/{id}", delete(api_keys::revoke))
+        .route("/service-accounts", get(api_keys::list_service_accounts).post(api_keys::create_service_account))
         .route("/users", get(users::list_users).post(users::create_user))
         .route("/users/{id}", get(users::get_user).delete(users::delete_user))
+        .route("/users/{id}/profile", get(profiles::get_profile).put(profiles::update_profile))
+        .route("/users/{id}/data-export", get(data_subject::export))
+        .route("/users/{id}/erasure-requests", post(data_subject::request_erasure))
+        .route("/erasure-requests", get(data_subject::list_requests))
+        .route("/erasure-requests/{id}", get(data_subject::get_request))
+        .route("/erasure-requests/{id}/approve", post(data_subject::approve))
+        .route("/erasure-requests/{id}/reject", post(data_subject::reject))
+        .route("/users/{id}/2fa", delete(two_factor::reset))
+        .route("/users/{id}/lockout", delete(users::unlock_user))
+        .route("/users/{id}/class", put(announcements::set_class).delete(announcements::clear_class))
+        .route("/users/{id}/guardians", get(messaging::list_guardians).post(messaging::link_guardian))
+        .route("/users/{id}/guardians/{guardian_id}", delete(messaging::unlink_guardian))
+        .route("/users/{id}/absence-alerts", post(email::send_absence_alert))
+        .route("/fees/schedules", get(fees::list_schedules).post(fees::create_schedule))
+        .route("/fees/schedules/{id}", get(fees::get_schedule))
+        .route("/fees/schedules/{id}/invoices", post(fees::generate_invoices))
+        .route("/fees/discounts", get(fees::list_discounts).post(fees::create_discount))
+        .route("/fees/discounts/{id}", delete(fees::deactivate_discount))
+        .route("/fees/invoices", get(fees::list_invoices))
+        .route("/fees/invoices/{id}", get(fees::get_invoice))
+        .route("/fees/invoices/{id}/payments", post(fees::record_payment))
+        .route("/fees/invoices/{id}/checkout", post(payments::create_checkout))
+        .route("/fees/payments/{id}/receipt", get(fees::receipt))
+        .route("/fees/payments/{id}/refunds", get(payments::list_refunds).post(payments::create_refund))
+        .route("/fees/reports/balances", get(fees::balances))
+        .route("/fees/reports/aging", get(fees::aging))
+        .route("/payments/webhook", post(payments::webhook))
+        .route("/library/titles", get(library::list_titles).post(library::create_title))
+        .route("/library/titles/{id}", get(library::get_title))
+        .route("/library/titles/{id}/copies", post(library::add_copy))
+        .route("/library/titles/{id}/holds", post(library::place_hold))
+        .route("/library/copies/{id}/status", put(library::update_copy_status))
+        .route("/library/loans", get(library::list_loans).post(library::checkout))
+        .route("/library/loans/return", post(library::return_copy))
+        .route("/library/loans/{id}/renew", post(library::renew))
+        .route("/library/loans/{id}/fine", post(library::settle_fine))
+        .route("/library/holds", get(library::list_holds))
+        .route("/library/holds/{id}", delete(library::cancel_hold))
+        .route("/library/import", post(library::import))
+        .route("/transport/vehicles", get(transport::list_vehicles).post(transport::create_vehicle))
+        .route("/transport/vehicles/{id}", put(transport::update_vehicle))
+        .route("/transport/drivers", get(transport::list_drivers).post(transport::create_driver))
+        .route("/transport/drivers/{id}", put(transport::update_driver))
+        .route("/transport/routes", get(transport::list_routes).post(transport::create_route))
+        .route("/transport/routes/{id}", get(transport::get_route).put(transport::update_route))
+        .route("/transport/routes/{id}/stops", put(transport::replace_stops))
+        .route("/transport/routes/{id}/assignments", post(transport::assign_student))
+        .route("/transport/routes/{id}/manifest", get(transport::manifest))
+        .route("/transport/assignments", get(transport::list_assignments))
+        .route("/transport/assignments/{id}", delete(transport::remove_assignment))
+        .route("/hostel/rooms", get(hostel::list_rooms).post(hostel::create_room))
+        .route("/hostel/rooms/{id}", get(hostel::get_room).put(hostel::update_room))
+        .route("/hostel/rooms/{id}/beds", post(hostel::add_bed))
+        .route("/hostel/beds/{id}", put(hostel::update_bed))
+        .route("/hostel/allocations", get(hostel::list_allocations).post(hostel::allocate))
+        .route("/hostel/allocations/auto", post(hostel::auto_allocate))
+        .route("/hostel/allocations/{id}", delete(hostel::remove_allocation))
+        .route("/hostel/allocations/{id}/check-in", post(hostel::check_in))
+        .route("/hostel/allocations/{id}/check-out", post(hostel::check_out))
+        .route("/hostel/allocations/{id}/logs", get(hostel::stay_logs))
+        .route("/hostel/reports/occupancy", get(hostel::occupancy))
+        .route("/admissions/stages", get(admissions::list_stages).put(admissions::replace_stages))
+        .route("/admissions/apply", post(admissions::apply))
+        .route(
+            "/admissions/apply/documents",
+            post(admissions::upload_with_token).layer(DefaultBodyLimit::max(MAX_DOCUMENT_BYTES)),
+        )
+        .route("/admissions/applications", get(admissions::list_applications).post(admissions::create_application))
+        .route("/admissions/applications/{id}", get(admissions::get_application))
+        .route("/admissions/applications/{id}/stage", put(admissions::move_stage))
+        .route(
+            "/admissions/applications/{id}/documents",
+            post(admissions::upload_document).layer(DefaultBodyLimit::max(MAX_DOCUMENT_BYTES)),
+        )
+        .route("/admissions/applications/{id}/reviews", post(admissions::review))
+        .route("/admissions/applications/{id}/enrol", post(admissions::enrol))
+        .route("/admissions/documents/{id}", get(admissions::download_document))
+        .route("/admissions/decisions", post(admissions::decide))
+        .route("/announcements", get(announcements::list_announcements).post(announcements::create_announcement))
+        .route("/announcements/unread-count", get(announcements::unread_count))
+        .route(
+            "/announcements/{id}",
+            get(announcements::get_announcement)
+                .put(announcements::update_announcement)
+                .delete(announcements::delete_announcement),
+        )
+        .route("/announcements/{id}/read", post(announcements::mark_read))
+        .route("/announcements/{id}/receipts", get(announcements::receipts))
+        .route(
+            "/announcements/{id}/attachments",
+            post(announcements::add_attachment).layer(DefaultBodyLimit::max(MAX_ATTACHMENT_BYTES)),
+        )
+        .route(
+            "/announcements/attachments/{id}",
+            get(announcements::download_attachment).delete(announcements::remove_attachment),
+        )
+        .route("/messages/threads", get(messaging::list_threads).post(messaging::create_thread))
+        .route("/messages/threads/{id}", get(messaging::get_thread))
+        .route("/messages/threads/{id}/messages", post(messaging::reply))
+        .route("/messages/threads/{id}/read", post(messaging::mark_read))
+        .route("/messages/threads/{id}/lock", put(messaging::set_locked))
+        .route(
+            "/messages/{id}/attachments",
+            post(messaging::add_attachment).layer(DefaultBodyLimit::max(MAX_MESSAGE_ATTACHMENT_BYTES)),
+        )
+        .route("/messages/{id}/reports", post(messaging::report_message))
+        .route("/messages/attachments/{id}", get(messaging::download_attachment))
+        .route("/messages/reports", get(messaging::list_reports))
+        .route("/messages/reports/{id}/resolve", post(messaging::resolve_report))
+        .route("/messages/settings", get(messaging::get_settings).put(messaging::update_settings))
+        .route("/messages/archive", get(messaging::list_archive))
+        .route("/notifications/stream", get(notifications::stream))
+        .route("/notifications/ws", get(notifications::websocket))
+        .route("/email/outbox", get(email::list_outbox))
+        .route("/email/outbox/{id}/retry", post(email::retry_email))
+        .route("/lti/platforms", get(lti::list_platforms).post(lti::create_platform))
+        .route("/lti/platforms/{id}/links", get(lti::list_user_links))
+        .route("/lti/platforms/{id}/links/{link_id}", delete(lti::delete_user_link))
+        .route("/lti/jwks", get(lti::jwks))
+        .route("/lti/login", get(lti::login_get).post(lti::login_post))
+        .route("/lti/launch", post(lti::launch))
+        .route("/lti/launches/{id}/link", post(lti::link_launch))
+        .route("/lti/launches/{id}/deep-linking", post(lti::deep_linking))
+        .route("/lti/launches/{id}/scores", post(lti::post_score))
+        .nest("/xapi", xapi::routes())
 }
\ No newline at end of file
@@ -0,0 +1,212 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        FromRef, FromRequestParts, Query, State,
+    },
+    http::{header, request::Parts, HeaderValue, Request, StatusCode, Uri},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use chrono::Utc;
+use futures::Stream;
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::api::extractors::AuthUser;
+use crate::config::Config;
+use crate::infrastructure::notifications::{NotificationHub, Subscription};
+
+type ApiError = (StatusCode, String);
+
+// How often an idle WebSocket is pinged, so dead connections are noticed
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    access_token: Option<String>,
+}
+
+// The request URI with any `?access_token=` blanked, so stream tokens stay
+// out of request logs
+fn redacted(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query: Vec<&str> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some(("access_token", _)) => "access_token=redacted",
+            _ => pair,
+        })
+        .collect();
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+// Span of a traced request, as tower-http's default one but with tokens
+// redacted from the URI
+pub fn trace_span<B>(request: &Request<B>) -> tracing::Span {
+    tracing::debug_span!(
+        "request",
+        method = %request.method(),
+        uri = %redacted(request.uri()),
+        version = ?request.version(),
+    )
+}
+
+// The signed-in user of a notification stream. Browsers cannot set headers on
+// an EventSource or a WebSocket, so the token may also come as
+// `?access_token=`; either way it is checked like any other.
+pub struct StreamUser(AuthUser);
+
+impl<S> FromRequestParts<S> for StreamUser
+where
+    Config: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key(header::AUTHORIZATION) {
+            let Query(query) = Query::<TokenQuery>::from_request_parts(parts, state)
+                .await
+                .map_err(|e| (StatusCode::BAD_REQUEST, e.body_text()))?;
+            if let Some(token) = query.access_token {
+                let value = HeaderValue::from_str(&format!("Bearer {}", token))
+                    .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string()))?;
+                parts.headers.insert(header::AUTHORIZATION, value);
+            }
+        }
+        <AuthUser as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(StreamUser)
+    }
+}
+
+impl StreamUser {
+    // Streams end when the token does; clients reconnect with a fresh one
+    fn time_left(&self) -> Duration {
+        let AuthUser(claims) = &self.0;
+        let seconds = claims.exp - Utc::now().timestamp();
+        Duration::from_secs(seconds.max(0) as u64)
+    }
+}
+
+fn subscribe(hub: &NotificationHub, user: &StreamUser) -> Result<Subscription, ApiError> {
+    let StreamUser(user) = user;
+    let subscription = hub.subscribe(user.id()?);
+    tracing::debug!(
+        "Notification stream opened for {} ({} users connected)",
+        user.0.email,
+        hub.connected_users()
+    );
+    Ok(subscription)
+}
+
+// GET /api/v1/notifications/stream - The caller's notifications as
+// Server-Sent Events, one JSON object per event
+pub async fn stream(
+    State(hub): State<NotificationHub>,
+    user: StreamUser,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let mut subscription = subscribe(&hub, &user)?;
+    let expiry = tokio::time::sleep(user.time_left());
+
+    let events = async_stream::stream! {
+        tokio::pin!(expiry);
+        loop {
+            tokio::select! {
+                notification = subscription.next() => {
+                    let Some(notification) = notification else { break };
+                    match Event::default().json_data(&notification) {
+                        Ok(event) => yield Ok(event),
+                        Err(e) => tracing::error!("Failed to encode notification: {}", e),
+                    }
+                }
+                _ = &mut expiry => {
+                    yield Ok(Event::default().event("expired").data("token expired"));
+                    break;
+                }
+            }
+        }
+    };
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+// GET /api/v1/notifications/ws - The caller's notifications over a
+// WebSocket, one JSON text message each
+pub async fn websocket(
+    State(hub): State<NotificationHub>,
+    user: StreamUser,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let subscription = subscribe(&hub, &user)?;
+    let time_left = user.time_left();
+    Ok(upgrade
+        .on_upgrade(move |socket| forward(socket, subscription, time_left))
+        .into_response())
+}
+
+// Send notifications down the socket until the client goes away or the
+// token expires. Anything the client sends is ignored.
+async fn forward(mut socket: WebSocket, mut subscription: Subscription, time_left: Duration) {
+    let expiry = tokio::time::sleep(time_left);
+    tokio::pin!(expiry);
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.tick().await;
+
+    loop {
+        tokio::select! {
+            notification = subscription.next() => {
+                let Some(notification) = notification else { break };
+                let text = match serde_json::to_string(&notification) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        tracing::error!("Failed to encode notification: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            received = socket.recv() => match received {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+            }
+            _ = &mut expiry => {
+                let frame = CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "token expired".into(),
+                };
+                let _ = socket.send(Message::Close(Some(frame))).await;
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_tokens_are_redacted_from_traces() {
+        let uri: Uri = "/api/v1/notifications/stream?since=4&access_token=eyJhbGci.secret".parse().unwrap();
+        assert_eq!(redacted(&uri), "/api/v1/notifications/stream?since=4&access_token=redacted");
+
+        let uri: Uri = "/api/v1/users?format=csv".parse().unwrap();
+        assert_eq!(redacted(&uri), "/api/v1/users?format=csv");
+        assert_eq!(redacted(&"/health".parse().unwrap()), "/health");
+    }
+}
@@ -0,0 +1,162 @@
+use axum::{extract::State, http::StatusCode, Json};
+use sea_orm::{DatabaseConnection, DbErr};
+use validator::Validate;
+
+use crate::api::extractors::{AuthUser, ClientIp, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::email::{self, Branding};
+use crate::application::{auth, password::{self, PasswordPolicy}};
+use crate::config::Config;
+use crate::dto::auth::{ChangePasswordRequest, ForgotPasswordRequest, ResetPasswordRequest};
+use crate::entities::users;
+use crate::repositories::email_repository::EmailRepository;
+use crate::repositories::password_repository::PasswordRepository;
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: DbErr) -> ApiError {
+    tracing::error!("Database error in password management: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
+
+// Check a new password against the policy and the user's previous passwords,
+// and hash it
+async fn checked_hash(
+    db: &DatabaseConnection,
+    policy: &PasswordPolicy,
+    user: &users::Model,
+    new_password: &str,
+) -> Result<String, ApiError> {
+    let previous = PasswordRepository::previous_hashes(db, user, policy.history_size)
+        .await
+        .map_err(db_error)?;
+    policy
+        .check(new_password, &user.email, &previous)
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    auth::hash_password(new_password)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash password".to_string()))
+}
+
+// POST /api/v1/auth/password/change - Change the signed-in user's password; every
+// token issued so far, this one included, stops working
+pub async fn change(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<PasswordPolicy>,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if let Err(e) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+
+    let account = UserRepository::find_account(&db, user.0.tid, &user.0.email)
+        .await
+        .map_err(db_error)?
+        .filter(|account| account.is_active)
+        .ok_or((StatusCode::UNAUTHORIZED, "Account not found or deactivated".to_string()))?;
+    if account.auth_source != "local" {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("This account signs in through {}, change the password there", account.auth_source),
+        ));
+    }
+    if !auth::verify_password(&payload.current_password, &account.password_hash) {
+        return Err((StatusCode::UNAUTHORIZED, "Current password is wrong".to_string()));
+    }
+
+    let password_hash = checked_hash(&db, &policy, &account, &payload.new_password).await?;
+    PasswordRepository::set_password(&db, account, password_hash, policy.history_size, "change_password", &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Password changed for {}", user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/v1/auth/password/forgot - Queue an email with a reset link. Always answers 202 so
+// the endpoint cannot be used to find out which emails have accounts.
+pub async fn forgot(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    tenant: CurrentTenant,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if let Err(e) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+
+    let account = UserRepository::find_account(&db, tenant.id(), &payload.email)
+        .await
+        .map_err(db_error)?
+        .filter(|account| account.is_active && account.auth_source == "local");
+    let Some(account) = account else {
+        tracing::info!("Password reset requested for unknown or external account {}", payload.email);
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let (token, token_hash) = password::new_reset_token();
+    PasswordRepository::create_reset_token(&db, account.id, token_hash, config.password_reset_token_minutes)
+        .await
+        .map_err(db_error)?;
+
+    let email = email::password_reset(
+        &Branding::from(&tenant.0),
+        &account.email,
+        &account.full_name,
+        &config.password_reset_url,
+        &token,
+        config.password_reset_token_minutes,
+    );
+    match email {
+        Ok(email) => {
+            EmailRepository::enqueue(&db, tenant.id(), email::PASSWORD_RESET, &[email])
+                .await
+                .map_err(db_error)?;
+        }
+        Err(e) => tracing::error!("Failed to render password reset for {}: {}", account.email, e),
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+// POST /api/v1/auth/password/reset - Set a new password with an emailed token
+pub async fn reset(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<PasswordPolicy>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if let Err(e) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+
+    let invalid = || (StatusCode::BAD_REQUEST, "Reset link is invalid or has expired".to_string());
+    let token_hash = password::hash_reset_token(&payload.token);
+    let account = PasswordRepository::find_reset_token(&db, &token_hash)
+        .await
+        .map_err(db_error)?
+        .filter(|account| account.is_active)
+        .ok_or_else(invalid)?;
+
+    // Check the policy before spending the token, so a rejected password can be retried
+    let password_hash = checked_hash(&db, &policy, &account, &payload.new_password).await?;
+    if !PasswordRepository::spend_reset_token(&db, &token_hash).await.map_err(db_error)? {
+        return Err(invalid());
+    }
+
+    let email = account.email.clone();
+    // The link holder acts as the account owner, in the account's own school
+    let context = AuditContext {
+        tenant_id: Some(account.tenant_id),
+        actor_id: Some(account.id),
+        actor_email: Some(email.clone()),
+        ip_address: ip.map(|ip| ip.to_string()),
+        ..Default::default()
+    };
+    PasswordRepository::set_password(&db, account, password_hash, policy.history_size, "reset_password", &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Password reset for {}", email);
+    Ok(StatusCode::NO_CONTENT)
+}
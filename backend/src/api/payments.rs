@@ -0,0 +1,233 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, ClientIp, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::fees::{self, FeeError, FEE_ROLES};
+use crate::application::payments::{self as online, Reconciliation};
+use crate::dto::payments::{CheckoutResponse, CreateCheckoutRequest, CreateRefundRequest, RefundResponse, WebhookResponse};
+use crate::infrastructure::payments::{CheckoutRequest, PaymentError, PaymentGateway};
+use crate::repositories::fee_repository::FeeRepository;
+use crate::repositories::payment_repository::PaymentRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn fee_error(e: FeeError) -> ApiError {
+    match e {
+        FeeError::InvoiceNotFound => (StatusCode::NOT_FOUND, "Invoice not found".to_string()),
+        FeeError::PaymentNotFound => (StatusCode::NOT_FOUND, "Payment not found".to_string()),
+        FeeError::Overpayment { .. } | FeeError::AlreadyPaid | FeeError::RefundTooLarge { .. } => {
+            (StatusCode::CONFLICT, e.to_string())
+        }
+        FeeError::Database(e) => db_error(e),
+    }
+}
+
+fn provider_error(e: PaymentError) -> ApiError {
+    tracing::error!("Payment provider error: {}", e);
+    (StatusCode::BAD_GATEWAY, "The payment provider could not be reached".to_string())
+}
+
+fn require_gateway(gateway: Option<PaymentGateway>) -> Result<PaymentGateway, ApiError> {
+    gateway.ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Online payments are not enabled".to_string(),
+    ))
+}
+
+fn require_fee_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !FEE_ROLES.contains(&user.0.role.as_str()) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and bursars can refund payments".to_string()));
+    }
+    Ok(())
+}
+
+impl ExportRow for RefundResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "payment_id", "amount", "reason", "status", "created_at", "settled_at"];
+}
+
+// POST /api/v1/fees/invoices/:id/checkout - Start paying an invoice online;
+// the payer is sent to the returned checkout URL
+pub async fn create_checkout(
+    State(db): State<DatabaseConnection>,
+    State(gateway): State<Option<PaymentGateway>>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<CreateCheckoutRequest>,
+) -> Result<(StatusCode, Json<CheckoutResponse>), ApiError> {
+    let gateway = require_gateway(gateway)?;
+    let invoice = FeeRepository::find_invoice(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .filter(|invoice| FEE_ROLES.contains(&user.0.role.as_str()) || user.0.user_id() == Some(invoice.student_id))
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+    let amount = online::checkout_amount(payload.amount, invoice.total - invoice.amount_paid)
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    let request = CheckoutRequest {
+        reference: fees::invoice_number(invoice.id),
+        amount,
+        currency: gateway.currency.clone(),
+        description: format!("{} fees, {}", invoice.term, tenant.0.name),
+        return_url: gateway.return_url.clone(),
+    };
+    let session = gateway.provider.create_checkout(&request).await.map_err(provider_error)?;
+    let session = PaymentRepository::create_session(
+        &db,
+        &invoice,
+        gateway.provider.name(),
+        &request,
+        &session,
+        Some(user.id()?),
+        &context,
+    )
+    .await
+    .map_err(db_error)?;
+
+    tracing::info!(
+        "Checkout of {} for {} started by {}",
+        session.amount,
+        request.reference,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(session.into())))
+}
+
+// POST /api/v1/payments/webhook - Events from the payment provider. Only the
+// signature is trusted; answering 2xx for events already handled stops retries.
+pub async fn webhook(
+    State(db): State<DatabaseConnection>,
+    State(gateway): State<Option<PaymentGateway>>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<WebhookResponse>, ApiError> {
+    let provider = gateway
+        .map(|gateway| gateway.provider)
+        .ok_or((StatusCode::NOT_FOUND, "Online payments are not enabled".to_string()))?;
+    let signature = headers
+        .get(provider.signature_header())
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let verified = provider.verify_webhook(signature, &body).map_err(|e| {
+        tracing::warn!("Rejected payment webhook: {}", e);
+        (StatusCode::BAD_REQUEST, e.to_string())
+    })?;
+
+    // Providers call one URL on whatever host they were given, so the school is
+    // not resolved from the request; the repository audits under the school
+    // that owns the session or refund
+    let context = AuditContext {
+        ip_address: ip.map(|ip| ip.to_string()),
+        request_id: headers
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        ..Default::default()
+    };
+    let outcome = PaymentRepository::reconcile(&db, provider.name(), &verified, &context)
+        .await
+        .map_err(db_error)?;
+    match &outcome {
+        Reconciliation::Unmatched => tracing::warn!(
+            "Payment webhook {} ({}) matches no session or refund",
+            verified.id,
+            verified.event.kind()
+        ),
+        other => tracing::info!("Payment webhook {} ({}): {:?}", verified.id, verified.event.kind(), other),
+    }
+    Ok(Json(WebhookResponse::from(&outcome)))
+}
+
+// GET /api/v1/fees/payments/:id/refunds - Refunds on a payment
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_refunds(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_fee_staff(&user)?;
+
+    let refunds = PaymentRepository::list_refunds(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "refunds", refunds.into_iter().map(RefundResponse::from).collect()).await)
+}
+
+// POST /api/v1/fees/payments/:id/refunds - Give money back. Online payments
+// are refunded through the provider; others are settled as handed back.
+pub async fn create_refund(
+    State(db): State<DatabaseConnection>,
+    State(gateway): State<Option<PaymentGateway>>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<CreateRefundRequest>,
+) -> Result<(StatusCode, Json<RefundResponse>), ApiError> {
+    require_fee_staff(&user)?;
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+
+    let (refund, payment) =
+        PaymentRepository::begin_refund(&db, tenant.id(), id, payload.amount, &payload.reason, Some(user.id()?), &context)
+            .await
+            .map_err(fee_error)?;
+
+    let refund = match (&payment.provider_payment_id, gateway) {
+        (None, _) => PaymentRepository::settle_refund(&db, refund, true, &context)
+            .await
+            .map_err(db_error)?,
+        (Some(provider_payment_id), gateway) => {
+            let provider = match require_gateway(gateway) {
+                Ok(gateway) => gateway.provider,
+                Err(e) => {
+                    PaymentRepository::settle_refund(&db, refund, false, &context)
+                        .await
+                        .map_err(db_error)?;
+                    return Err(e);
+                }
+            };
+            match provider.refund(provider_payment_id, refund.amount).await {
+                Ok(provider_refund) => PaymentRepository::record_provider_refund(&db, refund, &provider_refund, &context)
+                    .await
+                    .map_err(db_error)?,
+                Err(e) => {
+                    // Free the amount so the refund can be tried again
+                    PaymentRepository::settle_refund(&db, refund, false, &context)
+                        .await
+                        .map_err(db_error)?;
+                    return Err(provider_error(e));
+                }
+            }
+        }
+    };
+
+    tracing::info!(
+        "Refund of {} on {} ({}) by {}",
+        refund.amount,
+        fees::receipt_number(payment.id),
+        refund.status,
+        user.0.email
+    );
+    Ok((StatusCode::CREATED, Json(refund.into())))
+}
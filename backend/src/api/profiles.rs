@@ -0,0 +1,144 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::encryption::{self, EncryptionError, Keyring};
+use crate::dto::profile::{ProfileResponse, ProfileSearchQuery, ReencryptResponse, UpdateProfileRequest};
+use crate::dto::user::UserResponse;
+use crate::entities::user_profiles;
+use crate::repositories::profile_repository::{self, ProfileRepository};
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn decryption_error(user_id: i32, e: EncryptionError) -> ApiError {
+    tracing::error!("Cannot decrypt profile of user {}: {}", user_id, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Profile could not be decrypted".to_string())
+}
+
+fn require_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can do this".to_string()));
+    }
+    Ok(())
+}
+
+fn require_self_or_admin(user: &AuthUser, id: i32) -> Result<(), ApiError> {
+    if user.id()? != id && user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "You can only access your own profile".to_string()));
+    }
+    Ok(())
+}
+
+fn open_profile(keyring: &Keyring, profile: user_profiles::Model) -> Result<ProfileResponse, ApiError> {
+    let user_id = profile.user_id;
+    let open = |column, value| {
+        keyring
+            .open_opt(value, profile_repository::field(column, user_id))
+            .map_err(|e| decryption_error(user_id, e))
+    };
+    Ok(ProfileResponse {
+        user_id,
+        national_id: open("national_id", profile.national_id.as_ref())?,
+        address: open("address", profile.address.as_ref())?,
+        medical_notes: open("medical_notes", profile.medical_notes.as_ref())?,
+        updated_at: Some(profile.updated_at.format("%Y-%m-%d %H:%M:%S").to_string()),
+    })
+}
+
+async fn require_user(db: &DatabaseConnection, tenant: &CurrentTenant, id: i32) -> Result<(), ApiError> {
+    UserRepository::find_by_id(db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    Ok(())
+}
+
+// GET /api/v1/users/:id/profile - Decrypted sensitive profile (self or admin)
+pub async fn get_profile(
+    State(db): State<DatabaseConnection>,
+    State(keyring): State<Keyring>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    require_self_or_admin(&user, id)?;
+    require_user(&db, &tenant, id).await?;
+
+    match ProfileRepository::find(&db, id).await.map_err(db_error)? {
+        Some(profile) => Ok(Json(open_profile(&keyring, profile)?)),
+        None => Ok(Json(ProfileResponse {
+            user_id: id,
+            national_id: None,
+            address: None,
+            medical_notes: None,
+            updated_at: None,
+        })),
+    }
+}
+
+// PUT /api/v1/users/:id/profile - Replace the sensitive profile (self or admin)
+pub async fn update_profile(
+    State(db): State<DatabaseConnection>,
+    State(keyring): State<Keyring>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    require_self_or_admin(&user, id)?;
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+    require_user(&db, &tenant, id).await?;
+
+    let profile = ProfileRepository::save(&db, &keyring, id, &payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Profile of user {} updated by {}", id, user.0.email);
+    Ok(Json(open_profile(&keyring, profile)?))
+}
+
+// GET /api/v1/admin/profiles/search?national_id= - Users with that national ID (admin)
+pub async fn search(
+    State(db): State<DatabaseConnection>,
+    State(keyring): State<Keyring>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Query(query): Query<ProfileSearchQuery>,
+) -> Result<Json<Vec<UserResponse>>, ApiError> {
+    require_admin(&admin)?;
+
+    let users = ProfileRepository::find_users_by_national_id(&db, &keyring, tenant.id(), &query.national_id)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(users.into_iter().map(UserResponse::from).collect()))
+}
+
+// POST /api/v1/admin/encryption/reencrypt - Move every profile onto the active key now (admin)
+pub async fn reencrypt(
+    State(db): State<DatabaseConnection>,
+    State(keyring): State<Keyring>,
+    admin: AuthUser,
+) -> Result<Json<ReencryptResponse>, ApiError> {
+    require_admin(&admin)?;
+
+    let reencrypted = encryption::reencrypt_stale(&db, &keyring).await.map_err(db_error)?;
+    tracing::info!("Re-encryption run by {}: {} profiles", admin.0.email, reencrypted);
+    Ok(Json(ReencryptResponse {
+        active_key: keyring.active_key_id().to_string(),
+        reencrypted,
+    }))
+}
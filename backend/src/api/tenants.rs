@@ -0,0 +1,144 @@
+use axum::{extract::State, http::StatusCode, response::Response, Json};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::email;
+use crate::application::password::PasswordPolicy;
+use crate::application::tenancy;
+use crate::dto::tenant::{CreateTenantRequest, TenantResponse, UpdateTenantRequest};
+use crate::dto::user::UserResponse;
+use crate::repositories::tenant_repository::TenantRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn require_platform_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if !user.is_platform_admin() {
+        return Err((StatusCode::FORBIDDEN, "Only platform admins can manage schools".to_string()));
+    }
+    Ok(())
+}
+
+// Checks the validator attributes cannot express
+fn check_settings(settings: &UpdateTenantRequest) -> Result<(), ApiError> {
+    if let Some(scale) = &settings.grading_scale {
+        tenancy::validate_grading_scale(scale).map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+    }
+    if !tenancy::valid_timezone(&settings.timezone) {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown timezone: {}", settings.timezone)));
+    }
+    if settings.locale.as_deref().is_some_and(|locale| !email::LOCALES.contains(&locale)) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Locale must be one of: {}", email::LOCALES.join(", ")),
+        ));
+    }
+    if settings.brand_color.as_deref().is_some_and(|color| !email::valid_color(color)) {
+        return Err((StatusCode::BAD_REQUEST, "Brand colour must be a hex code like #1d4ed8".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct CreatedTenantResponse {
+    tenant: TenantResponse,
+    admin: UserResponse,
+}
+
+impl ExportRow for TenantResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "slug", "name", "logo_url", "grading_scale", "timezone", "locale", "brand_color", "is_active"];
+}
+
+// GET /api/v1/tenant - Branding and settings of the school the request is for
+pub async fn current(tenant: CurrentTenant) -> Json<TenantResponse> {
+    Json(tenant.0.into())
+}
+
+// PUT /api/v1/tenant - Change the school's name, logo, grading scale and timezone (admin)
+pub async fn update_current(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<UpdateTenantRequest>,
+) -> Result<Json<TenantResponse>, ApiError> {
+    if admin.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can change school settings".to_string()));
+    }
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+    check_settings(&payload)?;
+
+    let updated = TenantRepository::update(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Unknown school".to_string()))?;
+    tracing::info!("Settings of school {} updated by {}", updated.slug, admin.0.email);
+    Ok(Json(updated))
+}
+
+// GET /api/v1/admin/tenants - Every school, active or not (platform admin)
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list(
+    State(db): State<DatabaseConnection>,
+    admin: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_platform_admin(&admin)?;
+
+    let tenants = TenantRepository::find_all(&db).await.map_err(db_error)?;
+    Ok(list_response(format, "tenants", tenants.into_iter().map(TenantResponse::from).collect()).await)
+}
+
+// POST /api/v1/admin/tenants - Add a school with its first admin (platform admin)
+pub async fn create(
+    State(db): State<DatabaseConnection>,
+    State(policy): State<PasswordPolicy>,
+    admin: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<CreateTenantRequest>,
+) -> Result<(StatusCode, Json<CreatedTenantResponse>), ApiError> {
+    require_platform_admin(&admin)?;
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)))?;
+    if !tenancy::valid_slug(&payload.slug) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Slug must be 2-63 lowercase letters, digits or dashes".to_string(),
+        ));
+    }
+    check_settings(&payload.settings)?;
+    policy
+        .check(&payload.admin.password, &payload.admin.email, &[])
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+    if TenantRepository::find_by_slug(&db, &payload.slug)
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err((StatusCode::CONFLICT, format!("A school with slug {} already exists", payload.slug)));
+    }
+
+    let (tenant, school_admin) = TenantRepository::create(&db, &payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("School {} created by {}", tenant.slug, admin.0.email);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedTenantResponse {
+            tenant,
+            admin: school_admin,
+        }),
+    ))
+}
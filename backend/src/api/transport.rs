@@ -0,0 +1,408 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::api::export::{export_response, format_error, list_response, Export, ExportFormat, ExportQuery, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::transport::{TransportError, TRANSPORT_ROLES};
+use crate::dto::transport::{
+    AssignStudentRequest, AssignmentQuery, AssignmentResponse, DriverRequest, DriverResponse, ManifestResponse,
+    ManifestRow, ManifestStop, ManifestStudent, ReplaceStopsRequest, RouteDetail, RouteRequest, RouteResponse,
+    StopResponse, VehicleRequest, VehicleResponse,
+};
+use crate::repositories::transport_repository::TransportRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn transport_error(e: TransportError) -> ApiError {
+    match e {
+        TransportError::RouteNotFound => (StatusCode::NOT_FOUND, "Route not found".to_string()),
+        TransportError::VehicleNotFound => (StatusCode::NOT_FOUND, "Vehicle not found".to_string()),
+        TransportError::DriverNotFound => (StatusCode::NOT_FOUND, "Driver not found".to_string()),
+        TransportError::StudentNotFound => (StatusCode::NOT_FOUND, "Student not found".to_string()),
+        TransportError::AssignmentNotFound => (StatusCode::NOT_FOUND, "Assignment not found".to_string()),
+        TransportError::StopNotFound => (StatusCode::BAD_REQUEST, "Stop is not on this route".to_string()),
+        TransportError::StopsOutOfOrder(_) => (StatusCode::BAD_REQUEST, capitalize(&e.to_string())),
+        TransportError::Database(e) => db_error(e),
+        e => (StatusCode::CONFLICT, capitalize(&e.to_string())),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_transport_staff(user: &AuthUser) -> bool {
+    TRANSPORT_ROLES.contains(&user.0.role.as_str())
+}
+
+fn require_transport_staff(user: &AuthUser) -> Result<(), ApiError> {
+    if !is_transport_staff(user) {
+        return Err((StatusCode::FORBIDDEN, "Only admins and transport staff can manage transport".to_string()));
+    }
+    Ok(())
+}
+
+fn validation_error(e: validator::ValidationErrors) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("Validation error: {}", e))
+}
+
+// GET /api/v1/transport/vehicles - The fleet
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_vehicles(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_transport_staff(&user)?;
+
+    let vehicles = TransportRepository::list_vehicles(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "vehicles", vehicles.into_iter().map(VehicleResponse::from).collect()).await)
+}
+
+// POST /api/v1/transport/vehicles - Add a vehicle
+pub async fn create_vehicle(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<VehicleRequest>,
+) -> Result<(StatusCode, Json<VehicleResponse>), ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let vehicle = TransportRepository::create_vehicle(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(transport_error)?;
+    tracing::info!("Vehicle {} added by {}", vehicle.registration, user.0.email);
+    Ok((StatusCode::CREATED, Json(vehicle.into())))
+}
+
+// PUT /api/v1/transport/vehicles/:id - Update a vehicle
+pub async fn update_vehicle(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<VehicleRequest>,
+) -> Result<Json<VehicleResponse>, ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let vehicle = TransportRepository::update_vehicle(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(transport_error)?;
+    tracing::info!("Vehicle {} updated by {}", vehicle.registration, user.0.email);
+    Ok(Json(vehicle.into()))
+}
+
+// GET /api/v1/transport/drivers - Drivers
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_drivers(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_transport_staff(&user)?;
+
+    let drivers = TransportRepository::list_drivers(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "drivers", drivers.into_iter().map(DriverResponse::from).collect()).await)
+}
+
+// POST /api/v1/transport/drivers - Add a driver
+pub async fn create_driver(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<DriverRequest>,
+) -> Result<(StatusCode, Json<DriverResponse>), ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let driver = TransportRepository::create_driver(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Driver {} added by {}", driver.full_name, user.0.email);
+    Ok((StatusCode::CREATED, Json(driver.into())))
+}
+
+// PUT /api/v1/transport/drivers/:id - Update a driver
+pub async fn update_driver(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<DriverRequest>,
+) -> Result<Json<DriverResponse>, ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let driver = TransportRepository::update_driver(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Driver not found".to_string()))?;
+    tracing::info!("Driver {} updated by {}", driver.full_name, user.0.email);
+    Ok(Json(driver.into()))
+}
+
+// GET /api/v1/transport/routes - Routes by name
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_routes(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    _user: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    let routes = TransportRepository::list_routes(&db, tenant.id())
+        .await
+        .map_err(db_error)?;
+    Ok(list_response(format, "routes", routes.into_iter().map(RouteResponse::from).collect()).await)
+}
+
+// POST /api/v1/transport/routes - Add a route
+pub async fn create_route(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<RouteRequest>,
+) -> Result<(StatusCode, Json<RouteResponse>), ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let route = TransportRepository::create_route(&db, tenant.id(), &payload, &context)
+        .await
+        .map_err(transport_error)?;
+    tracing::info!("Route {} added by {}", route.name, user.0.email);
+    Ok((StatusCode::CREATED, Json(route.into())))
+}
+
+// GET /api/v1/transport/routes/:id - A route with its vehicle, driver and timetable
+pub async fn get_route(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    _user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<Json<RouteDetail>, ApiError> {
+    let route = TransportRepository::route_detail(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Route not found".to_string()))?;
+    Ok(Json(route))
+}
+
+// PUT /api/v1/transport/routes/:id - Rename a route or change who runs it
+pub async fn update_route(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<RouteRequest>,
+) -> Result<Json<RouteResponse>, ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let route = TransportRepository::update_route(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(transport_error)?;
+    tracing::info!("Route {} updated by {}", route.name, user.0.email);
+    Ok(Json(route.into()))
+}
+
+// PUT /api/v1/transport/routes/:id/stops - Set a route's stops and times, in order
+pub async fn replace_stops(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<ReplaceStopsRequest>,
+) -> Result<Json<Vec<StopResponse>>, ApiError> {
+    require_transport_staff(&user)?;
+    payload.validate().map_err(validation_error)?;
+
+    let stops = TransportRepository::replace_stops(&db, tenant.id(), id, &payload.stops, &context)
+        .await
+        .map_err(transport_error)?;
+    tracing::info!("{} stops set on route {} by {}", stops.len(), id, user.0.email);
+    Ok(Json(stops.into_iter().map(StopResponse::from).collect()))
+}
+
+// POST /api/v1/transport/routes/:id/assignments - Seat a student at a stop
+pub async fn assign_student(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<AssignStudentRequest>,
+) -> Result<Json<AssignmentResponse>, ApiError> {
+    require_transport_staff(&user)?;
+
+    let assignment = TransportRepository::assign_student(&db, tenant.id(), id, &payload, &context)
+        .await
+        .map_err(transport_error)?;
+    tracing::info!(
+        "Student {} assigned to route {} stop {} by {}",
+        assignment.student_id,
+        assignment.route_id,
+        assignment.stop_id,
+        user.0.email
+    );
+    Ok(Json(assignment.into()))
+}
+
+// GET /api/v1/transport/assignments - Who rides where; students only see their own
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_assignments(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(mut query): Query<AssignmentQuery>,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    if !is_transport_staff(&user) {
+        query.student_id = Some(user.id()?);
+    }
+
+    let assignments = TransportRepository::list_assignments(&db, tenant.id(), &query)
+        .await
+        .map_err(db_error)?;
+    let assignments: Vec<_> = assignments.into_iter().map(AssignmentResponse::from).collect();
+    Ok(list_response(format, "transport-assignments", assignments).await)
+}
+
+// DELETE /api/v1/transport/assignments/:id - Take a student off a route
+pub async fn remove_assignment(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_transport_staff(&user)?;
+
+    if !TransportRepository::remove_assignment(&db, tenant.id(), id, &context)
+        .await
+        .map_err(db_error)?
+    {
+        return Err(transport_error(TransportError::AssignmentNotFound));
+    }
+    tracing::info!("Transport assignment {} removed by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+impl ExportRow for AssignmentResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "route_id", "stop_id", "student_id", "created_at"];
+}
+
+impl ExportRow for DriverResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "full_name", "phone", "license_number", "license_expires_on", "is_active"];
+}
+
+impl ExportRow for RouteResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "vehicle_id", "driver_id", "is_active"];
+}
+
+impl ExportRow for VehicleResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "registration", "description", "capacity", "is_active"];
+}
+
+impl ExportRow for ManifestRow {
+    const COLUMNS: &'static [&'static str] = &["stop_position", "stop", "time", "student_id", "student_name"];
+}
+
+// GET /api/v1/transport/routes/:id/manifest - Stops in order with the
+// students boarding at each, for the driver to carry. Also exports as
+// CSV/XLSX via `Accept` or `?format=` for printing.
+pub async fn manifest(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    require_transport_staff(&user)?;
+    let format = ExportFormat::negotiate(&query, &headers)
+        .map_err(format_error)?;
+
+    let route = TransportRepository::route_detail(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Route not found".to_string()))?;
+    let stops = TransportRepository::manifest(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?;
+
+    if let Some(format) = format {
+        let rows: Vec<Result<ManifestRow, sea_orm::DbErr>> = stops
+            .into_iter()
+            .flat_map(|(stop, students)| {
+                students.into_iter().map(move |student| {
+                    Ok(ManifestRow {
+                        stop_position: stop.position,
+                        stop: stop.name.clone(),
+                        time: stop.scheduled_time,
+                        student_id: student.id,
+                        student_name: student.full_name,
+                    })
+                })
+            })
+            .collect();
+        let name = format!("manifest-route-{}", route.route.id);
+        return Ok(export_response(format, &name, futures::stream::iter(rows)).await);
+    }
+
+    Ok(Json(ManifestResponse {
+        school: tenant.0.name.clone(),
+        route: route.route.name,
+        vehicle: route.vehicle.as_ref().map(|vehicle| vehicle.registration.clone()),
+        capacity: route.vehicle.as_ref().map(|vehicle| vehicle.capacity),
+        driver: route.driver.as_ref().map(|driver| driver.full_name.clone()),
+        driver_phone: route.driver.and_then(|driver| driver.phone),
+        seats_taken: stops.iter().map(|(_, students)| students.len()).sum(),
+        stops: stops
+            .into_iter()
+            .map(|(stop, students)| ManifestStop {
+                position: stop.position,
+                name: stop.name,
+                time: stop.scheduled_time,
+                students: students
+                    .into_iter()
+                    .map(|student| ManifestStudent {
+                        id: student.id,
+                        full_name: student.full_name,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    })
+    .into_response())
+}
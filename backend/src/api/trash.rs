@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use sea_orm::DatabaseConnection;
+
+use crate::api::export::{list_response, Export, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::trash;
+use crate::config::Config;
+use crate::dto::user::{TrashedUserResponse, UserResponse};
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn require_admin(user: &AuthUser) -> Result<(), ApiError> {
+    if user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can manage the trash".to_string()));
+    }
+    Ok(())
+}
+
+impl ExportRow for TrashedUserResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "tenant_id", "email", "full_name", "role", "is_active", "created_at", "deleted_at", "purge_after"];
+}
+
+// GET /api/v1/admin/trash/users - Deleted users awaiting purge (admin)
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
+pub async fn list_users(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Export(format): Export,
+) -> Result<Response, ApiError> {
+    require_admin(&admin)?;
+
+    let users = UserRepository::find_deleted(&db, tenant.id()).await.map_err(db_error)?;
+    let timestamp = |time: chrono::NaiveDateTime| time.format("%Y-%m-%d %H:%M:%S").to_string();
+    let users: Vec<TrashedUserResponse> = users
+        .into_iter()
+        .filter_map(|user| {
+            let deleted_at = user.deleted_at?;
+            Some(TrashedUserResponse {
+                user: UserResponse::from(user),
+                deleted_at: timestamp(deleted_at),
+                purge_after: timestamp(trash::purge_after(deleted_at, config.trash_retention_days)),
+            })
+        })
+        .collect();
+    Ok(list_response(format, "trashed-users", users).await)
+}
+
+// POST /api/v1/admin/trash/users/:id/restore - Bring a deleted user back (admin)
+pub async fn restore_user(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<Json<UserResponse>, ApiError> {
+    require_admin(&admin)?;
+
+    let user = UserRepository::restore(&db, tenant.id(), id, &context)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User is not in the trash".to_string()))?;
+    tracing::info!("User {} restored by {}", user.email, admin.0.email);
+    Ok(Json(user))
+}
+
+// DELETE /api/v1/admin/trash/users/:id - Purge a deleted user now (admin)
+pub async fn purge_user(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&admin)?;
+
+    if !UserRepository::purge(&db, tenant.id(), id, &context).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, "User is not in the trash".to_string()));
+    }
+    tracing::info!("User {} purged by {}", id, admin.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,295 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sea_orm::{DatabaseConnection, DbErr};
+use validator::Validate;
+
+use crate::api::extractors::{AuthUser, ClientIp, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::auth;
+use crate::application::login_guard::LoginGuard;
+use crate::application::two_factor::{self, TwoFactorError};
+use crate::config::Config;
+use crate::dto::auth::{
+    LoginOutcome, LoginResponse, RecoveryCodesResponse, TwoFactorChallengeResponse, TwoFactorCodeRequest,
+    TwoFactorEnabledResponse, TwoFactorSetupRequest, TwoFactorSetupResponse, TwoFactorStatusResponse,
+};
+use crate::dto::user::UserResponse;
+use crate::repositories::two_factor_repository::TwoFactorRepository;
+use crate::repositories::user_repository::UserRepository;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: DbErr) -> ApiError {
+    tracing::error!("Database error in two-factor auth: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
+
+fn two_factor_error(e: TwoFactorError) -> ApiError {
+    tracing::error!("Two-factor error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn token_error(e: jsonwebtoken::errors::Error) -> ApiError {
+    tracing::error!("Failed to issue token: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Failed to issue token".to_string())
+}
+
+fn invalid_code() -> ApiError {
+    (StatusCode::UNAUTHORIZED, "Invalid authentication code".to_string())
+}
+
+fn now() -> u64 {
+    Utc::now().timestamp().max(0) as u64
+}
+
+// Finish a password login: hand out an access token, or a challenge when the
+// user has 2FA on or their role requires it
+pub async fn login_outcome(
+    db: &DatabaseConnection,
+    config: &Config,
+    user: UserResponse,
+) -> Result<Json<LoginOutcome>, ApiError> {
+    let enabled = TwoFactorRepository::is_enabled(db, user.id).await.map_err(db_error)?;
+    let required = two_factor::required_for(config, &user.role);
+
+    if !enabled && !required {
+        let token = auth::issue_token(config, &user).map_err(token_error)?;
+        return Ok(Json(LoginOutcome::SignedIn(LoginResponse { token, user })));
+    }
+
+    let challenge_token = auth::issue_challenge(config, user.id).map_err(token_error)?;
+    Ok(Json(LoginOutcome::TwoFactor(TwoFactorChallengeResponse {
+        two_factor_required: true,
+        enrollment_required: !enabled,
+        challenge_token,
+    })))
+}
+
+// The user being enrolled: the signed-in user, or the one with a pending login
+fn enrolling_user(config: &Config, user: Option<AuthUser>, challenge_token: Option<&str>) -> Result<(i32, bool), ApiError> {
+    if let Some(user) = user {
+        return Ok((user.id()?, false));
+    }
+    challenge_token
+        .and_then(|token| auth::verify_challenge(config, token))
+        .map(|user_id| (user_id, true))
+        .ok_or((StatusCode::UNAUTHORIZED, "Sign in or send a valid challenge token".to_string()))
+}
+
+async fn load_user(db: &DatabaseConnection, tenant: &CurrentTenant, user_id: i32) -> Result<UserResponse, ApiError> {
+    UserRepository::find_by_id(db, tenant.id(), user_id)
+        .await
+        .map_err(db_error)?
+        .filter(|user| user.is_active)
+        .ok_or((StatusCode::UNAUTHORIZED, "Account not found or deactivated".to_string()))
+}
+
+// Check a TOTP code of a confirmed authenticator and burn its time step
+async fn check_totp(db: &DatabaseConnection, user_id: i32, code: &str) -> Result<bool, ApiError> {
+    let Some(totp) = TwoFactorRepository::find(db, user_id).await.map_err(db_error)? else {
+        return Ok(false);
+    };
+    if totp.confirmed_at.is_none() {
+        return Ok(false);
+    }
+    let Some(step) = two_factor::verify_code(&totp.secret, code, totp.last_used_step, now())
+        .map_err(two_factor_error)?
+    else {
+        return Ok(false);
+    };
+    TwoFactorRepository::use_step(db, user_id, step).await.map_err(db_error)
+}
+
+// GET /api/v1/auth/2fa - Two-factor status of the signed-in user
+pub async fn status(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    user: AuthUser,
+) -> Result<Json<TwoFactorStatusResponse>, ApiError> {
+    let user_id = user.id()?;
+    let enabled = TwoFactorRepository::is_enabled(&db, user_id).await.map_err(db_error)?;
+    let recovery_codes_remaining = TwoFactorRepository::remaining_recovery_codes(&db, user_id)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(TwoFactorStatusResponse {
+        enabled,
+        required: two_factor::required_for(&config, &user.0.role),
+        recovery_codes_remaining,
+    }))
+}
+
+// POST /api/v1/auth/2fa/setup - Generate a secret to add to an authenticator app
+pub async fn setup(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    tenant: CurrentTenant,
+    user: Option<AuthUser>,
+    payload: Option<Json<TwoFactorSetupRequest>>,
+) -> Result<Json<TwoFactorSetupResponse>, ApiError> {
+    let Json(payload) = payload.unwrap_or_default();
+    let (user_id, _) = enrolling_user(&config, user, payload.challenge_token.as_deref())?;
+    let user = load_user(&db, &tenant, user_id).await?;
+
+    if TwoFactorRepository::is_enabled(&db, user_id).await.map_err(db_error)? {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is already enabled".to_string()));
+    }
+
+    let secret = two_factor::new_secret();
+    let provisioning_uri = two_factor::provisioning_uri(&config.two_factor_issuer, &secret, &user.email)
+        .map_err(two_factor_error)?;
+    TwoFactorRepository::start_enrollment(&db, user_id, secret.clone())
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(TwoFactorSetupResponse { secret, provisioning_uri }))
+}
+
+// POST /api/v1/auth/2fa/enable - Confirm the authenticator with a first code
+pub async fn enable(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    tenant: CurrentTenant,
+    user: Option<AuthUser>,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<TwoFactorEnabledResponse>, ApiError> {
+    if let Err(e) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+    let (user_id, pending_login) = enrolling_user(&config, user, payload.challenge_token.as_deref())?;
+    let user = load_user(&db, &tenant, user_id).await?;
+
+    let totp = TwoFactorRepository::find(&db, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::BAD_REQUEST, "Start setup first".to_string()))?;
+    if totp.confirmed_at.is_some() {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is already enabled".to_string()));
+    }
+    let step = two_factor::verify_code(&totp.secret, &payload.code, None, now())
+        .map_err(two_factor_error)?
+        .ok_or_else(invalid_code)?;
+
+    let recovery_codes = two_factor::new_recovery_codes();
+    TwoFactorRepository::confirm(&db, user_id, step, &recovery_codes)
+        .await
+        .map_err(db_error)?;
+    tracing::info!("Two-factor authentication enabled for {}", user.email);
+
+    let login = if pending_login {
+        let token = auth::issue_token(&config, &user).map_err(token_error)?;
+        Some(LoginResponse { token, user })
+    } else {
+        None
+    };
+    Ok(Json(TwoFactorEnabledResponse { recovery_codes, login }))
+}
+
+// POST /api/v1/auth/2fa/verify - Second login step: TOTP or recovery code
+pub async fn verify(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    State(guard): State<LoginGuard>,
+    tenant: CurrentTenant,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    if let Err(e) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+    }
+    let user_id = payload
+        .challenge_token
+        .as_deref()
+        .and_then(|token| auth::verify_challenge(&config, token))
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid or expired challenge token".to_string()))?;
+    let user = load_user(&db, &tenant, user_id).await?;
+
+    // Second factors are guessable too, so they share the login backoff and lockout
+    let account = format!("2fa:{}", user_id);
+    guard
+        .check(tenant.id(), &account, ip)
+        .await
+        .map_err(|blocked| (StatusCode::TOO_MANY_REQUESTS, blocked.message()))?;
+
+    let accepted = check_totp(&db, user_id, &payload.code).await?
+        || TwoFactorRepository::use_recovery_code(&db, user_id, &payload.code)
+            .await
+            .map_err(db_error)?;
+    if !accepted {
+        tracing::warn!("Failed second factor for {}", user.email);
+        guard.record_failure(tenant.id(), &account, ip).await;
+        return Err(invalid_code());
+    }
+    guard.record_success(tenant.id(), &account).await;
+
+    tracing::info!("User logged in with second factor: {}", user.email);
+    let token = auth::issue_token(&config, &user).map_err(token_error)?;
+    Ok(Json(LoginResponse { token, user }))
+}
+
+// POST /api/v1/auth/2fa/recovery-codes - Replace all recovery codes
+pub async fn regenerate_recovery_codes(
+    State(db): State<DatabaseConnection>,
+    user: AuthUser,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ApiError> {
+    let user_id = user.id()?;
+    if !check_totp(&db, user_id, &payload.code).await? {
+        return Err(invalid_code());
+    }
+
+    let recovery_codes = two_factor::new_recovery_codes();
+    TwoFactorRepository::replace_recovery_codes(&db, user_id, &recovery_codes)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+// POST /api/v1/auth/2fa/disable - Turn two-factor authentication off
+pub async fn disable(
+    State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    user: AuthUser,
+    context: AuditContext,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = user.id()?;
+    if two_factor::required_for(&config, &user.0.role) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Two-factor authentication is required for {} accounts", user.0.role),
+        ));
+    }
+    if !check_totp(&db, user_id, &payload.code).await? {
+        return Err(invalid_code());
+    }
+
+    TwoFactorRepository::disable(&db, user_id, "disable_2fa", &context).await.map_err(db_error)?;
+    tracing::info!("Two-factor authentication disabled for {}", user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DELETE /api/v1/users/{id}/2fa - Admin reset for a user who lost their authenticator
+pub async fn reset(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    context: AuditContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    if user.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can reset two-factor authentication".to_string()));
+    }
+    UserRepository::find_by_id(&db, tenant.id(), id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    TwoFactorRepository::disable(&db, id, "reset_2fa", &context).await.map_err(db_error)?;
+    tracing::info!("Two-factor authentication of user {} reset by {}", id, user.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
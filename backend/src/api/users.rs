@@ -1,51 +1,116 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use sea_orm::DatabaseConnection;
 use validator::Validate;
 
+use crate::api::export::{export_response, ExportFormat, ExportQuery, ExportRow};
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::audit::AuditContext;
+use crate::application::auth::{self, DIRECTORY_ROLES};
+use crate::application::email::{self, Branding};
+use crate::application::login_guard::LoginGuard;
+use crate::application::password::PasswordPolicy;
+use crate::config::Config;
 use crate::dto::user::{CreateUserRequest, UserResponse, UsersListResponse};
+use crate::repositories::email_repository::EmailRepository;
 use crate::repositories::user_repository::UserRepository;
 
-// GET /api/v1/users - List all users
+impl ExportRow for UserResponse {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "tenant_id", "email", "full_name", "role", "is_active", "created_at"];
+}
+
+// GET /api/v1/users - List all users (staff)
+// Also exports as CSV/XLSX/JSON Lines via `Accept` or `?format=`
 pub async fn list_users(
     State(db): State<DatabaseConnection>,
-) -> Result<Json<UsersListResponse>, StatusCode> {
-    match UserRepository::find_all(&db).await {
-        Ok(users) => Ok(Json(users)),
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !DIRECTORY_ROLES.contains(&user.0.role.as_str()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if let Some(format) = ExportFormat::negotiate(&query, &headers)? {
+        return Ok(export_response(format, "users", UserRepository::stream_all(db, tenant.id())).await);
+    }
+
+    match UserRepository::find_all(&db, tenant.id()).await {
+        Ok(users) => Ok(Json::<UsersListResponse>(users).into_response()),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-// GET /api/v1/users/:id - Get user by ID
+// GET /api/v1/users/:id - Get user by ID (staff, or the user themselves)
 pub async fn get_user(
     State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
     Path(id): Path<i32>,
 ) -> Result<Json<UserResponse>, StatusCode> {
-    match UserRepository::find_by_id(&db, id).await {
+    if !auth::can_view_user(&user.0, id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    match UserRepository::find_by_id(&db, tenant.id(), id).await {
         Ok(Some(user)) => Ok(Json(user)),
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-// POST /api/v1/users - Create new user
+// The account exists either way, so a welcome that cannot be queued is
+// only logged
+async fn queue_welcome(db: &DatabaseConnection, config: &Config, tenant: &CurrentTenant, user: &UserResponse) {
+    let queued = match email::welcome(&Branding::from(&tenant.0), &user.email, &user.full_name, &config.app_url) {
+        Ok(welcome) => EmailRepository::enqueue(db, tenant.id(), email::WELCOME, &[welcome])
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = queued {
+        tracing::error!("Failed to queue welcome email for {}: {}", user.email, e);
+    }
+}
+
+// POST /api/v1/users - Create new user and queue their welcome email
 pub async fn create_user(
     State(db): State<DatabaseConnection>,
+    State(config): State<Config>,
+    State(policy): State<PasswordPolicy>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<(StatusCode, Json<UserResponse>), (StatusCode, String)> {
+    if admin.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can create users".to_string()));
+    }
     // Validate input
     if let Err(e) = payload.validate() {
         tracing::error!("Validation error: {:?}", e);
         return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
     }
+    policy
+        .check(&payload.password, &payload.email, &[])
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+    match UserRepository::email_in_trash(&db, tenant.id(), &payload.email).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return Err((StatusCode::CONFLICT, "A deleted user has this email; restore it instead".to_string()));
+        }
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))),
+    }
 
-    match UserRepository::create(&db, payload).await {
+    match UserRepository::create(&db, tenant.id(), payload, &context).await {
         Ok(user) => {
-            tracing::info!("User created: {}", user.email);
+            tracing::info!("User {} created by {}", user.email, admin.0.email);
+            queue_welcome(&db, &config, &tenant, &user).await;
             Ok((StatusCode::CREATED, Json(user)))
         }
         Err(e) => {
@@ -58,11 +123,45 @@ pub async fn create_user(
 // DELETE /api/v1/users/:id - Delete user
 pub async fn delete_user(
     State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    context: AuditContext,
     Path(id): Path<i32>,
 ) -> impl IntoResponse {
-    match UserRepository::delete(&db, id).await {
+    if admin.0.role != "admin" {
+        return StatusCode::FORBIDDEN;
+    }
+    match UserRepository::delete(&db, tenant.id(), id, &context).await {
         Ok(true) => StatusCode::NO_CONTENT,
         Ok(false) => StatusCode::NOT_FOUND,
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
-}
\ No newline at end of file
+}
+
+// DELETE /api/v1/users/:id/lockout - Admin unlock after too many failed logins
+pub async fn unlock_user(
+    State(db): State<DatabaseConnection>,
+    State(guard): State<LoginGuard>,
+    tenant: CurrentTenant,
+    admin: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if admin.0.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Only admins can unlock accounts".to_string()));
+    }
+
+    let user = UserRepository::find_by_id(&db, tenant.id(), id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    for account in [user.email.clone(), format!("2fa:{}", user.id)] {
+        guard.unlock(tenant.id(), &account).await.map_err(|e| {
+            tracing::error!("Failed to unlock {}: {}", user.email, e);
+            (StatusCode::SERVICE_UNAVAILABLE, "Lockout store is unavailable".to_string())
+        })?;
+    }
+
+    tracing::info!("User {} unlocked by {}", user.email, admin.0.email);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,367 @@
+use axum::{
+    body::Bytes,
+    extract::{Query, RawQuery, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{SecondsFormat, Utc};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde_json::Value;
+
+use crate::api::extractors::{AuthUser, CurrentTenant};
+use crate::application::xapi::{self, PreparedStatement, XapiError, LRS_ROLES, VERSION_HEADER, XAPI_VERSION};
+use crate::dto::xapi::{AboutResponse, PutStatementQuery, StatementQuery, StatementResult};
+use crate::repositories::xapi_repository::{StatementFilter, XapiRepository};
+use crate::state::AppState;
+
+const DEFAULT_LIMIT: u64 = 100;
+const MAX_LIMIT: u64 = 500;
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: DbErr) -> ApiError {
+    tracing::error!("Database error in LRS: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
+
+fn xapi_error(e: XapiError) -> ApiError {
+    match e {
+        XapiError::Conflict(_) => (StatusCode::CONFLICT, e.to_string()),
+        XapiError::NotYourStatement => (StatusCode::FORBIDDEN, capitalize(&e.to_string())),
+        XapiError::Database(e) => db_error(e),
+        _ => {
+            tracing::warn!("Rejected xAPI request: {}", e);
+            (StatusCode::BAD_REQUEST, e.to_string())
+        }
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// The only actor a user may record and read statements about, or None when
+// they may cover the whole school
+fn own_actor(user: &AuthUser) -> Option<String> {
+    (!LRS_ROLES.contains(&user.0.role.as_str())).then(|| xapi::user_ifi(&user.0.email))
+}
+
+fn check_actors(user: &AuthUser, statements: &[PreparedStatement]) -> Result<(), XapiError> {
+    match own_actor(user) {
+        Some(own) if statements.iter().any(|statement| statement.actor_ifi != own) => Err(XapiError::NotYourStatement),
+        _ => Ok(()),
+    }
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/statements",
+            get(get_statements).post(post_statements).put(put_statement),
+        )
+        .route_layer(middleware::from_fn(require_version))
+        .route("/about", get(about))
+        .layer(middleware::map_response(add_version_header))
+}
+
+// Every LRS request must declare a 1.0.x xAPI version
+async fn require_version(request: Request, next: Next) -> Result<Response, ApiError> {
+    let version = request
+        .headers()
+        .get(VERSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if !version.starts_with("1.0") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} header must be 1.0.x", VERSION_HEADER),
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+async fn add_version_header(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(VERSION_HEADER, HeaderValue::from_static(XAPI_VERSION));
+    response
+}
+
+// GET /api/v1/xapi/about - LRS information
+async fn about() -> Json<AboutResponse> {
+    Json(AboutResponse {
+        version: vec![XAPI_VERSION.to_string()],
+    })
+}
+
+// Read a statement request body: plain JSON or multipart/mixed with attachments
+fn parse_body(
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(Value, Vec<xapi::AttachmentPart>), XapiError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/json");
+
+    if content_type.starts_with("multipart/mixed") {
+        let boundary = content_type
+            .split(';')
+            .filter_map(|param| param.trim().strip_prefix("boundary="))
+            .next()
+            .map(|boundary| boundary.trim_matches('"'))
+            .ok_or_else(|| XapiError::Invalid("multipart request without boundary".to_string()))?;
+        return xapi::parse_multipart(boundary, body);
+    }
+
+    let statement = serde_json::from_slice(body)
+        .map_err(|e| XapiError::Invalid(format!("body is not valid JSON: {}", e)))?;
+    Ok((statement, Vec::new()))
+}
+
+// POST /api/v1/xapi/statements - Store one statement or a list of statements
+async fn post_statements(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let (body, attachments) = parse_body(&headers, &body).map_err(xapi_error)?;
+
+    let statements = match body {
+        Value::Array(statements) => statements,
+        statement => vec![statement],
+    };
+    let prepared = statements
+        .into_iter()
+        .map(|statement| xapi::prepare_statement(statement, None))
+        .collect::<Result<Vec<PreparedStatement>, _>>()
+        .map_err(xapi_error)?;
+    check_actors(&user, &prepared).map_err(xapi_error)?;
+    for statement in &prepared {
+        xapi::check_attachments(statement, &attachments).map_err(xapi_error)?;
+    }
+
+    let ids = XapiRepository::store(&db, tenant.id(), prepared, attachments, own_actor(&user).as_deref())
+        .await
+        .map_err(xapi_error)?;
+    tracing::info!("Stored {} xAPI statement(s) from {}", ids.len(), user.0.email);
+    Ok(Json(ids))
+}
+
+// PUT /api/v1/xapi/statements?statementId= - Store a statement under a given id
+async fn put_statement(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<PutStatementQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    let (body, attachments) = parse_body(&headers, &body).map_err(xapi_error)?;
+
+    let prepared = xapi::prepare_statement(body, Some(&query.statement_id)).map_err(xapi_error)?;
+    let prepared = vec![prepared];
+    check_actors(&user, &prepared).map_err(xapi_error)?;
+    xapi::check_attachments(&prepared[0], &attachments).map_err(xapi_error)?;
+
+    XapiRepository::store(&db, tenant.id(), prepared, attachments, own_actor(&user).as_deref())
+        .await
+        .map_err(xapi_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/v1/xapi/statements - Fetch a statement by id or query statements
+async fn get_statements(
+    State(db): State<DatabaseConnection>,
+    tenant: CurrentTenant,
+    user: AuthUser,
+    Query(query): Query<StatementQuery>,
+    RawQuery(raw_query): RawQuery,
+) -> Result<Response, ApiError> {
+    let consistent_through = XapiRepository::consistent_through(&db, tenant.id())
+        .await
+        .map_err(db_error)?
+        .unwrap_or_else(|| Utc::now().naive_utc())
+        .and_utc()
+        .to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let single = match (&query.statement_id, &query.voided_statement_id) {
+        (Some(_), Some(_)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "statementId and voidedStatementId are mutually exclusive".to_string(),
+            ));
+        }
+        (Some(id), None) => Some((id, false)),
+        (None, Some(id)) => Some((id, true)),
+        (None, None) => None,
+    };
+
+    let (body, statements) = if let Some((id, voided)) = single {
+        let statement = XapiRepository::find_by_id(&db, tenant.id(), id, voided)
+            .await
+            .map_err(db_error)?
+            .filter(|statement| match own_actor(&user) {
+                Some(own) => statement.get("actor").and_then(|actor| xapi::agent_ifi(actor).ok()) == Some(own),
+                None => true,
+            })
+            .ok_or((StatusCode::NOT_FOUND, "Statement not found".to_string()))?;
+        (statement.clone(), vec![statement])
+    } else {
+        let filter = statement_filter(&query, own_actor(&user)).map_err(xapi_error)?;
+        let limit = match query.limit {
+            Some(0) | None => DEFAULT_LIMIT,
+            Some(limit) => limit.min(MAX_LIMIT),
+        };
+        let offset = query.offset.unwrap_or(0);
+
+        let (statements, more) = XapiRepository::find(&db, tenant.id(), &filter, limit, offset)
+            .await
+            .map_err(db_error)?;
+        let more = if more {
+            more_link(raw_query.as_deref(), offset + limit)
+        } else {
+            String::new()
+        };
+        let result = StatementResult {
+            statements: statements.clone(),
+            more,
+        };
+        (serde_json::to_value(result).unwrap_or_default(), statements)
+    };
+
+    let mut response = if query.attachments.unwrap_or(false) {
+        let hashes = statements
+            .iter()
+            .filter_map(|statement| statement.get("attachments").and_then(Value::as_array))
+            .flatten()
+            .filter_map(|attachment| attachment.get("sha2").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+        let attachments = XapiRepository::find_attachments(&db, tenant.id(), hashes)
+            .await
+            .map_err(db_error)?;
+
+        let boundary = xapi::random_boundary();
+        let json = serde_json::to_vec(&body).unwrap_or_default();
+        let mut response = xapi::build_multipart(&boundary, &json, &attachments).into_response();
+        if let Ok(value) = HeaderValue::from_str(&format!("multipart/mixed; boundary={}", boundary)) {
+            response.headers_mut().insert(header::CONTENT_TYPE, value);
+        }
+        response
+    } else {
+        Json(body).into_response()
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&consistent_through) {
+        response
+            .headers_mut()
+            .insert("X-Experience-API-Consistent-Through", value);
+    }
+    Ok(response)
+}
+
+// Resolve the query's filters; `own_actor` narrows them to that actor's statements
+fn statement_filter(query: &StatementQuery, own_actor: Option<String>) -> Result<StatementFilter, XapiError> {
+    let actor_ifi = match &query.agent {
+        Some(agent) => {
+            let agent: Value = serde_json::from_str(agent)
+                .map_err(|_| XapiError::Invalid("agent must be a JSON object".to_string()))?;
+            Some(xapi::agent_ifi(&agent)?)
+        }
+        None => None,
+    };
+    let actor_ifi = match (actor_ifi, own_actor) {
+        (Some(actor), Some(own)) if actor != own => return Err(XapiError::NotYourStatement),
+        (actor, own) => own.or(actor),
+    };
+
+    Ok(StatementFilter {
+        actor_ifi,
+        verb_id: query.verb.clone(),
+        activity_id: query.activity.clone(),
+        registration: query.registration.clone(),
+        since: query.since.as_deref().map(xapi::parse_timestamp).transpose()?,
+        until: query.until.as_deref().map(xapi::parse_timestamp).transpose()?,
+        ascending: query.ascending.unwrap_or(false),
+    })
+}
+
+// Relative IRL of the next page: same query, moved offset
+fn more_link(raw_query: Option<&str>, offset: u64) -> String {
+    let mut params: Vec<String> = raw_query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("offset="))
+        .map(str::to_string)
+        .collect();
+    params.push(format!("offset={}", offset));
+    format!("/api/v1/xapi/statements?{}", params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(agent: Option<&str>) -> StatementQuery {
+        StatementQuery {
+            statement_id: None,
+            voided_statement_id: None,
+            agent: agent.map(str::to_string),
+            verb: Some("http://adlnet.gov/expapi/verbs/completed".to_string()),
+            activity: None,
+            registration: None,
+            since: Some("2026-09-01T08:00:00+02:00".to_string()),
+            until: None,
+            limit: None,
+            ascending: Some(true),
+            attachments: None,
+            offset: None,
+        }
+    }
+
+    #[test]
+    fn filters_resolve_to_column_values() {
+        let filter = statement_filter(&query(Some(r#"{"mbox":"mailto:Ana@School.example"}"#)), None).unwrap();
+        assert_eq!(filter.actor_ifi.as_deref(), Some("mbox:mailto:ana@school.example"));
+        assert_eq!(filter.verb_id.as_deref(), Some("http://adlnet.gov/expapi/verbs/completed"));
+        assert_eq!(filter.since.unwrap().to_string(), "2026-09-01 06:00:00");
+        assert!(filter.ascending);
+
+        assert!(statement_filter(&query(Some("ana")), None).is_err());
+        let mut bad_since = query(None);
+        bad_since.since = Some("yesterday".to_string());
+        assert!(statement_filter(&bad_since, None).is_err());
+    }
+
+    #[test]
+    fn learners_only_query_their_own_statements() {
+        let own = xapi::user_ifi("ana@school.example");
+        let filter = statement_filter(&query(None), Some(own.clone())).unwrap();
+        assert_eq!(filter.actor_ifi, Some(own.clone()));
+        assert!(statement_filter(&query(Some(r#"{"mbox":"mailto:ana@school.example"}"#)), Some(own.clone())).is_ok());
+        assert!(matches!(
+            statement_filter(&query(Some(r#"{"mbox":"mailto:ben@school.example"}"#)), Some(own)),
+            Err(XapiError::NotYourStatement)
+        ));
+    }
+
+    #[test]
+    fn more_link_keeps_the_query_and_moves_the_offset() {
+        assert_eq!(more_link(None, 100), "/api/v1/xapi/statements?offset=100");
+        assert_eq!(
+            more_link(Some("verb=x&offset=100&limit=100"), 200),
+            "/api/v1/xapi/statements?verb=x&limit=100&offset=200"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+
+use crate::entities::admission_applications;
+use crate::infrastructure::mailer::Email;
+
+// Who may run admissions: stages, decisions, letters and enrolment
+pub const ADMISSIONS_ROLES: &[&str] = &["admin", "admissions"];
+
+// Who may read applications and score them
+pub const REVIEWER_ROLES: &[&str] = &["admin", "admissions", "teacher"];
+
+// The pipeline a school starts with until it sets its own
+pub const DEFAULT_STAGES: &[&str] = &[
+    "Inquiry",
+    "Application",
+    "Documents",
+    "Interview",
+    "Offer",
+    "Accepted",
+    "Enrolled",
+];
+
+pub const PENDING: &str = "pending";
+pub const OFFERED: &str = "offered";
+pub const WAITLISTED: &str = "waitlisted";
+pub const REJECTED: &str = "rejected";
+// The family took up the offer
+pub const ACCEPTED: &str = "accepted";
+// The family turned the offer down
+pub const DECLINED: &str = "declined";
+
+pub const DECISIONS: &[&str] = &[OFFERED, WAITLISTED, REJECTED, ACCEPTED, DECLINED];
+
+// What families may upload: scans and photos of certificates and reports
+pub const DOCUMENT_TYPES: &[&str] = &["application/pdf", "image/jpeg", "image/png"];
+pub const MAX_DOCUMENT_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdmissionsError {
+    #[error("application not found")]
+    ApplicationNotFound,
+    #[error("stage not found")]
+    StageNotFound,
+    #[error("document not found")]
+    DocumentNotFound,
+    #[error("a pipeline needs at least one stage")]
+    NoStages,
+    #[error("stage {0} appears twice")]
+    DuplicateStage(String),
+    #[error("stage {0} still has applications")]
+    StageInUse(String),
+    #[error("decision must be one of: offered, waitlisted, rejected, accepted, declined")]
+    InvalidDecision,
+    #[error("application {0} is {1} and cannot be marked {2}")]
+    DecisionNotAllowed(i32, String, String),
+    #[error("application {0} has already been enrolled")]
+    AlreadyEnrolled(i32),
+    #[error("only accepted applications can be enrolled")]
+    NotAccepted,
+    #[error("an email address is needed for the student's account")]
+    EmailRequired,
+    #[error("a user with email {0} already exists")]
+    EmailTaken(String),
+    #[error("documents can only be added while the application is pending")]
+    UploadsClosed,
+    #[error("documents must be PDF, JPEG or PNG")]
+    UnsupportedDocument,
+    #[error("document is empty")]
+    EmptyDocument,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+// Which decisions may follow which. Offers, waitlisting and rejections are
+// made on pending or waitlisted applications; the family's answer is
+// recorded on an offer, which may also be withdrawn.
+pub fn check_decision(id: i32, current: &str, decision: &str, enrolled: bool) -> Result<(), AdmissionsError> {
+    if !DECISIONS.contains(&decision) {
+        return Err(AdmissionsError::InvalidDecision);
+    }
+    if enrolled {
+        return Err(AdmissionsError::AlreadyEnrolled(id));
+    }
+    let allowed = match decision {
+        OFFERED | WAITLISTED => matches!(current, PENDING | WAITLISTED),
+        REJECTED => matches!(current, PENDING | WAITLISTED | OFFERED),
+        _ => current == OFFERED,
+    };
+    if !allowed {
+        return Err(AdmissionsError::DecisionNotAllowed(id, current.to_string(), decision.to_string()));
+    }
+    Ok(())
+}
+
+// A pipeline's stage names must be present and distinct
+pub fn check_stage_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<(), AdmissionsError> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name.trim().to_lowercase()) {
+            return Err(AdmissionsError::DuplicateStage(name.to_string()));
+        }
+    }
+    if seen.is_empty() {
+        return Err(AdmissionsError::NoStages);
+    }
+    Ok(())
+}
+
+pub fn check_document(content_type: &str, length: usize) -> Result<(), AdmissionsError> {
+    if !DOCUMENT_TYPES.contains(&content_type) {
+        return Err(AdmissionsError::UnsupportedDocument);
+    }
+    if length == 0 {
+        return Err(AdmissionsError::EmptyDocument);
+    }
+    Ok(())
+}
+
+// A filename safe to store and to hand back in Content-Disposition: path
+// parts dropped and anything but letters, digits, dots, dashes and
+// underscores replaced
+pub fn safe_filename(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+    if cleaned.is_empty() {
+        "document".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+// A document as received, before it is stored
+#[derive(Debug)]
+pub struct DocumentUpload {
+    pub kind: String,
+    pub filename: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+// Mean reviewer score to one decimal, None before anyone has reviewed
+pub fn average_score(scores: &[i32]) -> Option<f64> {
+    if scores.is_empty() {
+        return None;
+    }
+    let mean = scores.iter().map(|score| *score as f64).sum::<f64>() / scores.len() as f64;
+    Some((mean * 10.0).round() / 10.0)
+}
+
+// The letter sent to the family when a decision is made. `message` is an
+// optional paragraph from the admissions office, added before the sign-off.
+pub fn decision_letter(
+    school: &str,
+    application: &admission_applications::Model,
+    decision: &str,
+    message: Option<&str>,
+) -> Email {
+    let term = application
+        .entry_term
+        .as_deref()
+        .map(|term| format!(" for {}", term))
+        .unwrap_or_default();
+    let (subject, paragraph) = match decision {
+        OFFERED => (
+            format!("Offer of a place at {}", school),
+            format!(
+                "We are delighted to offer {} a place in {}{}. Please let us know whether you accept the offer.",
+                application.full_name, application.grade, term
+            ),
+        ),
+        WAITLISTED => (
+            format!("Your application to {}", school),
+            format!(
+                "We are not able to offer {} a place in {}{} at this time, but we have placed the application \
+                 on our waiting list and will contact you if a place becomes available.",
+                application.full_name, application.grade, term
+            ),
+        ),
+        REJECTED => (
+            format!("Your application to {}", school),
+            format!(
+                "Thank you for your interest in {}. We are sorry that we are unable to offer {} a place in {}{}.",
+                school, application.full_name, application.grade, term
+            ),
+        ),
+        ACCEPTED => (
+            format!("Welcome to {}", school),
+            format!(
+                "Thank you for accepting our offer. We look forward to welcoming {} to {}{} and will be in touch \
+                 with everything you need before the first day.",
+                application.full_name, application.grade, term
+            ),
+        ),
+        _ => (
+            format!("Your application to {}", school),
+            format!(
+                "We have recorded that you have declined the offer of a place for {}. We wish {} every success.",
+                application.full_name, application.full_name
+            ),
+        ),
+    };
+
+    let mut body = format!("Dear {},\n\n{}\n\n", application.guardian_name, paragraph);
+    if let Some(message) = message.map(str::trim).filter(|message| !message.is_empty()) {
+        body.push_str(message);
+        body.push_str("\n\n");
+    }
+    body.push_str(&format!("Yours sincerely,\nAdmissions Office\n{}\n", school));
+
+    Email {
+        to: application.guardian_email.clone(),
+        subject,
+        body,
+        html: None,
+        from_name: Some(school.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn application() -> admission_applications::Model {
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 10, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        admission_applications::Model {
+            id: 7,
+            tenant_id: 1,
+            stage_id: 1,
+            full_name: "Amina Yusuf".to_string(),
+            date_of_birth: None,
+            grade: "Grade 7".to_string(),
+            entry_term: Some("Term 1 2027".to_string()),
+            student_email: None,
+            guardian_name: "Hassan Yusuf".to_string(),
+            guardian_email: "hassan@example.com".to_string(),
+            guardian_phone: None,
+            notes: None,
+            decision: PENDING.to_string(),
+            decided_at: None,
+            upload_token_hash: None,
+            student_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn decisions_follow_the_offer() {
+        assert!(check_decision(1, PENDING, OFFERED, false).is_ok());
+        assert!(check_decision(1, WAITLISTED, OFFERED, false).is_ok());
+        assert!(check_decision(1, OFFERED, ACCEPTED, false).is_ok());
+        assert!(check_decision(1, OFFERED, REJECTED, false).is_ok());
+        assert!(matches!(
+            check_decision(1, PENDING, ACCEPTED, false),
+            Err(AdmissionsError::DecisionNotAllowed(1, _, _))
+        ));
+        assert!(matches!(
+            check_decision(1, REJECTED, OFFERED, false),
+            Err(AdmissionsError::DecisionNotAllowed(1, _, _))
+        ));
+        assert!(matches!(
+            check_decision(1, PENDING, "maybe", false),
+            Err(AdmissionsError::InvalidDecision)
+        ));
+        assert!(matches!(
+            check_decision(1, ACCEPTED, DECLINED, true),
+            Err(AdmissionsError::AlreadyEnrolled(1))
+        ));
+    }
+
+    #[test]
+    fn letters_are_addressed_to_the_guardian() {
+        let letter = decision_letter("Hillside School", &application(), OFFERED, Some("  Term starts 6 January.  "));
+        assert_eq!(letter.to, "hassan@example.com");
+        assert_eq!(letter.subject, "Offer of a place at Hillside School");
+        assert!(letter.body.starts_with("Dear Hassan Yusuf,\n\n"));
+        assert!(letter.body.contains("offer Amina Yusuf a place in Grade 7 for Term 1 2027."));
+        assert!(letter.body.contains("\n\nTerm starts 6 January.\n\nYours sincerely,"));
+
+        let letter = decision_letter("Hillside School", &application(), REJECTED, Some(" "));
+        assert_eq!(letter.subject, "Your application to Hillside School");
+        assert!(!letter.body.contains("\n\n\n"));
+    }
+
+    #[test]
+    fn uploads_are_checked_and_named_safely() {
+        assert_eq!(safe_filename("C:\\scans\\birth cert.pdf"), "birth_cert.pdf");
+        assert_eq!(safe_filename("../../etc/passwd"), "passwd");
+        assert_eq!(safe_filename("\"report\".pdf"), "_report_.pdf");
+        assert_eq!(safe_filename(".."), "document");
+        assert!(check_document("application/pdf", 10).is_ok());
+        assert!(matches!(check_document("text/html", 10), Err(AdmissionsError::UnsupportedDocument)));
+        assert!(matches!(check_document("image/png", 0), Err(AdmissionsError::EmptyDocument)));
+
+        assert!(check_stage_names(DEFAULT_STAGES.iter().copied()).is_ok());
+        assert!(matches!(
+            check_stage_names(["Inquiry", "inquiry "]),
+            Err(AdmissionsError::DuplicateStage(_))
+        ));
+        assert!(matches!(check_stage_names([]), Err(AdmissionsError::NoStages)));
+        assert_eq!(average_score(&[7, 8, 8]), Some(7.7));
+        assert_eq!(average_score(&[]), None);
+    }
+}
@@ -0,0 +1,228 @@
+use chrono::NaiveDateTime;
+
+use crate::entities::announcements;
+
+// Who may post notices and see who has read them
+pub const ANNOUNCEMENT_ROLES: &[&str] = &["admin", "principal"];
+
+// Everyone else is staff, as far as a staff-only notice is concerned
+const NON_STAFF_ROLES: &[&str] = &["student", "guardian"];
+
+pub const SCHOOL: &str = "school";
+pub const GRADE: &str = "grade";
+pub const SECTION: &str = "section";
+pub const GUARDIANS: &str = "guardians";
+pub const STAFF: &str = "staff";
+
+pub const ATTACHMENT_TYPES: &[&str] = &["application/pdf", "image/jpeg", "image/png"];
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnnouncementError {
+    #[error("announcement not found")]
+    NotFound,
+    #[error("attachment not found")]
+    AttachmentNotFound,
+    #[error("user not found")]
+    UserNotFound,
+    #[error("audience must be one of: school, grade, section, guardians, staff")]
+    InvalidAudience,
+    #[error("a grade is needed for a grade or section notice")]
+    GradeRequired,
+    #[error("a section is needed for a section notice")]
+    SectionRequired,
+    #[error("grade and section only apply to grade and section notices")]
+    UnexpectedClass,
+    #[error("a notice must expire after it is published")]
+    ExpiresBeforePublish,
+    #[error("attachments must be PDF, JPEG or PNG")]
+    UnsupportedAttachment,
+    #[error("attachment is empty")]
+    EmptyAttachment,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+// The person looking at the notice board: their role, and the class they
+// are in if they have one
+#[derive(Debug, Clone)]
+pub struct Reader {
+    pub user_id: i32,
+    pub role: String,
+    pub grade: Option<String>,
+    pub section: Option<String>,
+}
+
+impl Reader {
+    pub fn manages_announcements(&self) -> bool {
+        ANNOUNCEMENT_ROLES.contains(&self.role.as_str())
+    }
+}
+
+// An audience must name the class it targets, and only grade and section
+// notices may name one
+pub fn check_audience(
+    audience: &str,
+    grade: Option<&str>,
+    section: Option<&str>,
+) -> Result<(), AnnouncementError> {
+    let grade = grade.filter(|grade| !grade.trim().is_empty());
+    let section = section.filter(|section| !section.trim().is_empty());
+    match audience {
+        GRADE if section.is_some() => Err(AnnouncementError::UnexpectedClass),
+        GRADE | SECTION if grade.is_none() => Err(AnnouncementError::GradeRequired),
+        SECTION if section.is_none() => Err(AnnouncementError::SectionRequired),
+        GRADE | SECTION => Ok(()),
+        SCHOOL | GUARDIANS | STAFF if grade.is_some() || section.is_some() => Err(AnnouncementError::UnexpectedClass),
+        SCHOOL | GUARDIANS | STAFF => Ok(()),
+        _ => Err(AnnouncementError::InvalidAudience),
+    }
+}
+
+pub fn check_schedule(publish_at: NaiveDateTime, expires_at: Option<NaiveDateTime>) -> Result<(), AnnouncementError> {
+    match expires_at {
+        Some(expires_at) if expires_at <= publish_at => Err(AnnouncementError::ExpiresBeforePublish),
+        _ => Ok(()),
+    }
+}
+
+pub fn check_attachment(content_type: &str, length: usize) -> Result<(), AnnouncementError> {
+    if !ATTACHMENT_TYPES.contains(&content_type) {
+        return Err(AnnouncementError::UnsupportedAttachment);
+    }
+    if length == 0 {
+        return Err(AnnouncementError::EmptyAttachment);
+    }
+    Ok(())
+}
+
+// An attachment as received, before it is stored
+#[derive(Debug)]
+pub struct AttachmentUpload {
+    pub filename: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+// Published and not yet expired
+pub fn is_live(announcement: &announcements::Model, now: NaiveDateTime) -> bool {
+    announcement.publish_at <= now && announcement.expires_at.is_none_or(|expires_at| expires_at > now)
+}
+
+// Whether a notice is addressed to the reader. Grades and sections compare
+// without regard to case or surrounding spaces.
+pub fn reaches(announcement: &announcements::Model, reader: &Reader) -> bool {
+    let same = |a: Option<&str>, b: Option<&str>| match (a, b) {
+        (Some(a), Some(b)) => a.trim().eq_ignore_ascii_case(b.trim()),
+        _ => false,
+    };
+    match announcement.audience.as_str() {
+        SCHOOL => true,
+        GRADE => same(announcement.grade.as_deref(), reader.grade.as_deref()),
+        SECTION => {
+            same(announcement.grade.as_deref(), reader.grade.as_deref())
+                && same(announcement.section.as_deref(), reader.section.as_deref())
+        }
+        GUARDIANS => reader.role == "guardian",
+        STAFF => !NON_STAFF_ROLES.contains(&reader.role.as_str()),
+        _ => false,
+    }
+}
+
+// Whether the reader may open a notice: its audience once it is live, and
+// those who manage notices at any time
+pub fn visible_to(announcement: &announcements::Model, reader: &Reader, now: NaiveDateTime) -> bool {
+    reader.manages_announcements() || (is_live(announcement, now) && reaches(announcement, reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn notice(audience: &str, grade: Option<&str>, section: Option<&str>) -> announcements::Model {
+        announcements::Model {
+            id: 1,
+            tenant_id: 1,
+            title: "Sports day".to_string(),
+            body: "Bring water.".to_string(),
+            audience: audience.to_string(),
+            grade: grade.map(str::to_string),
+            section: section.map(str::to_string),
+            is_pinned: false,
+            publish_at: at(10, 8),
+            expires_at: Some(at(20, 8)),
+            created_by: None,
+            created_at: at(9, 8),
+            updated_at: at(9, 8),
+            notified_at: None,
+        }
+    }
+
+    fn reader(role: &str, grade: Option<&str>, section: Option<&str>) -> Reader {
+        Reader {
+            user_id: 5,
+            role: role.to_string(),
+            grade: grade.map(str::to_string),
+            section: section.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn audiences_name_their_class() {
+        assert!(check_audience(SCHOOL, None, None).is_ok());
+        assert!(check_audience(GRADE, Some("Grade 7"), None).is_ok());
+        assert!(check_audience(SECTION, Some("Grade 7"), Some("B")).is_ok());
+        assert!(matches!(check_audience(GRADE, None, None), Err(AnnouncementError::GradeRequired)));
+        assert!(matches!(check_audience(SECTION, Some("Grade 7"), Some(" ")), Err(AnnouncementError::SectionRequired)));
+        assert!(matches!(check_audience(GRADE, Some("Grade 7"), Some("B")), Err(AnnouncementError::UnexpectedClass)));
+        assert!(matches!(check_audience(STAFF, Some("Grade 7"), None), Err(AnnouncementError::UnexpectedClass)));
+        assert!(matches!(check_audience("everyone", None, None), Err(AnnouncementError::InvalidAudience)));
+        assert!(matches!(check_schedule(at(10, 8), Some(at(10, 8))), Err(AnnouncementError::ExpiresBeforePublish)));
+        assert!(check_schedule(at(10, 8), None).is_ok());
+    }
+
+    #[test]
+    fn notices_reach_their_audience() {
+        let student = reader("student", Some("Grade 7"), Some("B"));
+        let guardian = reader("guardian", None, None);
+        let teacher = reader("teacher", Some("grade 7 "), None);
+
+        assert!(reaches(&notice(SCHOOL, None, None), &guardian));
+        assert!(reaches(&notice(GRADE, Some("Grade 7"), None), &student));
+        assert!(reaches(&notice(GRADE, Some("Grade 7"), None), &teacher));
+        assert!(!reaches(&notice(GRADE, Some("Grade 8"), None), &student));
+        assert!(reaches(&notice(SECTION, Some("Grade 7"), Some("b")), &student));
+        assert!(!reaches(&notice(SECTION, Some("Grade 7"), Some("A")), &student));
+        assert!(!reaches(&notice(SECTION, Some("Grade 7"), Some("B")), &teacher));
+        assert!(reaches(&notice(GUARDIANS, None, None), &guardian));
+        assert!(!reaches(&notice(GUARDIANS, None, None), &student));
+        assert!(reaches(&notice(STAFF, None, None), &teacher));
+        assert!(!reaches(&notice(STAFF, None, None), &student));
+        assert!(!reaches(&notice(STAFF, None, None), &guardian));
+    }
+
+    #[test]
+    fn notices_show_between_publish_and_expiry() {
+        let student = reader("student", None, None);
+        let principal = reader("principal", None, None);
+        let notice = notice(SCHOOL, None, None);
+
+        assert!(!is_live(&notice, at(10, 7)));
+        assert!(is_live(&notice, at(10, 8)));
+        assert!(!is_live(&notice, at(20, 8)));
+        assert!(!visible_to(&notice, &student, at(9, 12)));
+        assert!(visible_to(&notice, &principal, at(9, 12)));
+        assert!(visible_to(&notice, &student, at(15, 12)));
+
+        assert!(check_attachment("application/pdf", 3).is_ok());
+        assert!(matches!(check_attachment("text/html", 3), Err(AnnouncementError::UnsupportedAttachment)));
+        assert!(matches!(check_attachment("image/png", 0), Err(AnnouncementError::EmptyAttachment)));
+    }
+}
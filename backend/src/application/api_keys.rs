@@ -0,0 +1,124 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::http::Method;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+// Every key starts with this, so leaked keys are easy to spot (and to scan for)
+const KEY_MARKER: &str = "rsk";
+const PREFIX_LENGTH: usize = 8;
+const PREFIX_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+pub const AUTH_SOURCE_SERVICE: &str = "service";
+
+// A freshly generated key; `key` is shown to its owner exactly once
+pub struct NewApiKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+// Key format: rsk_<prefix>_<secret>. The prefix is stored in clear to find
+// the key and to let people tell their keys apart; only a hash of the whole
+// key is kept.
+pub fn generate() -> NewApiKey {
+    let mut bytes = [0u8; PREFIX_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+    let prefix: String = bytes
+        .iter()
+        .map(|byte| PREFIX_ALPHABET[*byte as usize % PREFIX_ALPHABET.len()] as char)
+        .collect();
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let key = format!("{}_{}_{}", KEY_MARKER, prefix, URL_SAFE_NO_PAD.encode(secret));
+
+    NewApiKey {
+        hash: hash(&key),
+        key,
+        prefix,
+    }
+}
+
+// Keys are long and random, so a fast hash is as good as a slow one here
+pub fn hash(key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(key.as_bytes()))
+}
+
+// Prefix of something that looks like an rsEdu API key
+pub fn prefix_of(key: &str) -> Option<&str> {
+    let rest = key.strip_prefix(KEY_MARKER)?.strip_prefix('_')?;
+    let (prefix, secret) = rest.split_once('_')?;
+    (prefix.len() == PREFIX_LENGTH && !secret.is_empty()).then_some(prefix)
+}
+
+pub fn hashes_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Scopes look like "users:read", "xapi:write", "lti:*" or "*"
+pub fn valid_scope(scope: &str) -> bool {
+    if scope == "*" {
+        return true;
+    }
+    match scope.split_once(':') {
+        Some((group, access)) => {
+            !group.is_empty()
+                && group.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+                && matches!(access, "read" | "write" | "*")
+        }
+        None => false,
+    }
+}
+
+// Scope a request needs: the first path segment under /api/v1, and read for
+// safe methods or write for everything else
+pub fn required_scope(method: &Method, path: &str) -> String {
+    let path = path.strip_prefix("/api/v1").unwrap_or(path);
+    let group = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+    let access = if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        "read"
+    } else {
+        "write"
+    };
+    format!("{}:{}", group, access)
+}
+
+pub fn allows(scopes: &[String], required: &str) -> bool {
+    let group = required.split(':').next().unwrap_or_default();
+    scopes.iter().any(|scope| {
+        scope == "*" || scope == required || *scope == format!("{}:*", group)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_carry_their_prefix_and_hash() {
+        let key = generate();
+        assert_eq!(prefix_of(&key.key), Some(key.prefix.as_str()));
+        assert!(hashes_match(&hash(&key.key), &key.hash));
+        assert!(!hashes_match(&hash(&format!("{}x", key.key)), &key.hash));
+
+        // JWTs and other bearer tokens are not mistaken for keys
+        assert_eq!(prefix_of("eyJhbGciOiJIUzI1NiJ9.e30.sig"), None);
+        assert_eq!(prefix_of("rsk_short_secret"), None);
+    }
+
+    #[test]
+    fn scopes_cover_route_groups_by_method() {
+        let scopes = vec!["users:read".to_string(), "xapi:*".to_string()];
+
+        assert!(allows(&scopes, &required_scope(&Method::GET, "/api/v1/users/7")));
+        assert!(!allows(&scopes, &required_scope(&Method::DELETE, "/users/7")));
+        assert!(allows(&scopes, &required_scope(&Method::POST, "/xapi/statements")));
+        assert!(!allows(&scopes, &required_scope(&Method::GET, "/api-keys")));
+        assert!(allows(&["*".to_string()], "anything:write"));
+
+        assert!(valid_scope("api-keys:write"));
+        assert!(!valid_scope("users"));
+        assert!(!valid_scope("Users:read"));
+        assert!(!valid_scope("users:delete"));
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, SubsecRound, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::application::tenancy::DEFAULT_TENANT_ID;
+use crate::entities::audit_log;
+
+// prev_hash of the first entry in each school's chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Who made a change and from where; handlers extract it from the request,
+// changes made by rsEdu itself have no actor
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    // School the change was made in; None for changes outside any one school
+    pub tenant_id: Option<i32>,
+    pub actor_id: Option<i32>,
+    pub actor_email: Option<String>,
+    // Admin behind an impersonated token, if any
+    pub impersonator_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub request_id: Option<String>,
+}
+
+// Timestamps are hashed as stored, and Postgres keeps microseconds
+pub fn now() -> NaiveDateTime {
+    Utc::now().naive_utc().trunc_subsecs(6)
+}
+
+// Serialize a record for the before/after columns
+pub fn snapshot<T: Serialize>(record: &T) -> Option<Value> {
+    serde_json::to_value(record).ok()
+}
+
+// Reduce two snapshots to the fields that changed. Creates and deletes keep
+// their whole snapshot.
+pub fn changes(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let keys: Vec<&String> = before
+                .keys()
+                .chain(after.keys())
+                .filter(|key| before.get(*key) != after.get(*key))
+                .collect();
+            let pick = |side: &Map<String, Value>| {
+                let mut picked = Map::new();
+                for key in &keys {
+                    picked.insert((*key).clone(), side.get(*key).cloned().unwrap_or(Value::Null));
+                }
+                Value::Object(picked)
+            };
+            (Some(pick(&before)), Some(pick(&after)))
+        }
+        other => other,
+    }
+}
+
+// Digest of an entry's before/after snapshots, which the entry hash covers
+// in their place so the snapshots can be redacted
+pub fn snapshot_hash(before: &Option<Value>, after: &Option<Value>) -> String {
+    let snapshots = serde_json::json!({ "before": before, "after": after });
+    hex(&Sha256::digest(canonical(&snapshots).as_bytes()))
+}
+
+// Hash of an entry, covering its content and the previous entry's hash, so
+// editing, removing or reordering entries breaks every hash after it
+pub fn entry_hash(entry: &audit_log::Model) -> String {
+    let mut content = serde_json::json!({
+        "actor_id": entry.actor_id,
+        "actor_email": entry.actor_email,
+        "impersonator_id": entry.impersonator_id,
+        "action": entry.action,
+        "entity": entry.entity,
+        "entity_id": entry.entity_id,
+        "ip_address": entry.ip_address,
+        "request_id": entry.request_id,
+        "created_at": entry.created_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+    });
+    // Entries from before redaction was possible hashed the snapshots themselves
+    match &entry.snapshot_hash {
+        Some(snapshot_hash) => content["snapshot_hash"] = snapshot_hash.as_str().into(),
+        None => {
+            content["before"] = entry.before.clone().into();
+            content["after"] = entry.after.clone().into();
+        }
+    }
+    // Entries from before tenancy have no school and were hashed without one
+    if let Some(tenant_id) = entry.tenant_id {
+        content["tenant_id"] = tenant_id.into();
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(entry.prev_hash.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(canonical(&content).as_bytes());
+    hex(&hasher.finalize())
+}
+
+// JSON with object keys sorted, since jsonb does not keep key order
+fn canonical(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                let mut out = Map::new();
+                for key in keys {
+                    out.insert(key.clone(), sorted(&map[key]));
+                }
+                Value::Object(out)
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Whether an entry's content matches its hash. Redacted snapshots are taken
+// on trust from their digest; an old entry that hashed its snapshots
+// directly can only be checked for its place in the chain once redacted.
+fn verifies(entry: &audit_log::Model) -> bool {
+    let redacted = entry.redacted_at.is_some();
+    if redacted && (entry.before.is_some() || entry.after.is_some()) {
+        return false;
+    }
+    match &entry.snapshot_hash {
+        Some(digest) if !redacted && *digest != snapshot_hash(&entry.before, &entry.after) => false,
+        None if redacted => true,
+        _ => entry_hash(entry) == entry.hash,
+    }
+}
+
+// Each school's entries form their own chain, so schools append without
+// waiting on each other. Entries from before tenancy are the default school's.
+pub fn chain_of(tenant_id: Option<i32>) -> i32 {
+    tenant_id.unwrap_or(DEFAULT_TENANT_ID)
+}
+
+// Where each chain ended among the entries checked so far
+#[derive(Debug)]
+pub struct ChainHeads {
+    schools: HashMap<i32, String>,
+    // The entry before in id order, which entries link to from when all
+    // schools shared one chain
+    previous: String,
+}
+
+impl Default for ChainHeads {
+    fn default() -> Self {
+        Self { schools: HashMap::new(), previous: GENESIS_HASH.to_string() }
+    }
+}
+
+// Check entries that follow those in `heads`, in id order: the id of the
+// first entry whose hash or link does not check out
+pub fn first_broken(entries: &[audit_log::Model], heads: &mut ChainHeads) -> Result<(), i32> {
+    for entry in entries {
+        let chain = chain_of(entry.tenant_id);
+        let head = heads.schools.get(&chain).map_or(GENESIS_HASH, String::as_str);
+        let linked = entry.prev_hash == head || entry.prev_hash == heads.previous;
+        if !linked || !verifies(entry) {
+            return Err(entry.id);
+        }
+        heads.schools.insert(chain, entry.hash.clone());
+        heads.previous = entry.hash.clone();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(id: i32, tenant_id: i32, prev_hash: &str) -> audit_log::Model {
+        let after = Some(json!({ "id": 100 + id, "role": "student" }));
+        let mut entry = audit_log::Model {
+            id,
+            actor_id: Some(1),
+            actor_email: Some("admin@school.local".to_string()),
+            impersonator_id: None,
+            action: "create".to_string(),
+            entity: "users".to_string(),
+            entity_id: (100 + id).to_string(),
+            before: None,
+            snapshot_hash: Some(snapshot_hash(&None, &after)),
+            after,
+            ip_address: Some("10.0.0.1".to_string()),
+            request_id: None,
+            created_at: now(),
+            prev_hash: prev_hash.to_string(),
+            hash: String::new(),
+            tenant_id: Some(tenant_id),
+            redacted_at: None,
+        };
+        entry.hash = entry_hash(&entry);
+        entry
+    }
+
+    fn chain(count: i32) -> Vec<audit_log::Model> {
+        let mut entries: Vec<audit_log::Model> = Vec::new();
+        for id in 1..=count {
+            let prev_hash = entries.last().map_or(GENESIS_HASH, |last| last.hash.as_str());
+            entries.push(entry(id, 1, prev_hash));
+        }
+        entries
+    }
+
+    fn check(entries: &[audit_log::Model]) -> Result<(), i32> {
+        first_broken(entries, &mut ChainHeads::default())
+    }
+
+    #[test]
+    fn hash_chain_detects_tampering() {
+        let entries = chain(3);
+        assert_eq!(check(&entries), Ok(()));
+
+        let mut edited = entries.clone();
+        edited[1].after = Some(json!({ "id": 102, "role": "admin" }));
+        assert_eq!(check(&edited), Err(2));
+
+        let mut moved = entries.clone();
+        moved[1].tenant_id = Some(2);
+        assert_eq!(check(&moved), Err(2));
+
+        let mut removed = entries.clone();
+        removed.remove(1);
+        assert_eq!(check(&removed), Err(3));
+    }
+
+    #[test]
+    fn schools_keep_separate_chains() {
+        let one = entry(1, 1, GENESIS_HASH);
+        let two = entry(2, 2, GENESIS_HASH);
+        let three = entry(3, 1, &one.hash);
+        let four = entry(4, 2, &two.hash);
+        let entries = vec![one, two, three, four];
+        assert_eq!(check(&entries), Ok(()));
+
+        // Across batches too
+        let mut heads = ChainHeads::default();
+        assert_eq!(first_broken(&entries[..2], &mut heads), Ok(()));
+        assert_eq!(first_broken(&entries[2..], &mut heads), Ok(()));
+
+        let mut removed = entries.clone();
+        removed.remove(2);
+        let five = entry(5, 1, &entries[2].hash);
+        removed.push(five);
+        assert_eq!(check(&removed), Err(5));
+
+        // Entries written while every school shared one chain still verify
+        let first = entry(1, 1, GENESIS_HASH);
+        let second = entry(2, 2, &first.hash);
+        let third = entry(3, 1, &second.hash);
+        assert_eq!(check(&[first, second, third]), Ok(()));
+    }
+
+    #[test]
+    fn hash_ignores_json_key_order() {
+        let mut entry = chain(1).remove(0);
+        entry.after = Some(json!({ "role": "student", "id": 101 }));
+        assert_eq!(snapshot_hash(&entry.before, &entry.after), entry.snapshot_hash.clone().unwrap());
+        assert_eq!(entry_hash(&entry), entry.hash);
+    }
+
+    #[test]
+    fn redacted_snapshots_keep_the_chain_valid() {
+        let mut entries = chain(3);
+        entries[1].after = None;
+        entries[1].redacted_at = Some(now());
+        assert_eq!(check(&entries), Ok(()));
+
+        // Blanking without marking the entry redacted, or marking it
+        // without blanking, is tampering
+        let mut blanked = chain(3);
+        blanked[1].after = None;
+        assert_eq!(check(&blanked), Err(2));
+        let mut marked = chain(3);
+        marked[1].redacted_at = Some(now());
+        assert_eq!(check(&marked), Err(2));
+
+        let mut renamed = entries.clone();
+        renamed[1].actor_email = Some("someone@else.local".to_string());
+        assert_eq!(check(&renamed), Err(2));
+    }
+
+    #[test]
+    fn changes_keep_only_modified_fields() {
+        let before = json!({ "id": 7, "role": "student", "is_active": true });
+        let after = json!({ "id": 7, "role": "teacher", "is_active": true });
+        assert_eq!(
+            changes(Some(before.clone()), Some(after)),
+            (Some(json!({ "role": "student" })), Some(json!({ "role": "teacher" })))
+        );
+        assert_eq!(changes(Some(before.clone()), None), (Some(before), None));
+    }
+}
@@ -0,0 +1,271 @@
+use std::sync::OnceLock;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::application::tenancy::DEFAULT_TENANT_ID;
+use crate::config::Config;
+use crate::dto::user::UserResponse;
+
+// Every role a user can have. Permissions are granted by role name, so a
+// misspelt role would silently grant nothing (or, where roles are denied by
+// name, everything).
+pub const ROLES: &[&str] = &[
+    "admin",
+    "principal",
+    "teacher",
+    "student",
+    "guardian",
+    "bursar",
+    "admissions",
+    "librarian",
+    "warden",
+    "transport",
+    "safeguarding",
+];
+
+// Roles that may browse the school's user list; students and guardians only
+// see their own account
+pub const DIRECTORY_ROLES: &[&str] = &[
+    "admin",
+    "principal",
+    "teacher",
+    "bursar",
+    "admissions",
+    "librarian",
+    "warden",
+    "transport",
+    "safeguarding",
+];
+
+// How long a password-verified login may wait for its second factor
+const CHALLENGE_MINUTES: i64 = 5;
+const CHALLENGE_PURPOSE: &str = "two_factor";
+
+// Claims carried by rsEdu access tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    // User id as a string, per the JWT spec
+    pub sub: String,
+    pub email: String,
+    pub role: String,
+    pub iat: i64,
+    pub exp: i64,
+    // School the user belongs to; tokens from before tenancy belong to the default one
+    #[serde(default = "default_tenant")]
+    pub tid: i32,
+    // Set while an admin views rsEdu as this user (the JWT "act" claim)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+}
+
+fn default_tenant() -> i32 {
+    DEFAULT_TENANT_ID
+}
+
+impl Claims {
+    pub fn user_id(&self) -> Option<i32> {
+        self.sub.parse().ok()
+    }
+}
+
+// The admin acting through an impersonated token, and their session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    pub sub: String,
+    pub email: String,
+    pub session_id: i32,
+}
+
+// Claims of a two-factor challenge: proof that the password step passed, and
+// nothing more. It has no email/role, so it never decodes as an access token.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeClaims {
+    sub: String,
+    purpose: String,
+    iat: i64,
+    exp: i64,
+}
+
+// Argon2 cost parameters for new hashes, set once at startup from config
+static ARGON2_PARAMS: OnceLock<Params> = OnceLock::new();
+
+// Staff may look up anyone in their school; everyone else only themselves
+pub fn can_view_user(viewer: &Claims, user_id: i32) -> bool {
+    viewer.user_id() == Some(user_id) || DIRECTORY_ROLES.contains(&viewer.role.as_str())
+}
+
+// Use these argon2 costs for every hash from now on. Existing hashes keep
+// working and are upgraded the next time their owner logs in.
+pub fn configure_password_hashing(config: &Config) -> Result<(), argon2::Error> {
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )?;
+    let _ = ARGON2_PARAMS.set(params);
+    Ok(())
+}
+
+fn argon2() -> Argon2<'static> {
+    let params = ARGON2_PARAMS.get().cloned().unwrap_or_default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+// Emails are stored and compared trimmed and lowercased, so an address
+// typed with different capitals still finds its account
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+// Hash a password with argon2 and a fresh salt
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+}
+
+// Check a password against a stored argon2 hash (verification uses the
+// parameters recorded in the hash itself)
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+// Whether a stored hash was made with another algorithm or weaker/different costs
+pub fn needs_rehash(hash: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return true;
+    };
+    let current = ARGON2_PARAMS.get().cloned().unwrap_or_default();
+    hash.algorithm != Algorithm::Argon2id.ident()
+        || hash.version != Some(Version::V0x13.into())
+        || Params::try_from(&hash).map_or(true, |params| {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        })
+}
+
+// Issue an access token for a user (HS256, signed with JWT_SECRET)
+pub fn issue_token(config: &Config, user: &UserResponse) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        role: user.role.clone(),
+        iat: now.timestamp(),
+        exp: (now + Duration::hours(config.jwt_expiration_hours)).timestamp(),
+        tid: user.tenant_id,
+        act: None,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+}
+
+// Issue a token that signs `user` in on behalf of an impersonating admin. It
+// expires with the impersonation session, not after the usual login lifetime.
+pub fn issue_impersonation_token(
+    config: &Config,
+    user: &UserResponse,
+    actor: Actor,
+    expires_at: DateTime<Utc>,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = Claims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        role: user.role.clone(),
+        iat: Utc::now().timestamp(),
+        exp: expires_at.timestamp(),
+        tid: user.tenant_id,
+        act: Some(actor),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+}
+
+// Check an access token's signature and expiry
+pub fn verify_token(config: &Config, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+}
+
+// Issue a short-lived token that can only be exchanged for an access token
+// together with a second factor
+pub fn issue_challenge(config: &Config, user_id: i32) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = ChallengeClaims {
+        sub: user_id.to_string(),
+        purpose: CHALLENGE_PURPOSE.to_string(),
+        iat: now.timestamp(),
+        exp: (now + Duration::minutes(CHALLENGE_MINUTES)).timestamp(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+}
+
+// User id of a valid two-factor challenge token
+pub fn verify_challenge(config: &Config, token: &str) -> Option<i32> {
+    let claims = decode::<ChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .ok()?
+    .claims;
+
+    (claims.purpose == CHALLENGE_PURPOSE)
+        .then(|| claims.sub.parse().ok())
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: i32, role: &str) -> Claims {
+        Claims {
+            sub: id.to_string(),
+            email: "ada@example.com".to_string(),
+            role: role.to_string(),
+            iat: 0,
+            exp: 0,
+            tid: 1,
+            act: None,
+        }
+    }
+
+    #[test]
+    fn only_staff_look_up_other_users() {
+        assert!(can_view_user(&user(1, "teacher"), 2));
+        assert!(can_view_user(&user(1, "bursar"), 2));
+        assert!(can_view_user(&user(1, "student"), 1));
+        assert!(!can_view_user(&user(1, "student"), 2));
+        assert!(!can_view_user(&user(1, "guardian"), 2));
+        assert!(DIRECTORY_ROLES.iter().all(|role| ROLES.contains(role)));
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::{Cursor, Write};
+
+use serde_json::{Map, Value};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::application::xapi::AttachmentPart;
+
+pub const AUTH_SOURCE_ERASED: &str = "erased";
+pub const ERASED_NAME: &str = "Erased user";
+// Stands in for free text written by or about an erased user
+pub const ERASED_TEXT: &str = "[erased]";
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_REJECTED: &str = "rejected";
+pub const STATUS_COMPLETED: &str = "completed";
+
+// LTI launch claims that identify a person. Everything else (context,
+// resource link, roles) is kept for course statistics.
+const PERSONAL_LTI_CLAIMS: &[&str] = &[
+    "name",
+    "given_name",
+    "family_name",
+    "middle_name",
+    "email",
+    "picture",
+    "locale",
+    "https://purl.imsglobal.org/spec/lti/claim/lis",
+];
+
+// Placeholder address of an erased user; unique so the email column stays unique
+pub fn erased_email(user_id: i32) -> String {
+    format!("erased-{}@erased.invalid", user_id)
+}
+
+// The xAPI agent erased users' statements are attributed to
+pub fn erased_agent(user_id: i32) -> Value {
+    serde_json::json!({
+        "objectType": "Agent",
+        "mbox": format!("mailto:{}", erased_email(user_id)),
+    })
+}
+
+// Swap the actor of a stored statement for the erased agent. Verb, object and
+// result stay, since scores feed course statistics.
+pub fn anonymize_statement(statement: &mut Value, user_id: i32) {
+    if let Some(statement) = statement.as_object_mut() {
+        statement.insert("actor".to_string(), erased_agent(user_id));
+    }
+}
+
+pub fn anonymize_launch_claims(claims: &mut Value) {
+    if let Some(claims) = claims.as_object_mut() {
+        for claim in PERSONAL_LTI_CLAIMS {
+            claims.remove(*claim);
+        }
+    }
+}
+
+// Whether enough admins have approved: distinct approvers other than the requester
+pub fn approved(requested_by: i32, approvers: &[i32], required: usize) -> bool {
+    let mut distinct: Vec<i32> = approvers
+        .iter()
+        .copied()
+        .filter(|approver| *approver != requested_by)
+        .collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    distinct.len() >= required.max(1)
+}
+
+// Zip the export: everything as data.json, plus attachment files by hash
+pub fn build_archive(data: &Map<String, Value>, attachments: &[AttachmentPart]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("data.json", options)?;
+    let json = serde_json::to_vec_pretty(data).unwrap_or_default();
+    zip.write_all(&json)?;
+
+    for attachment in attachments {
+        zip.start_file(format!("attachments/{}", attachment.sha2), options)?;
+        zip.write_all(&attachment.content)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn archive_holds_data_and_attachments() {
+        let mut data = Map::new();
+        data.insert("user".to_string(), serde_json::json!({ "id": 7 }));
+        let attachment = AttachmentPart {
+            sha2: "abc123".to_string(),
+            content_type: "text/plain".to_string(),
+            content: b"certificate".to_vec(),
+        };
+
+        let bytes = build_archive(&data, &[attachment]).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let mut json = String::new();
+        archive.by_name("data.json").unwrap().read_to_string(&mut json).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap()["user"]["id"], 7);
+
+        let mut content = Vec::new();
+        archive.by_name("attachments/abc123").unwrap().read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"certificate");
+    }
+
+    #[test]
+    fn anonymizing_keeps_what_statistics_need() {
+        let mut statement = serde_json::json!({
+            "actor": { "mbox": "mailto:amina@school.local", "name": "Amina" },
+            "verb": { "id": "http://adlnet.gov/expapi/verbs/completed" },
+            "result": { "score": { "scaled": 0.9 } },
+        });
+        anonymize_statement(&mut statement, 7);
+        assert_eq!(statement["actor"]["mbox"], "mailto:erased-7@erased.invalid");
+        assert!(statement["actor"].get("name").is_none());
+        assert_eq!(statement["result"]["score"]["scaled"], 0.9);
+
+        let mut claims = serde_json::json!({ "name": "Amina", "email": "amina@school.local", "sub": "x" });
+        anonymize_launch_claims(&mut claims);
+        assert_eq!(claims, serde_json::json!({ "sub": "x" }));
+    }
+
+    #[test]
+    fn requester_cannot_approve_their_own_erasure() {
+        assert!(!approved(1, &[1], 1));
+        assert!(approved(1, &[1, 2], 1));
+        assert!(!approved(1, &[2, 2], 2));
+        assert!(approved(1, &[2, 3], 2));
+    }
+}
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use ldap3::{
+    adapters::{Adapter, EntriesOnly, PagedResults},
+    ldap_escape, Ldap, LdapConnAsync, Scope, SearchEntry,
+};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde::Serialize;
+
+use crate::application::audit::AuditContext;
+use crate::application::auth::ROLES;
+use crate::application::tenancy::DEFAULT_TENANT_ID;
+use crate::config::Config;
+use crate::dto::user::UserResponse;
+use crate::entities::users;
+use crate::repositories::user_repository::UserRepository;
+
+pub const AUTH_SOURCE_LDAP: &str = "ldap";
+// The directory is configured for the whole deployment and serves the default school
+pub const DIRECTORY_TENANT_ID: i32 = DEFAULT_TENANT_ID;
+const PAGE_SIZE: i32 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DirectoryError {
+    #[error("directory error: {0}")]
+    Ldap(#[from] ldap3::LdapError),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+// A person as the directory describes them
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryUser {
+    pub dn: String,
+    pub email: String,
+    pub full_name: String,
+    // Group DNs from memberOf
+    pub groups: Vec<String>,
+}
+
+// A source of accounts that can verify passwords: Active Directory, OpenLDAP,
+// or an in-memory stand-in in tests.
+pub trait Directory {
+    // Ok(None) means unknown user or wrong password
+    fn authenticate(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> impl Future<Output = Result<Option<DirectoryUser>, DirectoryError>> + Send;
+
+    fn list_users(&self) -> impl Future<Output = Result<Vec<DirectoryUser>, DirectoryError>> + Send;
+}
+
+#[derive(Clone)]
+pub struct LdapDirectory {
+    url: String,
+    bind_dn: String,
+    bind_password: String,
+    base_dn: String,
+    user_filter: String,
+    email_attribute: String,
+    name_attribute: String,
+}
+
+impl LdapDirectory {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Some(Self {
+            url: config.ldap_url.clone()?,
+            bind_dn: config.ldap_bind_dn.clone(),
+            bind_password: config.ldap_bind_password.clone(),
+            base_dn: config.ldap_base_dn.clone(),
+            user_filter: config.ldap_user_filter.clone(),
+            email_attribute: config.ldap_email_attribute.clone(),
+            name_attribute: config.ldap_name_attribute.clone(),
+        })
+    }
+
+    // Open a connection bound as the service account
+    async fn service_connection(&self) -> Result<Ldap, DirectoryError> {
+        let (conn, mut ldap) = LdapConnAsync::new(&self.url).await?;
+        ldap3::drive!(conn);
+        ldap.simple_bind(&self.bind_dn, &self.bind_password)
+            .await?
+            .success()?;
+        Ok(ldap)
+    }
+
+    fn to_user(&self, entry: SearchEntry) -> Option<DirectoryUser> {
+        let first = |attribute: &str| {
+            entry
+                .attrs
+                .get(attribute)
+                .and_then(|values| values.first())
+                .cloned()
+        };
+        let email = first(&self.email_attribute)?.to_lowercase();
+        let full_name = first(&self.name_attribute).unwrap_or_else(|| email.clone());
+
+        Some(DirectoryUser {
+            groups: entry.attrs.get("memberOf").cloned().unwrap_or_default(),
+            dn: entry.dn,
+            email,
+            full_name,
+        })
+    }
+
+    fn attributes(&self) -> Vec<&str> {
+        vec![self.email_attribute.as_str(), self.name_attribute.as_str(), "memberOf"]
+    }
+}
+
+impl Directory for LdapDirectory {
+    async fn authenticate(&self, email: &str, password: &str) -> Result<Option<DirectoryUser>, DirectoryError> {
+        // An empty password would turn the bind into an anonymous one and "succeed"
+        if password.is_empty() {
+            return Ok(None);
+        }
+
+        let mut ldap = self.service_connection().await?;
+        let filter = format!(
+            "(&{}({}={}))",
+            self.user_filter,
+            self.email_attribute,
+            ldap_escape(email)
+        );
+        let (entries, _) = ldap
+            .search(&self.base_dn, Scope::Subtree, &filter, self.attributes())
+            .await?
+            .success()?;
+        ldap.unbind().await?;
+
+        let Some(user) = entries
+            .into_iter()
+            .next()
+            .and_then(|entry| self.to_user(SearchEntry::construct(entry)))
+        else {
+            return Ok(None);
+        };
+
+        // Verify the password by binding as the user on a fresh connection
+        let (conn, mut ldap) = LdapConnAsync::new(&self.url).await?;
+        ldap3::drive!(conn);
+        let bound = ldap.simple_bind(&user.dn, password).await?.success().is_ok();
+        ldap.unbind().await?;
+
+        Ok(bound.then_some(user))
+    }
+
+    async fn list_users(&self) -> Result<Vec<DirectoryUser>, DirectoryError> {
+        let mut ldap = self.service_connection().await?;
+        let filter = format!("(&{}({}=*))", self.user_filter, self.email_attribute);
+
+        // Active Directory caps plain searches at 1000 entries, so page through
+        let adapters: Vec<Box<dyn Adapter<_, _>>> = vec![
+            Box::new(EntriesOnly::new()),
+            Box::new(PagedResults::new(PAGE_SIZE)),
+        ];
+        let mut stream = ldap
+            .streaming_search_with(adapters, &self.base_dn, Scope::Subtree, &filter, self.attributes())
+            .await?;
+
+        let mut users = Vec::new();
+        while let Some(entry) = stream.next().await? {
+            if let Some(user) = self.to_user(SearchEntry::construct(entry)) {
+                users.push(user);
+            }
+        }
+        stream.finish().await.success()?;
+        ldap.unbind().await?;
+
+        Ok(users)
+    }
+}
+
+// Common name of a group DN ("CN=Teachers,OU=Groups,DC=school" -> "Teachers")
+fn group_name(dn: &str) -> &str {
+    let first = dn.split(',').next().unwrap_or(dn);
+    match first.split_once('=') {
+        Some((_, name)) => name.trim(),
+        None => first.trim(),
+    }
+}
+
+// rsEdu role for a set of directory groups; the first matching mapping wins
+pub fn role_for(groups: &[String], group_role_map: &[(String, String)]) -> Option<String> {
+    group_role_map
+        .iter()
+        .filter(|(_, role)| ROLES.contains(&role.as_str()))
+        .find(|(group, _)| {
+            groups
+                .iter()
+                .any(|dn| group_name(dn).eq_ignore_ascii_case(group) || dn.eq_ignore_ascii_case(group))
+        })
+        .map(|(_, role)| role.clone())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncAction {
+    Create { email: String, full_name: String, role: String },
+    Update { user_id: i32, full_name: String, role: String },
+    Deactivate { user_id: i32 },
+}
+
+// Work out what a sync has to change. Only directory-sourced accounts are touched;
+// directory users outside every mapped group count as removed. A returning
+// user is only reactivated if the sync was what deactivated them.
+pub fn plan_sync(
+    directory_users: &[DirectoryUser],
+    existing: &[users::Model],
+    group_role_map: &[(String, String)],
+) -> Vec<SyncAction> {
+    let mut wanted: HashMap<&str, (&DirectoryUser, String)> = HashMap::new();
+    for user in directory_users {
+        if let Some(role) = role_for(&user.groups, group_role_map) {
+            wanted.insert(user.email.as_str(), (user, role));
+        }
+    }
+
+    let mut actions = Vec::new();
+    for user in existing {
+        match wanted.remove(user.email.to_lowercase().as_str()) {
+            Some(_) if !user.is_active && user.directory_deactivated_at.is_none() => {}
+            Some((entry, role))
+                if !user.is_active || user.role != role || user.full_name != entry.full_name =>
+            {
+                actions.push(SyncAction::Update {
+                    user_id: user.id,
+                    full_name: entry.full_name.clone(),
+                    role,
+                });
+            }
+            Some(_) => {}
+            None if user.is_active => actions.push(SyncAction::Deactivate { user_id: user.id }),
+            None => {}
+        }
+    }
+
+    let mut created: Vec<_> = wanted.into_values().collect();
+    created.sort_by(|(a, _), (b, _)| a.email.cmp(&b.email));
+    actions.extend(created.into_iter().map(|(entry, role)| SyncAction::Create {
+        email: entry.email.clone(),
+        full_name: entry.full_name.clone(),
+        role,
+    }));
+
+    actions
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deactivated: usize,
+    pub skipped: usize,
+}
+
+// Mirror the directory into `users`: create, update roles and deactivate leavers
+pub async fn sync<D: Directory>(
+    db: &DatabaseConnection,
+    directory: &D,
+    group_role_map: &[(String, String)],
+    context: &AuditContext,
+) -> Result<SyncReport, DirectoryError> {
+    let directory_users = directory.list_users().await?;
+    let existing = UserRepository::find_by_auth_source(db, DIRECTORY_TENANT_ID, AUTH_SOURCE_LDAP).await?;
+
+    let mut report = SyncReport::default();
+    for action in plan_sync(&directory_users, &existing, group_role_map) {
+        match action {
+            SyncAction::Create { email, full_name, role } => {
+                // A local account with the same email is left alone, and so is a
+                // deleted one until it is restored or purged
+                if UserRepository::find_by_email(db, DIRECTORY_TENANT_ID, &email).await?.is_some()
+                    || UserRepository::email_in_trash(db, DIRECTORY_TENANT_ID, &email).await?
+                {
+                    tracing::warn!("Directory user {} clashes with a non-directory account", email);
+                    report.skipped += 1;
+                    continue;
+                }
+                UserRepository::provision(db, DIRECTORY_TENANT_ID, &email, &full_name, &role, AUTH_SOURCE_LDAP, context).await?;
+                report.created += 1;
+            }
+            SyncAction::Update { user_id, full_name, role } => {
+                UserRepository::update_directory_user(db, DIRECTORY_TENANT_ID, user_id, &full_name, &role, true, context).await?;
+                report.updated += 1;
+            }
+            SyncAction::Deactivate { user_id } => {
+                UserRepository::deactivate_directory_user(db, DIRECTORY_TENANT_ID, user_id, context).await?;
+                report.deactivated += 1;
+            }
+        }
+    }
+
+    tracing::info!(
+        "Directory sync: {} created, {} updated, {} deactivated, {} skipped",
+        report.created,
+        report.updated,
+        report.deactivated,
+        report.skipped
+    );
+    Ok(report)
+}
+
+// Password login against the directory. Returns the rsEdu user, creating or
+// updating it from the directory entry, or None when the directory says no.
+pub async fn login<D: Directory>(
+    db: &DatabaseConnection,
+    directory: &D,
+    group_role_map: &[(String, String)],
+    email: &str,
+    password: &str,
+    context: &AuditContext,
+) -> Result<Option<UserResponse>, DirectoryError> {
+    let Some(entry) = directory.authenticate(email, password).await? else {
+        return Ok(None);
+    };
+    let Some(role) = role_for(&entry.groups, group_role_map) else {
+        tracing::warn!("Directory user {} is not in any mapped group", entry.email);
+        return Ok(None);
+    };
+
+    let account = UserRepository::find_account(db, DIRECTORY_TENANT_ID, &entry.email).await?;
+    let in_trash = account.is_none() && UserRepository::email_in_trash(db, DIRECTORY_TENANT_ID, &entry.email).await?;
+    match login_account(account.as_ref(), in_trash) {
+        LoginAccount::Refuse => Ok(None),
+        LoginAccount::Update(user_id) => {
+            Ok(UserRepository::update_directory_user(db, DIRECTORY_TENANT_ID, user_id, &entry.full_name, &role, true, context).await?)
+        }
+        LoginAccount::Provision => Ok(Some(
+            UserRepository::provision(db, DIRECTORY_TENANT_ID, &entry.email, &entry.full_name, &role, AUTH_SOURCE_LDAP, context).await?,
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum LoginAccount {
+    Refuse,
+    Update(i32),
+    Provision,
+}
+
+// What a directory login does with the rsEdu account holding the email.
+// Local accounts keep their own password, a deactivated user stays out, and
+// a trashed email stays taken until the user is restored or purged.
+fn login_account(account: Option<&users::Model>, in_trash: bool) -> LoginAccount {
+    match account {
+        Some(user) if user.auth_source != AUTH_SOURCE_LDAP || !user.is_active => LoginAccount::Refuse,
+        Some(user) => LoginAccount::Update(user.id),
+        None if in_trash => LoginAccount::Refuse,
+        None => LoginAccount::Provision,
+    }
+}
+
+// Background task running `sync` every LDAP_SYNC_INTERVAL_MINUTES
+pub fn spawn_scheduled_sync(db: DatabaseConnection, directory: LdapDirectory, config: &Config) {
+    if config.ldap_sync_interval_minutes == 0 {
+        return;
+    }
+    let group_role_map = config.ldap_group_role_map.clone();
+    let period = std::time::Duration::from_secs(config.ldap_sync_interval_minutes * 60);
+
+    // Scheduled runs have no actor; the audit log shows them as system changes
+    let context = AuditContext { tenant_id: Some(DIRECTORY_TENANT_ID), ..Default::default() };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = sync(&db, &directory, &group_role_map, &context).await {
+                tracing::error!("Directory sync failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    // Local LDAP stand-in: a fixed set of entries with their passwords
+    struct StaticDirectory {
+        entries: Vec<(DirectoryUser, &'static str)>,
+    }
+
+    impl Directory for StaticDirectory {
+        async fn authenticate(&self, email: &str, password: &str) -> Result<Option<DirectoryUser>, DirectoryError> {
+            Ok(self
+                .entries
+                .iter()
+                .find(|(user, secret)| user.email == email && !password.is_empty() && *secret == password)
+                .map(|(user, _)| user.clone()))
+        }
+
+        async fn list_users(&self) -> Result<Vec<DirectoryUser>, DirectoryError> {
+            Ok(self.entries.iter().map(|(user, _)| user.clone()).collect())
+        }
+    }
+
+    fn entry(email: &str, groups: &[&str]) -> DirectoryUser {
+        DirectoryUser {
+            dn: format!("CN={},OU=People,DC=school,DC=local", email),
+            email: email.to_string(),
+            full_name: email.to_string(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        }
+    }
+
+    fn existing(id: i32, email: &str, role: &str, is_active: bool) -> users::Model {
+        deactivated(id, email, role, is_active, None)
+    }
+
+    fn deactivated(
+        id: i32,
+        email: &str,
+        role: &str,
+        is_active: bool,
+        by_sync: Option<chrono::NaiveDateTime>,
+    ) -> users::Model {
+        let now = Utc::now().naive_utc();
+        users::Model {
+            id,
+            tenant_id: DIRECTORY_TENANT_ID,
+            email: email.to_string(),
+            password_hash: String::new(),
+            full_name: email.to_string(),
+            role: role.to_string(),
+            is_active,
+            created_at: now,
+            updated_at: now,
+            auth_source: AUTH_SOURCE_LDAP.to_string(),
+            deleted_at: None,
+            directory_deactivated_at: by_sync,
+            password_changed_at: None,
+        }
+    }
+
+    fn group_map() -> Vec<(String, String)> {
+        vec![
+            ("Staff Admins".to_string(), "admin".to_string()),
+            ("Teachers".to_string(), "teacher".to_string()),
+            ("Students".to_string(), "student".to_string()),
+        ]
+    }
+
+    #[test]
+    fn groups_map_to_roles_by_common_name() {
+        let groups = vec![
+            "CN=Teachers,OU=Groups,DC=school,DC=local".to_string(),
+            "CN=Staff Admins,OU=Groups,DC=school,DC=local".to_string(),
+        ];
+        assert_eq!(role_for(&groups, &group_map()).as_deref(), Some("admin"));
+        assert_eq!(role_for(&["CN=Parents,DC=x".to_string()], &group_map()), None);
+    }
+
+    #[tokio::test]
+    async fn directory_login_says_no_before_touching_accounts() {
+        let directory = StaticDirectory {
+            entries: vec![
+                (entry("t@school.local", &["CN=Teachers,DC=x"]), "s3cret"),
+                (entry("p@school.local", &["CN=Parents,DC=x"]), "s3cret"),
+            ],
+        };
+        // No database: using it would panic, so these refusals come from the
+        // directory alone
+        let db = DatabaseConnection::Disconnected;
+        assert!(login(&db, &directory, &group_map(), "t@school.local", "wrong", &AuditContext::default()).await.unwrap().is_none());
+        assert!(login(&db, &directory, &group_map(), "t@school.local", "", &AuditContext::default()).await.unwrap().is_none());
+        assert!(login(&db, &directory, &group_map(), "p@school.local", "s3cret", &AuditContext::default()).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn directory_login_only_signs_in_directory_accounts() {
+        let teacher = existing(1, "t@school.local", "teacher", true);
+        let mut local = existing(2, "l@school.local", "teacher", true);
+        local.auth_source = "local".to_string();
+        let left = existing(3, "gone@school.local", "teacher", false);
+
+        assert_eq!(login_account(Some(&teacher), false), LoginAccount::Update(1));
+        assert_eq!(login_account(Some(&local), false), LoginAccount::Refuse);
+        assert_eq!(login_account(Some(&left), false), LoginAccount::Refuse);
+        assert_eq!(login_account(None, true), LoginAccount::Refuse);
+        assert_eq!(login_account(None, false), LoginAccount::Provision);
+    }
+
+    #[test]
+    fn sync_only_reactivates_users_it_deactivated() {
+        let back = vec![
+            entry("returned@school.local", &["CN=Teachers,DC=x"]),
+            entry("suspended@school.local", &["CN=Teachers,DC=x"]),
+        ];
+        let users = vec![
+            deactivated(1, "returned@school.local", "teacher", false, Some(Utc::now().naive_utc())),
+            deactivated(2, "suspended@school.local", "teacher", false, None),
+        ];
+        assert_eq!(
+            plan_sync(&back, &users, &group_map()),
+            vec![SyncAction::Update {
+                user_id: 1,
+                full_name: "returned@school.local".to_string(),
+                role: "teacher".to_string()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_plan_creates_updates_and_deactivates() {
+        let directory = StaticDirectory {
+            entries: vec![
+                (entry("new@school.local", &["CN=Students,DC=x"]), "pw"),
+                (entry("promoted@school.local", &["CN=Teachers,DC=x"]), "pw"),
+                (entry("unmapped@school.local", &["CN=Parents,DC=x"]), "pw"),
+            ],
+        };
+        let users = vec![
+            existing(1, "promoted@school.local", "student", true),
+            existing(2, "unmapped@school.local", "student", true),
+            existing(3, "left@school.local", "teacher", true),
+            existing(4, "already-off@school.local", "teacher", false),
+        ];
+
+        let actions = plan_sync(&directory.list_users().await.unwrap(), &users, &group_map());
+        assert_eq!(
+            actions,
+            vec![
+                SyncAction::Update {
+                    user_id: 1,
+                    full_name: "promoted@school.local".to_string(),
+                    role: "teacher".to_string()
+                },
+                SyncAction::Deactivate { user_id: 2 },
+                SyncAction::Deactivate { user_id: 3 },
+                SyncAction::Create {
+                    email: "new@school.local".to_string(),
+                    full_name: "new@school.local".to_string(),
+                    role: "student".to_string()
+                },
+            ]
+        );
+    }
+}
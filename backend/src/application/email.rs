@@ -0,0 +1,403 @@
+use std::sync::LazyLock;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use minijinja::{context, Environment, Value};
+use sea_orm::{DatabaseConnection, DbErr};
+
+use crate::config::Config;
+use crate::entities::{email_outbox, invoices, tenants, users};
+use crate::infrastructure::mailer::{Email, MailError, SharedMailer};
+use crate::repositories::email_repository::EmailRepository;
+
+pub const WELCOME: &str = "welcome";
+pub const PASSWORD_RESET: &str = "password_reset";
+pub const ABSENCE_ALERT: &str = "absence_alert";
+pub const INVOICE_DUE: &str = "invoice_due";
+// Templates whose body carries a secret, such as a reset link. Their body is
+// blanked once they are sent or given up on, and they are never retried:
+// the user asks for a new one instead.
+pub const SENSITIVE_TEMPLATES: &[&str] = &[PASSWORD_RESET];
+
+// Languages schools can have their emails written in
+pub const LOCALES: &[&str] = &["en", "fr"];
+pub const DEFAULT_LOCALE: &str = "en";
+const DEFAULT_COLOR: &str = "#1d4ed8";
+
+pub const PENDING: &str = "pending";
+pub const SENT: &str = "sent";
+pub const FAILED: &str = "failed";
+pub const STATUSES: &[&str] = &[PENDING, SENT, FAILED];
+
+// Who may see the outbox and retry failed emails
+pub const OUTBOX_ROLES: &[&str] = &["admin"];
+// Who may tell families a student is absent
+pub const ABSENCE_ALERT_ROLES: &[&str] = &["admin", "principal", "teacher"];
+
+// Emails handed to the mailer per pass of the worker
+pub const BATCH_SIZE: u64 = 50;
+// How long a claimed email is left to its worker before another may send it
+pub const LEASE: Duration = Duration::minutes(5);
+// Retries wait a minute, then twice as long each time, up to six hours
+const FIRST_RETRY_SECONDS: i64 = 60;
+const MAX_RETRY_SECONDS: i64 = 6 * 60 * 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmailError {
+    #[error("email not found")]
+    NotFound,
+    #[error("only failed emails can be retried")]
+    NotFailed,
+    #[error("this email held a one-time link and cannot be sent again; ask for a new one")]
+    NotRetriable,
+    #[error("student not found")]
+    StudentNotFound,
+    #[error("the student has no guardians with an account to email")]
+    NoGuardians,
+    #[error("failed to render email: {0}")]
+    Template(#[from] minijinja::Error),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+// Template sources, compiled into the binary. A template missing from a
+// locale falls back to the English one.
+const SOURCES: &[(&str, &str)] = &[
+    ("layout.html", include_str!("../../templates/email/layout.html")),
+    ("en/welcome.txt", include_str!("../../templates/email/en/welcome.txt")),
+    ("en/password_reset.txt", include_str!("../../templates/email/en/password_reset.txt")),
+    ("en/absence_alert.txt", include_str!("../../templates/email/en/absence_alert.txt")),
+    ("en/invoice_due.txt", include_str!("../../templates/email/en/invoice_due.txt")),
+    ("fr/welcome.txt", include_str!("../../templates/email/fr/welcome.txt")),
+    ("fr/password_reset.txt", include_str!("../../templates/email/fr/password_reset.txt")),
+    ("fr/absence_alert.txt", include_str!("../../templates/email/fr/absence_alert.txt")),
+    ("fr/invoice_due.txt", include_str!("../../templates/email/fr/invoice_due.txt")),
+];
+
+static TEMPLATES: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    for (name, source) in SOURCES {
+        env.add_template(name, source).expect("email templates must compile");
+    }
+    env
+});
+
+// How a school's emails look and which language they are in
+#[derive(Debug, Clone)]
+pub struct Branding {
+    pub school: String,
+    pub logo_url: Option<String>,
+    pub color: String,
+    pub locale: String,
+}
+
+impl From<&tenants::Model> for Branding {
+    fn from(tenant: &tenants::Model) -> Self {
+        Branding {
+            school: tenant.name.clone(),
+            logo_url: tenant.logo_url.clone(),
+            color: tenant.brand_color.clone().unwrap_or_else(|| DEFAULT_COLOR.to_string()),
+            locale: tenant.locale.clone(),
+        }
+    }
+}
+
+// A "#rrggbb" colour, the only form safe to put in a style attribute
+pub fn valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Render a template's subject and body in the school's language, and wrap
+// the body in the school's branded HTML layout. Blank lines in the body
+// separate paragraphs.
+pub fn render(template: &str, branding: &Branding, to: &str, values: Value) -> Result<Email, minijinja::Error> {
+    let localized = format!("{}/{}.txt", branding.locale, template);
+    let source = match TEMPLATES.get_template(&localized) {
+        Ok(source) => source,
+        Err(_) => TEMPLATES.get_template(&format!("{}/{}.txt", DEFAULT_LOCALE, template))?,
+    };
+    let (subject, body) = source
+        .render_captured(context! { school => &branding.school, ..values })?
+        .with_state_mut(|state| Ok::<_, minijinja::Error>((state.render_block("subject")?, state.render_block("body")?)))?;
+    let subject = subject.trim().to_string();
+    let body = format!("{}\n", body.trim());
+
+    let paragraphs: Vec<Vec<&str>> = body
+        .split("\n\n")
+        .map(|paragraph| paragraph.lines().collect::<Vec<_>>())
+        .filter(|lines| !lines.is_empty())
+        .collect();
+    let html = TEMPLATES.get_template("layout.html")?.render(context! {
+        locale => &branding.locale,
+        subject => &subject,
+        school => &branding.school,
+        logo_url => &branding.logo_url,
+        color => &branding.color,
+        paragraphs => paragraphs,
+    })?;
+
+    Ok(Email {
+        to: to.to_string(),
+        subject,
+        body,
+        html: Some(html),
+        from_name: Some(branding.school.clone()),
+    })
+}
+
+pub fn welcome(branding: &Branding, to: &str, full_name: &str, login_url: &str) -> Result<Email, minijinja::Error> {
+    render(WELCOME, branding, to, context! { full_name, login_url })
+}
+
+// `reset_url` is the frontend page the token is handed to, valid for `minutes`
+pub fn password_reset(
+    branding: &Branding,
+    to: &str,
+    full_name: &str,
+    reset_url: &str,
+    token: &str,
+    minutes: i64,
+) -> Result<Email, minijinja::Error> {
+    let link = format!("{}?token={}", reset_url, token);
+    render(PASSWORD_RESET, branding, to, context! { full_name, link, minutes })
+}
+
+pub fn absence_alert(
+    branding: &Branding,
+    guardian: &users::Model,
+    student: &users::Model,
+    date: NaiveDate,
+    note: Option<&str>,
+) -> Result<Email, minijinja::Error> {
+    let note = note.map(str::trim).filter(|note| !note.is_empty());
+    render(
+        ABSENCE_ALERT,
+        branding,
+        &guardian.email,
+        context! {
+            guardian_name => &guardian.full_name,
+            student_name => &student.full_name,
+            date => date.format("%Y-%m-%d").to_string(),
+            note,
+        },
+    )
+}
+
+pub fn invoice_due(
+    branding: &Branding,
+    recipient: &users::Model,
+    student: &users::Model,
+    invoice: &invoices::Model,
+    currency: &str,
+) -> Result<Email, minijinja::Error> {
+    render(
+        INVOICE_DUE,
+        branding,
+        &recipient.email,
+        context! {
+            name => &recipient.full_name,
+            student_name => &student.full_name,
+            term => &invoice.term,
+            due_date => invoice.due_date.format("%Y-%m-%d").to_string(),
+            total => format!("{:.2}", invoice.total),
+            balance => format!("{:.2}", invoice.total - invoice.amount_paid),
+            currency,
+        },
+    )
+}
+
+// The email a queued row describes
+pub fn from_outbox(row: &email_outbox::Model) -> Email {
+    Email {
+        to: row.to_address.clone(),
+        subject: row.subject.clone(),
+        body: row.body.clone(),
+        html: row.html.clone(),
+        from_name: row.from_name.clone(),
+    }
+}
+
+// How long to wait before the next try, after `attempts` failed ones
+pub fn retry_delay(attempts: i32) -> Duration {
+    let doublings = (attempts - 1).clamp(0, 20) as u32;
+    Duration::seconds((FIRST_RETRY_SECONDS << doublings).min(MAX_RETRY_SECONDS))
+}
+
+// When a failed email is tried next; None means it has had its attempts
+// (or was rejected outright) and is marked failed
+pub fn next_attempt(error: &MailError, attempts: i32, max_attempts: i32, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    match error {
+        MailError::Rejected(_) => None,
+        MailError::Send(_) if attempts >= max_attempts => None,
+        MailError::Send(_) => Some(now + retry_delay(attempts)),
+    }
+}
+
+// Send everything in the outbox, in any school, that is due. Emails are
+// claimed first, so workers on several instances do not send one twice.
+pub async fn deliver_due(db: &DatabaseConnection, mailer: &SharedMailer, max_attempts: i32) -> Result<usize, DbErr> {
+    let due = EmailRepository::claim_due(db, Utc::now().naive_utc(), BATCH_SIZE).await?;
+    for row in &due {
+        match mailer.send(&from_outbox(row)).await {
+            Ok(()) => EmailRepository::mark_sent(db, row.id).await?,
+            Err(e) => {
+                let attempts = row.attempts + 1;
+                let retry_at = next_attempt(&e, attempts, max_attempts, Utc::now().naive_utc());
+                match retry_at {
+                    Some(at) => tracing::warn!("Email {} to {} failed, retrying at {}: {}", row.id, row.to_address, at, e),
+                    None => tracing::error!("Email {} to {} failed for good: {}", row.id, row.to_address, e),
+                }
+                EmailRepository::mark_failed(db, row.id, attempts, &e.to_string(), retry_at).await?;
+            }
+        }
+    }
+    Ok(due.len())
+}
+
+pub fn spawn_outbox_worker(db: DatabaseConnection, mailer: SharedMailer, config: &Config) {
+    if config.outbox_poll_seconds == 0 {
+        return;
+    }
+    let period = std::time::Duration::from_secs(config.outbox_poll_seconds);
+    let max_attempts = config.outbox_max_attempts;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            // A full batch means there may be more waiting
+            loop {
+                match deliver_due(&db, &mailer, max_attempts).await {
+                    Ok(sent) if sent as u64 == BATCH_SIZE => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::error!("Email delivery failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+// Queue a reminder to the family of every unpaid invoice falling due within
+// `invoice_reminder_days` of today in its school, once per invoice
+pub async fn queue_invoice_reminders(db: &DatabaseConnection, config: &Config) -> Result<usize, EmailError> {
+    let mut reminded = 0;
+    for (invoice, tenant, student) in EmailRepository::invoices_to_remind(db, config.invoice_reminder_days).await? {
+        let branding = Branding::from(&tenant);
+        let emails = EmailRepository::family(db, tenant.id, &student)
+            .await?
+            .iter()
+            .map(|recipient| invoice_due(&branding, recipient, &student, &invoice, &config.payment_currency))
+            .collect::<Result<Vec<_>, _>>()?;
+        if EmailRepository::remind(db, &invoice, &emails).await? {
+            reminded += 1;
+        }
+    }
+    Ok(reminded)
+}
+
+pub fn spawn_invoice_reminders(db: DatabaseConnection, config: &Config) {
+    if config.invoice_reminder_interval_minutes == 0 {
+        return;
+    }
+    let period = std::time::Duration::from_secs(config.invoice_reminder_interval_minutes * 60);
+    let config = config.clone();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            match queue_invoice_reminders(&db, &config).await {
+                Ok(0) => {}
+                Ok(reminded) => tracing::info!("Queued reminders for {} invoices", reminded),
+                Err(e) => tracing::error!("Invoice reminders failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branding(locale: &str) -> Branding {
+        Branding {
+            school: "North High".to_string(),
+            logo_url: Some("https://north.example/logo.png".to_string()),
+            color: "#0f766e".to_string(),
+            locale: locale.to_string(),
+        }
+    }
+
+    fn user(id: i32, full_name: &str) -> users::Model {
+        let at = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        users::Model {
+            id,
+            tenant_id: 1,
+            email: format!("user{}@example.com", id),
+            password_hash: String::new(),
+            full_name: full_name.to_string(),
+            role: "guardian".to_string(),
+            is_active: true,
+            auth_source: "local".to_string(),
+            created_at: at,
+            updated_at: at,
+            deleted_at: None,
+            directory_deactivated_at: None,
+            password_changed_at: None,
+        }
+    }
+
+    #[test]
+    fn emails_are_branded_and_localised() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap();
+        let guardian = user(2, "Sam Okafor");
+        let student = user(3, "Ada <Okafor>");
+
+        let english = absence_alert(&branding("en"), &guardian, &student, date, Some(" Left after lunch. ")).unwrap();
+        assert_eq!(english.to, "user2@example.com");
+        assert_eq!(english.subject, "Ada <Okafor> was absent on 2026-10-20");
+        assert!(english.body.starts_with("Dear Sam Okafor,\n\nAda <Okafor> was marked absent"));
+        assert!(english.body.contains("on 2026-10-20.\n\nLeft after lunch.\n\nIf you know"));
+        assert_eq!(english.from_name.as_deref(), Some("North High"));
+        let html = english.html.unwrap();
+        assert!(html.contains("background:#0f766e") && html.contains("logo.png"));
+        assert!(html.contains("Ada &lt;Okafor&gt;") && !html.contains("<Okafor>"));
+
+        let french = absence_alert(&branding("fr"), &guardian, &student, date, None).unwrap();
+        assert_eq!(french.subject, "Absence de Ada <Okafor> le 2026-10-20");
+        assert!(french.body.contains("le 2026-10-20.\n\nSi vous connaissez"));
+    }
+
+    #[test]
+    fn every_template_renders_and_unknown_locales_fall_back_to_english() {
+        let reset_url = "https://north.example/reset-password";
+        for locale in LOCALES {
+            let branding = branding(locale);
+            assert!(!welcome(&branding, "a@example.com", "Ada", "https://north.example").unwrap().subject.is_empty());
+            let reset = password_reset(&branding, "a@example.com", "Ada", reset_url, "tok", 30).unwrap();
+            assert!(reset.body.contains("https://north.example/reset-password?token=tok"));
+            assert!(reset.body.contains("30 minutes"));
+        }
+
+        let fallback = welcome(&branding("de"), "a@example.com", "Ada", "https://north.example").unwrap();
+        assert_eq!(fallback.subject, "Welcome to North High");
+        assert!(fallback.body.ends_with("North High\n"));
+    }
+
+    #[test]
+    fn failed_sends_back_off_then_give_up() {
+        let now = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        let transient = MailError::Send("connection refused".to_string());
+
+        assert_eq!(retry_delay(1), Duration::minutes(1));
+        assert_eq!(retry_delay(4), Duration::minutes(8));
+        assert_eq!(retry_delay(30), Duration::hours(6));
+        assert_eq!(next_attempt(&transient, 2, 8, now), Some(now + Duration::minutes(2)));
+        assert_eq!(next_attempt(&transient, 8, 8, now), None);
+        assert_eq!(next_attempt(&MailError::Rejected("no such user".to_string()), 1, 8, now), None);
+        assert!(valid_color("#0F766e") && !valid_color("red") && !valid_color("#0f766e;x"));
+    }
+}
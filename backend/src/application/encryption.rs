@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use sea_orm::{DatabaseConnection, DbErr, DeriveValueType};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::repositories::profile_repository::ProfileRepository;
+
+const FORMAT_VERSION: &str = "v2";
+// Values sealed before fields were bound to their row; they open without AAD
+// and `reencrypt_stale` moves them to the current format
+const LEGACY_FORMAT_VERSION: &str = "v1";
+pub const LEGACY_FORMAT_PREFIX: &str = "v1:";
+const NONCE_LEN: usize = 12;
+// Key id used when no ENCRYPTION_KEYS are configured (development only)
+const DEV_KEY_ID: &str = "dev";
+const REENCRYPT_BATCH: u64 = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("ENCRYPTION_KEYS must be set in production")]
+    MissingKeys,
+    #[error("BLIND_INDEX_KEY must be set in production")]
+    MissingIndexKey,
+    #[error("encryption key {0} must have an id without ':' and be 32 bytes, base64 encoded")]
+    InvalidKey(String),
+    #[error("active encryption key {0} is not in ENCRYPTION_KEYS")]
+    UnknownActiveKey(String),
+    #[error("value is sealed with unknown key {0}")]
+    UnknownKey(String),
+    #[error("malformed encrypted value")]
+    Malformed,
+    #[error("encrypted value failed authentication")]
+    Decrypt,
+}
+
+// Ciphertext as stored in an encrypted column:
+// "v2:<master key id>:<wrapped data key>:<sealed value>", both parts base64
+// of nonce + AES-256-GCM output. Only a `Keyring` turns it back into text.
+#[derive(Clone, PartialEq, Eq, DeriveValueType)]
+pub struct EncryptedString(String);
+
+impl EncryptedString {
+    // Id of the master key that wrapped this value's data key
+    pub fn key_id(&self) -> Option<&str> {
+        let mut parts = self.0.splitn(3, ':');
+        match (parts.next(), parts.next()) {
+            (Some(FORMAT_VERSION | LEGACY_FORMAT_VERSION), Some(key_id)) => Some(key_id),
+            _ => None,
+        }
+    }
+}
+
+// Where an encrypted value lives. It is bound to the value as AES-GCM
+// associated data, so a ciphertext copied into another row or column no
+// longer opens.
+#[derive(Clone, Copy, Debug)]
+pub struct Field<'a> {
+    pub table: &'a str,
+    pub column: &'a str,
+    pub row_id: i32,
+}
+
+impl<'a> Field<'a> {
+    pub fn new(table: &'a str, column: &'a str, row_id: i32) -> Self {
+        Field { table, column, row_id }
+    }
+
+    fn aad(&self) -> Vec<u8> {
+        format!("{}.{}:{}", self.table, self.column, self.row_id).into_bytes()
+    }
+}
+
+// Never print ciphertext (or anything else) into logs
+impl fmt::Debug for EncryptedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptedString({})", self.key_id().unwrap_or("?"))
+    }
+}
+
+// Master keys for envelope encryption. Every value gets its own random data
+// key, wrapped with the active master key and stored next to the value.
+// Retired master keys stay in the ring until `reencrypt_stale` has moved
+// every row onto the active one.
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Arc<HashMap<String, Key<Aes256Gcm>>>,
+    active: String,
+    index_key: Arc<Vec<u8>>,
+}
+
+impl Keyring {
+    pub fn new(keys: &[(String, String)], active: Option<&str>, index_key: &str) -> Result<Self, EncryptionError> {
+        let mut parsed = HashMap::new();
+        for (id, encoded) in keys {
+            // The id is stored inside each value, which is ':'-separated
+            if id.is_empty() || id.contains(':') {
+                return Err(EncryptionError::InvalidKey(id.clone()));
+            }
+            let bytes = STANDARD
+                .decode(encoded)
+                .ok()
+                .filter(|bytes| bytes.len() == 32)
+                .ok_or_else(|| EncryptionError::InvalidKey(id.clone()))?;
+            parsed.insert(id.clone(), *Key::<Aes256Gcm>::from_slice(&bytes));
+        }
+        // Without an explicit choice the last listed key is the newest
+        let active = match active {
+            Some(active) => active.to_string(),
+            None => keys.last().map(|(id, _)| id.clone()).ok_or(EncryptionError::MissingKeys)?,
+        };
+        if !parsed.contains_key(&active) {
+            return Err(EncryptionError::UnknownActiveKey(active));
+        }
+        let index_key = STANDARD
+            .decode(index_key)
+            .map_err(|_| EncryptionError::InvalidKey("blind index".to_string()))?;
+
+        Ok(Keyring {
+            keys: Arc::new(parsed),
+            active,
+            index_key: Arc::new(index_key),
+        })
+    }
+
+    // Keys from ENCRYPTION_KEYS / BLIND_INDEX_KEY. Outside production, missing
+    // keys are derived from JWT_SECRET so a fresh checkout still runs.
+    pub fn from_config(config: &Config) -> Result<Self, EncryptionError> {
+        let production = config.environment == "production";
+        let derive = |purpose: &str| STANDARD.encode(Sha256::digest(format!("{}:{}", purpose, config.jwt_secret)));
+
+        let keys = if config.encryption_keys.is_empty() {
+            if production {
+                return Err(EncryptionError::MissingKeys);
+            }
+            tracing::warn!("⚠️ ENCRYPTION_KEYS not set, deriving a development key from JWT_SECRET");
+            vec![(DEV_KEY_ID.to_string(), derive("rsedu-field-encryption"))]
+        } else {
+            config.encryption_keys.clone()
+        };
+        let index_key = match &config.blind_index_key {
+            Some(key) => key.clone(),
+            None if production => return Err(EncryptionError::MissingIndexKey),
+            None => derive("rsedu-blind-index"),
+        };
+
+        Self::new(&keys, config.encryption_active_key.as_deref(), &index_key)
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active
+    }
+
+    // Encrypt under a fresh data key wrapped with the active master key; both
+    // are bound to `field`
+    pub fn seal(&self, plaintext: &str, field: Field) -> EncryptedString {
+        let aad = field.aad();
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let value = encrypt(&data_key, plaintext.as_bytes(), &aad);
+        let wrapped = encrypt(&self.keys[&self.active], &data_key, &aad);
+        EncryptedString(format!(
+            "{}:{}:{}:{}",
+            FORMAT_VERSION,
+            self.active,
+            STANDARD.encode(wrapped),
+            STANDARD.encode(value)
+        ))
+    }
+
+    // Decrypt a value sealed for `field`; legacy values carry no field to check
+    pub fn open(&self, value: &EncryptedString, field: Field) -> Result<String, EncryptionError> {
+        let mut parts = value.0.splitn(4, ':');
+        let (Some(version), Some(key_id), Some(wrapped), Some(sealed)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(EncryptionError::Malformed);
+        };
+        let aad = match version {
+            FORMAT_VERSION => field.aad(),
+            LEGACY_FORMAT_VERSION => Vec::new(),
+            _ => return Err(EncryptionError::Malformed),
+        };
+        let master_key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.to_string()))?;
+
+        let wrapped = STANDARD.decode(wrapped).map_err(|_| EncryptionError::Malformed)?;
+        let data_key = decrypt(master_key, &wrapped, &aad)?;
+        if data_key.len() != 32 {
+            return Err(EncryptionError::Malformed);
+        }
+        let sealed = STANDARD.decode(sealed).map_err(|_| EncryptionError::Malformed)?;
+        let plaintext = decrypt(Key::<Aes256Gcm>::from_slice(&data_key), &sealed, &aad)?;
+        String::from_utf8(plaintext).map_err(|_| EncryptionError::Malformed)
+    }
+
+    pub fn seal_opt(&self, plaintext: Option<&str>, field: Field) -> Option<EncryptedString> {
+        plaintext.map(|plaintext| self.seal(plaintext, field))
+    }
+
+    pub fn open_opt(&self, value: Option<&EncryptedString>, field: Field) -> Result<Option<String>, EncryptionError> {
+        value.map(|value| self.open(value, field)).transpose()
+    }
+
+    // Keyed hash of an identifier for exact-match lookups. Case, spaces and
+    // punctuation are ignored, so "ab 12-34" and "AB1234" match.
+    pub fn blind_index(&self, value: &str) -> String {
+        let normalized: String = value
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_uppercase)
+            .collect();
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.index_key).expect("HMAC accepts any key length");
+        mac.update(normalized.as_bytes());
+        STANDARD.encode(mac.finalize().into_bytes())
+    }
+}
+
+fn encrypt(key: &Key<Aes256Gcm>, plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .expect("AES-GCM encryption of an in-memory buffer");
+    [nonce.as_slice(), &ciphertext].concat()
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(EncryptionError::Malformed);
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| EncryptionError::Decrypt)
+}
+
+// Re-seal every profile whose values are under an older master key or in
+// the legacy format. Rows that fail to decrypt are logged and skipped so one
+// bad row cannot stall the rotation.
+pub async fn reencrypt_stale(db: &DatabaseConnection, keyring: &Keyring) -> Result<usize, DbErr> {
+    let mut after = 0;
+    let mut reencrypted = 0;
+    loop {
+        let batch = ProfileRepository::stale(db, keyring.active_key_id(), after, REENCRYPT_BATCH).await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after = last.user_id;
+        for profile in batch {
+            let user_id = profile.user_id;
+            match ProfileRepository::reencrypt(db, keyring, profile).await {
+                Ok(true) => reencrypted += 1,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Cannot re-encrypt profile of user {}: {}", user_id, e),
+            }
+        }
+    }
+    if reencrypted > 0 {
+        tracing::info!("🔐 Re-encrypted {} profiles under key {}", reencrypted, keyring.active_key_id());
+    }
+    Ok(reencrypted)
+}
+
+// Background task running `reencrypt_stale` every REENCRYPT_INTERVAL_MINUTES
+pub fn spawn_scheduled_reencryption(db: DatabaseConnection, keyring: Keyring, config: &Config) {
+    if config.reencrypt_interval_minutes == 0 {
+        return;
+    }
+    let period = std::time::Duration::from_secs(config.reencrypt_interval_minutes * 60);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = reencrypt_stale(&db, &keyring).await {
+                tracing::error!("Re-encryption failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        STANDARD.encode([byte; 32])
+    }
+
+    fn keyring(keys: &[(&str, u8)], active: &str) -> Keyring {
+        let keys: Vec<(String, String)> = keys.iter().map(|(id, byte)| (id.to_string(), key(*byte))).collect();
+        Keyring::new(&keys, Some(active), &key(9)).unwrap()
+    }
+
+    const FIELD: Field = Field { table: "user_profiles", column: "address", row_id: 7 };
+
+    #[test]
+    fn sealed_values_round_trip_and_differ_each_time() {
+        let keyring = keyring(&[("k1", 1)], "k1");
+        let first = keyring.seal("AB 123456", FIELD);
+        let second = keyring.seal("AB 123456", FIELD);
+
+        assert_ne!(first, second);
+        assert_eq!(first.key_id(), Some("k1"));
+        assert_eq!(keyring.open(&first, FIELD).unwrap(), "AB 123456");
+        assert!(!format!("{:?}", first).contains("AB"));
+    }
+
+    #[test]
+    fn rotated_keyring_still_opens_old_values() {
+        let old = keyring(&[("k1", 1)], "k1");
+        let sealed = old.seal("peanut allergy", FIELD);
+
+        let rotated = keyring(&[("k1", 1), ("k2", 2)], "k2");
+        assert_eq!(rotated.open(&sealed, FIELD).unwrap(), "peanut allergy");
+        assert_eq!(rotated.seal("x", FIELD).key_id(), Some("k2"));
+
+        let retired = keyring(&[("k2", 2)], "k2");
+        assert!(matches!(retired.open(&sealed, FIELD), Err(EncryptionError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn tampered_values_are_rejected() {
+        let keyring = keyring(&[("k1", 1)], "k1");
+        let EncryptedString(sealed) = keyring.seal("secret", FIELD);
+        let mut tampered = sealed.into_bytes();
+        let last = tampered.len() - 2;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+
+        let tampered = EncryptedString(String::from_utf8(tampered).unwrap());
+        assert!(keyring.open(&tampered, FIELD).is_err());
+        assert!(matches!(
+            keyring.open(&EncryptedString("plain text".to_string()), FIELD),
+            Err(EncryptionError::Malformed)
+        ));
+    }
+
+    #[test]
+    fn values_only_open_in_the_field_they_were_sealed_for() {
+        let keyring = keyring(&[("k1", 1)], "k1");
+        let sealed = keyring.seal("12 Elm Street", FIELD);
+
+        for other in [
+            Field { row_id: 8, ..FIELD },
+            Field { column: "medical_notes", ..FIELD },
+            Field { table: "admission_applications", ..FIELD },
+        ] {
+            assert!(matches!(keyring.open(&sealed, other), Err(EncryptionError::Decrypt)));
+        }
+        assert_eq!(keyring.open(&sealed, FIELD).unwrap(), "12 Elm Street");
+    }
+
+    #[test]
+    fn legacy_values_open_without_a_field() {
+        let keyring = keyring(&[("k1", 1)], "k1");
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let legacy = EncryptedString(format!(
+            "{}k1:{}:{}",
+            LEGACY_FORMAT_PREFIX,
+            STANDARD.encode(encrypt(&keyring.keys["k1"], &data_key, &[])),
+            STANDARD.encode(encrypt(&data_key, b"old value", &[]))
+        ));
+
+        assert_eq!(legacy.key_id(), Some("k1"));
+        assert_eq!(keyring.open(&legacy, FIELD).unwrap(), "old value");
+        assert!(keyring.seal("new value", FIELD).0.starts_with("v2:"));
+    }
+
+    #[test]
+    fn blind_index_ignores_formatting_but_not_content() {
+        let keyring = keyring(&[("k1", 1)], "k1");
+        assert_eq!(keyring.blind_index("ab 12-34"), keyring.blind_index("AB1234"));
+        assert_ne!(keyring.blind_index("AB1234"), keyring.blind_index("AB1235"));
+
+        let other = Keyring::new(&[("k1".to_string(), key(1))], None, &key(8)).unwrap();
+        assert_ne!(keyring.blind_index("AB1234"), other.blind_index("AB1234"));
+    }
+}
@@ -0,0 +1,210 @@
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+pub const CATEGORIES: &[&str] = &["tuition", "transport", "lab", "exam", "other"];
+pub const DISCOUNT_KINDS: &[&str] = &["discount", "scholarship"];
+pub const PAYMENT_METHODS: &[&str] = &["cash", "bank_transfer", "card", "mobile_money", "cheque"];
+
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_PARTIALLY_PAID: &str = "partially_paid";
+pub const STATUS_PAID: &str = "paid";
+
+// Days past due at which each aging bucket starts, after "current"
+pub const AGING_BUCKETS: &[(&str, i64)] = &[("current", 0), ("1-30", 1), ("31-60", 31), ("61-90", 61), ("90+", 91)];
+
+// Who may set up fees, issue invoices and record payments
+pub const FEE_ROLES: &[&str] = &["admin", "bursar"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeeError {
+    #[error("invoice not found")]
+    InvoiceNotFound,
+    #[error("payment of {amount} is more than the outstanding balance of {balance}")]
+    Overpayment { amount: Decimal, balance: Decimal },
+    #[error("invoice is already paid")]
+    AlreadyPaid,
+    #[error("payment not found")]
+    PaymentNotFound,
+    #[error("refund of {amount} is more than the {refundable} left to refund")]
+    RefundTooLarge { amount: Decimal, refundable: Decimal },
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+// Cents, half away from zero: what a bursar would write by hand
+pub fn round_money(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+// A line of an invoice before it is stored; discounts are negative
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeLine {
+    pub category: String,
+    pub description: String,
+    pub amount: Decimal,
+}
+
+// A discount or scholarship as it applies to one invoice
+#[derive(Debug, Clone)]
+pub struct DiscountRule {
+    pub name: String,
+    pub kind: String,
+    pub percent: Option<Decimal>,
+    pub amount: Option<Decimal>,
+    pub category: Option<String>,
+}
+
+impl DiscountRule {
+    fn covers(&self, category: &str) -> bool {
+        self.category.as_deref().is_none_or(|covered| covered == category)
+    }
+}
+
+// Discount lines for a set of fees. Percentages are taken off the fees they
+// cover before fixed amounts, and together they never take a category below
+// zero, so stacked awards cannot produce a credit.
+pub fn discount_lines(fees: &[FeeLine], rules: &[DiscountRule]) -> Vec<FeeLine> {
+    let mut remaining: Vec<(&str, Decimal)> = Vec::new();
+    for fee in fees {
+        match remaining.iter_mut().find(|(category, _)| *category == fee.category) {
+            Some((_, amount)) => *amount += fee.amount,
+            None => remaining.push((&fee.category, fee.amount)),
+        }
+    }
+
+    let ordered = rules
+        .iter()
+        .filter(|rule| rule.percent.is_some())
+        .chain(rules.iter().filter(|rule| rule.percent.is_none()));
+    let mut lines = Vec::new();
+    for rule in ordered {
+        let base: Decimal = fees.iter().filter(|fee| rule.covers(&fee.category)).map(|fee| fee.amount).sum();
+        let available: Decimal = remaining
+            .iter()
+            .filter(|(category, _)| rule.covers(category))
+            .map(|(_, amount)| *amount)
+            .sum();
+        let wanted = match (rule.percent, rule.amount) {
+            (Some(percent), _) => round_money(base * percent / Decimal::ONE_HUNDRED),
+            (None, Some(amount)) => amount,
+            (None, None) => Decimal::ZERO,
+        };
+        let discount = wanted.min(available);
+        if discount <= Decimal::ZERO {
+            continue;
+        }
+
+        let mut left = discount;
+        for (_, amount) in remaining.iter_mut().filter(|(category, _)| rule.covers(category)) {
+            let taken = left.min(*amount);
+            *amount -= taken;
+            left -= taken;
+        }
+        lines.push(FeeLine {
+            category: rule.category.clone().unwrap_or_else(|| "all".to_string()),
+            description: format!("{} ({})", rule.name, rule.kind),
+            amount: -discount,
+        });
+    }
+    lines
+}
+
+pub fn invoice_status(total: Decimal, paid: Decimal) -> &'static str {
+    if paid >= total {
+        STATUS_PAID
+    } else if paid > Decimal::ZERO {
+        STATUS_PARTIALLY_PAID
+    } else {
+        STATUS_OPEN
+    }
+}
+
+// Index into AGING_BUCKETS of a balance due on `due_date`
+pub fn aging_bucket(due_date: NaiveDate, as_of: NaiveDate) -> usize {
+    let days_overdue = (as_of - due_date).num_days();
+    AGING_BUCKETS
+        .iter()
+        .rposition(|(_, from)| days_overdue >= *from)
+        .unwrap_or(0)
+}
+
+pub fn invoice_number(id: i32) -> String {
+    format!("INV-{:06}", id)
+}
+
+pub fn receipt_number(id: i32) -> String {
+    format!("RCT-{:06}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn fee(category: &str, amount: Decimal) -> FeeLine {
+        FeeLine {
+            category: category.to_string(),
+            description: category.to_string(),
+            amount,
+        }
+    }
+
+    fn rule(name: &str, percent: Option<Decimal>, amount: Option<Decimal>, category: Option<&str>) -> DiscountRule {
+        DiscountRule {
+            name: name.to_string(),
+            kind: "scholarship".to_string(),
+            percent,
+            amount,
+            category: category.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn discounts_stack_without_going_negative() {
+        let fees = [fee("tuition", dec!(1000.00)), fee("transport", dec!(150.00)), fee("lab", dec!(33.33))];
+
+        let lines = discount_lines(
+            &fees,
+            &[
+                rule("Sibling", None, Some(dec!(100.00)), None),
+                rule("Merit", Some(dec!(12.5)), None, Some("tuition")),
+                rule("Lab bursary", Some(dec!(50)), None, Some("lab")),
+            ],
+        );
+        let amounts: Vec<Decimal> = lines.iter().map(|line| line.amount).collect();
+        // Percentages first; half a cent rounds away from zero
+        assert_eq!(amounts, vec![dec!(-125.00), dec!(-16.67), dec!(-100.00)]);
+        assert_eq!(lines[0].category, "tuition");
+        assert_eq!(lines[2].category, "all");
+
+        let capped = discount_lines(
+            &fees,
+            &[
+                rule("Full transport", Some(dec!(100)), None, Some("transport")),
+                rule("Transport grant", None, Some(dec!(80.00)), Some("transport")),
+            ],
+        );
+        assert_eq!(capped.len(), 1);
+        assert_eq!(capped[0].amount, dec!(-150.00));
+    }
+
+    #[test]
+    fn status_follows_payments() {
+        assert_eq!(invoice_status(dec!(500.00), dec!(0)), STATUS_OPEN);
+        assert_eq!(invoice_status(dec!(500.00), dec!(0.01)), STATUS_PARTIALLY_PAID);
+        assert_eq!(invoice_status(dec!(500.00), dec!(500.00)), STATUS_PAID);
+        assert_eq!(invoice_status(dec!(0), dec!(0)), STATUS_PAID);
+    }
+
+    #[test]
+    fn balances_age_from_the_due_date() {
+        let due = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        let bucket = |y, m, d| AGING_BUCKETS[aging_bucket(due, NaiveDate::from_ymd_opt(y, m, d).unwrap())].0;
+        assert_eq!(bucket(2026, 1, 15), "current");
+        assert_eq!(bucket(2026, 1, 31), "current");
+        assert_eq!(bucket(2026, 2, 1), "1-30");
+        assert_eq!(bucket(2026, 3, 2), "1-30");
+        assert_eq!(bucket(2026, 3, 3), "31-60");
+        assert_eq!(bucket(2026, 5, 2), "90+");
+    }
+}
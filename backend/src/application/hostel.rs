@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+// Who may manage rooms, allocations and check-in/check-out
+pub const HOSTEL_ROLES: &[&str] = &["admin", "warden"];
+
+// Rooms house one gender; users carry no gender, so it is given when a
+// student is allocated
+pub const GENDERS: &[&str] = &["female", "male"];
+
+pub const ALLOCATED: &str = "allocated";
+pub const CHECKED_IN: &str = "checked_in";
+pub const CHECKED_OUT: &str = "checked_out";
+
+pub const CHECK_IN: &str = "check_in";
+pub const CHECK_OUT: &str = "check_out";
+
+#[derive(Debug, thiserror::Error)]
+pub enum HostelError {
+    #[error("room not found")]
+    RoomNotFound,
+    #[error("bed not found")]
+    BedNotFound,
+    #[error("student not found")]
+    StudentNotFound,
+    #[error("allocation not found")]
+    AllocationNotFound,
+    #[error("a room named {0} already exists")]
+    RoomNameTaken(String),
+    #[error("room already has a bed labelled {0}")]
+    BedLabelTaken(String),
+    #[error("gender must be one of: female, male")]
+    InvalidGender,
+    #[error("bed {0} is already allocated this term")]
+    BedTaken(String),
+    #[error("student already has a bed this term")]
+    AlreadyAllocated,
+    #[error("room {0} is for {1} students")]
+    WrongGender(String, String),
+    #[error("room {0} is reserved for {1}")]
+    WrongGrade(String, String),
+    #[error("room or bed is not in use")]
+    Inactive,
+    #[error("student is already checked in")]
+    AlreadyCheckedIn,
+    #[error("student is not checked in")]
+    NotCheckedIn,
+    #[error("a checked-in student cannot lose their bed; check them out first")]
+    StillCheckedIn,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+pub fn check_gender(gender: &str) -> Result<(), HostelError> {
+    if !GENDERS.contains(&gender) {
+        return Err(HostelError::InvalidGender);
+    }
+    Ok(())
+}
+
+// Whether a student of `gender` in `grade` may sleep in a room
+pub fn check_room(
+    room: &str,
+    room_gender: &str,
+    room_grade: Option<&str>,
+    gender: &str,
+    grade: &str,
+) -> Result<(), HostelError> {
+    if room_gender != gender {
+        return Err(HostelError::WrongGender(room.to_string(), room_gender.to_string()));
+    }
+    match room_grade {
+        Some(room_grade) if room_grade != grade => Err(HostelError::WrongGrade(room.to_string(), room_grade.to_string())),
+        _ => Ok(()),
+    }
+}
+
+// Which stay event moves an allocation from `status`, and where it ends up.
+// Students may leave and return any number of times during a term.
+pub fn next_status(status: &str, event: &str) -> Result<&'static str, HostelError> {
+    match (status, event) {
+        (ALLOCATED | CHECKED_OUT, CHECK_IN) => Ok(CHECKED_IN),
+        (CHECKED_IN, CHECK_IN) => Err(HostelError::AlreadyCheckedIn),
+        (CHECKED_IN, CHECK_OUT) => Ok(CHECKED_OUT),
+        _ => Err(HostelError::NotCheckedIn),
+    }
+}
+
+// A room as the allocator sees it for one term
+#[derive(Debug, Clone)]
+pub struct RoomVacancy {
+    pub room_id: i32,
+    pub gender: String,
+    pub grade: Option<String>,
+    // Free active beds, in the order they should be filled
+    pub free_beds: Vec<i32>,
+    // Grades of the students already allocated to the room
+    pub occupant_grades: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Boarder {
+    pub student_id: i32,
+    pub gender: String,
+    pub grade: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Placement {
+    // (student_id, bed_id)
+    pub placed: Vec<(i32, i32)>,
+    // (student_id, reason)
+    pub unplaced: Vec<(i32, String)>,
+}
+
+// Place boarders into free beds. A room only takes its own gender and,
+// when reserved, its own grade; a room with occupants only takes more of
+// their grade, so year groups share rooms. Rooms already holding the
+// boarder's grade are filled first, fullest first, before an empty room is
+// opened, keeping empty rooms free for later arrivals. Boarders are placed
+// grade by grade so classmates end up together.
+pub fn allocate(rooms: &[RoomVacancy], boarders: &[Boarder]) -> Placement {
+    let mut rooms: Vec<RoomVacancy> = rooms.to_vec();
+    for room in &mut rooms {
+        room.free_beds.reverse();
+    }
+    let mut boarders: Vec<&Boarder> = boarders.iter().collect();
+    boarders.sort_by(|a, b| (&a.grade, a.student_id).cmp(&(&b.grade, b.student_id)));
+
+    let mut placement = Placement::default();
+    for boarder in boarders {
+        let chosen = rooms
+            .iter_mut()
+            .filter(|room| {
+                room.gender == boarder.gender
+                    && room.grade.as_ref().is_none_or(|grade| *grade == boarder.grade)
+                    && !room.free_beds.is_empty()
+                    && room.occupant_grades.iter().all(|grade| *grade == boarder.grade)
+            })
+            .min_by_key(|room| (room.occupant_grades.is_empty(), room.free_beds.len(), room.room_id));
+        match chosen {
+            Some(room) => {
+                let bed_id = room.free_beds.pop().expect("room has a free bed");
+                room.occupant_grades.push(boarder.grade.clone());
+                placement.placed.push((boarder.student_id, bed_id));
+            }
+            None => placement.unplaced.push((
+                boarder.student_id,
+                format!("no free bed for {} students in {}", boarder.gender, boarder.grade),
+            )),
+        }
+    }
+    placement
+}
+
+// Beds and who is in them, for one room in the occupancy report
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Occupancy {
+    pub beds: usize,
+    pub allocated: usize,
+    pub checked_in: usize,
+}
+
+// Tally allocation statuses per room. `beds` counts active beds per room.
+pub fn occupancy(beds: &HashMap<i32, usize>, statuses: &[(i32, String)]) -> HashMap<i32, Occupancy> {
+    let mut report: HashMap<i32, Occupancy> = beds
+        .iter()
+        .map(|(room_id, beds)| {
+            (
+                *room_id,
+                Occupancy {
+                    beds: *beds,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    for (room_id, status) in statuses {
+        let room = report.entry(*room_id).or_default();
+        room.allocated += 1;
+        if status == CHECKED_IN {
+            room.checked_in += 1;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(room_id: i32, gender: &str, grade: Option<&str>, free_beds: &[i32], occupants: &[&str]) -> RoomVacancy {
+        RoomVacancy {
+            room_id,
+            gender: gender.to_string(),
+            grade: grade.map(str::to_string),
+            free_beds: free_beds.to_vec(),
+            occupant_grades: occupants.iter().map(|grade| grade.to_string()).collect(),
+        }
+    }
+
+    fn boarder(student_id: i32, gender: &str, grade: &str) -> Boarder {
+        Boarder {
+            student_id,
+            gender: gender.to_string(),
+            grade: grade.to_string(),
+        }
+    }
+
+    #[test]
+    fn allocation_respects_gender_grade_and_capacity() {
+        let rooms = [
+            room(1, "female", None, &[10, 11], &[]),
+            room(2, "male", Some("Grade 8"), &[20, 21], &[]),
+            room(3, "male", None, &[30], &["Grade 9"]),
+        ];
+        let boarders = [
+            boarder(1, "female", "Grade 8"),
+            boarder(2, "male", "Grade 8"),
+            boarder(3, "male", "Grade 9"),
+            boarder(4, "male", "Grade 9"),
+            boarder(5, "female", "Grade 8"),
+            boarder(6, "female", "Grade 8"),
+        ];
+        let placement = allocate(&rooms, &boarders);
+
+        assert_eq!(placement.placed, vec![(1, 10), (2, 20), (5, 11), (3, 30)]);
+        let unplaced: Vec<i32> = placement.unplaced.iter().map(|(student, _)| *student).collect();
+        assert_eq!(unplaced, vec![6, 4]);
+        assert_eq!(placement.unplaced[1].1, "no free bed for male students in Grade 9");
+    }
+
+    #[test]
+    fn allocation_fills_rooms_of_the_same_grade_first() {
+        let rooms = [
+            room(1, "female", None, &[10, 11, 12], &[]),
+            room(2, "female", None, &[20, 21], &[]),
+            room(3, "female", None, &[30, 31], &["Grade 7"]),
+        ];
+        let placement = allocate(&rooms, &[boarder(1, "female", "Grade 7"), boarder(2, "female", "Grade 7")]);
+        assert_eq!(placement.placed, vec![(1, 30), (2, 31)]);
+
+        let placement = allocate(&rooms, &[boarder(1, "female", "Grade 10")]);
+        assert_eq!(placement.placed, vec![(1, 20)]);
+    }
+
+    #[test]
+    fn stays_move_between_checked_in_and_out() {
+        assert_eq!(next_status(ALLOCATED, CHECK_IN).unwrap(), CHECKED_IN);
+        assert_eq!(next_status(CHECKED_IN, CHECK_OUT).unwrap(), CHECKED_OUT);
+        assert_eq!(next_status(CHECKED_OUT, CHECK_IN).unwrap(), CHECKED_IN);
+        assert!(matches!(next_status(CHECKED_IN, CHECK_IN), Err(HostelError::AlreadyCheckedIn)));
+        assert!(matches!(next_status(ALLOCATED, CHECK_OUT), Err(HostelError::NotCheckedIn)));
+
+        assert!(check_room("A1", "male", Some("Grade 8"), "male", "Grade 8").is_ok());
+        assert!(matches!(
+            check_room("A1", "male", None, "female", "Grade 8"),
+            Err(HostelError::WrongGender(_, _))
+        ));
+        assert!(matches!(
+            check_room("A1", "male", Some("Grade 8"), "male", "Grade 9"),
+            Err(HostelError::WrongGrade(_, _))
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use axum::http::Method;
+
+use crate::application::auth::Claims;
+use crate::dto::user::UserResponse;
+
+// Response header telling the frontend to show the "viewing as" banner
+pub const BANNER_HEADER: &str = "x-impersonated-by";
+
+// The one write an impersonated token may make: ending the impersonation
+pub const STOP_PATH: &str = "/api/v1/auth/impersonation/stop";
+
+// Impersonation is for looking, not for acting as someone: only safe methods
+// get through, so an admin cannot submit work or change settings as a student
+pub fn allows(method: &Method, path: &str) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) || path == STOP_PATH
+}
+
+// Why `admin` may not impersonate `target`, if they may not
+pub fn refusal(admin: &Claims, target: &UserResponse) -> Option<&'static str> {
+    if admin.role != "admin" {
+        Some("Only admins can impersonate users")
+    } else if admin.act.is_some() {
+        Some("Stop the current impersonation first")
+    } else if admin.user_id() == Some(target.id) {
+        Some("You cannot impersonate yourself")
+    } else if target.role == "admin" {
+        // Otherwise impersonation would be a way around another admin's 2FA
+        Some("Admins cannot be impersonated")
+    } else if !target.is_active {
+        Some("Deactivated users cannot be impersonated")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::auth::Actor;
+
+    fn claims(id: i32, role: &str) -> Claims {
+        Claims {
+            sub: id.to_string(),
+            email: format!("user{}@school.local", id),
+            role: role.to_string(),
+            iat: 0,
+            exp: 0,
+            tid: 1,
+            act: None,
+        }
+    }
+
+    fn user(id: i32, role: &str, is_active: bool) -> UserResponse {
+        UserResponse {
+            id,
+            tenant_id: 1,
+            email: format!("user{}@school.local", id),
+            full_name: "Test User".to_string(),
+            role: role.to_string(),
+            is_active,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn impersonated_tokens_are_read_only() {
+        assert!(allows(&Method::GET, "/api/v1/users/7"));
+        assert!(allows(&Method::POST, STOP_PATH));
+        assert!(!allows(&Method::POST, "/api/v1/auth/password/change"));
+        assert!(!allows(&Method::DELETE, "/api/v1/api-keys/3"));
+    }
+
+    #[test]
+    fn only_admins_impersonate_active_non_admins() {
+        let admin = claims(1, "admin");
+        assert_eq!(refusal(&admin, &user(2, "student", true)), None);
+        assert!(refusal(&claims(3, "teacher"), &user(2, "student", true)).is_some());
+        assert!(refusal(&admin, &user(1, "admin", true)).is_some());
+        assert!(refusal(&admin, &user(4, "admin", true)).is_some());
+        assert!(refusal(&admin, &user(2, "student", false)).is_some());
+
+        let impersonating = Claims {
+            act: Some(Actor {
+                sub: "9".to_string(),
+                email: "root@school.local".to_string(),
+                session_id: 1,
+            }),
+            ..admin
+        };
+        assert!(refusal(&impersonating, &user(2, "student", true)).is_some());
+    }
+}
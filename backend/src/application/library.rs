@@ -0,0 +1,571 @@
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::application::fees::round_money;
+use crate::config::Config;
+
+// Who may catalog, lend and take back items
+pub const LIBRARY_ROLES: &[&str] = &["admin", "librarian"];
+
+pub const COPY_AVAILABLE: &str = "available";
+pub const COPY_ON_LOAN: &str = "on_loan";
+// Set aside for a ready hold
+pub const COPY_ON_HOLD_SHELF: &str = "on_hold_shelf";
+pub const COPY_LOST: &str = "lost";
+pub const COPY_WITHDRAWN: &str = "withdrawn";
+// Statuses staff may set by hand; the others follow loans and holds
+pub const SHELF_STATUSES: &[&str] = &[COPY_AVAILABLE, COPY_LOST, COPY_WITHDRAWN];
+
+pub const HOLD_WAITING: &str = "waiting";
+pub const HOLD_READY: &str = "ready";
+pub const HOLD_FULFILLED: &str = "fulfilled";
+pub const HOLD_CANCELLED: &str = "cancelled";
+pub const HOLD_EXPIRED: &str = "expired";
+
+pub const FINE_NONE: &str = "none";
+pub const FINE_OWED: &str = "owed";
+pub const FINE_PAID: &str = "paid";
+pub const FINE_WAIVED: &str = "waived";
+
+#[derive(Debug, thiserror::Error)]
+pub enum LibraryError {
+    #[error("no copy with that barcode")]
+    CopyNotFound,
+    #[error("barcode {0} is already in the catalog")]
+    DuplicateBarcode(String),
+    #[error("copy is {0}")]
+    CopyUnavailable(String),
+    #[error("copy is set aside for another reader's hold")]
+    ReservedForHold,
+    #[error("borrower not found")]
+    BorrowerNotFound,
+    #[error("borrower already has {0} items on loan")]
+    LoanLimit(usize),
+    #[error("borrower owes {0} in library fines")]
+    FinesOwed(Decimal),
+    #[error("loan not found")]
+    LoanNotFound,
+    #[error("loan was already returned")]
+    AlreadyReturned,
+    #[error("loan was renewed the most times allowed ({0})")]
+    RenewalLimit(i32),
+    #[error("overdue loans cannot be renewed")]
+    Overdue,
+    #[error("other readers are waiting for this title")]
+    HoldsWaiting,
+    #[error("title not found")]
+    TitleNotFound,
+    #[error("a copy is on the shelf; borrow it instead of placing a hold")]
+    CopyAvailable,
+    #[error("reader already has this title on loan or on hold")]
+    AlreadyHolding,
+    #[error("hold not found")]
+    HoldNotFound,
+    #[error("no fine is owed on this loan")]
+    NoFineOwed,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+// Lending rules, the same for every school
+#[derive(Debug, Clone)]
+pub struct LibraryPolicy {
+    pub loan_days: u64,
+    pub max_renewals: i32,
+    pub max_loans: usize,
+    // Charged per day overdue, up to `max_fine` a loan
+    pub fine_per_day: Decimal,
+    pub max_fine: Decimal,
+    // Borrowing stops once unpaid fines reach this
+    pub fine_block_amount: Decimal,
+    // Days a returned copy waits on the hold shelf for its reader
+    pub hold_days: u64,
+}
+
+impl LibraryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            loan_days: config.library_loan_days,
+            max_renewals: config.library_max_renewals,
+            max_loans: config.library_max_loans,
+            fine_per_day: config.library_fine_per_day,
+            max_fine: config.library_max_fine,
+            fine_block_amount: config.library_fine_block_amount,
+            hold_days: config.library_hold_days,
+        }
+    }
+
+    pub fn due_date(&self, from: NaiveDate) -> NaiveDate {
+        from + Days::new(self.loan_days)
+    }
+
+    pub fn hold_expiry(&self, today: NaiveDate) -> NaiveDate {
+        today + Days::new(self.hold_days)
+    }
+
+    // Fine for returning on `returned_on` a loan due on `due_date`
+    pub fn fine(&self, due_date: NaiveDate, returned_on: NaiveDate) -> Decimal {
+        let days_overdue = (returned_on - due_date).num_days();
+        if days_overdue <= 0 {
+            return Decimal::ZERO;
+        }
+        round_money(self.fine_per_day * Decimal::from(days_overdue)).min(self.max_fine)
+    }
+
+    // New due date for a renewal, a full loan period from today
+    pub fn renewal(
+        &self,
+        due_date: NaiveDate,
+        renewals: i32,
+        today: NaiveDate,
+        holds_waiting: bool,
+    ) -> Result<NaiveDate, LibraryError> {
+        if renewals >= self.max_renewals {
+            return Err(LibraryError::RenewalLimit(self.max_renewals));
+        }
+        if today > due_date {
+            return Err(LibraryError::Overdue);
+        }
+        if holds_waiting {
+            return Err(LibraryError::HoldsWaiting);
+        }
+        Ok(self.due_date(today).max(due_date))
+    }
+}
+
+// ISBN-13 for an ISBN-10 or ISBN-13 with or without hyphens and spaces; None
+// when the check digit is wrong
+pub fn normalize_isbn(value: &str) -> Option<String> {
+    let chars: Vec<char> = value
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    match chars.len() {
+        10 => {
+            let mut sum = 0;
+            for (i, c) in chars.iter().enumerate() {
+                let digit = match (i, c) {
+                    (9, 'X') => 10,
+                    (_, c) => c.to_digit(10)?,
+                };
+                sum += digit * (10 - i as u32);
+            }
+            if sum % 11 != 0 {
+                return None;
+            }
+            let stem: String = "978".chars().chain(chars[..9].iter().copied()).collect();
+            Some(format!("{}{}", stem, isbn13_check_digit(&stem)?))
+        }
+        13 => {
+            let digits: String = chars.iter().collect();
+            let check = digits[12..].parse::<u32>().ok()?;
+            (isbn13_check_digit(&digits[..12])? == check).then_some(digits)
+        }
+        _ => None,
+    }
+}
+
+fn isbn13_check_digit(stem: &str) -> Option<u32> {
+    let mut sum = 0;
+    for (i, c) in stem.chars().enumerate() {
+        sum += c.to_digit(10)? * if i % 2 == 0 { 1 } else { 3 };
+    }
+    Some((10 - sum % 10) % 10)
+}
+
+// One catalog record read from an import file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportRecord {
+    pub isbn: Option<String>,
+    pub title: String,
+    pub author: Option<String>,
+    pub publisher: Option<String>,
+    pub published_year: Option<i32>,
+    pub copies: Vec<ImportCopy>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportCopy {
+    pub barcode: String,
+    pub location: Option<String>,
+}
+
+// Records read from a file, and those that could not be read by position
+// (MARC record or CSV line, from 1)
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub records: Vec<ImportRecord>,
+    pub errors: Vec<(usize, String)>,
+}
+
+const MARC_RECORD_END: u8 = 0x1D;
+const MARC_FIELD_END: u8 = 0x1E;
+const MARC_SUBFIELD: u8 = 0x1F;
+
+// A data field of a MARC record: its tag and subfields in order
+#[derive(Debug)]
+struct MarcField {
+    tag: String,
+    subfields: Vec<(char, String)>,
+}
+
+impl MarcField {
+    fn first(&self, code: char) -> Option<&str> {
+        self.subfields
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Split a MARC21 (ISO 2709) file into catalog records. Titles come from 245,
+// authors from 100/110, ISBNs from 020, imprint from 264 or 260, and one copy
+// per 852 or 952 holding that has a barcode ($p).
+pub fn parse_marc(bytes: &[u8]) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    let records = bytes
+        .split(|byte| *byte == MARC_RECORD_END)
+        .map(|record| record.trim_ascii_start())
+        .filter(|record| !record.is_empty());
+    for (i, record) in records.enumerate() {
+        let position = i + 1;
+        match marc_fields(record).and_then(|fields| marc_record(&fields)) {
+            Ok(record) => parsed.records.push(record),
+            Err(message) => parsed.errors.push((position, message)),
+        }
+    }
+    parsed
+}
+
+fn marc_fields(record: &[u8]) -> Result<Vec<MarcField>, String> {
+    if record.len() < 24 {
+        return Err("record is shorter than its leader".to_string());
+    }
+    let number = |range: std::ops::Range<usize>| -> Result<usize, String> {
+        std::str::from_utf8(&record[range])
+            .ok()
+            .and_then(|digits| digits.trim().parse().ok())
+            .ok_or_else(|| "leader or directory has a malformed number".to_string())
+    };
+    let base = number(12..17)?;
+    if base > record.len() || base < 25 {
+        return Err("base address of data is out of range".to_string());
+    }
+
+    let mut fields = Vec::new();
+    // Directory entries run from the leader to the field terminator before the data
+    let directory = &record[24..base - 1];
+    for entry in directory.chunks(12) {
+        if entry.len() < 12 {
+            return Err("directory entry is truncated".to_string());
+        }
+        let tag = String::from_utf8_lossy(&entry[0..3]).to_string();
+        let length = number_in(entry, 3..7)?;
+        let start = base + number_in(entry, 7..12)?;
+        let data = record
+            .get(start..start + length)
+            .ok_or_else(|| format!("field {} runs past the end of the record", tag))?;
+        let data = data.strip_suffix(&[MARC_FIELD_END]).unwrap_or(data);
+
+        // Control fields (001-009) have no indicators or subfields
+        if tag.starts_with("00") {
+            continue;
+        }
+        let subfields = data
+            .split(|byte| *byte == MARC_SUBFIELD)
+            .skip(1)
+            .filter_map(|subfield| {
+                let (code, value) = subfield.split_first()?;
+                Some((*code as char, String::from_utf8_lossy(value).trim().to_string()))
+            })
+            .collect();
+        fields.push(MarcField { tag, subfields });
+    }
+    Ok(fields)
+}
+
+fn number_in(entry: &[u8], range: std::ops::Range<usize>) -> Result<usize, String> {
+    std::str::from_utf8(&entry[range])
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| "directory entry has a malformed number".to_string())
+}
+
+// MARC values end in ISBD punctuation (" /", " :", ","); catalogs do not want it
+fn strip_isbd(value: &str) -> String {
+    value
+        .trim_end_matches(|c: char| matches!(c, '/' | ':' | ';' | ',' | '.' | '=') || c.is_whitespace())
+        .to_string()
+}
+
+fn marc_record(fields: &[MarcField]) -> Result<ImportRecord, String> {
+    let field = |tag: &str| fields.iter().find(|field| field.tag == tag);
+
+    let title_field = field("245").ok_or("record has no title (245)")?;
+    let title = [title_field.first('a'), title_field.first('b')]
+        .into_iter()
+        .flatten()
+        .map(strip_isbd)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(": ");
+    if title.is_empty() {
+        return Err("record has an empty title (245 $a)".to_string());
+    }
+
+    // 020 $a may carry a qualifier: "0306406152 (pbk.)"
+    let isbn = fields
+        .iter()
+        .filter(|field| field.tag == "020")
+        .filter_map(|field| field.first('a'))
+        .find_map(|value| normalize_isbn(value.split_whitespace().next().unwrap_or_default()));
+    let author = field("100")
+        .or_else(|| field("110"))
+        .and_then(|field| field.first('a'))
+        .map(strip_isbd);
+    let imprint = field("264").or_else(|| field("260"));
+    let publisher = imprint.and_then(|field| field.first('b')).map(strip_isbd);
+    let published_year = imprint.and_then(|field| field.first('c')).and_then(first_year);
+
+    let copies = fields
+        .iter()
+        .filter(|field| field.tag == "852" || field.tag == "952")
+        .filter_map(|field| {
+            let barcode = field.first('p').filter(|barcode| !barcode.is_empty())?;
+            let location = if field.tag == "852" { field.first('b') } else { field.first('c') };
+            Some(ImportCopy {
+                barcode: barcode.to_string(),
+                location: location.map(str::to_string),
+            })
+        })
+        .collect();
+
+    Ok(ImportRecord {
+        isbn,
+        title,
+        author,
+        publisher,
+        published_year,
+        copies,
+    })
+}
+
+// "c2019." or "[2019?]" -> 2019
+fn first_year(value: &str) -> Option<i32> {
+    value
+        .as_bytes()
+        .windows(4)
+        .find(|window| window.iter().all(u8::is_ascii_digit))
+        .and_then(|window| std::str::from_utf8(window).ok()?.parse().ok())
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    #[serde(default)]
+    isbn: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    publisher: Option<String>,
+    #[serde(default)]
+    year: Option<String>,
+    #[serde(default)]
+    barcode: Option<String>,
+    #[serde(default)]
+    location: Option<String>,
+}
+
+// A CSV with a header row naming any of isbn, title, author, publisher, year,
+// barcode and location (title is required). Each row with a barcode is one
+// copy; rows for the same ISBN are copies of one title.
+pub fn parse_csv(bytes: &[u8]) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(bytes);
+    match reader.headers() {
+        Ok(headers) => {
+            let lowered: csv::StringRecord = headers.iter().map(str::to_lowercase).collect();
+            reader.set_headers(lowered);
+        }
+        Err(e) => {
+            parsed.errors.push((1, format!("unreadable header row: {}", e)));
+            return parsed;
+        }
+    }
+
+    let blank = |value: Option<String>| value.filter(|value| !value.is_empty());
+    for (i, row) in reader.deserialize::<CsvRow>().enumerate() {
+        // Data starts on line 2, after the header
+        let line = i + 2;
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                parsed.errors.push((line, e.to_string()));
+                continue;
+            }
+        };
+        let Some(title) = blank(row.title) else {
+            parsed.errors.push((line, "title is required".to_string()));
+            continue;
+        };
+        let isbn = match blank(row.isbn) {
+            Some(isbn) => match normalize_isbn(&isbn) {
+                Some(isbn) => Some(isbn),
+                None => {
+                    parsed.errors.push((line, format!("{} is not a valid ISBN", isbn)));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let published_year = match blank(row.year) {
+            Some(year) => match year.parse() {
+                Ok(year) => Some(year),
+                Err(_) => {
+                    parsed.errors.push((line, format!("{} is not a year", year)));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let copies = blank(row.barcode)
+            .map(|barcode| ImportCopy {
+                barcode,
+                location: blank(row.location),
+            })
+            .into_iter()
+            .collect();
+        parsed.records.push(ImportRecord {
+            isbn,
+            title,
+            author: blank(row.author),
+            publisher: blank(row.publisher),
+            published_year,
+            copies,
+        });
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn policy() -> LibraryPolicy {
+        LibraryPolicy {
+            loan_days: 14,
+            max_renewals: 2,
+            max_loans: 5,
+            fine_per_day: dec!(0.25),
+            max_fine: dec!(5.00),
+            fine_block_amount: dec!(5.00),
+            hold_days: 3,
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    // Build an ISO 2709 record from (tag, indicators + subfields) pairs
+    fn marc(fields: &[(&str, &str)]) -> Vec<u8> {
+        let mut directory = Vec::new();
+        let mut data = Vec::new();
+        for (tag, body) in fields {
+            let mut field = body.replace('$', "\u{1F}").into_bytes();
+            field.push(MARC_FIELD_END);
+            directory.extend(format!("{}{:04}{:05}", tag, field.len(), data.len()).into_bytes());
+            data.extend(field);
+        }
+        directory.push(MARC_FIELD_END);
+        let base = 24 + directory.len();
+        let length = base + data.len() + 1;
+        let mut record = format!("{:05}nam a22{:05}   4500", length, base).into_bytes();
+        record.extend(directory);
+        record.extend(data);
+        record.push(MARC_RECORD_END);
+        record
+    }
+
+    #[test]
+    fn isbns_are_checked_and_widened_to_13_digits() {
+        assert_eq!(normalize_isbn("0-306-40615-2").as_deref(), Some("9780306406157"));
+        assert_eq!(normalize_isbn("978 0 306 40615 7").as_deref(), Some("9780306406157"));
+        assert_eq!(normalize_isbn("080442957X").as_deref(), Some("9780804429573"));
+        assert_eq!(normalize_isbn("0-306-40615-3"), None);
+        assert_eq!(normalize_isbn("9780306406158"), None);
+        assert_eq!(normalize_isbn("12345"), None);
+    }
+
+    #[test]
+    fn fines_accrue_per_day_up_to_the_cap() {
+        let policy = policy();
+        let due = date(2026, 3, 10);
+        assert_eq!(policy.fine(due, date(2026, 3, 10)), dec!(0));
+        assert_eq!(policy.fine(due, date(2026, 3, 13)), dec!(0.75));
+        assert_eq!(policy.fine(due, date(2026, 6, 1)), dec!(5.00));
+    }
+
+    #[test]
+    fn renewals_run_from_today_within_limits() {
+        let policy = policy();
+        let due = date(2026, 3, 10);
+        assert_eq!(policy.renewal(due, 0, date(2026, 3, 8), false).unwrap(), date(2026, 3, 22));
+        assert!(matches!(policy.renewal(due, 2, date(2026, 3, 8), false), Err(LibraryError::RenewalLimit(2))));
+        assert!(matches!(policy.renewal(due, 0, date(2026, 3, 11), false), Err(LibraryError::Overdue)));
+        assert!(matches!(policy.renewal(due, 0, date(2026, 3, 8), true), Err(LibraryError::HoldsWaiting)));
+    }
+
+    #[test]
+    fn marc_records_become_titles_with_copies() {
+        let mut file = marc(&[
+            ("001", "ocm123"),
+            ("020", "  $a0306406152 (pbk.)"),
+            ("100", "1 $aTolkien, J. R. R.,"),
+            ("245", "14$aThe hobbit :$bor, There and back again /$cJ.R.R. Tolkien."),
+            ("264", " 1$aLondon :$bAllen & Unwin,$cc1937."),
+            ("852", "  $bMAIN$p30001000123"),
+            ("852", "  $bANNEX$p30001000124"),
+        ]);
+        file.extend(b"\n");
+        file.extend(marc(&[("020", "  $a123")]));
+
+        let parsed = parse_marc(&file);
+        assert_eq!(parsed.records.len(), 1);
+        let record = &parsed.records[0];
+        assert_eq!(record.title, "The hobbit: or, There and back again");
+        assert_eq!(record.isbn.as_deref(), Some("9780306406157"));
+        assert_eq!(record.author.as_deref(), Some("Tolkien, J. R. R"));
+        assert_eq!(record.publisher.as_deref(), Some("Allen & Unwin"));
+        assert_eq!(record.published_year, Some(1937));
+        assert_eq!(record.copies.len(), 2);
+        assert_eq!(record.copies[1].barcode, "30001000124");
+        assert_eq!(record.copies[1].location.as_deref(), Some("ANNEX"));
+
+        assert_eq!(parsed.errors, vec![(2, "record has no title (245)".to_string())]);
+        assert_eq!(parse_marc(b"00010nam").errors.len(), 1);
+    }
+
+    #[test]
+    fn csv_rows_are_read_by_header_name() {
+        let file = b"Title,ISBN,Barcode,Year\n\
+            Matilda,0-14-032759-2,B-1,1988\n\
+            Matilda,,B-2,\n\
+            ,9780306406157,B-3,\n\
+            Bad ISBN,12345,B-4,\n";
+        let parsed = parse_csv(file);
+
+        assert_eq!(parsed.records.len(), 2);
+        assert_eq!(parsed.records[0].isbn.as_deref(), Some("9780140327595"));
+        assert_eq!(parsed.records[0].published_year, Some(1988));
+        assert_eq!(parsed.records[1].copies[0].barcode, "B-2");
+        assert_eq!(
+            parsed.errors.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::infrastructure::counters::{CounterError, CounterStore};
+
+// Longest wait exponential backoff can impose before the lockout takes over
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Why an attempt is refused, and for how long
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Blocked {
+    Backoff(Duration),
+    AccountLocked(Duration),
+    AddressLocked(Duration),
+}
+
+impl Blocked {
+    pub fn retry_after(&self) -> Duration {
+        match self {
+            Blocked::Backoff(wait) | Blocked::AccountLocked(wait) | Blocked::AddressLocked(wait) => *wait,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let seconds = self.retry_after().as_secs().max(1);
+        match self {
+            Blocked::Backoff(_) => format!("Too many failed attempts, try again in {} seconds", seconds),
+            Blocked::AccountLocked(_) => format!("Account temporarily locked, try again in {} seconds", seconds),
+            Blocked::AddressLocked(_) => format!("Too many failed attempts from your network, try again in {} seconds", seconds),
+        }
+    }
+}
+
+// Failed-login bookkeeping per account of a school and per client IP:
+// exponential backoff after a few failures, then a temporary lockout. Counter
+// outages fail open so a Redis hiccup never locks everybody out.
+#[derive(Clone)]
+pub struct LoginGuard {
+    store: CounterStore,
+    max_attempts: u64,
+    backoff_after: u64,
+    ip_max_attempts: u64,
+    failure_window: Duration,
+    lockout: Duration,
+}
+
+// The same email can be an account at several schools; each is locked on its own
+fn account_key(kind: &str, tenant_id: i32, account: &str) -> String {
+    format!("login:{}:account:{}:{}", kind, tenant_id, account.to_lowercase())
+}
+
+fn ip_key(kind: &str, ip: IpAddr) -> String {
+    format!("login:{}:ip:{}", kind, ip)
+}
+
+impl LoginGuard {
+    pub fn new(store: CounterStore, config: &Config) -> Self {
+        Self {
+            store,
+            max_attempts: config.login_max_attempts,
+            backoff_after: config.login_backoff_after,
+            ip_max_attempts: config.login_ip_max_attempts,
+            failure_window: Duration::from_secs(config.login_failure_window_minutes * 60),
+            lockout: Duration::from_secs(config.login_lockout_minutes * 60),
+        }
+    }
+
+    // Whether an attempt for this account from this address may go ahead
+    pub async fn check(&self, tenant_id: i32, account: &str, ip: Option<IpAddr>) -> Result<(), Blocked> {
+        let lookups = async {
+            if let Some(ip) = ip
+                && let Some(wait) = self.store.ttl(&ip_key("lock", ip)).await?
+            {
+                return Ok(Some(Blocked::AddressLocked(wait)));
+            }
+            if let Some(wait) = self.store.ttl(&account_key("lock", tenant_id, account)).await? {
+                return Ok(Some(Blocked::AccountLocked(wait)));
+            }
+            if let Some(wait) = self.store.ttl(&account_key("wait", tenant_id, account)).await? {
+                return Ok(Some(Blocked::Backoff(wait)));
+            }
+            Ok::<_, CounterError>(None)
+        };
+
+        match lookups.await {
+            Ok(Some(blocked)) => Err(blocked),
+            Ok(None) => Ok(()),
+            Err(e) => {
+                tracing::error!("Login guard unavailable: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    // Count a failed attempt and impose backoff or lockout when due
+    pub async fn record_failure(&self, tenant_id: i32, account: &str, ip: Option<IpAddr>) {
+        let result = async {
+            let failures = self
+                .store
+                .increment(&account_key("failures", tenant_id, account), self.failure_window)
+                .await?;
+            if failures >= self.max_attempts {
+                self.store.set(&account_key("lock", tenant_id, account), self.lockout).await?;
+                tracing::warn!("Account {} locked after {} failed attempts", account, failures);
+            } else if failures >= self.backoff_after {
+                let exponent = (failures - self.backoff_after).min(16) as u32;
+                let wait = Duration::from_secs(2u64.pow(exponent)).min(MAX_BACKOFF);
+                self.store.set(&account_key("wait", tenant_id, account), wait).await?;
+            }
+
+            if let Some(ip) = ip {
+                let failures = self.store.increment(&ip_key("failures", ip), self.failure_window).await?;
+                if failures >= self.ip_max_attempts {
+                    self.store.set(&ip_key("lock", ip), self.lockout).await?;
+                    tracing::warn!("Address {} locked after {} failed attempts", ip, failures);
+                }
+            }
+            Ok::<_, CounterError>(())
+        };
+
+        if let Err(e) = result.await {
+            tracing::error!("Failed to record login failure: {}", e);
+        }
+    }
+
+    // A successful login clears the account's failures (not the address's)
+    pub async fn record_success(&self, tenant_id: i32, account: &str) {
+        let keys = [account_key("failures", tenant_id, account), account_key("wait", tenant_id, account)];
+        if let Err(e) = self.store.delete(&keys).await {
+            tracing::error!("Failed to reset login failures: {}", e);
+        }
+    }
+
+    // Admin unlock: clear failures, backoff and lockout of an account
+    pub async fn unlock(&self, tenant_id: i32, account: &str) -> Result<(), CounterError> {
+        let keys = [
+            account_key("failures", tenant_id, account),
+            account_key("wait", tenant_id, account),
+            account_key("lock", tenant_id, account),
+        ];
+        self.store.delete(&keys).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> LoginGuard {
+        LoginGuard {
+            store: CounterStore::memory(),
+            max_attempts: 5,
+            backoff_after: 3,
+            ip_max_attempts: 8,
+            failure_window: Duration::from_secs(900),
+            lockout: Duration::from_secs(900),
+        }
+    }
+
+    #[tokio::test]
+    async fn failures_back_off_then_lock_until_unlocked() {
+        let guard = guard();
+        let ip = Some("10.0.0.7".parse().unwrap());
+
+        for _ in 0..2 {
+            guard.record_failure(1, "Amina@school.local", ip).await;
+        }
+        assert_eq!(guard.check(1, "amina@school.local", ip).await, Ok(()));
+
+        guard.record_failure(1, "amina@school.local", ip).await;
+        assert!(matches!(guard.check(1, "amina@school.local", ip).await, Err(Blocked::Backoff(wait)) if wait <= Duration::from_secs(1)));
+
+        guard.record_failure(1, "amina@school.local", ip).await;
+        assert!(matches!(guard.check(1, "amina@school.local", ip).await, Err(Blocked::Backoff(wait)) if wait > Duration::from_secs(1)));
+
+        guard.record_failure(1, "amina@school.local", ip).await;
+        assert!(matches!(guard.check(1, "amina@school.local", ip).await, Err(Blocked::AccountLocked(_))));
+        // Other accounts are unaffected
+        assert_eq!(guard.check(1, "omar@school.local", ip).await, Ok(()));
+
+        guard.unlock(1, "amina@school.local").await.unwrap();
+        assert_eq!(guard.check(1, "amina@school.local", ip).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn schools_lock_their_own_accounts() {
+        let guard = guard();
+
+        for _ in 0..5 {
+            guard.record_failure(1, "amina@school.local", None).await;
+        }
+        assert!(matches!(guard.check(1, "amina@school.local", None).await, Err(Blocked::AccountLocked(_))));
+        assert_eq!(guard.check(2, "amina@school.local", None).await, Ok(()));
+
+        // Another school's admin cannot lift the lock either
+        guard.unlock(2, "amina@school.local").await.unwrap();
+        assert!(matches!(guard.check(1, "amina@school.local", None).await, Err(Blocked::AccountLocked(_))));
+    }
+
+    #[tokio::test]
+    async fn one_address_failing_on_many_accounts_gets_locked() {
+        let guard = guard();
+        let ip = Some("10.0.0.9".parse().unwrap());
+
+        for n in 0..8 {
+            guard.record_failure(1, &format!("user{}@school.local", n), ip).await;
+        }
+        assert!(matches!(guard.check(1, "fresh@school.local", ip).await, Err(Blocked::AddressLocked(_))));
+        assert_eq!(guard.check(1, "fresh@school.local", Some("10.0.0.10".parse().unwrap())).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn success_clears_account_failures() {
+        let guard = guard();
+
+        for _ in 0..3 {
+            guard.record_failure(1, "omar@school.local", None).await;
+        }
+        guard.record_success(1, "omar@school.local").await;
+        assert_eq!(guard.check(1, "omar@school.local", None).await, Ok(()));
+
+        // Counting starts over: two more failures are no backoff yet
+        for _ in 0..2 {
+            guard.record_failure(1, "omar@school.local", None).await;
+        }
+        assert_eq!(guard.check(1, "omar@school.local", None).await, Ok(()));
+    }
+}
@@ -0,0 +1,560 @@
+use chrono::Utc;
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::{Jwk, JwkSet, KeyAlgorithm},
+    Algorithm, EncodingKey, Header, Validation,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::dto::lti::LoginInitiationRequest;
+use crate::entities::lti_platforms;
+use crate::infrastructure::jwks::{JwksCache, JwksError};
+
+pub const LTI_VERSION: &str = "1.3.0";
+pub const RESOURCE_LINK_REQUEST: &str = "LtiResourceLinkRequest";
+pub const DEEP_LINKING_REQUEST: &str = "LtiDeepLinkingRequest";
+const DEEP_LINKING_RESPONSE: &str = "LtiDeepLinkingResponse";
+
+pub const AGS_SCOPE_SCORE: &str = "https://purl.imsglobal.org/spec/lti-ags/scope/score";
+const SCORE_CONTENT_TYPE: &str = "application/vnd.ims.lis.v1.score+json";
+const CLIENT_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+// Who may register platforms and manage their user links
+pub const PLATFORM_ROLES: &[&str] = &["admin"];
+// Who may pass back a score; the score is taken from the request, so students may not
+pub const GRADER_ROLES: &[&str] = &["admin", "principal", "teacher"];
+// How long after a launch its platform user can be linked to an rsEdu account
+pub const LINK_WINDOW_MINUTES: i64 = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LtiError {
+    #[error("unknown or inactive LTI platform")]
+    UnknownPlatform,
+    #[error("several schools registered this platform, it must send its client and deployment ids")]
+    AmbiguousPlatform,
+    #[error("invalid or expired login state")]
+    InvalidState,
+    #[error("invalid id_token: {0}")]
+    InvalidToken(String),
+    #[error(transparent)]
+    Jwks(#[from] JwksError),
+    #[error("LTI tool key is not configured")]
+    MissingToolKey,
+    #[error("launch does not support {0}")]
+    Unsupported(&'static str),
+    #[error("platform request failed: {0}")]
+    Platform(String),
+    #[error(transparent)]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+    #[error("this platform user is already linked to an account")]
+    AlreadyLinked,
+    #[error("the launch is too old to link, launch again from the platform")]
+    LinkExpired,
+    #[error("the linked account is deactivated")]
+    AccountInactive,
+    #[error("only teachers can pass back scores")]
+    NotGrader,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkingSettings {
+    pub deep_link_return_url: String,
+    #[serde(default)]
+    pub accept_types: Vec<String>,
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgsEndpoint {
+    #[serde(default)]
+    pub scope: Vec<String>,
+    pub lineitem: Option<String>,
+    pub lineitems: Option<String>,
+}
+
+// The parts of an LTI 1.3 launch id_token rsEdu cares about.
+// The full payload is kept alongside in `lti_launches.claims`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/message_type")]
+    pub message_type: String,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/version")]
+    pub version: String,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/deployment_id")]
+    pub deployment_id: String,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/target_link_uri")]
+    pub target_link_uri: Option<String>,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/roles", default)]
+    pub roles: Vec<String>,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/context")]
+    pub context: Option<Value>,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti/claim/resource_link")]
+    pub resource_link: Option<Value>,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti-dl/claim/deep_linking_settings")]
+    pub deep_linking_settings: Option<DeepLinkingSettings>,
+    #[serde(rename = "https://purl.imsglobal.org/spec/lti-ags/claim/endpoint")]
+    pub ags_endpoint: Option<AgsEndpoint>,
+}
+
+// Score published to the platform gradebook (AGS Score service)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Score {
+    pub user_id: String,
+    pub score_given: f64,
+    pub score_maximum: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub timestamp: String,
+    pub activity_progress: String,
+    pub grading_progress: String,
+}
+
+// The RSA key rsEdu signs deep linking responses and client assertions with.
+// Its public half is published at /api/v1/lti/jwks for platforms to fetch.
+#[derive(Clone)]
+pub struct ToolKey {
+    kid: String,
+    encoding: EncodingKey,
+    jwk: Jwk,
+}
+
+impl ToolKey {
+    pub fn from_pem(kid: &str, pem: &[u8]) -> Result<Self, LtiError> {
+        let encoding = EncodingKey::from_rsa_pem(pem)?;
+        let mut jwk = Jwk::from_encoding_key(&encoding, Algorithm::RS256)?;
+        jwk.common.key_id = Some(kid.to_string());
+        jwk.common.key_algorithm = Some(KeyAlgorithm::RS256);
+
+        Ok(Self {
+            kid: kid.to_string(),
+            encoding,
+            jwk,
+        })
+    }
+
+    // Load the key configured with LTI_PRIVATE_KEY_PATH, if any
+    pub fn from_config(config: &Config) -> Result<Option<Self>, LtiError> {
+        let Some(path) = config.lti_private_key_path.as_deref() else {
+            return Ok(None);
+        };
+        let pem = std::fs::read(path)
+            .map_err(|e| LtiError::Platform(format!("cannot read LTI key {}: {}", path, e)))?;
+        Self::from_pem(&config.lti_key_id, &pem).map(Some)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: vec![self.jwk.clone()],
+        }
+    }
+
+    fn sign<T: Serialize>(&self, claims: &T) -> Result<String, LtiError> {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(self.kid.clone());
+        Ok(encode(&header, claims, &self.encoding)?)
+    }
+}
+
+// Whether a URL is on rsEdu's own origin (scheme, host and port), so a
+// launch never sends the browser anywhere else
+pub fn is_app_url(url: &str, app_url: &str) -> bool {
+    match (reqwest::Url::parse(url), reqwest::Url::parse(app_url)) {
+        (Ok(url), Ok(app)) => url.origin() == app.origin(),
+        _ => false,
+    }
+}
+
+// Random value for `state` and `nonce`
+pub fn random_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+// Build the OIDC authentication request the browser is redirected to
+// at the end of third-party login initiation.
+pub fn authorization_url(
+    platform: &lti_platforms::Model,
+    login: &LoginInitiationRequest,
+    redirect_uri: &str,
+    state: &str,
+    nonce: &str,
+) -> Result<String, LtiError> {
+    let mut params = vec![
+        ("scope", "openid"),
+        ("response_type", "id_token"),
+        ("response_mode", "form_post"),
+        ("prompt", "none"),
+        ("client_id", platform.client_id.as_str()),
+        ("redirect_uri", redirect_uri),
+        ("login_hint", login.login_hint.as_str()),
+        ("state", state),
+        ("nonce", nonce),
+    ];
+    if let Some(hint) = login.lti_message_hint.as_deref() {
+        params.push(("lti_message_hint", hint));
+    }
+
+    reqwest::Url::parse_with_params(&platform.auth_login_url, &params)
+        .map(String::from)
+        .map_err(|e| LtiError::Platform(format!("invalid auth login URL: {}", e)))
+}
+
+// Validate a launch id_token against the platform's JWKS and the nonce we issued.
+// Returns the typed claims plus the raw payload for storage.
+pub async fn verify_launch(
+    http: &reqwest::Client,
+    jwks: &JwksCache,
+    platform: &lti_platforms::Model,
+    id_token: &str,
+    expected_nonce: &str,
+) -> Result<(LaunchClaims, Value), LtiError> {
+    let header = decode_header(id_token)?;
+    if header.alg != Algorithm::RS256 {
+        return Err(LtiError::InvalidToken(format!("unexpected algorithm {:?}", header.alg)));
+    }
+
+    let key = jwks
+        .decoding_key(http, &platform.jwks_url, header.kid.as_deref())
+        .await?;
+
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_issuer(&[&platform.issuer]);
+    validation.set_audience(&[&platform.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    let raw = decode::<Value>(id_token, &key, &validation)
+        .map_err(|e| LtiError::InvalidToken(e.to_string()))?
+        .claims;
+    let claims: LaunchClaims = serde_json::from_value(raw.clone())
+        .map_err(|e| LtiError::InvalidToken(e.to_string()))?;
+
+    if claims.nonce != expected_nonce {
+        return Err(LtiError::InvalidToken("nonce mismatch".to_string()));
+    }
+    if claims.version != LTI_VERSION {
+        return Err(LtiError::InvalidToken(format!("unsupported LTI version {}", claims.version)));
+    }
+    if let Some(deployment_id) = platform.deployment_id.as_deref()
+        && claims.deployment_id != deployment_id
+    {
+        return Err(LtiError::InvalidToken("unknown deployment".to_string()));
+    }
+    match claims.message_type.as_str() {
+        RESOURCE_LINK_REQUEST => {}
+        DEEP_LINKING_REQUEST if claims.deep_linking_settings.is_some() => {}
+        other => {
+            return Err(LtiError::InvalidToken(format!("unsupported message type {}", other)));
+        }
+    }
+
+    Ok((claims, raw))
+}
+
+// Sign the LtiDeepLinkingResponse carrying the content items the user picked.
+// Returns the platform return URL and the JWT to post to it.
+pub fn deep_linking_response(
+    tool: &ToolKey,
+    platform: &lti_platforms::Model,
+    claims: &LaunchClaims,
+    items: Vec<Value>,
+    message: Option<String>,
+) -> Result<(String, String), LtiError> {
+    let settings = claims
+        .deep_linking_settings
+        .as_ref()
+        .ok_or(LtiError::Unsupported("deep linking"))?;
+
+    let now = Utc::now().timestamp();
+    let mut payload = json!({
+        "iss": platform.client_id,
+        "aud": platform.issuer,
+        "iat": now,
+        "exp": now + 300,
+        "nonce": random_token(),
+        "https://purl.imsglobal.org/spec/lti/claim/deployment_id": claims.deployment_id,
+        "https://purl.imsglobal.org/spec/lti/claim/message_type": DEEP_LINKING_RESPONSE,
+        "https://purl.imsglobal.org/spec/lti/claim/version": LTI_VERSION,
+        "https://purl.imsglobal.org/spec/lti-dl/claim/content_items": items,
+    });
+    if let Some(data) = &settings.data {
+        payload["https://purl.imsglobal.org/spec/lti-dl/claim/data"] = json!(data);
+    }
+    if let Some(message) = message {
+        payload["https://purl.imsglobal.org/spec/lti-dl/claim/msg"] = json!(message);
+    }
+
+    Ok((settings.deep_link_return_url.clone(), tool.sign(&payload)?))
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+}
+
+// OAuth2 client-credentials grant with a signed JWT client assertion
+pub async fn fetch_access_token(
+    http: &reqwest::Client,
+    tool: &ToolKey,
+    platform: &lti_platforms::Model,
+    scopes: &[&str],
+) -> Result<String, LtiError> {
+    let now = Utc::now().timestamp();
+    let assertion = tool.sign(&json!({
+        "iss": platform.client_id,
+        "sub": platform.client_id,
+        "aud": platform.auth_token_url,
+        "iat": now,
+        "exp": now + 300,
+        "jti": random_token(),
+    }))?;
+
+    let scope = scopes.join(" ");
+    let form = [
+        ("grant_type", "client_credentials"),
+        ("client_assertion_type", CLIENT_ASSERTION_TYPE),
+        ("client_assertion", assertion.as_str()),
+        ("scope", scope.as_str()),
+    ];
+
+    let response = http
+        .post(&platform.auth_token_url)
+        .form(&form)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| LtiError::Platform(e.to_string()))?;
+
+    response
+        .json::<AccessTokenResponse>()
+        .await
+        .map(|token| token.access_token)
+        .map_err(|e| LtiError::Platform(e.to_string()))
+}
+
+// Publish a score to the line item of the launch
+pub async fn post_score(
+    http: &reqwest::Client,
+    tool: &ToolKey,
+    platform: &lti_platforms::Model,
+    endpoint: &AgsEndpoint,
+    score: &Score,
+) -> Result<(), LtiError> {
+    if !endpoint.scope.iter().any(|scope| scope == AGS_SCOPE_SCORE) {
+        return Err(LtiError::Unsupported("score passback"));
+    }
+    let lineitem = endpoint
+        .lineitem
+        .as_deref()
+        .ok_or(LtiError::Unsupported("score passback without a line item"))?;
+
+    let token = fetch_access_token(http, tool, platform, &[AGS_SCOPE_SCORE]).await?;
+
+    http.post(scores_url(lineitem))
+        .bearer_auth(token)
+        .header(reqwest::header::CONTENT_TYPE, SCORE_CONTENT_TYPE)
+        .body(serde_json::to_vec(score).map_err(|e| LtiError::Platform(e.to_string()))?)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| LtiError::Platform(e.to_string()))?;
+
+    Ok(())
+}
+
+// Score passback writes to the platform gradebook, for the launching user or anyone else
+pub fn require_grader(role: &str) -> Result<(), LtiError> {
+    if !GRADER_ROLES.contains(&role) {
+        return Err(LtiError::NotGrader);
+    }
+    Ok(())
+}
+
+// The score service lives at `{lineitem}/scores`, keeping any query string
+fn scores_url(lineitem: &str) -> String {
+    match lineitem.split_once('?') {
+        Some((path, query)) => format!("{}/scores?{}", path.trim_end_matches('/'), query),
+        None => format!("{}/scores", lineitem.trim_end_matches('/')),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::{get, post}, Form, Json, Router};
+    use jsonwebtoken::DecodingKey;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const PLATFORM_KEY: &[u8] = include_bytes!("testdata/platform_key.pem");
+    const TOOL_KEY: &[u8] = include_bytes!("testdata/tool_key.pem");
+    const CLIENT_ID: &str = "rsedu-client";
+
+    #[test]
+    fn only_graders_pass_back_scores() {
+        assert!(matches!(require_grader("student"), Err(LtiError::NotGrader)));
+        assert!(matches!(require_grader("guardian"), Err(LtiError::NotGrader)));
+        assert!(require_grader("teacher").is_ok());
+        assert!(require_grader("admin").is_ok());
+    }
+
+    #[test]
+    fn only_urls_on_the_app_origin_are_targets() {
+        let app = "https://app.example.com";
+        assert!(is_app_url("https://app.example.com/lti/activity?id=3", app));
+        assert!(is_app_url("https://app.example.com:443/", app));
+        assert!(!is_app_url("https://app.example.com.evil.io/", app));
+        assert!(!is_app_url("https://app.example.com@evil.io/", app));
+        assert!(!is_app_url("http://app.example.com/", app));
+        assert!(!is_app_url("/relative", app));
+    }
+
+    // A local stand-in for an LMS: publishes its JWKS, issues AGS tokens
+    // for valid client assertions and records posted scores.
+    #[derive(Clone)]
+    struct MockPlatform {
+        tool_jwks: JwkSet,
+        token_url: Arc<Mutex<String>>,
+        scores: Arc<Mutex<Vec<Value>>>,
+    }
+
+    async fn jwks() -> Json<JwkSet> {
+        Json(ToolKey::from_pem("platform-1", PLATFORM_KEY).unwrap().jwks())
+    }
+
+    async fn token(
+        State(mock): State<MockPlatform>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<Value>, axum::http::StatusCode> {
+        let key = DecodingKey::from_jwk(&mock.tool_jwks.keys[0]).unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[mock.token_url.lock().unwrap().as_str()]);
+        decode::<Value>(&form["client_assertion"], &key, &validation)
+            .map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
+        Ok(Json(json!({ "access_token": "mock-token", "token_type": "Bearer" })))
+    }
+
+    async fn scores(State(mock): State<MockPlatform>, Json(score): Json<Value>) {
+        mock.scores.lock().unwrap().push(score);
+    }
+
+    async fn start_platform() -> (lti_platforms::Model, MockPlatform) {
+        let mock = MockPlatform {
+            tool_jwks: ToolKey::from_pem("tool-1", TOOL_KEY).unwrap().jwks(),
+            token_url: Arc::new(Mutex::new(String::new())),
+            scores: Arc::new(Mutex::new(Vec::new())),
+        };
+        let app = Router::new()
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .route("/lineitems/1/scores", post(scores))
+            .with_state(mock.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        *mock.token_url.lock().unwrap() = format!("{}/token", base);
+
+        let now = Utc::now().naive_utc();
+        let platform = lti_platforms::Model {
+            id: 1,
+            tenant_id: 1,
+            name: "Mock LMS".to_string(),
+            issuer: base.clone(),
+            client_id: CLIENT_ID.to_string(),
+            deployment_id: Some("deployment-1".to_string()),
+            auth_login_url: format!("{}/auth", base),
+            auth_token_url: format!("{}/token", base),
+            jwks_url: format!("{}/jwks", base),
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        };
+        (platform, mock)
+    }
+
+    fn launch_token(platform: &lti_platforms::Model, nonce: &str, audience: &str) -> String {
+        let now = Utc::now().timestamp();
+        let claims = json!({
+            "iss": platform.issuer,
+            "aud": audience,
+            "sub": "lms-user-42",
+            "iat": now,
+            "exp": now + 300,
+            "nonce": nonce,
+            "email": "student@example.com",
+            "https://purl.imsglobal.org/spec/lti/claim/message_type": RESOURCE_LINK_REQUEST,
+            "https://purl.imsglobal.org/spec/lti/claim/version": LTI_VERSION,
+            "https://purl.imsglobal.org/spec/lti/claim/deployment_id": "deployment-1",
+            "https://purl.imsglobal.org/spec/lti/claim/resource_link": { "id": "link-1" },
+            "https://purl.imsglobal.org/spec/lti-ags/claim/endpoint": {
+                "scope": [AGS_SCOPE_SCORE],
+                "lineitem": format!("{}/lineitems/1", platform.issuer),
+            },
+        });
+        ToolKey::from_pem("platform-1", PLATFORM_KEY)
+            .unwrap()
+            .sign(&claims)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn launch_is_validated_against_platform_jwks() {
+        let (platform, _mock) = start_platform().await;
+        let http = reqwest::Client::new();
+        let jwks = JwksCache::default();
+
+        let token = launch_token(&platform, "nonce-1", CLIENT_ID);
+        let (claims, _) = verify_launch(&http, &jwks, &platform, &token, "nonce-1")
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "lms-user-42");
+        assert_eq!(claims.email.as_deref(), Some("student@example.com"));
+
+        let replayed = verify_launch(&http, &jwks, &platform, &token, "other-nonce").await;
+        assert!(matches!(replayed, Err(LtiError::InvalidToken(_))));
+
+        let foreign = launch_token(&platform, "nonce-1", "someone-else");
+        let foreign = verify_launch(&http, &jwks, &platform, &foreign, "nonce-1").await;
+        assert!(matches!(foreign, Err(LtiError::InvalidToken(_))));
+    }
+
+    #[tokio::test]
+    async fn scores_are_posted_to_the_line_item() {
+        let (platform, mock) = start_platform().await;
+        let http = reqwest::Client::new();
+        let tool = ToolKey::from_pem("tool-1", TOOL_KEY).unwrap();
+
+        let token = launch_token(&platform, "nonce-1", CLIENT_ID);
+        let (claims, _) = verify_launch(&http, &JwksCache::default(), &platform, &token, "nonce-1")
+            .await
+            .unwrap();
+
+        let score = Score {
+            user_id: claims.sub.clone(),
+            score_given: 8.0,
+            score_maximum: 10.0,
+            comment: None,
+            timestamp: Utc::now().to_rfc3339(),
+            activity_progress: "Completed".to_string(),
+            grading_progress: "FullyGraded".to_string(),
+        };
+        post_score(&http, &tool, &platform, claims.ags_endpoint.as_ref().unwrap(), &score)
+            .await
+            .unwrap();
+
+        let posted = mock.scores.lock().unwrap();
+        assert_eq!(posted.len(), 1);
+        assert_eq!(posted[0]["userId"], "lms-user-42");
+        assert_eq!(posted[0]["scoreGiven"], 8.0);
+    }
+}
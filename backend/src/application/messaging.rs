@@ -0,0 +1,369 @@
+use crate::entities::messaging_settings;
+
+// Who handles reports, sees the safeguarding archive and sets its rules
+pub const SAFEGUARDING_ROLES: &[&str] = &["admin", "principal", "safeguarding"];
+
+// Who may link guardians to students
+pub const GUARDIAN_LINK_ROLES: &[&str] = &["admin", "principal"];
+
+// Anyone may message these roles, and they may message anyone
+const LEADERSHIP_ROLES: &[&str] = &["admin", "principal", "safeguarding"];
+// Staff who do not teach: the office, bursar, librarian...
+const OTHER_STAFF_ROLES: &[&str] = &["bursar", "admissions", "librarian", "warden", "transport"];
+
+pub const MAX_RECIPIENTS: usize = 50;
+
+pub const ATTACHMENT_TYPES: &[&str] = &["application/pdf", "image/jpeg", "image/png"];
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+
+pub const OPEN: &str = "open";
+pub const DISMISSED: &str = "dismissed";
+// The reported message was hidden
+pub const ACTIONED: &str = "actioned";
+
+// Why a message went into the safeguarding archive
+pub const STAFF_STUDENT: &str = "staff_student";
+pub const STUDENT_STUDENT: &str = "student_student";
+
+#[derive(Debug, thiserror::Error)]
+pub enum MessagingError {
+    #[error("thread not found")]
+    ThreadNotFound,
+    #[error("message not found")]
+    MessageNotFound,
+    #[error("attachment not found")]
+    AttachmentNotFound,
+    #[error("report not found")]
+    ReportNotFound,
+    #[error("user not found")]
+    UserNotFound,
+    #[error("a message needs at least one recipient")]
+    NoRecipients,
+    #[error("a message can have at most {MAX_RECIPIENTS} recipients")]
+    TooManyRecipients,
+    #[error("you may not message user {0}")]
+    NotAllowed(i32),
+    #[error("users {0} and {1} may not be in a conversation together")]
+    NotTogether(i32, i32),
+    #[error("this thread has been locked by a moderator")]
+    ThreadLocked,
+    #[error("only the sender may add attachments to a message")]
+    NotSender,
+    #[error("you have already reported this message")]
+    AlreadyReported,
+    #[error("this report has already been resolved")]
+    AlreadyResolved,
+    #[error("action must be dismiss or hide")]
+    InvalidAction,
+    #[error("guardian_id must belong to a guardian")]
+    NotGuardian,
+    #[error("student_id must belong to a student")]
+    NotStudent,
+    #[error("this guardian is already linked to the student")]
+    AlreadyLinked,
+    #[error("attachments must be PDF, JPEG or PNG")]
+    UnsupportedAttachment,
+    #[error("attachment is empty")]
+    EmptyAttachment,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+// How a role takes part in messaging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Leadership,
+    Teacher,
+    // Other staff: the office, bursar, librarian...
+    Staff,
+    Student,
+    Guardian,
+}
+
+// None for a role messaging does not know, which may message no one
+pub fn kind(role: &str) -> Option<Kind> {
+    match role {
+        role if LEADERSHIP_ROLES.contains(&role) => Some(Kind::Leadership),
+        role if OTHER_STAFF_ROLES.contains(&role) => Some(Kind::Staff),
+        "teacher" => Some(Kind::Teacher),
+        "student" => Some(Kind::Student),
+        "guardian" => Some(Kind::Guardian),
+        _ => None,
+    }
+}
+
+// A grade and optional section, as placed by class membership
+#[derive(Debug, Clone)]
+pub struct Class {
+    pub grade: String,
+    pub section: Option<String>,
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+impl Class {
+    // A teacher's class takes in a student's when the grades match and the
+    // teacher has no section or the same one
+    fn takes_in(&self, student: &Class) -> bool {
+        same(&self.grade, &student.grade)
+            && match (&self.section, &student.section) {
+                (None, _) => true,
+                (Some(a), Some(b)) => same(a, b),
+                (Some(_), None) => false,
+            }
+    }
+
+    fn classmates(&self, other: &Class) -> bool {
+        same(&self.grade, &other.grade)
+            && match (&self.section, &other.section) {
+                (None, None) => true,
+                (Some(a), Some(b)) => same(a, b),
+                _ => false,
+            }
+    }
+}
+
+// Someone sending or receiving a message. `classes` is a teacher's or
+// student's own class, or the classes of a guardian's children; `linked`
+// is a guardian's children or a student's guardians.
+#[derive(Debug, Clone)]
+pub struct Party {
+    pub id: i32,
+    pub name: String,
+    pub role: String,
+    pub classes: Vec<Class>,
+    pub linked: Vec<i32>,
+}
+
+// A school's safeguarding rules
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    pub archive_staff_student: bool,
+    pub archive_student_student: bool,
+    pub students_may_message_students: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            archive_staff_student: true,
+            archive_student_student: false,
+            students_may_message_students: true,
+        }
+    }
+}
+
+impl From<&messaging_settings::Model> for Rules {
+    fn from(settings: &messaging_settings::Model) -> Self {
+        Rules {
+            archive_staff_student: settings.archive_staff_student,
+            archive_student_student: settings.archive_student_student,
+            students_may_message_students: settings.students_may_message_students,
+        }
+    }
+}
+
+// Whether two people may be in a conversation. The school's leadership may
+// talk to anyone and staff to each other; teachers to the students in their
+// class and those students' guardians; other staff to guardians; students
+// to their classmates (if the school allows it) and their own guardians.
+pub fn may_message(sender: &Party, recipient: &Party, rules: &Rules) -> bool {
+    if sender.id == recipient.id {
+        return false;
+    }
+    allowed(sender, recipient, rules) || allowed(recipient, sender, rules)
+}
+
+// Everyone in a conversation sees everything said in it, so every two of
+// them must be allowed to talk, not just the sender to each recipient.
+// Returns the first pair that may not.
+pub fn may_converse(parties: &[Party], rules: &Rules) -> Result<(), (i32, i32)> {
+    for (i, a) in parties.iter().enumerate() {
+        for b in &parties[i + 1..] {
+            if !may_message(a, b, rules) {
+                return Err((a.id, b.id));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn allowed(a: &Party, b: &Party, rules: &Rules) -> bool {
+    let teaches = |teacher: &Party, student_classes: &[Class]| {
+        teacher
+            .classes
+            .iter()
+            .any(|class| student_classes.iter().any(|student| class.takes_in(student)))
+    };
+    let (Some(a_kind), Some(b_kind)) = (kind(&a.role), kind(&b.role)) else {
+        return false;
+    };
+    match (a_kind, b_kind) {
+        (Kind::Leadership, _) => true,
+        (Kind::Teacher | Kind::Staff, Kind::Teacher | Kind::Staff) => true,
+        (Kind::Teacher, Kind::Student | Kind::Guardian) => teaches(a, &b.classes),
+        (Kind::Staff, Kind::Guardian) => true,
+        (Kind::Student, Kind::Student) => {
+            rules.students_may_message_students
+                && a.classes
+                    .iter()
+                    .any(|class| b.classes.iter().any(|other| class.classmates(other)))
+        }
+        (Kind::Student, Kind::Guardian) => a.linked.contains(&b.id),
+        _ => false,
+    }
+}
+
+// Why messages in a conversation between these roles are archived, if the
+// school's rules say they should be
+pub fn archive_reason<'a>(roles: impl IntoIterator<Item = &'a str>, rules: &Rules) -> Option<&'static str> {
+    let (mut staff, mut students) = (0, 0);
+    for role in roles {
+        match kind(role) {
+            Some(Kind::Leadership | Kind::Teacher | Kind::Staff) => staff += 1,
+            Some(Kind::Student) => students += 1,
+            Some(Kind::Guardian) | None => {}
+        }
+    }
+    if rules.archive_staff_student && staff > 0 && students > 0 {
+        Some(STAFF_STUDENT)
+    } else if rules.archive_student_student && students > 1 {
+        Some(STUDENT_STUDENT)
+    } else {
+        None
+    }
+}
+
+pub fn check_attachment(content_type: &str, length: usize) -> Result<(), MessagingError> {
+    if !ATTACHMENT_TYPES.contains(&content_type) {
+        return Err(MessagingError::UnsupportedAttachment);
+    }
+    if length == 0 {
+        return Err(MessagingError::EmptyAttachment);
+    }
+    Ok(())
+}
+
+// An attachment as received, before it is stored
+#[derive(Debug)]
+pub struct AttachmentUpload {
+    pub filename: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn party(id: i32, role: &str, classes: &[(&str, Option<&str>)], linked: &[i32]) -> Party {
+        Party {
+            id,
+            name: format!("User {}", id),
+            role: role.to_string(),
+            classes: classes
+                .iter()
+                .map(|(grade, section)| Class {
+                    grade: grade.to_string(),
+                    section: section.map(str::to_string),
+                })
+                .collect(),
+            linked: linked.to_vec(),
+        }
+    }
+
+    #[test]
+    fn teachers_reach_their_students_and_their_families() {
+        let rules = Rules::default();
+        let teacher = party(1, "teacher", &[("Grade 7", None)], &[]);
+        let student = party(2, "student", &[("Grade 7", Some("B"))], &[3]);
+        let guardian = party(3, "guardian", &[("Grade 7", Some("B"))], &[2]);
+        let other_student = party(4, "student", &[("Grade 8", Some("A"))], &[5]);
+        let other_guardian = party(5, "guardian", &[("Grade 8", Some("A"))], &[4]);
+        let section_teacher = party(6, "teacher", &[("Grade 7", Some("A"))], &[]);
+
+        assert!(may_message(&teacher, &student, &rules));
+        assert!(may_message(&student, &teacher, &rules));
+        assert!(may_message(&teacher, &guardian, &rules));
+        assert!(!may_message(&teacher, &other_student, &rules));
+        assert!(!may_message(&teacher, &other_guardian, &rules));
+        assert!(!may_message(&section_teacher, &student, &rules));
+        assert!(may_message(&teacher, &section_teacher, &rules));
+        assert!(!may_message(&teacher, &teacher, &rules));
+    }
+
+    #[test]
+    fn students_only_reach_classmates_and_their_guardians() {
+        let mut rules = Rules::default();
+        let student = party(2, "student", &[("Grade 7", Some("B"))], &[3]);
+        let classmate = party(7, "student", &[("grade 7", Some("b"))], &[]);
+        let stranger = party(4, "student", &[("Grade 7", Some("A"))], &[]);
+        let guardian = party(3, "guardian", &[("Grade 7", Some("B"))], &[2]);
+        let other_guardian = party(5, "guardian", &[("Grade 7", Some("B"))], &[7]);
+        let bursar = party(8, "bursar", &[], &[]);
+        let principal = party(9, "principal", &[], &[]);
+
+        assert!(may_message(&student, &classmate, &rules));
+        assert!(!may_message(&student, &stranger, &rules));
+        assert!(may_message(&student, &guardian, &rules));
+        assert!(!may_message(&student, &other_guardian, &rules));
+        assert!(!may_message(&guardian, &other_guardian, &rules));
+        assert!(!may_message(&student, &bursar, &rules));
+        assert!(may_message(&bursar, &guardian, &rules));
+        assert!(may_message(&student, &principal, &rules));
+
+        rules.students_may_message_students = false;
+        assert!(!may_message(&student, &classmate, &rules));
+    }
+
+    #[test]
+    fn everyone_in_a_conversation_must_be_allowed_to_talk() {
+        let rules = Rules::default();
+        let teacher = party(1, "teacher", &[("Grade 7", None)], &[]);
+        let student = party(2, "student", &[("Grade 7", Some("B"))], &[3]);
+        let guardian = party(3, "guardian", &[("Grade 7", Some("B"))], &[2]);
+        let other_student = party(4, "student", &[("Grade 7", Some("A"))], &[5]);
+        let other_guardian = party(5, "guardian", &[("Grade 7", Some("A"))], &[4]);
+
+        assert!(may_converse(&[teacher.clone(), student.clone(), guardian.clone()], &rules).is_ok());
+        // The teacher reaches both families, but the families may not reach each other
+        assert_eq!(
+            may_converse(&[teacher.clone(), student.clone(), other_guardian], &rules),
+            Err((2, 5))
+        );
+        assert_eq!(may_converse(&[teacher, student, other_student], &rules), Err((2, 4)));
+    }
+
+    #[test]
+    fn unknown_roles_message_no_one() {
+        let rules = Rules::default();
+        let principal = party(9, "principal", &[], &[]);
+        let bursar = party(8, "bursar", &[], &[]);
+        let stranger = party(10, "contractor", &[], &[]);
+
+        assert_eq!(kind("bursar"), Some(Kind::Staff));
+        assert_eq!(kind("contractor"), None);
+        assert!(!may_message(&stranger, &bursar, &rules));
+        assert!(!may_message(&principal, &stranger, &rules));
+        assert_eq!(archive_reason(["contractor", "student"], &rules), None);
+    }
+
+    #[test]
+    fn archiving_follows_the_rules() {
+        let mut rules = Rules::default();
+        assert_eq!(archive_reason(["teacher", "student"], &rules), Some(STAFF_STUDENT));
+        assert_eq!(archive_reason(["teacher", "guardian"], &rules), None);
+        assert_eq!(archive_reason(["student", "student"], &rules), None);
+
+        rules.archive_student_student = true;
+        assert_eq!(archive_reason(["student", "student", "guardian"], &rules), Some(STUDENT_STUDENT));
+
+        rules.archive_staff_student = false;
+        assert_eq!(archive_reason(["principal", "student"], &rules), None);
+
+        assert!(check_attachment("image/jpeg", 1).is_ok());
+        assert!(matches!(check_attachment("image/gif", 1), Err(MessagingError::UnsupportedAttachment)));
+    }
+}
@@ -1 +1,26 @@
 // Business logic will go here
+pub mod admissions;
+pub mod announcements;
+pub mod api_keys;
+pub mod audit;
+pub mod auth;
+pub mod data_subject;
+pub mod directory;
+pub mod email;
+pub mod encryption;
+pub mod fees;
+pub mod hostel;
+pub mod impersonation;
+pub mod library;
+pub mod login_guard;
+pub mod lti;
+pub mod messaging;
+pub mod notifications;
+pub mod oidc;
+pub mod payments;
+pub mod password;
+pub mod tenancy;
+pub mod transport;
+pub mod trash;
+pub mod two_factor;
+pub mod xapi;
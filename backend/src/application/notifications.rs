@@ -0,0 +1,134 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{DatabaseConnection, DbErr};
+
+use crate::config::Config;
+use crate::entities::{announcements, message_threads, messages};
+use crate::infrastructure::notifications::{Notification, NotificationHub};
+use crate::repositories::announcement_repository::AnnouncementRepository;
+
+pub const ANNOUNCEMENT: &str = "announcement";
+pub const MESSAGE: &str = "message";
+
+// Characters of a notice or message shown in its notification
+pub const PREVIEW_CHARS: usize = 140;
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// The start of a text on one line, cut at a character boundary
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", flat[..end].trim_end()),
+        None => flat,
+    }
+}
+
+// A notice having gone up, for everyone it is addressed to
+pub fn for_announcement(announcement: &announcements::Model, recipients: &[i32]) -> Vec<Notification> {
+    recipients
+        .iter()
+        .map(|user_id| Notification {
+            user_id: *user_id,
+            kind: ANNOUNCEMENT.to_string(),
+            resource_id: announcement.id,
+            title: announcement.title.clone(),
+            body: preview(&announcement.body, PREVIEW_CHARS),
+            created_at: format_time(announcement.publish_at),
+        })
+        .collect()
+}
+
+// A new message, for everyone in the thread but its sender
+pub fn for_message(thread: &message_threads::Model, message: &messages::Model, participants: &[i32]) -> Vec<Notification> {
+    participants
+        .iter()
+        .filter(|user_id| Some(**user_id) != message.sender_id)
+        .map(|user_id| Notification {
+            user_id: *user_id,
+            kind: MESSAGE.to_string(),
+            resource_id: thread.id,
+            title: thread.subject.clone(),
+            body: preview(&message.body, PREVIEW_CHARS),
+            created_at: format_time(message.created_at),
+        })
+        .collect()
+}
+
+// Tell readers about every notice, in any school, that has gone live and not
+// been announced yet. Each notice is claimed before it is announced, so
+// instances running this together announce it once.
+pub async fn announce_due(db: &DatabaseConnection, hub: &NotificationHub) -> Result<usize, DbErr> {
+    let due = AnnouncementRepository::claim_due(db, Utc::now().naive_utc()).await?;
+    for announcement in &due {
+        let recipients = AnnouncementRepository::audience(db, announcement).await?;
+        hub.publish(for_announcement(announcement, &recipients)).await;
+    }
+    Ok(due.len())
+}
+
+// Notices scheduled for later are announced by a sweep once they go live
+pub fn spawn_scheduled_announcements(db: DatabaseConnection, hub: NotificationHub, config: &Config) {
+    if config.notification_sweep_seconds == 0 {
+        return;
+    }
+    let period = std::time::Duration::from_secs(config.notification_sweep_seconds);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = announce_due(&db, &hub).await {
+                tracing::error!("Announcement notifications failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, 20)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn previews_are_short_and_on_one_line() {
+        assert_eq!(preview("Bring\n  water   and a hat.", 40), "Bring water and a hat.");
+        assert_eq!(preview("Sports day is on Friday", 10), "Sports day…");
+        assert_eq!(preview("Café ouvert", 4), "Café…");
+        assert_eq!(preview("", 10), "");
+    }
+
+    #[test]
+    fn message_notifications_skip_the_sender() {
+        let thread = message_threads::Model {
+            id: 7,
+            tenant_id: 1,
+            subject: "Homework".to_string(),
+            created_by: Some(1),
+            is_locked: false,
+            created_at: at(8),
+            last_message_at: at(9),
+        };
+        let message = messages::Model {
+            id: 3,
+            thread_id: 7,
+            sender_id: Some(1),
+            body: "Page 12, please.".to_string(),
+            hidden_at: None,
+            hidden_by: None,
+            created_at: at(9),
+        };
+
+        let sent = for_message(&thread, &message, &[1, 2, 3]);
+        assert_eq!(sent.iter().map(|n| n.user_id).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(sent.iter().all(|n| n.kind == MESSAGE && n.resource_id == 7 && n.body == "Page 12, please."));
+        assert_eq!(sent[0].created_at, "2026-10-20 09:00:00");
+    }
+}
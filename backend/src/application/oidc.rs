@@ -0,0 +1,425 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::application::auth::ROLES;
+use crate::config::Config;
+use crate::infrastructure::jwks::{JwksCache, JwksError};
+
+const METADATA_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("identity provider request failed: {0}")]
+    Provider(String),
+    #[error("invalid id_token: {0}")]
+    InvalidToken(String),
+    #[error(transparent)]
+    Jwks(#[from] JwksError),
+    #[error("identity provider did not return a verified email")]
+    MissingEmail,
+}
+
+// The subset of the discovery document rsEdu uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+// Identity asserted by the IdP after a successful login
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub subject: String,
+    pub email: String,
+    pub name: Option<String>,
+    // rsEdu role derived from the configured role claim, if any matched
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+// OpenID Connect relying party for staff and student single sign-on.
+// Discovery metadata is fetched lazily and cached; signing keys go through the shared JwksCache.
+#[derive(Clone)]
+pub struct OidcClient {
+    issuer_url: String,
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    role_claim: String,
+    role_map: Vec<(String, String)>,
+    metadata: Arc<RwLock<Option<(ProviderMetadata, Instant)>>>,
+}
+
+impl OidcClient {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let issuer_url = config.oidc_issuer_url.clone()?;
+        Some(Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id: config.oidc_client_id.clone(),
+            client_secret: config.oidc_client_secret.clone(),
+            redirect_url: config.oidc_redirect_url.clone(),
+            role_claim: config.oidc_role_claim.clone(),
+            role_map: config.oidc_role_map.clone(),
+            metadata: Arc::new(RwLock::new(None)),
+        })
+    }
+
+    // Provider metadata from /.well-known/openid-configuration
+    pub async fn metadata(&self, http: &reqwest::Client) -> Result<ProviderMetadata, OidcError> {
+        if let Some((metadata, fetched_at)) = self.metadata.read().await.as_ref()
+            && fetched_at.elapsed() < METADATA_TTL
+        {
+            return Ok(metadata.clone());
+        }
+
+        let url = format!("{}/.well-known/openid-configuration", self.issuer_url);
+        let metadata = http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| OidcError::Provider(e.to_string()))?
+            .json::<ProviderMetadata>()
+            .await
+            .map_err(|e| OidcError::Provider(e.to_string()))?;
+
+        if metadata.issuer.trim_end_matches('/') != self.issuer_url {
+            return Err(OidcError::Provider(format!(
+                "discovery issuer {} does not match {}",
+                metadata.issuer, self.issuer_url
+            )));
+        }
+
+        *self.metadata.write().await = Some((metadata.clone(), Instant::now()));
+        Ok(metadata)
+    }
+
+    // Authorization request the browser is redirected to
+    pub fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<String, OidcError> {
+        let params = [
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", self.redirect_url.as_str()),
+            ("scope", "openid email profile"),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", code_challenge),
+            ("code_challenge_method", "S256"),
+        ];
+
+        reqwest::Url::parse_with_params(&metadata.authorization_endpoint, &params)
+            .map(String::from)
+            .map_err(|e| OidcError::Provider(format!("invalid authorization endpoint: {}", e)))
+    }
+
+    // Redeem the authorization code and validate the returned id_token
+    pub async fn exchange_code(
+        &self,
+        http: &reqwest::Client,
+        jwks: &JwksCache,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<Identity, OidcError> {
+        let metadata = self.metadata(http).await?;
+
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_url.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        let tokens = http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| OidcError::Provider(e.to_string()))?
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| OidcError::Provider(e.to_string()))?;
+
+        let claims = self.verify_id_token(http, jwks, &metadata, &tokens.id_token, nonce).await?;
+        self.identity(&claims)
+    }
+
+    async fn verify_id_token(
+        &self,
+        http: &reqwest::Client,
+        jwks: &JwksCache,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<Value, OidcError> {
+        let header = decode_header(id_token).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+        // Only asymmetric algorithms; the key always comes from the IdP's JWKS
+        if !matches!(
+            header.alg,
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 | Algorithm::ES256 | Algorithm::ES384
+        ) {
+            return Err(OidcError::InvalidToken(format!("unexpected algorithm {:?}", header.alg)));
+        }
+
+        let key = jwks
+            .decoding_key(http, &metadata.jwks_uri, header.kid.as_deref())
+            .await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<Value>(id_token, &key, &validation)
+            .map_err(|e| OidcError::InvalidToken(e.to_string()))?
+            .claims;
+
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(OidcError::InvalidToken("nonce mismatch".to_string()));
+        }
+        Ok(claims)
+    }
+
+    fn identity(&self, claims: &Value) -> Result<Identity, OidcError> {
+        // Providers that report email_verified=false must not be trusted with the address
+        if claims.get("email_verified").and_then(Value::as_bool) == Some(false) {
+            return Err(OidcError::MissingEmail);
+        }
+        let email = claims
+            .get("email")
+            .and_then(Value::as_str)
+            .ok_or(OidcError::MissingEmail)?
+            .to_lowercase();
+
+        Ok(Identity {
+            subject: claims
+                .get("sub")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            email,
+            name: claims.get("name").and_then(Value::as_str).map(str::to_string),
+            role: self.map_role(claims.get(&self.role_claim)),
+        })
+    }
+
+    // Translate the IdP role claim (a string or a list) into an rsEdu role.
+    // Without a role map the first claim value that is an rsEdu role is used.
+    fn map_role(&self, claim: Option<&Value>) -> Option<String> {
+        let values: Vec<&str> = match claim? {
+            Value::String(value) => vec![value.as_str()],
+            Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+            _ => return None,
+        };
+
+        if self.role_map.is_empty() {
+            return values.into_iter().find(|value| ROLES.contains(value)).map(str::to_string);
+        }
+        // The first mapping that matches wins, so the map doubles as a priority list
+        self.role_map
+            .iter()
+            .filter(|(_, to)| ROLES.contains(&to.as_str()))
+            .find(|(from, _)| values.contains(&from.as_str()))
+            .map(|(_, to)| to.clone())
+    }
+}
+
+// PKCE code verifier and its S256 challenge
+pub fn pkce_pair() -> (String, String) {
+    let verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    (verifier, challenge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::lti::ToolKey;
+    use axum::{extract::State, http::StatusCode, routing::{get, post}, Form, Json, Router};
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const IDP_KEY: &[u8] = include_bytes!("testdata/platform_key.pem");
+
+    // A local fake identity provider: discovery, JWKS and a token endpoint
+    // that only redeems codes registered by the test with the matching PKCE verifier.
+    #[derive(Clone, Default)]
+    struct FakeIdp {
+        issuer: Arc<Mutex<String>>,
+        codes: Arc<Mutex<HashMap<String, IssuedCode>>>,
+    }
+
+    struct IssuedCode {
+        nonce: String,
+        code_challenge: String,
+        claims: Value,
+    }
+
+    async fn discovery(State(idp): State<FakeIdp>) -> Json<Value> {
+        let issuer = idp.issuer.lock().unwrap().clone();
+        Json(json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        }))
+    }
+
+    async fn jwks() -> Json<jsonwebtoken::jwk::JwkSet> {
+        Json(ToolKey::from_pem("idp-1", IDP_KEY).unwrap().jwks())
+    }
+
+    async fn token(
+        State(idp): State<FakeIdp>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<Value>, StatusCode> {
+        let issued = idp
+            .codes
+            .lock()
+            .unwrap()
+            .remove(&form["code"])
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let expected = URL_SAFE_NO_PAD.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+        if expected != issued.code_challenge {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let now = Utc::now().timestamp();
+        let mut claims = json!({
+            "iss": idp.issuer.lock().unwrap().clone(),
+            "aud": form["client_id"],
+            "sub": "idp-user-7",
+            "iat": now,
+            "exp": now + 300,
+            "nonce": issued.nonce,
+        });
+        for (key, value) in issued.claims.as_object().unwrap() {
+            claims[key] = value.clone();
+        }
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("idp-1".to_string());
+        let id_token = encode(&header, &claims, &EncodingKey::from_rsa_pem(IDP_KEY).unwrap()).unwrap();
+        Ok(Json(json!({ "id_token": id_token, "access_token": "at", "token_type": "Bearer" })))
+    }
+
+    async fn start_idp() -> (FakeIdp, OidcClient) {
+        let idp = FakeIdp::default();
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(idp.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        *idp.issuer.lock().unwrap() = issuer.clone();
+
+        let client = OidcClient {
+            issuer_url: issuer,
+            client_id: "rsedu".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_url: "http://localhost:3000/api/v1/auth/oidc/callback".to_string(),
+            role_claim: "groups".to_string(),
+            role_map: vec![
+                ("school-admins".to_string(), "admin".to_string()),
+                ("staff".to_string(), "teacher".to_string()),
+            ],
+            metadata: Arc::new(RwLock::new(None)),
+        };
+        (idp, client)
+    }
+
+    #[tokio::test]
+    async fn code_exchange_with_pkce_yields_mapped_identity() {
+        let (idp, client) = start_idp().await;
+        let http = reqwest::Client::new();
+        let metadata = client.metadata(&http).await.unwrap();
+
+        let (verifier, challenge) = pkce_pair();
+        let url = client.authorization_url(&metadata, "state-1", "nonce-1", &challenge).unwrap();
+        assert!(url.contains("code_challenge_method=S256"));
+
+        idp.codes.lock().unwrap().insert(
+            "code-1".to_string(),
+            IssuedCode {
+                nonce: "nonce-1".to_string(),
+                code_challenge: challenge,
+                claims: json!({ "email": "Teacher@Example.com", "groups": ["staff", "everyone"] }),
+            },
+        );
+
+        let identity = client
+            .exchange_code(&http, &JwksCache::default(), "code-1", &verifier, "nonce-1")
+            .await
+            .unwrap();
+        assert_eq!(identity.email, "teacher@example.com");
+        assert_eq!(identity.role.as_deref(), Some("teacher"));
+    }
+
+    #[tokio::test]
+    async fn wrong_verifier_or_nonce_is_rejected() {
+        let (idp, client) = start_idp().await;
+        let http = reqwest::Client::new();
+        let jwks = JwksCache::default();
+        let (verifier, challenge) = pkce_pair();
+
+        let register = |code: &str| {
+            idp.codes.lock().unwrap().insert(
+                code.to_string(),
+                IssuedCode {
+                    nonce: "nonce-1".to_string(),
+                    code_challenge: challenge.clone(),
+                    claims: json!({ "email": "a@example.com" }),
+                },
+            );
+        };
+
+        register("code-1");
+        let (other_verifier, _) = pkce_pair();
+        let result = client.exchange_code(&http, &jwks, "code-1", &other_verifier, "nonce-1").await;
+        assert!(matches!(result, Err(OidcError::Provider(_))));
+
+        register("code-2");
+        let result = client.exchange_code(&http, &jwks, "code-2", &verifier, "nonce-2").await;
+        assert!(matches!(result, Err(OidcError::InvalidToken(_))));
+    }
+
+    #[tokio::test]
+    async fn only_rsedu_roles_are_taken_from_the_provider() {
+        let (_, mut client) = start_idp().await;
+        client.role_map.push(("it".to_string(), "superuser".to_string()));
+        assert_eq!(client.map_role(Some(&json!(["it", "staff"]))).as_deref(), Some("teacher"));
+        assert_eq!(client.map_role(Some(&json!("it"))), None);
+
+        client.role_map.clear();
+        assert_eq!(client.map_role(Some(&json!(["owner", "teacher"]))).as_deref(), Some("teacher"));
+        assert_eq!(client.map_role(Some(&json!("owner"))), None);
+    }
+}
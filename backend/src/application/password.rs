@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::application::auth;
+use crate::config::Config;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PasswordError {
+    #[error("failed to read breached password list {0}: {1}")]
+    BreachList(String, std::io::Error),
+}
+
+// Rules new passwords must satisfy, built from config at startup
+#[derive(Clone, Default)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    // How many previous passwords may not be reused
+    pub history_size: usize,
+    // Known breached passwords, lowercased
+    breached: Arc<HashSet<String>>,
+}
+
+impl PasswordPolicy {
+    pub fn from_config(config: &Config) -> Result<Self, PasswordError> {
+        let breached = match &config.password_breach_list_path {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| PasswordError::BreachList(path.clone(), e))?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+            None => HashSet::new(),
+        };
+        tracing::info!("🔑 Password policy loaded with {} breached passwords", breached.len());
+
+        Ok(Self {
+            min_length: config.password_min_length,
+            require_uppercase: config.password_require_uppercase,
+            require_lowercase: config.password_require_lowercase,
+            require_digit: config.password_require_digit,
+            require_symbol: config.password_require_symbol,
+            history_size: config.password_history_size,
+            breached: Arc::new(breached),
+        })
+    }
+
+    // Everything wrong with a candidate password; empty means it is acceptable.
+    // `previous_hashes` is the current hash plus the remembered history.
+    pub fn violations(&self, password: &str, email: &str, previous_hashes: &[String]) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(format!("must be at least {} characters", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push("must contain an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push("must contain a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("must contain a digit".to_string());
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            violations.push("must contain a symbol".to_string());
+        }
+
+        let lowered = password.to_lowercase();
+        let local_part = email.split('@').next().unwrap_or_default().to_lowercase();
+        if local_part.len() >= 3 && lowered.contains(&local_part) {
+            violations.push("must not contain your email address".to_string());
+        }
+        if self.breached.contains(&lowered) {
+            violations.push("appears in a list of breached passwords".to_string());
+        }
+        if previous_hashes
+            .iter()
+            .take(self.history_size + 1)
+            .any(|hash| auth::verify_password(password, hash))
+        {
+            violations.push(format!("must differ from your last {} passwords", self.history_size + 1));
+        }
+
+        violations
+    }
+
+    // `violations` as one message for an API error
+    pub fn check(&self, password: &str, email: &str, previous_hashes: &[String]) -> Result<(), String> {
+        let violations = self.violations(password, email, previous_hashes);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(format!("Password {}", violations.join(", ")))
+    }
+}
+
+// A reset token to send to the user, and the hash that is stored instead of it
+pub fn new_reset_token() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = URL_SAFE_NO_PAD.encode(bytes);
+    let hash = hash_reset_token(&token);
+    (token, hash)
+}
+
+// Tokens are long and random, so a fast hash is enough (and lets us look them up)
+pub fn hash_reset_token(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(breached: &[&str]) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 10,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: false,
+            history_size: 2,
+            breached: Arc::new(breached.iter().map(|password| password.to_lowercase()).collect()),
+        }
+    }
+
+    #[test]
+    fn policy_reports_each_broken_rule() {
+        let policy = policy(&["Summer2024!x"]);
+
+        assert!(policy.violations("Correct9Horse", "amina@school.local", &[]).is_empty());
+        assert_eq!(
+            policy.violations("short", "amina@school.local", &[]),
+            vec![
+                "must be at least 10 characters",
+                "must contain an uppercase letter",
+                "must contain a digit",
+            ]
+        );
+        assert_eq!(
+            policy.violations("AMINA-rocks-1", "amina@school.local", &[]),
+            vec!["must not contain your email address"]
+        );
+        assert_eq!(
+            policy.violations("summer2024!X", "amina@school.local", &[]),
+            vec!["appears in a list of breached passwords"]
+        );
+    }
+
+    #[test]
+    fn policy_rejects_recent_passwords() {
+        let policy = policy(&[]);
+        let history: Vec<String> = ["Current9Pass", "Previous9Pass", "Older9Pass", "Oldest9Pass"]
+            .iter()
+            .map(|password| auth::hash_password(password).unwrap())
+            .collect();
+
+        // Current password plus the last two are remembered
+        assert!(!policy.violations("Current9Pass", "a@b.c", &history).is_empty());
+        assert!(!policy.violations("Older9Pass", "a@b.c", &history).is_empty());
+        assert!(policy.violations("Oldest9Pass", "a@b.c", &history).is_empty());
+    }
+
+    #[test]
+    fn reset_tokens_are_stored_hashed() {
+        let (token, hash) = new_reset_token();
+        assert_ne!(token, hash);
+        assert_eq!(hash_reset_token(&token), hash);
+        assert_ne!(new_reset_token().0, token);
+    }
+}
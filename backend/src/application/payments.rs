@@ -0,0 +1,80 @@
+use rust_decimal::Decimal;
+
+use crate::application::fees::round_money;
+
+// Method recorded on payments that came through the payment provider
+pub const METHOD_ONLINE: &str = "online";
+
+pub const SESSION_OPEN: &str = "open";
+pub const SESSION_COMPLETED: &str = "completed";
+pub const SESSION_EXPIRED: &str = "expired";
+
+pub const REFUND_PENDING: &str = "pending";
+pub const REFUND_SUCCEEDED: &str = "succeeded";
+pub const REFUND_FAILED: &str = "failed";
+
+// What handling one webhook event did
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reconciliation {
+    // The event (or the payment it reports) was handled before
+    Duplicate,
+    PaymentRecorded { payment_id: i32, invoice_id: i32 },
+    SessionExpired,
+    RefundSettled { refund_id: i32, status: &'static str },
+    // Refers to a session or refund we have no record of
+    Unmatched,
+    Ignored,
+}
+
+// Amount to open a checkout for: the whole balance unless the payer asks
+// for less
+pub fn checkout_amount(requested: Option<Decimal>, balance: Decimal) -> Result<Decimal, String> {
+    if balance <= Decimal::ZERO {
+        return Err("Invoice is already paid".to_string());
+    }
+    let amount = round_money(requested.unwrap_or(balance));
+    if amount <= Decimal::ZERO {
+        return Err("Amount must be more than zero".to_string());
+    }
+    if amount > balance {
+        return Err(format!("Amount is more than the outstanding balance of {}", balance));
+    }
+    Ok(amount)
+}
+
+// What is left to refund on a payment once refunds that succeeded or are
+// still in flight are counted
+pub fn refundable(paid: Decimal, refunds: impl IntoIterator<Item = (Decimal, String)>) -> Decimal {
+    let committed: Decimal = refunds
+        .into_iter()
+        .filter(|(_, status)| status != REFUND_FAILED)
+        .map(|(amount, _)| amount)
+        .sum();
+    (paid - committed).max(Decimal::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    #[test]
+    fn checkout_defaults_to_the_balance() {
+        assert_eq!(checkout_amount(None, dec!(250.00)), Ok(dec!(250.00)));
+        assert_eq!(checkout_amount(Some(dec!(100.005)), dec!(250.00)), Ok(dec!(100.01)));
+        assert!(checkout_amount(Some(dec!(250.01)), dec!(250.00)).is_err());
+        assert!(checkout_amount(Some(dec!(0.001)), dec!(250.00)).is_err());
+        assert!(checkout_amount(None, dec!(0)).is_err());
+    }
+
+    #[test]
+    fn failed_refunds_do_not_count_against_the_payment() {
+        let refunds = vec![
+            (dec!(20.00), REFUND_SUCCEEDED.to_string()),
+            (dec!(30.00), REFUND_PENDING.to_string()),
+            (dec!(50.00), REFUND_FAILED.to_string()),
+        ];
+        assert_eq!(refundable(dec!(100.00), refunds), dec!(50.00));
+        assert_eq!(refundable(dec!(10.00), vec![(dec!(20.00), REFUND_SUCCEEDED.to_string())]), dec!(0));
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// The school created with tenancy; everything from before belongs to it, and
+// its admins manage the other schools
+pub const DEFAULT_TENANT_ID: i32 = 1;
+
+// Header naming the school of a request by slug, for clients that cannot use a subdomain
+pub const TENANT_HEADER: &str = "x-tenant";
+
+// One step of a grading scale: scores of at least `min_score` (percent) get `grade`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradeBand {
+    pub grade: String,
+    pub min_score: f64,
+}
+
+// Used by schools that have not set their own scale
+pub fn default_grading_scale() -> Vec<GradeBand> {
+    [("A", 90.0), ("B", 80.0), ("C", 70.0), ("D", 60.0), ("F", 0.0)]
+        .into_iter()
+        .map(|(grade, min_score)| GradeBand {
+            grade: grade.to_string(),
+            min_score,
+        })
+        .collect()
+}
+
+// A school's stored scale, or the default one when it has none (or it does not parse)
+pub fn grading_scale(stored: &Value) -> Vec<GradeBand> {
+    serde_json::from_value::<Vec<GradeBand>>(stored.clone())
+        .ok()
+        .filter(|scale| !scale.is_empty())
+        .unwrap_or_else(default_grading_scale)
+}
+
+// A scale must go from the highest band down, cover 0-100 and name each grade once
+pub fn validate_grading_scale(scale: &[GradeBand]) -> Result<(), String> {
+    if scale.is_empty() {
+        return Err("Grading scale needs at least one band".to_string());
+    }
+    for (i, band) in scale.iter().enumerate() {
+        if band.grade.trim().is_empty() {
+            return Err("Every band needs a grade".to_string());
+        }
+        if !(0.0..=100.0).contains(&band.min_score) {
+            return Err(format!("Minimum score of {} must be between 0 and 100", band.grade));
+        }
+        if scale[..i].iter().any(|earlier| earlier.grade == band.grade) {
+            return Err(format!("Grade {} appears twice", band.grade));
+        }
+        if i > 0 && band.min_score >= scale[i - 1].min_score {
+            return Err("Bands must be ordered from the highest minimum score down".to_string());
+        }
+    }
+    if scale.last().is_some_and(|lowest| lowest.min_score != 0.0) {
+        return Err("The lowest band must start at 0".to_string());
+    }
+    Ok(())
+}
+
+// IANA timezone names, e.g. "Africa/Nairobi"
+pub fn valid_timezone(timezone: &str) -> bool {
+    timezone.parse::<chrono_tz::Tz>().is_ok()
+}
+
+// Today's date at the school, falling back to UTC for a bad timezone
+pub fn local_today(timezone: &str) -> NaiveDate {
+    let now = Utc::now();
+    match timezone.parse::<chrono_tz::Tz>() {
+        Ok(tz) => now.with_timezone(&tz).date_naive(),
+        Err(_) => now.date_naive(),
+    }
+}
+
+// Lowercase letters, digits and dashes, usable as a DNS label
+pub fn valid_slug(slug: &str) -> bool {
+    (2..=63).contains(&slug.len())
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+// The school slug in a Host header under `base_domain`: "north" for
+// "north.rsedu.example:443". The bare domain and deeper names have none.
+pub fn subdomain<'a>(host: &'a str, base_domain: &str) -> Option<&'a str> {
+    let host = host.split(':').next().unwrap_or(host);
+    let label = host
+        .strip_suffix(base_domain)?
+        .strip_suffix('.')
+        .filter(|label| !label.is_empty() && !label.contains('.'))?;
+    Some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band(grade: &str, min_score: f64) -> GradeBand {
+        GradeBand {
+            grade: grade.to_string(),
+            min_score,
+        }
+    }
+
+    #[test]
+    fn subdomains_name_schools() {
+        assert_eq!(subdomain("north.rsedu.example", "rsedu.example"), Some("north"));
+        assert_eq!(subdomain("north.rsedu.example:8443", "rsedu.example"), Some("north"));
+        assert_eq!(subdomain("rsedu.example", "rsedu.example"), None);
+        assert_eq!(subdomain("a.north.rsedu.example", "rsedu.example"), None);
+        assert_eq!(subdomain("north.evil-rsedu.example", "rsedu.example"), None);
+    }
+
+    #[test]
+    fn grading_scales_are_checked() {
+        let scale = vec![band("Distinction", 75.0), band("Pass", 50.0), band("Fail", 0.0)];
+        assert!(validate_grading_scale(&scale).is_ok());
+
+        assert!(validate_grading_scale(&[band("Pass", 50.0), band("Merit", 60.0), band("Fail", 0.0)]).is_err());
+        assert!(validate_grading_scale(&[band("Pass", 50.0)]).is_err());
+        assert!(validate_grading_scale(&[band("A", 50.0), band("A", 0.0)]).is_err());
+
+        assert_eq!(grading_scale(&serde_json::json!([])), default_grading_scale());
+    }
+
+    #[test]
+    fn slugs_and_timezones() {
+        assert!(valid_slug("north-campus"));
+        assert!(!valid_slug("North"));
+        assert!(!valid_slug("-north"));
+        assert!(!valid_slug("n"));
+        assert!(valid_timezone("Africa/Nairobi"));
+        assert!(!valid_timezone("Mars/Olympus"));
+    }
+}
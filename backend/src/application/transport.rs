@@ -0,0 +1,101 @@
+use chrono::NaiveTime;
+
+// Who may manage vehicles, drivers, routes and seating
+pub const TRANSPORT_ROLES: &[&str] = &["admin", "transport"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransportError {
+    #[error("route not found")]
+    RouteNotFound,
+    #[error("vehicle not found")]
+    VehicleNotFound,
+    #[error("driver not found")]
+    DriverNotFound,
+    #[error("stop is not on this route")]
+    StopNotFound,
+    #[error("student not found")]
+    StudentNotFound,
+    #[error("assignment not found")]
+    AssignmentNotFound,
+    #[error("a vehicle with registration {0} already exists")]
+    RegistrationTaken(String),
+    #[error("a route named {0} already exists")]
+    RouteNameTaken(String),
+    #[error("route has no vehicle to seat students")]
+    NoVehicle,
+    #[error("route is full ({0} seats)")]
+    RouteFull(i32),
+    #[error("{assigned} students ride this vehicle's routes but it only seats {capacity}")]
+    CapacityTooSmall { capacity: i32, assigned: i32 },
+    #[error("stop {0} must be scheduled after the stop before it")]
+    StopsOutOfOrder(usize),
+    #[error("stop {0} still has students assigned")]
+    StopInUse(String),
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+// Stops are called at in order, so each must be scheduled strictly after
+// the one before. Positions in the error count from 1.
+pub fn check_stop_order(times: &[NaiveTime]) -> Result<(), TransportError> {
+    match times.windows(2).position(|pair| pair[1] <= pair[0]) {
+        Some(index) => Err(TransportError::StopsOutOfOrder(index + 2)),
+        None => Ok(()),
+    }
+}
+
+// Whether one more student fits on a route served by a vehicle of
+// `capacity` seats (None when the route has no vehicle)
+pub fn check_seat(capacity: Option<i32>, assigned: i32) -> Result<(), TransportError> {
+    let capacity = capacity.ok_or(TransportError::NoVehicle)?;
+    if assigned >= capacity {
+        return Err(TransportError::RouteFull(capacity));
+    }
+    Ok(())
+}
+
+// Whether a vehicle of `capacity` seats can carry the busiest route it serves
+pub fn check_capacity(capacity: i32, busiest_route: i32) -> Result<(), TransportError> {
+    if busiest_route > capacity {
+        return Err(TransportError::CapacityTooSmall {
+            capacity,
+            assigned: busiest_route,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn stops_must_be_scheduled_in_order() {
+        assert!(check_stop_order(&[]).is_ok());
+        assert!(check_stop_order(&[time(7, 0), time(7, 10), time(7, 25)]).is_ok());
+        assert!(matches!(
+            check_stop_order(&[time(7, 0), time(7, 10), time(7, 10)]),
+            Err(TransportError::StopsOutOfOrder(3))
+        ));
+        assert!(matches!(
+            check_stop_order(&[time(7, 30), time(7, 10)]),
+            Err(TransportError::StopsOutOfOrder(2))
+        ));
+    }
+
+    #[test]
+    fn seats_are_limited_by_the_vehicle() {
+        assert!(check_seat(Some(40), 39).is_ok());
+        assert!(matches!(check_seat(Some(40), 40), Err(TransportError::RouteFull(40))));
+        assert!(matches!(check_seat(None, 0), Err(TransportError::NoVehicle)));
+        assert!(check_capacity(30, 30).is_ok());
+        assert!(matches!(
+            check_capacity(20, 25),
+            Err(TransportError::CapacityTooSmall { capacity: 20, assigned: 25 })
+        ));
+    }
+}
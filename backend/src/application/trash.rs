@@ -0,0 +1,68 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::{DatabaseConnection, DbErr};
+
+use crate::application::audit::AuditContext;
+use crate::config::Config;
+use crate::repositories::user_repository::UserRepository;
+
+// When a record deleted at `deleted_at` is purged for good
+pub fn purge_after(deleted_at: NaiveDateTime, retention_days: i64) -> NaiveDateTime {
+    deleted_at + Duration::days(retention_days)
+}
+
+// Purge every user, in every school, that has been in the trash longer than
+// the retention period
+pub async fn purge_expired(db: &DatabaseConnection, retention_days: i64) -> Result<usize, DbErr> {
+    let cutoff = Utc::now().naive_utc() - Duration::days(retention_days);
+    let expired = UserRepository::deleted_before(db, cutoff).await?;
+
+    // Each purge is its own transaction and audit entry, with no actor
+    let mut purged = 0;
+    for (tenant_id, id) in expired {
+        let context = AuditContext {
+            tenant_id: Some(tenant_id),
+            ..Default::default()
+        };
+        if UserRepository::purge(db, tenant_id, id, &context).await? {
+            purged += 1;
+        }
+    }
+    if purged > 0 {
+        tracing::info!("🗑️ Purged {} users from the trash", purged);
+    }
+    Ok(purged)
+}
+
+// Background task running `purge_expired` every TRASH_PURGE_INTERVAL_MINUTES
+pub fn spawn_scheduled_purge(db: DatabaseConnection, config: &Config) {
+    if config.trash_purge_interval_minutes == 0 {
+        return;
+    }
+    let retention_days = config.trash_retention_days;
+    let period = std::time::Duration::from_secs(config.trash_purge_interval_minutes * 60);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_expired(&db, retention_days).await {
+                tracing::error!("Trash purge failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn users_are_purged_once_the_retention_period_is_over() {
+        let deleted_at = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        let purge_at = NaiveDate::from_ymd_opt(2026, 10, 31).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        assert_eq!(purge_after(deleted_at, 30), purge_at);
+        assert_eq!(purge_after(deleted_at, 0), deleted_at);
+    }
+}
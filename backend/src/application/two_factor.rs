@@ -0,0 +1,141 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::config::Config;
+
+// RFC 6238 defaults, which is what every authenticator app expects
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+const SECRET_BYTES: usize = 20;
+// Codes from one step before or after are accepted to absorb clock drift
+const SKEW_STEPS: u64 = 1;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+// Unambiguous alphabet: no 0/o, 1/l/i
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TwoFactorError {
+    #[error("stored TOTP secret is invalid")]
+    InvalidSecret,
+}
+
+// A fresh random TOTP secret, base32 encoded as it is stored and shown
+pub fn new_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+fn totp(secret: &str, issuer: &str, account: &str) -> Result<TOTP, TwoFactorError> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|_| TwoFactorError::InvalidSecret)?;
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        SKEW_STEPS as u8,
+        STEP_SECONDS,
+        secret,
+        Some(issuer.to_string()),
+        account.to_string(),
+    )
+    .map_err(|_| TwoFactorError::InvalidSecret)
+}
+
+// otpauth:// URI that authenticator apps scan as a QR code
+pub fn provisioning_uri(issuer: &str, secret: &str, account: &str) -> Result<String, TwoFactorError> {
+    Ok(totp(secret, issuer, account)?.get_url())
+}
+
+// Check a code at `now` (unix seconds). Returns the matching time step, which
+// must be stored so the same code cannot be replayed; steps up to and
+// including `last_used_step` are refused.
+pub fn verify_code(
+    secret: &str,
+    code: &str,
+    last_used_step: Option<i64>,
+    now: u64,
+) -> Result<Option<i64>, TwoFactorError> {
+    let totp = totp(secret, "rsEdu", "")?;
+    let code = code.trim();
+    let current = now / STEP_SECONDS;
+
+    for step in current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS {
+        if last_used_step.is_some_and(|last| step as i64 <= last) {
+            continue;
+        }
+        if constant_time_eq(totp.generate(step * STEP_SECONDS).as_bytes(), code.as_bytes()) {
+            return Ok(Some(step as i64));
+        }
+    }
+    Ok(None)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// One-time recovery codes in "xxxxx-xxxxx" form, shown to the user once
+pub fn new_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 10];
+            OsRng.fill_bytes(&mut bytes);
+            let chars: String = bytes
+                .iter()
+                .map(|byte| RECOVERY_ALPHABET[*byte as usize % RECOVERY_ALPHABET.len()] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+// Recovery codes are hashed and compared without dashes, spaces or case
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+// Whether accounts with this role must use a second factor
+pub fn required_for(config: &Config, role: &str) -> bool {
+    config.two_factor_required_roles.iter().any(|required| required == role)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::auth;
+
+    #[test]
+    fn codes_verify_once_within_the_drift_window() {
+        let secret = new_secret();
+        let now = 1_800_000_000;
+        let code = totp(&secret, "rsEdu", "").unwrap().generate(now);
+
+        let step = verify_code(&secret, &code, None, now).unwrap();
+        assert_eq!(step, Some((now / STEP_SECONDS) as i64));
+        // Same code again is a replay
+        assert_eq!(verify_code(&secret, &code, step, now).unwrap(), None);
+        // A clock one step off is fine, three steps off is not
+        assert!(verify_code(&secret, &code, None, now + STEP_SECONDS).unwrap().is_some());
+        assert!(verify_code(&secret, &code, None, now + 3 * STEP_SECONDS).unwrap().is_none());
+
+        let uri = provisioning_uri("rsEdu Test", &secret, "teacher@school.local").unwrap();
+        assert!(uri.starts_with("otpauth://totp/"));
+        assert!(uri.contains(&format!("secret={}", secret)));
+    }
+
+    #[test]
+    fn recovery_codes_are_hashed_and_matched_loosely() {
+        let codes = new_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+        let hash = auth::hash_password(&normalize_recovery_code(&codes[0])).unwrap();
+        let typed = codes[0].to_uppercase().replace('-', " ");
+        assert!(auth::verify_password(&normalize_recovery_code(&typed), &hash));
+        assert!(!auth::verify_password(&normalize_recovery_code(&codes[1]), &hash));
+    }
+}
@@ -0,0 +1,564 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub const XAPI_VERSION: &str = "1.0.3";
+pub const VERSION_HEADER: &str = "X-Experience-API-Version";
+pub const HASH_HEADER: &str = "X-Experience-API-Hash";
+pub const VOIDED_VERB: &str = "http://adlnet.gov/expapi/verbs/voided";
+const ATTACHMENT_USAGE_SIGNATURE: &str = "http://adlnet.gov/expapi/attachments/signature";
+
+// Roles that record and read statements about anyone in their school, e.g.
+// the service account behind an activity provider. Everyone else only
+// records and reads statements about themselves.
+pub const LRS_ROLES: &[&str] = &["admin", "principal", "teacher"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum XapiError {
+    #[error("invalid statement: {0}")]
+    Invalid(String),
+    #[error("statement {0} already exists with different content")]
+    Conflict(String),
+    #[error("voided statement {0} does not exist or is itself a voiding statement")]
+    InvalidVoid(String),
+    #[error("missing attachment content for sha2 {0}")]
+    MissingAttachment(String),
+    #[error("you can only record and read your own statements")]
+    NotYourStatement,
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+}
+
+fn invalid(message: impl Into<String>) -> XapiError {
+    XapiError::Invalid(message.into())
+}
+
+// A statement after validation, with the values the LRS indexes on
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    pub id: String,
+    pub actor_ifi: String,
+    pub actor_email: Option<String>,
+    pub verb_id: String,
+    pub object_type: String,
+    pub object_id: Option<String>,
+    pub registration: Option<String>,
+    pub timestamp: NaiveDateTime,
+    pub stored: NaiveDateTime,
+    // Statement id this statement voids, if it is a voiding statement
+    pub voids: Option<String>,
+    pub attachment_hashes: Vec<String>,
+    pub statement: Value,
+}
+
+// A raw attachment part received in a multipart/mixed request
+#[derive(Debug, Clone)]
+pub struct AttachmentPart {
+    pub sha2: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+// Inverse Functional Identifier of an agent or identified group, flattened to one string
+// so it can be stored in and matched against a single indexed column.
+pub fn agent_ifi(agent: &Value) -> Result<String, XapiError> {
+    let agent = agent.as_object().ok_or_else(|| invalid("agent must be an object"))?;
+
+    let mut ifis = Vec::new();
+    if let Some(mbox) = agent.get("mbox") {
+        let mbox = mbox.as_str().filter(|m| m.starts_with("mailto:"));
+        ifis.push(format!("mbox:{}", mbox.ok_or_else(|| invalid("mbox must be a mailto IRI"))?.to_lowercase()));
+    }
+    if let Some(sha1) = agent.get("mbox_sha1sum") {
+        ifis.push(format!("mbox_sha1sum:{}", sha1.as_str().ok_or_else(|| invalid("mbox_sha1sum must be a string"))?));
+    }
+    if let Some(openid) = agent.get("openid") {
+        ifis.push(format!("openid:{}", openid.as_str().ok_or_else(|| invalid("openid must be a string"))?));
+    }
+    if let Some(account) = agent.get("account") {
+        let home_page = account.get("homePage").and_then(Value::as_str);
+        let name = account.get("name").and_then(Value::as_str);
+        match (home_page, name) {
+            (Some(home_page), Some(name)) => ifis.push(format!("account:{}|{}", home_page, name)),
+            _ => return Err(invalid("account needs homePage and name")),
+        }
+    }
+
+    match ifis.len() {
+        1 => Ok(ifis.remove(0)),
+        0 => Err(invalid("agent has no inverse functional identifier")),
+        _ => Err(invalid("agent has more than one inverse functional identifier")),
+    }
+}
+
+// The IFI of the agent a signed-in user is in statements
+pub fn user_ifi(email: &str) -> String {
+    format!("mbox:mailto:{}", email.to_lowercase())
+}
+
+// Email address behind an agent's mbox, used to map actors onto rsEdu users
+pub fn agent_email(agent: &Value) -> Option<String> {
+    agent
+        .get("mbox")
+        .and_then(Value::as_str)
+        .and_then(|mbox| mbox.strip_prefix("mailto:"))
+        .map(str::to_lowercase)
+}
+
+fn validate_actor(actor: &Value) -> Result<String, XapiError> {
+    let object_type = actor.get("objectType").and_then(Value::as_str).unwrap_or("Agent");
+    match object_type {
+        "Agent" => agent_ifi(actor),
+        "Group" => {
+            let members = actor.get("member").and_then(Value::as_array);
+            if let Some(members) = members {
+                for member in members {
+                    agent_ifi(member)?;
+                }
+            }
+            match agent_ifi(actor) {
+                Ok(ifi) => Ok(ifi),
+                // Anonymous groups are identified by their members
+                Err(_) if members.is_some_and(|m| !m.is_empty()) => Ok(format!(
+                    "group:{}",
+                    members
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|member| agent_ifi(member).ok())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )),
+                Err(e) => Err(e),
+            }
+        }
+        other => Err(invalid(format!("actor objectType {} is not allowed", other))),
+    }
+}
+
+fn require_iri(value: Option<&Value>, field: &str) -> Result<String, XapiError> {
+    let iri = value
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid(format!("{} is required", field)))?;
+    if !iri.contains(':') {
+        return Err(invalid(format!("{} must be an IRI", field)));
+    }
+    Ok(iri.to_string())
+}
+
+fn require_uuid(value: Option<&Value>, field: &str) -> Result<String, XapiError> {
+    let id = value
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid(format!("{} is required", field)))?;
+    Uuid::parse_str(id)
+        .map(|id| id.hyphenated().to_string())
+        .map_err(|_| invalid(format!("{} must be a UUID", field)))
+}
+
+pub fn parse_timestamp(value: &str) -> Result<NaiveDateTime, XapiError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| ts.naive_utc())
+        .map_err(|_| invalid(format!("{} is not an ISO 8601 timestamp", value)))
+}
+
+fn format_timestamp(ts: NaiveDateTime) -> String {
+    ts.and_utc().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+// Validate the object of a statement and return (objectType, id)
+fn validate_object(object: &Value, sub_statement_allowed: bool) -> Result<(String, Option<String>), XapiError> {
+    let object_type = object.get("objectType").and_then(Value::as_str).unwrap_or("Activity");
+    match object_type {
+        "Activity" => Ok((object_type.to_string(), Some(require_iri(object.get("id"), "object.id")?))),
+        "Agent" | "Group" => {
+            validate_actor(object)?;
+            Ok((object_type.to_string(), None))
+        }
+        "StatementRef" => Ok((object_type.to_string(), Some(require_uuid(object.get("id"), "object.id")?))),
+        "SubStatement" if sub_statement_allowed => {
+            for forbidden in ["id", "stored", "version", "authority"] {
+                if object.get(forbidden).is_some() {
+                    return Err(invalid(format!("SubStatement must not have {}", forbidden)));
+                }
+            }
+            validate_actor(object.get("actor").ok_or_else(|| invalid("SubStatement actor is required"))?)?;
+            require_iri(object.get("verb").and_then(|verb| verb.get("id")), "SubStatement verb.id")?;
+            validate_object(
+                object.get("object").ok_or_else(|| invalid("SubStatement object is required"))?,
+                false,
+            )?;
+            Ok((object_type.to_string(), None))
+        }
+        other => Err(invalid(format!("object objectType {} is not allowed here", other))),
+    }
+}
+
+fn validate_result(result: &Value) -> Result<(), XapiError> {
+    let Some(score) = result.get("score") else {
+        return Ok(());
+    };
+    if let Some(scaled) = score.get("scaled").and_then(Value::as_f64)
+        && !(-1.0..=1.0).contains(&scaled)
+    {
+        return Err(invalid("result.score.scaled must be between -1 and 1"));
+    }
+    let min = score.get("min").and_then(Value::as_f64);
+    let max = score.get("max").and_then(Value::as_f64);
+    let raw = score.get("raw").and_then(Value::as_f64);
+    if let (Some(min), Some(max)) = (min, max)
+        && min > max
+    {
+        return Err(invalid("result.score.min must not exceed max"));
+    }
+    if let Some(raw) = raw
+        && (min.is_some_and(|min| raw < min) || max.is_some_and(|max| raw > max))
+    {
+        return Err(invalid("result.score.raw must lie between min and max"));
+    }
+    Ok(())
+}
+
+fn validate_attachments(statement: &Map<String, Value>) -> Result<Vec<String>, XapiError> {
+    let Some(attachments) = statement.get("attachments") else {
+        return Ok(Vec::new());
+    };
+    let attachments = attachments.as_array().ok_or_else(|| invalid("attachments must be an array"))?;
+
+    let mut hashes = Vec::new();
+    for attachment in attachments {
+        require_iri(attachment.get("usageType"), "attachment.usageType")?;
+        if attachment.get("display").is_none_or(|display| !display.is_object()) {
+            return Err(invalid("attachment.display must be a language map"));
+        }
+        if attachment.get("contentType").and_then(Value::as_str).is_none() {
+            return Err(invalid("attachment.contentType is required"));
+        }
+        if attachment.get("length").and_then(Value::as_u64).is_none() {
+            return Err(invalid("attachment.length is required"));
+        }
+        let sha2 = attachment
+            .get("sha2")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("attachment.sha2 is required"))?;
+        let usage = attachment.get("usageType").and_then(Value::as_str);
+        let content_type = attachment.get("contentType").and_then(Value::as_str);
+        if usage == Some(ATTACHMENT_USAGE_SIGNATURE) && content_type != Some("application/octet-stream") {
+            return Err(invalid("signature attachments must be application/octet-stream"));
+        }
+        // Attachments referenced by fileUrl are not uploaded to us
+        if attachment.get("fileUrl").is_none() {
+            hashes.push(sha2.to_string());
+        }
+    }
+    Ok(hashes)
+}
+
+// Validate a statement as received from a client and fill in the LRS-owned properties
+pub fn prepare_statement(statement: Value, id_override: Option<&str>) -> Result<PreparedStatement, XapiError> {
+    let Value::Object(mut statement) = statement else {
+        return Err(invalid("statement must be a JSON object"));
+    };
+
+    // `stored` and `authority` are set by the LRS, never by the client
+    statement.remove("stored");
+    statement.remove("authority");
+
+    let id = match (statement.get("id"), id_override) {
+        (Some(id), Some(expected)) => {
+            let id = require_uuid(Some(id), "id")?;
+            if id != require_uuid(Some(&json!(expected)), "statementId")? {
+                return Err(invalid("statement id does not match statementId"));
+            }
+            id
+        }
+        (Some(id), None) => require_uuid(Some(id), "id")?,
+        (None, Some(expected)) => require_uuid(Some(&json!(expected)), "statementId")?,
+        (None, None) => Uuid::new_v4().hyphenated().to_string(),
+    };
+
+    let actor = statement.get("actor").ok_or_else(|| invalid("actor is required"))?;
+    let actor_ifi = validate_actor(actor)?;
+    let actor_email = agent_email(actor);
+
+    let verb_id = require_iri(statement.get("verb").and_then(|verb| verb.get("id")), "verb.id")?;
+
+    let object = statement.get("object").ok_or_else(|| invalid("object is required"))?;
+    let (object_type, object_id) = validate_object(object, true)?;
+
+    let voids = if verb_id == VOIDED_VERB {
+        if object_type != "StatementRef" {
+            return Err(invalid("a voiding statement must target a StatementRef"));
+        }
+        object_id.clone()
+    } else {
+        None
+    };
+
+    if let Some(result) = statement.get("result") {
+        validate_result(result)?;
+    }
+
+    let registration = match statement.get("context").and_then(|context| context.get("registration")) {
+        Some(registration) => Some(require_uuid(Some(registration), "context.registration")?),
+        None => None,
+    };
+
+    let attachment_hashes = validate_attachments(&statement)?;
+
+    let stored = Utc::now().naive_utc();
+    let timestamp = match statement.get("timestamp").and_then(Value::as_str) {
+        Some(ts) => parse_timestamp(ts)?,
+        None => stored,
+    };
+
+    statement.insert("id".to_string(), json!(id));
+    statement.insert("timestamp".to_string(), json!(format_timestamp(timestamp)));
+    statement.insert("stored".to_string(), json!(format_timestamp(stored)));
+    statement
+        .entry("version".to_string())
+        .or_insert_with(|| json!("1.0.0"));
+
+    Ok(PreparedStatement {
+        id,
+        actor_ifi,
+        actor_email,
+        verb_id,
+        object_type,
+        object_id,
+        registration,
+        timestamp,
+        stored,
+        voids,
+        attachment_hashes,
+        statement: Value::Object(statement),
+    })
+}
+
+// Two versions of a statement are the same if they only differ in LRS-owned properties
+pub fn same_statement(existing: &Value, incoming: &Value) -> bool {
+    let strip = |value: &Value| {
+        let mut value = value.clone();
+        if let Some(object) = value.as_object_mut() {
+            for key in ["stored", "authority", "version", "timestamp"] {
+                object.remove(key);
+            }
+        }
+        value
+    };
+    strip(existing) == strip(incoming)
+}
+
+// Check every attachment a statement references has a matching uploaded part
+pub fn check_attachments(statement: &PreparedStatement, parts: &[AttachmentPart]) -> Result<(), XapiError> {
+    for sha2 in &statement.attachment_hashes {
+        if !parts.iter().any(|part| &part.sha2 == sha2) {
+            return Err(XapiError::MissingAttachment(sha2.clone()));
+        }
+    }
+    Ok(())
+}
+
+// Split a multipart/mixed statement request into its JSON part and attachment parts.
+// Each attachment's content is checked against its X-Experience-API-Hash.
+pub fn parse_multipart(boundary: &str, body: &[u8]) -> Result<(Value, Vec<AttachmentPart>), XapiError> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = split_parts(body, delimiter.as_bytes()).into_iter();
+
+    let (_, statement_body) = parts.next().ok_or_else(|| invalid("multipart body has no parts"))?;
+    let statement = serde_json::from_slice(statement_body)
+        .map_err(|e| invalid(format!("first part must be the statement JSON: {}", e)))?;
+
+    let mut attachments = Vec::new();
+    for (headers, content) in parts {
+        let header = |name: &str| {
+            headers
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim().to_string())
+        };
+        let sha2 = header(HASH_HEADER).ok_or_else(|| invalid("attachment part is missing X-Experience-API-Hash"))?;
+        if format!("{:x}", Sha256::digest(content)) != sha2 {
+            return Err(invalid(format!("attachment content does not match sha2 {}", sha2)));
+        }
+        attachments.push(AttachmentPart {
+            sha2,
+            content_type: header("Content-Type").unwrap_or_else(|| "application/octet-stream".to_string()),
+            content: content.to_vec(),
+        });
+    }
+
+    Ok((statement, attachments))
+}
+
+fn split_parts<'a>(body: &'a [u8], delimiter: &[u8]) -> Vec<(String, &'a [u8])> {
+    let mut parts = Vec::new();
+    let mut rest = body;
+
+    // Skip the preamble up to the first delimiter
+    let Some(start) = find(rest, delimiter) else {
+        return parts;
+    };
+    rest = &rest[start + delimiter.len()..];
+
+    while !rest.starts_with(b"--") {
+        let Some(end) = find(rest, delimiter) else {
+            break;
+        };
+        let part = rest[..end]
+            .strip_prefix(b"\r\n")
+            .unwrap_or(&rest[..end]);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+
+        if let Some(split) = find(part, b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&part[..split]).to_string();
+            parts.push((headers, &part[split + 4..]));
+        } else {
+            parts.push((String::new(), part));
+        }
+        rest = &rest[end + delimiter.len()..];
+    }
+
+    parts
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+pub fn random_boundary() -> String {
+    format!("xapi-{}", Uuid::new_v4().simple())
+}
+
+// Build a multipart/mixed response body: the statement JSON followed by its attachments
+pub fn build_multipart(boundary: &str, json: &[u8], attachments: &[AttachmentPart]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(format!("--{}\r\nContent-Type: application/json\r\n\r\n", boundary).as_bytes());
+    body.extend_from_slice(json);
+    for attachment in attachments {
+        body.extend_from_slice(
+            format!(
+                "\r\n--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: binary\r\n{}: {}\r\n\r\n",
+                boundary, attachment.content_type, HASH_HEADER, attachment.sha2
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&attachment.content);
+    }
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement() -> Value {
+        json!({
+            "actor": { "mbox": "mailto:Ana@School.example" },
+            "verb": { "id": "http://adlnet.gov/expapi/verbs/completed" },
+            "object": { "id": "https://school.example/activities/fractions" },
+        })
+    }
+
+    fn attachment(content: &[u8]) -> (Value, AttachmentPart) {
+        let sha2 = format!("{:x}", Sha256::digest(content));
+        let reference = json!({
+            "usageType": "https://school.example/usage/worksheet",
+            "display": { "en-US": "Worksheet" },
+            "contentType": "text/plain",
+            "length": content.len(),
+            "sha2": sha2,
+        });
+        let part = AttachmentPart {
+            sha2,
+            content_type: "text/plain".to_string(),
+            content: content.to_vec(),
+        };
+        (reference, part)
+    }
+
+    #[test]
+    fn statements_are_validated_and_completed_by_the_lrs() {
+        let mut with_authority = statement();
+        with_authority["authority"] = json!({ "mbox": "mailto:someone@else.example" });
+        let prepared = prepare_statement(with_authority, None).unwrap();
+        assert_eq!(prepared.actor_ifi, "mbox:mailto:ana@school.example");
+        assert_eq!(prepared.actor_email.as_deref(), Some("ana@school.example"));
+        assert_eq!(prepared.object_type, "Activity");
+        assert!(prepared.statement.get("authority").is_none());
+        assert!(prepared.statement.get("stored").is_some());
+        assert_eq!(prepared.statement["id"], json!(prepared.id));
+
+        let id = Uuid::new_v4().hyphenated().to_string();
+        assert_eq!(prepare_statement(statement(), Some(&id)).unwrap().id, id);
+        let mut other_id = statement();
+        other_id["id"] = json!(Uuid::new_v4().hyphenated().to_string());
+        assert!(prepare_statement(other_id, Some(&id)).is_err());
+
+        let mut no_verb = statement();
+        no_verb.as_object_mut().unwrap().remove("verb");
+        assert!(prepare_statement(no_verb, None).is_err());
+        let mut two_ifis = statement();
+        two_ifis["actor"]["openid"] = json!("https://id.example/ana");
+        assert!(prepare_statement(two_ifis, None).is_err());
+        let mut bad_score = statement();
+        bad_score["result"] = json!({ "score": { "raw": 12, "min": 0, "max": 10 } });
+        assert!(prepare_statement(bad_score, None).is_err());
+        assert!(prepare_statement(json!([statement()]), None).is_err());
+    }
+
+    #[test]
+    fn voiding_statements_must_point_at_a_statement() {
+        let target = Uuid::new_v4().hyphenated().to_string();
+        let mut voiding = statement();
+        voiding["verb"]["id"] = json!(VOIDED_VERB);
+        voiding["object"] = json!({ "objectType": "StatementRef", "id": target });
+        assert_eq!(prepare_statement(voiding.clone(), None).unwrap().voids, Some(target));
+
+        voiding["object"] = json!({ "id": "https://school.example/activities/fractions" });
+        assert!(prepare_statement(voiding, None).is_err());
+        assert_eq!(prepare_statement(statement(), None).unwrap().voids, None);
+    }
+
+    #[test]
+    fn statements_are_immutable_apart_from_lrs_properties() {
+        let stored = prepare_statement(statement(), None).unwrap();
+        let id = stored.id.clone();
+
+        let mut again = statement();
+        again["id"] = json!(id);
+        let resent = prepare_statement(again.clone(), None).unwrap();
+        assert!(same_statement(&stored.statement, &resent.statement));
+
+        again["result"] = json!({ "success": true });
+        let changed = prepare_statement(again, None).unwrap();
+        assert!(!same_statement(&stored.statement, &changed.statement));
+    }
+
+    #[test]
+    fn multipart_attachments_round_trip_and_are_hash_checked() {
+        let (reference, part) = attachment(b"3/4 + 1/4 = 1");
+        let mut with_attachment = statement();
+        with_attachment["attachments"] = json!([reference]);
+        let json = serde_json::to_vec(&with_attachment).unwrap();
+
+        let boundary = random_boundary();
+        let body = build_multipart(&boundary, &json, std::slice::from_ref(&part));
+        let (parsed, parts) = parse_multipart(&boundary, &body).unwrap();
+        assert_eq!(parsed, with_attachment);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].content, part.content);
+
+        let prepared = prepare_statement(parsed, None).unwrap();
+        assert!(check_attachments(&prepared, &parts).is_ok());
+        assert!(matches!(check_attachments(&prepared, &[]), Err(XapiError::MissingAttachment(_))));
+
+        let tampered = AttachmentPart {
+            content: b"3/4 + 1/4 = 2".to_vec(),
+            ..part
+        };
+        let body = build_multipart(&boundary, &json, &[tampered]);
+        assert!(parse_multipart(&boundary, &body).is_err());
+    }
+}
@@ -1,22 +1,170 @@
 use std::env;
 
+use rust_decimal::Decimal;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub environment: String,
     pub port: u16,
     pub database_url: String,
-    #[allow(dead_code)]
+    // Shared counters for rate limiting and lockouts; memory is used if unreachable
     pub redis_url: String,
-    #[allow(dead_code)]
     pub jwt_secret: String,
-    #[allow(dead_code)]
     pub jwt_expiration_hours: i64,
+    // Public base URL of this deployment, used in redirects and LTI messages
+    pub app_url: String,
+    // PEM file with the RSA key rsEdu signs LTI messages with (tool key)
+    pub lti_private_key_path: Option<String>,
+    pub lti_key_id: String,
+    // OpenID Connect single sign-on; disabled when no issuer is set
+    pub oidc_issuer_url: Option<String>,
+    pub oidc_client_id: String,
+    pub oidc_client_secret: String,
+    pub oidc_redirect_url: String,
+    // Claim holding the user's role at the IdP, and how IdP roles map to rsEdu roles
+    pub oidc_role_claim: String,
+    pub oidc_role_map: Vec<(String, String)>,
+    // Create rsEdu accounts on first SSO login
+    pub oidc_jit_provisioning: bool,
+    pub oidc_default_role: String,
+    // Roles that must sign in through SSO instead of a local password
+    pub local_login_disabled_roles: Vec<String>,
+    // LDAP / Active Directory; disabled when no URL is set
+    pub ldap_url: Option<String>,
+    pub ldap_bind_dn: String,
+    pub ldap_bind_password: String,
+    pub ldap_base_dn: String,
+    pub ldap_user_filter: String,
+    pub ldap_email_attribute: String,
+    pub ldap_name_attribute: String,
+    // Directory group (CN) to rsEdu role, first match wins
+    pub ldap_group_role_map: Vec<(String, String)>,
+    // Minutes between directory syncs, 0 disables the scheduled sync
+    pub ldap_sync_interval_minutes: u64,
+    // Name authenticator apps show next to rsEdu codes
+    pub two_factor_issuer: String,
+    // Roles that must enroll a TOTP authenticator before they can sign in
+    pub two_factor_required_roles: Vec<String>,
+    // Password policy for new passwords
+    pub password_min_length: usize,
+    pub password_require_uppercase: bool,
+    pub password_require_lowercase: bool,
+    pub password_require_digit: bool,
+    pub password_require_symbol: bool,
+    pub password_history_size: usize,
+    // Text file with one known-breached password per line
+    pub password_breach_list_path: Option<String>,
+    // Frontend page the reset link points to, and how long the link works
+    pub password_reset_url: String,
+    pub password_reset_token_minutes: i64,
+    // Argon2 costs for new hashes; older hashes are upgraded on login
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    // Failed logins: backoff starts after `backoff_after` failures per account,
+    // the account locks at `max_attempts`, an address at `ip_max_attempts`
+    pub login_max_attempts: u64,
+    pub login_backoff_after: u64,
+    pub login_ip_max_attempts: u64,
+    pub login_failure_window_minutes: u64,
+    pub login_lockout_minutes: u64,
+    // Request budgets per path prefix, "prefix=requests/seconds"
+    pub rate_limits: Vec<(String, String)>,
+    // Proxies in front of the app that append to X-Forwarded-For; the client
+    // address is the hop the outermost one added. 0 ignores the header.
+    pub trusted_proxies: usize,
+    // Lifetime of an admin's "view as user" session
+    pub impersonation_minutes: i64,
+    // Days deleted users stay in the trash before they are purged, and how
+    // often the purge runs (0 disables it)
+    pub trash_retention_days: i64,
+    pub trash_purge_interval_minutes: u64,
+    // How often scheduled notices that have gone live are announced (0
+    // disables it)
+    pub notification_sweep_seconds: u64,
+    // Admins (other than the requester) who must approve a data erasure
+    pub erasure_required_approvals: usize,
+    // Master keys for encrypted columns, "id=base64 32-byte key"; new values are
+    // sealed with the active key, older keys only decrypt until rows are re-encrypted
+    pub encryption_keys: Vec<(String, String)>,
+    pub encryption_active_key: Option<String>,
+    // Base64 key for the keyed hashes that make encrypted identifiers searchable
+    pub blind_index_key: Option<String>,
+    // Minutes between re-encryption passes over rows sealed with an older key,
+    // 0 disables the scheduled pass
+    pub reencrypt_interval_minutes: u64,
+    // Schools are told apart by subdomain of this domain (e.g. "north" in
+    // north.rsedu.example); without it only the X-Tenant header and tokens are used
+    pub tenant_base_domain: Option<String>,
+    // Online fee payments; disabled when no provider is set. Webhooks are
+    // signed with the shared secret, checkouts are in `payment_currency`, and
+    // payers land on `payment_return_url` afterwards.
+    pub payment_provider: Option<String>,
+    pub payment_webhook_secret: String,
+    pub payment_currency: String,
+    pub payment_return_url: String,
+    // Library lending rules: loan length, renewals and loans per reader,
+    // the daily overdue fine and its cap per loan, the unpaid total that
+    // stops further borrowing, and days a returned copy is held for a reserver
+    pub library_loan_days: u64,
+    pub library_max_renewals: i32,
+    pub library_max_loans: usize,
+    pub library_fine_per_day: Decimal,
+    pub library_max_fine: Decimal,
+    pub library_fine_block_amount: Decimal,
+    pub library_hold_days: u64,
+    // How email leaves: "log" (the default), "smtp" through `smtp_url`, or
+    // "file" as .eml files in `mail_dir`. Mail comes from `mail_from`,
+    // under the school's name.
+    pub mail_transport: String,
+    pub smtp_url: String,
+    pub mail_from: String,
+    pub mail_dir: String,
+    // How often the outbox is checked for email to send (0 disables sending),
+    // and the attempts an email gets before it is marked failed
+    pub outbox_poll_seconds: u64,
+    pub outbox_max_attempts: i32,
+    // Families are reminded of an unpaid invoice this many days before it is
+    // due; the check runs every `invoice_reminder_interval_minutes` (0
+    // disables it)
+    pub invoice_reminder_days: i64,
+    pub invoice_reminder_interval_minutes: u64,
+}
+
+// Parse a boolean flag, "true" or "1"
+fn parse_flag(name: &str) -> bool {
+    env::var(name)
+        .map(|value| value == "true" || value == "1")
+        .unwrap_or(false)
+}
+
+// Parse "a,b,c" into a list, ignoring blanks
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Parse "from=to,from=to" into pairs
+fn parse_pairs(value: &str) -> Vec<(String, String)> {
+    parse_list(value)
+        .into_iter()
+        .filter_map(|pair| {
+            pair.split_once('=')
+                .map(|(from, to)| (from.trim().to_string(), to.trim().to_string()))
+        })
+        .collect()
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, env::VarError> {
         dotenvy::dotenv().ok();
 
+        let app_url = env::var("APP_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+
         Ok(Config {
             environment: env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string()),
             port: env::var("PORT")
@@ -30,6 +178,176 @@ impl Config {
                 .unwrap_or_else(|_| "24".to_string())
                 .parse()
                 .expect("JWT_EXPIRATION_HOURS must be a number"),
+            lti_private_key_path: env::var("LTI_PRIVATE_KEY_PATH").ok(),
+            lti_key_id: env::var("LTI_KEY_ID").unwrap_or_else(|_| "rsedu-lti-1".to_string()),
+            oidc_issuer_url: env::var("OIDC_ISSUER_URL").ok(),
+            oidc_client_id: env::var("OIDC_CLIENT_ID").unwrap_or_default(),
+            oidc_client_secret: env::var("OIDC_CLIENT_SECRET").unwrap_or_default(),
+            oidc_redirect_url: env::var("OIDC_REDIRECT_URL")
+                .unwrap_or_else(|_| format!("{}/api/v1/auth/oidc/callback", app_url)),
+            oidc_role_claim: env::var("OIDC_ROLE_CLAIM").unwrap_or_else(|_| "role".to_string()),
+            oidc_role_map: parse_pairs(&env::var("OIDC_ROLE_MAP").unwrap_or_default()),
+            oidc_jit_provisioning: env::var("OIDC_JIT_PROVISIONING")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+            oidc_default_role: env::var("OIDC_DEFAULT_ROLE").unwrap_or_else(|_| "student".to_string()),
+            local_login_disabled_roles: parse_list(&env::var("LOCAL_LOGIN_DISABLED_ROLES").unwrap_or_default()),
+            ldap_url: env::var("LDAP_URL").ok(),
+            ldap_bind_dn: env::var("LDAP_BIND_DN").unwrap_or_default(),
+            ldap_bind_password: env::var("LDAP_BIND_PASSWORD").unwrap_or_default(),
+            ldap_base_dn: env::var("LDAP_BASE_DN").unwrap_or_default(),
+            ldap_user_filter: env::var("LDAP_USER_FILTER")
+                .unwrap_or_else(|_| "(&(objectCategory=person)(objectClass=user))".to_string()),
+            ldap_email_attribute: env::var("LDAP_EMAIL_ATTRIBUTE").unwrap_or_else(|_| "mail".to_string()),
+            ldap_name_attribute: env::var("LDAP_NAME_ATTRIBUTE").unwrap_or_else(|_| "displayName".to_string()),
+            ldap_group_role_map: parse_pairs(&env::var("LDAP_GROUP_ROLE_MAP").unwrap_or_default()),
+            ldap_sync_interval_minutes: env::var("LDAP_SYNC_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("LDAP_SYNC_INTERVAL_MINUTES must be a number"),
+            two_factor_issuer: env::var("TWO_FACTOR_ISSUER").unwrap_or_else(|_| "rsEdu".to_string()),
+            two_factor_required_roles: parse_list(&env::var("TWO_FACTOR_REQUIRED_ROLES").unwrap_or_default()),
+            password_min_length: env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .expect("PASSWORD_MIN_LENGTH must be a number"),
+            password_require_uppercase: parse_flag("PASSWORD_REQUIRE_UPPERCASE"),
+            password_require_lowercase: parse_flag("PASSWORD_REQUIRE_LOWERCASE"),
+            password_require_digit: parse_flag("PASSWORD_REQUIRE_DIGIT"),
+            password_require_symbol: parse_flag("PASSWORD_REQUIRE_SYMBOL"),
+            password_history_size: env::var("PASSWORD_HISTORY_SIZE")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("PASSWORD_HISTORY_SIZE must be a number"),
+            password_breach_list_path: env::var("PASSWORD_BREACH_LIST_PATH").ok(),
+            password_reset_url: env::var("PASSWORD_RESET_URL")
+                .unwrap_or_else(|_| format!("{}/reset-password", app_url)),
+            password_reset_token_minutes: env::var("PASSWORD_RESET_TOKEN_MINUTES")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("PASSWORD_RESET_TOKEN_MINUTES must be a number"),
+            argon2_memory_kib: env::var("ARGON2_MEMORY_KIB")
+                .unwrap_or_else(|_| "19456".to_string())
+                .parse()
+                .expect("ARGON2_MEMORY_KIB must be a number"),
+            argon2_iterations: env::var("ARGON2_ITERATIONS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .expect("ARGON2_ITERATIONS must be a number"),
+            argon2_parallelism: env::var("ARGON2_PARALLELISM")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .expect("ARGON2_PARALLELISM must be a number"),
+            login_max_attempts: env::var("LOGIN_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("LOGIN_MAX_ATTEMPTS must be a number"),
+            login_backoff_after: env::var("LOGIN_BACKOFF_AFTER")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("LOGIN_BACKOFF_AFTER must be a number"),
+            login_ip_max_attempts: env::var("LOGIN_IP_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()
+                .expect("LOGIN_IP_MAX_ATTEMPTS must be a number"),
+            login_failure_window_minutes: env::var("LOGIN_FAILURE_WINDOW_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .expect("LOGIN_FAILURE_WINDOW_MINUTES must be a number"),
+            login_lockout_minutes: env::var("LOGIN_LOCKOUT_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .expect("LOGIN_LOCKOUT_MINUTES must be a number"),
+            rate_limits: parse_pairs(
+                &env::var("RATE_LIMITS")
+                    .unwrap_or_else(|_| "/api/v1/auth=30/60,/api/v1=600/60".to_string()),
+            ),
+            // TRUST_FORWARDED_FOR=true predates the count and means one proxy
+            trusted_proxies: match env::var("TRUSTED_PROXIES") {
+                Ok(count) => count.parse().expect("TRUSTED_PROXIES must be a number"),
+                Err(_) => usize::from(parse_flag("TRUST_FORWARDED_FOR")),
+            },
+            impersonation_minutes: env::var("IMPERSONATION_MINUTES")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("IMPERSONATION_MINUTES must be a number"),
+            trash_retention_days: env::var("TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("TRASH_RETENTION_DAYS must be a number"),
+            trash_purge_interval_minutes: env::var("TRASH_PURGE_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("TRASH_PURGE_INTERVAL_MINUTES must be a number"),
+            notification_sweep_seconds: env::var("NOTIFICATION_SWEEP_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("NOTIFICATION_SWEEP_SECONDS must be a number"),
+            erasure_required_approvals: env::var("ERASURE_REQUIRED_APPROVALS")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .expect("ERASURE_REQUIRED_APPROVALS must be a number"),
+            encryption_keys: parse_pairs(&env::var("ENCRYPTION_KEYS").unwrap_or_default()),
+            encryption_active_key: env::var("ENCRYPTION_ACTIVE_KEY").ok(),
+            blind_index_key: env::var("BLIND_INDEX_KEY").ok(),
+            reencrypt_interval_minutes: env::var("REENCRYPT_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("REENCRYPT_INTERVAL_MINUTES must be a number"),
+            tenant_base_domain: env::var("TENANT_BASE_DOMAIN").ok(),
+            payment_provider: env::var("PAYMENT_PROVIDER").ok(),
+            payment_webhook_secret: env::var("PAYMENT_WEBHOOK_SECRET").unwrap_or_default(),
+            payment_currency: env::var("PAYMENT_CURRENCY").unwrap_or_else(|_| "USD".to_string()),
+            payment_return_url: env::var("PAYMENT_RETURN_URL").unwrap_or_else(|_| format!("{}/fees", app_url)),
+            library_loan_days: env::var("LIBRARY_LOAN_DAYS")
+                .unwrap_or_else(|_| "14".to_string())
+                .parse()
+                .expect("LIBRARY_LOAN_DAYS must be a number"),
+            library_max_renewals: env::var("LIBRARY_MAX_RENEWALS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .expect("LIBRARY_MAX_RENEWALS must be a number"),
+            library_max_loans: env::var("LIBRARY_MAX_LOANS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("LIBRARY_MAX_LOANS must be a number"),
+            library_fine_per_day: env::var("LIBRARY_FINE_PER_DAY")
+                .unwrap_or_else(|_| "0.10".to_string())
+                .parse()
+                .expect("LIBRARY_FINE_PER_DAY must be an amount"),
+            library_max_fine: env::var("LIBRARY_MAX_FINE")
+                .unwrap_or_else(|_| "5.00".to_string())
+                .parse()
+                .expect("LIBRARY_MAX_FINE must be an amount"),
+            library_fine_block_amount: env::var("LIBRARY_FINE_BLOCK_AMOUNT")
+                .unwrap_or_else(|_| "5.00".to_string())
+                .parse()
+                .expect("LIBRARY_FINE_BLOCK_AMOUNT must be an amount"),
+            library_hold_days: env::var("LIBRARY_HOLD_DAYS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("LIBRARY_HOLD_DAYS must be a number"),
+            mail_transport: env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "log".to_string()),
+            smtp_url: env::var("SMTP_URL").unwrap_or_else(|_| "smtp://localhost:1025".to_string()),
+            mail_from: env::var("MAIL_FROM").unwrap_or_else(|_| "rsEdu <no-reply@rsedu.local>".to_string()),
+            mail_dir: env::var("MAIL_DIR").unwrap_or_else(|_| "mail".to_string()),
+            outbox_poll_seconds: env::var("OUTBOX_POLL_SECONDS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .expect("OUTBOX_POLL_SECONDS must be a number"),
+            outbox_max_attempts: env::var("OUTBOX_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .expect("OUTBOX_MAX_ATTEMPTS must be a number"),
+            invoice_reminder_days: env::var("INVOICE_REMINDER_DAYS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("INVOICE_REMINDER_DAYS must be a number"),
+            invoice_reminder_interval_minutes: env::var("INVOICE_REMINDER_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("INVOICE_REMINDER_INTERVAL_MINUTES must be a number"),
+            app_url,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,253 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::dto::user::UserResponse;
+use crate::entities::{admission_applications, admission_documents, admission_letters, admission_reviews, admission_stages};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - one stage of the pipeline; stages with an id are kept
+// (and may be renamed), others added
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct StageRequest {
+    pub id: Option<i32>,
+    #[validate(length(min = 1, message = "Stage name is required"))]
+    pub name: String,
+}
+
+// Request DTO - the school's full pipeline, in order. Stages left out are
+// removed.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReplaceStagesRequest {
+    #[validate(nested)]
+    pub stages: Vec<StageRequest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageResponse {
+    pub id: i32,
+    pub name: String,
+    pub position: i32,
+}
+
+impl From<admission_stages::Model> for StageResponse {
+    fn from(stage: admission_stages::Model) -> Self {
+        StageResponse {
+            id: stage.id,
+            name: stage.name,
+            position: stage.position,
+        }
+    }
+}
+
+// Request DTO - an application, from a family online or entered by staff
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApplicationRequest {
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub full_name: String,
+    pub date_of_birth: Option<NaiveDate>,
+    #[validate(length(min = 1, message = "Grade is required"))]
+    pub grade: String,
+    pub entry_term: Option<String>,
+    #[validate(email(message = "Invalid student email address"))]
+    pub student_email: Option<String>,
+    #[validate(length(min = 2, message = "Guardian name must be at least 2 characters"))]
+    pub guardian_name: String,
+    #[validate(email(message = "Invalid guardian email address"))]
+    pub guardian_email: String,
+    pub guardian_phone: Option<String>,
+    pub notes: Option<String>,
+}
+
+// What a family gets back after applying: the token lets them upload
+// documents until a decision is made. It is only shown once.
+#[derive(Debug, Serialize)]
+pub struct ApplyResponse {
+    pub application_id: i32,
+    pub upload_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplicationQuery {
+    pub stage_id: Option<i32>,
+    pub decision: Option<String>,
+    pub grade: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplicationResponse {
+    pub id: i32,
+    pub stage_id: i32,
+    pub full_name: String,
+    pub date_of_birth: Option<NaiveDate>,
+    pub grade: String,
+    pub entry_term: Option<String>,
+    pub student_email: Option<String>,
+    pub guardian_name: String,
+    pub guardian_email: String,
+    pub guardian_phone: Option<String>,
+    pub notes: Option<String>,
+    pub decision: String,
+    pub decided_at: Option<String>,
+    pub student_id: Option<i32>,
+    pub created_at: String,
+}
+
+impl From<admission_applications::Model> for ApplicationResponse {
+    fn from(application: admission_applications::Model) -> Self {
+        ApplicationResponse {
+            id: application.id,
+            stage_id: application.stage_id,
+            full_name: application.full_name,
+            date_of_birth: application.date_of_birth,
+            grade: application.grade,
+            entry_term: application.entry_term,
+            student_email: application.student_email,
+            guardian_name: application.guardian_name,
+            guardian_email: application.guardian_email,
+            guardian_phone: application.guardian_phone,
+            notes: application.notes,
+            decision: application.decision,
+            decided_at: application.decided_at.map(format_time),
+            student_id: application.student_id,
+            created_at: format_time(application.created_at),
+        }
+    }
+}
+
+// An application with its documents, scores and letters
+#[derive(Debug, Serialize)]
+pub struct ApplicationDetail {
+    pub application: ApplicationResponse,
+    pub stage: String,
+    pub average_score: Option<f64>,
+    pub reviews: Vec<ReviewResponse>,
+    pub documents: Vec<DocumentResponse>,
+    pub letters: Vec<LetterResponse>,
+}
+
+// Request DTO - move an application to another stage
+#[derive(Debug, Deserialize)]
+pub struct MoveStageRequest {
+    pub stage_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentQuery {
+    pub kind: String,
+    pub filename: String,
+}
+
+// Document metadata; the content is downloaded separately
+#[derive(Debug, Serialize)]
+pub struct DocumentResponse {
+    pub id: i32,
+    pub application_id: i32,
+    pub kind: String,
+    pub filename: String,
+    pub content_type: String,
+    pub length: i64,
+    pub uploaded_by: Option<i32>,
+    pub created_at: String,
+}
+
+impl From<admission_documents::Model> for DocumentResponse {
+    fn from(document: admission_documents::Model) -> Self {
+        DocumentResponse {
+            id: document.id,
+            application_id: document.application_id,
+            kind: document.kind,
+            filename: document.filename,
+            content_type: document.content_type,
+            length: document.length,
+            uploaded_by: document.uploaded_by,
+            created_at: format_time(document.created_at),
+        }
+    }
+}
+
+// Request DTO - a reviewer's score out of 10; scoring again replaces it
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReviewRequest {
+    #[validate(range(min = 1, max = 10, message = "Score must be between 1 and 10"))]
+    pub score: i32,
+    pub comments: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewResponse {
+    pub id: i32,
+    pub application_id: i32,
+    pub reviewer_id: i32,
+    pub score: i32,
+    pub comments: Option<String>,
+    pub updated_at: String,
+}
+
+impl From<admission_reviews::Model> for ReviewResponse {
+    fn from(review: admission_reviews::Model) -> Self {
+        ReviewResponse {
+            id: review.id,
+            application_id: review.application_id,
+            reviewer_id: review.reviewer_id,
+            score: review.score,
+            comments: review.comments,
+            updated_at: format_time(review.updated_at),
+        }
+    }
+}
+
+// Request DTO - decide a batch of applications at once and send each
+// family its letter
+#[derive(Debug, Deserialize, Validate)]
+pub struct DecisionRequest {
+    #[validate(length(min = 1, message = "At least one application is required"))]
+    pub application_ids: Vec<i32>,
+    pub decision: String,
+    // Added to every letter in the batch
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LetterResponse {
+    pub id: i32,
+    pub application_id: i32,
+    pub decision: String,
+    pub sent_to: String,
+    pub subject: String,
+    pub body: String,
+    pub sent_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<admission_letters::Model> for LetterResponse {
+    fn from(letter: admission_letters::Model) -> Self {
+        LetterResponse {
+            id: letter.id,
+            application_id: letter.application_id,
+            decision: letter.decision,
+            sent_to: letter.sent_to,
+            subject: letter.subject,
+            body: letter.body,
+            sent_at: letter.sent_at.map(format_time),
+            created_at: format_time(letter.created_at),
+        }
+    }
+}
+
+// Request DTO - turn an accepted applicant into a student account. The
+// email defaults to the one on the application.
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct EnrolRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnrolResponse {
+    pub application: ApplicationResponse,
+    pub student: UserResponse,
+}
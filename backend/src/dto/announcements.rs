@@ -0,0 +1,156 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entities::{announcement_attachments, announcements, class_memberships};
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - post or edit a notice. A new notice goes up straight away
+// unless `publish_at` is set, and stays up until `expires_at`, if given. An
+// edit without `publish_at` keeps the notice's publication time.
+#[derive(Debug, Deserialize, Validate)]
+pub struct AnnouncementRequest {
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: String,
+    #[validate(length(min = 1, message = "Body is required"))]
+    pub body: String,
+    pub audience: String,
+    pub grade: Option<String>,
+    pub section: Option<String>,
+    #[serde(default)]
+    pub is_pinned: bool,
+    pub publish_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AnnouncementQuery {
+    // For those who manage notices: include scheduled, expired and
+    // untargeted notices
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnouncementResponse {
+    pub id: i32,
+    pub title: String,
+    pub body: String,
+    pub audience: String,
+    pub grade: Option<String>,
+    pub section: Option<String>,
+    pub is_pinned: bool,
+    pub publish_at: String,
+    pub expires_at: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<announcements::Model> for AnnouncementResponse {
+    fn from(announcement: announcements::Model) -> Self {
+        AnnouncementResponse {
+            id: announcement.id,
+            title: announcement.title,
+            body: announcement.body,
+            audience: announcement.audience,
+            grade: announcement.grade,
+            section: announcement.section,
+            is_pinned: announcement.is_pinned,
+            publish_at: format_time(announcement.publish_at),
+            expires_at: announcement.expires_at.map(format_time),
+            created_by: announcement.created_by,
+            created_at: format_time(announcement.created_at),
+            updated_at: format_time(announcement.updated_at),
+        }
+    }
+}
+
+// A notice as the reader sees it: with its attachments and when they read it
+#[derive(Debug, Serialize)]
+pub struct NoticeResponse {
+    pub announcement: AnnouncementResponse,
+    pub attachments: Vec<AttachmentResponse>,
+    pub read_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentQuery {
+    pub filename: String,
+}
+
+// Attachment metadata; the content is downloaded separately
+#[derive(Debug, Serialize)]
+pub struct AttachmentResponse {
+    pub id: i32,
+    pub announcement_id: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub length: i64,
+    pub created_at: String,
+}
+
+impl From<announcement_attachments::Model> for AttachmentResponse {
+    fn from(attachment: announcement_attachments::Model) -> Self {
+        AttachmentResponse {
+            id: attachment.id,
+            announcement_id: attachment.announcement_id,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            length: attachment.length,
+            created_at: format_time(attachment.created_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReceiptResponse {
+    pub user_id: i32,
+    pub full_name: String,
+    pub role: String,
+    pub read_at: String,
+}
+
+// Who has read a notice, out of everyone it is addressed to
+#[derive(Debug, Serialize)]
+pub struct ReceiptsResponse {
+    pub announcement_id: i32,
+    pub audience: usize,
+    pub read: usize,
+    pub readers: Vec<ReceiptResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnreadCountResponse {
+    pub unread: usize,
+}
+
+// Request DTO - place a user in a grade and, optionally, a section
+#[derive(Debug, Deserialize, Validate)]
+pub struct ClassRequest {
+    #[validate(length(min = 1, message = "Grade is required"))]
+    pub grade: String,
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClassResponse {
+    pub user_id: i32,
+    pub grade: String,
+    pub section: Option<String>,
+    pub updated_at: String,
+}
+
+impl From<class_memberships::Model> for ClassResponse {
+    fn from(membership: class_memberships::Model) -> Self {
+        ClassResponse {
+            user_id: membership.user_id,
+            grade: membership.grade,
+            section: membership.section,
+            updated_at: format_time(membership.updated_at),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::dto::user::{validate_role, UserResponse};
+use crate::entities::api_keys;
+
+// Request DTO - create an API key for yourself, or (admins) for a service account
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1 to 100 characters"))]
+    pub name: String,
+
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<String>,
+
+    #[validate(range(min = 1, max = 3650, message = "Expiry must be 1 to 3650 days"))]
+    pub expires_in_days: Option<i64>,
+
+    // Owner of the key: the caller, or a service account (admins only)
+    pub user_id: Option<i32>,
+}
+
+// Response DTO - a key as listed; the secret itself is never shown again
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<api_keys::Model> for ApiKeyResponse {
+    fn from(key: api_keys::Model) -> Self {
+        let format = |time: chrono::NaiveDateTime| time.format("%Y-%m-%d %H:%M:%S").to_string();
+        ApiKeyResponse {
+            id: key.id,
+            user_id: key.user_id,
+            name: key.name,
+            prefix: key.prefix,
+            scopes: serde_json::from_value(key.scopes).unwrap_or_default(),
+            expires_at: key.expires_at.map(format),
+            last_used_at: key.last_used_at.map(format),
+            revoked_at: key.revoked_at.map(format),
+            created_at: format(key.created_at),
+        }
+    }
+}
+
+// Response DTO - a newly created key, including the one-time secret
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyResponse {
+    pub key: String,
+    pub api_key: ApiKeyResponse,
+}
+
+// Request DTO - create a service account (a user that only signs in with API keys)
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateServiceAccountRequest {
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub name: String,
+
+    #[validate(custom(function = "validate_role"))]
+    pub role: String,
+}
+
+// List response
+#[derive(Debug, Serialize)]
+pub struct ServiceAccountsListResponse {
+    pub service_accounts: Vec<UserResponse>,
+    pub total: usize,
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::entities::audit_log;
+
+// Query parameters of GET /audit
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub entity: Option<String>,
+    pub entity_id: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntryResponse {
+    pub id: i32,
+    pub actor_id: Option<i32>,
+    pub actor_email: Option<String>,
+    pub impersonator_id: Option<i32>,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip_address: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: String,
+    pub hash: String,
+    // When the snapshots were blanked by an erasure
+    pub redacted_at: Option<String>,
+}
+
+impl From<audit_log::Model> for AuditEntryResponse {
+    fn from(entry: audit_log::Model) -> Self {
+        AuditEntryResponse {
+            id: entry.id,
+            actor_id: entry.actor_id,
+            actor_email: entry.actor_email,
+            impersonator_id: entry.impersonator_id,
+            action: entry.action,
+            entity: entry.entity,
+            entity_id: entry.entity_id,
+            before: entry.before,
+            after: entry.after,
+            ip_address: entry.ip_address,
+            request_id: entry.request_id,
+            created_at: entry.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            hash: entry.hash,
+            redacted_at: entry.redacted_at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string()),
+        }
+    }
+}
+
+// List response
+#[derive(Debug, Serialize)]
+pub struct AuditListResponse {
+    pub entries: Vec<AuditEntryResponse>,
+    pub total: u64,
+}
+
+// Result of walking the hash chain
+#[derive(Debug, Serialize)]
+pub struct AuditVerifyResponse {
+    pub valid: bool,
+    pub entries_checked: u64,
+    pub first_invalid_id: Option<i32>,
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::dto::user::UserResponse;
+
+// Request DTO - local email/password login
+#[derive(Debug, Deserialize, Validate)]
+pub struct LoginRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+
+    #[validate(length(min = 1, message = "Password is required"))]
+    pub password: String,
+}
+
+// Response DTO - issued access token and the signed-in user
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub user: UserResponse,
+}
+
+// Query string the identity provider redirects back with
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub state: String,
+    pub code: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+// Response DTO - password accepted, second factor still needed. The challenge
+// token is exchanged at /auth/2fa/verify (or /auth/2fa/enable when the role
+// requires 2FA and the user has not enrolled yet).
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    pub enrollment_required: bool,
+    pub challenge_token: String,
+}
+
+// Response DTO - outcome of a password login
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    SignedIn(LoginResponse),
+    TwoFactor(TwoFactorChallengeResponse),
+}
+
+// Request DTO - start TOTP enrollment; signed-in users send a bearer token instead
+#[derive(Debug, Default, Deserialize)]
+pub struct TwoFactorSetupRequest {
+    pub challenge_token: Option<String>,
+}
+
+// Response DTO - secret to add to an authenticator app
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetupResponse {
+    pub secret: String,
+    // otpauth:// URI, rendered as a QR code by the frontend
+    pub provisioning_uri: String,
+}
+
+// Request DTO - a TOTP or recovery code
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodeRequest {
+    #[validate(length(min = 6, max = 32, message = "Code must be 6 to 32 characters"))]
+    pub code: String,
+
+    pub challenge_token: Option<String>,
+}
+
+// Response DTO - 2FA switched on; recovery codes are only ever shown here
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnabledResponse {
+    pub recovery_codes: Vec<String>,
+    // Set when enrollment finished a pending login
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login: Option<LoginResponse>,
+}
+
+// Response DTO - newly generated recovery codes
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+// Response DTO - 2FA state of the signed-in user
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub required: bool,
+    pub recovery_codes_remaining: u64,
+}
+
+// Request DTO - change the signed-in user's password
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, message = "Current password is required"))]
+    pub current_password: String,
+
+    #[validate(length(min = 1, message = "New password is required"))]
+    pub new_password: String,
+}
+
+// Request DTO - ask for a password reset link
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+}
+
+// Request DTO - set a new password with a reset token
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+
+    #[validate(length(min = 1, message = "New password is required"))]
+    pub new_password: String,
+}
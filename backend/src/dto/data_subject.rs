@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entities::{erasure_request_events, erasure_requests};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - open an erasure request
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateErasureRequest {
+    #[validate(length(min = 5, message = "Give a reason of at least 5 characters"))]
+    pub reason: String,
+}
+
+// Request DTO - approve or reject an erasure request
+#[derive(Debug, Deserialize)]
+pub struct ErasureDecisionRequest {
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErasureRequestResponse {
+    pub id: i32,
+    pub user_id: i32,
+    pub requested_by: i32,
+    pub reason: String,
+    pub status: String,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+}
+
+impl From<erasure_requests::Model> for ErasureRequestResponse {
+    fn from(request: erasure_requests::Model) -> Self {
+        ErasureRequestResponse {
+            id: request.id,
+            user_id: request.user_id,
+            requested_by: request.requested_by,
+            reason: request.reason,
+            status: request.status,
+            created_at: format_time(request.created_at),
+            completed_at: request.completed_at.map(format_time),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErasureEventResponse {
+    pub actor_id: Option<i32>,
+    pub event: String,
+    pub comment: Option<String>,
+    pub created_at: String,
+}
+
+impl From<erasure_request_events::Model> for ErasureEventResponse {
+    fn from(event: erasure_request_events::Model) -> Self {
+        ErasureEventResponse {
+            actor_id: event.actor_id,
+            event: event.event,
+            comment: event.comment,
+            created_at: format_time(event.created_at),
+        }
+    }
+}
+
+// A request with its approval log
+#[derive(Debug, Serialize)]
+pub struct ErasureRequestDetail {
+    pub request: ErasureRequestResponse,
+    pub events: Vec<ErasureEventResponse>,
+}
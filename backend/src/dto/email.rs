@@ -0,0 +1,61 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entities::email_outbox;
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OutboxQuery {
+    // pending, sent or failed; all of them when absent
+    pub status: Option<String>,
+}
+
+// A queued email without its body, which may hold a reset link
+#[derive(Debug, Serialize)]
+pub struct OutboxEmailResponse {
+    pub id: i32,
+    pub template: String,
+    pub to_address: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub sent_at: Option<String>,
+}
+
+impl From<email_outbox::Model> for OutboxEmailResponse {
+    fn from(email: email_outbox::Model) -> Self {
+        OutboxEmailResponse {
+            id: email.id,
+            template: email.template,
+            to_address: email.to_address,
+            subject: email.subject,
+            status: email.status,
+            attempts: email.attempts,
+            next_attempt_at: format_time(email.next_attempt_at),
+            last_error: email.last_error,
+            created_at: format_time(email.created_at),
+            sent_at: email.sent_at.map(format_time),
+        }
+    }
+}
+
+// Request DTO - tell a student's guardians they were absent
+#[derive(Debug, Deserialize, Validate)]
+pub struct AbsenceAlertRequest {
+    pub date: NaiveDate,
+    #[validate(length(max = 1000, message = "Note must be at most 1000 characters"))]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AbsenceAlertResponse {
+    // Guardians an email was queued for
+    pub queued: usize,
+}
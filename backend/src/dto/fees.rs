@@ -0,0 +1,299 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::application::fees;
+use crate::entities::{fee_discounts, fee_items, fee_schedules, invoice_lines, invoices, payments};
+
+// Money goes over the wire as decimal strings ("1250.00"), never floats
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - one fee of a schedule
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct FeeItemRequest {
+    pub category: String,
+    #[validate(length(min = 1, message = "Description is required"))]
+    pub description: String,
+    pub amount: Decimal,
+}
+
+// Request DTO - what a grade pays for a term
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateFeeScheduleRequest {
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "Grade is required"))]
+    pub grade: String,
+    #[validate(length(min = 1, message = "Term is required"))]
+    pub term: String,
+    pub due_date: NaiveDate,
+    #[validate(length(min = 1, message = "A schedule needs at least one fee"), nested)]
+    pub items: Vec<FeeItemRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeeScheduleQuery {
+    pub grade: Option<String>,
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeItemResponse {
+    pub id: i32,
+    pub category: String,
+    pub description: String,
+    pub amount: Decimal,
+}
+
+impl From<fee_items::Model> for FeeItemResponse {
+    fn from(item: fee_items::Model) -> Self {
+        FeeItemResponse {
+            id: item.id,
+            category: item.category,
+            description: item.description,
+            amount: item.amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeScheduleResponse {
+    pub id: i32,
+    pub name: String,
+    pub grade: String,
+    pub term: String,
+    pub due_date: NaiveDate,
+    pub total: Decimal,
+    pub items: Vec<FeeItemResponse>,
+}
+
+impl From<(fee_schedules::Model, Vec<fee_items::Model>)> for FeeScheduleResponse {
+    fn from((schedule, items): (fee_schedules::Model, Vec<fee_items::Model>)) -> Self {
+        FeeScheduleResponse {
+            id: schedule.id,
+            name: schedule.name,
+            grade: schedule.grade,
+            term: schedule.term,
+            due_date: schedule.due_date,
+            total: items.iter().map(|item| item.amount).sum(),
+            items: items.into_iter().map(FeeItemResponse::from).collect(),
+        }
+    }
+}
+
+// Request DTO - a discount or scholarship for a student: either a percentage
+// or a fixed amount, off one category (or all fees), for one term (or all)
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateDiscountRequest {
+    pub student_id: i32,
+    pub kind: String,
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub name: String,
+    pub percent: Option<Decimal>,
+    pub amount: Option<Decimal>,
+    pub category: Option<String>,
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscountQuery {
+    pub student_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscountResponse {
+    pub id: i32,
+    pub student_id: i32,
+    pub kind: String,
+    pub name: String,
+    pub percent: Option<Decimal>,
+    pub amount: Option<Decimal>,
+    pub category: Option<String>,
+    pub term: Option<String>,
+    pub is_active: bool,
+}
+
+impl From<fee_discounts::Model> for DiscountResponse {
+    fn from(discount: fee_discounts::Model) -> Self {
+        DiscountResponse {
+            id: discount.id,
+            student_id: discount.student_id,
+            kind: discount.kind,
+            name: discount.name,
+            percent: discount.percent,
+            amount: discount.amount,
+            category: discount.category,
+            term: discount.term,
+            is_active: discount.is_active,
+        }
+    }
+}
+
+// Request DTO - invoice these students for a schedule
+#[derive(Debug, Deserialize, Validate)]
+pub struct GenerateInvoicesRequest {
+    #[validate(length(min = 1, message = "Name at least one student"))]
+    pub student_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GenerateInvoicesResponse {
+    pub created: Vec<InvoiceResponse>,
+    // Students that already have an invoice for the schedule
+    pub already_invoiced: Vec<i32>,
+    // Ids that are not live students of the school
+    pub not_students: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceQuery {
+    pub student_id: Option<i32>,
+    pub status: Option<String>,
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceResponse {
+    pub id: i32,
+    pub number: String,
+    pub student_id: i32,
+    pub schedule_id: i32,
+    pub term: String,
+    pub due_date: NaiveDate,
+    pub subtotal: Decimal,
+    pub discount_total: Decimal,
+    pub total: Decimal,
+    pub amount_paid: Decimal,
+    pub balance: Decimal,
+    pub status: String,
+    pub issued_at: String,
+}
+
+impl From<invoices::Model> for InvoiceResponse {
+    fn from(invoice: invoices::Model) -> Self {
+        InvoiceResponse {
+            id: invoice.id,
+            number: fees::invoice_number(invoice.id),
+            student_id: invoice.student_id,
+            schedule_id: invoice.schedule_id,
+            term: invoice.term,
+            due_date: invoice.due_date,
+            subtotal: invoice.subtotal,
+            discount_total: invoice.discount_total,
+            total: invoice.total,
+            amount_paid: invoice.amount_paid,
+            balance: invoice.total - invoice.amount_paid,
+            status: invoice.status,
+            issued_at: format_time(invoice.issued_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceLineResponse {
+    pub category: String,
+    pub description: String,
+    pub amount: Decimal,
+}
+
+impl From<invoice_lines::Model> for InvoiceLineResponse {
+    fn from(line: invoice_lines::Model) -> Self {
+        InvoiceLineResponse {
+            category: line.category,
+            description: line.description,
+            amount: line.amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceDetail {
+    #[serde(flatten)]
+    pub invoice: InvoiceResponse,
+    pub lines: Vec<InvoiceLineResponse>,
+    pub payments: Vec<PaymentResponse>,
+}
+
+// Request DTO - money received against an invoice
+#[derive(Debug, Deserialize, Validate)]
+pub struct RecordPaymentRequest {
+    pub amount: Decimal,
+    pub method: String,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentResponse {
+    pub id: i32,
+    pub receipt_number: String,
+    pub invoice_id: i32,
+    pub amount: Decimal,
+    pub method: String,
+    pub reference: Option<String>,
+    pub refunded_amount: Decimal,
+    pub received_at: String,
+}
+
+impl From<payments::Model> for PaymentResponse {
+    fn from(payment: payments::Model) -> Self {
+        PaymentResponse {
+            id: payment.id,
+            receipt_number: fees::receipt_number(payment.id),
+            invoice_id: payment.invoice_id,
+            amount: payment.amount,
+            method: payment.method,
+            reference: payment.reference,
+            refunded_amount: payment.refunded_amount,
+            received_at: format_time(payment.received_at),
+        }
+    }
+}
+
+// Everything printed on a receipt
+#[derive(Debug, Serialize)]
+pub struct ReceiptResponse {
+    pub receipt_number: String,
+    pub school: String,
+    pub student_id: i32,
+    pub student_name: Option<String>,
+    pub invoice_number: String,
+    pub term: String,
+    pub amount: Decimal,
+    pub method: String,
+    pub reference: Option<String>,
+    pub received_at: String,
+    pub invoice_total: Decimal,
+    pub paid_to_date: Decimal,
+    pub balance_after: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StudentBalance {
+    pub student_id: i32,
+    pub invoiced: Decimal,
+    pub paid: Decimal,
+    pub outstanding: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AgingQuery {
+    pub as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgingBucketResponse {
+    pub bucket: String,
+    pub invoices: usize,
+    pub outstanding: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgingReportResponse {
+    pub as_of: NaiveDate,
+    pub buckets: Vec<AgingBucketResponse>,
+    pub total_outstanding: Decimal,
+}
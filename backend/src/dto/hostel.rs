@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entities::{hostel_allocations, hostel_beds, hostel_rooms, hostel_stay_logs};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn active() -> bool {
+    true
+}
+
+// Request DTO - add or update a room. `beds` labels the beds created with
+// a new room and is ignored on update.
+#[derive(Debug, Deserialize, Validate)]
+pub struct RoomRequest {
+    #[validate(length(min = 1, message = "Name is required"))]
+    pub name: String,
+    pub building: Option<String>,
+    pub gender: String,
+    pub grade: Option<String>,
+    #[serde(default = "active")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub beds: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoomResponse {
+    pub id: i32,
+    pub name: String,
+    pub building: Option<String>,
+    pub gender: String,
+    pub grade: Option<String>,
+    pub is_active: bool,
+}
+
+impl From<hostel_rooms::Model> for RoomResponse {
+    fn from(room: hostel_rooms::Model) -> Self {
+        RoomResponse {
+            id: room.id,
+            name: room.name,
+            building: room.building,
+            gender: room.gender,
+            grade: room.grade,
+            is_active: room.is_active,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoomDetail {
+    pub room: RoomResponse,
+    pub beds: Vec<BedResponse>,
+}
+
+// Request DTO - add a bed to a room, or take one out of use
+#[derive(Debug, Deserialize, Validate)]
+pub struct BedRequest {
+    #[validate(length(min = 1, message = "Label is required"))]
+    pub label: String,
+    #[serde(default = "active")]
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BedResponse {
+    pub id: i32,
+    pub room_id: i32,
+    pub label: String,
+    pub is_active: bool,
+}
+
+impl From<hostel_beds::Model> for BedResponse {
+    fn from(bed: hostel_beds::Model) -> Self {
+        BedResponse {
+            id: bed.id,
+            room_id: bed.room_id,
+            label: bed.label,
+            is_active: bed.is_active,
+        }
+    }
+}
+
+// Request DTO - put a student in a particular bed for a term
+#[derive(Debug, Deserialize, Validate)]
+pub struct AllocateRequest {
+    #[validate(length(min = 1, message = "Term is required"))]
+    pub term: String,
+    pub student_id: i32,
+    pub bed_id: i32,
+    pub gender: String,
+    #[validate(length(min = 1, message = "Grade is required"))]
+    pub grade: String,
+}
+
+// Request DTO - one student to be placed by automatic allocation
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct BoarderRequest {
+    pub student_id: i32,
+    pub gender: String,
+    #[validate(length(min = 1, message = "Grade is required"))]
+    pub grade: String,
+}
+
+// Request DTO - place students into free beds for a term. Students who
+// already have a bed that term are left where they are.
+#[derive(Debug, Deserialize, Validate)]
+pub struct AutoAllocateRequest {
+    #[validate(length(min = 1, message = "Term is required"))]
+    pub term: String,
+    #[validate(length(min = 1, message = "At least one student is required"), nested)]
+    pub students: Vec<BoarderRequest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnplacedStudent {
+    pub student_id: i32,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AutoAllocateResponse {
+    pub allocated: Vec<AllocationResponse>,
+    pub unplaced: Vec<UnplacedStudent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllocationQuery {
+    pub term: Option<String>,
+    pub student_id: Option<i32>,
+    pub room_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllocationResponse {
+    pub id: i32,
+    pub term: String,
+    pub bed_id: i32,
+    pub student_id: i32,
+    pub gender: String,
+    pub grade: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+impl From<hostel_allocations::Model> for AllocationResponse {
+    fn from(allocation: hostel_allocations::Model) -> Self {
+        AllocationResponse {
+            id: allocation.id,
+            term: allocation.term,
+            bed_id: allocation.bed_id,
+            student_id: allocation.student_id,
+            gender: allocation.gender,
+            grade: allocation.grade,
+            status: allocation.status,
+            created_at: format_time(allocation.created_at),
+        }
+    }
+}
+
+// Request DTO - record a check-in or check-out
+#[derive(Debug, Default, Deserialize)]
+pub struct StayRequest {
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StayLogResponse {
+    pub id: i32,
+    pub allocation_id: i32,
+    pub event: String,
+    pub notes: Option<String>,
+    pub recorded_by: Option<i32>,
+    pub occurred_at: String,
+}
+
+impl From<hostel_stay_logs::Model> for StayLogResponse {
+    fn from(log: hostel_stay_logs::Model) -> Self {
+        StayLogResponse {
+            id: log.id,
+            allocation_id: log.allocation_id,
+            event: log.event,
+            notes: log.notes,
+            recorded_by: log.recorded_by,
+            occurred_at: format_time(log.occurred_at),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OccupancyQuery {
+    pub term: String,
+}
+
+// One room's line of the occupancy report; also the CSV/XLSX row
+#[derive(Debug, Serialize)]
+pub struct RoomOccupancy {
+    pub room_id: i32,
+    pub room: String,
+    pub building: Option<String>,
+    pub gender: String,
+    pub grade: Option<String>,
+    pub beds: usize,
+    pub allocated: usize,
+    pub checked_in: usize,
+    pub free: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OccupancyResponse {
+    pub term: String,
+    pub beds: usize,
+    pub allocated: usize,
+    pub checked_in: usize,
+    pub free: usize,
+    pub rooms: Vec<RoomOccupancy>,
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::dto::user::UserResponse;
+use crate::entities::{impersonation_actions, impersonation_sessions};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - why support needs to see the app as this user
+#[derive(Debug, Deserialize, Validate)]
+pub struct StartImpersonationRequest {
+    #[validate(length(min = 5, message = "Give a reason of at least 5 characters"))]
+    pub reason: String,
+}
+
+// Response DTO - a token that signs in as the target user until `expires_at`
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    pub token: String,
+    pub user: UserResponse,
+    pub impersonated_by: String,
+    pub session_id: i32,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationSessionResponse {
+    pub id: i32,
+    pub admin_id: i32,
+    pub target_user_id: i32,
+    pub reason: String,
+    pub started_at: String,
+    pub expires_at: String,
+    pub ended_at: Option<String>,
+}
+
+impl From<impersonation_sessions::Model> for ImpersonationSessionResponse {
+    fn from(session: impersonation_sessions::Model) -> Self {
+        ImpersonationSessionResponse {
+            id: session.id,
+            admin_id: session.admin_id,
+            target_user_id: session.target_user_id,
+            reason: session.reason,
+            started_at: format_time(session.started_at),
+            expires_at: format_time(session.expires_at),
+            ended_at: session.ended_at.map(format_time),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationActionResponse {
+    pub method: String,
+    pub path: String,
+    pub status: i32,
+    pub ip_address: Option<String>,
+    pub created_at: String,
+}
+
+impl From<impersonation_actions::Model> for ImpersonationActionResponse {
+    fn from(action: impersonation_actions::Model) -> Self {
+        ImpersonationActionResponse {
+            method: action.method,
+            path: action.path,
+            status: action.status,
+            ip_address: action.ip_address,
+            created_at: format_time(action.created_at),
+        }
+    }
+}
+
+// A session with everything done during it
+#[derive(Debug, Serialize)]
+pub struct ImpersonationSessionDetail {
+    pub session: ImpersonationSessionResponse,
+    pub actions: Vec<ImpersonationActionResponse>,
+}
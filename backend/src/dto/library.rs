@@ -0,0 +1,225 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::application::library::COPY_AVAILABLE;
+use crate::entities::{library_copies, library_holds, library_loans, library_titles};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - add a title to the catalog
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTitleRequest {
+    // ISBN-10 or ISBN-13, stored as ISBN-13
+    pub isbn: Option<String>,
+    #[validate(length(min = 1, message = "Title is required"))]
+    pub title: String,
+    pub author: Option<String>,
+    pub publisher: Option<String>,
+    #[validate(range(min = 1000, max = 9999, message = "Year must have four digits"))]
+    pub published_year: Option<i32>,
+}
+
+// Searches title and author, or matches an ISBN exactly
+#[derive(Debug, Deserialize)]
+pub struct TitleQuery {
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TitleResponse {
+    pub id: i32,
+    pub isbn: Option<String>,
+    pub title: String,
+    pub author: Option<String>,
+    pub publisher: Option<String>,
+    pub published_year: Option<i32>,
+    pub available: usize,
+    pub copies: Vec<CopyResponse>,
+}
+
+impl From<(library_titles::Model, Vec<library_copies::Model>)> for TitleResponse {
+    fn from((title, copies): (library_titles::Model, Vec<library_copies::Model>)) -> Self {
+        TitleResponse {
+            id: title.id,
+            isbn: title.isbn,
+            title: title.title,
+            author: title.author,
+            publisher: title.publisher,
+            published_year: title.published_year,
+            available: copies.iter().filter(|copy| copy.status == COPY_AVAILABLE).count(),
+            copies: copies.into_iter().map(CopyResponse::from).collect(),
+        }
+    }
+}
+
+// Request DTO - a physical copy of a title
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCopyRequest {
+    #[validate(length(min = 1, message = "Barcode is required"))]
+    pub barcode: String,
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CopyResponse {
+    pub id: i32,
+    pub title_id: i32,
+    pub barcode: String,
+    pub location: Option<String>,
+    pub status: String,
+}
+
+impl From<library_copies::Model> for CopyResponse {
+    fn from(copy: library_copies::Model) -> Self {
+        CopyResponse {
+            id: copy.id,
+            title_id: copy.title_id,
+            barcode: copy.barcode,
+            location: copy.location,
+            status: copy.status,
+        }
+    }
+}
+
+// Request DTO - mark a copy lost or withdrawn, or put it back on the shelf
+#[derive(Debug, Deserialize)]
+pub struct UpdateCopyStatusRequest {
+    pub status: String,
+}
+
+// Request DTO - lend the copy with this barcode to a user
+#[derive(Debug, Deserialize, Validate)]
+pub struct CheckoutRequest {
+    #[validate(length(min = 1, message = "Barcode is required"))]
+    pub barcode: String,
+    pub user_id: i32,
+}
+
+// Request DTO - take back the copy with this barcode
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReturnRequest {
+    #[validate(length(min = 1, message = "Barcode is required"))]
+    pub barcode: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoanQuery {
+    pub user_id: Option<i32>,
+    // Only loans not yet returned
+    #[serde(default)]
+    pub open: bool,
+    // Only loans with a fine still owed
+    #[serde(default)]
+    pub fines_owed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoanResponse {
+    pub id: i32,
+    pub copy_id: i32,
+    pub user_id: i32,
+    pub checked_out_at: String,
+    pub due_date: NaiveDate,
+    pub returned_at: Option<String>,
+    pub renewals: i32,
+    pub fine_amount: Decimal,
+    pub fine_status: String,
+}
+
+impl From<library_loans::Model> for LoanResponse {
+    fn from(loan: library_loans::Model) -> Self {
+        LoanResponse {
+            id: loan.id,
+            copy_id: loan.copy_id,
+            user_id: loan.user_id,
+            checked_out_at: format_time(loan.checked_out_at),
+            due_date: loan.due_date,
+            returned_at: loan.returned_at.map(format_time),
+            renewals: loan.renewals,
+            fine_amount: loan.fine_amount,
+            fine_status: loan.fine_status,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReturnResponse {
+    pub loan: LoanResponse,
+    // The hold the copy was set aside for, if anyone was waiting
+    pub ready_hold: Option<HoldResponse>,
+}
+
+// Request DTO - settle a loan's fine as "paid" or "waived"
+#[derive(Debug, Deserialize)]
+pub struct SettleFineRequest {
+    pub status: String,
+}
+
+// Request DTO - join the queue for a title; staff may place holds for others
+#[derive(Debug, Deserialize)]
+pub struct PlaceHoldRequest {
+    pub user_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HoldQuery {
+    pub user_id: Option<i32>,
+    pub title_id: Option<i32>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HoldResponse {
+    pub id: i32,
+    pub title_id: i32,
+    pub user_id: i32,
+    pub status: String,
+    // The copy waiting on the hold shelf once the hold is ready
+    pub copy_id: Option<i32>,
+    pub created_at: String,
+    pub ready_at: Option<String>,
+    pub expires_on: Option<NaiveDate>,
+}
+
+impl From<library_holds::Model> for HoldResponse {
+    fn from(hold: library_holds::Model) -> Self {
+        HoldResponse {
+            id: hold.id,
+            title_id: hold.title_id,
+            user_id: hold.user_id,
+            status: hold.status,
+            copy_id: hold.copy_id,
+            created_at: format_time(hold.created_at),
+            ready_at: hold.ready_at.map(format_time),
+            expires_on: hold.expires_on,
+        }
+    }
+}
+
+// The file format of a catalog import: "csv" or "marc"
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub format: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportErrorResponse {
+    // CSV line or MARC record number
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResponse {
+    pub titles_created: usize,
+    // Records whose ISBN matched a title already in the catalog
+    pub titles_matched: usize,
+    pub copies_created: usize,
+    // Barcodes already in the catalog, left as they were
+    pub duplicate_barcodes: Vec<String>,
+    pub errors: Vec<ImportErrorResponse>,
+}
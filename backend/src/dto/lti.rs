@@ -0,0 +1,157 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use validator::Validate;
+
+use crate::dto::auth::TwoFactorChallengeResponse;
+use crate::entities::{lti_platforms, lti_user_links};
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Response DTO - a registered LMS platform
+#[derive(Debug, Serialize, Clone)]
+pub struct PlatformResponse {
+    pub id: i32,
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub deployment_id: Option<String>,
+    pub auth_login_url: String,
+    pub auth_token_url: String,
+    pub jwks_url: String,
+    pub is_active: bool,
+}
+
+impl From<lti_platforms::Model> for PlatformResponse {
+    fn from(platform: lti_platforms::Model) -> Self {
+        PlatformResponse {
+            id: platform.id,
+            name: platform.name,
+            issuer: platform.issuer,
+            client_id: platform.client_id,
+            deployment_id: platform.deployment_id,
+            auth_login_url: platform.auth_login_url,
+            auth_token_url: platform.auth_token_url,
+            jwks_url: platform.jwks_url,
+            is_active: platform.is_active,
+        }
+    }
+}
+
+// Request DTO - register an LMS platform with rsEdu
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreatePlatformRequest {
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub name: String,
+
+    #[validate(length(min = 1, message = "Issuer is required"))]
+    pub issuer: String,
+
+    #[validate(length(min = 1, message = "Client id is required"))]
+    pub client_id: String,
+
+    pub deployment_id: Option<String>,
+
+    #[validate(url(message = "Invalid OIDC auth login URL"))]
+    pub auth_login_url: String,
+
+    #[validate(url(message = "Invalid access token URL"))]
+    pub auth_token_url: String,
+
+    #[validate(url(message = "Invalid JWKS URL"))]
+    pub jwks_url: String,
+}
+
+// OIDC third-party login initiation, sent by the platform as query or form
+#[derive(Debug, Deserialize)]
+pub struct LoginInitiationRequest {
+    pub iss: String,
+    pub login_hint: String,
+    pub target_link_uri: String,
+    pub client_id: Option<String>,
+    pub lti_message_hint: Option<String>,
+    pub lti_deployment_id: Option<String>,
+}
+
+// Form posted by the platform to the launch URL
+#[derive(Debug, Deserialize)]
+pub struct LaunchForm {
+    pub id_token: String,
+    pub state: String,
+}
+
+// What the frontend gets back after a successful launch. A platform user
+// linked to an account gets a token, or a 2FA challenge when the account
+// needs a second factor; an unlinked one gets neither and can link the
+// launch once signed in.
+#[derive(Debug, Serialize)]
+pub struct LaunchResponse {
+    pub launch_id: String,
+    pub message_type: String,
+    pub target_link_uri: String,
+    pub user_id: Option<i32>,
+    pub token: Option<String>,
+    pub two_factor: Option<TwoFactorChallengeResponse>,
+    pub roles: Vec<String>,
+    pub context: Option<Value>,
+    pub resource_link: Option<Value>,
+    pub deep_linking: bool,
+    pub can_post_scores: bool,
+}
+
+// Response DTO - a platform user signing in as an rsEdu account
+#[derive(Debug, Serialize)]
+pub struct UserLinkResponse {
+    pub id: i32,
+    pub platform_id: i32,
+    pub subject: String,
+    pub user_id: i32,
+    pub created_at: String,
+}
+
+impl From<lti_user_links::Model> for UserLinkResponse {
+    fn from(link: lti_user_links::Model) -> Self {
+        UserLinkResponse {
+            id: link.id,
+            platform_id: link.platform_id,
+            subject: link.subject,
+            user_id: link.user_id,
+            created_at: format_time(link.created_at),
+        }
+    }
+}
+
+// Request DTO - content items picked during deep linking
+#[derive(Debug, Deserialize)]
+pub struct DeepLinkingRequest {
+    pub items: Vec<Value>,
+    pub message: Option<String>,
+}
+
+// The signed deep linking response the browser has to post back to the platform
+#[derive(Debug, Serialize)]
+pub struct DeepLinkingResponse {
+    pub return_url: String,
+    pub jwt: String,
+}
+
+// Request DTO - score passback through Assignment and Grade Services
+#[derive(Debug, Deserialize, Validate)]
+pub struct ScoreRequest {
+    // Platform user id (`sub`); defaults to the user of the launch
+    pub user_id: Option<String>,
+
+    #[validate(range(min = 0.0, message = "Score must not be negative"))]
+    pub score_given: f64,
+
+    #[validate(range(min = 0.0, message = "Maximum score must not be negative"))]
+    pub score_maximum: f64,
+
+    pub comment: Option<String>,
+
+    pub activity_progress: Option<String>,
+
+    pub grading_progress: Option<String>,
+}
@@ -0,0 +1,265 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::application::messaging::Rules;
+use crate::entities::{guardian_links, message_archive, message_attachments, message_threads, messaging_settings};
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - start a conversation with one or more people
+#[derive(Debug, Deserialize, Validate)]
+pub struct ThreadRequest {
+    #[validate(length(min = 1, max = 200, message = "Subject must be between 1 and 200 characters"))]
+    pub subject: String,
+    #[validate(length(min = 1, message = "At least one recipient is required"))]
+    pub recipient_ids: Vec<i32>,
+    #[validate(length(min = 1, message = "Message is required"))]
+    pub body: String,
+}
+
+// Request DTO - reply in a thread
+#[derive(Debug, Deserialize, Validate)]
+pub struct MessageRequest {
+    #[validate(length(min = 1, message = "Message is required"))]
+    pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreadResponse {
+    pub id: i32,
+    pub subject: String,
+    pub created_by: Option<i32>,
+    pub is_locked: bool,
+    pub created_at: String,
+    pub last_message_at: String,
+}
+
+impl From<message_threads::Model> for ThreadResponse {
+    fn from(thread: message_threads::Model) -> Self {
+        ThreadResponse {
+            id: thread.id,
+            subject: thread.subject,
+            created_by: thread.created_by,
+            is_locked: thread.is_locked,
+            created_at: format_time(thread.created_at),
+            last_message_at: format_time(thread.last_message_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParticipantResponse {
+    pub user_id: i32,
+    pub full_name: String,
+    pub role: String,
+    pub last_read_at: Option<String>,
+}
+
+// A line in the inbox
+#[derive(Debug, Serialize)]
+pub struct ThreadSummary {
+    pub thread: ThreadResponse,
+    pub participants: Vec<ParticipantResponse>,
+    pub unread: i64,
+}
+
+// A message as a participant sees it. Hidden messages keep their place in
+// the thread but lose their body, except for moderators. `read_by` lists
+// the other participants who have read up to it.
+#[derive(Debug, Serialize)]
+pub struct MessageResponse {
+    pub id: i32,
+    pub thread_id: i32,
+    pub sender_id: Option<i32>,
+    pub body: Option<String>,
+    pub hidden: bool,
+    pub created_at: String,
+    pub attachments: Vec<AttachmentResponse>,
+    pub read_by: Vec<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreadDetail {
+    pub thread: ThreadResponse,
+    pub participants: Vec<ParticipantResponse>,
+    pub messages: Vec<MessageResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentQuery {
+    pub filename: String,
+}
+
+// Attachment metadata; the content is downloaded separately
+#[derive(Debug, Serialize)]
+pub struct AttachmentResponse {
+    pub id: i32,
+    pub message_id: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub length: i64,
+    pub created_at: String,
+}
+
+impl From<message_attachments::Model> for AttachmentResponse {
+    fn from(attachment: message_attachments::Model) -> Self {
+        AttachmentResponse {
+            id: attachment.id,
+            message_id: attachment.message_id,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            length: attachment.length,
+            created_at: format_time(attachment.created_at),
+        }
+    }
+}
+
+// Request DTO - flag a message for the moderators
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReportRequest {
+    #[validate(length(min = 1, message = "Reason is required"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    pub status: Option<String>,
+}
+
+// A report with the message it is about, for moderators
+#[derive(Debug, Serialize)]
+pub struct ReportResponse {
+    pub id: i32,
+    pub message_id: i32,
+    pub thread_id: i32,
+    pub sender_id: Option<i32>,
+    pub message: String,
+    pub message_hidden: bool,
+    pub reporter_id: Option<i32>,
+    pub reason: String,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub resolved_by: Option<i32>,
+    pub resolved_at: Option<String>,
+    pub created_at: String,
+}
+
+// Request DTO - close a report, hiding the message or leaving it be
+#[derive(Debug, Deserialize)]
+pub struct ResolveRequest {
+    pub action: String,
+    pub resolution: Option<String>,
+}
+
+// Request DTO - stop or allow replies in a thread
+#[derive(Debug, Deserialize)]
+pub struct LockRequest {
+    pub locked: bool,
+}
+
+// The school's safeguarding rules, as set and as read back
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SafeguardingSettings {
+    pub archive_staff_student: bool,
+    pub archive_student_student: bool,
+    pub students_may_message_students: bool,
+}
+
+impl From<Rules> for SafeguardingSettings {
+    fn from(rules: Rules) -> Self {
+        SafeguardingSettings {
+            archive_staff_student: rules.archive_staff_student,
+            archive_student_student: rules.archive_student_student,
+            students_may_message_students: rules.students_may_message_students,
+        }
+    }
+}
+
+impl From<messaging_settings::Model> for SafeguardingSettings {
+    fn from(settings: messaging_settings::Model) -> Self {
+        Rules::from(&settings).into()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveQuery {
+    // Messages sent by or to this user
+    pub user_id: Option<i32>,
+    pub thread_id: Option<i32>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchivedMessageResponse {
+    pub id: i32,
+    pub thread_id: i32,
+    pub message_id: i32,
+    pub subject: String,
+    pub sender_id: Option<i32>,
+    pub sender_name: String,
+    pub sender_role: String,
+    pub participants: serde_json::Value,
+    pub body: String,
+    pub reason: String,
+    pub sent_at: String,
+    pub archived_at: String,
+}
+
+impl From<message_archive::Model> for ArchivedMessageResponse {
+    fn from(archived: message_archive::Model) -> Self {
+        ArchivedMessageResponse {
+            id: archived.id,
+            thread_id: archived.thread_id,
+            message_id: archived.message_id,
+            subject: archived.subject,
+            sender_id: archived.sender_id,
+            sender_name: archived.sender_name,
+            sender_role: archived.sender_role,
+            participants: archived.participants,
+            body: archived.body,
+            reason: archived.reason,
+            sent_at: format_time(archived.sent_at),
+            archived_at: format_time(archived.archived_at),
+        }
+    }
+}
+
+// One participant as recorded in the archive
+#[derive(Debug, Serialize)]
+pub struct ArchivedParticipant {
+    pub user_id: i32,
+    pub full_name: String,
+    pub role: String,
+}
+
+// Request DTO - record that a guardian looks after a student
+#[derive(Debug, Deserialize)]
+pub struct GuardianLinkRequest {
+    pub guardian_id: i32,
+    pub relationship: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuardianLinkResponse {
+    pub id: i32,
+    pub guardian_id: i32,
+    pub student_id: i32,
+    pub relationship: Option<String>,
+    pub created_at: String,
+}
+
+impl From<guardian_links::Model> for GuardianLinkResponse {
+    fn from(link: guardian_links::Model) -> Self {
+        GuardianLinkResponse {
+            id: link.id,
+            guardian_id: link.guardian_id,
+            student_id: link.student_id,
+            relationship: link.relationship,
+            created_at: format_time(link.created_at),
+        }
+    }
+}
@@ -1 +1,19 @@
+pub mod admissions;
+pub mod announcements;
+pub mod api_key;
+pub mod audit;
+pub mod auth;
+pub mod data_subject;
+pub mod email;
+pub mod fees;
+pub mod hostel;
+pub mod impersonation;
+pub mod library;
+pub mod lti;
+pub mod messaging;
+pub mod payments;
+pub mod profile;
+pub mod tenant;
+pub mod transport;
 pub mod user;
+pub mod xapi;
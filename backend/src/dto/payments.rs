@@ -0,0 +1,95 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::application::payments::Reconciliation;
+use crate::entities::{checkout_sessions, refunds};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Request DTO - pay an invoice online; the whole balance when no amount is given
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateCheckoutRequest {
+    pub amount: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckoutResponse {
+    pub id: i32,
+    pub invoice_id: i32,
+    pub amount: Decimal,
+    pub currency: String,
+    // Hosted payment page to send the payer to
+    pub checkout_url: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+impl From<checkout_sessions::Model> for CheckoutResponse {
+    fn from(session: checkout_sessions::Model) -> Self {
+        CheckoutResponse {
+            id: session.id,
+            invoice_id: session.invoice_id,
+            amount: session.amount,
+            currency: session.currency,
+            checkout_url: session.checkout_url,
+            status: session.status,
+            created_at: format_time(session.created_at),
+        }
+    }
+}
+
+// Request DTO - give money back on a payment; all that is left when no amount is given
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateRefundRequest {
+    pub amount: Option<Decimal>,
+    #[validate(length(min = 2, message = "Give a reason for the refund"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefundResponse {
+    pub id: i32,
+    pub payment_id: i32,
+    pub amount: Decimal,
+    pub reason: String,
+    pub status: String,
+    pub created_at: String,
+    pub settled_at: Option<String>,
+}
+
+impl From<refunds::Model> for RefundResponse {
+    fn from(refund: refunds::Model) -> Self {
+        RefundResponse {
+            id: refund.id,
+            payment_id: refund.payment_id,
+            amount: refund.amount,
+            reason: refund.reason,
+            status: refund.status,
+            created_at: format_time(refund.created_at),
+            settled_at: refund.settled_at.map(format_time),
+        }
+    }
+}
+
+// What the webhook did, for the provider's delivery log
+#[derive(Debug, Serialize)]
+pub struct WebhookResponse {
+    pub result: &'static str,
+}
+
+impl From<&Reconciliation> for WebhookResponse {
+    fn from(outcome: &Reconciliation) -> Self {
+        let result = match outcome {
+            Reconciliation::Duplicate => "duplicate",
+            Reconciliation::PaymentRecorded { .. } => "payment_recorded",
+            Reconciliation::SessionExpired => "session_expired",
+            Reconciliation::RefundSettled { .. } => "refund_settled",
+            Reconciliation::Unmatched => "unmatched",
+            Reconciliation::Ignored => "ignored",
+        };
+        WebhookResponse { result }
+    }
+}
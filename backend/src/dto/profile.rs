@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+// Request DTO - replace a user's sensitive profile fields; omitted fields are cleared
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    #[validate(length(min = 1, max = 64, message = "National ID must be 1-64 characters"))]
+    pub national_id: Option<String>,
+    #[validate(length(max = 500, message = "Address must be at most 500 characters"))]
+    pub address: Option<String>,
+    #[validate(length(max = 10000, message = "Medical notes must be at most 10000 characters"))]
+    pub medical_notes: Option<String>,
+}
+
+// Decrypted profile; all fields are empty when none has been saved yet
+#[derive(Debug, Serialize)]
+pub struct ProfileResponse {
+    pub user_id: i32,
+    pub national_id: Option<String>,
+    pub address: Option<String>,
+    pub medical_notes: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileSearchQuery {
+    pub national_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReencryptResponse {
+    pub active_key: String,
+    pub reencrypted: usize,
+}
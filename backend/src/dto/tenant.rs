@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::application::tenancy::{self, GradeBand};
+use crate::dto::user::CreateUserRequest;
+use crate::entities::tenants;
+
+// A school and the settings clients brand and grade with
+#[derive(Debug, Serialize)]
+pub struct TenantResponse {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+    pub logo_url: Option<String>,
+    pub grading_scale: Vec<GradeBand>,
+    pub timezone: String,
+    pub locale: String,
+    pub brand_color: Option<String>,
+    pub is_active: bool,
+}
+
+impl From<tenants::Model> for TenantResponse {
+    fn from(tenant: tenants::Model) -> Self {
+        TenantResponse {
+            id: tenant.id,
+            grading_scale: tenancy::grading_scale(&tenant.grading_scale),
+            slug: tenant.slug,
+            name: tenant.name,
+            logo_url: tenant.logo_url,
+            timezone: tenant.timezone,
+            locale: tenant.locale,
+            brand_color: tenant.brand_color,
+            is_active: tenant.is_active,
+        }
+    }
+}
+
+// Request DTO - a school's settings; no grading scale means the default A-F
+// scale, no locale English emails and no brand colour the default one
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTenantRequest {
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub name: String,
+    #[validate(url(message = "Invalid logo URL"))]
+    pub logo_url: Option<String>,
+    pub grading_scale: Option<Vec<GradeBand>>,
+    pub timezone: String,
+    pub locale: Option<String>,
+    pub brand_color: Option<String>,
+}
+
+// Request DTO - add a school along with its first admin
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTenantRequest {
+    pub slug: String,
+    #[validate(nested)]
+    pub settings: UpdateTenantRequest,
+    #[validate(nested)]
+    pub admin: CreateUserRequest,
+}
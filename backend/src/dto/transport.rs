@@ -0,0 +1,231 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entities::{transport_assignments, transport_drivers, transport_routes, transport_stops, transport_vehicles};
+
+fn format_time(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn active() -> bool {
+    true
+}
+
+// Request DTO - add or update a vehicle
+#[derive(Debug, Deserialize, Validate)]
+pub struct VehicleRequest {
+    #[validate(length(min = 1, message = "Registration is required"))]
+    pub registration: String,
+    pub description: Option<String>,
+    #[validate(range(min = 1, message = "Capacity must be at least 1"))]
+    pub capacity: i32,
+    #[serde(default = "active")]
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VehicleResponse {
+    pub id: i32,
+    pub registration: String,
+    pub description: Option<String>,
+    pub capacity: i32,
+    pub is_active: bool,
+}
+
+impl From<transport_vehicles::Model> for VehicleResponse {
+    fn from(vehicle: transport_vehicles::Model) -> Self {
+        VehicleResponse {
+            id: vehicle.id,
+            registration: vehicle.registration,
+            description: vehicle.description,
+            capacity: vehicle.capacity,
+            is_active: vehicle.is_active,
+        }
+    }
+}
+
+// Request DTO - add or update a driver
+#[derive(Debug, Deserialize, Validate)]
+pub struct DriverRequest {
+    #[validate(length(min = 2, message = "Name must be at least 2 characters"))]
+    pub full_name: String,
+    pub phone: Option<String>,
+    #[validate(length(min = 1, message = "License number is required"))]
+    pub license_number: String,
+    pub license_expires_on: Option<NaiveDate>,
+    #[serde(default = "active")]
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriverResponse {
+    pub id: i32,
+    pub full_name: String,
+    pub phone: Option<String>,
+    pub license_number: String,
+    pub license_expires_on: Option<NaiveDate>,
+    pub is_active: bool,
+}
+
+impl From<transport_drivers::Model> for DriverResponse {
+    fn from(driver: transport_drivers::Model) -> Self {
+        DriverResponse {
+            id: driver.id,
+            full_name: driver.full_name,
+            phone: driver.phone,
+            license_number: driver.license_number,
+            license_expires_on: driver.license_expires_on,
+            is_active: driver.is_active,
+        }
+    }
+}
+
+// Request DTO - add or update a route and who runs it
+#[derive(Debug, Deserialize, Validate)]
+pub struct RouteRequest {
+    #[validate(length(min = 1, message = "Name is required"))]
+    pub name: String,
+    pub vehicle_id: Option<i32>,
+    pub driver_id: Option<i32>,
+    #[serde(default = "active")]
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteResponse {
+    pub id: i32,
+    pub name: String,
+    pub vehicle_id: Option<i32>,
+    pub driver_id: Option<i32>,
+    pub is_active: bool,
+}
+
+impl From<transport_routes::Model> for RouteResponse {
+    fn from(route: transport_routes::Model) -> Self {
+        RouteResponse {
+            id: route.id,
+            name: route.name,
+            vehicle_id: route.vehicle_id,
+            driver_id: route.driver_id,
+            is_active: route.is_active,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteDetail {
+    pub route: RouteResponse,
+    pub vehicle: Option<VehicleResponse>,
+    pub driver: Option<DriverResponse>,
+    pub stops: Vec<StopResponse>,
+    pub seats_taken: i32,
+}
+
+// Request DTO - one stop of a route; stops with an id are kept, others added
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct StopRequest {
+    pub id: Option<i32>,
+    #[validate(length(min = 1, message = "Stop name is required"))]
+    pub name: String,
+    pub time: NaiveTime,
+}
+
+// Request DTO - a route's full list of stops, in calling order. Stops left
+// out are removed.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReplaceStopsRequest {
+    #[validate(nested)]
+    pub stops: Vec<StopRequest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StopResponse {
+    pub id: i32,
+    pub position: i32,
+    pub name: String,
+    pub time: NaiveTime,
+}
+
+impl From<transport_stops::Model> for StopResponse {
+    fn from(stop: transport_stops::Model) -> Self {
+        StopResponse {
+            id: stop.id,
+            position: stop.position,
+            name: stop.name,
+            time: stop.scheduled_time,
+        }
+    }
+}
+
+// Request DTO - seat a student on a route, boarding at one of its stops.
+// A student already on the route is moved to the new stop.
+#[derive(Debug, Deserialize)]
+pub struct AssignStudentRequest {
+    pub student_id: i32,
+    pub stop_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignmentQuery {
+    pub student_id: Option<i32>,
+    pub route_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssignmentResponse {
+    pub id: i32,
+    pub route_id: i32,
+    pub stop_id: i32,
+    pub student_id: i32,
+    pub created_at: String,
+}
+
+impl From<transport_assignments::Model> for AssignmentResponse {
+    fn from(assignment: transport_assignments::Model) -> Self {
+        AssignmentResponse {
+            id: assignment.id,
+            route_id: assignment.route_id,
+            stop_id: assignment.stop_id,
+            student_id: assignment.student_id,
+            created_at: format_time(assignment.created_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestStudent {
+    pub id: i32,
+    pub full_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestStop {
+    pub position: i32,
+    pub name: String,
+    pub time: NaiveTime,
+    pub students: Vec<ManifestStudent>,
+}
+
+// What the driver carries: every stop in order with who boards there
+#[derive(Debug, Serialize)]
+pub struct ManifestResponse {
+    pub school: String,
+    pub route: String,
+    pub vehicle: Option<String>,
+    pub capacity: Option<i32>,
+    pub driver: Option<String>,
+    pub driver_phone: Option<String>,
+    pub seats_taken: usize,
+    pub stops: Vec<ManifestStop>,
+}
+
+// One line of a manifest exported as CSV or XLSX
+#[derive(Debug, Serialize)]
+pub struct ManifestRow {
+    pub stop_position: i32,
+    pub stop: String,
+    pub time: NaiveTime,
+    pub student_id: i32,
+    pub student_name: String,
+}
@@ -1,10 +1,21 @@
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::application::auth::ROLES;
+use crate::entities::users;
+
+pub fn validate_role(role: &str) -> Result<(), ValidationError> {
+    if !ROLES.contains(&role) {
+        return Err(ValidationError::new("role").with_message(format!("Role must be one of: {}", ROLES.join(", ")).into()));
+    }
+    Ok(())
+}
 
 // Response DTO - what we send to clients
 #[derive(Debug, Serialize, Clone)]
 pub struct UserResponse {
     pub id: i32,
+    pub tenant_id: i32,
     pub email: String,
     pub full_name: String,
     pub role: String,
@@ -12,6 +23,20 @@ pub struct UserResponse {
     pub created_at: String,
 }
 
+impl From<users::Model> for UserResponse {
+    fn from(user: users::Model) -> Self {
+        UserResponse {
+            id: user.id,
+            tenant_id: user.tenant_id,
+            email: user.email,
+            full_name: user.full_name,
+            role: user.role,
+            is_active: user.is_active,
+            created_at: user.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
 // Request DTO - what clients send to create a user
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateUserRequest {
@@ -24,7 +49,7 @@ pub struct CreateUserRequest {
     #[validate(length(min = 2, message = "Full name must be at least 2 characters"))]
     pub full_name: String,
     
-    #[validate(length(min = 1, message = "Role is required"))]
+    #[validate(custom(function = "validate_role"))]
     pub role: String,
 }
 
@@ -33,4 +58,13 @@ pub struct CreateUserRequest {
 pub struct UsersListResponse {
     pub users: Vec<UserResponse>,
     pub total: usize,
-}
\ No newline at end of file
+}
+
+// A user in the trash and when it will be purged
+#[derive(Debug, Serialize)]
+pub struct TrashedUserResponse {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    pub deleted_at: String,
+    pub purge_after: String,
+}
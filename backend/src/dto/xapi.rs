@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Query parameters of GET /xapi/statements
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementQuery {
+    pub statement_id: Option<String>,
+    pub voided_statement_id: Option<String>,
+    // JSON-encoded Agent or Group
+    pub agent: Option<String>,
+    pub verb: Option<String>,
+    pub activity: Option<String>,
+    pub registration: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<u64>,
+    pub ascending: Option<bool>,
+    pub attachments: Option<bool>,
+    // Paging position used in the `more` link
+    pub offset: Option<u64>,
+}
+
+// Query parameters of PUT /xapi/statements
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutStatementQuery {
+    pub statement_id: String,
+}
+
+// StatementResult object returned by statement queries
+#[derive(Debug, Serialize)]
+pub struct StatementResult {
+    pub statements: Vec<Value>,
+    pub more: String,
+}
+
+// Response of GET /xapi/about
+#[derive(Debug, Serialize)]
+pub struct AboutResponse {
+    pub version: Vec<String>,
+}
@@ -1,7 +1,125 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub mod admission_applications;
+pub mod admission_documents;
+pub mod admission_letters;
+pub mod admission_reviews;
+pub mod admission_stages;
+pub mod announcement_attachments;
+pub mod announcement_reads;
+pub mod announcements;
+pub mod api_keys;
+pub mod audit_log;
+pub mod checkout_sessions;
+pub mod class_memberships;
+pub mod email_outbox;
+pub mod erasure_request_events;
+pub mod erasure_requests;
+pub mod fee_discounts;
+pub mod fee_items;
+pub mod fee_schedules;
+pub mod guardian_links;
+pub mod hostel_allocations;
+pub mod hostel_beds;
+pub mod hostel_rooms;
+pub mod hostel_stay_logs;
+pub mod impersonation_actions;
+pub mod impersonation_sessions;
+pub mod invoice_lines;
+pub mod invoices;
+pub mod library_copies;
+pub mod library_holds;
+pub mod library_loans;
+pub mod library_titles;
+pub mod lti_launches;
+pub mod lti_login_states;
+pub mod lti_platforms;
+pub mod lti_user_links;
+pub mod message_archive;
+pub mod message_attachments;
+pub mod message_participants;
+pub mod message_reports;
+pub mod message_threads;
+pub mod messages;
+pub mod messaging_settings;
+pub mod oidc_login_states;
+pub mod password_history;
+pub mod password_reset_tokens;
+pub mod payment_webhook_events;
+pub mod payments;
+pub mod refunds;
+pub mod tenants;
+pub mod transport_assignments;
+pub mod transport_drivers;
+pub mod transport_routes;
+pub mod transport_stops;
+pub mod transport_vehicles;
+pub mod user_profiles;
+pub mod user_recovery_codes;
+pub mod user_totp;
 pub mod users;
+pub mod xapi_attachments;
+pub mod xapi_statements;
 
 pub mod prelude {
+    pub use super::admission_applications::Entity as AdmissionApplications;
+    pub use super::admission_documents::Entity as AdmissionDocuments;
+    pub use super::admission_letters::Entity as AdmissionLetters;
+    pub use super::admission_reviews::Entity as AdmissionReviews;
+    pub use super::admission_stages::Entity as AdmissionStages;
+    pub use super::announcement_attachments::Entity as AnnouncementAttachments;
+    pub use super::announcement_reads::Entity as AnnouncementReads;
+    pub use super::announcements::Entity as Announcements;
+    pub use super::api_keys::Entity as ApiKeys;
+    pub use super::audit_log::Entity as AuditLog;
+    pub use super::checkout_sessions::Entity as CheckoutSessions;
+    pub use super::class_memberships::Entity as ClassMemberships;
+    pub use super::email_outbox::Entity as EmailOutbox;
+    pub use super::erasure_request_events::Entity as ErasureRequestEvents;
+    pub use super::erasure_requests::Entity as ErasureRequests;
+    pub use super::fee_discounts::Entity as FeeDiscounts;
+    pub use super::fee_items::Entity as FeeItems;
+    pub use super::fee_schedules::Entity as FeeSchedules;
+    pub use super::guardian_links::Entity as GuardianLinks;
+    pub use super::hostel_allocations::Entity as HostelAllocations;
+    pub use super::hostel_beds::Entity as HostelBeds;
+    pub use super::hostel_rooms::Entity as HostelRooms;
+    pub use super::hostel_stay_logs::Entity as HostelStayLogs;
+    pub use super::impersonation_actions::Entity as ImpersonationActions;
+    pub use super::impersonation_sessions::Entity as ImpersonationSessions;
+    pub use super::invoice_lines::Entity as InvoiceLines;
+    pub use super::invoices::Entity as Invoices;
+    pub use super::library_copies::Entity as LibraryCopies;
+    pub use super::library_holds::Entity as LibraryHolds;
+    pub use super::library_loans::Entity as LibraryLoans;
+    pub use super::library_titles::Entity as LibraryTitles;
+    pub use super::lti_launches::Entity as LtiLaunches;
+    pub use super::lti_login_states::Entity as LtiLoginStates;
+    pub use super::lti_platforms::Entity as LtiPlatforms;
+    pub use super::lti_user_links::Entity as LtiUserLinks;
+    pub use super::message_archive::Entity as MessageArchive;
+    pub use super::message_attachments::Entity as MessageAttachments;
+    pub use super::message_participants::Entity as MessageParticipants;
+    pub use super::message_reports::Entity as MessageReports;
+    pub use super::message_threads::Entity as MessageThreads;
+    pub use super::messages::Entity as Messages;
+    pub use super::messaging_settings::Entity as MessagingSettings;
+    pub use super::oidc_login_states::Entity as OidcLoginStates;
+    pub use super::password_history::Entity as PasswordHistory;
+    pub use super::password_reset_tokens::Entity as PasswordResetTokens;
+    pub use super::payment_webhook_events::Entity as PaymentWebhookEvents;
+    pub use super::payments::Entity as Payments;
+    pub use super::refunds::Entity as Refunds;
+    pub use super::tenants::Entity as Tenants;
+    pub use super::transport_assignments::Entity as TransportAssignments;
+    pub use super::transport_drivers::Entity as TransportDrivers;
+    pub use super::transport_routes::Entity as TransportRoutes;
+    pub use super::transport_stops::Entity as TransportStops;
+    pub use super::transport_vehicles::Entity as TransportVehicles;
+    pub use super::user_profiles::Entity as UserProfiles;
+    pub use super::user_recovery_codes::Entity as UserRecoveryCodes;
+    pub use super::user_totp::Entity as UserTotp;
     pub use super::users::Entity as Users;
+    pub use super::xapi_attachments::Entity as XapiAttachments;
+    pub use super::xapi_statements::Entity as XapiStatements;
 }
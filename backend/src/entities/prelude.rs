@@ -1,2 +1,61 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
+pub use super::admission_applications::Entity as AdmissionApplications;
+pub use super::admission_documents::Entity as AdmissionDocuments;
+pub use super::admission_letters::Entity as AdmissionLetters;
+pub use super::admission_reviews::Entity as AdmissionReviews;
+pub use super::admission_stages::Entity as AdmissionStages;
+pub use super::announcement_attachments::Entity as AnnouncementAttachments;
+pub use super::announcement_reads::Entity as AnnouncementReads;
+pub use super::announcements::Entity as Announcements;
+pub use super::api_keys::Entity as ApiKeys;
+pub use super::audit_log::Entity as AuditLog;
+pub use super::checkout_sessions::Entity as CheckoutSessions;
+pub use super::class_memberships::Entity as ClassMemberships;
+pub use super::email_outbox::Entity as EmailOutbox;
+pub use super::erasure_request_events::Entity as ErasureRequestEvents;
+pub use super::erasure_requests::Entity as ErasureRequests;
+pub use super::fee_discounts::Entity as FeeDiscounts;
+pub use super::fee_items::Entity as FeeItems;
+pub use super::fee_schedules::Entity as FeeSchedules;
+pub use super::guardian_links::Entity as GuardianLinks;
+pub use super::hostel_allocations::Entity as HostelAllocations;
+pub use super::hostel_beds::Entity as HostelBeds;
+pub use super::hostel_rooms::Entity as HostelRooms;
+pub use super::hostel_stay_logs::Entity as HostelStayLogs;
+pub use super::impersonation_actions::Entity as ImpersonationActions;
+pub use super::impersonation_sessions::Entity as ImpersonationSessions;
+pub use super::invoice_lines::Entity as InvoiceLines;
+pub use super::invoices::Entity as Invoices;
+pub use super::library_copies::Entity as LibraryCopies;
+pub use super::library_holds::Entity as LibraryHolds;
+pub use super::library_loans::Entity as LibraryLoans;
+pub use super::library_titles::Entity as LibraryTitles;
+pub use super::lti_launches::Entity as LtiLaunches;
+pub use super::lti_login_states::Entity as LtiLoginStates;
+pub use super::lti_platforms::Entity as LtiPlatforms;
+pub use super::lti_user_links::Entity as LtiUserLinks;
+pub use super::message_archive::Entity as MessageArchive;
+pub use super::message_attachments::Entity as MessageAttachments;
+pub use super::message_participants::Entity as MessageParticipants;
+pub use super::message_reports::Entity as MessageReports;
+pub use super::message_threads::Entity as MessageThreads;
+pub use super::messages::Entity as Messages;
+pub use super::messaging_settings::Entity as MessagingSettings;
+pub use super::oidc_login_states::Entity as OidcLoginStates;
+pub use super::password_history::Entity as PasswordHistory;
+pub use super::password_reset_tokens::Entity as PasswordResetTokens;
+pub use super::payment_webhook_events::Entity as PaymentWebhookEvents;
+pub use super::payments::Entity as Payments;
+pub use super::refunds::Entity as Refunds;
+pub use super::tenants::Entity as Tenants;
+pub use super::transport_assignments::Entity as TransportAssignments;
+pub use super::transport_drivers::Entity as TransportDrivers;
+pub use super::transport_routes::Entity as TransportRoutes;
+pub use super::transport_stops::Entity as TransportStops;
+pub use super::transport_vehicles::Entity as TransportVehicles;
+pub use super::user_profiles::Entity as UserProfiles;
+pub use super::user_recovery_codes::Entity as UserRecoveryCodes;
+pub use super::user_totp::Entity as UserTotp;
 pub use super::users::Entity as Users;
+pub use super::xapi_attachments::Entity as XapiAttachments;
+pub use super::xapi_statements::Entity as XapiStatements;
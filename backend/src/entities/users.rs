@@ -7,7 +7,6 @@ use sea_orm::entity::prelude::*;
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    #[sea_orm(unique)]
     pub email: String,
     pub password_hash: String,
     pub full_name: String,
@@ -15,9 +14,197 @@ pub struct Model {
     pub is_active: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    pub auth_source: String,
+    pub deleted_at: Option<DateTime>,
+    pub tenant_id: i32,
+    pub directory_deactivated_at: Option<DateTime>,
+    pub password_changed_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::admission_applications::Entity")]
+    AdmissionApplications,
+    #[sea_orm(has_many = "super::admission_reviews::Entity")]
+    AdmissionReviews,
+    #[sea_orm(has_many = "super::announcement_reads::Entity")]
+    AnnouncementReads,
+    #[sea_orm(has_many = "super::announcements::Entity")]
+    Announcements,
+    #[sea_orm(has_one = "super::class_memberships::Entity")]
+    ClassMemberships,
+    #[sea_orm(has_many = "super::fee_discounts::Entity")]
+    FeeDiscounts,
+    #[sea_orm(has_many = "super::hostel_allocations::Entity")]
+    HostelAllocations,
+    #[sea_orm(has_many = "super::library_holds::Entity")]
+    LibraryHolds,
+    #[sea_orm(has_many = "super::lti_launches::Entity")]
+    LtiLaunches,
+    #[sea_orm(has_many = "super::lti_user_links::Entity")]
+    LtiUserLinks,
+    #[sea_orm(has_many = "super::message_participants::Entity")]
+    MessageParticipants,
+    #[sea_orm(has_many = "super::message_reports::Entity")]
+    MessageReports,
+    #[sea_orm(has_many = "super::message_threads::Entity")]
+    MessageThreads,
+    #[sea_orm(has_many = "super::messages::Entity")]
+    Messages,
+    #[sea_orm(has_many = "super::password_history::Entity")]
+    PasswordHistory,
+    #[sea_orm(has_many = "super::password_reset_tokens::Entity")]
+    PasswordResetTokens,
+    #[sea_orm(
+        belongs_to = "super::tenants::Entity",
+        from = "Column::TenantId",
+        to = "super::tenants::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Restrict"
+    )]
+    Tenants,
+    #[sea_orm(has_many = "super::transport_assignments::Entity")]
+    TransportAssignments,
+    #[sea_orm(has_one = "super::user_profiles::Entity")]
+    UserProfiles,
+    #[sea_orm(has_many = "super::user_recovery_codes::Entity")]
+    UserRecoveryCodes,
+    #[sea_orm(has_one = "super::user_totp::Entity")]
+    UserTotp,
+    #[sea_orm(has_many = "super::xapi_statements::Entity")]
+    XapiStatements,
+}
+
+impl Related<super::admission_applications::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AdmissionApplications.def()
+    }
+}
+
+impl Related<super::admission_reviews::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AdmissionReviews.def()
+    }
+}
+
+impl Related<super::announcement_reads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AnnouncementReads.def()
+    }
+}
+
+impl Related<super::announcements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Announcements.def()
+    }
+}
+
+impl Related<super::class_memberships::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ClassMemberships.def()
+    }
+}
+
+impl Related<super::fee_discounts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FeeDiscounts.def()
+    }
+}
+
+impl Related<super::hostel_allocations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HostelAllocations.def()
+    }
+}
+
+impl Related<super::library_holds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LibraryHolds.def()
+    }
+}
+
+impl Related<super::lti_launches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LtiLaunches.def()
+    }
+}
+
+impl Related<super::lti_user_links::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LtiUserLinks.def()
+    }
+}
+
+impl Related<super::message_participants::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageParticipants.def()
+    }
+}
+
+impl Related<super::message_reports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageReports.def()
+    }
+}
+
+impl Related<super::message_threads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageThreads.def()
+    }
+}
+
+impl Related<super::messages::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Messages.def()
+    }
+}
+
+impl Related<super::password_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PasswordHistory.def()
+    }
+}
+
+impl Related<super::password_reset_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PasswordResetTokens.def()
+    }
+}
+
+impl Related<super::tenants::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Tenants.def()
+    }
+}
+
+impl Related<super::transport_assignments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TransportAssignments.def()
+    }
+}
+
+impl Related<super::user_profiles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserProfiles.def()
+    }
+}
+
+impl Related<super::user_recovery_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRecoveryCodes.def()
+    }
+}
+
+impl Related<super::user_totp::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserTotp.def()
+    }
+}
+
+impl Related<super::xapi_statements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::XapiStatements.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
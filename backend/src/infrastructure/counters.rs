@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+// How long startup waits for Redis before falling back to memory
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// Expired in-memory entries are swept once the map grows past this
+const MEMORY_SWEEP_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CounterError {
+    #[error("redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+}
+
+pub struct Entry {
+    value: u64,
+    expires_at: Instant,
+}
+
+// Expiring counters shared by the rate limiter and the login guard. Redis keeps
+// them consistent across instances; without Redis each process counts alone.
+#[derive(Clone)]
+pub enum CounterStore {
+    Redis(ConnectionManager),
+    Memory(Arc<Mutex<HashMap<String, Entry>>>),
+}
+
+impl CounterStore {
+    pub fn memory() -> Self {
+        Self::Memory(Arc::default())
+    }
+
+    // Connect to Redis, or fall back to in-process counters if it is unreachable
+    pub async fn connect(redis_url: &str) -> Self {
+        let connection = async {
+            let client = redis::Client::open(redis_url)?;
+            client.get_connection_manager().await
+        };
+        match tokio::time::timeout(CONNECT_TIMEOUT, connection).await {
+            Ok(Ok(manager)) => {
+                tracing::info!("🧮 Counters stored in Redis");
+                Self::Redis(manager)
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("Redis unavailable ({}), counting in memory", e);
+                Self::memory()
+            }
+            Err(_) => {
+                tracing::warn!("Redis connection timed out, counting in memory");
+                Self::memory()
+            }
+        }
+    }
+
+    // Add one to a counter and return the new value. The expiry is set when the
+    // counter is created, so counts cover a fixed window. In Redis the counter
+    // is created with its expiry and incremented in one transaction, so a
+    // failure between the two cannot leave a counter that never expires.
+    pub async fn increment(&self, key: &str, window: Duration) -> Result<u64, CounterError> {
+        match self {
+            Self::Redis(manager) => {
+                let mut conn = manager.clone();
+                let (value,): (u64,) = redis::pipe()
+                    .atomic()
+                    .cmd("SET")
+                    .arg(key)
+                    .arg(0)
+                    .arg("NX")
+                    .arg("EX")
+                    .arg(window.as_secs().max(1))
+                    .ignore()
+                    .incr(key, 1)
+                    .query_async(&mut conn)
+                    .await?;
+                Ok(value)
+            }
+            Self::Memory(entries) => {
+                let mut entries = lock(entries);
+                let now = Instant::now();
+                sweep(&mut entries, now);
+                let entry = entries
+                    .entry(key.to_string())
+                    .and_modify(|entry| {
+                        if entry.expires_at <= now {
+                            *entry = Entry { value: 0, expires_at: now + window };
+                        }
+                    })
+                    .or_insert(Entry { value: 0, expires_at: now + window });
+                entry.value += 1;
+                Ok(entry.value)
+            }
+        }
+    }
+
+    // Set a flag that disappears after `ttl`
+    pub async fn set(&self, key: &str, ttl: Duration) -> Result<(), CounterError> {
+        match self {
+            Self::Redis(manager) => {
+                let mut conn = manager.clone();
+                let _: () = conn.set_ex(key, 1, ttl.as_secs().max(1)).await?;
+                Ok(())
+            }
+            Self::Memory(entries) => {
+                let mut entries = lock(entries);
+                entries.insert(
+                    key.to_string(),
+                    Entry { value: 1, expires_at: Instant::now() + ttl },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    // Time until a key expires, or None if it does not exist
+    pub async fn ttl(&self, key: &str) -> Result<Option<Duration>, CounterError> {
+        match self {
+            Self::Redis(manager) => {
+                let mut conn = manager.clone();
+                let seconds: i64 = conn.ttl(key).await?;
+                Ok((seconds > 0).then(|| Duration::from_secs(seconds as u64)))
+            }
+            Self::Memory(entries) => {
+                let entries = lock(entries);
+                let now = Instant::now();
+                Ok(entries
+                    .get(key)
+                    .filter(|entry| entry.expires_at > now)
+                    .map(|entry| entry.expires_at - now))
+            }
+        }
+    }
+
+    pub async fn delete(&self, keys: &[String]) -> Result<(), CounterError> {
+        match self {
+            Self::Redis(manager) => {
+                let mut conn = manager.clone();
+                let _: () = conn.del(keys).await?;
+                Ok(())
+            }
+            Self::Memory(entries) => {
+                let mut entries = lock(entries);
+                for key in keys {
+                    entries.remove(key);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// A poisoned lock only means another request panicked mid-update; counters are still usable
+fn lock(entries: &Mutex<HashMap<String, Entry>>) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+    entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn sweep(entries: &mut HashMap<String, Entry>, now: Instant) {
+    if entries.len() > MEMORY_SWEEP_THRESHOLD {
+        entries.retain(|_, entry| entry.expires_at > now);
+    }
+}
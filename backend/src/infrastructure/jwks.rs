@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::{jwk::JwkSet, DecodingKey};
+use tokio::sync::RwLock;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JwksError {
+    #[error("failed to fetch JWKS from {0}: {1}")]
+    Fetch(String, String),
+    #[error("no key with kid {0:?} in JWKS")]
+    UnknownKey(Option<String>),
+    #[error("unusable JWK: {0}")]
+    InvalidKey(#[from] jsonwebtoken::errors::Error),
+}
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+// Caches remote JSON Web Key Sets (LMS platforms, identity providers) by URL.
+// A kid that is not in the cached set forces one refetch so key rotation works.
+#[derive(Clone)]
+pub struct JwksCache {
+    ttl: Duration,
+    sets: Arc<RwLock<HashMap<String, CachedJwks>>>,
+}
+
+impl Default for JwksCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(3600))
+    }
+}
+
+impl JwksCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            sets: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    // Find the decoding key for `kid` in the key set published at `url`
+    pub async fn decoding_key(
+        &self,
+        http: &reqwest::Client,
+        url: &str,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, JwksError> {
+        {
+            let sets = self.sets.read().await;
+            if let Some(cached) = sets.get(url)
+                && cached.fetched_at.elapsed() < self.ttl
+                && let Some(key) = Self::select(&cached.keys, kid)
+            {
+                return key;
+            }
+        }
+
+        let keys = Self::fetch(http, url).await?;
+        let key = Self::select(&keys, kid);
+        self.sets.write().await.insert(
+            url.to_string(),
+            CachedJwks {
+                keys,
+                fetched_at: Instant::now(),
+            },
+        );
+
+        key.unwrap_or_else(|| Err(JwksError::UnknownKey(kid.map(str::to_string))))
+    }
+
+    fn select(keys: &JwkSet, kid: Option<&str>) -> Option<Result<DecodingKey, JwksError>> {
+        let jwk = match kid {
+            Some(kid) => keys.find(kid),
+            // Without a kid we can only pick a key when there is exactly one
+            None if keys.keys.len() == 1 => keys.keys.first(),
+            None => None,
+        }?;
+        Some(DecodingKey::from_jwk(jwk).map_err(JwksError::from))
+    }
+
+    async fn fetch(http: &reqwest::Client, url: &str) -> Result<JwkSet, JwksError> {
+        let fetch_err = |e: reqwest::Error| JwksError::Fetch(url.to_string(), e.to_string());
+
+        tracing::debug!("Fetching JWKS from {}", url);
+        http.get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(fetch_err)?
+            .json::<JwkSet>()
+            .await
+            .map_err(fetch_err)
+    }
+}
@@ -1 +1,7 @@
 // Database and external services will go here
+pub mod counters;
+pub mod jwks;
+pub mod mailer;
+pub mod notifications;
+pub mod payments;
+pub mod rate_limit;
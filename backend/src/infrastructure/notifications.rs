@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+// Every instance publishes to and listens on this one channel
+const CHANNEL: &str = "rsedu:notifications";
+// How long startup waits for Redis before falling back to memory
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// Pause before resubscribing after the Redis subscription drops
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+// Notifications held for a slow connection before it starts missing some
+const BUFFER: usize = 64;
+
+// Something that happened which one user should hear about straight away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub user_id: i32,
+    pub kind: String,
+    // The announcement, thread... the notification is about
+    pub resource_id: i32,
+    pub title: String,
+    pub body: String,
+    pub created_at: String,
+}
+
+// Hands notifications to the connections of this process, by user
+#[derive(Clone, Default)]
+pub struct Broker {
+    senders: Arc<Mutex<HashMap<i32, broadcast::Sender<Notification>>>>,
+}
+
+impl Broker {
+    pub fn subscribe(&self, user_id: i32) -> Subscription {
+        let receiver = lock(&self.senders)
+            .entry(user_id)
+            .or_insert_with(|| broadcast::channel(BUFFER).0)
+            .subscribe();
+        Subscription {
+            user_id,
+            receiver,
+            broker: self.clone(),
+        }
+    }
+
+    // Pass a notification to the user's open connections, if they have any here
+    pub fn deliver(&self, notification: Notification) {
+        if let Some(sender) = lock(&self.senders).get(&notification.user_id) {
+            let _ = sender.send(notification);
+        }
+    }
+
+    // Forget a user once their last connection is gone
+    fn release(&self, user_id: i32) {
+        let mut senders = lock(&self.senders);
+        if senders.get(&user_id).is_some_and(|sender| sender.receiver_count() == 0) {
+            senders.remove(&user_id);
+        }
+    }
+
+    fn connected_users(&self) -> usize {
+        lock(&self.senders).len()
+    }
+}
+
+// One open connection's feed of notifications
+pub struct Subscription {
+    user_id: i32,
+    receiver: broadcast::Receiver<Notification>,
+    broker: Broker,
+}
+
+impl Subscription {
+    // The next notification; a connection too slow to keep up skips what it missed
+    pub async fn next(&mut self) -> Option<Notification> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(notification) => return Some(notification),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Notification stream for user {} skipped {} notifications", self.user_id, missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Swap in a receiver of a throwaway channel so ours is gone before the
+        // broker counts what is left
+        let (_, detached) = broadcast::channel(1);
+        drop(std::mem::replace(&mut self.receiver, detached));
+        self.broker.release(self.user_id);
+    }
+}
+
+// Fans notifications out to connected users. With Redis every instance hears
+// every notification and passes on those for users connected to it; without
+// Redis only this process's connections are reached.
+#[derive(Clone)]
+pub enum NotificationHub {
+    Redis { publisher: ConnectionManager, broker: Broker },
+    Memory(Broker),
+}
+
+impl NotificationHub {
+    pub fn memory() -> Self {
+        Self::Memory(Broker::default())
+    }
+
+    // Connect to Redis and start listening, or fall back to in-process delivery
+    // if it is unreachable
+    pub async fn connect(redis_url: &str) -> Self {
+        let connection = async {
+            let client = redis::Client::open(redis_url)?;
+            let publisher = client.get_connection_manager().await?;
+            Ok::<_, redis::RedisError>((client, publisher))
+        };
+        match tokio::time::timeout(CONNECT_TIMEOUT, connection).await {
+            Ok(Ok((client, publisher))) => {
+                tracing::info!("🔔 Notifications fanned out through Redis");
+                let broker = Broker::default();
+                tokio::spawn(listen(client, broker.clone()));
+                Self::Redis { publisher, broker }
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("Redis unavailable ({}), notifying this instance only", e);
+                Self::memory()
+            }
+            Err(_) => {
+                tracing::warn!("Redis connection timed out, notifying this instance only");
+                Self::memory()
+            }
+        }
+    }
+
+    pub fn subscribe(&self, user_id: i32) -> Subscription {
+        self.broker().subscribe(user_id)
+    }
+
+    // Send notifications on their way. Delivery is best effort: a failure is
+    // logged, and whatever it was about is still there to be fetched.
+    pub async fn publish(&self, notifications: Vec<Notification>) {
+        match self {
+            Self::Redis { publisher, .. } => {
+                let mut pipe = redis::pipe();
+                for notification in &notifications {
+                    match serde_json::to_string(notification) {
+                        Ok(payload) => {
+                            pipe.publish(CHANNEL, payload).ignore();
+                        }
+                        Err(e) => tracing::error!("Failed to encode notification: {}", e),
+                    }
+                }
+                let mut conn = publisher.clone();
+                if let Err(e) = pipe.query_async::<()>(&mut conn).await {
+                    tracing::error!("Failed to publish {} notifications: {}", notifications.len(), e);
+                }
+            }
+            Self::Memory(broker) => {
+                for notification in notifications {
+                    broker.deliver(notification);
+                }
+            }
+        }
+    }
+
+    // Users with a stream open on this instance
+    pub fn connected_users(&self) -> usize {
+        self.broker().connected_users()
+    }
+
+    fn broker(&self) -> &Broker {
+        match self {
+            Self::Redis { broker, .. } | Self::Memory(broker) => broker,
+        }
+    }
+}
+
+// Pass everything published on the channel, by any instance, to this
+// instance's broker; resubscribe whenever the subscription drops
+async fn listen(client: redis::Client, broker: Broker) {
+    loop {
+        match client.get_async_pubsub().await {
+            Ok(mut pubsub) => match pubsub.subscribe(CHANNEL).await {
+                Ok(()) => {
+                    let mut messages = pubsub.into_on_message();
+                    while let Some(message) = messages.next().await {
+                        match serde_json::from_slice::<Notification>(message.get_payload_bytes()) {
+                            Ok(notification) => broker.deliver(notification),
+                            Err(e) => tracing::warn!("Ignoring malformed notification: {}", e),
+                        }
+                    }
+                    tracing::warn!("Notification subscription closed, resubscribing");
+                }
+                Err(e) => tracing::warn!("Failed to subscribe to notifications: {}", e),
+            },
+            Err(e) => tracing::warn!("Failed to connect for notifications: {}", e),
+        }
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+// A poisoned lock only means a connection panicked mid-update; the map is still usable
+fn lock(
+    senders: &Mutex<HashMap<i32, broadcast::Sender<Notification>>>,
+) -> std::sync::MutexGuard<'_, HashMap<i32, broadcast::Sender<Notification>>> {
+    senders.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(user_id: i32, title: &str) -> Notification {
+        Notification {
+            user_id,
+            kind: "message".to_string(),
+            resource_id: 1,
+            title: title.to_string(),
+            body: String::new(),
+            created_at: "2026-10-20 08:00:00".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn notifications_reach_only_their_user() {
+        let hub = NotificationHub::memory();
+        let mut first = hub.subscribe(1);
+        let mut second_tab = hub.subscribe(1);
+        let mut other = hub.subscribe(2);
+
+        hub.publish(vec![notification(1, "For one"), notification(3, "Nobody here")]).await;
+        hub.publish(vec![notification(2, "For two")]).await;
+
+        assert_eq!(first.next().await.unwrap().title, "For one");
+        assert_eq!(second_tab.next().await.unwrap().title, "For one");
+        assert_eq!(other.next().await.unwrap().title, "For two");
+        assert!(first.receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn users_are_forgotten_when_their_last_stream_closes() {
+        let hub = NotificationHub::memory();
+        let first = hub.subscribe(1);
+        let second = hub.subscribe(1);
+        assert_eq!(hub.connected_users(), 1);
+
+        drop(first);
+        assert_eq!(hub.connected_users(), 1);
+        drop(second);
+        assert_eq!(hub.connected_users(), 0);
+    }
+}
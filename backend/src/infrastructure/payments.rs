@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::config::Config;
+
+// How old a signed webhook may be before it is refused as a replay
+pub const SIGNATURE_TOLERANCE_SECONDS: i64 = 300;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentError {
+    #[error("webhook signature is missing, stale or does not match")]
+    InvalidSignature,
+    #[error("malformed webhook payload: {0}")]
+    MalformedPayload(String),
+    #[error("payment provider error: {0}")]
+    Provider(String),
+}
+
+// What to charge in one hosted checkout
+#[derive(Debug, Clone)]
+pub struct CheckoutRequest {
+    // Our reference, shown in the provider's dashboard
+    pub reference: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub description: String,
+    // Where the payer is sent back to afterwards
+    pub return_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckoutSession {
+    pub id: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefundStatus {
+    Succeeded,
+    // Settles later through a refund webhook
+    Pending,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderRefund {
+    pub id: String,
+    pub status: RefundStatus,
+}
+
+// What a webhook tells us, in provider-neutral terms
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookEvent {
+    CheckoutCompleted {
+        session_id: String,
+        payment_id: String,
+        amount: Decimal,
+    },
+    CheckoutExpired {
+        session_id: String,
+    },
+    RefundSucceeded {
+        refund_id: String,
+    },
+    RefundFailed {
+        refund_id: String,
+    },
+    // Event types we do not act on
+    Other(String),
+}
+
+impl WebhookEvent {
+    pub fn kind(&self) -> &str {
+        match self {
+            WebhookEvent::CheckoutCompleted { .. } => "checkout.completed",
+            WebhookEvent::CheckoutExpired { .. } => "checkout.expired",
+            WebhookEvent::RefundSucceeded { .. } => "refund.succeeded",
+            WebhookEvent::RefundFailed { .. } => "refund.failed",
+            WebhookEvent::Other(kind) => kind,
+        }
+    }
+}
+
+// A webhook whose signature checked out. `id` is the provider's event id,
+// the same on every redelivery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedEvent {
+    pub id: String,
+    pub event: WebhookEvent,
+}
+
+// A card/wallet payment service with hosted checkout and webhooks. Shared as
+// `Arc<dyn PaymentProvider>` in the app state so deployments pick one and
+// tests use `FakeProvider`.
+pub trait PaymentProvider: Send + Sync {
+    // Stored with sessions and events, e.g. "fake"
+    fn name(&self) -> &'static str;
+
+    // Header the provider puts its webhook signature in
+    fn signature_header(&self) -> &'static str;
+
+    fn create_checkout<'a>(
+        &'a self,
+        request: &'a CheckoutRequest,
+    ) -> BoxFuture<'a, Result<CheckoutSession, PaymentError>>;
+
+    fn refund<'a>(
+        &'a self,
+        payment_id: &'a str,
+        amount: Decimal,
+    ) -> BoxFuture<'a, Result<ProviderRefund, PaymentError>>;
+
+    // Check the signature over the raw body before trusting anything in it
+    fn verify_webhook(&self, signature: &str, payload: &[u8]) -> Result<VerifiedEvent, PaymentError>;
+}
+
+pub type SharedPaymentProvider = Arc<dyn PaymentProvider>;
+
+// The configured provider with the settings every checkout uses
+#[derive(Clone)]
+pub struct PaymentGateway {
+    pub provider: SharedPaymentProvider,
+    pub currency: String,
+    pub return_url: String,
+}
+
+// The provider named by PAYMENT_PROVIDER; online payments are off without one.
+// The fake provider approves anything, so only development and test may use it.
+pub fn from_config(config: &Config) -> Result<Option<PaymentGateway>, String> {
+    let Some(name) = config.payment_provider.as_deref() else {
+        return Ok(None);
+    };
+    if name == "fake" && !fake_allowed(&config.environment) {
+        return Err(format!("The fake payment provider cannot be used in {}", config.environment));
+    }
+    if config.payment_webhook_secret.is_empty() {
+        return Err("PAYMENT_WEBHOOK_SECRET must be set with PAYMENT_PROVIDER".to_string());
+    }
+    let provider: SharedPaymentProvider = match name {
+        "fake" => Arc::new(FakeProvider::new(&config.payment_webhook_secret)),
+        other => return Err(format!("Unknown payment provider: {}", other)),
+    };
+    Ok(Some(PaymentGateway {
+        provider,
+        currency: config.payment_currency.clone(),
+        return_url: config.payment_return_url.clone(),
+    }))
+}
+
+fn fake_allowed(environment: &str) -> bool {
+    matches!(environment, "development" | "test")
+}
+
+fn mac(secret: &str, timestamp: i64, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+// "t=<unix seconds>,v1=<hex HMAC-SHA256 of "t.payload">", the scheme most
+// providers use. The timestamp is signed so old deliveries cannot be replayed.
+#[cfg(test)]
+pub fn sign_payload(secret: &str, timestamp: i64, payload: &[u8]) -> String {
+    let digest = mac(secret, timestamp, payload).finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("t={},v1={}", timestamp, hex)
+}
+
+pub fn verify_signature(secret: &str, header: &str, payload: &[u8], now: i64) -> Result<(), PaymentError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or(PaymentError::InvalidSignature)?;
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECONDS {
+        return Err(PaymentError::InvalidSignature);
+    }
+
+    // Several v1 entries are allowed while a secret is being rolled
+    let matches = signatures.iter().any(|signature| {
+        decode_hex(signature).is_some_and(|bytes| mac(secret, timestamp, payload).verify_slice(&bytes).is_ok())
+    });
+    if !matches {
+        return Err(PaymentError::InvalidSignature);
+    }
+    Ok(())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Body of the fake provider's webhooks
+#[derive(Debug, Serialize, Deserialize)]
+struct FakeWebhook {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    payment_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refund_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    amount: Option<Decimal>,
+}
+
+#[derive(Default)]
+struct FakeLedger {
+    next_id: u64,
+    sessions: HashMap<String, Decimal>,
+    refunds: Vec<(String, Decimal)>,
+}
+
+// In-memory provider for tests and local development. Nothing is charged and
+// there is no hosted page: checkout sends the payer straight back to the
+// return URL. Tests play the provider's part with `webhook`, which signs
+// events the way a real provider would.
+pub struct FakeProvider {
+    secret: String,
+    ledger: Mutex<FakeLedger>,
+}
+
+impl FakeProvider {
+    pub fn new(secret: &str) -> Self {
+        FakeProvider {
+            secret: secret.to_string(),
+            ledger: Mutex::default(),
+        }
+    }
+
+    fn next_id(&self, prefix: &str) -> String {
+        let mut ledger = self.ledger.lock().expect("fake provider ledger");
+        ledger.next_id += 1;
+        format!("{}_fake_{}", prefix, ledger.next_id)
+    }
+
+    // Amount a checkout session was opened for
+    #[cfg(test)]
+    pub fn session_amount(&self, session_id: &str) -> Option<Decimal> {
+        self.ledger.lock().expect("fake provider ledger").sessions.get(session_id).copied()
+    }
+
+    // Refunds issued so far, as (payment id, amount)
+    #[cfg(test)]
+    pub fn refunds(&self) -> Vec<(String, Decimal)> {
+        self.ledger.lock().expect("fake provider ledger").refunds.clone()
+    }
+
+    // A signed delivery of `event` as (signature header value, body)
+    #[cfg(test)]
+    pub fn webhook(&self, event_id: &str, event: &WebhookEvent, timestamp: i64) -> (String, Vec<u8>) {
+        let mut body = FakeWebhook {
+            id: event_id.to_string(),
+            kind: event.kind().to_string(),
+            session_id: None,
+            payment_id: None,
+            refund_id: None,
+            amount: None,
+        };
+        match event {
+            WebhookEvent::CheckoutCompleted {
+                session_id,
+                payment_id,
+                amount,
+            } => {
+                body.session_id = Some(session_id.clone());
+                body.payment_id = Some(payment_id.clone());
+                body.amount = Some(*amount);
+            }
+            WebhookEvent::CheckoutExpired { session_id } => body.session_id = Some(session_id.clone()),
+            WebhookEvent::RefundSucceeded { refund_id } | WebhookEvent::RefundFailed { refund_id } => {
+                body.refund_id = Some(refund_id.clone())
+            }
+            WebhookEvent::Other(_) => {}
+        }
+        let payload = serde_json::to_vec(&body).expect("webhook body serializes");
+        (sign_payload(&self.secret, timestamp, &payload), payload)
+    }
+}
+
+impl PaymentProvider for FakeProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn signature_header(&self) -> &'static str {
+        "x-fake-signature"
+    }
+
+    fn create_checkout<'a>(
+        &'a self,
+        request: &'a CheckoutRequest,
+    ) -> BoxFuture<'a, Result<CheckoutSession, PaymentError>> {
+        Box::pin(async move {
+            if request.amount <= Decimal::ZERO {
+                return Err(PaymentError::Provider("amount must be positive".to_string()));
+            }
+            let id = self.next_id("cs");
+            self.ledger
+                .lock()
+                .expect("fake provider ledger")
+                .sessions
+                .insert(id.clone(), request.amount);
+            Ok(CheckoutSession {
+                url: request.return_url.clone(),
+                id,
+            })
+        })
+    }
+
+    fn refund<'a>(
+        &'a self,
+        payment_id: &'a str,
+        amount: Decimal,
+    ) -> BoxFuture<'a, Result<ProviderRefund, PaymentError>> {
+        Box::pin(async move {
+            if amount <= Decimal::ZERO {
+                return Err(PaymentError::Provider("amount must be positive".to_string()));
+            }
+            let id = self.next_id("re");
+            self.ledger
+                .lock()
+                .expect("fake provider ledger")
+                .refunds
+                .push((payment_id.to_string(), amount));
+            Ok(ProviderRefund {
+                id,
+                status: RefundStatus::Succeeded,
+            })
+        })
+    }
+
+    fn verify_webhook(&self, signature: &str, payload: &[u8]) -> Result<VerifiedEvent, PaymentError> {
+        verify_signature(&self.secret, signature, payload, chrono::Utc::now().timestamp())?;
+        let body: FakeWebhook =
+            serde_json::from_slice(payload).map_err(|e| PaymentError::MalformedPayload(e.to_string()))?;
+        let missing = |field: &str| PaymentError::MalformedPayload(format!("{} is missing", field));
+
+        let event = match body.kind.as_str() {
+            "checkout.completed" => WebhookEvent::CheckoutCompleted {
+                session_id: body.session_id.ok_or_else(|| missing("session_id"))?,
+                payment_id: body.payment_id.ok_or_else(|| missing("payment_id"))?,
+                amount: body.amount.ok_or_else(|| missing("amount"))?,
+            },
+            "checkout.expired" => WebhookEvent::CheckoutExpired {
+                session_id: body.session_id.ok_or_else(|| missing("session_id"))?,
+            },
+            "refund.succeeded" => WebhookEvent::RefundSucceeded {
+                refund_id: body.refund_id.ok_or_else(|| missing("refund_id"))?,
+            },
+            "refund.failed" => WebhookEvent::RefundFailed {
+                refund_id: body.refund_id.ok_or_else(|| missing("refund_id"))?,
+            },
+            other => WebhookEvent::Other(other.to_string()),
+        };
+        Ok(VerifiedEvent { id: body.id, event })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    #[test]
+    fn signatures_are_checked_for_tampering_and_age() {
+        let payload = br#"{"id":"evt_1"}"#;
+        let header = sign_payload("whsec", 1_700_000_000, payload);
+
+        assert!(verify_signature("whsec", &header, payload, 1_700_000_100).is_ok());
+        assert!(verify_signature("other", &header, payload, 1_700_000_100).is_err());
+        assert!(verify_signature("whsec", &header, br#"{"id":"evt_2"}"#, 1_700_000_100).is_err());
+        assert!(verify_signature("whsec", &header, payload, 1_700_000_000 + SIGNATURE_TOLERANCE_SECONDS + 1).is_err());
+        assert!(verify_signature("whsec", "v1=abcd", payload, 1_700_000_000).is_err());
+
+        // A header carrying the old and the new secret's signature verifies under either
+        let rolled = format!("{},v1=00ff", header);
+        assert!(verify_signature("whsec", &rolled, payload, 1_700_000_000).is_ok());
+    }
+
+    #[test]
+    fn fake_provider_is_refused_outside_development() {
+        assert!(fake_allowed("development"));
+        assert!(fake_allowed("test"));
+        assert!(!fake_allowed("production"));
+        assert!(!fake_allowed("staging"));
+    }
+
+    #[tokio::test]
+    async fn fake_provider_round_trips_webhooks() {
+        let provider = FakeProvider::new("whsec");
+        let session = provider
+            .create_checkout(&CheckoutRequest {
+                reference: "INV-000001".to_string(),
+                amount: dec!(120.50),
+                currency: "USD".to_string(),
+                description: "Term 1 fees".to_string(),
+                return_url: "http://localhost:3000/fees".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(session.id.starts_with("cs_fake_"));
+        assert_eq!(session.url, "http://localhost:3000/fees");
+        assert_eq!(provider.session_amount(&session.id), Some(dec!(120.50)));
+
+        let completed = WebhookEvent::CheckoutCompleted {
+            session_id: session.id.clone(),
+            payment_id: "pay_1".to_string(),
+            amount: dec!(120.50),
+        };
+        let (signature, body) = provider.webhook("evt_1", &completed, chrono::Utc::now().timestamp());
+        let verified = provider.verify_webhook(&signature, &body).unwrap();
+        assert_eq!(verified.id, "evt_1");
+        assert_eq!(verified.event, completed);
+
+        let mut forged = body.clone();
+        forged.extend_from_slice(b" ");
+        assert!(matches!(provider.verify_webhook(&signature, &forged), Err(PaymentError::InvalidSignature)));
+
+        let refund = provider.refund("pay_1", dec!(20.50)).await.unwrap();
+        assert_eq!(refund.status, RefundStatus::Succeeded);
+        assert_eq!(provider.refunds(), vec![("pay_1".to_string(), dec!(20.50))]);
+    }
+}
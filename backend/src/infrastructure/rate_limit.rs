@@ -0,0 +1,243 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode, Extensions},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::infrastructure::counters::CounterStore;
+
+// Request budget for every path under `prefix`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitRule {
+    pub prefix: String,
+    pub limit: u64,
+    pub window: Duration,
+}
+
+impl RateLimitRule {
+    // Parse config pairs like ("/api/v1/auth", "20/60"): 20 requests per 60 seconds
+    pub fn from_pairs(pairs: &[(String, String)]) -> Vec<Self> {
+        let mut rules: Vec<Self> = pairs
+            .iter()
+            .filter_map(|(prefix, budget)| {
+                let (limit, seconds) = budget.split_once('/')?;
+                let rule = Self {
+                    prefix: prefix.clone(),
+                    limit: limit.trim().parse().ok()?,
+                    window: Duration::from_secs(seconds.trim().parse().ok()?),
+                };
+                Some(rule)
+            })
+            .collect();
+        // Most specific prefix first
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.prefix.len()));
+        rules
+    }
+
+    // Whether `path` is the prefix itself or below it, whole segments only:
+    // "/api/v1/auth" covers "/api/v1/auth/login" but not "/api/v1/authors"
+    pub fn covers(&self, path: &str) -> bool {
+        let prefix = self.prefix.trim_end_matches('/');
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+// Address of the client: behind `trusted_proxies` proxies, the X-Forwarded-For
+// hop the outermost of them appended, otherwise the TCP peer. Hops further
+// left come from the client and are never believed.
+pub fn client_ip(headers: &HeaderMap, extensions: &Extensions, trusted_proxies: usize) -> Option<IpAddr> {
+    if trusted_proxies > 0 {
+        let hops: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        let forwarded = hops
+            .len()
+            .checked_sub(trusted_proxies)
+            .and_then(|hop| hops[hop].parse().ok());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+// Fixed-window request limiter per client IP and route group
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    store: CounterStore,
+    rules: Arc<Vec<RateLimitRule>>,
+    trusted_proxies: usize,
+}
+
+impl RateLimitLayer {
+    pub fn new(store: CounterStore, rules: Vec<RateLimitRule>, trusted_proxies: usize) -> Self {
+        Self {
+            store,
+            rules: Arc::new(rules),
+            trusted_proxies,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: RateLimitLayer,
+}
+
+impl<S> Service<Request<Body>> for RateLimit<S>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let path = request.uri().path();
+            let rule = limiter.rules.iter().find(|rule| rule.covers(path));
+            let ip = client_ip(request.headers(), request.extensions(), limiter.trusted_proxies);
+
+            let (Some(rule), Some(ip)) = (rule, ip) else {
+                return inner.call(request).await;
+            };
+
+            let key = format!("rate:{}:{}", rule.prefix, ip);
+            match limiter.store.increment(&key, rule.window).await {
+                Ok(count) if count > rule.limit => {
+                    let retry_after = limiter
+                        .store
+                        .ttl(&key)
+                        .await
+                        .ok()
+                        .flatten()
+                        .unwrap_or(rule.window);
+                    tracing::warn!("Rate limit hit for {} on {}", ip, rule.prefix);
+                    Ok(too_many_requests(retry_after))
+                }
+                Ok(_) => inner.call(request).await,
+                Err(e) => {
+                    // Better to serve without limits than to fail every request
+                    tracing::error!("Rate limiter unavailable: {}", e);
+                    inner.call(request).await
+                }
+            }
+        })
+    }
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs().max(1);
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        format!("Too many requests, try again in {} seconds", seconds),
+    )
+        .into_response();
+    if let Ok(value) = HeaderValue::from_str(&seconds.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_parsed_most_specific_first() {
+        let pairs = vec![
+            ("/api/v1".to_string(), "600/60".to_string()),
+            ("/api/v1/auth".to_string(), "20/60".to_string()),
+            ("/broken".to_string(), "many".to_string()),
+        ];
+        let rules = RateLimitRule::from_pairs(&pairs);
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].prefix, "/api/v1/auth");
+        assert_eq!(rules[0].limit, 20);
+        assert_eq!(rules[1].window, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn prefixes_match_whole_path_segments() {
+        let rules = RateLimitRule::from_pairs(&[
+            ("/api/v1/auth".to_string(), "20/60".to_string()),
+            ("/api/v1/".to_string(), "600/60".to_string()),
+        ]);
+        let rule = |path: &str| rules.iter().find(|rule| rule.covers(path)).map(|rule| rule.limit);
+
+        assert_eq!(rule("/api/v1/auth"), Some(20));
+        assert_eq!(rule("/api/v1/auth/login"), Some(20));
+        assert_eq!(rule("/api/v1/authors"), Some(600));
+        assert_eq!(rule("/api/v1"), Some(600));
+        assert_eq!(rule("/api/v10/users"), None);
+        assert_eq!(rule("/"), None);
+    }
+
+    #[test]
+    fn spoofed_forwarded_hops_are_ignored() {
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 2], 443))));
+        let forwarded = |values: &[&str]| {
+            let mut headers = HeaderMap::new();
+            for value in values {
+                headers.append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+            }
+            headers
+        };
+        let ip = |text: &str| Some(text.parse::<IpAddr>().unwrap());
+
+        // The client made up "1.2.3.4"; the proxy appended the address it saw
+        let spoofed = forwarded(&["1.2.3.4, 203.0.113.7"]);
+        assert_eq!(client_ip(&spoofed, &extensions, 1), ip("203.0.113.7"));
+        assert_eq!(client_ip(&forwarded(&["1.2.3.4", "203.0.113.7"]), &extensions, 1), ip("203.0.113.7"));
+
+        // Two proxies: the outer one's hop is second from the right
+        let chained = forwarded(&["1.2.3.4, 203.0.113.7, 10.0.0.1"]);
+        assert_eq!(client_ip(&chained, &extensions, 2), ip("203.0.113.7"));
+
+        // Fewer hops than proxies, or no trusted proxy at all: the TCP peer
+        assert_eq!(client_ip(&spoofed, &extensions, 3), ip("10.0.0.2"));
+        assert_eq!(client_ip(&spoofed, &extensions, 0), ip("10.0.0.2"));
+    }
+}
@@ -1,20 +1,26 @@
 use axum::{
+    http::HeaderName,
+    middleware,
     routing::get,
     Router,
     Json,
 };
 use serde::Serialize;
 use std::net::SocketAddr;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
 use tower_http::cors::{CorsLayer, Any};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod api;
+mod application;
 mod config;
 mod database;
 mod dto;
 mod entities;
+mod infrastructure;
 mod repositories;
+mod state;
 
 #[derive(Serialize)]
 struct HealthResponse {
@@ -51,18 +57,92 @@ async fn main() {
 
     tracing::info!("📊 Database connected");
 
+    let lti_key = application::lti::ToolKey::from_config(&config)
+        .expect("Failed to load LTI tool key");
+
+    application::auth::configure_password_hashing(&config)
+        .expect("Invalid argon2 parameters");
+    let password_policy = application::password::PasswordPolicy::from_config(&config)
+        .expect("Failed to load password policy");
+
+    let keyring = application::encryption::Keyring::from_config(&config)
+        .expect("Failed to load encryption keys");
+
+    let payments = infrastructure::payments::from_config(&config)
+        .expect("Failed to configure payment provider");
+
+    let mailer = infrastructure::mailer::from_config(&config).expect("Failed to configure mail transport");
+
+    let counters = infrastructure::counters::CounterStore::connect(&config.redis_url).await;
+    let rate_limit = infrastructure::rate_limit::RateLimitLayer::new(
+        counters.clone(),
+        infrastructure::rate_limit::RateLimitRule::from_pairs(&config.rate_limits),
+        config.trusted_proxies,
+    );
+
+    let state = state::AppState {
+        db,
+        config: config.clone(),
+        http: reqwest::Client::new(),
+        jwks: infrastructure::jwks::JwksCache::default(),
+        lti_key,
+        oidc: application::oidc::OidcClient::from_config(&config),
+        ldap: application::directory::LdapDirectory::from_config(&config),
+        password_policy,
+        mailer,
+        login_guard: application::login_guard::LoginGuard::new(counters, &config),
+        keyring,
+        payments,
+        library_policy: application::library::LibraryPolicy::from_config(&config),
+        notifications: infrastructure::notifications::NotificationHub::connect(&config.redis_url).await,
+    };
+
+    // Keep directory users, roles and deactivations in step with LDAP
+    if let Some(ldap) = state.ldap.clone() {
+        application::directory::spawn_scheduled_sync(state.db.clone(), ldap, &config);
+    }
+
+    // Deleted users are purged once they have been in the trash long enough
+    application::trash::spawn_scheduled_purge(state.db.clone(), &config);
+
+    // Rows sealed with a retired encryption key, or in the legacy format, are
+    // moved onto the active key
+    application::encryption::spawn_scheduled_reencryption(state.db.clone(), state.keyring.clone(), &config);
+
+    // Notices scheduled for later are announced once they go live
+    application::notifications::spawn_scheduled_announcements(
+        state.db.clone(),
+        state.notifications.clone(),
+        &config,
+    );
+
+    // Queued email is sent by a worker, which retries failed deliveries
+    application::email::spawn_outbox_worker(state.db.clone(), state.mailer.clone(), &config);
+
+    // Families are reminded of invoices that are nearly due
+    application::email::spawn_invoice_reminders(state.db.clone(), &config);
+
     // Build our API routes
     let app = Router::new()
     .route("/health", get(health_check))
-    .nest("/api/v1", api::routes())
-    .with_state(db)  // ← At the end
+    .nest(
+        "/api/v1",
+        api::routes().layer(middleware::from_fn_with_state(state.clone(), api::track_impersonation)),
+    )
+    .with_state(state)  // ← At the end
+    .layer(rate_limit)
     .layer(
         CorsLayer::new()
             .allow_origin(Any)
             .allow_methods(Any)
             .allow_headers(Any)
+            .expose_headers([HeaderName::from_static(application::impersonation::BANNER_HEADER)])
     )
-    .layer(TraceLayer::new_for_http());
+    .layer(PropagateRequestIdLayer::x_request_id())
+    // Stream tokens can come in the query string, so traces log it redacted
+    .layer(TraceLayer::new_for_http().make_span_with(api::trace_span))
+    // Outermost, so the audit log and traces see the id and clients get it back
+    .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid));
 
     // Start the server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -72,7 +152,8 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
+    // Connection info gives the rate limiter and login guard the client address
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .expect("Server failed to start");
 }
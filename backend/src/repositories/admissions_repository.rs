@@ -0,0 +1,552 @@
+use chrono::Utc;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::*;
+
+use crate::application::admissions::{self, AdmissionsError, DocumentUpload};
+use crate::application::audit::{self, AuditContext};
+use crate::application::{auth, password};
+use crate::dto::admissions::{
+    ApplicationDetail, ApplicationQuery, ApplicationRequest, ApplicationResponse, DocumentResponse, LetterResponse,
+    ReviewRequest, ReviewResponse, StageRequest, StageResponse,
+};
+use crate::dto::user::UserResponse;
+use crate::entities::{
+    admission_applications, admission_documents, admission_letters, admission_reviews, admission_stages, prelude::*,
+    users,
+};
+use crate::infrastructure::mailer::Email;
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+pub struct AdmissionsRepository;
+
+impl AdmissionsRepository {
+    // The school's pipeline in order, starting it with the default stages
+    // the first time it is needed
+    pub async fn list_stages(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<admission_stages::Model>, DbErr> {
+        ensure_stages(db, tenant_id).await
+    }
+
+    // Set the pipeline to `stages`, in order. Stages named by id keep their
+    // applications; stages left out are removed unless applications sit in
+    // them.
+    pub async fn replace_stages(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        stages: &[StageRequest],
+        context: &AuditContext,
+    ) -> Result<Vec<admission_stages::Model>, AdmissionsError> {
+        admissions::check_stage_names(stages.iter().map(|stage| stage.name.as_str()))?;
+
+        let txn = db.begin().await?;
+        let existing = ensure_stages(&txn, tenant_id).await?;
+        if stages
+            .iter()
+            .filter_map(|stage| stage.id)
+            .any(|id| !existing.iter().any(|stage| stage.id == id))
+        {
+            return Err(AdmissionsError::StageNotFound);
+        }
+
+        let before = audit::snapshot(&existing.iter().cloned().map(StageResponse::from).collect::<Vec<_>>());
+        for stage in &existing {
+            if stages.iter().any(|kept| kept.id == Some(stage.id)) {
+                continue;
+            }
+            let applications = AdmissionApplications::find()
+                .filter(admission_applications::Column::StageId.eq(stage.id))
+                .count(&txn)
+                .await?;
+            if applications > 0 {
+                return Err(AdmissionsError::StageInUse(stage.name.clone()));
+            }
+            AdmissionStages::delete_by_id(stage.id).exec(&txn).await?;
+        }
+
+        // Park kept stages under their ids so swapping two names never
+        // collides on (tenant_id, name)
+        AdmissionStages::update_many()
+            .col_expr(admission_stages::Column::Name, sea_query::Expr::cust("'#' || \"id\""))
+            .filter(admission_stages::Column::TenantId.eq(tenant_id))
+            .exec(&txn)
+            .await?;
+
+        let mut saved = Vec::with_capacity(stages.len());
+        for (index, stage) in stages.iter().enumerate() {
+            let mut model = admission_stages::ActiveModel {
+                tenant_id: Set(tenant_id),
+                name: Set(stage.name.trim().to_string()),
+                position: Set(index as i32 + 1),
+                ..Default::default()
+            };
+            let model = match stage.id {
+                Some(id) => {
+                    model.id = Unchanged(id);
+                    model.update(&txn).await?
+                }
+                None => model.insert(&txn).await?,
+            };
+            saved.push(model);
+        }
+
+        let after = audit::snapshot(&saved.iter().cloned().map(StageResponse::from).collect::<Vec<_>>());
+        AuditRepository::record(&txn, context, "update_stages", "tenants", tenant_id, before, after).await?;
+        txn.commit().await?;
+        Ok(saved)
+    }
+
+    // Open an application at the first stage. Online applications get an
+    // upload token for their documents, returned here and stored hashed.
+    pub async fn create_application(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &ApplicationRequest,
+        with_upload_token: bool,
+        context: &AuditContext,
+    ) -> Result<(admission_applications::Model, Option<String>), DbErr> {
+        let txn = db.begin().await?;
+        let stages = ensure_stages(&txn, tenant_id).await?;
+        let first = stages
+            .first()
+            .ok_or_else(|| DbErr::Custom("admissions pipeline has no stages".to_string()))?;
+        let (token, token_hash) = match with_upload_token {
+            true => {
+                let (token, hash) = password::new_reset_token();
+                (Some(token), Some(hash))
+            }
+            false => (None, None),
+        };
+
+        let application = admission_applications::ActiveModel {
+            tenant_id: Set(tenant_id),
+            stage_id: Set(first.id),
+            full_name: Set(request.full_name.clone()),
+            date_of_birth: Set(request.date_of_birth),
+            grade: Set(request.grade.clone()),
+            entry_term: Set(request.entry_term.clone()),
+            student_email: Set(request.student_email.clone()),
+            guardian_name: Set(request.guardian_name.clone()),
+            guardian_email: Set(request.guardian_email.clone()),
+            guardian_phone: Set(request.guardian_phone.clone()),
+            notes: Set(request.notes.clone()),
+            decision: Set(admissions::PENDING.to_string()),
+            upload_token_hash: Set(token_hash),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&ApplicationResponse::from(application.clone()));
+        AuditRepository::record(&txn, context, "create", "admission_applications", application.id, None, snapshot)
+            .await?;
+        txn.commit().await?;
+        Ok((application, token))
+    }
+
+    pub async fn list_applications(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &ApplicationQuery,
+    ) -> Result<Vec<admission_applications::Model>, DbErr> {
+        let mut select = AdmissionApplications::scoped(tenant_id);
+        if let Some(stage_id) = query.stage_id {
+            select = select.filter(admission_applications::Column::StageId.eq(stage_id));
+        }
+        if let Some(decision) = &query.decision {
+            select = select.filter(admission_applications::Column::Decision.eq(decision.as_str()));
+        }
+        if let Some(grade) = &query.grade {
+            select = select.filter(admission_applications::Column::Grade.eq(grade.as_str()));
+        }
+        select.order_by_asc(admission_applications::Column::Id).all(db).await
+    }
+
+    pub async fn application_detail(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<ApplicationDetail>, DbErr> {
+        let Some((application, Some(stage))) = AdmissionApplications::scoped(tenant_id)
+            .filter(admission_applications::Column::Id.eq(id))
+            .find_also_related(AdmissionStages)
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let reviews = application
+            .find_related(AdmissionReviews)
+            .order_by_asc(admission_reviews::Column::Id)
+            .all(db)
+            .await?;
+        // Metadata only; content is fetched one document at a time
+        let documents = application
+            .find_related(AdmissionDocuments)
+            .select_only()
+            .columns([
+                admission_documents::Column::Id,
+                admission_documents::Column::ApplicationId,
+                admission_documents::Column::Kind,
+                admission_documents::Column::Filename,
+                admission_documents::Column::ContentType,
+                admission_documents::Column::Length,
+                admission_documents::Column::UploadedBy,
+                admission_documents::Column::CreatedAt,
+            ])
+            .order_by_asc(admission_documents::Column::Id)
+            .into_tuple::<(i32, i32, String, String, String, i64, Option<i32>, chrono::NaiveDateTime)>()
+            .all(db)
+            .await?;
+        let letters = application
+            .find_related(AdmissionLetters)
+            .order_by_asc(admission_letters::Column::Id)
+            .all(db)
+            .await?;
+
+        let scores: Vec<i32> = reviews.iter().map(|review| review.score).collect();
+        Ok(Some(ApplicationDetail {
+            application: application.into(),
+            stage: stage.name,
+            average_score: admissions::average_score(&scores),
+            reviews: reviews.into_iter().map(ReviewResponse::from).collect(),
+            documents: documents
+                .into_iter()
+                .map(|(id, application_id, kind, filename, content_type, length, uploaded_by, created_at)| {
+                    DocumentResponse::from(admission_documents::Model {
+                        id,
+                        application_id,
+                        kind,
+                        filename,
+                        content_type,
+                        length,
+                        content: Vec::new(),
+                        uploaded_by,
+                        created_at,
+                    })
+                })
+                .collect(),
+            letters: letters.into_iter().map(LetterResponse::from).collect(),
+        }))
+    }
+
+    pub async fn move_stage(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        stage_id: i32,
+        context: &AuditContext,
+    ) -> Result<admission_applications::Model, AdmissionsError> {
+        let txn = db.begin().await?;
+        let application = lock_application(&txn, tenant_id, id).await?;
+        AdmissionStages::scoped(tenant_id)
+            .filter(admission_stages::Column::Id.eq(stage_id))
+            .one(&txn)
+            .await?
+            .ok_or(AdmissionsError::StageNotFound)?;
+
+        let before = audit::snapshot(&ApplicationResponse::from(application.clone()));
+        let mut application: admission_applications::ActiveModel = application.into();
+        application.stage_id = Set(stage_id);
+        application.updated_at = Set(Utc::now().naive_utc());
+        let application = application.update(&txn).await?;
+
+        let after = audit::snapshot(&ApplicationResponse::from(application.clone()));
+        AuditRepository::record(&txn, context, "move_stage", "admission_applications", id, before, after).await?;
+        txn.commit().await?;
+        Ok(application)
+    }
+
+    // The pending application an upload token belongs to
+    pub async fn find_by_upload_token(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        token: &str,
+    ) -> Result<Option<admission_applications::Model>, DbErr> {
+        AdmissionApplications::scoped(tenant_id)
+            .filter(admission_applications::Column::UploadTokenHash.eq(password::hash_reset_token(token)))
+            .one(db)
+            .await
+    }
+
+    // Store a document against an application. Families may only add
+    // documents while the application is pending; staff at any time.
+    pub async fn add_document(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        application_id: i32,
+        upload: DocumentUpload,
+        context: &AuditContext,
+    ) -> Result<admission_documents::Model, AdmissionsError> {
+        admissions::check_document(&upload.content_type, upload.content.len())?;
+
+        let txn = db.begin().await?;
+        let application = lock_application(&txn, tenant_id, application_id).await?;
+        if context.actor_id.is_none() && application.decision != admissions::PENDING {
+            return Err(AdmissionsError::UploadsClosed);
+        }
+        let document = admission_documents::ActiveModel {
+            application_id: Set(application_id),
+            kind: Set(upload.kind),
+            filename: Set(admissions::safe_filename(&upload.filename)),
+            content_type: Set(upload.content_type),
+            length: Set(upload.content.len() as i64),
+            content: Set(upload.content),
+            uploaded_by: Set(context.actor_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&DocumentResponse::from(document.clone()));
+        AuditRepository::record(&txn, context, "create", "admission_documents", document.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(document)
+    }
+
+    pub async fn document(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<admission_documents::Model>, DbErr> {
+        AdmissionDocuments::find_by_id(id)
+            .inner_join(AdmissionApplications)
+            .filter(admission_applications::Column::TenantId.eq(tenant_id))
+            .one(db)
+            .await
+    }
+
+    // Record a reviewer's score, replacing one they gave before
+    pub async fn review(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        application_id: i32,
+        request: &ReviewRequest,
+        context: &AuditContext,
+    ) -> Result<admission_reviews::Model, AdmissionsError> {
+        let reviewer_id = context
+            .actor_id
+            .ok_or_else(|| DbErr::Custom("reviews need a signed-in reviewer".to_string()))?;
+        let txn = db.begin().await?;
+        lock_application(&txn, tenant_id, application_id).await?;
+
+        let current = AdmissionReviews::find()
+            .filter(admission_reviews::Column::ApplicationId.eq(application_id))
+            .filter(admission_reviews::Column::ReviewerId.eq(reviewer_id))
+            .one(&txn)
+            .await?;
+        let before = current
+            .clone()
+            .and_then(|review| audit::snapshot(&ReviewResponse::from(review)));
+        let review = match current {
+            Some(current) => {
+                let mut review: admission_reviews::ActiveModel = current.into();
+                review.score = Set(request.score);
+                review.comments = Set(request.comments.clone());
+                review.updated_at = Set(Utc::now().naive_utc());
+                review.update(&txn).await?
+            }
+            None => {
+                admission_reviews::ActiveModel {
+                    application_id: Set(application_id),
+                    reviewer_id: Set(reviewer_id),
+                    score: Set(request.score),
+                    comments: Set(request.comments.clone()),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?
+            }
+        };
+
+        let action = if before.is_some() { "update" } else { "create" };
+        let after = audit::snapshot(&ReviewResponse::from(review.clone()));
+        AuditRepository::record(&txn, context, action, "admission_reviews", review.id, before, after).await?;
+        txn.commit().await?;
+        Ok(review)
+    }
+
+    // Decide a batch of applications and write each family's letter. Either
+    // every application takes the decision or none does; letters are sent
+    // by the caller once this commits. A decision closes online uploads.
+    pub async fn decide(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        ids: &[i32],
+        decision: &str,
+        letter: impl Fn(&admission_applications::Model) -> Email,
+        context: &AuditContext,
+    ) -> Result<Vec<admission_letters::Model>, AdmissionsError> {
+        let txn = db.begin().await?;
+        let applications = AdmissionApplications::scoped(tenant_id)
+            .filter(admission_applications::Column::Id.is_in(ids.iter().copied()))
+            .order_by_asc(admission_applications::Column::Id)
+            .lock_exclusive()
+            .all(&txn)
+            .await?;
+        if ids.iter().any(|id| !applications.iter().any(|application| application.id == *id)) {
+            return Err(AdmissionsError::ApplicationNotFound);
+        }
+
+        let now = Utc::now().naive_utc();
+        let mut letters = Vec::with_capacity(applications.len());
+        for application in applications {
+            admissions::check_decision(
+                application.id,
+                &application.decision,
+                decision,
+                application.student_id.is_some(),
+            )?;
+            let email = letter(&application);
+
+            let before = audit::snapshot(&ApplicationResponse::from(application.clone()));
+            let mut application: admission_applications::ActiveModel = application.into();
+            application.decision = Set(decision.to_string());
+            application.decided_at = Set(Some(now));
+            application.upload_token_hash = Set(None);
+            application.updated_at = Set(now);
+            let application = application.update(&txn).await?;
+            let after = audit::snapshot(&ApplicationResponse::from(application.clone()));
+            AuditRepository::record(&txn, context, "decide", "admission_applications", application.id, before, after)
+                .await?;
+
+            letters.push(
+                admission_letters::ActiveModel {
+                    application_id: Set(application.id),
+                    decision: Set(decision.to_string()),
+                    sent_to: Set(email.to),
+                    subject: Set(email.subject),
+                    body: Set(email.body),
+                    sent_by: Set(context.actor_id),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?,
+            );
+        }
+
+        txn.commit().await?;
+        Ok(letters)
+    }
+
+    pub async fn mark_letter_sent(db: &DatabaseConnection, id: i32) -> Result<(), DbErr> {
+        AdmissionLetters::update_many()
+            .col_expr(admission_letters::Column::SentAt, sea_query::Expr::value(Utc::now().naive_utc()))
+            .filter(admission_letters::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    // Create the student account for an accepted applicant from the data on
+    // the application and move it to the last stage. The account gets a
+    // random password; the student sets their own through a password reset.
+    pub async fn enrol(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        email: Option<&str>,
+        context: &AuditContext,
+    ) -> Result<(admission_applications::Model, users::Model), AdmissionsError> {
+        let txn = db.begin().await?;
+        let application = lock_application(&txn, tenant_id, id).await?;
+        if application.student_id.is_some() {
+            return Err(AdmissionsError::AlreadyEnrolled(id));
+        }
+        if application.decision != admissions::ACCEPTED {
+            return Err(AdmissionsError::NotAccepted);
+        }
+        let email = email
+            .or(application.student_email.as_deref())
+            .map(auth::normalize_email)
+            .ok_or(AdmissionsError::EmailRequired)?;
+        // Trashed users keep their email, so they count too
+        if Users::scoped(tenant_id)
+            .filter(user_repository::email_is(&email))
+            .count(&txn)
+            .await?
+            > 0
+        {
+            return Err(AdmissionsError::EmailTaken(email));
+        }
+
+        let password_hash = auth::hash_password(&uuid::Uuid::new_v4().to_string())
+            .map_err(|_| DbErr::Custom("Failed to hash password".to_string()))?;
+        let student = users::ActiveModel {
+            tenant_id: Set(tenant_id),
+            email: Set(email),
+            password_hash: Set(password_hash),
+            full_name: Set(application.full_name.clone()),
+            role: Set("student".to_string()),
+            is_active: Set(true),
+            auth_source: Set("local".to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let snapshot = audit::snapshot(&UserResponse::from(student.clone()));
+        AuditRepository::record(&txn, context, "create", "users", student.id, None, snapshot).await?;
+
+        let last = ensure_stages(&txn, tenant_id).await?.pop();
+        let before = audit::snapshot(&ApplicationResponse::from(application.clone()));
+        let mut application: admission_applications::ActiveModel = application.into();
+        application.student_id = Set(Some(student.id));
+        if let Some(last) = last {
+            application.stage_id = Set(last.id);
+        }
+        application.updated_at = Set(Utc::now().naive_utc());
+        let application = application.update(&txn).await?;
+        let after = audit::snapshot(&ApplicationResponse::from(application.clone()));
+        AuditRepository::record(&txn, context, "enrol", "admission_applications", id, before, after).await?;
+
+        txn.commit().await?;
+        Ok((application, student))
+    }
+}
+
+// The school's stages in order, inserting the default pipeline when it has
+// none. Concurrent first requests may both try; the unique name index keeps
+// one copy.
+async fn ensure_stages<C: ConnectionTrait>(conn: &C, tenant_id: i32) -> Result<Vec<admission_stages::Model>, DbErr> {
+    let stages = AdmissionStages::scoped(tenant_id)
+        .order_by_asc(admission_stages::Column::Position)
+        .all(conn)
+        .await?;
+    if !stages.is_empty() {
+        return Ok(stages);
+    }
+
+    AdmissionStages::insert_many(admissions::DEFAULT_STAGES.iter().enumerate().map(|(index, name)| {
+        admission_stages::ActiveModel {
+            tenant_id: Set(tenant_id),
+            name: Set((*name).to_owned()),
+            position: Set(index as i32 + 1),
+            ..Default::default()
+        }
+    }))
+    .on_conflict(
+        OnConflict::columns([admission_stages::Column::TenantId, admission_stages::Column::Name])
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(conn)
+    .await?;
+
+    AdmissionStages::scoped(tenant_id)
+        .order_by_asc(admission_stages::Column::Position)
+        .all(conn)
+        .await
+}
+
+async fn lock_application<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    id: i32,
+) -> Result<admission_applications::Model, AdmissionsError> {
+    AdmissionApplications::scoped(tenant_id)
+        .filter(admission_applications::Column::Id.eq(id))
+        .lock_exclusive()
+        .one(conn)
+        .await?
+        .ok_or(AdmissionsError::ApplicationNotFound)
+}
@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::*;
+
+use crate::application::admissions::safe_filename;
+use crate::application::announcements::{
+    check_attachment, check_audience, check_schedule, reaches, visible_to, AnnouncementError, AttachmentUpload, Reader,
+};
+use crate::application::audit::{self, AuditContext};
+use crate::dto::announcements::{
+    AnnouncementRequest, AnnouncementResponse, AttachmentResponse, ClassRequest, ClassResponse, NoticeResponse,
+    ReceiptResponse, ReceiptsResponse,
+};
+use crate::entities::{
+    announcement_attachments, announcement_reads, announcements, class_memberships, prelude::*, users,
+};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn trimmed(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+pub struct AnnouncementRepository;
+
+impl AnnouncementRepository {
+    // The person behind a token, with their class if they are in one
+    pub async fn reader(db: &DatabaseConnection, user_id: i32, role: &str) -> Result<Reader, DbErr> {
+        let membership = ClassMemberships::find_by_id(user_id).one(db).await?;
+        Ok(Reader {
+            user_id,
+            role: role.to_string(),
+            grade: membership.as_ref().map(|membership| membership.grade.clone()),
+            section: membership.and_then(|membership| membership.section),
+        })
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &AnnouncementRequest,
+        context: &AuditContext,
+    ) -> Result<announcements::Model, AnnouncementError> {
+        let now = Utc::now().naive_utc();
+        let publish_at = request.publish_at.unwrap_or(now);
+        check_request(request, publish_at)?;
+
+        let txn = db.begin().await?;
+        let announcement = announcements::ActiveModel {
+            tenant_id: Set(tenant_id),
+            title: Set(request.title.trim().to_string()),
+            body: Set(request.body.clone()),
+            audience: Set(request.audience.clone()),
+            grade: Set(trimmed(&request.grade)),
+            section: Set(trimmed(&request.section)),
+            is_pinned: Set(request.is_pinned),
+            publish_at: Set(publish_at),
+            expires_at: Set(request.expires_at),
+            created_by: Set(context.actor_id),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&AnnouncementResponse::from(announcement.clone()));
+        AuditRepository::record(&txn, context, "create", "announcements", announcement.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(announcement)
+    }
+
+    pub async fn update(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &AnnouncementRequest,
+        context: &AuditContext,
+    ) -> Result<announcements::Model, AnnouncementError> {
+        let txn = db.begin().await?;
+        let existing = Announcements::scoped(tenant_id)
+            .filter(announcements::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(AnnouncementError::NotFound)?;
+        let publish_at = request.publish_at.unwrap_or(existing.publish_at);
+        check_request(request, publish_at)?;
+
+        let before = audit::snapshot(&AnnouncementResponse::from(existing.clone()));
+        let mut announcement: announcements::ActiveModel = existing.into();
+        announcement.title = Set(request.title.trim().to_string());
+        announcement.body = Set(request.body.clone());
+        announcement.audience = Set(request.audience.clone());
+        announcement.grade = Set(trimmed(&request.grade));
+        announcement.section = Set(trimmed(&request.section));
+        announcement.is_pinned = Set(request.is_pinned);
+        announcement.publish_at = Set(publish_at);
+        announcement.expires_at = Set(request.expires_at);
+        announcement.updated_at = Set(Utc::now().naive_utc());
+        let announcement = announcement.update(&txn).await?;
+
+        let after = audit::snapshot(&AnnouncementResponse::from(announcement.clone()));
+        AuditRepository::record(&txn, context, "update", "announcements", id, before, after).await?;
+        txn.commit().await?;
+        Ok(announcement)
+    }
+
+    // Take a notice down for good, with its attachments and receipts
+    pub async fn delete(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some(announcement) = Announcements::scoped(tenant_id)
+            .filter(announcements::Column::Id.eq(id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let snapshot = audit::snapshot(&AnnouncementResponse::from(announcement.clone()));
+        announcement.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "announcements", id, snapshot, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    // The reader's notice board: live notices addressed to them, pinned
+    // first and then newest first. With `all`, those who manage notices see
+    // every notice, whatever its audience or schedule.
+    pub async fn board(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        reader: &Reader,
+        all: bool,
+    ) -> Result<Vec<NoticeResponse>, DbErr> {
+        let everything = all && reader.manages_announcements();
+        let mut select = Announcements::scoped(tenant_id);
+        if !everything {
+            select = select.filter(live(Utc::now().naive_utc()));
+        }
+        let found = select
+            .order_by_desc(announcements::Column::IsPinned)
+            .order_by_desc(announcements::Column::PublishAt)
+            .order_by_desc(announcements::Column::Id)
+            .all(db)
+            .await?;
+        let found: Vec<_> = found
+            .into_iter()
+            .filter(|announcement| everything || reaches(announcement, reader))
+            .collect();
+        notices(db, reader.user_id, found).await
+    }
+
+    pub async fn notice(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        reader: &Reader,
+    ) -> Result<Option<NoticeResponse>, DbErr> {
+        let Some(announcement) = visible(db, tenant_id, id, reader).await? else {
+            return Ok(None);
+        };
+        Ok(notices(db, reader.user_id, vec![announcement]).await?.pop())
+    }
+
+    // Record that the reader has read a notice; reading it again keeps the
+    // first receipt
+    pub async fn mark_read(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        reader: &Reader,
+    ) -> Result<NoticeResponse, AnnouncementError> {
+        let announcement = visible(db, tenant_id, id, reader)
+            .await?
+            .ok_or(AnnouncementError::NotFound)?;
+
+        AnnouncementReads::insert(announcement_reads::ActiveModel {
+            announcement_id: Set(id),
+            user_id: Set(reader.user_id),
+            read_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([announcement_reads::Column::AnnouncementId, announcement_reads::Column::UserId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        notices(db, reader.user_id, vec![announcement])
+            .await?
+            .pop()
+            .ok_or(AnnouncementError::NotFound)
+    }
+
+    // Live notices addressed to the reader that they have not read
+    pub async fn unread_count(db: &DatabaseConnection, tenant_id: i32, reader: &Reader) -> Result<usize, DbErr> {
+        let found = Announcements::scoped(tenant_id)
+            .filter(live(Utc::now().naive_utc()))
+            .all(db)
+            .await?;
+        let ids: Vec<i32> = found
+            .iter()
+            .filter(|announcement| reaches(announcement, reader))
+            .map(|announcement| announcement.id)
+            .collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let read = AnnouncementReads::find()
+            .filter(announcement_reads::Column::UserId.eq(reader.user_id))
+            .filter(announcement_reads::Column::AnnouncementId.is_in(ids.iter().copied()))
+            .count(db)
+            .await?;
+        Ok(ids.len().saturating_sub(read as usize))
+    }
+
+    // Who has read a notice, and how many active users it is addressed to
+    pub async fn receipts(db: &DatabaseConnection, tenant_id: i32, id: i32) -> Result<Option<ReceiptsResponse>, DbErr> {
+        let Some(announcement) = Announcements::scoped(tenant_id)
+            .filter(announcements::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let audience = Self::audience(db, &announcement).await?.len();
+
+        let readers: Vec<ReceiptResponse> = AnnouncementReads::find()
+            .filter(announcement_reads::Column::AnnouncementId.eq(id))
+            .find_also_related(Users)
+            .order_by_asc(announcement_reads::Column::ReadAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(receipt, user)| {
+                user.map(|user| ReceiptResponse {
+                    user_id: user.id,
+                    full_name: user.full_name,
+                    role: user.role,
+                    read_at: format_time(receipt.read_at),
+                })
+            })
+            .collect();
+
+        Ok(Some(ReceiptsResponse {
+            announcement_id: id,
+            audience,
+            read: readers.len(),
+            readers,
+        }))
+    }
+
+    // Everyone a notice is addressed to: active users of its school it reaches
+    pub async fn audience(db: &DatabaseConnection, announcement: &announcements::Model) -> Result<Vec<i32>, DbErr> {
+        let members = user_repository::live(announcement.tenant_id)
+            .filter(users::Column::IsActive.eq(true))
+            .find_also_related(ClassMemberships)
+            .all(db)
+            .await?;
+        Ok(members
+            .into_iter()
+            .filter(|(user, membership)| {
+                let reader = Reader {
+                    user_id: user.id,
+                    role: user.role.clone(),
+                    grade: membership.as_ref().map(|membership| membership.grade.clone()),
+                    section: membership.as_ref().and_then(|membership| membership.section.clone()),
+                };
+                reaches(announcement, &reader)
+            })
+            .map(|(user, _)| user.id)
+            .collect())
+    }
+
+    // Mark every live notice, in any school, that has not been announced as
+    // announced, and return them
+    pub async fn claim_due(db: &DatabaseConnection, now: NaiveDateTime) -> Result<Vec<announcements::Model>, DbErr> {
+        Announcements::update_many()
+            .col_expr(announcements::Column::NotifiedAt, Expr::value(now))
+            .filter(announcements::Column::NotifiedAt.is_null())
+            .filter(live(now))
+            .exec_with_returning(db)
+            .await
+    }
+
+    pub async fn add_attachment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        announcement_id: i32,
+        upload: AttachmentUpload,
+        context: &AuditContext,
+    ) -> Result<announcement_attachments::Model, AnnouncementError> {
+        check_attachment(&upload.content_type, upload.content.len())?;
+
+        let txn = db.begin().await?;
+        Announcements::scoped(tenant_id)
+            .filter(announcements::Column::Id.eq(announcement_id))
+            .one(&txn)
+            .await?
+            .ok_or(AnnouncementError::NotFound)?;
+        let attachment = announcement_attachments::ActiveModel {
+            announcement_id: Set(announcement_id),
+            filename: Set(safe_filename(&upload.filename)),
+            content_type: Set(upload.content_type),
+            length: Set(upload.content.len() as i64),
+            content: Set(upload.content),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&AttachmentResponse::from(attachment.clone()));
+        AuditRepository::record(&txn, context, "create", "announcement_attachments", attachment.id, None, snapshot)
+            .await?;
+        txn.commit().await?;
+        Ok(attachment)
+    }
+
+    pub async fn remove_attachment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some(attachment) = AnnouncementAttachments::find_by_id(id)
+            .inner_join(Announcements)
+            .filter(announcements::Column::TenantId.eq(tenant_id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let snapshot = audit::snapshot(&AttachmentResponse::from(attachment.clone()));
+        attachment.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "announcement_attachments", id, snapshot, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    // An attachment, if the reader may see the notice it belongs to
+    pub async fn attachment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        reader: &Reader,
+    ) -> Result<Option<announcement_attachments::Model>, DbErr> {
+        let Some((attachment, Some(announcement))) = AnnouncementAttachments::find_by_id(id)
+            .find_also_related(Announcements)
+            .filter(announcements::Column::TenantId.eq(tenant_id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        if !visible_to(&announcement, reader, Utc::now().naive_utc()) {
+            return Ok(None);
+        }
+        Ok(Some(attachment))
+    }
+
+    // Place a user in a grade and section, replacing any earlier placement
+    pub async fn set_class(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        user_id: i32,
+        request: &ClassRequest,
+        context: &AuditContext,
+    ) -> Result<class_memberships::Model, AnnouncementError> {
+        let txn = db.begin().await?;
+        user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(user_id))
+            .one(&txn)
+            .await?
+            .ok_or(AnnouncementError::UserNotFound)?;
+        let existing = ClassMemberships::find_by_id(user_id).one(&txn).await?;
+        let before = existing
+            .clone()
+            .and_then(|membership| audit::snapshot(&ClassResponse::from(membership)));
+
+        let mut membership = class_memberships::ActiveModel {
+            user_id: Set(user_id),
+            grade: Set(request.grade.trim().to_string()),
+            section: Set(trimmed(&request.section)),
+            updated_at: Set(Utc::now().naive_utc()),
+        };
+        let membership = match existing {
+            Some(_) => {
+                membership.user_id = Unchanged(user_id);
+                membership.update(&txn).await?
+            }
+            None => membership.insert(&txn).await?,
+        };
+
+        let after = audit::snapshot(&ClassResponse::from(membership.clone()));
+        AuditRepository::record(&txn, context, "set_class", "users", user_id, before, after).await?;
+        txn.commit().await?;
+        Ok(membership)
+    }
+
+    pub async fn clear_class(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        user_id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some((membership, Some(_))) = ClassMemberships::find_by_id(user_id)
+            .find_also_related(Users)
+            .filter(users::Column::TenantId.eq(tenant_id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let snapshot = audit::snapshot(&ClassResponse::from(membership.clone()));
+        membership.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "clear_class", "users", user_id, snapshot, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+}
+
+fn check_request(request: &AnnouncementRequest, publish_at: NaiveDateTime) -> Result<(), AnnouncementError> {
+    check_audience(&request.audience, request.grade.as_deref(), request.section.as_deref())?;
+    check_schedule(publish_at, request.expires_at)
+}
+
+// Published and not yet expired, as a query condition
+fn live(now: NaiveDateTime) -> Condition {
+    Condition::all()
+        .add(announcements::Column::PublishAt.lte(now))
+        .add(
+            Condition::any()
+                .add(announcements::Column::ExpiresAt.is_null())
+                .add(announcements::Column::ExpiresAt.gt(now)),
+        )
+}
+
+// A notice the reader may open
+async fn visible<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    id: i32,
+    reader: &Reader,
+) -> Result<Option<announcements::Model>, DbErr> {
+    let announcement = Announcements::scoped(tenant_id)
+        .filter(announcements::Column::Id.eq(id))
+        .one(conn)
+        .await?;
+    Ok(announcement.filter(|announcement| visible_to(announcement, reader, Utc::now().naive_utc())))
+}
+
+// Notices with their attachment metadata and the reader's receipts
+async fn notices<C: ConnectionTrait>(
+    conn: &C,
+    user_id: i32,
+    found: Vec<announcements::Model>,
+) -> Result<Vec<NoticeResponse>, DbErr> {
+    if found.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids: Vec<i32> = found.iter().map(|announcement| announcement.id).collect();
+
+    let mut attachments: HashMap<i32, Vec<AttachmentResponse>> = HashMap::new();
+    let rows = AnnouncementAttachments::find()
+        .select_only()
+        .columns([
+            announcement_attachments::Column::Id,
+            announcement_attachments::Column::AnnouncementId,
+            announcement_attachments::Column::Filename,
+            announcement_attachments::Column::ContentType,
+            announcement_attachments::Column::Length,
+            announcement_attachments::Column::CreatedAt,
+        ])
+        .filter(announcement_attachments::Column::AnnouncementId.is_in(ids.iter().copied()))
+        .order_by_asc(announcement_attachments::Column::Id)
+        .into_tuple::<(i32, i32, String, String, i64, NaiveDateTime)>()
+        .all(conn)
+        .await?;
+    for (id, announcement_id, filename, content_type, length, created_at) in rows {
+        attachments.entry(announcement_id).or_default().push(AttachmentResponse {
+            id,
+            announcement_id,
+            filename,
+            content_type,
+            length,
+            created_at: format_time(created_at),
+        });
+    }
+
+    let reads: HashMap<i32, NaiveDateTime> = AnnouncementReads::find()
+        .filter(announcement_reads::Column::UserId.eq(user_id))
+        .filter(announcement_reads::Column::AnnouncementId.is_in(ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|receipt| (receipt.announcement_id, receipt.read_at))
+        .collect();
+
+    Ok(found
+        .into_iter()
+        .map(|announcement| NoticeResponse {
+            attachments: attachments.remove(&announcement.id).unwrap_or_default(),
+            read_at: reads.get(&announcement.id).copied().map(format_time),
+            announcement: announcement.into(),
+        })
+        .collect())
+}
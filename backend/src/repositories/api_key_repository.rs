@@ -0,0 +1,134 @@
+use chrono::{Duration, Utc};
+use sea_orm::{sea_query::Expr, *};
+
+use crate::application::api_keys::NewApiKey;
+use crate::application::audit::{self, AuditContext};
+use crate::dto::api_key::{ApiKeyResponse, CreateApiKeyRequest};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::user_repository;
+
+use crate::entities::{api_keys, prelude::*, users};
+
+// last_used_at is only written when it is older than this, so busy keys do not
+// turn every request into a database write
+const TOUCH_INTERVAL_SECONDS: i64 = 60;
+
+pub struct ApiKeyRepository;
+
+impl ApiKeyRepository {
+    // Store a generated key for `user_id`; only its prefix and hash are kept
+    pub async fn create(
+        db: &DatabaseConnection,
+        user_id: i32,
+        created_by: i32,
+        data: &CreateApiKeyRequest,
+        new_key: &NewApiKey,
+        context: &AuditContext,
+    ) -> Result<api_keys::Model, DbErr> {
+        let txn = db.begin().await?;
+        let expires_at = data
+            .expires_in_days
+            .map(|days| Utc::now().naive_utc() + Duration::days(days));
+        let key = api_keys::ActiveModel {
+            user_id: Set(user_id),
+            name: Set(data.name.clone()),
+            prefix: Set(new_key.prefix.clone()),
+            key_hash: Set(new_key.hash.clone()),
+            scopes: Set(serde_json::json!(data.scopes)),
+            expires_at: Set(expires_at),
+            created_by: Set(Some(created_by)),
+            ..Default::default()
+        };
+        let key = key.insert(&txn).await?;
+        AuditRepository::record(&txn, context, "create", "api_keys", key.id, None, audit::snapshot(&ApiKeyResponse::from(key.clone()))).await?;
+        txn.commit().await?;
+        Ok(key)
+    }
+
+    // Keys belonging to a user, newest first, revoked ones included
+    pub async fn list_for_user(db: &DatabaseConnection, user_id: i32) -> Result<Vec<api_keys::Model>, DbErr> {
+        ApiKeys::find()
+            .filter(api_keys::Column::UserId.eq(user_id))
+            .order_by_desc(api_keys::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    // A key whose owner belongs to the school
+    pub async fn find_by_id(db: &DatabaseConnection, tenant_id: i32, id: i32) -> Result<Option<api_keys::Model>, DbErr> {
+        ApiKeys::find_by_id(id)
+            .join(JoinType::InnerJoin, api_keys::Relation::Users1.def())
+            .filter(users::Column::TenantId.eq(tenant_id))
+            .one(db)
+            .await
+    }
+
+    // The school of a key's owner, so a request with only a key can be placed
+    pub async fn tenant_of(db: &DatabaseConnection, prefix: &str) -> Result<Option<i32>, DbErr> {
+        Users::find()
+            .select_only()
+            .column(users::Column::TenantId)
+            .join(JoinType::InnerJoin, api_keys::Relation::Users1.def().rev())
+            .filter(api_keys::Column::Prefix.eq(prefix))
+            .into_tuple()
+            .one(db)
+            .await
+    }
+
+    // A key with its owner, for authenticating a request to the owner's school
+    pub async fn find_by_prefix(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        prefix: &str,
+    ) -> Result<Option<(api_keys::Model, users::Model)>, DbErr> {
+        let Some(key) = ApiKeys::find()
+            .filter(api_keys::Column::Prefix.eq(prefix))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let user = user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(key.user_id))
+            .one(db)
+            .await?;
+        Ok(user.map(|user| (key, user)))
+    }
+
+    // Revoked keys stay listed so their owners can see what happened to them
+    pub async fn revoke(db: &DatabaseConnection, id: i32, context: &AuditContext) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some(key) = ApiKeys::find_by_id(id)
+            .filter(api_keys::Column::RevokedAt.is_null())
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let before = audit::snapshot(&ApiKeyResponse::from(key.clone()));
+        let mut key: api_keys::ActiveModel = key.into();
+        key.revoked_at = Set(Some(Utc::now().naive_utc()));
+        let key = key.update(&txn).await?;
+        AuditRepository::record(&txn, context, "revoke", "api_keys", id, before, audit::snapshot(&ApiKeyResponse::from(key))).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn touch(db: &DatabaseConnection, key: &api_keys::Model) -> Result<(), DbErr> {
+        let now = Utc::now().naive_utc();
+        if key
+            .last_used_at
+            .is_some_and(|used| now - used < Duration::seconds(TOUCH_INTERVAL_SECONDS))
+        {
+            return Ok(());
+        }
+        ApiKeys::update_many()
+            .col_expr(api_keys::Column::LastUsedAt, Expr::value(now))
+            .filter(api_keys::Column::Id.eq(key.id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use futures::{Stream, TryStreamExt};
+use serde_json::Value;
+use sea_orm::{sea_query::Expr, *};
+
+use crate::application::audit::{self, AuditContext, ChainHeads, GENESIS_HASH};
+use crate::application::tenancy::DEFAULT_TENANT_ID;
+use crate::dto::audit::AuditEntryResponse;
+use crate::entities::{audit_log, prelude::*};
+
+// Entries checked per query when verifying the chain
+const VERIFY_BATCH: u64 = 1000;
+
+// Filters of GET /audit
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub entity: Option<String>,
+    pub entity_id: Option<String>,
+}
+
+pub struct AuditRepository;
+
+// A school's entries matching `filter`. Entries from before tenancy belong
+// to the default school.
+fn filtered(tenant_id: i32, filter: &AuditFilter) -> Select<AuditLog> {
+    let mut school = Condition::any().add(audit_log::Column::TenantId.eq(tenant_id));
+    if tenant_id == DEFAULT_TENANT_ID {
+        school = school.add(audit_log::Column::TenantId.is_null());
+    }
+    let mut query = AuditLog::find().filter(school);
+    if let Some(actor_id) = filter.actor_id {
+        query = query.filter(audit_log::Column::ActorId.eq(actor_id));
+    }
+    if let Some(action) = &filter.action {
+        query = query.filter(audit_log::Column::Action.eq(action.as_str()));
+    }
+    if let Some(entity) = &filter.entity {
+        query = query.filter(audit_log::Column::Entity.eq(entity.as_str()));
+    }
+    if let Some(entity_id) = &filter.entity_id {
+        query = query.filter(audit_log::Column::EntityId.eq(entity_id.as_str()));
+    }
+    query
+}
+
+impl AuditRepository {
+    // Append an entry. Call it with the transaction that makes the change, so
+    // the change and its record commit (or roll back) together.
+    pub async fn record<C: ConnectionTrait>(
+        conn: &C,
+        context: &AuditContext,
+        action: &str,
+        entity: &str,
+        entity_id: impl ToString,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Result<(), DbErr> {
+        // Entries are chained per school, so one school's appends must not
+        // interleave; the lock is held until the transaction ends
+        let chain = audit::chain_of(context.tenant_id);
+        if conn.get_database_backend() == DbBackend::Postgres {
+            conn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT pg_advisory_xact_lock(hashtext('audit_log'), $1)",
+                [chain.into()],
+            ))
+            .await?;
+        }
+        let prev_hash = filtered(chain, &AuditFilter::default())
+            .order_by_desc(audit_log::Column::Id)
+            .one(conn)
+            .await?
+            .map(|last| last.hash)
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+        let (before, after) = audit::changes(before, after);
+        let mut entry = audit_log::Model {
+            id: 0,
+            actor_id: context.actor_id,
+            actor_email: context.actor_email.clone(),
+            impersonator_id: context.impersonator_id,
+            action: action.to_string(),
+            entity: entity.to_string(),
+            entity_id: entity_id.to_string(),
+            snapshot_hash: Some(audit::snapshot_hash(&before, &after)),
+            before,
+            after,
+            ip_address: context.ip_address.clone(),
+            request_id: context.request_id.clone(),
+            created_at: audit::now(),
+            prev_hash,
+            hash: String::new(),
+            tenant_id: context.tenant_id,
+            redacted_at: None,
+        };
+        entry.hash = audit::entry_hash(&entry);
+
+        let mut active: audit_log::ActiveModel = entry.into();
+        active.id = NotSet;
+        active.insert(conn).await?;
+        Ok(())
+    }
+
+    // A school's entries, newest first, with the total number of matches
+    pub async fn find(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        filter: &AuditFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<audit_log::Model>, u64), DbErr> {
+        let query = filtered(tenant_id, filter);
+        let total = query.clone().count(db).await?;
+        let entries = query
+            .order_by_desc(audit_log::Column::Id)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await?;
+        Ok((entries, total))
+    }
+
+    // Every matching entry, newest first, row by row (used by exports)
+    pub fn stream(
+        db: DatabaseConnection,
+        tenant_id: i32,
+        filter: AuditFilter,
+    ) -> impl Stream<Item = Result<AuditEntryResponse, DbErr>> + Send + 'static {
+        async_stream::try_stream! {
+            let mut rows = filtered(tenant_id, &filter)
+                .order_by_desc(audit_log::Column::Id)
+                .stream(&db)
+                .await?;
+            while let Some(entry) = rows.try_next().await? {
+                yield AuditEntryResponse::from(entry);
+            }
+        }
+    }
+
+    // Blank the before/after snapshots of a school's entries about the given
+    // records (entity name and ids), leaving who did what and when. Returns
+    // how many entries were redacted.
+    pub async fn redact<C: ConnectionTrait>(
+        conn: &C,
+        tenant_id: i32,
+        records: &[(&str, Vec<i32>)],
+    ) -> Result<u64, DbErr> {
+        let mut about = Condition::any();
+        for (entity, ids) in records.iter().filter(|(_, ids)| !ids.is_empty()) {
+            about = about.add(
+                Condition::all()
+                    .add(audit_log::Column::Entity.eq(*entity))
+                    .add(audit_log::Column::EntityId.is_in(ids.iter().map(i32::to_string))),
+            );
+        }
+        if about.is_empty() {
+            return Ok(0);
+        }
+        let result = AuditLog::update_many()
+            .col_expr(audit_log::Column::Before, Expr::value(Option::<Value>::None))
+            .col_expr(audit_log::Column::After, Expr::value(Option::<Value>::None))
+            .col_expr(audit_log::Column::RedactedAt, Expr::value(audit::now()))
+            .filter(audit_log::Column::TenantId.eq(tenant_id))
+            .filter(audit_log::Column::RedactedAt.is_null())
+            .filter(about)
+            .exec(conn)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    // Walk every school's chain. Returns how many entries were checked and
+    // the id of the first one that does not verify.
+    pub async fn verify(db: &DatabaseConnection) -> Result<(u64, Option<i32>), DbErr> {
+        let mut heads = ChainHeads::default();
+        let mut checked = 0;
+        let mut last_id = 0;
+
+        loop {
+            let batch = AuditLog::find()
+                .filter(audit_log::Column::Id.gt(last_id))
+                .order_by_asc(audit_log::Column::Id)
+                .limit(VERIFY_BATCH)
+                .all(db)
+                .await?;
+            let Some(last) = batch.last() else {
+                return Ok((checked, None));
+            };
+            last_id = last.id;
+
+            if let Err(id) = audit::first_broken(&batch, &mut heads) {
+                return Ok((checked, Some(id)));
+            }
+            checked += batch.len() as u64;
+        }
+    }
+}
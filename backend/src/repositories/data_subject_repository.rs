@@ -0,0 +1,868 @@
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, *};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::auth;
+use crate::application::data_subject::{self, AUTH_SOURCE_ERASED, ERASED_NAME, ERASED_TEXT, STATUS_COMPLETED};
+use crate::application::encryption::Keyring;
+use crate::application::library::{COPY_AVAILABLE, FINE_OWED, HOLD_READY};
+use crate::application::xapi::AttachmentPart;
+use crate::dto::admissions::{ApplicationResponse, DocumentResponse};
+use crate::dto::email::OutboxEmailResponse;
+use crate::dto::fees::{InvoiceResponse, PaymentResponse};
+use crate::dto::hostel::{AllocationResponse, StayLogResponse};
+use crate::dto::library::{HoldResponse, LoanResponse};
+use crate::dto::lti::UserLinkResponse;
+use crate::dto::messaging::{AttachmentResponse, GuardianLinkResponse};
+use crate::dto::payments::RefundResponse;
+use crate::dto::transport::AssignmentResponse;
+use crate::entities::{
+    admission_applications, admission_documents, admission_letters, admission_reviews, announcement_reads, api_keys,
+    audit_log, email_outbox, erasure_request_events, erasure_requests, guardian_links, hostel_allocations,
+    hostel_stay_logs, impersonation_sessions, invoices, library_copies, library_holds, library_loans, lti_launches,
+    lti_user_links, message_attachments, message_participants, messages, password_history, password_reset_tokens,
+    payments, prelude::*, refunds, transport_assignments, user_recovery_codes, users, xapi_statements,
+};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::profile_repository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::xapi_repository::XapiRepository;
+
+pub struct DataSubjectRepository;
+
+// One export section: each record as its API response
+fn section<M, T: Serialize>(rows: Vec<M>, response: impl Fn(M) -> T) -> Value {
+    Value::Array(
+        rows.into_iter()
+            .map(|row| audit::snapshot(&response(row)).unwrap_or(Value::Null))
+            .collect(),
+    )
+}
+
+// An uploaded file, shipped alongside the export under its hash
+fn file(content_type: &str, content: &[u8]) -> AttachmentPart {
+    AttachmentPart {
+        sha2: format!("{:x}", Sha256::digest(content)),
+        content_type: content_type.to_string(),
+        content: content.to_vec(),
+    }
+}
+
+impl DataSubjectRepository {
+    // Everything rsEdu holds about a user, section by section, plus the xAPI
+    // attachments their statements reference and the admission documents and
+    // message attachments they uploaded. Trashed users are included.
+    // Secrets (password hashes, TOTP secrets, key hashes) are left out;
+    // encrypted profile fields are exported decrypted.
+    pub async fn collect(
+        db: &DatabaseConnection,
+        keyring: &Keyring,
+        tenant_id: i32,
+        user_id: i32,
+    ) -> Result<Option<(Map<String, Value>, Vec<AttachmentPart>)>, DbErr> {
+        let Some(user) = Users::scoped(tenant_id)
+            .filter(users::Column::Id.eq(user_id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let mut data = Map::new();
+
+        data.insert(
+            "user".to_string(),
+            json!({
+                "id": user.id,
+                "email": user.email,
+                "full_name": user.full_name,
+                "role": user.role,
+                "is_active": user.is_active,
+                "auth_source": user.auth_source,
+                "created_at": user.created_at,
+                "updated_at": user.updated_at,
+                "deleted_at": user.deleted_at,
+            }),
+        );
+
+        if let Some(profile) = UserProfiles::find_by_id(user_id).one(db).await? {
+            let open = |column, value| {
+                keyring
+                    .open_opt(value, profile_repository::field(column, user_id))
+                    .map_err(|e| DbErr::Custom(e.to_string()))
+            };
+            data.insert(
+                "profile".to_string(),
+                json!({
+                    "national_id": open("national_id", profile.national_id.as_ref())?,
+                    "address": open("address", profile.address.as_ref())?,
+                    "medical_notes": open("medical_notes", profile.medical_notes.as_ref())?,
+                    "updated_at": profile.updated_at,
+                }),
+            );
+        }
+
+        let totp = UserTotp::find_by_id(user_id).one(db).await?;
+        let recovery_codes = UserRecoveryCodes::find()
+            .filter(user_recovery_codes::Column::UserId.eq(user_id))
+            .filter(user_recovery_codes::Column::UsedAt.is_null())
+            .count(db)
+            .await?;
+        data.insert(
+            "two_factor".to_string(),
+            json!({
+                "enabled": totp.as_ref().is_some_and(|totp| totp.confirmed_at.is_some()),
+                "enrolled_at": totp.and_then(|totp| totp.confirmed_at),
+                "recovery_codes_remaining": recovery_codes,
+            }),
+        );
+
+        let password_changes = PasswordHistory::find()
+            .filter(password_history::Column::UserId.eq(user_id))
+            .order_by_asc(password_history::Column::CreatedAt)
+            .all(db)
+            .await?;
+        data.insert(
+            "password_changes".to_string(),
+            Value::Array(password_changes.into_iter().map(|row| json!(row.created_at)).collect()),
+        );
+
+        let reset_requests = PasswordResetTokens::find()
+            .filter(password_reset_tokens::Column::UserId.eq(user_id))
+            .order_by_asc(password_reset_tokens::Column::CreatedAt)
+            .all(db)
+            .await?;
+        data.insert(
+            "password_reset_requests".to_string(),
+            Value::Array(
+                reset_requests
+                    .into_iter()
+                    .map(|row| json!({ "created_at": row.created_at, "used_at": row.used_at }))
+                    .collect(),
+            ),
+        );
+
+        let keys = ApiKeys::find()
+            .filter(api_keys::Column::UserId.eq(user_id))
+            .order_by_asc(api_keys::Column::Id)
+            .all(db)
+            .await?;
+        data.insert(
+            "api_keys".to_string(),
+            Value::Array(
+                keys.into_iter()
+                    .map(|key| {
+                        json!({
+                            "name": key.name,
+                            "prefix": key.prefix,
+                            "scopes": key.scopes,
+                            "created_at": key.created_at,
+                            "last_used_at": key.last_used_at,
+                            "expires_at": key.expires_at,
+                            "revoked_at": key.revoked_at,
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+
+        let launches = LtiLaunches::find()
+            .filter(lti_launches::Column::UserId.eq(user_id))
+            .order_by_asc(lti_launches::Column::CreatedAt)
+            .all(db)
+            .await?;
+        data.insert(
+            "lti_launches".to_string(),
+            Value::Array(
+                launches
+                    .into_iter()
+                    .map(|launch| {
+                        json!({
+                            "id": launch.id,
+                            "platform_id": launch.platform_id,
+                            "message_type": launch.message_type,
+                            "claims": launch.claims,
+                            "created_at": launch.created_at,
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+
+        let statements = XapiStatements::find()
+            .filter(xapi_statements::Column::UserId.eq(user_id))
+            .order_by_asc(xapi_statements::Column::Stored)
+            .all(db)
+            .await?;
+        let hashes: Vec<String> = statements
+            .iter()
+            .filter_map(|row| row.statement.get("attachments").and_then(Value::as_array))
+            .flatten()
+            .filter_map(|attachment| attachment.get("sha2").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+        data.insert(
+            "xapi_statements".to_string(),
+            Value::Array(statements.into_iter().map(|row| row.statement).collect()),
+        );
+
+        let guardian_links = GuardianLinks::find()
+            .filter(
+                Condition::any()
+                    .add(guardian_links::Column::GuardianId.eq(user_id))
+                    .add(guardian_links::Column::StudentId.eq(user_id)),
+            )
+            .order_by_asc(guardian_links::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("guardian_links".to_string(), section(guardian_links, GuardianLinkResponse::from));
+
+        let lti_links = LtiUserLinks::find()
+            .filter(lti_user_links::Column::UserId.eq(user_id))
+            .order_by_asc(lti_user_links::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("lti_user_links".to_string(), section(lti_links, UserLinkResponse::from));
+
+        let invoices = Invoices::scoped(tenant_id)
+            .filter(invoices::Column::StudentId.eq(user_id))
+            .order_by_asc(invoices::Column::Id)
+            .all(db)
+            .await?;
+        let payments = Payments::scoped(tenant_id)
+            .filter(payments::Column::InvoiceId.is_in(invoices.iter().map(|invoice| invoice.id)))
+            .order_by_asc(payments::Column::Id)
+            .all(db)
+            .await?;
+        let refunds = Refunds::scoped(tenant_id)
+            .filter(refunds::Column::PaymentId.is_in(payments.iter().map(|payment| payment.id)))
+            .order_by_asc(refunds::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("invoices".to_string(), section(invoices, InvoiceResponse::from));
+        data.insert("payments".to_string(), section(payments, PaymentResponse::from));
+        data.insert("refunds".to_string(), section(refunds, RefundResponse::from));
+
+        let loans = LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::UserId.eq(user_id))
+            .order_by_asc(library_loans::Column::Id)
+            .all(db)
+            .await?;
+        let holds = LibraryHolds::scoped(tenant_id)
+            .filter(library_holds::Column::UserId.eq(user_id))
+            .order_by_asc(library_holds::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("library_loans".to_string(), section(loans, LoanResponse::from));
+        data.insert("library_holds".to_string(), section(holds, HoldResponse::from));
+
+        let assignments = TransportAssignments::scoped(tenant_id)
+            .filter(transport_assignments::Column::StudentId.eq(user_id))
+            .order_by_asc(transport_assignments::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("transport_assignments".to_string(), section(assignments, AssignmentResponse::from));
+
+        let allocations = HostelAllocations::scoped(tenant_id)
+            .filter(hostel_allocations::Column::StudentId.eq(user_id))
+            .order_by_asc(hostel_allocations::Column::Id)
+            .all(db)
+            .await?;
+        let stay_logs = HostelStayLogs::find()
+            .filter(hostel_stay_logs::Column::AllocationId.is_in(allocations.iter().map(|allocation| allocation.id)))
+            .order_by_asc(hostel_stay_logs::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("hostel_allocations".to_string(), section(allocations, AllocationResponse::from));
+        data.insert("hostel_stay_logs".to_string(), section(stay_logs, StayLogResponse::from));
+
+        let applications = AdmissionApplications::scoped(tenant_id)
+            .filter(admission_applications::Column::StudentId.eq(user_id))
+            .order_by_asc(admission_applications::Column::Id)
+            .all(db)
+            .await?;
+        let documents = AdmissionDocuments::find()
+            .filter(admission_documents::Column::ApplicationId.is_in(applications.iter().map(|application| application.id)))
+            .order_by_asc(admission_documents::Column::Id)
+            .all(db)
+            .await?;
+        let mut files: Vec<AttachmentPart> = documents.iter().map(|document| file(&document.content_type, &document.content)).collect();
+        data.insert("admission_applications".to_string(), section(applications, ApplicationResponse::from));
+        data.insert(
+            "admission_documents".to_string(),
+            Value::Array(
+                documents
+                    .into_iter()
+                    .zip(&files)
+                    .map(|(document, file)| json!({ "sha2": file.sha2, "document": DocumentResponse::from(document) }))
+                    .collect(),
+            ),
+        );
+
+        let threads = MessageParticipants::find()
+            .filter(message_participants::Column::UserId.eq(user_id))
+            .order_by_asc(message_participants::Column::ThreadId)
+            .all(db)
+            .await?;
+        data.insert(
+            "message_threads".to_string(),
+            Value::Array(
+                threads
+                    .into_iter()
+                    .map(|participant| {
+                        json!({
+                            "thread_id": participant.thread_id,
+                            "joined_at": participant.joined_at,
+                            "last_read_at": participant.last_read_at,
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+        let messages = Messages::find()
+            .filter(messages::Column::SenderId.eq(user_id))
+            .order_by_asc(messages::Column::Id)
+            .all(db)
+            .await?;
+        let message_files = MessageAttachments::find()
+            .filter(message_attachments::Column::MessageId.is_in(messages.iter().map(|message| message.id)))
+            .order_by_asc(message_attachments::Column::Id)
+            .all(db)
+            .await?;
+        data.insert(
+            "messages_sent".to_string(),
+            Value::Array(
+                messages
+                    .into_iter()
+                    .map(|message| {
+                        json!({
+                            "id": message.id,
+                            "thread_id": message.thread_id,
+                            "body": message.body,
+                            "hidden_at": message.hidden_at,
+                            "created_at": message.created_at,
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+        data.insert(
+            "message_attachments".to_string(),
+            Value::Array(
+                message_files
+                    .into_iter()
+                    .map(|attachment| {
+                        let part = file(&attachment.content_type, &attachment.content);
+                        let entry = json!({ "sha2": part.sha2, "attachment": AttachmentResponse::from(attachment) });
+                        files.push(part);
+                        entry
+                    })
+                    .collect(),
+            ),
+        );
+
+        let reads = AnnouncementReads::find()
+            .filter(announcement_reads::Column::UserId.eq(user_id))
+            .order_by_asc(announcement_reads::Column::Id)
+            .all(db)
+            .await?;
+        data.insert(
+            "announcements_read".to_string(),
+            Value::Array(
+                reads
+                    .into_iter()
+                    .map(|read| json!({ "announcement_id": read.announcement_id, "read_at": read.read_at }))
+                    .collect(),
+            ),
+        );
+
+        let emails = EmailOutbox::scoped(tenant_id)
+            .filter(email_outbox::Column::ToAddress.eq(user.email.as_str()))
+            .order_by_asc(email_outbox::Column::Id)
+            .all(db)
+            .await?;
+        data.insert("emails".to_string(), section(emails, OutboxEmailResponse::from));
+
+        let sessions = ImpersonationSessions::find()
+            .filter(impersonation_sessions::Column::TargetUserId.eq(user_id))
+            .order_by_asc(impersonation_sessions::Column::StartedAt)
+            .all(db)
+            .await?;
+        data.insert(
+            "viewed_by_support".to_string(),
+            Value::Array(
+                sessions
+                    .into_iter()
+                    .map(|session| {
+                        json!({
+                            "admin_id": session.admin_id,
+                            "reason": session.reason,
+                            "started_at": session.started_at,
+                            "ended_at": session.ended_at,
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+
+        let changes = AuditLog::find()
+            .filter(audit_log::Column::TenantId.eq(tenant_id))
+            .filter(
+                Condition::any()
+                    .add(audit_log::Column::ActorId.eq(user_id))
+                    .add(
+                        Condition::all()
+                            .add(audit_log::Column::Entity.eq("users"))
+                            .add(audit_log::Column::EntityId.eq(user_id.to_string())),
+                    ),
+            )
+            .order_by_asc(audit_log::Column::Id)
+            .all(db)
+            .await?;
+        data.insert(
+            "audit_log".to_string(),
+            Value::Array(
+                changes
+                    .into_iter()
+                    .map(|entry| {
+                        json!({
+                            "actor_id": entry.actor_id,
+                            "action": entry.action,
+                            "entity": entry.entity,
+                            "entity_id": entry.entity_id,
+                            "before": entry.before,
+                            "after": entry.after,
+                            "ip_address": entry.ip_address,
+                            "created_at": entry.created_at,
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+
+        let mut attachments = XapiRepository::find_attachments(db, tenant_id, hashes).await?;
+        attachments.extend(files);
+        Ok(Some((data, attachments)))
+    }
+
+    // Anonymize a user and finish the request, in one transaction. Records
+    // kept for statistics (xAPI statements, LTI launches) lose what identifies
+    // the person; credentials, keys and the sensitive profile are deleted.
+    // Fees, messages and admissions keep their rows but lose personal text.
+    // The audit log keeps its entries as the legal record, with the
+    // before/after snapshots of every touched record redacted. The
+    // safeguarding message archive is kept as it is.
+    pub async fn erase(
+        db: &DatabaseConnection,
+        request: &erasure_requests::Model,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let user_id = request.user_id;
+        let txn = db.begin().await?;
+        let Some(user) = Users::scoped(request.tenant_id)
+            .filter(users::Column::Id.eq(user_id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let password_hash = auth::hash_password(&uuid::Uuid::new_v4().to_string())
+            .map_err(|_| DbErr::Custom("Failed to hash password".to_string()))?;
+        let old_address = user.email.clone();
+        let mut user: users::ActiveModel = user.into();
+        user.email = Set(data_subject::erased_email(user_id));
+        user.full_name = Set(ERASED_NAME.to_string());
+        user.password_hash = Set(password_hash);
+        user.is_active = Set(false);
+        user.auth_source = Set(AUTH_SOURCE_ERASED.to_string());
+        user.updated_at = Set(Utc::now().naive_utc());
+        user.update(&txn).await?;
+
+        PasswordHistory::delete_many()
+            .filter(password_history::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        PasswordResetTokens::delete_many()
+            .filter(password_reset_tokens::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        UserRecoveryCodes::delete_many()
+            .filter(user_recovery_codes::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        UserTotp::delete_by_id(user_id).exec(&txn).await?;
+        UserProfiles::delete_by_id(user_id).exec(&txn).await?;
+        ApiKeys::delete_many()
+            .filter(api_keys::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+
+        let launches = LtiLaunches::find()
+            .filter(lti_launches::Column::UserId.eq(user_id))
+            .all(&txn)
+            .await?;
+        for launch in launches {
+            let mut claims = launch.claims.clone();
+            data_subject::anonymize_launch_claims(&mut claims);
+            let mut launch: lti_launches::ActiveModel = launch.into();
+            launch.subject = Set(format!("erased-{}", user_id));
+            launch.claims = Set(claims);
+            launch.update(&txn).await?;
+        }
+
+        let erased_address = data_subject::erased_email(user_id);
+        let tenant_id = request.tenant_id;
+
+        let links = GuardianLinks::find()
+            .filter(
+                Condition::any()
+                    .add(guardian_links::Column::GuardianId.eq(user_id))
+                    .add(guardian_links::Column::StudentId.eq(user_id)),
+            )
+            .all(&txn)
+            .await?;
+        let link_ids: Vec<i32> = links.iter().map(|link| link.id).collect();
+        GuardianLinks::delete_many()
+            .filter(guardian_links::Column::Id.is_in(link_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        let lti_links = LtiUserLinks::find()
+            .filter(lti_user_links::Column::UserId.eq(user_id))
+            .all(&txn)
+            .await?;
+        let lti_link_ids: Vec<i32> = lti_links.iter().map(|link| link.id).collect();
+        LtiUserLinks::delete_many()
+            .filter(lti_user_links::Column::Id.is_in(lti_link_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        // Invoices and payments are kept for the accounts; refund reasons are free text
+        let invoice_ids: Vec<i32> = Invoices::scoped(tenant_id)
+            .filter(invoices::Column::StudentId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|invoice| invoice.id)
+            .collect();
+        let payment_ids: Vec<i32> = Payments::scoped(tenant_id)
+            .filter(payments::Column::InvoiceId.is_in(invoice_ids.clone()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|payment| payment.id)
+            .collect();
+        let refund_ids: Vec<i32> = Refunds::scoped(tenant_id)
+            .filter(refunds::Column::PaymentId.is_in(payment_ids.clone()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|refund| refund.id)
+            .collect();
+        Refunds::update_many()
+            .col_expr(refunds::Column::Reason, Expr::value(ERASED_TEXT))
+            .filter(refunds::Column::Id.is_in(refund_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        // Copies held for the user go back on the shelf. Loans still out or
+        // with a fine owed stay, so the copy and the debt can be followed up.
+        let holds = LibraryHolds::scoped(tenant_id)
+            .filter(library_holds::Column::UserId.eq(user_id))
+            .all(&txn)
+            .await?;
+        let hold_ids: Vec<i32> = holds.iter().map(|hold| hold.id).collect();
+        let held_copies: Vec<i32> = holds
+            .iter()
+            .filter(|hold| hold.status == HOLD_READY)
+            .filter_map(|hold| hold.copy_id)
+            .collect();
+        LibraryCopies::update_many()
+            .col_expr(library_copies::Column::Status, Expr::value(COPY_AVAILABLE))
+            .filter(library_copies::Column::Id.is_in(held_copies))
+            .exec(&txn)
+            .await?;
+        LibraryHolds::delete_many()
+            .filter(library_holds::Column::Id.is_in(hold_ids.clone()))
+            .exec(&txn)
+            .await?;
+        let loan_ids: Vec<i32> = LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::UserId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|loan| loan.id)
+            .collect();
+        LibraryLoans::delete_many()
+            .filter(library_loans::Column::Id.is_in(loan_ids.clone()))
+            .filter(library_loans::Column::ReturnedAt.is_not_null())
+            .filter(library_loans::Column::FineStatus.ne(FINE_OWED))
+            .exec(&txn)
+            .await?;
+
+        let assignment_ids: Vec<i32> = TransportAssignments::scoped(tenant_id)
+            .filter(transport_assignments::Column::StudentId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|assignment| assignment.id)
+            .collect();
+        TransportAssignments::delete_many()
+            .filter(transport_assignments::Column::Id.is_in(assignment_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        let allocation_ids: Vec<i32> = HostelAllocations::scoped(tenant_id)
+            .filter(hostel_allocations::Column::StudentId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|allocation| allocation.id)
+            .collect();
+        HostelStayLogs::delete_many()
+            .filter(hostel_stay_logs::Column::AllocationId.is_in(allocation_ids.clone()))
+            .exec(&txn)
+            .await?;
+        HostelAllocations::delete_many()
+            .filter(hostel_allocations::Column::Id.is_in(allocation_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        // Applications stay for admission statistics, without the person
+        let application_ids: Vec<i32> = AdmissionApplications::scoped(tenant_id)
+            .filter(admission_applications::Column::StudentId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|application| application.id)
+            .collect();
+        let document_ids: Vec<i32> = AdmissionDocuments::find()
+            .filter(admission_documents::Column::ApplicationId.is_in(application_ids.clone()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|document| document.id)
+            .collect();
+        AdmissionDocuments::delete_many()
+            .filter(admission_documents::Column::Id.is_in(document_ids.clone()))
+            .exec(&txn)
+            .await?;
+        AdmissionLetters::delete_many()
+            .filter(admission_letters::Column::ApplicationId.is_in(application_ids.clone()))
+            .exec(&txn)
+            .await?;
+        AdmissionReviews::update_many()
+            .col_expr(admission_reviews::Column::Comments, Expr::value(Option::<String>::None))
+            .filter(admission_reviews::Column::ApplicationId.is_in(application_ids.clone()))
+            .exec(&txn)
+            .await?;
+        AdmissionApplications::update_many()
+            .col_expr(admission_applications::Column::FullName, Expr::value(ERASED_NAME))
+            .col_expr(admission_applications::Column::GuardianName, Expr::value(ERASED_NAME))
+            .col_expr(admission_applications::Column::GuardianEmail, Expr::value(erased_address.as_str()))
+            .col_expr(admission_applications::Column::DateOfBirth, Expr::value(Option::<chrono::NaiveDate>::None))
+            .col_expr(admission_applications::Column::StudentEmail, Expr::value(Option::<String>::None))
+            .col_expr(admission_applications::Column::GuardianPhone, Expr::value(Option::<String>::None))
+            .col_expr(admission_applications::Column::Notes, Expr::value(Option::<String>::None))
+            .col_expr(admission_applications::Column::UploadTokenHash, Expr::value(Option::<String>::None))
+            .filter(admission_applications::Column::Id.is_in(application_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        // Messages stay in their threads so the conversation still reads;
+        // their text and files go. The archive keeps its copy.
+        let message_ids: Vec<i32> = Messages::find()
+            .filter(messages::Column::SenderId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|message| message.id)
+            .collect();
+        Messages::update_many()
+            .col_expr(messages::Column::Body, Expr::value(ERASED_TEXT))
+            .filter(messages::Column::Id.is_in(message_ids.clone()))
+            .exec(&txn)
+            .await?;
+        MessageAttachments::delete_many()
+            .filter(message_attachments::Column::MessageId.is_in(message_ids))
+            .exec(&txn)
+            .await?;
+        MessageParticipants::delete_many()
+            .filter(message_participants::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        AnnouncementReads::delete_many()
+            .filter(announcement_reads::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+
+        let email_ids: Vec<i32> = EmailOutbox::scoped(tenant_id)
+            .filter(email_outbox::Column::ToAddress.eq(old_address.as_str()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|email| email.id)
+            .collect();
+        EmailOutbox::update_many()
+            .col_expr(email_outbox::Column::ToAddress, Expr::value(erased_address.as_str()))
+            .col_expr(email_outbox::Column::Subject, Expr::value(""))
+            .col_expr(email_outbox::Column::Body, Expr::value(""))
+            .col_expr(email_outbox::Column::Html, Expr::value(Option::<String>::None))
+            .filter(email_outbox::Column::Id.is_in(email_ids.clone()))
+            .exec(&txn)
+            .await?;
+
+        let erased_ifi = format!("mbox:mailto:{}", erased_address);
+        let statements = XapiStatements::find()
+            .filter(xapi_statements::Column::UserId.eq(user_id))
+            .all(&txn)
+            .await?;
+        for statement in statements {
+            let mut body = statement.statement.clone();
+            data_subject::anonymize_statement(&mut body, user_id);
+            let mut statement: xapi_statements::ActiveModel = statement.into();
+            statement.actor_ifi = Set(erased_ifi.clone());
+            statement.statement = Set(body);
+            statement.update(&txn).await?;
+        }
+
+        ErasureRequests::update_many()
+            .col_expr(erasure_requests::Column::Status, Expr::value(STATUS_COMPLETED))
+            .col_expr(erasure_requests::Column::CompletedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(erasure_requests::Column::Id.eq(request.id))
+            .exec(&txn)
+            .await?;
+        Self::add_event(&txn, request.id, context.actor_id, STATUS_COMPLETED, None).await?;
+        AuditRepository::redact(
+            &txn,
+            tenant_id,
+            &[
+                ("users", vec![user_id]),
+                ("guardian_links", link_ids),
+                ("lti_user_links", lti_link_ids),
+                ("invoices", invoice_ids),
+                ("payments", payment_ids),
+                ("refunds", refund_ids),
+                ("library_loans", loan_ids),
+                ("library_holds", hold_ids),
+                ("transport_assignments", assignment_ids),
+                ("hostel_allocations", allocation_ids),
+                ("admission_applications", application_ids),
+                ("admission_documents", document_ids),
+                ("email_outbox", email_ids),
+            ],
+        )
+        .await?;
+        // No before/after: the audit entry must not hold on to the erased data
+        AuditRepository::record(&txn, context, "erase", "users", user_id, None, None).await?;
+
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    // Exports are personal data leaving rsEdu, so each one is audited
+    pub async fn log_export(db: &DatabaseConnection, user_id: i32, context: &AuditContext) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        AuditRepository::record(&txn, context, "export", "users", user_id, None, None).await?;
+        txn.commit().await
+    }
+
+    // None when the school has no such user
+    pub async fn create_request(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        user_id: i32,
+        requested_by: i32,
+        reason: &str,
+    ) -> Result<Option<erasure_requests::Model>, DbErr> {
+        let txn = db.begin().await?;
+        if Users::scoped(tenant_id)
+            .filter(users::Column::Id.eq(user_id))
+            .count(&txn)
+            .await?
+            == 0
+        {
+            return Ok(None);
+        }
+        let request = erasure_requests::ActiveModel {
+            tenant_id: Set(tenant_id),
+            user_id: Set(user_id),
+            requested_by: Set(requested_by),
+            reason: Set(reason.to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        Self::add_event(&txn, request.id, Some(requested_by), "requested", Some(reason)).await?;
+        txn.commit().await?;
+        Ok(Some(request))
+    }
+
+    pub async fn find_request(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<erasure_requests::Model>, DbErr> {
+        ErasureRequests::scoped(tenant_id)
+            .filter(erasure_requests::Column::Id.eq(id))
+            .one(db)
+            .await
+    }
+
+    // A school's requests, newest first
+    pub async fn list_requests(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<erasure_requests::Model>, DbErr> {
+        ErasureRequests::scoped(tenant_id)
+            .order_by_desc(erasure_requests::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    // A request's history, oldest first
+    pub async fn events(db: &DatabaseConnection, request_id: i32) -> Result<Vec<erasure_request_events::Model>, DbErr> {
+        ErasureRequestEvents::find()
+            .filter(erasure_request_events::Column::RequestId.eq(request_id))
+            .order_by_asc(erasure_request_events::Column::Id)
+            .all(db)
+            .await
+    }
+
+    pub async fn add_event<C: ConnectionTrait>(
+        conn: &C,
+        request_id: i32,
+        actor_id: Option<i32>,
+        event: &str,
+        comment: Option<&str>,
+    ) -> Result<(), DbErr> {
+        erasure_request_events::ActiveModel {
+            request_id: Set(request_id),
+            actor_id: Set(actor_id),
+            event: Set(event.to_string()),
+            comment: Set(comment.map(str::to_string)),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+        Ok(())
+    }
+
+    // Close a pending request without erasing anything
+    pub async fn reject(db: &DatabaseConnection, id: i32, actor_id: i32, comment: Option<&str>) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let result = ErasureRequests::update_many()
+            .col_expr(erasure_requests::Column::Status, Expr::value(data_subject::STATUS_REJECTED))
+            .col_expr(erasure_requests::Column::CompletedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(erasure_requests::Column::Id.eq(id))
+            .filter(erasure_requests::Column::Status.eq(data_subject::STATUS_PENDING))
+            .exec(&txn)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+        Self::add_event(&txn, id, Some(actor_id), data_subject::STATUS_REJECTED, comment).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+}
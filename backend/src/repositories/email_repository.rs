@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, LockBehavior, LockType, SimpleExpr};
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::email::{self, EmailError};
+use crate::application::fees;
+use crate::application::messaging::{kind, Kind};
+use crate::application::tenancy;
+use crate::dto::email::OutboxEmailResponse;
+use crate::entities::{email_outbox, guardian_links, invoices, prelude::*, tenants, users};
+use crate::infrastructure::mailer::Email;
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+// Most recent emails listed in the outbox
+const OUTBOX_LIMIT: u64 = 500;
+
+async fn insert<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    template: &str,
+    email: &Email,
+) -> Result<email_outbox::Model, DbErr> {
+    email_outbox::ActiveModel {
+        tenant_id: Set(tenant_id),
+        template: Set(template.to_string()),
+        to_address: Set(email.to.clone()),
+        from_name: Set(email.from_name.clone()),
+        subject: Set(email.subject.clone()),
+        body: Set(email.body.clone()),
+        html: Set(email.html.clone()),
+        status: Set(email::PENDING.to_string()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+}
+
+// Keep `column` unless the email is a sensitive one, whose copy of the
+// secret is no longer needed once it is done with
+fn unless_sensitive(column: email_outbox::Column, blank: SimpleExpr) -> SimpleExpr {
+    Expr::case(email_outbox::Column::Template.is_in(email::SENSITIVE_TEMPLATES.iter().copied()), blank)
+        .finally(Expr::col(column))
+        .into()
+}
+
+// Push due pending emails back by a lease, skipping rows another worker has locked
+fn claim(now: NaiveDateTime, limit: u64) -> UpdateMany<EmailOutbox> {
+    let due = EmailOutbox::find()
+        .select_only()
+        .column(email_outbox::Column::Id)
+        .filter(email_outbox::Column::Status.eq(email::PENDING))
+        .filter(email_outbox::Column::NextAttemptAt.lte(now))
+        .order_by_asc(email_outbox::Column::NextAttemptAt)
+        .limit(limit)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .into_query();
+    EmailOutbox::update_many()
+        .col_expr(email_outbox::Column::NextAttemptAt, Expr::value(now + email::LEASE))
+        .filter(email_outbox::Column::Id.in_subquery(due))
+}
+
+fn sent(id: i32, now: NaiveDateTime) -> UpdateMany<EmailOutbox> {
+    EmailOutbox::update_many()
+        .col_expr(email_outbox::Column::Status, Expr::value(email::SENT))
+        .col_expr(email_outbox::Column::Attempts, Expr::col(email_outbox::Column::Attempts).add(1))
+        .col_expr(email_outbox::Column::LastError, Expr::value(Option::<String>::None))
+        .col_expr(email_outbox::Column::SentAt, Expr::value(now))
+        .col_expr(email_outbox::Column::Body, unless_sensitive(email_outbox::Column::Body, Expr::value("")))
+        .col_expr(
+            email_outbox::Column::Html,
+            unless_sensitive(email_outbox::Column::Html, Expr::value(Option::<String>::None)),
+        )
+        .filter(email_outbox::Column::Id.eq(id))
+}
+
+fn failed(
+    id: i32,
+    attempts: i32,
+    error: &str,
+    retry_at: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> UpdateMany<EmailOutbox> {
+    let update = EmailOutbox::update_many()
+        .col_expr(email_outbox::Column::Attempts, Expr::value(attempts))
+        .col_expr(email_outbox::Column::LastError, Expr::value(error))
+        .filter(email_outbox::Column::Id.eq(id));
+    match retry_at {
+        Some(at) => update
+            .col_expr(email_outbox::Column::Status, Expr::value(email::PENDING))
+            .col_expr(email_outbox::Column::NextAttemptAt, Expr::value(at)),
+        None => update
+            .col_expr(email_outbox::Column::Status, Expr::value(email::FAILED))
+            .col_expr(email_outbox::Column::NextAttemptAt, Expr::value(now))
+            .col_expr(email_outbox::Column::Body, unless_sensitive(email_outbox::Column::Body, Expr::value("")))
+            .col_expr(
+                email_outbox::Column::Html,
+                unless_sensitive(email_outbox::Column::Html, Expr::value(Option::<String>::None)),
+            ),
+    }
+}
+
+// Mark an invoice reminded, unless it already was
+fn claim_invoice(invoice_id: i32, now: NaiveDateTime) -> UpdateMany<Invoices> {
+    Invoices::update_many()
+        .col_expr(invoices::Column::RemindedAt, Expr::value(now))
+        .filter(invoices::Column::Id.eq(invoice_id))
+        .filter(invoices::Column::RemindedAt.is_null())
+}
+
+pub struct EmailRepository;
+
+impl EmailRepository {
+    // Queue emails for the worker to send
+    pub async fn enqueue(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        template: &str,
+        emails: &[Email],
+    ) -> Result<Vec<email_outbox::Model>, DbErr> {
+        let txn = db.begin().await?;
+        let mut queued = Vec::with_capacity(emails.len());
+        for email in emails {
+            queued.push(insert(&txn, tenant_id, template, email).await?);
+        }
+        txn.commit().await?;
+        Ok(queued)
+    }
+
+    // Take up to `limit` pending emails that are due, in any school, and push
+    // them back by a lease so no other worker takes them while they are
+    // sent. Rows another worker is claiming right now are skipped.
+    pub async fn claim_due(
+        db: &DatabaseConnection,
+        now: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<email_outbox::Model>, DbErr> {
+        claim(now, limit).exec_with_returning(db).await
+    }
+
+    // Sensitive emails lose their body once sent
+    pub async fn mark_sent(db: &DatabaseConnection, id: i32) -> Result<(), DbErr> {
+        sent(id, Utc::now().naive_utc()).exec(db).await?;
+        Ok(())
+    }
+
+    // Record a failed attempt; with no `retry_at` the email is given up on
+    // (and a sensitive one loses its body)
+    pub async fn mark_failed(
+        db: &DatabaseConnection,
+        id: i32,
+        attempts: i32,
+        error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> Result<(), DbErr> {
+        failed(id, attempts, error, retry_at, Utc::now().naive_utc()).exec(db).await?;
+        Ok(())
+    }
+
+    pub async fn outbox(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        status: Option<&str>,
+    ) -> Result<Vec<email_outbox::Model>, DbErr> {
+        let mut query = EmailOutbox::scoped(tenant_id);
+        if let Some(status) = status {
+            query = query.filter(email_outbox::Column::Status.eq(status));
+        }
+        query
+            .order_by_desc(email_outbox::Column::CreatedAt)
+            .order_by_desc(email_outbox::Column::Id)
+            .limit(OUTBOX_LIMIT)
+            .all(db)
+            .await
+    }
+
+    // Give a failed email a fresh set of attempts, starting now
+    pub async fn retry(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<email_outbox::Model, EmailError> {
+        let txn = db.begin().await?;
+        let row = EmailOutbox::scoped(tenant_id)
+            .filter(email_outbox::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(EmailError::NotFound)?;
+        if row.status != email::FAILED {
+            return Err(EmailError::NotFailed);
+        }
+        if email::SENSITIVE_TEMPLATES.contains(&row.template.as_str()) {
+            return Err(EmailError::NotRetriable);
+        }
+
+        let before = audit::snapshot(&OutboxEmailResponse::from(row.clone()));
+        let mut row: email_outbox::ActiveModel = row.into();
+        row.status = Set(email::PENDING.to_string());
+        row.attempts = Set(0);
+        row.next_attempt_at = Set(Utc::now().naive_utc());
+        let row = row.update(&txn).await?;
+        let after = audit::snapshot(&OutboxEmailResponse::from(row.clone()));
+        AuditRepository::record(&txn, context, "retry", "email_outbox", id, before, after).await?;
+        txn.commit().await?;
+        Ok(row)
+    }
+
+    // A student and the active guardians linked to them
+    pub async fn guardians(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student_id: i32,
+    ) -> Result<(users::Model, Vec<users::Model>), EmailError> {
+        let student = user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(student_id))
+            .one(db)
+            .await?
+            .filter(|student| kind(&student.role) == Some(Kind::Student))
+            .ok_or(EmailError::StudentNotFound)?;
+        let guardians = guardians_of(db, tenant_id, student_id).await?;
+        Ok((student, guardians))
+    }
+
+    // Who hears about a student's fees: their guardians, or the student
+    // themselves when no guardian is linked
+    pub async fn family(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student: &users::Model,
+    ) -> Result<Vec<users::Model>, DbErr> {
+        let guardians = guardians_of(db, tenant_id, student.id).await?;
+        if !guardians.is_empty() {
+            return Ok(guardians);
+        }
+        Ok(if student.is_active && student.deleted_at.is_none() {
+            vec![student.clone()]
+        } else {
+            Vec::new()
+        })
+    }
+
+    // Unpaid invoices, in any school, due within `days` of the school's
+    // today (or already overdue) whose family has not been reminded yet
+    pub async fn invoices_to_remind(
+        db: &DatabaseConnection,
+        days: i64,
+    ) -> Result<Vec<(invoices::Model, tenants::Model, users::Model)>, DbErr> {
+        // Schools' todays are within a day of UTC's, so this can only take
+        // in more invoices than are due; each is checked against its school
+        let horizon = Utc::now().date_naive() + Duration::days(days + 1);
+        let candidates = Invoices::find()
+            .find_also_related(Tenants)
+            .filter(invoices::Column::RemindedAt.is_null())
+            .filter(invoices::Column::Status.ne(fees::STATUS_PAID))
+            .filter(invoices::Column::DueDate.lte(horizon))
+            .filter(tenants::Column::IsActive.eq(true))
+            .order_by_asc(invoices::Column::Id)
+            .all(db)
+            .await?;
+
+        let student_ids: Vec<i32> = candidates.iter().map(|(invoice, _)| invoice.student_id).collect();
+        let students: HashMap<i32, users::Model> = Users::find()
+            .filter(users::Column::Id.is_in(student_ids))
+            .filter(users::Column::DeletedAt.is_null())
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|student| (student.id, student))
+            .collect();
+
+        Ok(candidates
+            .into_iter()
+            .filter_map(|(invoice, tenant)| {
+                let tenant = tenant?;
+                let student = students.get(&invoice.student_id)?.clone();
+                let due_by = tenancy::local_today(&tenant.timezone) + Duration::days(days);
+                (invoice.due_date <= due_by).then_some((invoice, tenant, student))
+            })
+            .collect())
+    }
+
+    // Mark an invoice reminded and queue its reminders together. False when
+    // another instance got to the invoice first.
+    pub async fn remind(db: &DatabaseConnection, invoice: &invoices::Model, emails: &[Email]) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let claimed = claim_invoice(invoice.id, Utc::now().naive_utc()).exec(&txn).await?;
+        if claimed.rows_affected == 0 {
+            return Ok(false);
+        }
+        for email in emails {
+            insert(&txn, invoice.tenant_id, email::INVOICE_DUE, email).await?;
+        }
+        txn.commit().await?;
+        Ok(true)
+    }
+}
+
+async fn guardians_of<C: ConnectionTrait>(conn: &C, tenant_id: i32, student_id: i32) -> Result<Vec<users::Model>, DbErr> {
+    let guardian_ids: Vec<i32> = GuardianLinks::find()
+        .filter(guardian_links::Column::StudentId.eq(student_id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|link| link.guardian_id)
+        .collect();
+    user_repository::live(tenant_id)
+        .filter(users::Column::Id.is_in(guardian_ids))
+        .filter(users::Column::IsActive.eq(true))
+        .order_by_asc(users::Column::Id)
+        .all(conn)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, 20).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn sql<E: EntityTrait>(update: UpdateMany<E>) -> String {
+        update.build(DbBackend::Postgres).to_string()
+    }
+
+    const BLANKED: &str = r#""body" = (CASE WHEN ("email_outbox"."template" IN ('password_reset')) THEN '' ELSE "body" END), "html" = (CASE WHEN ("email_outbox"."template" IN ('password_reset')) THEN NULL ELSE "html" END)"#;
+
+    #[test]
+    fn claiming_leases_due_emails_and_skips_locked_ones() {
+        let claimed = sql(claim(at(8, 0), 50));
+        assert!(claimed.starts_with(r#"UPDATE "email_outbox" SET "next_attempt_at" = '2026-10-20 08:05:00.000000' WHERE"#));
+        assert!(claimed.contains(r#""status" = 'pending' AND "email_outbox"."next_attempt_at" <= '2026-10-20 08:00:00.000000'"#));
+        assert!(claimed.ends_with(r#"ORDER BY "email_outbox"."next_attempt_at" ASC LIMIT 50 FOR UPDATE SKIP LOCKED)"#));
+    }
+
+    #[test]
+    fn failures_back_off_until_given_up_and_then_lose_secrets() {
+        let retry_at = at(8, 2);
+        let retried = sql(failed(3, 2, "connection refused", Some(retry_at), at(8, 0)));
+        assert!(retried.contains(r#""attempts" = 2, "last_error" = 'connection refused', "status" = 'pending', "next_attempt_at" = '2026-10-20 08:02:00.000000'"#));
+        assert!(!retried.contains(r#""body""#));
+
+        let given_up = sql(failed(3, 8, "connection refused", None, at(8, 0)));
+        assert!(given_up.contains(r#""status" = 'failed', "next_attempt_at" = '2026-10-20 08:00:00.000000'"#));
+        assert!(given_up.contains(BLANKED));
+
+        let delivered = sql(sent(3, at(8, 0)));
+        assert!(delivered.contains(r#""status" = 'sent', "attempts" = "attempts" + 1"#));
+        assert!(delivered.contains(BLANKED));
+    }
+
+    #[test]
+    fn invoices_are_only_claimed_for_reminders_once() {
+        assert_eq!(
+            sql(claim_invoice(4, at(8, 0))),
+            r#"UPDATE "invoices" SET "reminded_at" = '2026-10-20 08:00:00.000000' WHERE "invoices"."id" = 4 AND "invoices"."reminded_at" IS NULL"#
+        );
+    }
+}
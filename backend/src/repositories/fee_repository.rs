@@ -0,0 +1,505 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::fees::{self, DiscountRule, FeeError, FeeLine, AGING_BUCKETS};
+use crate::dto::fees::{
+    AgingBucketResponse, AgingReportResponse, CreateDiscountRequest, CreateFeeScheduleRequest, InvoiceQuery,
+    InvoiceResponse, PaymentResponse, RecordPaymentRequest, StudentBalance,
+};
+use crate::entities::{
+    fee_discounts, fee_items, fee_schedules, invoice_lines, invoices, payments, prelude::*, users,
+};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+// Result of invoicing a schedule
+pub struct GeneratedInvoices {
+    pub created: Vec<invoices::Model>,
+    pub already_invoiced: Vec<i32>,
+    pub not_students: Vec<i32>,
+}
+
+pub struct FeeRepository;
+
+impl FeeRepository {
+    pub async fn create_schedule(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &CreateFeeScheduleRequest,
+        context: &AuditContext,
+    ) -> Result<(fee_schedules::Model, Vec<fee_items::Model>), DbErr> {
+        let txn = db.begin().await?;
+        let schedule = fee_schedules::ActiveModel {
+            tenant_id: Set(tenant_id),
+            name: Set(request.name.clone()),
+            grade: Set(request.grade.clone()),
+            term: Set(request.term.clone()),
+            due_date: Set(request.due_date),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let mut items = Vec::with_capacity(request.items.len());
+        for item in &request.items {
+            let item = fee_items::ActiveModel {
+                schedule_id: Set(schedule.id),
+                category: Set(item.category.clone()),
+                description: Set(item.description.clone()),
+                amount: Set(fees::round_money(item.amount)),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            items.push(item);
+        }
+
+        let snapshot = serde_json::json!({
+            "name": schedule.name,
+            "grade": schedule.grade,
+            "term": schedule.term,
+            "items": items.iter().map(|item| (item.category.clone(), item.amount)).collect::<Vec<_>>(),
+        });
+        AuditRepository::record(&txn, context, "create", "fee_schedules", schedule.id, None, Some(snapshot)).await?;
+        txn.commit().await?;
+        Ok((schedule, items))
+    }
+
+    // A school's schedules with their fees, newest first
+    pub async fn list_schedules(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        grade: Option<&str>,
+        term: Option<&str>,
+    ) -> Result<Vec<(fee_schedules::Model, Vec<fee_items::Model>)>, DbErr> {
+        let mut query = FeeSchedules::scoped(tenant_id);
+        if let Some(grade) = grade {
+            query = query.filter(fee_schedules::Column::Grade.eq(grade));
+        }
+        if let Some(term) = term {
+            query = query.filter(fee_schedules::Column::Term.eq(term));
+        }
+        query
+            .order_by_desc(fee_schedules::Column::Id)
+            .find_with_related(FeeItems)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_schedule(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<(fee_schedules::Model, Vec<fee_items::Model>)>, DbErr> {
+        let Some(schedule) = FeeSchedules::scoped(tenant_id)
+            .filter(fee_schedules::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let items = schedule
+            .find_related(FeeItems)
+            .order_by_asc(fee_items::Column::Id)
+            .all(db)
+            .await?;
+        Ok(Some((schedule, items)))
+    }
+
+    // None when the student is not a live student of the school
+    pub async fn create_discount(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &CreateDiscountRequest,
+        context: &AuditContext,
+    ) -> Result<Option<fee_discounts::Model>, DbErr> {
+        let txn = db.begin().await?;
+        let student = user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(request.student_id))
+            .filter(users::Column::Role.eq("student"))
+            .one(&txn)
+            .await?;
+        if student.is_none() {
+            return Ok(None);
+        }
+
+        let discount = fee_discounts::ActiveModel {
+            tenant_id: Set(tenant_id),
+            student_id: Set(request.student_id),
+            kind: Set(request.kind.clone()),
+            name: Set(request.name.clone()),
+            percent: Set(request.percent),
+            amount: Set(request.amount.map(fees::round_money)),
+            category: Set(request.category.clone()),
+            term: Set(request.term.clone()),
+            is_active: Set(true),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let snapshot = serde_json::json!({
+            "student_id": discount.student_id,
+            "kind": discount.kind,
+            "name": discount.name,
+            "percent": discount.percent,
+            "amount": discount.amount,
+        });
+        AuditRepository::record(&txn, context, "create", "fee_discounts", discount.id, None, Some(snapshot)).await?;
+        txn.commit().await?;
+        Ok(Some(discount))
+    }
+
+    pub async fn list_discounts(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student_id: Option<i32>,
+    ) -> Result<Vec<fee_discounts::Model>, DbErr> {
+        let mut query = FeeDiscounts::scoped(tenant_id);
+        if let Some(student_id) = student_id {
+            query = query.filter(fee_discounts::Column::StudentId.eq(student_id));
+        }
+        query.order_by_asc(fee_discounts::Column::Id).all(db).await
+    }
+
+    // Stop applying a discount to new invoices; existing invoices keep it
+    pub async fn deactivate_discount(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let result = FeeDiscounts::update_many()
+            .col_expr(fee_discounts::Column::IsActive, sea_query::Expr::value(false))
+            .filter(fee_discounts::Column::TenantId.eq(tenant_id))
+            .filter(fee_discounts::Column::Id.eq(id))
+            .filter(fee_discounts::Column::IsActive.eq(true))
+            .exec(&txn)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+        AuditRepository::record(&txn, context, "deactivate", "fee_discounts", id, None, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    // Invoice each student for a schedule, applying their active discounts
+    // for its term. Students invoiced before are skipped, so this can be
+    // re-run when students join a grade.
+    pub async fn generate_invoices(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        schedule: &fee_schedules::Model,
+        items: &[fee_items::Model],
+        student_ids: &[i32],
+        context: &AuditContext,
+    ) -> Result<GeneratedInvoices, DbErr> {
+        let fee_lines: Vec<FeeLine> = items
+            .iter()
+            .map(|item| FeeLine {
+                category: item.category.clone(),
+                description: item.description.clone(),
+                amount: item.amount,
+            })
+            .collect();
+        let subtotal: Decimal = fee_lines.iter().map(|line| line.amount).sum();
+
+        let txn = db.begin().await?;
+        let students: Vec<i32> = user_repository::live(tenant_id)
+            .select_only()
+            .column(users::Column::Id)
+            .filter(users::Column::Id.is_in(student_ids.iter().copied()))
+            .filter(users::Column::Role.eq("student"))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+        let invoiced: Vec<i32> = Invoices::find()
+            .select_only()
+            .column(invoices::Column::StudentId)
+            .filter(invoices::Column::ScheduleId.eq(schedule.id))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+
+        let mut generated = GeneratedInvoices {
+            created: Vec::new(),
+            already_invoiced: Vec::new(),
+            not_students: Vec::new(),
+        };
+        for &student_id in student_ids {
+            if !students.contains(&student_id) {
+                generated.not_students.push(student_id);
+                continue;
+            }
+            if invoiced.contains(&student_id) || generated.created.iter().any(|i| i.student_id == student_id) {
+                generated.already_invoiced.push(student_id);
+                continue;
+            }
+
+            let rules: Vec<DiscountRule> = FeeDiscounts::scoped(tenant_id)
+                .filter(fee_discounts::Column::StudentId.eq(student_id))
+                .filter(fee_discounts::Column::IsActive.eq(true))
+                .filter(
+                    Condition::any()
+                        .add(fee_discounts::Column::Term.is_null())
+                        .add(fee_discounts::Column::Term.eq(schedule.term.as_str())),
+                )
+                .order_by_asc(fee_discounts::Column::Id)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(|discount| DiscountRule {
+                    name: discount.name,
+                    kind: discount.kind,
+                    percent: discount.percent,
+                    amount: discount.amount,
+                    category: discount.category,
+                })
+                .collect();
+            let discounts = fees::discount_lines(&fee_lines, &rules);
+            let discount_total: Decimal = -discounts.iter().map(|line| line.amount).sum::<Decimal>();
+            let total = subtotal - discount_total;
+
+            let invoice = invoices::ActiveModel {
+                tenant_id: Set(tenant_id),
+                student_id: Set(student_id),
+                schedule_id: Set(schedule.id),
+                term: Set(schedule.term.clone()),
+                due_date: Set(schedule.due_date),
+                subtotal: Set(subtotal),
+                discount_total: Set(discount_total),
+                total: Set(total),
+                amount_paid: Set(Decimal::ZERO),
+                status: Set(fees::invoice_status(total, Decimal::ZERO).to_string()),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            for line in fee_lines.iter().chain(&discounts) {
+                invoice_lines::ActiveModel {
+                    invoice_id: Set(invoice.id),
+                    category: Set(line.category.clone()),
+                    description: Set(line.description.clone()),
+                    amount: Set(line.amount),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+            }
+
+            let snapshot = audit::snapshot(&InvoiceResponse::from(invoice.clone()));
+            AuditRepository::record(&txn, context, "create", "invoices", invoice.id, None, snapshot).await?;
+            generated.created.push(invoice);
+        }
+
+        txn.commit().await?;
+        Ok(generated)
+    }
+
+    pub async fn find_invoice(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<invoices::Model>, DbErr> {
+        Invoices::scoped(tenant_id)
+            .filter(invoices::Column::Id.eq(id))
+            .one(db)
+            .await
+    }
+
+    // An invoice's lines, fees before discounts, and its payments, oldest first
+    pub async fn invoice_details(
+        db: &DatabaseConnection,
+        invoice: &invoices::Model,
+    ) -> Result<(Vec<invoice_lines::Model>, Vec<payments::Model>), DbErr> {
+        let lines = invoice
+            .find_related(InvoiceLines)
+            .order_by_asc(invoice_lines::Column::Id)
+            .all(db)
+            .await?;
+        let payments = invoice
+            .find_related(Payments)
+            .order_by_asc(payments::Column::Id)
+            .all(db)
+            .await?;
+        Ok((lines, payments))
+    }
+
+    pub async fn list_invoices(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &InvoiceQuery,
+    ) -> Result<Vec<invoices::Model>, DbErr> {
+        let mut select = Invoices::scoped(tenant_id);
+        if let Some(student_id) = query.student_id {
+            select = select.filter(invoices::Column::StudentId.eq(student_id));
+        }
+        if let Some(status) = &query.status {
+            select = select.filter(invoices::Column::Status.eq(status.as_str()));
+        }
+        if let Some(term) = &query.term {
+            select = select.filter(invoices::Column::Term.eq(term.as_str()));
+        }
+        select.order_by_desc(invoices::Column::Id).all(db).await
+    }
+
+    // Record money received against an invoice. The invoice row is locked so
+    // concurrent payments cannot together pay more than the balance.
+    pub async fn record_payment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        invoice_id: i32,
+        request: &RecordPaymentRequest,
+        recorded_by: Option<i32>,
+        context: &AuditContext,
+    ) -> Result<(payments::Model, invoices::Model), FeeError> {
+        let amount = fees::round_money(request.amount);
+        let txn = db.begin().await?;
+        let invoice = Invoices::scoped(tenant_id)
+            .filter(invoices::Column::Id.eq(invoice_id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(FeeError::InvoiceNotFound)?;
+
+        let balance = invoice.total - invoice.amount_paid;
+        if balance <= Decimal::ZERO {
+            return Err(FeeError::AlreadyPaid);
+        }
+        if amount > balance {
+            return Err(FeeError::Overpayment { amount, balance });
+        }
+
+        let payment = payments::ActiveModel {
+            amount: Set(amount),
+            method: Set(request.method.clone()),
+            reference: Set(request.reference.clone()),
+            recorded_by: Set(recorded_by),
+            ..Default::default()
+        };
+        let recorded = Self::apply_payment(&txn, invoice, payment, context).await?;
+        txn.commit().await?;
+        Ok(recorded)
+    }
+
+    // Insert a payment against a locked invoice and bring the invoice's paid
+    // amount and status up to date. Shared by payments recorded by staff and
+    // those reconciled from a payment provider.
+    pub async fn apply_payment<C: ConnectionTrait>(
+        conn: &C,
+        invoice: invoices::Model,
+        mut payment: payments::ActiveModel,
+        context: &AuditContext,
+    ) -> Result<(payments::Model, invoices::Model), DbErr> {
+        payment.tenant_id = Set(invoice.tenant_id);
+        payment.invoice_id = Set(invoice.id);
+        payment.received_at = Set(Utc::now().naive_utc());
+        let payment = payment.insert(conn).await?;
+
+        let amount_paid = invoice.amount_paid + payment.amount;
+        let invoice = Self::set_amount_paid(conn, invoice, amount_paid).await?;
+
+        let snapshot = audit::snapshot(&PaymentResponse::from(payment.clone()));
+        AuditRepository::record(conn, context, "create", "payments", payment.id, None, snapshot).await?;
+        Ok((payment, invoice))
+    }
+
+    pub async fn set_amount_paid<C: ConnectionTrait>(
+        conn: &C,
+        invoice: invoices::Model,
+        amount_paid: Decimal,
+    ) -> Result<invoices::Model, DbErr> {
+        let status = fees::invoice_status(invoice.total, amount_paid);
+        let mut invoice: invoices::ActiveModel = invoice.into();
+        invoice.amount_paid = Set(amount_paid);
+        invoice.status = Set(status.to_string());
+        invoice.update(conn).await
+    }
+
+    // A payment with the invoice it paid, for its receipt
+    pub async fn find_payment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<(payments::Model, invoices::Model)>, DbErr> {
+        let payment = Payments::scoped(tenant_id)
+            .filter(payments::Column::Id.eq(id))
+            .find_also_related(Invoices)
+            .one(db)
+            .await?;
+        Ok(payment.and_then(|(payment, invoice)| Some((payment, invoice?))))
+    }
+
+    // Sum of the payments on an invoice up to and including `payment_id`
+    pub async fn paid_through(db: &DatabaseConnection, invoice_id: i32, payment_id: i32) -> Result<Decimal, DbErr> {
+        let amounts: Vec<Decimal> = Payments::find()
+            .select_only()
+            .column(payments::Column::Amount)
+            .filter(payments::Column::InvoiceId.eq(invoice_id))
+            .filter(payments::Column::Id.lte(payment_id))
+            .into_tuple()
+            .all(db)
+            .await?;
+        Ok(amounts.into_iter().sum())
+    }
+
+    // Invoiced, paid and outstanding per student, largest balance first
+    pub async fn balances(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student_id: Option<i32>,
+    ) -> Result<Vec<StudentBalance>, DbErr> {
+        let mut query = Invoices::scoped(tenant_id);
+        if let Some(student_id) = student_id {
+            query = query.filter(invoices::Column::StudentId.eq(student_id));
+        }
+        let mut balances: BTreeMap<i32, StudentBalance> = BTreeMap::new();
+        for invoice in query.all(db).await? {
+            let balance = balances.entry(invoice.student_id).or_insert(StudentBalance {
+                student_id: invoice.student_id,
+                invoiced: Decimal::ZERO,
+                paid: Decimal::ZERO,
+                outstanding: Decimal::ZERO,
+            });
+            balance.invoiced += invoice.total;
+            balance.paid += invoice.amount_paid;
+            balance.outstanding += invoice.total - invoice.amount_paid;
+        }
+        let mut balances: Vec<StudentBalance> = balances.into_values().collect();
+        balances.sort_by_key(|balance| std::cmp::Reverse(balance.outstanding));
+        Ok(balances)
+    }
+
+    // Unpaid balances grouped by how far past their due date they are
+    pub async fn aging(db: &DatabaseConnection, tenant_id: i32, as_of: NaiveDate) -> Result<AgingReportResponse, DbErr> {
+        let unpaid = Invoices::scoped(tenant_id)
+            .filter(invoices::Column::Status.ne(fees::STATUS_PAID))
+            .all(db)
+            .await?;
+
+        let mut buckets: Vec<AgingBucketResponse> = AGING_BUCKETS
+            .iter()
+            .map(|(bucket, _)| AgingBucketResponse {
+                bucket: (*bucket).to_string(),
+                invoices: 0,
+                outstanding: Decimal::ZERO,
+            })
+            .collect();
+        for invoice in unpaid {
+            let bucket = &mut buckets[fees::aging_bucket(invoice.due_date, as_of)];
+            bucket.invoices += 1;
+            bucket.outstanding += invoice.total - invoice.amount_paid;
+        }
+        Ok(AgingReportResponse {
+            as_of,
+            total_outstanding: buckets.iter().map(|bucket| bucket.outstanding).sum(),
+            buckets,
+        })
+    }
+}
@@ -0,0 +1,600 @@
+use std::collections::{HashMap, HashSet};
+
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::hostel::{self, Boarder, HostelError, RoomVacancy};
+use crate::dto::hostel::{
+    AllocateRequest, AllocationQuery, AllocationResponse, AutoAllocateRequest, BedRequest, BedResponse, RoomDetail,
+    RoomOccupancy, RoomRequest, RoomResponse,
+};
+use crate::entities::{hostel_allocations, hostel_beds, hostel_rooms, hostel_stay_logs, prelude::*, users};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+pub struct HostelRepository;
+
+impl HostelRepository {
+    pub async fn list_rooms(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<hostel_rooms::Model>, DbErr> {
+        HostelRooms::scoped(tenant_id)
+            .order_by_asc(hostel_rooms::Column::Name)
+            .all(db)
+            .await
+    }
+
+    pub async fn room_detail(db: &DatabaseConnection, tenant_id: i32, id: i32) -> Result<Option<RoomDetail>, DbErr> {
+        let Some(room) = HostelRooms::scoped(tenant_id)
+            .filter(hostel_rooms::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let beds = room
+            .find_related(HostelBeds)
+            .order_by_asc(hostel_beds::Column::Label)
+            .all(db)
+            .await?;
+        Ok(Some(RoomDetail {
+            room: room.into(),
+            beds: beds.into_iter().map(BedResponse::from).collect(),
+        }))
+    }
+
+    // Add a room together with its beds
+    pub async fn create_room(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &RoomRequest,
+        context: &AuditContext,
+    ) -> Result<RoomDetail, HostelError> {
+        hostel::check_gender(&request.gender)?;
+        let mut labels = HashSet::new();
+        if let Some(label) = request.beds.iter().find(|label| !labels.insert(label.as_str())) {
+            return Err(HostelError::BedLabelTaken(label.clone()));
+        }
+
+        let txn = db.begin().await?;
+        if room_name_taken(&txn, tenant_id, &request.name, None).await? {
+            return Err(HostelError::RoomNameTaken(request.name.clone()));
+        }
+        let room = hostel_rooms::ActiveModel {
+            tenant_id: Set(tenant_id),
+            name: Set(request.name.clone()),
+            building: Set(request.building.clone()),
+            gender: Set(request.gender.clone()),
+            grade: Set(request.grade.clone()),
+            is_active: Set(request.is_active),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let mut beds = Vec::with_capacity(request.beds.len());
+        for label in &request.beds {
+            let bed = hostel_beds::ActiveModel {
+                room_id: Set(room.id),
+                label: Set(label.clone()),
+                is_active: Set(true),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            beds.push(BedResponse::from(bed));
+        }
+
+        let detail = RoomDetail {
+            room: room.into(),
+            beds,
+        };
+        let snapshot = audit::snapshot(&detail);
+        AuditRepository::record(&txn, context, "create", "hostel_rooms", detail.room.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(detail)
+    }
+
+    // Rename a room or change who it houses. Students already allocated
+    // keep their beds; the new rules apply to later allocations.
+    pub async fn update_room(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &RoomRequest,
+        context: &AuditContext,
+    ) -> Result<hostel_rooms::Model, HostelError> {
+        hostel::check_gender(&request.gender)?;
+        let txn = db.begin().await?;
+        let room = lock_room(&txn, tenant_id, id).await?;
+        if room_name_taken(&txn, tenant_id, &request.name, Some(id)).await? {
+            return Err(HostelError::RoomNameTaken(request.name.clone()));
+        }
+
+        let before = audit::snapshot(&RoomResponse::from(room.clone()));
+        let mut room: hostel_rooms::ActiveModel = room.into();
+        room.name = Set(request.name.clone());
+        room.building = Set(request.building.clone());
+        room.gender = Set(request.gender.clone());
+        room.grade = Set(request.grade.clone());
+        room.is_active = Set(request.is_active);
+        let room = room.update(&txn).await?;
+
+        let after = audit::snapshot(&RoomResponse::from(room.clone()));
+        AuditRepository::record(&txn, context, "update", "hostel_rooms", room.id, before, after).await?;
+        txn.commit().await?;
+        Ok(room)
+    }
+
+    pub async fn add_bed(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        room_id: i32,
+        request: &BedRequest,
+        context: &AuditContext,
+    ) -> Result<hostel_beds::Model, HostelError> {
+        let txn = db.begin().await?;
+        lock_room(&txn, tenant_id, room_id).await?;
+        if bed_label_taken(&txn, room_id, &request.label, None).await? {
+            return Err(HostelError::BedLabelTaken(request.label.clone()));
+        }
+        let bed = hostel_beds::ActiveModel {
+            room_id: Set(room_id),
+            label: Set(request.label.clone()),
+            is_active: Set(request.is_active),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&BedResponse::from(bed.clone()));
+        AuditRepository::record(&txn, context, "create", "hostel_beds", bed.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(bed)
+    }
+
+    // Relabel a bed or take it out of use. A bed out of use is skipped by
+    // new allocations but its current occupant stays.
+    pub async fn update_bed(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &BedRequest,
+        context: &AuditContext,
+    ) -> Result<hostel_beds::Model, HostelError> {
+        let txn = db.begin().await?;
+        let (bed, _) = find_bed(&txn, tenant_id, id).await?;
+        if bed_label_taken(&txn, bed.room_id, &request.label, Some(id)).await? {
+            return Err(HostelError::BedLabelTaken(request.label.clone()));
+        }
+
+        let before = audit::snapshot(&BedResponse::from(bed.clone()));
+        let mut bed: hostel_beds::ActiveModel = bed.into();
+        bed.label = Set(request.label.clone());
+        bed.is_active = Set(request.is_active);
+        let bed = bed.update(&txn).await?;
+
+        let after = audit::snapshot(&BedResponse::from(bed.clone()));
+        AuditRepository::record(&txn, context, "update", "hostel_beds", bed.id, before, after).await?;
+        txn.commit().await?;
+        Ok(bed)
+    }
+
+    // Put a student in a chosen bed for a term. The room row is locked so
+    // two allocations cannot take the same bed together.
+    pub async fn allocate(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &AllocateRequest,
+        context: &AuditContext,
+    ) -> Result<hostel_allocations::Model, HostelError> {
+        hostel::check_gender(&request.gender)?;
+        let txn = db.begin().await?;
+        let (bed, room) = find_bed(&txn, tenant_id, request.bed_id).await?;
+        let room = lock_room(&txn, tenant_id, room.id).await?;
+        if !room.is_active || !bed.is_active {
+            return Err(HostelError::Inactive);
+        }
+        hostel::check_room(
+            &room.name,
+            &room.gender,
+            room.grade.as_deref(),
+            &request.gender,
+            &request.grade,
+        )?;
+        user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(request.student_id))
+            .filter(users::Column::Role.eq("student"))
+            .one(&txn)
+            .await?
+            .ok_or(HostelError::StudentNotFound)?;
+
+        let term = HostelAllocations::scoped(tenant_id).filter(hostel_allocations::Column::Term.eq(request.term.as_str()));
+        if term
+            .clone()
+            .filter(hostel_allocations::Column::StudentId.eq(request.student_id))
+            .count(&txn)
+            .await?
+            > 0
+        {
+            return Err(HostelError::AlreadyAllocated);
+        }
+        if term
+            .filter(hostel_allocations::Column::BedId.eq(bed.id))
+            .count(&txn)
+            .await?
+            > 0
+        {
+            return Err(HostelError::BedTaken(bed.label));
+        }
+
+        let allocation = insert_allocation(
+            &txn,
+            tenant_id,
+            &request.term,
+            bed.id,
+            &Boarder {
+                student_id: request.student_id,
+                gender: request.gender.clone(),
+                grade: request.grade.clone(),
+            },
+            context,
+        )
+        .await?;
+        txn.commit().await?;
+        Ok(allocation)
+    }
+
+    // Place students into free beds for a term by gender, grade and
+    // capacity. Students who already have a bed that term, or are not
+    // students of the school, come back unplaced with the reason.
+    pub async fn auto_allocate(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &AutoAllocateRequest,
+        context: &AuditContext,
+    ) -> Result<(Vec<hostel_allocations::Model>, Vec<(i32, String)>), HostelError> {
+        for student in &request.students {
+            hostel::check_gender(&student.gender)?;
+        }
+
+        let txn = db.begin().await?;
+        let rooms = HostelRooms::scoped(tenant_id)
+            .filter(hostel_rooms::Column::IsActive.eq(true))
+            .order_by_asc(hostel_rooms::Column::Id)
+            .lock_exclusive()
+            .all(&txn)
+            .await?;
+
+        let ids: Vec<i32> = request.students.iter().map(|student| student.student_id).collect();
+        let known: HashSet<i32> = user_repository::live(tenant_id)
+            .select_only()
+            .column(users::Column::Id)
+            .filter(users::Column::Id.is_in(ids.clone()))
+            .filter(users::Column::Role.eq("student"))
+            .into_tuple::<i32>()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .collect();
+        let term = HostelAllocations::scoped(tenant_id).filter(hostel_allocations::Column::Term.eq(request.term.as_str()));
+        let housed: HashSet<i32> = term
+            .clone()
+            .select_only()
+            .column(hostel_allocations::Column::StudentId)
+            .filter(hostel_allocations::Column::StudentId.is_in(ids))
+            .into_tuple::<i32>()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut unplaced = Vec::new();
+        let mut seen = HashSet::new();
+        let mut boarders = Vec::new();
+        for student in &request.students {
+            if !seen.insert(student.student_id) {
+                continue;
+            }
+            if !known.contains(&student.student_id) {
+                unplaced.push((student.student_id, "student not found".to_string()));
+            } else if housed.contains(&student.student_id) {
+                unplaced.push((student.student_id, "student already has a bed this term".to_string()));
+            } else {
+                boarders.push(Boarder {
+                    student_id: student.student_id,
+                    gender: student.gender.clone(),
+                    grade: student.grade.clone(),
+                });
+            }
+        }
+
+        let beds = HostelBeds::find()
+            .filter(hostel_beds::Column::RoomId.is_in(rooms.iter().map(|room| room.id)))
+            .filter(hostel_beds::Column::IsActive.eq(true))
+            .order_by_asc(hostel_beds::Column::Label)
+            .all(&txn)
+            .await?;
+        let taken: Vec<(i32, i32, String)> = term
+            .select_only()
+            .column(hostel_allocations::Column::BedId)
+            .column(hostel_beds::Column::RoomId)
+            .column(hostel_allocations::Column::Grade)
+            .inner_join(HostelBeds)
+            .into_tuple()
+            .all(&txn)
+            .await?;
+        let taken_beds: HashSet<i32> = taken.iter().map(|(bed_id, _, _)| *bed_id).collect();
+        let vacancies: Vec<RoomVacancy> = rooms
+            .iter()
+            .map(|room| RoomVacancy {
+                room_id: room.id,
+                gender: room.gender.clone(),
+                grade: room.grade.clone(),
+                free_beds: beds
+                    .iter()
+                    .filter(|bed| bed.room_id == room.id && !taken_beds.contains(&bed.id))
+                    .map(|bed| bed.id)
+                    .collect(),
+                occupant_grades: taken
+                    .iter()
+                    .filter(|(_, room_id, _)| *room_id == room.id)
+                    .map(|(_, _, grade)| grade.clone())
+                    .collect(),
+            })
+            .collect();
+
+        let placement = hostel::allocate(&vacancies, &boarders);
+        let mut allocated = Vec::with_capacity(placement.placed.len());
+        for (student_id, bed_id) in placement.placed {
+            let boarder = boarders
+                .iter()
+                .find(|boarder| boarder.student_id == student_id)
+                .expect("placed boarder was requested");
+            allocated.push(
+                insert_allocation(&txn, tenant_id, &request.term, bed_id, boarder, context).await?,
+            );
+        }
+        unplaced.extend(placement.unplaced);
+
+        txn.commit().await?;
+        Ok((allocated, unplaced))
+    }
+
+    pub async fn list_allocations(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &AllocationQuery,
+    ) -> Result<Vec<hostel_allocations::Model>, DbErr> {
+        let mut select = HostelAllocations::scoped(tenant_id);
+        if let Some(term) = &query.term {
+            select = select.filter(hostel_allocations::Column::Term.eq(term.as_str()));
+        }
+        if let Some(student_id) = query.student_id {
+            select = select.filter(hostel_allocations::Column::StudentId.eq(student_id));
+        }
+        if let Some(room_id) = query.room_id {
+            select = select
+                .inner_join(HostelBeds)
+                .filter(hostel_beds::Column::RoomId.eq(room_id));
+        }
+        select.order_by_asc(hostel_allocations::Column::Id).all(db).await
+    }
+
+    // Free a bed. Students checked in must be checked out first; their stay
+    // log goes with the allocation.
+    pub async fn remove_allocation(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<(), HostelError> {
+        let txn = db.begin().await?;
+        let allocation = lock_allocation(&txn, tenant_id, id).await?;
+        if allocation.status == hostel::CHECKED_IN {
+            return Err(HostelError::StillCheckedIn);
+        }
+        let before = audit::snapshot(&AllocationResponse::from(allocation.clone()));
+        allocation.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "hostel_allocations", id, before, None).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    // Log a student arriving (`check_in`) or leaving (`check_out`)
+    pub async fn record_stay(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        event: &str,
+        notes: Option<String>,
+        context: &AuditContext,
+    ) -> Result<(hostel_allocations::Model, hostel_stay_logs::Model), HostelError> {
+        let txn = db.begin().await?;
+        let allocation = lock_allocation(&txn, tenant_id, id).await?;
+        let status = hostel::next_status(&allocation.status, event)?;
+
+        let before = audit::snapshot(&AllocationResponse::from(allocation.clone()));
+        let mut allocation: hostel_allocations::ActiveModel = allocation.into();
+        allocation.status = Set(status.to_string());
+        let allocation = allocation.update(&txn).await?;
+        let log = hostel_stay_logs::ActiveModel {
+            allocation_id: Set(id),
+            event: Set(event.to_string()),
+            notes: Set(notes),
+            recorded_by: Set(context.actor_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let after = audit::snapshot(&AllocationResponse::from(allocation.clone()));
+        AuditRepository::record(&txn, context, event, "hostel_allocations", id, before, after).await?;
+        txn.commit().await?;
+        Ok((allocation, log))
+    }
+
+    // An allocation's arrivals and departures, oldest first
+    pub async fn stay_logs(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<(hostel_allocations::Model, Vec<hostel_stay_logs::Model>)>, DbErr> {
+        let Some(allocation) = HostelAllocations::scoped(tenant_id)
+            .filter(hostel_allocations::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let logs = allocation
+            .find_related(HostelStayLogs)
+            .order_by_asc(hostel_stay_logs::Column::OccurredAt)
+            .order_by_asc(hostel_stay_logs::Column::Id)
+            .all(db)
+            .await?;
+        Ok(Some((allocation, logs)))
+    }
+
+    // Beds, allocations and check-ins per active room for a term
+    pub async fn occupancy(db: &DatabaseConnection, tenant_id: i32, term: &str) -> Result<Vec<RoomOccupancy>, DbErr> {
+        let rooms = HostelRooms::scoped(tenant_id)
+            .filter(hostel_rooms::Column::IsActive.eq(true))
+            .order_by_asc(hostel_rooms::Column::Name)
+            .all(db)
+            .await?;
+        let room_ids: Vec<i32> = rooms.iter().map(|room| room.id).collect();
+        let mut beds: HashMap<i32, usize> = room_ids.iter().map(|id| (*id, 0)).collect();
+        let bed_rooms: Vec<i32> = HostelBeds::find()
+            .select_only()
+            .column(hostel_beds::Column::RoomId)
+            .filter(hostel_beds::Column::RoomId.is_in(room_ids.clone()))
+            .filter(hostel_beds::Column::IsActive.eq(true))
+            .into_tuple()
+            .all(db)
+            .await?;
+        for room_id in bed_rooms {
+            *beds.entry(room_id).or_default() += 1;
+        }
+        let statuses: Vec<(i32, String)> = HostelAllocations::scoped(tenant_id)
+            .select_only()
+            .column(hostel_beds::Column::RoomId)
+            .column(hostel_allocations::Column::Status)
+            .inner_join(HostelBeds)
+            .filter(hostel_allocations::Column::Term.eq(term))
+            .filter(hostel_beds::Column::RoomId.is_in(room_ids))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let report = hostel::occupancy(&beds, &statuses);
+        Ok(rooms
+            .into_iter()
+            .map(|room| {
+                let counts = report.get(&room.id).cloned().unwrap_or_default();
+                RoomOccupancy {
+                    room_id: room.id,
+                    room: room.name,
+                    building: room.building,
+                    gender: room.gender,
+                    grade: room.grade,
+                    beds: counts.beds,
+                    allocated: counts.allocated,
+                    checked_in: counts.checked_in,
+                    free: counts.beds.saturating_sub(counts.allocated),
+                }
+            })
+            .collect())
+    }
+}
+
+async fn lock_room<C: ConnectionTrait>(conn: &C, tenant_id: i32, id: i32) -> Result<hostel_rooms::Model, HostelError> {
+    HostelRooms::scoped(tenant_id)
+        .filter(hostel_rooms::Column::Id.eq(id))
+        .lock_exclusive()
+        .one(conn)
+        .await?
+        .ok_or(HostelError::RoomNotFound)
+}
+
+async fn lock_allocation<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    id: i32,
+) -> Result<hostel_allocations::Model, HostelError> {
+    HostelAllocations::scoped(tenant_id)
+        .filter(hostel_allocations::Column::Id.eq(id))
+        .lock_exclusive()
+        .one(conn)
+        .await?
+        .ok_or(HostelError::AllocationNotFound)
+}
+
+// Beds have no tenant of their own; they belong to the school of their room
+async fn find_bed<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    id: i32,
+) -> Result<(hostel_beds::Model, hostel_rooms::Model), HostelError> {
+    match HostelBeds::find_by_id(id)
+        .find_also_related(HostelRooms)
+        .filter(hostel_rooms::Column::TenantId.eq(tenant_id))
+        .one(conn)
+        .await?
+    {
+        Some((bed, Some(room))) => Ok((bed, room)),
+        _ => Err(HostelError::BedNotFound),
+    }
+}
+
+async fn room_name_taken<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    name: &str,
+    except: Option<i32>,
+) -> Result<bool, DbErr> {
+    let mut query = HostelRooms::scoped(tenant_id).filter(hostel_rooms::Column::Name.eq(name));
+    if let Some(id) = except {
+        query = query.filter(hostel_rooms::Column::Id.ne(id));
+    }
+    Ok(query.count(conn).await? > 0)
+}
+
+async fn bed_label_taken<C: ConnectionTrait>(
+    conn: &C,
+    room_id: i32,
+    label: &str,
+    except: Option<i32>,
+) -> Result<bool, DbErr> {
+    let mut query = HostelBeds::find()
+        .filter(hostel_beds::Column::RoomId.eq(room_id))
+        .filter(hostel_beds::Column::Label.eq(label));
+    if let Some(id) = except {
+        query = query.filter(hostel_beds::Column::Id.ne(id));
+    }
+    Ok(query.count(conn).await? > 0)
+}
+
+async fn insert_allocation<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    term: &str,
+    bed_id: i32,
+    boarder: &Boarder,
+    context: &AuditContext,
+) -> Result<hostel_allocations::Model, DbErr> {
+    let allocation = hostel_allocations::ActiveModel {
+        tenant_id: Set(tenant_id),
+        bed_id: Set(bed_id),
+        student_id: Set(boarder.student_id),
+        term: Set(term.to_string()),
+        gender: Set(boarder.gender.clone()),
+        grade: Set(boarder.grade.clone()),
+        status: Set(hostel::ALLOCATED.to_string()),
+        allocated_by: Set(context.actor_id),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    let snapshot = audit::snapshot(&AllocationResponse::from(allocation.clone()));
+    AuditRepository::record(conn, context, "create", "hostel_allocations", allocation.id, None, snapshot).await?;
+    Ok(allocation)
+}
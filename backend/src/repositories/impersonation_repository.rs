@@ -0,0 +1,102 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{sea_query::Expr, *};
+
+use crate::entities::{impersonation_actions, impersonation_sessions, prelude::*};
+use crate::repositories::tenant_repository::TenantScoped;
+
+// Most recent sessions returned by the admin listing
+const SESSION_LIST_LIMIT: u64 = 200;
+
+pub struct ImpersonationRepository;
+
+impl ImpersonationRepository {
+    pub async fn start(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        admin_id: i32,
+        target_user_id: i32,
+        reason: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<impersonation_sessions::Model, DbErr> {
+        let session = impersonation_sessions::ActiveModel {
+            tenant_id: Set(tenant_id),
+            admin_id: Set(admin_id),
+            target_user_id: Set(target_user_id),
+            reason: Set(reason.to_string()),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+        session.insert(db).await
+    }
+
+    // A session that has neither been stopped nor run out
+    pub async fn find_active(
+        db: &DatabaseConnection,
+        id: i32,
+    ) -> Result<Option<impersonation_sessions::Model>, DbErr> {
+        ImpersonationSessions::find_by_id(id)
+            .filter(impersonation_sessions::Column::EndedAt.is_null())
+            .filter(impersonation_sessions::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+            .one(db)
+            .await
+    }
+
+    pub async fn end(db: &DatabaseConnection, id: i32) -> Result<bool, DbErr> {
+        let result = ImpersonationSessions::update_many()
+            .col_expr(impersonation_sessions::Column::EndedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(impersonation_sessions::Column::Id.eq(id))
+            .filter(impersonation_sessions::Column::EndedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    pub async fn record_action(
+        db: &DatabaseConnection,
+        session_id: i32,
+        method: &str,
+        path: &str,
+        status: u16,
+        ip_address: Option<String>,
+    ) -> Result<(), DbErr> {
+        let action = impersonation_actions::ActiveModel {
+            session_id: Set(session_id),
+            method: Set(method.to_string()),
+            path: Set(path.to_string()),
+            status: Set(i32::from(status)),
+            ip_address: Set(ip_address),
+            ..Default::default()
+        };
+        action.insert(db).await?;
+        Ok(())
+    }
+
+    // Newest sessions of a school first
+    pub async fn list_sessions(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<impersonation_sessions::Model>, DbErr> {
+        ImpersonationSessions::scoped(tenant_id)
+            .order_by_desc(impersonation_sessions::Column::StartedAt)
+            .limit(SESSION_LIST_LIMIT)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_session(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<impersonation_sessions::Model>, DbErr> {
+        ImpersonationSessions::scoped(tenant_id)
+            .filter(impersonation_sessions::Column::Id.eq(id))
+            .one(db)
+            .await
+    }
+
+    // Everything done during a session, in order
+    pub async fn actions(db: &DatabaseConnection, session_id: i32) -> Result<Vec<impersonation_actions::Model>, DbErr> {
+        ImpersonationActions::find()
+            .filter(impersonation_actions::Column::SessionId.eq(session_id))
+            .order_by_asc(impersonation_actions::Column::Id)
+            .all(db)
+            .await
+    }
+}
@@ -0,0 +1,693 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::extension::postgres::PgExpr;
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::library::{
+    ImportRecord, LibraryError, LibraryPolicy, COPY_AVAILABLE, COPY_ON_HOLD_SHELF, COPY_ON_LOAN, FINE_NONE,
+    FINE_OWED, HOLD_CANCELLED, HOLD_EXPIRED, HOLD_FULFILLED, HOLD_READY, HOLD_WAITING,
+};
+use crate::dto::library::{
+    CheckoutRequest, CopyResponse, CreateCopyRequest, CreateTitleRequest, HoldQuery, HoldResponse, LoanQuery,
+    LoanResponse,
+};
+use crate::entities::{library_copies, library_holds, library_loans, library_titles, prelude::*, users};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+// Result of importing a catalog file
+#[derive(Default)]
+pub struct ImportedCatalog {
+    pub titles_created: usize,
+    pub titles_matched: usize,
+    pub copies_created: usize,
+    pub duplicate_barcodes: Vec<String>,
+}
+
+pub struct LibraryRepository;
+
+impl LibraryRepository {
+    // `request.isbn` is expected to be normalized already
+    pub async fn create_title(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &CreateTitleRequest,
+        context: &AuditContext,
+    ) -> Result<library_titles::Model, DbErr> {
+        let txn = db.begin().await?;
+        let title = library_titles::ActiveModel {
+            tenant_id: Set(tenant_id),
+            isbn: Set(request.isbn.clone()),
+            title: Set(request.title.clone()),
+            author: Set(request.author.clone()),
+            publisher: Set(request.publisher.clone()),
+            published_year: Set(request.published_year),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let snapshot = serde_json::json!({ "isbn": title.isbn, "title": title.title, "author": title.author });
+        AuditRepository::record(&txn, context, "create", "library_titles", title.id, None, Some(snapshot)).await?;
+        txn.commit().await?;
+        Ok(title)
+    }
+
+    // Titles with their copies, alphabetically. `search` is matched against
+    // title and author, or exactly against the ISBN when it is one.
+    pub async fn list_titles(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        search: Option<&str>,
+        isbn: Option<&str>,
+    ) -> Result<Vec<(library_titles::Model, Vec<library_copies::Model>)>, DbErr> {
+        let mut query = LibraryTitles::scoped(tenant_id);
+        if let Some(isbn) = isbn {
+            query = query.filter(library_titles::Column::Isbn.eq(isbn));
+        } else if let Some(search) = search {
+            let pattern = format!("%{}%", search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            query = query.filter(
+                Condition::any()
+                    .add(sea_query::Expr::col(library_titles::Column::Title).ilike(pattern.as_str()))
+                    .add(sea_query::Expr::col(library_titles::Column::Author).ilike(pattern.as_str())),
+            );
+        }
+        query
+            .order_by_asc(library_titles::Column::Title)
+            .order_by_asc(library_titles::Column::Id)
+            .find_with_related(LibraryCopies)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_title(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<(library_titles::Model, Vec<library_copies::Model>)>, DbErr> {
+        let Some(title) = LibraryTitles::scoped(tenant_id)
+            .filter(library_titles::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let copies = title
+            .find_related(LibraryCopies)
+            .order_by_asc(library_copies::Column::Id)
+            .all(db)
+            .await?;
+        Ok(Some((title, copies)))
+    }
+
+    pub async fn add_copy(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        title_id: i32,
+        request: &CreateCopyRequest,
+        context: &AuditContext,
+    ) -> Result<library_copies::Model, LibraryError> {
+        let txn = db.begin().await?;
+        LibraryTitles::scoped(tenant_id)
+            .filter(library_titles::Column::Id.eq(title_id))
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::TitleNotFound)?;
+        if Self::copy_by_barcode(tenant_id, &request.barcode).one(&txn).await?.is_some() {
+            return Err(LibraryError::DuplicateBarcode(request.barcode.clone()));
+        }
+
+        let copy = insert_copy(&txn, tenant_id, title_id, &request.barcode, request.location.clone()).await?;
+        let snapshot = audit::snapshot(&CopyResponse::from(copy.clone()));
+        AuditRepository::record(&txn, context, "create", "library_copies", copy.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(copy)
+    }
+
+    // Set a copy's shelf status. Copies on loan or set aside for a hold are
+    // left alone until they come back or the hold ends.
+    pub async fn set_shelf_status(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        status: &str,
+        context: &AuditContext,
+    ) -> Result<library_copies::Model, LibraryError> {
+        let txn = db.begin().await?;
+        let copy = LibraryCopies::scoped(tenant_id)
+            .filter(library_copies::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::CopyNotFound)?;
+        if copy.status == COPY_ON_LOAN || copy.status == COPY_ON_HOLD_SHELF {
+            return Err(LibraryError::CopyUnavailable(copy.status.replace('_', " ")));
+        }
+
+        let before = audit::snapshot(&CopyResponse::from(copy.clone()));
+        let copy = set_copy_status(&txn, copy, status).await?;
+        let after = audit::snapshot(&CopyResponse::from(copy.clone()));
+        AuditRepository::record(&txn, context, "update", "library_copies", copy.id, before, after).await?;
+        txn.commit().await?;
+        Ok(copy)
+    }
+
+    fn copy_by_barcode(tenant_id: i32, barcode: &str) -> Select<LibraryCopies> {
+        LibraryCopies::scoped(tenant_id).filter(library_copies::Column::Barcode.eq(barcode))
+    }
+
+    // Lend a copy. A copy on the hold shelf only goes to the reader it was
+    // set aside for, which fulfils their hold.
+    pub async fn checkout(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        policy: &LibraryPolicy,
+        today: NaiveDate,
+        request: &CheckoutRequest,
+        checked_out_by: Option<i32>,
+        context: &AuditContext,
+    ) -> Result<library_loans::Model, LibraryError> {
+        let txn = db.begin().await?;
+        expire_holds(&txn, tenant_id, policy, today).await?;
+        let copy = Self::copy_by_barcode(tenant_id, &request.barcode)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::CopyNotFound)?;
+        user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(request.user_id))
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::BorrowerNotFound)?;
+
+        let hold = match copy.status.as_str() {
+            COPY_AVAILABLE => None,
+            COPY_ON_HOLD_SHELF => {
+                let hold = LibraryHolds::scoped(tenant_id)
+                    .filter(library_holds::Column::CopyId.eq(copy.id))
+                    .filter(library_holds::Column::Status.eq(HOLD_READY))
+                    .one(&txn)
+                    .await?;
+                match hold {
+                    Some(hold) if hold.user_id == request.user_id => Some(hold),
+                    _ => return Err(LibraryError::ReservedForHold),
+                }
+            }
+            status => return Err(LibraryError::CopyUnavailable(status.replace('_', " "))),
+        };
+
+        let open_loans = LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::UserId.eq(request.user_id))
+            .filter(library_loans::Column::ReturnedAt.is_null())
+            .count(&txn)
+            .await? as usize;
+        if open_loans >= policy.max_loans {
+            return Err(LibraryError::LoanLimit(open_loans));
+        }
+        let owed = fines_owed(&txn, tenant_id, request.user_id).await?;
+        if owed >= policy.fine_block_amount {
+            return Err(LibraryError::FinesOwed(owed));
+        }
+
+        if let Some(hold) = hold {
+            let mut hold: library_holds::ActiveModel = hold.into();
+            hold.status = Set(HOLD_FULFILLED.to_string());
+            hold.update(&txn).await?;
+        }
+        let copy_id = copy.id;
+        set_copy_status(&txn, copy, COPY_ON_LOAN).await?;
+        let loan = library_loans::ActiveModel {
+            tenant_id: Set(tenant_id),
+            copy_id: Set(copy_id),
+            user_id: Set(request.user_id),
+            checked_out_at: Set(Utc::now().naive_utc()),
+            due_date: Set(policy.due_date(today)),
+            renewals: Set(0),
+            fine_amount: Set(Decimal::ZERO),
+            fine_status: Set(FINE_NONE.to_string()),
+            checked_out_by: Set(checked_out_by),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&LoanResponse::from(loan.clone()));
+        AuditRepository::record(&txn, context, "checkout", "library_loans", loan.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(loan)
+    }
+
+    // Take a copy back, charging any overdue fine. The copy goes to the
+    // first reader waiting for its title, or back on the shelf.
+    pub async fn return_copy(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        policy: &LibraryPolicy,
+        today: NaiveDate,
+        barcode: &str,
+        context: &AuditContext,
+    ) -> Result<(library_loans::Model, Option<library_holds::Model>), LibraryError> {
+        let txn = db.begin().await?;
+        expire_holds(&txn, tenant_id, policy, today).await?;
+        let copy = Self::copy_by_barcode(tenant_id, barcode)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::CopyNotFound)?;
+        let loan = LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::CopyId.eq(copy.id))
+            .filter(library_loans::Column::ReturnedAt.is_null())
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::LoanNotFound)?;
+
+        let before = audit::snapshot(&LoanResponse::from(loan.clone()));
+        let fine = policy.fine(loan.due_date, today);
+        let mut loan: library_loans::ActiveModel = loan.into();
+        loan.returned_at = Set(Some(Utc::now().naive_utc()));
+        loan.fine_amount = Set(fine);
+        loan.fine_status = Set(if fine > Decimal::ZERO { FINE_OWED } else { FINE_NONE }.to_string());
+        let loan = loan.update(&txn).await?;
+        let hold = release_copy(&txn, copy, policy, today).await?;
+
+        let after = audit::snapshot(&LoanResponse::from(loan.clone()));
+        AuditRepository::record(&txn, context, "return", "library_loans", loan.id, before, after).await?;
+        txn.commit().await?;
+        Ok((loan, hold))
+    }
+
+    pub async fn find_loan(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<library_loans::Model>, DbErr> {
+        LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::Id.eq(id))
+            .one(db)
+            .await
+    }
+
+    // Loans, newest first
+    pub async fn list_loans(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &LoanQuery,
+    ) -> Result<Vec<library_loans::Model>, DbErr> {
+        let mut select = LibraryLoans::scoped(tenant_id);
+        if let Some(user_id) = query.user_id {
+            select = select.filter(library_loans::Column::UserId.eq(user_id));
+        }
+        if query.open {
+            select = select.filter(library_loans::Column::ReturnedAt.is_null());
+        }
+        if query.fines_owed {
+            select = select.filter(library_loans::Column::FineStatus.eq(FINE_OWED));
+        }
+        select.order_by_desc(library_loans::Column::Id).all(db).await
+    }
+
+    pub async fn renew(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        policy: &LibraryPolicy,
+        today: NaiveDate,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<library_loans::Model, LibraryError> {
+        let txn = db.begin().await?;
+        let loan = LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::LoanNotFound)?;
+        if loan.returned_at.is_some() {
+            return Err(LibraryError::AlreadyReturned);
+        }
+        let title_id = LibraryCopies::find_by_id(loan.copy_id)
+            .select_only()
+            .column(library_copies::Column::TitleId)
+            .into_tuple::<i32>()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::CopyNotFound)?;
+        let holds_waiting = LibraryHolds::find()
+            .filter(library_holds::Column::TitleId.eq(title_id))
+            .filter(library_holds::Column::Status.eq(HOLD_WAITING))
+            .count(&txn)
+            .await?
+            > 0;
+        let due_date = policy.renewal(loan.due_date, loan.renewals, today, holds_waiting)?;
+
+        let before = audit::snapshot(&LoanResponse::from(loan.clone()));
+        let renewals = loan.renewals + 1;
+        let mut loan: library_loans::ActiveModel = loan.into();
+        loan.due_date = Set(due_date);
+        loan.renewals = Set(renewals);
+        let loan = loan.update(&txn).await?;
+
+        let after = audit::snapshot(&LoanResponse::from(loan.clone()));
+        AuditRepository::record(&txn, context, "renew", "library_loans", loan.id, before, after).await?;
+        txn.commit().await?;
+        Ok(loan)
+    }
+
+    // Mark an owed fine paid or waived
+    pub async fn settle_fine(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        status: &str,
+        context: &AuditContext,
+    ) -> Result<library_loans::Model, LibraryError> {
+        let txn = db.begin().await?;
+        let loan = LibraryLoans::scoped(tenant_id)
+            .filter(library_loans::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::LoanNotFound)?;
+        if loan.fine_status != FINE_OWED {
+            return Err(LibraryError::NoFineOwed);
+        }
+
+        let before = audit::snapshot(&LoanResponse::from(loan.clone()));
+        let mut loan: library_loans::ActiveModel = loan.into();
+        loan.fine_status = Set(status.to_string());
+        let loan = loan.update(&txn).await?;
+
+        let after = audit::snapshot(&LoanResponse::from(loan.clone()));
+        AuditRepository::record(&txn, context, status, "library_loans", loan.id, before, after).await?;
+        txn.commit().await?;
+        Ok(loan)
+    }
+
+    // Queue a reader for a title that has no copy on the shelf
+    pub async fn place_hold(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        policy: &LibraryPolicy,
+        today: NaiveDate,
+        title_id: i32,
+        user_id: i32,
+        context: &AuditContext,
+    ) -> Result<library_holds::Model, LibraryError> {
+        let txn = db.begin().await?;
+        expire_holds(&txn, tenant_id, policy, today).await?;
+        LibraryTitles::scoped(tenant_id)
+            .filter(library_titles::Column::Id.eq(title_id))
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::TitleNotFound)?;
+        user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(user_id))
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::BorrowerNotFound)?;
+
+        let copies = LibraryCopies::find()
+            .filter(library_copies::Column::TitleId.eq(title_id))
+            .all(&txn)
+            .await?;
+        if copies.iter().any(|copy| copy.status == COPY_AVAILABLE) {
+            return Err(LibraryError::CopyAvailable);
+        }
+        let holding = LibraryHolds::find()
+            .filter(library_holds::Column::TitleId.eq(title_id))
+            .filter(library_holds::Column::UserId.eq(user_id))
+            .filter(library_holds::Column::Status.is_in([HOLD_WAITING, HOLD_READY]))
+            .count(&txn)
+            .await?
+            > 0;
+        let borrowing = LibraryLoans::find()
+            .filter(library_loans::Column::UserId.eq(user_id))
+            .filter(library_loans::Column::CopyId.is_in(copies.iter().map(|copy| copy.id)))
+            .filter(library_loans::Column::ReturnedAt.is_null())
+            .count(&txn)
+            .await?
+            > 0;
+        if holding || borrowing {
+            return Err(LibraryError::AlreadyHolding);
+        }
+
+        let hold = library_holds::ActiveModel {
+            tenant_id: Set(tenant_id),
+            title_id: Set(title_id),
+            user_id: Set(user_id),
+            status: Set(HOLD_WAITING.to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let snapshot = audit::snapshot(&HoldResponse::from(hold.clone()));
+        AuditRepository::record(&txn, context, "create", "library_holds", hold.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(hold)
+    }
+
+    pub async fn find_hold(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<library_holds::Model>, DbErr> {
+        LibraryHolds::scoped(tenant_id)
+            .filter(library_holds::Column::Id.eq(id))
+            .one(db)
+            .await
+    }
+
+    // Holds in queue order
+    pub async fn list_holds(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &HoldQuery,
+    ) -> Result<Vec<library_holds::Model>, DbErr> {
+        let mut select = LibraryHolds::scoped(tenant_id);
+        if let Some(user_id) = query.user_id {
+            select = select.filter(library_holds::Column::UserId.eq(user_id));
+        }
+        if let Some(title_id) = query.title_id {
+            select = select.filter(library_holds::Column::TitleId.eq(title_id));
+        }
+        if let Some(status) = &query.status {
+            select = select.filter(library_holds::Column::Status.eq(status.as_str()));
+        }
+        select.order_by_asc(library_holds::Column::Id).all(db).await
+    }
+
+    // Leave the queue; a copy already set aside passes to the next reader
+    pub async fn cancel_hold(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        policy: &LibraryPolicy,
+        today: NaiveDate,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<library_holds::Model, LibraryError> {
+        let txn = db.begin().await?;
+        let hold = LibraryHolds::scoped(tenant_id)
+            .filter(library_holds::Column::Id.eq(id))
+            .filter(library_holds::Column::Status.is_in([HOLD_WAITING, HOLD_READY]))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LibraryError::HoldNotFound)?;
+
+        let before = audit::snapshot(&HoldResponse::from(hold.clone()));
+        let copy_id = hold.copy_id.filter(|_| hold.status == HOLD_READY);
+        let mut hold: library_holds::ActiveModel = hold.into();
+        hold.status = Set(HOLD_CANCELLED.to_string());
+        let hold = hold.update(&txn).await?;
+        if let Some(copy) = match copy_id {
+            Some(copy_id) => LibraryCopies::find_by_id(copy_id).one(&txn).await?,
+            None => None,
+        } {
+            release_copy(&txn, copy, policy, today).await?;
+        }
+
+        let after = audit::snapshot(&HoldResponse::from(hold.clone()));
+        AuditRepository::record(&txn, context, "cancel", "library_holds", hold.id, before, after).await?;
+        txn.commit().await?;
+        Ok(hold)
+    }
+
+    // Add imported records to the catalog. Records are matched to existing
+    // titles by ISBN (or by title and author when they have none); copies
+    // whose barcode is already taken are skipped.
+    pub async fn import(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        records: Vec<ImportRecord>,
+        context: &AuditContext,
+    ) -> Result<ImportedCatalog, DbErr> {
+        let txn = db.begin().await?;
+        let mut imported = ImportedCatalog::default();
+        let mut created: HashMap<(Option<String>, String, Option<String>), i32> = HashMap::new();
+
+        for record in records {
+            let key = match &record.isbn {
+                Some(isbn) => (Some(isbn.clone()), String::new(), None),
+                None => (None, record.title.clone(), record.author.clone()),
+            };
+            let title_id = match created.get(&key) {
+                Some(&title_id) => title_id,
+                None => {
+                    let mut existing = LibraryTitles::scoped(tenant_id);
+                    existing = match &record.isbn {
+                        Some(isbn) => existing.filter(library_titles::Column::Isbn.eq(isbn.as_str())),
+                        None => existing
+                            .filter(library_titles::Column::Isbn.is_null())
+                            .filter(library_titles::Column::Title.eq(record.title.as_str()))
+                            .filter(match &record.author {
+                                Some(author) => library_titles::Column::Author.eq(author.as_str()),
+                                None => library_titles::Column::Author.is_null(),
+                            }),
+                    };
+                    let title_id = match existing.order_by_asc(library_titles::Column::Id).one(&txn).await? {
+                        Some(title) => {
+                            imported.titles_matched += 1;
+                            title.id
+                        }
+                        None => {
+                            let title = library_titles::ActiveModel {
+                                tenant_id: Set(tenant_id),
+                                isbn: Set(record.isbn.clone()),
+                                title: Set(record.title.clone()),
+                                author: Set(record.author.clone()),
+                                publisher: Set(record.publisher.clone()),
+                                published_year: Set(record.published_year),
+                                ..Default::default()
+                            }
+                            .insert(&txn)
+                            .await?;
+                            let snapshot = serde_json::json!({ "isbn": title.isbn, "title": title.title });
+                            AuditRepository::record(&txn, context, "import", "library_titles", title.id, None, Some(snapshot))
+                                .await?;
+                            imported.titles_created += 1;
+                            title.id
+                        }
+                    };
+                    created.insert(key, title_id);
+                    title_id
+                }
+            };
+
+            for copy in record.copies {
+                if Self::copy_by_barcode(tenant_id, &copy.barcode).one(&txn).await?.is_some() {
+                    imported.duplicate_barcodes.push(copy.barcode);
+                    continue;
+                }
+                let copy = insert_copy(&txn, tenant_id, title_id, &copy.barcode, copy.location).await?;
+                let snapshot = audit::snapshot(&CopyResponse::from(copy.clone()));
+                AuditRepository::record(&txn, context, "import", "library_copies", copy.id, None, snapshot).await?;
+                imported.copies_created += 1;
+            }
+        }
+
+        txn.commit().await?;
+        Ok(imported)
+    }
+}
+
+async fn insert_copy<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    title_id: i32,
+    barcode: &str,
+    location: Option<String>,
+) -> Result<library_copies::Model, DbErr> {
+    library_copies::ActiveModel {
+        tenant_id: Set(tenant_id),
+        title_id: Set(title_id),
+        barcode: Set(barcode.to_string()),
+        location: Set(location),
+        status: Set(COPY_AVAILABLE.to_string()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+}
+
+async fn set_copy_status<C: ConnectionTrait>(
+    conn: &C,
+    copy: library_copies::Model,
+    status: &str,
+) -> Result<library_copies::Model, DbErr> {
+    let mut copy: library_copies::ActiveModel = copy.into();
+    copy.status = Set(status.to_string());
+    copy.update(conn).await
+}
+
+async fn fines_owed<C: ConnectionTrait>(conn: &C, tenant_id: i32, user_id: i32) -> Result<Decimal, DbErr> {
+    let fines: Vec<Decimal> = LibraryLoans::scoped(tenant_id)
+        .select_only()
+        .column(library_loans::Column::FineAmount)
+        .filter(library_loans::Column::UserId.eq(user_id))
+        .filter(library_loans::Column::FineStatus.eq(FINE_OWED))
+        .into_tuple()
+        .all(conn)
+        .await?;
+    Ok(fines.into_iter().sum())
+}
+
+// Set a copy that has come free aside for the first waiting hold on its
+// title, or put it back on the shelf when nobody is waiting
+async fn release_copy<C: ConnectionTrait>(
+    conn: &C,
+    copy: library_copies::Model,
+    policy: &LibraryPolicy,
+    today: NaiveDate,
+) -> Result<Option<library_holds::Model>, DbErr> {
+    let next = LibraryHolds::find()
+        .filter(library_holds::Column::TitleId.eq(copy.title_id))
+        .filter(library_holds::Column::Status.eq(HOLD_WAITING))
+        .order_by_asc(library_holds::Column::Id)
+        .lock_exclusive()
+        .one(conn)
+        .await?;
+    let Some(hold) = next else {
+        set_copy_status(conn, copy, COPY_AVAILABLE).await?;
+        return Ok(None);
+    };
+
+    let mut hold: library_holds::ActiveModel = hold.into();
+    hold.status = Set(HOLD_READY.to_string());
+    hold.copy_id = Set(Some(copy.id));
+    hold.ready_at = Set(Some(Utc::now().naive_utc()));
+    hold.expires_on = Set(Some(policy.hold_expiry(today)));
+    let hold = hold.update(conn).await?;
+    set_copy_status(conn, copy, COPY_ON_HOLD_SHELF).await?;
+    Ok(Some(hold))
+}
+
+// Ready holds not collected in time lapse, and their copies pass down the
+// queue. Run before lending and queueing so the shelf reflects today.
+async fn expire_holds<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    policy: &LibraryPolicy,
+    today: NaiveDate,
+) -> Result<(), DbErr> {
+    let lapsed = LibraryHolds::scoped(tenant_id)
+        .filter(library_holds::Column::Status.eq(HOLD_READY))
+        .filter(library_holds::Column::ExpiresOn.lt(today))
+        .order_by_asc(library_holds::Column::Id)
+        .all(conn)
+        .await?;
+    for hold in lapsed {
+        let copy_id = hold.copy_id;
+        let mut hold: library_holds::ActiveModel = hold.into();
+        hold.status = Set(HOLD_EXPIRED.to_string());
+        hold.update(conn).await?;
+        if let Some(copy) = match copy_id {
+            Some(copy_id) => LibraryCopies::find_by_id(copy_id).one(conn).await?,
+            None => None,
+        } {
+            release_copy(conn, copy, policy, today).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,309 @@
+use chrono::{Duration, Utc};
+use sea_orm::*;
+use serde_json::Value;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::lti::{LtiError, LINK_WINDOW_MINUTES};
+use crate::dto::lti::{CreatePlatformRequest, PlatformResponse, UserLinkResponse};
+use crate::entities::{lti_launches, lti_login_states, lti_platforms, lti_user_links, prelude::*, users};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+// How long a browser has to come back from the platform with an id_token
+const LOGIN_STATE_TTL_MINUTES: i64 = 10;
+
+pub struct LtiRepository;
+
+impl LtiRepository {
+    // Get all platforms registered by a school
+    pub async fn find_all_platforms(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<PlatformResponse>, DbErr> {
+        let platforms = LtiPlatforms::scoped(tenant_id)
+            .order_by_asc(lti_platforms::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(platforms.into_iter().map(PlatformResponse::from).collect())
+    }
+
+    // Find an active platform by issuer, narrowed by the client and deployment
+    // ids the platform sent. Platforms call in without naming a school; the
+    // registration they match decides it, so several matches (schools sharing
+    // a cloud LMS) are refused rather than guessed between.
+    pub async fn find_platform(
+        db: &DatabaseConnection,
+        issuer: &str,
+        client_id: Option<&str>,
+        deployment_id: Option<&str>,
+    ) -> Result<Option<lti_platforms::Model>, LtiError> {
+        let mut platforms = platform_query(issuer, client_id, deployment_id).limit(2).all(db).await?;
+        if platforms.len() > 1 {
+            return Err(LtiError::AmbiguousPlatform);
+        }
+        Ok(platforms.pop())
+    }
+
+    pub async fn find_platform_by_id(
+        db: &DatabaseConnection,
+        id: i32,
+    ) -> Result<Option<lti_platforms::Model>, DbErr> {
+        LtiPlatforms::find_by_id(id).one(db).await
+    }
+
+    // Register a new platform for a school
+    pub async fn create_platform(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        data: CreatePlatformRequest,
+        context: &AuditContext,
+    ) -> Result<PlatformResponse, DbErr> {
+        let txn = db.begin().await?;
+        let platform = lti_platforms::ActiveModel {
+            tenant_id: Set(tenant_id),
+            name: Set(data.name),
+            issuer: Set(data.issuer),
+            client_id: Set(data.client_id),
+            deployment_id: Set(data.deployment_id),
+            auth_login_url: Set(data.auth_login_url),
+            auth_token_url: Set(data.auth_token_url),
+            jwks_url: Set(data.jwks_url),
+            is_active: Set(true),
+            ..Default::default()
+        };
+
+        let platform = PlatformResponse::from(platform.insert(&txn).await?);
+        AuditRepository::record(&txn, context, "create", "lti_platforms", platform.id, None, audit::snapshot(&platform)).await?;
+        txn.commit().await?;
+        Ok(platform)
+    }
+
+    // Remember state/nonce issued during login initiation
+    pub async fn save_login_state(
+        db: &DatabaseConnection,
+        state: String,
+        nonce: String,
+        platform_id: i32,
+        target_link_uri: String,
+    ) -> Result<(), DbErr> {
+        let login_state = lti_login_states::ActiveModel {
+            state: Set(state),
+            nonce: Set(nonce),
+            platform_id: Set(platform_id),
+            target_link_uri: Set(target_link_uri),
+            expires_at: Set((Utc::now() + Duration::minutes(LOGIN_STATE_TTL_MINUTES)).naive_utc()),
+            ..Default::default()
+        };
+        login_state.insert(db).await?;
+
+        // Opportunistically clear abandoned logins
+        LtiLoginStates::delete_many()
+            .filter(lti_login_states::Column::ExpiresAt.lt(Utc::now().naive_utc()))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    // Consume a login state; each state can only be used once
+    pub async fn take_login_state(
+        db: &DatabaseConnection,
+        state: &str,
+    ) -> Result<Option<lti_login_states::Model>, DbErr> {
+        let Some(login_state) = LtiLoginStates::find_by_id(state).one(db).await? else {
+            return Ok(None);
+        };
+        // Of two requests racing with the same state only the one deleting it wins
+        if LtiLoginStates::delete_by_id(state).exec(db).await?.rows_affected != 1 {
+            return Ok(None);
+        }
+
+        if login_state.expires_at < Utc::now().naive_utc() {
+            return Ok(None);
+        }
+        Ok(Some(login_state))
+    }
+
+    // Store a validated launch so deep linking and score passback can refer to it
+    pub async fn create_launch(
+        db: &DatabaseConnection,
+        id: String,
+        platform_id: i32,
+        user_id: Option<i32>,
+        subject: String,
+        message_type: String,
+        claims: Value,
+    ) -> Result<lti_launches::Model, DbErr> {
+        let launch = lti_launches::ActiveModel {
+            id: Set(id),
+            platform_id: Set(platform_id),
+            user_id: Set(user_id),
+            subject: Set(subject),
+            message_type: Set(message_type),
+            claims: Set(claims),
+            ..Default::default()
+        };
+
+        launch.insert(db).await
+    }
+
+    pub async fn find_launch(
+        db: &DatabaseConnection,
+        id: &str,
+    ) -> Result<Option<lti_launches::Model>, DbErr> {
+        LtiLaunches::find_by_id(id).one(db).await
+    }
+
+    // The account a platform user signs in as, if they have been linked to
+    // one. Deleted accounts are never returned; deactivated ones are, for
+    // the caller to refuse.
+    pub async fn linked_user(
+        db: &DatabaseConnection,
+        platform: &lti_platforms::Model,
+        subject: &str,
+    ) -> Result<Option<users::Model>, DbErr> {
+        let Some(link) = LtiUserLinks::find()
+            .filter(lti_user_links::Column::PlatformId.eq(platform.id))
+            .filter(lti_user_links::Column::Subject.eq(subject))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        user_repository::live(platform.tenant_id)
+            .filter(users::Column::Id.eq(link.user_id))
+            .one(db)
+            .await
+    }
+
+    // Link the platform user of a recent, unlinked launch to the signed-in
+    // account, so their next launches sign them in
+    pub async fn link_launch(
+        db: &DatabaseConnection,
+        launch: &lti_launches::Model,
+        user_id: i32,
+        context: &AuditContext,
+    ) -> Result<lti_user_links::Model, LtiError> {
+        if launch.created_at + Duration::minutes(LINK_WINDOW_MINUTES) < Utc::now().naive_utc() {
+            return Err(LtiError::LinkExpired);
+        }
+
+        let txn = db.begin().await?;
+        let linked = LtiUserLinks::find()
+            .filter(lti_user_links::Column::PlatformId.eq(launch.platform_id))
+            .filter(lti_user_links::Column::Subject.eq(&launch.subject))
+            .count(&txn)
+            .await?;
+        if linked > 0 || launch.user_id.is_some() {
+            return Err(LtiError::AlreadyLinked);
+        }
+
+        let link = lti_user_links::ActiveModel {
+            platform_id: Set(launch.platform_id),
+            subject: Set(launch.subject.clone()),
+            user_id: Set(user_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        LtiLaunches::update_many()
+            .col_expr(lti_launches::Column::UserId, sea_query::Expr::value(user_id))
+            .filter(lti_launches::Column::Id.eq(&launch.id))
+            .exec(&txn)
+            .await?;
+
+        let snapshot = audit::snapshot(&UserLinkResponse::from(link.clone()));
+        AuditRepository::record(&txn, context, "create", "lti_user_links", link.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(link)
+    }
+
+    pub async fn user_links(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        platform_id: i32,
+    ) -> Result<Option<Vec<lti_user_links::Model>>, DbErr> {
+        if !platform_exists(db, tenant_id, platform_id).await? {
+            return Ok(None);
+        }
+        LtiUserLinks::find()
+            .filter(lti_user_links::Column::PlatformId.eq(platform_id))
+            .order_by_asc(lti_user_links::Column::Id)
+            .all(db)
+            .await
+            .map(Some)
+    }
+
+    pub async fn unlink_user(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        platform_id: i32,
+        link_id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        if !platform_exists(&txn, tenant_id, platform_id).await? {
+            return Ok(false);
+        }
+        let Some(link) = LtiUserLinks::find_by_id(link_id)
+            .filter(lti_user_links::Column::PlatformId.eq(platform_id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let before = audit::snapshot(&UserLinkResponse::from(link.clone()));
+        link.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "lti_user_links", link_id, before, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+}
+
+async fn platform_exists<C: ConnectionTrait>(conn: &C, tenant_id: i32, platform_id: i32) -> Result<bool, DbErr> {
+    Ok(LtiPlatforms::scoped(tenant_id)
+        .filter(lti_platforms::Column::Id.eq(platform_id))
+        .count(conn)
+        .await?
+        > 0)
+}
+
+// Active platforms registered for `issuer`. A registration without a
+// deployment id accepts any deployment of its client.
+fn platform_query(issuer: &str, client_id: Option<&str>, deployment_id: Option<&str>) -> Select<LtiPlatforms> {
+    let mut query = LtiPlatforms::find()
+        .filter(lti_platforms::Column::Issuer.eq(issuer))
+        .filter(lti_platforms::Column::IsActive.eq(true));
+    if let Some(client_id) = client_id {
+        query = query.filter(lti_platforms::Column::ClientId.eq(client_id));
+    }
+    if let Some(deployment_id) = deployment_id {
+        query = query.filter(
+            Condition::any()
+                .add(lti_platforms::Column::DeploymentId.eq(deployment_id))
+                .add(lti_platforms::Column::DeploymentId.is_null()),
+        );
+    }
+    query.order_by_asc(lti_platforms::Column::Id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platforms_match_on_issuer_client_and_deployment() {
+        let sql = platform_query("https://canvas.example", Some("client-1"), Some("dep-1"))
+            .limit(2)
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""issuer" = 'https://canvas.example'"#), "{}", sql);
+        assert!(sql.contains(r#""client_id" = 'client-1'"#), "{}", sql);
+        assert!(sql.contains(r#"("lti_platforms"."deployment_id" = 'dep-1' OR "lti_platforms"."deployment_id" IS NULL)"#), "{}", sql);
+        assert!(sql.contains("LIMIT 2"), "{}", sql);
+
+        let sql = platform_query("https://canvas.example", None, None).build(DbBackend::Postgres).to_string();
+        let (_, filter) = sql.split_once("WHERE").unwrap();
+        assert!(!filter.contains("client_id") && !filter.contains("deployment_id"), "{}", sql);
+    }
+}
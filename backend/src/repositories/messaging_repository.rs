@@ -0,0 +1,936 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde_json::json;
+
+use crate::application::admissions::safe_filename;
+use crate::application::audit::{self, AuditContext};
+use crate::application::messaging::{
+    self, archive_reason, kind, may_converse, AttachmentUpload, Class, Kind, MessagingError, Party, Rules,
+};
+use crate::dto::messaging::{
+    ArchiveQuery, ArchivedParticipant, AttachmentResponse, GuardianLinkRequest, GuardianLinkResponse,
+    MessageResponse, ParticipantResponse, ReportResponse, ResolveRequest, SafeguardingSettings, ThreadDetail,
+    ThreadRequest, ThreadSummary,
+};
+use crate::entities::{
+    class_memberships, guardian_links, message_archive, message_attachments, message_participants, message_reports,
+    message_threads, messages, messaging_settings, prelude::*, users,
+};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn report_response(report: message_reports::Model, message: messages::Model) -> ReportResponse {
+    ReportResponse {
+        id: report.id,
+        message_id: report.message_id,
+        thread_id: message.thread_id,
+        sender_id: message.sender_id,
+        message: message.body,
+        message_hidden: message.hidden_at.is_some(),
+        reporter_id: report.reporter_id,
+        reason: report.reason,
+        status: report.status,
+        resolution: report.resolution,
+        resolved_by: report.resolved_by,
+        resolved_at: report.resolved_at.map(format_time),
+        created_at: format_time(report.created_at),
+    }
+}
+
+pub struct MessagingRepository;
+
+impl MessagingRepository {
+    // Start a thread with the sender and recipients in it. Every two people
+    // in it must be allowed to message each other.
+    pub async fn create_thread(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        sender_id: i32,
+        request: &ThreadRequest,
+    ) -> Result<(message_threads::Model, messages::Model), MessagingError> {
+        let mut recipients: Vec<i32> = request
+            .recipient_ids
+            .iter()
+            .copied()
+            .filter(|id| *id != sender_id)
+            .collect();
+        recipients.sort_unstable();
+        recipients.dedup();
+        if recipients.is_empty() {
+            return Err(MessagingError::NoRecipients);
+        }
+        if recipients.len() > messaging::MAX_RECIPIENTS {
+            return Err(MessagingError::TooManyRecipients);
+        }
+
+        let txn = db.begin().await?;
+        let rules = rules(&txn, tenant_id).await?;
+        let mut ids = recipients.clone();
+        ids.push(sender_id);
+        let everyone = parties(&txn, tenant_id, &ids).await?;
+        if everyone.len() != ids.len() {
+            return Err(MessagingError::UserNotFound);
+        }
+        // Sender first, so a recipient they may not reach is named as such
+        let mut ordered = everyone.clone();
+        ordered.sort_by_key(|party| party.id != sender_id);
+        may_converse(&ordered, &rules).map_err(|(a, b)| match (a, b) {
+            (a, b) if a == sender_id => MessagingError::NotAllowed(b),
+            (a, b) => MessagingError::NotTogether(a, b),
+        })?;
+
+        let now = Utc::now().naive_utc();
+        let thread = message_threads::ActiveModel {
+            tenant_id: Set(tenant_id),
+            subject: Set(request.subject.trim().to_string()),
+            created_by: Set(Some(sender_id)),
+            is_locked: Set(false),
+            created_at: Set(now),
+            last_message_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        MessageParticipants::insert_many(everyone.iter().map(|party| message_participants::ActiveModel {
+            thread_id: Set(thread.id),
+            user_id: Set(party.id),
+            last_read_at: Set((party.id == sender_id).then_some(now)),
+            joined_at: Set(now),
+            ..Default::default()
+        }))
+        .exec_without_returning(&txn)
+        .await?;
+        let message = messages::ActiveModel {
+            thread_id: Set(thread.id),
+            sender_id: Set(Some(sender_id)),
+            body: Set(request.body.clone()),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let members: Vec<ArchivedParticipant> = everyone
+            .iter()
+            .map(|party| ArchivedParticipant {
+                user_id: party.id,
+                full_name: party.name.clone(),
+                role: party.role.clone(),
+            })
+            .collect();
+        archive(&txn, &thread, &message, &members, &rules).await?;
+        txn.commit().await?;
+        Ok((thread, message))
+    }
+
+    // Reply in a thread the sender is part of
+    pub async fn reply(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        thread_id: i32,
+        sender_id: i32,
+        body: &str,
+    ) -> Result<(message_threads::Model, messages::Model), MessagingError> {
+        let txn = db.begin().await?;
+        let thread = MessageThreads::scoped(tenant_id)
+            .filter(message_threads::Column::Id.eq(thread_id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(MessagingError::ThreadNotFound)?;
+        let members = members(&txn, thread_id).await?;
+        if !members.iter().any(|member| member.user_id == sender_id) {
+            return Err(MessagingError::ThreadNotFound);
+        }
+        if thread.is_locked {
+            return Err(MessagingError::ThreadLocked);
+        }
+
+        let now = Utc::now().naive_utc();
+        let message = messages::ActiveModel {
+            thread_id: Set(thread_id),
+            sender_id: Set(Some(sender_id)),
+            body: Set(body.to_string()),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let mut active: message_threads::ActiveModel = thread.into();
+        active.last_message_at = Set(now);
+        let thread = active.update(&txn).await?;
+        read_up_to(&txn, thread_id, sender_id, now).await?;
+
+        let rules = rules(&txn, tenant_id).await?;
+        archive(&txn, &thread, &message, &members, &rules).await?;
+        txn.commit().await?;
+        Ok((thread, message))
+    }
+
+    // Everyone in a thread
+    pub async fn participant_ids(db: &DatabaseConnection, thread_id: i32) -> Result<Vec<i32>, DbErr> {
+        MessageParticipants::find()
+            .select_only()
+            .column(message_participants::Column::UserId)
+            .filter(message_participants::Column::ThreadId.eq(thread_id))
+            .into_tuple()
+            .all(db)
+            .await
+    }
+
+    // The user's threads, latest activity first, with how many messages in
+    // each they have not read
+    pub async fn threads(db: &DatabaseConnection, tenant_id: i32, user_id: i32) -> Result<Vec<ThreadSummary>, DbErr> {
+        let found = MessageThreads::scoped(tenant_id)
+            .inner_join(MessageParticipants)
+            .filter(message_participants::Column::UserId.eq(user_id))
+            .order_by_desc(message_threads::Column::LastMessageAt)
+            .order_by_desc(message_threads::Column::Id)
+            .all(db)
+            .await?;
+        if found.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<i32> = found.iter().map(|thread| thread.id).collect();
+
+        let unread: HashMap<i32, i64> = Messages::find()
+            .select_only()
+            .column(messages::Column::ThreadId)
+            .column_as(Expr::col((Messages, messages::Column::Id)).count(), "unread")
+            .join(JoinType::InnerJoin, messages::Relation::MessageThreads.def())
+            .join(JoinType::InnerJoin, message_threads::Relation::MessageParticipants.def())
+            .filter(message_participants::Column::UserId.eq(user_id))
+            .filter(messages::Column::ThreadId.is_in(ids.iter().copied()))
+            .filter(messages::Column::HiddenAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(messages::Column::SenderId.is_null())
+                    .add(messages::Column::SenderId.ne(user_id)),
+            )
+            .filter(
+                Condition::any()
+                    .add(message_participants::Column::LastReadAt.is_null())
+                    .add(
+                        Expr::col((Messages, messages::Column::CreatedAt))
+                            .gt(Expr::col((MessageParticipants, message_participants::Column::LastReadAt))),
+                    ),
+            )
+            .group_by(messages::Column::ThreadId)
+            .into_tuple::<(i32, i64)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect();
+        let mut participants = participants(db, &ids).await?;
+
+        Ok(found
+            .into_iter()
+            .map(|thread| ThreadSummary {
+                participants: participants.remove(&thread.id).unwrap_or_default(),
+                unread: unread.get(&thread.id).copied().unwrap_or(0),
+                thread: thread.into(),
+            })
+            .collect())
+    }
+
+    // A thread with its messages, for a participant or a moderator
+    pub async fn thread(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        viewer_id: i32,
+        moderator: bool,
+    ) -> Result<Option<ThreadDetail>, DbErr> {
+        let Some(thread) = MessageThreads::scoped(tenant_id)
+            .filter(message_threads::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let participants = participants(db, &[id]).await?.remove(&id).unwrap_or_default();
+        if !moderator && !participants.iter().any(|participant| participant.user_id == viewer_id) {
+            return Ok(None);
+        }
+        let read_up_to: Vec<(i32, NaiveDateTime)> = MessageParticipants::find()
+            .select_only()
+            .columns([message_participants::Column::UserId, message_participants::Column::LastReadAt])
+            .filter(message_participants::Column::ThreadId.eq(id))
+            .filter(message_participants::Column::LastReadAt.is_not_null())
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let found = thread
+            .find_related(Messages)
+            .order_by_asc(messages::Column::CreatedAt)
+            .order_by_asc(messages::Column::Id)
+            .all(db)
+            .await?;
+        let mut attachments = attachments(db, found.iter().map(|message| message.id).collect()).await?;
+
+        let messages = found
+            .into_iter()
+            .map(|message| {
+                let hidden = message.hidden_at.is_some();
+                let shown = !hidden || moderator;
+                MessageResponse {
+                    id: message.id,
+                    thread_id: message.thread_id,
+                    sender_id: message.sender_id,
+                    body: shown.then_some(message.body),
+                    hidden,
+                    created_at: format_time(message.created_at),
+                    attachments: match shown {
+                        true => attachments.remove(&message.id).unwrap_or_default(),
+                        false => Vec::new(),
+                    },
+                    read_by: read_up_to
+                        .iter()
+                        .filter(|(user_id, read_at)| Some(*user_id) != message.sender_id && *read_at >= message.created_at)
+                        .map(|(user_id, _)| *user_id)
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Ok(Some(ThreadDetail {
+            thread: thread.into(),
+            participants,
+            messages,
+        }))
+    }
+
+    // Mark everything in a thread as read by the user
+    pub async fn mark_read(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        thread_id: i32,
+        user_id: i32,
+    ) -> Result<(), MessagingError> {
+        MessageThreads::scoped(tenant_id)
+            .filter(message_threads::Column::Id.eq(thread_id))
+            .one(db)
+            .await?
+            .ok_or(MessagingError::ThreadNotFound)?;
+        if read_up_to(db, thread_id, user_id, Utc::now().naive_utc()).await? == 0 {
+            return Err(MessagingError::ThreadNotFound);
+        }
+        Ok(())
+    }
+
+    // Attach a file to a message the user sent
+    pub async fn add_attachment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        message_id: i32,
+        user_id: i32,
+        upload: AttachmentUpload,
+    ) -> Result<message_attachments::Model, MessagingError> {
+        messaging::check_attachment(&upload.content_type, upload.content.len())?;
+
+        let (message, thread) = message_in_thread(db, tenant_id, message_id).await?;
+        if !is_participant(db, thread.id, user_id).await? {
+            return Err(MessagingError::MessageNotFound);
+        }
+        if message.sender_id != Some(user_id) {
+            return Err(MessagingError::NotSender);
+        }
+        if thread.is_locked {
+            return Err(MessagingError::ThreadLocked);
+        }
+
+        let attachment = message_attachments::ActiveModel {
+            message_id: Set(message_id),
+            filename: Set(safe_filename(&upload.filename)),
+            content_type: Set(upload.content_type),
+            length: Set(upload.content.len() as i64),
+            content: Set(upload.content),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(attachment)
+    }
+
+    // An attachment, for participants while its message is shown and for
+    // moderators always
+    pub async fn attachment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        user_id: i32,
+        moderator: bool,
+    ) -> Result<Option<message_attachments::Model>, MessagingError> {
+        let Some(attachment) = MessageAttachments::find_by_id(id).one(db).await? else {
+            return Ok(None);
+        };
+        let (message, thread) = match message_in_thread(db, tenant_id, attachment.message_id).await {
+            Ok(found) => found,
+            Err(MessagingError::MessageNotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if moderator || (message.hidden_at.is_none() && is_participant(db, thread.id, user_id).await?) {
+            return Ok(Some(attachment));
+        }
+        Ok(None)
+    }
+
+    // Flag a message in one of the user's threads for the moderators
+    pub async fn report(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        message_id: i32,
+        reporter_id: i32,
+        reason: &str,
+    ) -> Result<ReportResponse, MessagingError> {
+        let (message, thread) = message_in_thread(db, tenant_id, message_id).await?;
+        if !is_participant(db, thread.id, reporter_id).await? {
+            return Err(MessagingError::MessageNotFound);
+        }
+        let reported = MessageReports::find()
+            .filter(message_reports::Column::MessageId.eq(message_id))
+            .filter(message_reports::Column::ReporterId.eq(reporter_id))
+            .count(db)
+            .await?;
+        if reported > 0 {
+            return Err(MessagingError::AlreadyReported);
+        }
+
+        let report = message_reports::ActiveModel {
+            tenant_id: Set(tenant_id),
+            message_id: Set(message_id),
+            reporter_id: Set(Some(reporter_id)),
+            reason: Set(reason.trim().to_string()),
+            status: Set(messaging::OPEN.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(report_response(report, message))
+    }
+
+    pub async fn reports(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        status: Option<&str>,
+    ) -> Result<Vec<ReportResponse>, DbErr> {
+        let mut select = MessageReports::scoped(tenant_id);
+        if let Some(status) = status {
+            select = select.filter(message_reports::Column::Status.eq(status));
+        }
+        Ok(select
+            .find_also_related(Messages)
+            .order_by_desc(message_reports::Column::CreatedAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(report, message)| message.map(|message| report_response(report, message)))
+            .collect())
+    }
+
+    // Close an open report. Hiding the message closes every open report on
+    // it.
+    pub async fn resolve(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &ResolveRequest,
+        context: &AuditContext,
+    ) -> Result<ReportResponse, MessagingError> {
+        let status = match request.action.as_str() {
+            "dismiss" => messaging::DISMISSED,
+            "hide" => messaging::ACTIONED,
+            _ => return Err(MessagingError::InvalidAction),
+        };
+
+        let txn = db.begin().await?;
+        let report = MessageReports::scoped(tenant_id)
+            .filter(message_reports::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(MessagingError::ReportNotFound)?;
+        if report.status != messaging::OPEN {
+            return Err(MessagingError::AlreadyResolved);
+        }
+        let mut message = Messages::find_by_id(report.message_id)
+            .one(&txn)
+            .await?
+            .ok_or(MessagingError::MessageNotFound)?;
+
+        let now = Utc::now().naive_utc();
+        let resolution = request.resolution.as_deref().map(str::trim).filter(|text| !text.is_empty());
+        let mut resolved = MessageReports::update_many()
+            .col_expr(message_reports::Column::Status, Expr::value(status))
+            .col_expr(message_reports::Column::Resolution, Expr::value(resolution.map(str::to_string)))
+            .col_expr(message_reports::Column::ResolvedBy, Expr::value(context.actor_id))
+            .col_expr(message_reports::Column::ResolvedAt, Expr::value(now))
+            .filter(message_reports::Column::Status.eq(messaging::OPEN));
+        resolved = match status {
+            messaging::ACTIONED => resolved.filter(message_reports::Column::MessageId.eq(message.id)),
+            _ => resolved.filter(message_reports::Column::Id.eq(id)),
+        };
+        resolved.exec(&txn).await?;
+
+        if status == messaging::ACTIONED && message.hidden_at.is_none() {
+            let mut hidden: messages::ActiveModel = message.into();
+            hidden.hidden_at = Set(Some(now));
+            hidden.hidden_by = Set(context.actor_id);
+            message = hidden.update(&txn).await?;
+            AuditRepository::record(
+                &txn,
+                context,
+                "hide",
+                "messages",
+                message.id,
+                None,
+                Some(json!({ "hidden_at": now, "report_id": id })),
+            )
+            .await?;
+        }
+
+        let report = MessageReports::find_by_id(id)
+            .one(&txn)
+            .await?
+            .ok_or(MessagingError::ReportNotFound)?;
+        AuditRepository::record(
+            &txn,
+            context,
+            "resolve",
+            "message_reports",
+            id,
+            Some(json!({ "status": messaging::OPEN })),
+            Some(json!({ "status": report.status, "resolution": report.resolution })),
+        )
+        .await?;
+        txn.commit().await?;
+        Ok(report_response(report, message))
+    }
+
+    // Stop or allow replies in a thread
+    pub async fn set_locked(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        locked: bool,
+        context: &AuditContext,
+    ) -> Result<message_threads::Model, MessagingError> {
+        let txn = db.begin().await?;
+        let thread = MessageThreads::scoped(tenant_id)
+            .filter(message_threads::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(MessagingError::ThreadNotFound)?;
+        let before = json!({ "is_locked": thread.is_locked });
+        let mut thread: message_threads::ActiveModel = thread.into();
+        thread.is_locked = Set(locked);
+        let thread = thread.update(&txn).await?;
+
+        let action = if locked { "lock" } else { "unlock" };
+        let after = json!({ "is_locked": thread.is_locked });
+        AuditRepository::record(&txn, context, action, "message_threads", id, Some(before), Some(after)).await?;
+        txn.commit().await?;
+        Ok(thread)
+    }
+
+    pub async fn settings(db: &DatabaseConnection, tenant_id: i32) -> Result<Rules, DbErr> {
+        rules(db, tenant_id).await
+    }
+
+    pub async fn update_settings(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        settings: &SafeguardingSettings,
+        context: &AuditContext,
+    ) -> Result<messaging_settings::Model, DbErr> {
+        let txn = db.begin().await?;
+        let existing = MessagingSettings::find_by_id(tenant_id).lock_exclusive().one(&txn).await?;
+        let before = audit::snapshot(&SafeguardingSettings::from(match &existing {
+            Some(existing) => Rules::from(existing),
+            None => Rules::default(),
+        }));
+
+        let mut model = messaging_settings::ActiveModel {
+            tenant_id: Set(tenant_id),
+            archive_staff_student: Set(settings.archive_staff_student),
+            archive_student_student: Set(settings.archive_student_student),
+            students_may_message_students: Set(settings.students_may_message_students),
+            updated_at: Set(Utc::now().naive_utc()),
+        };
+        let saved = match existing {
+            Some(_) => {
+                model.tenant_id = Unchanged(tenant_id);
+                model.update(&txn).await?
+            }
+            None => model.insert(&txn).await?,
+        };
+
+        let after = audit::snapshot(&SafeguardingSettings::from(saved.clone()));
+        AuditRepository::record(&txn, context, "update", "messaging_settings", tenant_id, before, after).await?;
+        txn.commit().await?;
+        Ok(saved)
+    }
+
+    // Archived messages, oldest first
+    pub async fn archive(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &ArchiveQuery,
+    ) -> Result<Vec<message_archive::Model>, DbErr> {
+        let mut select = MessageArchive::scoped(tenant_id);
+        if let Some(user_id) = query.user_id {
+            select = select.filter(Expr::cust_with_values(
+                "\"participants\" @> $1",
+                [json!([{ "user_id": user_id }])],
+            ));
+        }
+        if let Some(thread_id) = query.thread_id {
+            select = select.filter(message_archive::Column::ThreadId.eq(thread_id));
+        }
+        if let Some(from) = query.from {
+            select = select.filter(message_archive::Column::SentAt.gte(from.and_time(chrono::NaiveTime::MIN)));
+        }
+        if let Some(to) = query.to.and_then(|to| to.succ_opt()) {
+            select = select.filter(message_archive::Column::SentAt.lt(to.and_time(chrono::NaiveTime::MIN)));
+        }
+        select
+            .order_by_asc(message_archive::Column::SentAt)
+            .order_by_asc(message_archive::Column::Id)
+            .all(db)
+            .await
+    }
+
+    pub async fn guardians(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student_id: i32,
+    ) -> Result<Vec<guardian_links::Model>, MessagingError> {
+        user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(student_id))
+            .one(db)
+            .await?
+            .ok_or(MessagingError::UserNotFound)?;
+        Ok(GuardianLinks::find()
+            .filter(guardian_links::Column::StudentId.eq(student_id))
+            .order_by_asc(guardian_links::Column::Id)
+            .all(db)
+            .await?)
+    }
+
+    pub async fn link_guardian(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student_id: i32,
+        request: &GuardianLinkRequest,
+        context: &AuditContext,
+    ) -> Result<guardian_links::Model, MessagingError> {
+        let txn = db.begin().await?;
+        let found = user_repository::live(tenant_id)
+            .filter(users::Column::Id.is_in([student_id, request.guardian_id]))
+            .all(&txn)
+            .await?;
+        let student = found
+            .iter()
+            .find(|user| user.id == student_id)
+            .ok_or(MessagingError::UserNotFound)?;
+        if kind(&student.role) != Some(Kind::Student) {
+            return Err(MessagingError::NotStudent);
+        }
+        let guardian = found
+            .iter()
+            .find(|user| user.id == request.guardian_id)
+            .ok_or(MessagingError::UserNotFound)?;
+        if kind(&guardian.role) != Some(Kind::Guardian) {
+            return Err(MessagingError::NotGuardian);
+        }
+        let linked = GuardianLinks::find()
+            .filter(guardian_links::Column::GuardianId.eq(request.guardian_id))
+            .filter(guardian_links::Column::StudentId.eq(student_id))
+            .count(&txn)
+            .await?;
+        if linked > 0 {
+            return Err(MessagingError::AlreadyLinked);
+        }
+
+        let link = guardian_links::ActiveModel {
+            guardian_id: Set(request.guardian_id),
+            student_id: Set(student_id),
+            relationship: Set(request
+                .relationship
+                .as_deref()
+                .map(str::trim)
+                .filter(|relationship| !relationship.is_empty())
+                .map(str::to_string)),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&GuardianLinkResponse::from(link.clone()));
+        AuditRepository::record(&txn, context, "create", "guardian_links", link.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(link)
+    }
+
+    pub async fn unlink_guardian(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        student_id: i32,
+        guardian_id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let student = user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(student_id))
+            .count(&txn)
+            .await?;
+        if student == 0 {
+            return Ok(false);
+        }
+        let Some(link) = GuardianLinks::find()
+            .filter(guardian_links::Column::GuardianId.eq(guardian_id))
+            .filter(guardian_links::Column::StudentId.eq(student_id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let snapshot = audit::snapshot(&GuardianLinkResponse::from(link.clone()));
+        let id = link.id;
+        link.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "guardian_links", id, snapshot, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+}
+
+async fn rules<C: ConnectionTrait>(conn: &C, tenant_id: i32) -> Result<Rules, DbErr> {
+    Ok(MessagingSettings::find_by_id(tenant_id)
+        .one(conn)
+        .await?
+        .map(|settings| Rules::from(&settings))
+        .unwrap_or_default())
+}
+
+fn class(membership: class_memberships::Model) -> Class {
+    Class {
+        grade: membership.grade,
+        section: membership.section,
+    }
+}
+
+// Active users of the school with what decides whom they may message:
+// their class, their guardians or children, and their children's classes
+async fn parties<C: ConnectionTrait>(conn: &C, tenant_id: i32, ids: &[i32]) -> Result<Vec<Party>, DbErr> {
+    let found = user_repository::live(tenant_id)
+        .filter(users::Column::Id.is_in(ids.iter().copied()))
+        .filter(users::Column::IsActive.eq(true))
+        .find_also_related(ClassMemberships)
+        .all(conn)
+        .await?;
+    let links = GuardianLinks::find()
+        .filter(
+            Condition::any()
+                .add(guardian_links::Column::GuardianId.is_in(ids.iter().copied()))
+                .add(guardian_links::Column::StudentId.is_in(ids.iter().copied())),
+        )
+        .all(conn)
+        .await?;
+    let children: Vec<i32> = links
+        .iter()
+        .filter(|link| ids.contains(&link.guardian_id))
+        .map(|link| link.student_id)
+        .collect();
+    let child_classes: HashMap<i32, Class> = ClassMemberships::find()
+        .filter(class_memberships::Column::UserId.is_in(children))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|membership| (membership.user_id, class(membership)))
+        .collect();
+
+    Ok(found
+        .into_iter()
+        .map(|(user, membership)| {
+            let linked: Vec<i32> = links
+                .iter()
+                .filter_map(|link| match user.id {
+                    id if id == link.guardian_id => Some(link.student_id),
+                    id if id == link.student_id => Some(link.guardian_id),
+                    _ => None,
+                })
+                .collect();
+            let classes = match kind(&user.role) {
+                Some(Kind::Guardian) => linked.iter().filter_map(|child| child_classes.get(child).cloned()).collect(),
+                _ => membership.map(class).into_iter().collect(),
+            };
+            Party {
+                id: user.id,
+                name: user.full_name,
+                role: user.role,
+                classes,
+                linked,
+            }
+        })
+        .collect())
+}
+
+// Everyone in a thread, whatever the state of their account, as recorded
+// in the archive
+async fn members<C: ConnectionTrait>(conn: &C, thread_id: i32) -> Result<Vec<ArchivedParticipant>, DbErr> {
+    Ok(MessageParticipants::find()
+        .filter(message_participants::Column::ThreadId.eq(thread_id))
+        .find_also_related(Users)
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|(_, user)| {
+            user.map(|user| ArchivedParticipant {
+                user_id: user.id,
+                full_name: user.full_name,
+                role: user.role,
+            })
+        })
+        .collect())
+}
+
+// Keep a copy of a message if the school's rules call for it
+async fn archive<C: ConnectionTrait>(
+    conn: &C,
+    thread: &message_threads::Model,
+    message: &messages::Model,
+    members: &[ArchivedParticipant],
+    rules: &Rules,
+) -> Result<(), DbErr> {
+    let Some(reason) = archive_reason(members.iter().map(|member| member.role.as_str()), rules) else {
+        return Ok(());
+    };
+    let sender = members.iter().find(|member| Some(member.user_id) == message.sender_id);
+    let participants = serde_json::to_value(members).map_err(|e| DbErr::Custom(e.to_string()))?;
+    message_archive::ActiveModel {
+        tenant_id: Set(thread.tenant_id),
+        thread_id: Set(thread.id),
+        message_id: Set(message.id),
+        subject: Set(thread.subject.clone()),
+        sender_id: Set(message.sender_id),
+        sender_name: Set(sender.map(|sender| sender.full_name.clone()).unwrap_or_default()),
+        sender_role: Set(sender.map(|sender| sender.role.clone()).unwrap_or_default()),
+        participants: Set(participants),
+        body: Set(message.body.clone()),
+        reason: Set(reason.to_string()),
+        sent_at: Set(message.created_at),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    Ok(())
+}
+
+// Participants of each thread, with names and roles
+async fn participants<C: ConnectionTrait>(
+    conn: &C,
+    thread_ids: &[i32],
+) -> Result<HashMap<i32, Vec<ParticipantResponse>>, DbErr> {
+    let mut grouped: HashMap<i32, Vec<ParticipantResponse>> = HashMap::new();
+    let found = MessageParticipants::find()
+        .filter(message_participants::Column::ThreadId.is_in(thread_ids.iter().copied()))
+        .find_also_related(Users)
+        .order_by_asc(message_participants::Column::Id)
+        .all(conn)
+        .await?;
+    for (participant, user) in found {
+        let Some(user) = user else { continue };
+        grouped.entry(participant.thread_id).or_default().push(ParticipantResponse {
+            user_id: user.id,
+            full_name: user.full_name,
+            role: user.role,
+            last_read_at: participant.last_read_at.map(format_time),
+        });
+    }
+    Ok(grouped)
+}
+
+// Attachment metadata for each message
+async fn attachments<C: ConnectionTrait>(
+    conn: &C,
+    message_ids: Vec<i32>,
+) -> Result<HashMap<i32, Vec<AttachmentResponse>>, DbErr> {
+    let mut grouped: HashMap<i32, Vec<AttachmentResponse>> = HashMap::new();
+    if message_ids.is_empty() {
+        return Ok(grouped);
+    }
+    let rows = MessageAttachments::find()
+        .select_only()
+        .columns([
+            message_attachments::Column::Id,
+            message_attachments::Column::MessageId,
+            message_attachments::Column::Filename,
+            message_attachments::Column::ContentType,
+            message_attachments::Column::Length,
+            message_attachments::Column::CreatedAt,
+        ])
+        .filter(message_attachments::Column::MessageId.is_in(message_ids))
+        .order_by_asc(message_attachments::Column::Id)
+        .into_tuple::<(i32, i32, String, String, i64, NaiveDateTime)>()
+        .all(conn)
+        .await?;
+    for (id, message_id, filename, content_type, length, created_at) in rows {
+        grouped.entry(message_id).or_default().push(AttachmentResponse {
+            id,
+            message_id,
+            filename,
+            content_type,
+            length,
+            created_at: format_time(created_at),
+        });
+    }
+    Ok(grouped)
+}
+
+// A message and the school thread it is in
+async fn message_in_thread<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    message_id: i32,
+) -> Result<(messages::Model, message_threads::Model), MessagingError> {
+    match Messages::find_by_id(message_id)
+        .find_also_related(MessageThreads)
+        .filter(message_threads::Column::TenantId.eq(tenant_id))
+        .one(conn)
+        .await?
+    {
+        Some((message, Some(thread))) => Ok((message, thread)),
+        _ => Err(MessagingError::MessageNotFound),
+    }
+}
+
+async fn is_participant<C: ConnectionTrait>(conn: &C, thread_id: i32, user_id: i32) -> Result<bool, DbErr> {
+    let count = MessageParticipants::find()
+        .filter(message_participants::Column::ThreadId.eq(thread_id))
+        .filter(message_participants::Column::UserId.eq(user_id))
+        .count(conn)
+        .await?;
+    Ok(count > 0)
+}
+
+// Move the user's read marker in a thread forward to `at`; returns how many
+// participant rows matched
+async fn read_up_to<C: ConnectionTrait>(conn: &C, thread_id: i32, user_id: i32, at: NaiveDateTime) -> Result<u64, DbErr> {
+    let result = MessageParticipants::update_many()
+        .col_expr(message_participants::Column::LastReadAt, Expr::value(at))
+        .filter(message_participants::Column::ThreadId.eq(thread_id))
+        .filter(message_participants::Column::UserId.eq(user_id))
+        .exec(conn)
+        .await?;
+    Ok(result.rows_affected)
+}
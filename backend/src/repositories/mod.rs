@@ -1 +1,21 @@
-pub mod user_repository;
\ No newline at end of file
+pub mod admissions_repository;
+pub mod announcement_repository;
+pub mod api_key_repository;
+pub mod audit_repository;
+pub mod data_subject_repository;
+pub mod email_repository;
+pub mod fee_repository;
+pub mod hostel_repository;
+pub mod impersonation_repository;
+pub mod library_repository;
+pub mod lti_repository;
+pub mod messaging_repository;
+pub mod oidc_repository;
+pub mod password_repository;
+pub mod payment_repository;
+pub mod profile_repository;
+pub mod tenant_repository;
+pub mod transport_repository;
+pub mod two_factor_repository;
+pub mod user_repository;
+pub mod xapi_repository;
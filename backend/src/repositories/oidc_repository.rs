@@ -0,0 +1,57 @@
+use chrono::{Duration, Utc};
+use sea_orm::*;
+
+use crate::entities::{oidc_login_states, prelude::*};
+
+// How long the user has to finish signing in at the identity provider
+const LOGIN_STATE_TTL_MINUTES: i64 = 10;
+
+pub struct OidcRepository;
+
+impl OidcRepository {
+    // Remember school, state, nonce and PKCE verifier of a login in progress
+    pub async fn save_login_state(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        state: String,
+        nonce: String,
+        code_verifier: String,
+    ) -> Result<(), DbErr> {
+        let login_state = oidc_login_states::ActiveModel {
+            tenant_id: Set(tenant_id),
+            state: Set(state),
+            nonce: Set(nonce),
+            code_verifier: Set(code_verifier),
+            expires_at: Set((Utc::now() + Duration::minutes(LOGIN_STATE_TTL_MINUTES)).naive_utc()),
+            ..Default::default()
+        };
+        login_state.insert(db).await?;
+
+        // Opportunistically clear abandoned logins
+        OidcLoginStates::delete_many()
+            .filter(oidc_login_states::Column::ExpiresAt.lt(Utc::now().naive_utc()))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    // Consume a login state; each state can only be used once
+    pub async fn take_login_state(
+        db: &DatabaseConnection,
+        state: &str,
+    ) -> Result<Option<oidc_login_states::Model>, DbErr> {
+        let Some(login_state) = OidcLoginStates::find_by_id(state).one(db).await? else {
+            return Ok(None);
+        };
+        // Of two requests racing with the same state only the one deleting it wins
+        if OidcLoginStates::delete_by_id(state).exec(db).await?.rows_affected != 1 {
+            return Ok(None);
+        }
+
+        if login_state.expires_at < Utc::now().naive_utc() {
+            return Ok(None);
+        }
+        Ok(Some(login_state))
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{Duration, SubsecRound, Utc};
+use sea_orm::{sea_query::Expr, *};
+
+use crate::application::audit::AuditContext;
+use crate::entities::{password_history, password_reset_tokens, prelude::*, users};
+use crate::repositories::audit_repository::AuditRepository;
+
+pub struct PasswordRepository;
+
+impl PasswordRepository {
+    // The user's current password hash followed by up to `limit` earlier ones, newest first
+    pub async fn previous_hashes(
+        db: &DatabaseConnection,
+        user: &users::Model,
+        limit: usize,
+    ) -> Result<Vec<String>, DbErr> {
+        let history = PasswordHistory::find()
+            .filter(password_history::Column::UserId.eq(user.id))
+            .order_by_desc(password_history::Column::Id)
+            .limit(limit as u64)
+            .all(db)
+            .await?;
+
+        Ok(std::iter::once(user.password_hash.clone())
+            .chain(history.into_iter().map(|row| row.password_hash))
+            .collect())
+    }
+
+    // Replace a user's password, remember the old one, cancel open reset links
+    // and revoke the access tokens issued so far.
+    // The audit entry names the `action` only; hashes stay out of the log.
+    pub async fn set_password(
+        db: &DatabaseConnection,
+        user: users::Model,
+        password_hash: String,
+        history_size: usize,
+        action: &str,
+        context: &AuditContext,
+    ) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        let user_id = user.id;
+
+        if history_size > 0 {
+            let old = password_history::ActiveModel {
+                user_id: Set(user_id),
+                password_hash: Set(user.password_hash.clone()),
+                ..Default::default()
+            };
+            old.insert(&txn).await?;
+
+            // Keep only the newest `history_size` entries
+            let keep: Vec<i32> = PasswordHistory::find()
+                .filter(password_history::Column::UserId.eq(user_id))
+                .order_by_desc(password_history::Column::Id)
+                .limit(history_size as u64)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(|row| row.id)
+                .collect();
+            PasswordHistory::delete_many()
+                .filter(password_history::Column::UserId.eq(user_id))
+                .filter(password_history::Column::Id.is_not_in(keep))
+                .exec(&txn)
+                .await?;
+        }
+
+        // Tokens carry whole seconds, and one issued later in this second is new
+        let now = Utc::now().naive_utc();
+        let mut user: users::ActiveModel = user.into();
+        user.password_hash = Set(password_hash);
+        user.password_changed_at = Set(Some(now.trunc_subsecs(0)));
+        user.updated_at = Set(now);
+        user.update(&txn).await?;
+
+        PasswordResetTokens::delete_many()
+            .filter(password_reset_tokens::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+
+        AuditRepository::record(&txn, context, action, "users", user_id, None, None).await?;
+        txn.commit().await
+    }
+
+    // Store a reset token (hashed); earlier unused tokens of the user stop working
+    pub async fn create_reset_token(
+        db: &DatabaseConnection,
+        user_id: i32,
+        token_hash: String,
+        ttl_minutes: i64,
+    ) -> Result<(), DbErr> {
+        PasswordResetTokens::delete_many()
+            .filter(
+                Condition::any()
+                    .add(password_reset_tokens::Column::UserId.eq(user_id))
+                    .add(password_reset_tokens::Column::ExpiresAt.lt(Utc::now().naive_utc())),
+            )
+            .exec(db)
+            .await?;
+
+        let token = password_reset_tokens::ActiveModel {
+            token_hash: Set(token_hash),
+            user_id: Set(user_id),
+            expires_at: Set((Utc::now() + Duration::minutes(ttl_minutes)).naive_utc()),
+            used_at: Set(None),
+            ..Default::default()
+        };
+        token.insert(db).await?;
+        Ok(())
+    }
+
+    // The user of a reset token that is neither expired nor used. The token
+    // is only ever mailed to that user, so it also names their school.
+    pub async fn find_reset_token(db: &DatabaseConnection, token_hash: &str) -> Result<Option<users::Model>, DbErr> {
+        let token = PasswordResetTokens::find_by_id(token_hash)
+            .filter(password_reset_tokens::Column::UsedAt.is_null())
+            .filter(password_reset_tokens::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+            .one(db)
+            .await?;
+
+        match token {
+            Some(token) => {
+                Users::find_by_id(token.user_id)
+                    .filter(users::Column::DeletedAt.is_null())
+                    .one(db)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Mark a reset token used. Conditional update, so a token can never be
+    // used twice, even by concurrent requests; false means it already was.
+    pub async fn spend_reset_token(db: &DatabaseConnection, token_hash: &str) -> Result<bool, DbErr> {
+        let now = Utc::now().naive_utc();
+        let result = PasswordResetTokens::update_many()
+            .col_expr(password_reset_tokens::Column::UsedAt, Expr::value(now))
+            .filter(password_reset_tokens::Column::TokenHash.eq(token_hash))
+            .filter(password_reset_tokens::Column::UsedAt.is_null())
+            .filter(password_reset_tokens::Column::ExpiresAt.gt(now))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
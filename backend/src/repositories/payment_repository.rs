@@ -0,0 +1,343 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::fees::{self, FeeError};
+use crate::application::payments::{
+    refundable, Reconciliation, METHOD_ONLINE, REFUND_FAILED, REFUND_PENDING, REFUND_SUCCEEDED, SESSION_COMPLETED,
+    SESSION_EXPIRED, SESSION_OPEN,
+};
+use crate::dto::payments::{CheckoutResponse, RefundResponse};
+use crate::entities::{checkout_sessions, invoices, payment_webhook_events, payments, prelude::*, refunds};
+use crate::infrastructure::payments::{
+    CheckoutRequest, CheckoutSession, ProviderRefund, RefundStatus, VerifiedEvent, WebhookEvent,
+};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::fee_repository::FeeRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+
+pub struct PaymentRepository;
+
+impl PaymentRepository {
+    // Remember a checkout opened with the provider so its webhook can be
+    // matched to the invoice
+    pub async fn create_session(
+        db: &DatabaseConnection,
+        invoice: &invoices::Model,
+        provider: &str,
+        request: &CheckoutRequest,
+        session: &CheckoutSession,
+        created_by: Option<i32>,
+        context: &AuditContext,
+    ) -> Result<checkout_sessions::Model, DbErr> {
+        let txn = db.begin().await?;
+        let session = checkout_sessions::ActiveModel {
+            tenant_id: Set(invoice.tenant_id),
+            invoice_id: Set(invoice.id),
+            provider: Set(provider.to_string()),
+            provider_session_id: Set(session.id.clone()),
+            amount: Set(request.amount),
+            currency: Set(request.currency.clone()),
+            checkout_url: Set(session.url.clone()),
+            status: Set(SESSION_OPEN.to_string()),
+            created_by: Set(created_by),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let snapshot = audit::snapshot(&CheckoutResponse::from(session.clone()));
+        AuditRepository::record(&txn, context, "create", "checkout_sessions", session.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(session)
+    }
+
+    // Apply a verified webhook event. Each event is handled once: redeliveries
+    // find their id already stored, and a payment already recorded for a
+    // session is never recorded again.
+    pub async fn reconcile(
+        db: &DatabaseConnection,
+        provider: &str,
+        verified: &VerifiedEvent,
+        context: &AuditContext,
+    ) -> Result<Reconciliation, DbErr> {
+        let txn = db.begin().await?;
+        let inserted = PaymentWebhookEvents::insert(payment_webhook_events::ActiveModel {
+            provider: Set(provider.to_string()),
+            event_id: Set(verified.id.clone()),
+            event_type: Set(verified.event.kind().to_string()),
+            received_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                payment_webhook_events::Column::Provider,
+                payment_webhook_events::Column::EventId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+        if inserted == 0 {
+            return Ok(Reconciliation::Duplicate);
+        }
+
+        let outcome = match &verified.event {
+            WebhookEvent::CheckoutCompleted {
+                session_id,
+                payment_id,
+                amount,
+            } => Self::complete_session(&txn, provider, session_id, payment_id, *amount, context).await?,
+            WebhookEvent::CheckoutExpired { session_id } => {
+                let expired = CheckoutSessions::update_many()
+                    .col_expr(checkout_sessions::Column::Status, sea_query::Expr::value(SESSION_EXPIRED))
+                    .filter(checkout_sessions::Column::Provider.eq(provider))
+                    .filter(checkout_sessions::Column::ProviderSessionId.eq(session_id.as_str()))
+                    .filter(checkout_sessions::Column::Status.eq(SESSION_OPEN))
+                    .exec(&txn)
+                    .await?;
+                if expired.rows_affected == 0 {
+                    Reconciliation::Unmatched
+                } else {
+                    Reconciliation::SessionExpired
+                }
+            }
+            WebhookEvent::RefundSucceeded { refund_id } | WebhookEvent::RefundFailed { refund_id } => {
+                let succeeded = matches!(verified.event, WebhookEvent::RefundSucceeded { .. });
+                let refund = Refunds::find()
+                    .filter(refunds::Column::ProviderRefundId.eq(refund_id.as_str()))
+                    .filter(refunds::Column::Status.eq(REFUND_PENDING))
+                    .lock_exclusive()
+                    .one(&txn)
+                    .await?;
+                match refund {
+                    Some(refund) => {
+                        let context = AuditContext {
+                            tenant_id: Some(refund.tenant_id),
+                            ..context.clone()
+                        };
+                        let refund = Self::settle(&txn, refund, None, succeeded, &context).await?;
+                        Reconciliation::RefundSettled {
+                            refund_id: refund.id,
+                            status: if succeeded { REFUND_SUCCEEDED } else { REFUND_FAILED },
+                        }
+                    }
+                    None => Reconciliation::Unmatched,
+                }
+            }
+            WebhookEvent::Other(_) => Reconciliation::Ignored,
+        };
+
+        txn.commit().await?;
+        Ok(outcome)
+    }
+
+    // Record the payment a completed checkout took. The captured amount is
+    // what counts, even if it pays past the balance: the money was received.
+    async fn complete_session(
+        txn: &DatabaseTransaction,
+        provider: &str,
+        session_id: &str,
+        provider_payment_id: &str,
+        amount: Decimal,
+        context: &AuditContext,
+    ) -> Result<Reconciliation, DbErr> {
+        let Some(session) = CheckoutSessions::find()
+            .filter(checkout_sessions::Column::Provider.eq(provider))
+            .filter(checkout_sessions::Column::ProviderSessionId.eq(session_id))
+            .lock_exclusive()
+            .one(txn)
+            .await?
+        else {
+            return Ok(Reconciliation::Unmatched);
+        };
+        let recorded = Payments::find()
+            .filter(payments::Column::ProviderPaymentId.eq(provider_payment_id))
+            .one(txn)
+            .await?;
+        if session.status == SESSION_COMPLETED || recorded.is_some() {
+            return Ok(Reconciliation::Duplicate);
+        }
+
+        let invoice = Invoices::find_by_id(session.invoice_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!("invoice {}", session.invoice_id)))?;
+        let balance = invoice.total - invoice.amount_paid;
+        if amount > balance {
+            tracing::warn!(
+                "Online payment {} of {} on {} is more than its balance of {}",
+                provider_payment_id,
+                amount,
+                fees::invoice_number(invoice.id),
+                balance
+            );
+        }
+
+        let context = AuditContext {
+            tenant_id: Some(session.tenant_id),
+            ..context.clone()
+        };
+        let payment = payments::ActiveModel {
+            amount: Set(fees::round_money(amount)),
+            method: Set(METHOD_ONLINE.to_string()),
+            reference: Set(Some(provider_payment_id.to_string())),
+            recorded_by: Set(session.created_by),
+            provider_payment_id: Set(Some(provider_payment_id.to_string())),
+            ..Default::default()
+        };
+        let (payment, invoice) = FeeRepository::apply_payment(txn, invoice, payment, &context).await?;
+
+        let mut session: checkout_sessions::ActiveModel = session.into();
+        session.status = Set(SESSION_COMPLETED.to_string());
+        session.payment_id = Set(Some(payment.id));
+        session.completed_at = Set(Some(Utc::now().naive_utc()));
+        session.update(txn).await?;
+
+        Ok(Reconciliation::PaymentRecorded {
+            payment_id: payment.id,
+            invoice_id: invoice.id,
+        })
+    }
+
+    pub async fn list_refunds(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        payment_id: i32,
+    ) -> Result<Vec<refunds::Model>, DbErr> {
+        Refunds::scoped(tenant_id)
+            .filter(refunds::Column::PaymentId.eq(payment_id))
+            .order_by_asc(refunds::Column::Id)
+            .all(db)
+            .await
+    }
+
+    // Reserve a refund on a payment. It stays pending until it is settled,
+    // straight away for money handed back in person, or once the provider
+    // confirms it for online payments.
+    pub async fn begin_refund(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        payment_id: i32,
+        amount: Option<Decimal>,
+        reason: &str,
+        requested_by: Option<i32>,
+        context: &AuditContext,
+    ) -> Result<(refunds::Model, payments::Model), FeeError> {
+        let txn = db.begin().await?;
+        let payment = Payments::scoped(tenant_id)
+            .filter(payments::Column::Id.eq(payment_id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(FeeError::PaymentNotFound)?;
+        let earlier = payment
+            .find_related(Refunds)
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|refund| (refund.amount, refund.status));
+        let left = refundable(payment.amount, earlier);
+        let amount = fees::round_money(amount.unwrap_or(left));
+        if amount <= Decimal::ZERO || amount > left {
+            return Err(FeeError::RefundTooLarge { amount, refundable: left });
+        }
+
+        let refund = refunds::ActiveModel {
+            tenant_id: Set(tenant_id),
+            payment_id: Set(payment.id),
+            amount: Set(amount),
+            reason: Set(reason.to_string()),
+            status: Set(REFUND_PENDING.to_string()),
+            requested_by: Set(requested_by),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let snapshot = audit::snapshot(&RefundResponse::from(refund.clone()));
+        AuditRepository::record(&txn, context, "create", "refunds", refund.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok((refund, payment))
+    }
+
+    // Apply the provider's answer to a refund request
+    pub async fn record_provider_refund(
+        db: &DatabaseConnection,
+        refund: refunds::Model,
+        provider_refund: &ProviderRefund,
+        context: &AuditContext,
+    ) -> Result<refunds::Model, DbErr> {
+        let txn = db.begin().await?;
+        let refund = match provider_refund.status {
+            RefundStatus::Succeeded => {
+                Self::settle(&txn, refund, Some(provider_refund.id.clone()), true, context).await?
+            }
+            RefundStatus::Pending => {
+                let mut refund: refunds::ActiveModel = refund.into();
+                refund.provider_refund_id = Set(Some(provider_refund.id.clone()));
+                refund.update(&txn).await?
+            }
+        };
+        txn.commit().await?;
+        Ok(refund)
+    }
+
+    // Settle a refund with no provider involved, or fail one the provider refused
+    pub async fn settle_refund(
+        db: &DatabaseConnection,
+        refund: refunds::Model,
+        succeeded: bool,
+        context: &AuditContext,
+    ) -> Result<refunds::Model, DbErr> {
+        let txn = db.begin().await?;
+        let refund = Self::settle(&txn, refund, None, succeeded, context).await?;
+        txn.commit().await?;
+        Ok(refund)
+    }
+
+    // A succeeded refund comes off the payment and the invoice's paid amount,
+    // reopening the invoice; a failed one frees the amount to refund again
+    async fn settle(
+        txn: &DatabaseTransaction,
+        refund: refunds::Model,
+        provider_refund_id: Option<String>,
+        succeeded: bool,
+        context: &AuditContext,
+    ) -> Result<refunds::Model, DbErr> {
+        let before = audit::snapshot(&RefundResponse::from(refund.clone()));
+        if succeeded {
+            let payment = Payments::find_by_id(refund.payment_id)
+                .lock_exclusive()
+                .one(txn)
+                .await?
+                .ok_or(DbErr::RecordNotFound(format!("payment {}", refund.payment_id)))?;
+            let invoice = Invoices::find_by_id(payment.invoice_id)
+                .lock_exclusive()
+                .one(txn)
+                .await?
+                .ok_or(DbErr::RecordNotFound(format!("invoice {}", payment.invoice_id)))?;
+
+            let refunded_amount = payment.refunded_amount + refund.amount;
+            let mut payment: payments::ActiveModel = payment.into();
+            payment.refunded_amount = Set(refunded_amount);
+            payment.update(txn).await?;
+            let amount_paid = invoice.amount_paid - refund.amount;
+            FeeRepository::set_amount_paid(txn, invoice, amount_paid).await?;
+        }
+
+        let mut refund: refunds::ActiveModel = refund.into();
+        refund.status = Set(if succeeded { REFUND_SUCCEEDED } else { REFUND_FAILED }.to_string());
+        refund.settled_at = Set(Some(Utc::now().naive_utc()));
+        if let Some(provider_refund_id) = provider_refund_id {
+            refund.provider_refund_id = Set(Some(provider_refund_id));
+        }
+        let refund = refund.update(txn).await?;
+
+        let after = audit::snapshot(&RefundResponse::from(refund.clone()));
+        AuditRepository::record(txn, context, "settle", "refunds", refund.id, before, after).await?;
+        Ok(refund)
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::Utc;
+use sea_orm::*;
+use serde_json::json;
+
+use crate::application::audit::AuditContext;
+use crate::application::encryption::{EncryptedString, Field, Keyring, LEGACY_FORMAT_PREFIX};
+use crate::dto::profile::UpdateProfileRequest;
+use crate::entities::{prelude::*, user_profiles, users};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::user_repository;
+
+pub struct ProfileRepository;
+
+// Encrypted column of a user's profile, for sealing and opening its value
+pub fn field(column: &str, user_id: i32) -> Field<'_> {
+    Field::new("user_profiles", column, user_id)
+}
+
+impl ProfileRepository {
+    pub async fn find(db: &DatabaseConnection, user_id: i32) -> Result<Option<user_profiles::Model>, DbErr> {
+        UserProfiles::find_by_id(user_id).one(db).await
+    }
+
+    // Replace a user's profile, sealing every field under the active key.
+    // The audit entry names the changed fields but never holds their values.
+    pub async fn save(
+        db: &DatabaseConnection,
+        keyring: &Keyring,
+        user_id: i32,
+        request: &UpdateProfileRequest,
+        context: &AuditContext,
+    ) -> Result<user_profiles::Model, DbErr> {
+        let txn = db.begin().await?;
+        let existing = UserProfiles::find_by_id(user_id).one(&txn).await?;
+
+        let old = |column: &str, value: fn(&user_profiles::Model) -> Option<&EncryptedString>| {
+            existing
+                .as_ref()
+                .and_then(|profile| keyring.open_opt(value(profile), field(column, user_id)).ok().flatten())
+        };
+        let changed: Vec<&str> = [
+            ("national_id", old("national_id", |p| p.national_id.as_ref()), request.national_id.as_deref()),
+            ("address", old("address", |p| p.address.as_ref()), request.address.as_deref()),
+            ("medical_notes", old("medical_notes", |p| p.medical_notes.as_ref()), request.medical_notes.as_deref()),
+        ]
+        .into_iter()
+        .filter(|(_, old, new)| old.as_deref() != *new)
+        .map(|(field, _, _)| field)
+        .collect();
+
+        let profile = user_profiles::ActiveModel {
+            user_id: Set(user_id),
+            key_id: Set(keyring.active_key_id().to_string()),
+            national_id: Set(keyring.seal_opt(request.national_id.as_deref(), field("national_id", user_id))),
+            national_id_index: Set(request.national_id.as_deref().map(|id| keyring.blind_index(id))),
+            address: Set(keyring.seal_opt(request.address.as_deref(), field("address", user_id))),
+            medical_notes: Set(keyring.seal_opt(request.medical_notes.as_deref(), field("medical_notes", user_id))),
+            updated_at: Set(Utc::now().naive_utc()),
+        };
+        let (action, profile) = if existing.is_some() {
+            ("update", profile.update(&txn).await?)
+        } else {
+            ("create", profile.insert(&txn).await?)
+        };
+        AuditRepository::record(
+            &txn,
+            context,
+            action,
+            "user_profiles",
+            user_id,
+            None,
+            Some(json!({ "changed": changed })),
+        )
+        .await?;
+
+        txn.commit().await?;
+        Ok(profile)
+    }
+
+    // Live users of a school whose national ID matches, via the blind index
+    pub async fn find_users_by_national_id(
+        db: &DatabaseConnection,
+        keyring: &Keyring,
+        tenant_id: i32,
+        national_id: &str,
+    ) -> Result<Vec<users::Model>, DbErr> {
+        user_repository::live(tenant_id)
+            .inner_join(UserProfiles)
+            .filter(user_profiles::Column::NationalIdIndex.eq(keyring.blind_index(national_id)))
+            .order_by_asc(users::Column::Id)
+            .all(db)
+            .await
+    }
+
+    // Profiles sealed with a key other than `active_key` or in the legacy
+    // format, in user id order after `after`
+    pub async fn stale(
+        db: &DatabaseConnection,
+        active_key: &str,
+        after: i32,
+        limit: u64,
+    ) -> Result<Vec<user_profiles::Model>, DbErr> {
+        stale_query(active_key, after, limit).all(db).await
+    }
+
+    // Open and re-seal a profile under the active key, recomputing the blind
+    // index. Only applies if nobody saved the profile in the meantime.
+    pub async fn reencrypt(
+        db: &DatabaseConnection,
+        keyring: &Keyring,
+        profile: user_profiles::Model,
+    ) -> Result<bool, DbErr> {
+        let user_id = profile.user_id;
+        let reseal = |column: &str, value: Option<&EncryptedString>| {
+            keyring
+                .open_opt(value, field(column, user_id))
+                .map(|plaintext| keyring.seal_opt(plaintext.as_deref(), field(column, user_id)))
+                .map_err(|e| DbErr::Custom(e.to_string()))
+        };
+        let national_id = keyring
+            .open_opt(profile.national_id.as_ref(), field("national_id", user_id))
+            .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+        let result = UserProfiles::update_many()
+            .set(user_profiles::ActiveModel {
+                key_id: Set(keyring.active_key_id().to_string()),
+                national_id: Set(keyring.seal_opt(national_id.as_deref(), field("national_id", user_id))),
+                national_id_index: Set(national_id.as_deref().map(|id| keyring.blind_index(id))),
+                address: Set(reseal("address", profile.address.as_ref())?),
+                medical_notes: Set(reseal("medical_notes", profile.medical_notes.as_ref())?),
+                ..Default::default()
+            })
+            .filter(user_profiles::Column::UserId.eq(profile.user_id))
+            .filter(user_profiles::Column::KeyId.eq(profile.key_id))
+            .filter(user_profiles::Column::UpdatedAt.eq(profile.updated_at))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+fn stale_query(active_key: &str, after: i32, limit: u64) -> Select<UserProfiles> {
+    let legacy = format!("{}%", LEGACY_FORMAT_PREFIX);
+    UserProfiles::find()
+        .filter(
+            Condition::any()
+                .add(user_profiles::Column::KeyId.ne(active_key))
+                .add(user_profiles::Column::NationalId.like(&legacy))
+                .add(user_profiles::Column::Address.like(&legacy))
+                .add(user_profiles::Column::MedicalNotes.like(&legacy)),
+        )
+        .filter(user_profiles::Column::UserId.gt(after))
+        .order_by_asc(user_profiles::Column::UserId)
+        .limit(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_profiles_include_legacy_values_under_the_active_key() {
+        let sql = stale_query("k2", 10, 100).build(DbBackend::Postgres).to_string();
+        assert!(sql.contains(r#""key_id" <> 'k2'"#));
+        for column in ["national_id", "address", "medical_notes"] {
+            assert!(sql.contains(&format!(r#""{}" LIKE 'v1:%'"#, column)), "{}", sql);
+        }
+        assert!(sql.contains(r#""user_id" > 10"#));
+    }
+}
@@ -0,0 +1,307 @@
+use chrono::Utc;
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::auth;
+use crate::application::email;
+use crate::dto::tenant::{CreateTenantRequest, TenantResponse, UpdateTenantRequest};
+use crate::dto::user::UserResponse;
+use crate::entities::{
+    admission_applications, admission_stages, announcements, checkout_sessions, email_outbox, erasure_requests, fee_discounts,
+    fee_schedules, hostel_allocations, hostel_rooms, impersonation_sessions, invoices, library_copies, library_holds,
+    library_loans, library_titles, lti_platforms, message_archive, message_reports, message_threads, payments,
+    prelude::*, refunds, tenants, transport_assignments, transport_drivers, transport_routes, transport_vehicles, users,
+    xapi_attachments, xapi_statements,
+};
+use crate::repositories::audit_repository::AuditRepository;
+
+// Tables whose rows belong to a school. Repositories start every query on
+// them from `scoped`, so rows of another school never come back; tables
+// hanging off these (a user's keys, a platform's launches) are reached
+// through an already scoped row.
+pub trait TenantScoped: EntityTrait {
+    fn tenant_column() -> Self::Column;
+
+    fn scoped(tenant_id: i32) -> Select<Self> {
+        Self::find().filter(Self::tenant_column().eq(tenant_id))
+    }
+}
+
+impl TenantScoped for Users {
+    fn tenant_column() -> users::Column {
+        users::Column::TenantId
+    }
+}
+
+impl TenantScoped for AdmissionStages {
+    fn tenant_column() -> admission_stages::Column {
+        admission_stages::Column::TenantId
+    }
+}
+
+impl TenantScoped for AdmissionApplications {
+    fn tenant_column() -> admission_applications::Column {
+        admission_applications::Column::TenantId
+    }
+}
+
+impl TenantScoped for Announcements {
+    fn tenant_column() -> announcements::Column {
+        announcements::Column::TenantId
+    }
+}
+
+impl TenantScoped for LtiPlatforms {
+    fn tenant_column() -> lti_platforms::Column {
+        lti_platforms::Column::TenantId
+    }
+}
+
+impl TenantScoped for XapiStatements {
+    fn tenant_column() -> xapi_statements::Column {
+        xapi_statements::Column::TenantId
+    }
+}
+
+impl TenantScoped for XapiAttachments {
+    fn tenant_column() -> xapi_attachments::Column {
+        xapi_attachments::Column::TenantId
+    }
+}
+
+impl TenantScoped for ImpersonationSessions {
+    fn tenant_column() -> impersonation_sessions::Column {
+        impersonation_sessions::Column::TenantId
+    }
+}
+
+impl TenantScoped for ErasureRequests {
+    fn tenant_column() -> erasure_requests::Column {
+        erasure_requests::Column::TenantId
+    }
+}
+
+impl TenantScoped for FeeSchedules {
+    fn tenant_column() -> fee_schedules::Column {
+        fee_schedules::Column::TenantId
+    }
+}
+
+impl TenantScoped for FeeDiscounts {
+    fn tenant_column() -> fee_discounts::Column {
+        fee_discounts::Column::TenantId
+    }
+}
+
+impl TenantScoped for Invoices {
+    fn tenant_column() -> invoices::Column {
+        invoices::Column::TenantId
+    }
+}
+
+impl TenantScoped for Payments {
+    fn tenant_column() -> payments::Column {
+        payments::Column::TenantId
+    }
+}
+
+impl TenantScoped for CheckoutSessions {
+    fn tenant_column() -> checkout_sessions::Column {
+        checkout_sessions::Column::TenantId
+    }
+}
+
+impl TenantScoped for Refunds {
+    fn tenant_column() -> refunds::Column {
+        refunds::Column::TenantId
+    }
+}
+
+impl TenantScoped for HostelRooms {
+    fn tenant_column() -> hostel_rooms::Column {
+        hostel_rooms::Column::TenantId
+    }
+}
+
+impl TenantScoped for HostelAllocations {
+    fn tenant_column() -> hostel_allocations::Column {
+        hostel_allocations::Column::TenantId
+    }
+}
+
+impl TenantScoped for LibraryTitles {
+    fn tenant_column() -> library_titles::Column {
+        library_titles::Column::TenantId
+    }
+}
+
+impl TenantScoped for LibraryCopies {
+    fn tenant_column() -> library_copies::Column {
+        library_copies::Column::TenantId
+    }
+}
+
+impl TenantScoped for LibraryLoans {
+    fn tenant_column() -> library_loans::Column {
+        library_loans::Column::TenantId
+    }
+}
+
+impl TenantScoped for LibraryHolds {
+    fn tenant_column() -> library_holds::Column {
+        library_holds::Column::TenantId
+    }
+}
+
+impl TenantScoped for TransportVehicles {
+    fn tenant_column() -> transport_vehicles::Column {
+        transport_vehicles::Column::TenantId
+    }
+}
+
+impl TenantScoped for TransportDrivers {
+    fn tenant_column() -> transport_drivers::Column {
+        transport_drivers::Column::TenantId
+    }
+}
+
+impl TenantScoped for TransportRoutes {
+    fn tenant_column() -> transport_routes::Column {
+        transport_routes::Column::TenantId
+    }
+}
+
+impl TenantScoped for TransportAssignments {
+    fn tenant_column() -> transport_assignments::Column {
+        transport_assignments::Column::TenantId
+    }
+}
+
+impl TenantScoped for MessageThreads {
+    fn tenant_column() -> message_threads::Column {
+        message_threads::Column::TenantId
+    }
+}
+
+impl TenantScoped for MessageReports {
+    fn tenant_column() -> message_reports::Column {
+        message_reports::Column::TenantId
+    }
+}
+
+impl TenantScoped for MessageArchive {
+    fn tenant_column() -> message_archive::Column {
+        message_archive::Column::TenantId
+    }
+}
+
+impl TenantScoped for EmailOutbox {
+    fn tenant_column() -> email_outbox::Column {
+        email_outbox::Column::TenantId
+    }
+}
+
+// A stored grading scale; None keeps the default one
+fn scale_json(request: &UpdateTenantRequest) -> serde_json::Value {
+    serde_json::to_value(request.grading_scale.as_deref().unwrap_or_default()).unwrap_or_default()
+}
+
+// A school's email language; None keeps the default one
+fn locale(request: &UpdateTenantRequest) -> String {
+    request.locale.clone().unwrap_or_else(|| email::DEFAULT_LOCALE.to_string())
+}
+
+pub struct TenantRepository;
+
+impl TenantRepository {
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> Result<Option<tenants::Model>, DbErr> {
+        Tenants::find_by_id(id).one(db).await
+    }
+
+    pub async fn find_by_slug(db: &DatabaseConnection, slug: &str) -> Result<Option<tenants::Model>, DbErr> {
+        Tenants::find()
+            .filter(tenants::Column::Slug.eq(slug))
+            .one(db)
+            .await
+    }
+
+    pub async fn find_all(db: &DatabaseConnection) -> Result<Vec<tenants::Model>, DbErr> {
+        Tenants::find().order_by_asc(tenants::Column::Id).all(db).await
+    }
+
+    // Add a school and its first admin in one transaction
+    pub async fn create(
+        db: &DatabaseConnection,
+        request: &CreateTenantRequest,
+        context: &AuditContext,
+    ) -> Result<(TenantResponse, UserResponse), DbErr> {
+        let password_hash = auth::hash_password(&request.admin.password)
+            .map_err(|_| DbErr::Custom("Failed to hash password".to_string()))?;
+
+        let txn = db.begin().await?;
+        let tenant = tenants::ActiveModel {
+            slug: Set(request.slug.clone()),
+            name: Set(request.settings.name.clone()),
+            logo_url: Set(request.settings.logo_url.clone()),
+            grading_scale: Set(scale_json(&request.settings)),
+            timezone: Set(request.settings.timezone.clone()),
+            locale: Set(locale(&request.settings)),
+            brand_color: Set(request.settings.brand_color.clone()),
+            is_active: Set(true),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let admin = users::ActiveModel {
+            tenant_id: Set(tenant.id),
+            email: Set(auth::normalize_email(&request.admin.email)),
+            password_hash: Set(password_hash),
+            full_name: Set(request.admin.full_name.clone()),
+            role: Set("admin".to_string()),
+            is_active: Set(true),
+            auth_source: Set("local".to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let tenant = TenantResponse::from(tenant);
+        let admin = UserResponse::from(admin);
+        AuditRepository::record(&txn, context, "create", "tenants", tenant.id, None, audit::snapshot(&tenant)).await?;
+        let admin_context = AuditContext {
+            tenant_id: Some(tenant.id),
+            ..context.clone()
+        };
+        AuditRepository::record(&txn, &admin_context, "create", "users", admin.id, None, audit::snapshot(&admin)).await?;
+        txn.commit().await?;
+        Ok((tenant, admin))
+    }
+
+    // Replace a school's name, logo, grading scale, timezone and email branding
+    pub async fn update(
+        db: &DatabaseConnection,
+        id: i32,
+        request: &UpdateTenantRequest,
+        context: &AuditContext,
+    ) -> Result<Option<TenantResponse>, DbErr> {
+        let txn = db.begin().await?;
+        let Some(tenant) = Tenants::find_by_id(id).one(&txn).await? else {
+            return Ok(None);
+        };
+
+        let before = audit::snapshot(&TenantResponse::from(tenant.clone()));
+        let mut tenant: tenants::ActiveModel = tenant.into();
+        tenant.name = Set(request.name.clone());
+        tenant.logo_url = Set(request.logo_url.clone());
+        tenant.grading_scale = Set(scale_json(request));
+        tenant.timezone = Set(request.timezone.clone());
+        tenant.locale = Set(locale(request));
+        tenant.brand_color = Set(request.brand_color.clone());
+        tenant.updated_at = Set(Utc::now().naive_utc());
+        let tenant = TenantResponse::from(tenant.update(&txn).await?);
+        AuditRepository::record(&txn, context, "update", "tenants", id, before, audit::snapshot(&tenant)).await?;
+        txn.commit().await?;
+        Ok(Some(tenant))
+    }
+}
@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+
+use sea_orm::*;
+
+use crate::application::audit::{self, AuditContext};
+use crate::application::transport::{self, TransportError};
+use crate::dto::transport::{
+    AssignStudentRequest, AssignmentQuery, AssignmentResponse, DriverRequest, DriverResponse, RouteDetail,
+    RouteRequest, RouteResponse, StopRequest, StopResponse, VehicleRequest, VehicleResponse,
+};
+use crate::entities::{
+    prelude::*, transport_assignments, transport_drivers, transport_routes, transport_stops, transport_vehicles,
+    users,
+};
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+pub struct TransportRepository;
+
+impl TransportRepository {
+    pub async fn list_vehicles(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+    ) -> Result<Vec<transport_vehicles::Model>, DbErr> {
+        TransportVehicles::scoped(tenant_id)
+            .order_by_asc(transport_vehicles::Column::Registration)
+            .all(db)
+            .await
+    }
+
+    pub async fn create_vehicle(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &VehicleRequest,
+        context: &AuditContext,
+    ) -> Result<transport_vehicles::Model, TransportError> {
+        let txn = db.begin().await?;
+        if registration_taken(&txn, tenant_id, &request.registration, None).await? {
+            return Err(TransportError::RegistrationTaken(request.registration.clone()));
+        }
+        let vehicle = transport_vehicles::ActiveModel {
+            tenant_id: Set(tenant_id),
+            registration: Set(request.registration.clone()),
+            description: Set(request.description.clone()),
+            capacity: Set(request.capacity),
+            is_active: Set(request.is_active),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&VehicleResponse::from(vehicle.clone()));
+        AuditRepository::record(&txn, context, "create", "transport_vehicles", vehicle.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(vehicle)
+    }
+
+    // A vehicle cannot shrink below the busiest route it serves
+    pub async fn update_vehicle(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &VehicleRequest,
+        context: &AuditContext,
+    ) -> Result<transport_vehicles::Model, TransportError> {
+        let txn = db.begin().await?;
+        let vehicle = TransportVehicles::scoped(tenant_id)
+            .filter(transport_vehicles::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(TransportError::VehicleNotFound)?;
+        if registration_taken(&txn, tenant_id, &request.registration, Some(id)).await? {
+            return Err(TransportError::RegistrationTaken(request.registration.clone()));
+        }
+        let routes: Vec<i32> = TransportRoutes::find()
+            .select_only()
+            .column(transport_routes::Column::Id)
+            .filter(transport_routes::Column::VehicleId.eq(id))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+        let mut busiest = 0;
+        for route_id in routes {
+            busiest = busiest.max(seats_taken(&txn, route_id).await?);
+        }
+        transport::check_capacity(request.capacity, busiest)?;
+
+        let before = audit::snapshot(&VehicleResponse::from(vehicle.clone()));
+        let mut vehicle: transport_vehicles::ActiveModel = vehicle.into();
+        vehicle.registration = Set(request.registration.clone());
+        vehicle.description = Set(request.description.clone());
+        vehicle.capacity = Set(request.capacity);
+        vehicle.is_active = Set(request.is_active);
+        let vehicle = vehicle.update(&txn).await?;
+
+        let after = audit::snapshot(&VehicleResponse::from(vehicle.clone()));
+        AuditRepository::record(&txn, context, "update", "transport_vehicles", vehicle.id, before, after).await?;
+        txn.commit().await?;
+        Ok(vehicle)
+    }
+
+    pub async fn list_drivers(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+    ) -> Result<Vec<transport_drivers::Model>, DbErr> {
+        TransportDrivers::scoped(tenant_id)
+            .order_by_asc(transport_drivers::Column::FullName)
+            .all(db)
+            .await
+    }
+
+    pub async fn create_driver(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &DriverRequest,
+        context: &AuditContext,
+    ) -> Result<transport_drivers::Model, DbErr> {
+        let txn = db.begin().await?;
+        let driver = transport_drivers::ActiveModel {
+            tenant_id: Set(tenant_id),
+            full_name: Set(request.full_name.clone()),
+            phone: Set(request.phone.clone()),
+            license_number: Set(request.license_number.clone()),
+            license_expires_on: Set(request.license_expires_on),
+            is_active: Set(request.is_active),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&DriverResponse::from(driver.clone()));
+        AuditRepository::record(&txn, context, "create", "transport_drivers", driver.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(driver)
+    }
+
+    pub async fn update_driver(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &DriverRequest,
+        context: &AuditContext,
+    ) -> Result<Option<transport_drivers::Model>, DbErr> {
+        let txn = db.begin().await?;
+        let Some(driver) = TransportDrivers::scoped(tenant_id)
+            .filter(transport_drivers::Column::Id.eq(id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let before = audit::snapshot(&DriverResponse::from(driver.clone()));
+        let mut driver: transport_drivers::ActiveModel = driver.into();
+        driver.full_name = Set(request.full_name.clone());
+        driver.phone = Set(request.phone.clone());
+        driver.license_number = Set(request.license_number.clone());
+        driver.license_expires_on = Set(request.license_expires_on);
+        driver.is_active = Set(request.is_active);
+        let driver = driver.update(&txn).await?;
+
+        let after = audit::snapshot(&DriverResponse::from(driver.clone()));
+        AuditRepository::record(&txn, context, "update", "transport_drivers", driver.id, before, after).await?;
+        txn.commit().await?;
+        Ok(Some(driver))
+    }
+
+    pub async fn list_routes(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<transport_routes::Model>, DbErr> {
+        TransportRoutes::scoped(tenant_id)
+            .order_by_asc(transport_routes::Column::Name)
+            .all(db)
+            .await
+    }
+
+    pub async fn create_route(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        request: &RouteRequest,
+        context: &AuditContext,
+    ) -> Result<transport_routes::Model, TransportError> {
+        let txn = db.begin().await?;
+        check_route(&txn, tenant_id, request, None).await?;
+        let route = transport_routes::ActiveModel {
+            tenant_id: Set(tenant_id),
+            name: Set(request.name.clone()),
+            vehicle_id: Set(request.vehicle_id),
+            driver_id: Set(request.driver_id),
+            is_active: Set(request.is_active),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let snapshot = audit::snapshot(&RouteResponse::from(route.clone()));
+        AuditRepository::record(&txn, context, "create", "transport_routes", route.id, None, snapshot).await?;
+        txn.commit().await?;
+        Ok(route)
+    }
+
+    // Change a route's name, vehicle or driver. A new vehicle must seat the
+    // students already on the route.
+    pub async fn update_route(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        request: &RouteRequest,
+        context: &AuditContext,
+    ) -> Result<transport_routes::Model, TransportError> {
+        let txn = db.begin().await?;
+        let route = lock_route(&txn, tenant_id, id).await?;
+        let vehicle = check_route(&txn, tenant_id, request, Some(id)).await?;
+        if let Some(vehicle) = vehicle {
+            transport::check_capacity(vehicle.capacity, seats_taken(&txn, id).await?)?;
+        }
+
+        let before = audit::snapshot(&RouteResponse::from(route.clone()));
+        let mut route: transport_routes::ActiveModel = route.into();
+        route.name = Set(request.name.clone());
+        route.vehicle_id = Set(request.vehicle_id);
+        route.driver_id = Set(request.driver_id);
+        route.is_active = Set(request.is_active);
+        let route = route.update(&txn).await?;
+
+        let after = audit::snapshot(&RouteResponse::from(route.clone()));
+        AuditRepository::record(&txn, context, "update", "transport_routes", route.id, before, after).await?;
+        txn.commit().await?;
+        Ok(route)
+    }
+
+    // A route with its vehicle, driver, stops in order and seats taken
+    pub async fn route_detail(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+    ) -> Result<Option<RouteDetail>, DbErr> {
+        let Some(route) = TransportRoutes::scoped(tenant_id)
+            .filter(transport_routes::Column::Id.eq(id))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let vehicle = route.find_related(TransportVehicles).one(db).await?;
+        let driver = route.find_related(TransportDrivers).one(db).await?;
+        let stops = route
+            .find_related(TransportStops)
+            .order_by_asc(transport_stops::Column::Position)
+            .all(db)
+            .await?;
+        let seats_taken = seats_taken(db, route.id).await?;
+        Ok(Some(RouteDetail {
+            route: route.into(),
+            vehicle: vehicle.map(VehicleResponse::from),
+            driver: driver.map(DriverResponse::from),
+            stops: stops.into_iter().map(StopResponse::from).collect(),
+            seats_taken,
+        }))
+    }
+
+    // Set a route's stops to `stops`, in calling order. Stops named by id
+    // keep their students; stops left out are removed unless someone boards
+    // there.
+    pub async fn replace_stops(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        route_id: i32,
+        stops: &[StopRequest],
+        context: &AuditContext,
+    ) -> Result<Vec<transport_stops::Model>, TransportError> {
+        let times: Vec<_> = stops.iter().map(|stop| stop.time).collect();
+        transport::check_stop_order(&times)?;
+
+        let txn = db.begin().await?;
+        let route = lock_route(&txn, tenant_id, route_id).await?;
+        let existing = route.find_related(TransportStops).all(&txn).await?;
+        if stops
+            .iter()
+            .filter_map(|stop| stop.id)
+            .any(|id| !existing.iter().any(|stop| stop.id == id))
+        {
+            return Err(TransportError::StopNotFound);
+        }
+
+        let before = audit::snapshot(
+            &existing
+                .iter()
+                .cloned()
+                .map(StopResponse::from)
+                .collect::<Vec<_>>(),
+        );
+        for stop in &existing {
+            if stops.iter().any(|kept| kept.id == Some(stop.id)) {
+                continue;
+            }
+            let boarding = TransportAssignments::find()
+                .filter(transport_assignments::Column::StopId.eq(stop.id))
+                .count(&txn)
+                .await?;
+            if boarding > 0 {
+                return Err(TransportError::StopInUse(stop.name.clone()));
+            }
+            TransportStops::delete_by_id(stop.id).exec(&txn).await?;
+        }
+
+        // Move kept stops out of the way so reordering never collides on
+        // (route_id, position)
+        TransportStops::update_many()
+            .col_expr(
+                transport_stops::Column::Position,
+                sea_query::Expr::col(transport_stops::Column::Position).mul(-1).sub(1),
+            )
+            .filter(transport_stops::Column::RouteId.eq(route_id))
+            .exec(&txn)
+            .await?;
+
+        let mut saved = Vec::with_capacity(stops.len());
+        for (index, stop) in stops.iter().enumerate() {
+            let mut model = transport_stops::ActiveModel {
+                route_id: Set(route_id),
+                position: Set(index as i32 + 1),
+                name: Set(stop.name.clone()),
+                scheduled_time: Set(stop.time),
+                ..Default::default()
+            };
+            let model = match stop.id {
+                Some(id) => {
+                    model.id = Unchanged(id);
+                    model.update(&txn).await?
+                }
+                None => model.insert(&txn).await?,
+            };
+            saved.push(model);
+        }
+
+        let after = audit::snapshot(&saved.iter().cloned().map(StopResponse::from).collect::<Vec<_>>());
+        AuditRepository::record(&txn, context, "update_stops", "transport_routes", route_id, before, after).await?;
+        txn.commit().await?;
+        Ok(saved)
+    }
+
+    // Seat a student on a route at one of its stops, or move a student
+    // already on the route to another stop. The route row is locked so two
+    // assignments cannot take the last seat together.
+    pub async fn assign_student(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        route_id: i32,
+        request: &AssignStudentRequest,
+        context: &AuditContext,
+    ) -> Result<transport_assignments::Model, TransportError> {
+        let txn = db.begin().await?;
+        let route = lock_route(&txn, tenant_id, route_id).await?;
+        TransportStops::find()
+            .filter(transport_stops::Column::Id.eq(request.stop_id))
+            .filter(transport_stops::Column::RouteId.eq(route_id))
+            .one(&txn)
+            .await?
+            .ok_or(TransportError::StopNotFound)?;
+        user_repository::live(tenant_id)
+            .filter(users::Column::Id.eq(request.student_id))
+            .filter(users::Column::Role.eq("student"))
+            .one(&txn)
+            .await?
+            .ok_or(TransportError::StudentNotFound)?;
+
+        let current = TransportAssignments::find()
+            .filter(transport_assignments::Column::RouteId.eq(route_id))
+            .filter(transport_assignments::Column::StudentId.eq(request.student_id))
+            .one(&txn)
+            .await?;
+        let assignment = match current {
+            Some(current) => {
+                let before = audit::snapshot(&AssignmentResponse::from(current.clone()));
+                let mut assignment: transport_assignments::ActiveModel = current.into();
+                assignment.stop_id = Set(request.stop_id);
+                let assignment = assignment.update(&txn).await?;
+                let after = audit::snapshot(&AssignmentResponse::from(assignment.clone()));
+                AuditRepository::record(&txn, context, "update", "transport_assignments", assignment.id, before, after)
+                    .await?;
+                assignment
+            }
+            None => {
+                let capacity = match route.vehicle_id {
+                    Some(vehicle_id) => TransportVehicles::find_by_id(vehicle_id)
+                        .one(&txn)
+                        .await?
+                        .map(|vehicle| vehicle.capacity),
+                    None => None,
+                };
+                transport::check_seat(capacity, seats_taken(&txn, route_id).await?)?;
+                let assignment = transport_assignments::ActiveModel {
+                    tenant_id: Set(tenant_id),
+                    route_id: Set(route_id),
+                    stop_id: Set(request.stop_id),
+                    student_id: Set(request.student_id),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+                let snapshot = audit::snapshot(&AssignmentResponse::from(assignment.clone()));
+                AuditRepository::record(&txn, context, "create", "transport_assignments", assignment.id, None, snapshot)
+                    .await?;
+                assignment
+            }
+        };
+
+        txn.commit().await?;
+        Ok(assignment)
+    }
+
+    pub async fn list_assignments(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        query: &AssignmentQuery,
+    ) -> Result<Vec<transport_assignments::Model>, DbErr> {
+        let mut select = TransportAssignments::scoped(tenant_id);
+        if let Some(student_id) = query.student_id {
+            select = select.filter(transport_assignments::Column::StudentId.eq(student_id));
+        }
+        if let Some(route_id) = query.route_id {
+            select = select.filter(transport_assignments::Column::RouteId.eq(route_id));
+        }
+        select.order_by_asc(transport_assignments::Column::Id).all(db).await
+    }
+
+    pub async fn remove_assignment(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        context: &AuditContext,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some(assignment) = TransportAssignments::scoped(tenant_id)
+            .filter(transport_assignments::Column::Id.eq(id))
+            .one(&txn)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let before = audit::snapshot(&AssignmentResponse::from(assignment.clone()));
+        assignment.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "transport_assignments", id, before, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    // A route's stops in calling order with the students boarding at each,
+    // by name. Students in the trash are left off.
+    pub async fn manifest(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        route_id: i32,
+    ) -> Result<Vec<(transport_stops::Model, Vec<users::Model>)>, DbErr> {
+        let stops = TransportStops::find()
+            .filter(transport_stops::Column::RouteId.eq(route_id))
+            .order_by_asc(transport_stops::Column::Position)
+            .all(db)
+            .await?;
+        let assignments = TransportAssignments::scoped(tenant_id)
+            .filter(transport_assignments::Column::RouteId.eq(route_id))
+            .all(db)
+            .await?;
+        let students: HashMap<i32, users::Model> = user_repository::live(tenant_id)
+            .filter(users::Column::Id.is_in(assignments.iter().map(|assignment| assignment.student_id)))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|student| (student.id, student))
+            .collect();
+
+        Ok(stops
+            .into_iter()
+            .map(|stop| {
+                let mut boarding: Vec<users::Model> = assignments
+                    .iter()
+                    .filter(|assignment| assignment.stop_id == stop.id)
+                    .filter_map(|assignment| students.get(&assignment.student_id).cloned())
+                    .collect();
+                boarding.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+                (stop, boarding)
+            })
+            .collect())
+    }
+}
+
+async fn lock_route<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    id: i32,
+) -> Result<transport_routes::Model, TransportError> {
+    TransportRoutes::scoped(tenant_id)
+        .filter(transport_routes::Column::Id.eq(id))
+        .lock_exclusive()
+        .one(conn)
+        .await?
+        .ok_or(TransportError::RouteNotFound)
+}
+
+async fn seats_taken<C: ConnectionTrait>(conn: &C, route_id: i32) -> Result<i32, DbErr> {
+    let count = TransportAssignments::find()
+        .filter(transport_assignments::Column::RouteId.eq(route_id))
+        .count(conn)
+        .await?;
+    Ok(count as i32)
+}
+
+async fn registration_taken<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    registration: &str,
+    except: Option<i32>,
+) -> Result<bool, DbErr> {
+    let mut query = TransportVehicles::scoped(tenant_id).filter(transport_vehicles::Column::Registration.eq(registration));
+    if let Some(id) = except {
+        query = query.filter(transport_vehicles::Column::Id.ne(id));
+    }
+    Ok(query.count(conn).await? > 0)
+}
+
+// Check a route's name is free and its vehicle and driver are active ones
+// of the school; returns the vehicle
+async fn check_route<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: i32,
+    request: &RouteRequest,
+    except: Option<i32>,
+) -> Result<Option<transport_vehicles::Model>, TransportError> {
+    let mut named = TransportRoutes::scoped(tenant_id).filter(transport_routes::Column::Name.eq(request.name.as_str()));
+    if let Some(id) = except {
+        named = named.filter(transport_routes::Column::Id.ne(id));
+    }
+    if named.count(conn).await? > 0 {
+        return Err(TransportError::RouteNameTaken(request.name.clone()));
+    }
+
+    let vehicle = match request.vehicle_id {
+        Some(vehicle_id) => Some(
+            TransportVehicles::scoped(tenant_id)
+                .filter(transport_vehicles::Column::Id.eq(vehicle_id))
+                .filter(transport_vehicles::Column::IsActive.eq(true))
+                .one(conn)
+                .await?
+                .ok_or(TransportError::VehicleNotFound)?,
+        ),
+        None => None,
+    };
+    if let Some(driver_id) = request.driver_id {
+        TransportDrivers::scoped(tenant_id)
+            .filter(transport_drivers::Column::Id.eq(driver_id))
+            .filter(transport_drivers::Column::IsActive.eq(true))
+            .one(conn)
+            .await?
+            .ok_or(TransportError::DriverNotFound)?;
+    }
+    Ok(vehicle)
+}
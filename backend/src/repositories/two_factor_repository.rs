@@ -0,0 +1,161 @@
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, *};
+
+use crate::application::audit::AuditContext;
+use crate::application::{auth, two_factor};
+use crate::entities::{prelude::*, user_recovery_codes, user_totp};
+use crate::repositories::audit_repository::AuditRepository;
+
+pub struct TwoFactorRepository;
+
+impl TwoFactorRepository {
+    // Get a user's authenticator, confirmed or not
+    pub async fn find(db: &DatabaseConnection, user_id: i32) -> Result<Option<user_totp::Model>, DbErr> {
+        UserTotp::find_by_id(user_id).one(db).await
+    }
+
+    // Whether the user has a confirmed authenticator
+    pub async fn is_enabled(db: &DatabaseConnection, user_id: i32) -> Result<bool, DbErr> {
+        Ok(Self::find(db, user_id)
+            .await?
+            .is_some_and(|totp| totp.confirmed_at.is_some()))
+    }
+
+    // Store a new, unconfirmed secret, replacing any earlier unfinished enrollment
+    pub async fn start_enrollment(db: &DatabaseConnection, user_id: i32, secret: String) -> Result<(), DbErr> {
+        UserTotp::delete_by_id(user_id).exec(db).await?;
+
+        let totp = user_totp::ActiveModel {
+            user_id: Set(user_id),
+            secret: Set(secret),
+            confirmed_at: Set(None),
+            last_used_step: Set(None),
+            ..Default::default()
+        };
+        totp.insert(db).await?;
+        Ok(())
+    }
+
+    // Turn an enrollment on once the user proved their app works, and issue recovery codes
+    pub async fn confirm(
+        db: &DatabaseConnection,
+        user_id: i32,
+        step: i64,
+        recovery_codes: &[String],
+    ) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+
+        UserTotp::update_many()
+            .col_expr(user_totp::Column::ConfirmedAt, Expr::value(Utc::now().naive_utc()))
+            .col_expr(user_totp::Column::LastUsedStep, Expr::value(step))
+            .filter(user_totp::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        Self::insert_recovery_codes(&txn, user_id, recovery_codes).await?;
+
+        txn.commit().await
+    }
+
+    // Record the time step of an accepted code. Returns false if that step (or a
+    // later one) was already used, e.g. by a concurrent login with the same code.
+    pub async fn use_step(db: &DatabaseConnection, user_id: i32, step: i64) -> Result<bool, DbErr> {
+        let result = UserTotp::update_many()
+            .col_expr(user_totp::Column::LastUsedStep, Expr::value(step))
+            .filter(user_totp::Column::UserId.eq(user_id))
+            .filter(
+                Condition::any()
+                    .add(user_totp::Column::LastUsedStep.is_null())
+                    .add(user_totp::Column::LastUsedStep.lt(step)),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    // Throw away all recovery codes and store new ones
+    pub async fn replace_recovery_codes(
+        db: &DatabaseConnection,
+        user_id: i32,
+        recovery_codes: &[String],
+    ) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        Self::insert_recovery_codes(&txn, user_id, recovery_codes).await?;
+        txn.commit().await
+    }
+
+    async fn insert_recovery_codes<C: ConnectionTrait>(
+        conn: &C,
+        user_id: i32,
+        recovery_codes: &[String],
+    ) -> Result<(), DbErr> {
+        UserRecoveryCodes::delete_many()
+            .filter(user_recovery_codes::Column::UserId.eq(user_id))
+            .exec(conn)
+            .await?;
+
+        let rows = recovery_codes
+            .iter()
+            .map(|code| {
+                let code_hash = auth::hash_password(&two_factor::normalize_recovery_code(code))
+                    .map_err(|_| DbErr::Custom("Failed to hash recovery code".to_string()))?;
+                Ok(user_recovery_codes::ActiveModel {
+                    user_id: Set(user_id),
+                    code_hash: Set(code_hash),
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<Vec<_>, DbErr>>()?;
+        if !rows.is_empty() {
+            UserRecoveryCodes::insert_many(rows).exec(conn).await?;
+        }
+        Ok(())
+    }
+
+    // Spend a recovery code; each code works exactly once
+    pub async fn use_recovery_code(db: &DatabaseConnection, user_id: i32, code: &str) -> Result<bool, DbErr> {
+        let code = two_factor::normalize_recovery_code(code);
+        let unused = UserRecoveryCodes::find()
+            .filter(user_recovery_codes::Column::UserId.eq(user_id))
+            .filter(user_recovery_codes::Column::UsedAt.is_null())
+            .all(db)
+            .await?;
+
+        let Some(matched) = unused
+            .into_iter()
+            .find(|row| auth::verify_password(&code, &row.code_hash))
+        else {
+            return Ok(false);
+        };
+
+        // Conditional update so two concurrent logins cannot both spend the code
+        let result = UserRecoveryCodes::update_many()
+            .col_expr(user_recovery_codes::Column::UsedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(user_recovery_codes::Column::Id.eq(matched.id))
+            .filter(user_recovery_codes::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    // Number of recovery codes the user has left
+    pub async fn remaining_recovery_codes(db: &DatabaseConnection, user_id: i32) -> Result<u64, DbErr> {
+        UserRecoveryCodes::find()
+            .filter(user_recovery_codes::Column::UserId.eq(user_id))
+            .filter(user_recovery_codes::Column::UsedAt.is_null())
+            .count(db)
+            .await
+    }
+
+    // Remove the authenticator and its recovery codes. `action` tells the audit
+    // log whether the user turned it off or an admin reset it; no secrets are logged.
+    pub async fn disable(db: &DatabaseConnection, user_id: i32, action: &str, context: &AuditContext) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        UserRecoveryCodes::delete_many()
+            .filter(user_recovery_codes::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        UserTotp::delete_by_id(user_id).exec(&txn).await?;
+        AuditRepository::record(&txn, context, action, "users", user_id, None, None).await?;
+        txn.commit().await
+    }
+}
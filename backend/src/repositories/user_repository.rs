@@ -1,22 +1,71 @@
+use futures::{Stream, TryStreamExt};
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
 use sea_orm::*;
 use crate::entities::{users, prelude::Users};
 use crate::dto::user::{UserResponse, CreateUserRequest, UsersListResponse};
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2,
-};
+use crate::application::audit::{self, AuditContext};
+use crate::application::auth;
+use crate::repositories::audit_repository::AuditRepository;
+use crate::repositories::tenant_repository::TenantScoped;
 
 pub struct UserRepository;
 
+// Hash a password with argon2 and a fresh salt
+fn hash_password(password: &str) -> Result<String, DbErr> {
+    auth::hash_password(password).map_err(|_| DbErr::Custom("Failed to hash password".to_string()))
+}
+
+// A school's users that are not in the trash; every normal query starts here
+pub fn live(tenant_id: i32) -> Select<Users> {
+    Users::scoped(tenant_id).filter(users::Column::DeletedAt.is_null())
+}
+
+// Match a user's email whatever its case; rows stored before emails were
+// normalized may still have capitals
+pub fn email_is(email: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col((users::Entity, users::Column::Email)))).eq(auth::normalize_email(email))
+}
+
+fn trashed(tenant_id: i32, id: i32) -> Select<Users> {
+    Users::scoped(tenant_id)
+        .filter(users::Column::Id.eq(id))
+        .filter(users::Column::DeletedAt.is_not_null())
+}
+
+fn trashed_with_email(tenant_id: i32, email: &str) -> Select<Users> {
+    Users::scoped(tenant_id)
+        .filter(email_is(email))
+        .filter(users::Column::DeletedAt.is_not_null())
+}
+
+// A user whose token, issued at `issued_at`, still lets them in: not in the
+// trash, not deactivated and with no password change since
+fn signed_in(tenant_id: i32, id: i32, issued_at: chrono::NaiveDateTime) -> Select<Users> {
+    live(tenant_id)
+        .filter(users::Column::Id.eq(id))
+        .filter(users::Column::IsActive.eq(true))
+        .filter(
+            Condition::any()
+                .add(users::Column::PasswordChangedAt.is_null())
+                .add(users::Column::PasswordChangedAt.lte(issued_at)),
+        )
+}
+
+// Users, in any school, in the trash since before `cutoff`
+fn purgeable(cutoff: chrono::NaiveDateTime) -> Select<Users> {
+    Users::find().filter(users::Column::DeletedAt.lt(cutoff))
+}
+
 impl UserRepository {
     // Get all users
-    pub async fn find_all(db: &DatabaseConnection) -> Result<UsersListResponse, DbErr> {
-        let users_list = Users::find().all(db).await?;
+    pub async fn find_all(db: &DatabaseConnection, tenant_id: i32) -> Result<UsersListResponse, DbErr> {
+        let users_list = live(tenant_id).all(db).await?;
         
         let users: Vec<UserResponse> = users_list
             .into_iter()
             .map(|user| UserResponse {
                 id: user.id,
+                tenant_id: user.tenant_id,
                 email: user.email,
                 full_name: user.full_name,
                 role: user.role,
@@ -30,12 +79,23 @@ impl UserRepository {
         Ok(UsersListResponse { users, total })
     }
     
+    // Stream all users row by row (used by exports, so large tables are never held in memory)
+    pub fn stream_all(db: DatabaseConnection, tenant_id: i32) -> impl Stream<Item = Result<UserResponse, DbErr>> + Send + 'static {
+        async_stream::try_stream! {
+            let mut rows = live(tenant_id).order_by_asc(users::Column::Id).stream(&db).await?;
+            while let Some(user) = rows.try_next().await? {
+                yield UserResponse::from(user);
+            }
+        }
+    }
+    
     // Get user by ID
-    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> Result<Option<UserResponse>, DbErr> {
-        let user = Users::find_by_id(id).one(db).await?;
+    pub async fn find_by_id(db: &DatabaseConnection, tenant_id: i32, id: i32) -> Result<Option<UserResponse>, DbErr> {
+        let user = live(tenant_id).filter(users::Column::Id.eq(id)).one(db).await?;
         
         Ok(user.map(|u| UserResponse {
             id: u.id,
+            tenant_id: u.tenant_id,
             email: u.email,
             full_name: u.full_name,
             role: u.role,
@@ -44,49 +104,307 @@ impl UserRepository {
         }))
     }
     
+    // Whether a token the user was issued at `issued_at` is still good: they
+    // exist, are not in the trash or deactivated, and have not changed their
+    // password since
+    pub async fn accepts_token(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: i32,
+        issued_at: chrono::NaiveDateTime,
+    ) -> Result<bool, DbErr> {
+        Ok(signed_in(tenant_id, id, issued_at).count(db).await? > 0)
+    }
+    
+    // Get user by email
+    pub async fn find_by_email(db: &DatabaseConnection, tenant_id: i32, email: &str) -> Result<Option<UserResponse>, DbErr> {
+        let user = live(tenant_id)
+            .filter(email_is(email))
+            .one(db)
+            .await?;
+        
+        Ok(user.map(UserResponse::from))
+    }
+    
+    // Check email and password of an active user
+    pub async fn authenticate(db: &DatabaseConnection, tenant_id: i32, email: &str, password: &str) -> Result<Option<UserResponse>, DbErr> {
+        let user = live(tenant_id)
+            .filter(email_is(email))
+            .filter(users::Column::IsActive.eq(true))
+            .one(db)
+            .await?;
+        
+        let Some(user) = user else {
+            return Ok(None);
+        };
+        if !auth::verify_password(password, &user.password_hash) {
+            return Ok(None);
+        }
+        
+        // The password is known right now, so this is the moment to move old
+        // hashes to the current argon2 parameters
+        if auth::needs_rehash(&user.password_hash) {
+            let mut upgraded: users::ActiveModel = user.clone().into();
+            upgraded.password_hash = Set(hash_password(password)?);
+            upgraded.update(db).await?;
+            tracing::info!("Upgraded password hash of {}", user.email);
+        }
+        
+        Ok(Some(UserResponse::from(user)))
+    }
+    
+    // Get the full user row by email, including where the account comes from
+    pub async fn find_account(db: &DatabaseConnection, tenant_id: i32, email: &str) -> Result<Option<users::Model>, DbErr> {
+        live(tenant_id)
+            .filter(email_is(email))
+            .one(db)
+            .await
+    }
+    
+    // Get all users created by an external source (e.g. "ldap")
+    pub async fn find_by_auth_source(db: &DatabaseConnection, tenant_id: i32, auth_source: &str) -> Result<Vec<users::Model>, DbErr> {
+        live(tenant_id)
+            .filter(users::Column::AuthSource.eq(auth_source))
+            .order_by_asc(users::Column::Id)
+            .all(db)
+            .await
+    }
+    
+    // Create a user on first single sign-on or directory sync. The account gets a
+    // random password nobody knows, so it can only be used through its source.
+    pub async fn provision(db: &DatabaseConnection, tenant_id: i32, email: &str, full_name: &str, role: &str, auth_source: &str, context: &AuditContext) -> Result<UserResponse, DbErr> {
+        let txn = db.begin().await?;
+        let user = users::ActiveModel {
+            tenant_id: Set(tenant_id),
+            email: Set(auth::normalize_email(email)),
+            password_hash: Set(hash_password(&uuid::Uuid::new_v4().to_string())?),
+            full_name: Set(full_name.to_string()),
+            role: Set(role.to_string()),
+            is_active: Set(true),
+            auth_source: Set(auth_source.to_string()),
+            ..Default::default()
+        };
+        
+        let user = UserResponse::from(user.insert(&txn).await?);
+        AuditRepository::record(&txn, context, "create", "users", user.id, None, audit::snapshot(&user)).await?;
+        txn.commit().await?;
+        Ok(user)
+    }
+    
+    // Change a user's role (e.g. when the identity provider says so)
+    pub async fn update_role(db: &DatabaseConnection, tenant_id: i32, id: i32, role: &str, context: &AuditContext) -> Result<Option<UserResponse>, DbErr> {
+        let txn = db.begin().await?;
+        let Some(user) = live(tenant_id).filter(users::Column::Id.eq(id)).one(&txn).await? else {
+            return Ok(None);
+        };
+        
+        let before = audit::snapshot(&UserResponse::from(user.clone()));
+        let mut user: users::ActiveModel = user.into();
+        user.role = Set(role.to_string());
+        user.updated_at = Set(chrono::Utc::now().naive_utc());
+        let user = UserResponse::from(user.update(&txn).await?);
+        AuditRepository::record(&txn, context, "update_role", "users", id, before, audit::snapshot(&user)).await?;
+        txn.commit().await?;
+        Ok(Some(user))
+    }
+    
+    // Bring a directory user in line with the directory entry; an active
+    // user is no longer one the sync deactivated
+    pub async fn update_directory_user(db: &DatabaseConnection, tenant_id: i32, id: i32, full_name: &str, role: &str, is_active: bool, context: &AuditContext) -> Result<Option<UserResponse>, DbErr> {
+        let txn = db.begin().await?;
+        let Some(user) = live(tenant_id).filter(users::Column::Id.eq(id)).one(&txn).await? else {
+            return Ok(None);
+        };
+        
+        let before = UserResponse::from(user.clone());
+        let mut user: users::ActiveModel = user.into();
+        user.full_name = Set(full_name.to_string());
+        user.role = Set(role.to_string());
+        user.is_active = Set(is_active);
+        if is_active {
+            user.directory_deactivated_at = Set(None);
+        }
+        user.updated_at = Set(chrono::Utc::now().naive_utc());
+        let user = UserResponse::from(user.update(&txn).await?);
+        // Logins refresh the account every time; only real changes are logged
+        let (before, after) = (audit::snapshot(&before), audit::snapshot(&user));
+        if before != after {
+            AuditRepository::record(&txn, context, "update", "users", id, before, after).await?;
+        }
+        txn.commit().await?;
+        Ok(Some(user))
+    }
+    
+    // Deactivate a user who has left the directory, remembering that the sync
+    // did it so they come back if they return; deactivated users cannot log in
+    pub async fn deactivate_directory_user(db: &DatabaseConnection, tenant_id: i32, id: i32, context: &AuditContext) -> Result<Option<UserResponse>, DbErr> {
+        let txn = db.begin().await?;
+        let Some(user) = live(tenant_id).filter(users::Column::Id.eq(id)).one(&txn).await? else {
+            return Ok(None);
+        };
+        
+        let now = chrono::Utc::now().naive_utc();
+        let before = audit::snapshot(&UserResponse::from(user.clone()));
+        let mut user: users::ActiveModel = user.into();
+        user.is_active = Set(false);
+        user.directory_deactivated_at = Set(Some(now));
+        user.updated_at = Set(now);
+        let user = UserResponse::from(user.update(&txn).await?);
+        AuditRepository::record(&txn, context, "deactivate", "users", id, before, audit::snapshot(&user)).await?;
+        txn.commit().await?;
+        Ok(Some(user))
+    }
+    
     // Create new user
-    pub async fn create(db: &DatabaseConnection, data: CreateUserRequest) -> Result<UserResponse, DbErr> {
+    pub async fn create(db: &DatabaseConnection, tenant_id: i32, data: CreateUserRequest, context: &AuditContext) -> Result<UserResponse, DbErr> {
         // Hash password
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(data.password.as_bytes(), &salt)
-            .map_err(|_| DbErr::Custom("Failed to hash password".to_string()))?
-            .to_string();
+        let password_hash = hash_password(&data.password)?;
         
         // Create user
         let user = users::ActiveModel {
-            email: Set(data.email),
+            tenant_id: Set(tenant_id),
+            email: Set(auth::normalize_email(&data.email)),
             password_hash: Set(password_hash),
             full_name: Set(data.full_name),
             role: Set(data.role),
             is_active: Set(true),
+            auth_source: Set("local".to_string()),
             ..Default::default()
         };
         
-        let result = user.insert(db).await?;
+        let txn = db.begin().await?;
+        let result = user.insert(&txn).await?;
         
-        Ok(UserResponse {
+        let user = UserResponse {
             id: result.id,
+            tenant_id: result.tenant_id,
             email: result.email,
             full_name: result.full_name,
             role: result.role,
             is_active: result.is_active,
             created_at: result.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
-        })
+        };
+        AuditRepository::record(&txn, context, "create", "users", user.id, None, audit::snapshot(&user)).await?;
+        txn.commit().await?;
+        
+        Ok(user)
     }
     
-    // Delete user
-    pub async fn delete(db: &DatabaseConnection, id: i32) -> Result<bool, DbErr> {
-        let user = Users::find_by_id(id).one(db).await?;
+    // Move a user to the trash. The row stays (so history that points at it
+    // survives) but is hidden from every query until restored or purged.
+    pub async fn delete(db: &DatabaseConnection, tenant_id: i32, id: i32, context: &AuditContext) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some(user) = live(tenant_id).filter(users::Column::Id.eq(id)).one(&txn).await? else {
+            return Ok(false);
+        };
         
-        match user {
-            Some(user) => {
-                let user: users::ActiveModel = user.into();
-                user.delete(db).await?;
-                Ok(true)
-            }
-            None => Ok(false),
-        }
+        let before = audit::snapshot(&UserResponse::from(user.clone()));
+        let mut user: users::ActiveModel = user.into();
+        user.deleted_at = Set(Some(chrono::Utc::now().naive_utc()));
+        user.update(&txn).await?;
+        AuditRepository::record(&txn, context, "delete", "users", id, before, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+    
+    // Users in the trash, most recently deleted first
+    pub async fn find_deleted(db: &DatabaseConnection, tenant_id: i32) -> Result<Vec<users::Model>, DbErr> {
+        Users::scoped(tenant_id)
+            .filter(users::Column::DeletedAt.is_not_null())
+            .order_by_desc(users::Column::DeletedAt)
+            .all(db)
+            .await
+    }
+    
+    // Whether a trashed user holds this email (it stays taken until purged)
+    pub async fn email_in_trash(db: &DatabaseConnection, tenant_id: i32, email: &str) -> Result<bool, DbErr> {
+        Ok(trashed_with_email(tenant_id, email).count(db).await? > 0)
+    }
+    
+    // Take a user back out of the trash
+    pub async fn restore(db: &DatabaseConnection, tenant_id: i32, id: i32, context: &AuditContext) -> Result<Option<UserResponse>, DbErr> {
+        let txn = db.begin().await?;
+        let Some(user) = trashed(tenant_id, id).one(&txn).await? else {
+            return Ok(None);
+        };
+        
+        let mut user: users::ActiveModel = user.into();
+        user.deleted_at = Set(None);
+        user.updated_at = Set(chrono::Utc::now().naive_utc());
+        let user = UserResponse::from(user.update(&txn).await?);
+        AuditRepository::record(&txn, context, "restore", "users", id, None, audit::snapshot(&user)).await?;
+        txn.commit().await?;
+        Ok(Some(user))
+    }
+    
+    // Delete a trashed user for good, along with everything that cascades from it
+    pub async fn purge(db: &DatabaseConnection, tenant_id: i32, id: i32, context: &AuditContext) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let Some(user) = trashed(tenant_id, id).one(&txn).await? else {
+            return Ok(false);
+        };
+        
+        let before = audit::snapshot(&UserResponse::from(user.clone()));
+        let user: users::ActiveModel = user.into();
+        user.delete(&txn).await?;
+        AuditRepository::record(&txn, context, "purge", "users", id, before, None).await?;
+        txn.commit().await?;
+        Ok(true)
+    }
+    
+    // School and id of users, in any school, that have been in the trash since before `cutoff`
+    pub async fn deleted_before(db: &DatabaseConnection, cutoff: chrono::NaiveDateTime) -> Result<Vec<(i32, i32)>, DbErr> {
+        purgeable(cutoff)
+            .select_only()
+            .column(users::Column::TenantId)
+            .column(users::Column::Id)
+            .into_tuple()
+            .all(db)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emails_are_matched_lowercased() {
+        let query = live(1).filter(email_is("  Ada.Okafor@Example.COM ")).build(DbBackend::Postgres).to_string();
+        assert!(query.contains(r#"LOWER("users"."email") = 'ada.okafor@example.com'"#), "{}", query);
+    }
+
+    fn sql(select: Select<Users>) -> String {
+        select.build(DbBackend::Postgres).to_string()
+    }
+
+    #[test]
+    fn trashed_users_are_hidden_and_only_they_are_restored_or_purged() {
+        assert!(sql(live(1)).ends_with(r#"WHERE "users"."tenant_id" = 1 AND "users"."deleted_at" IS NULL"#));
+        assert!(sql(trashed(1, 5)).ends_with(
+            r#"WHERE "users"."tenant_id" = 1 AND "users"."id" = 5 AND "users"."deleted_at" IS NOT NULL"#
+        ));
+    }
+
+    #[test]
+    fn deactivated_users_lose_api_access() {
+        let issued_at = chrono::NaiveDate::from_ymd_opt(2026, 10, 1).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        assert!(sql(signed_in(1, 5, issued_at)).ends_with(concat!(
+            r#"WHERE "users"."tenant_id" = 1 AND "users"."deleted_at" IS NULL AND "users"."id" = 5 AND "users"."is_active" = TRUE"#,
+            r#" AND ("users"."password_changed_at" IS NULL OR "users"."password_changed_at" <= '2026-10-01 08:00:00.000000')"#
+        )));
+    }
+
+    #[test]
+    fn deleted_users_keep_their_email_taken() {
+        assert!(sql(trashed_with_email(1, "Ada@Example.com")).ends_with(
+            r#"WHERE "users"."tenant_id" = 1 AND LOWER("users"."email") = 'ada@example.com' AND "users"."deleted_at" IS NOT NULL"#
+        ));
+    }
+
+    #[test]
+    fn purges_cover_every_school_past_the_cutoff() {
+        let cutoff = chrono::NaiveDate::from_ymd_opt(2026, 9, 20).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        assert!(sql(purgeable(cutoff)).ends_with(r#"WHERE "users"."deleted_at" < '2026-09-20 00:00:00.000000'"#));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,209 @@
+use chrono::NaiveDateTime;
+use sea_orm::*;
+use serde_json::Value;
+
+use crate::application::xapi::{same_statement, AttachmentPart, PreparedStatement, XapiError, VOIDED_VERB};
+use crate::entities::{prelude::*, xapi_attachments, xapi_statements};
+use crate::repositories::tenant_repository::TenantScoped;
+use crate::repositories::user_repository;
+
+// Filters of GET /xapi/statements, already resolved to column values
+#[derive(Debug, Default)]
+pub struct StatementFilter {
+    pub actor_ifi: Option<String>,
+    pub verb_id: Option<String>,
+    pub activity_id: Option<String>,
+    pub registration: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    pub ascending: bool,
+}
+
+pub struct XapiRepository;
+
+impl XapiRepository {
+    // Store a batch of statements for a school atomically, applying voids and saving attachments.
+    // Re-sending an identical statement is a no-op; a different one with the same id is a
+    // conflict, and so is an id already taken in another school. With `voider` set, only
+    // statements by that actor can be voided.
+    pub async fn store(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        statements: Vec<PreparedStatement>,
+        attachments: Vec<AttachmentPart>,
+        voider: Option<&str>,
+    ) -> Result<Vec<String>, XapiError> {
+        let txn = db.begin().await?;
+        let mut ids = Vec::with_capacity(statements.len());
+
+        for statement in statements {
+            if let Some(existing) = XapiStatements::find_by_id(&statement.id).one(&txn).await? {
+                if existing.tenant_id != tenant_id || !same_statement(&existing.statement, &statement.statement) {
+                    return Err(XapiError::Conflict(statement.id));
+                }
+                ids.push(statement.id);
+                continue;
+            }
+
+            if let Some(target_id) = &statement.voids {
+                let mut target = XapiStatements::scoped(tenant_id).filter(xapi_statements::Column::Id.eq(target_id.as_str()));
+                if let Some(voider) = voider {
+                    target = target.filter(xapi_statements::Column::ActorIfi.eq(voider));
+                }
+                let target = target
+                    .one(&txn)
+                    .await?
+                    .filter(|target| target.verb_id != VOIDED_VERB)
+                    .ok_or_else(|| XapiError::InvalidVoid(target_id.clone()))?;
+                let mut target: xapi_statements::ActiveModel = target.into();
+                target.voided = Set(true);
+                target.update(&txn).await?;
+            }
+
+            // Actors are linked to rsEdu users through their mbox email
+            let user_id = match &statement.actor_email {
+                Some(email) => user_repository::live(tenant_id)
+                    .filter(user_repository::email_is(email))
+                    .one(&txn)
+                    .await?
+                    .map(|user| user.id),
+                None => None,
+            };
+
+            let row = xapi_statements::ActiveModel {
+                id: Set(statement.id.clone()),
+                tenant_id: Set(tenant_id),
+                actor_ifi: Set(statement.actor_ifi),
+                user_id: Set(user_id),
+                verb_id: Set(statement.verb_id),
+                object_type: Set(statement.object_type),
+                object_id: Set(statement.object_id),
+                registration: Set(statement.registration),
+                voided: Set(false),
+                statement: Set(statement.statement),
+                timestamp: Set(statement.timestamp),
+                stored: Set(statement.stored),
+            };
+            row.insert(&txn).await?;
+            ids.push(statement.id);
+        }
+
+        // Attachments are content-addressed, so an existing hash is the same file
+        for attachment in attachments {
+            if XapiAttachments::find_by_id((tenant_id, attachment.sha2.clone())).one(&txn).await?.is_some() {
+                continue;
+            }
+            let row = xapi_attachments::ActiveModel {
+                tenant_id: Set(tenant_id),
+                sha2: Set(attachment.sha2),
+                content_type: Set(attachment.content_type),
+                length: Set(attachment.content.len() as i64),
+                content: Set(attachment.content),
+                ..Default::default()
+            };
+            row.insert(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(ids)
+    }
+
+    // Get a single statement by id, either among the live or the voided statements
+    pub async fn find_by_id(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        id: &str,
+        voided: bool,
+    ) -> Result<Option<Value>, DbErr> {
+        let statement = XapiStatements::scoped(tenant_id)
+            .filter(xapi_statements::Column::Id.eq(id))
+            .filter(xapi_statements::Column::Voided.eq(voided))
+            .one(db)
+            .await?;
+
+        Ok(statement.map(|statement| statement.statement))
+    }
+
+    // Query live statements; returns one page plus whether more rows follow
+    pub async fn find(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        filter: &StatementFilter,
+        limit: u64,
+        offset: u64,
+    ) -> Result<(Vec<Value>, bool), DbErr> {
+        let mut query = XapiStatements::scoped(tenant_id).filter(xapi_statements::Column::Voided.eq(false));
+
+        if let Some(actor_ifi) = &filter.actor_ifi {
+            query = query.filter(xapi_statements::Column::ActorIfi.eq(actor_ifi.as_str()));
+        }
+        if let Some(verb_id) = &filter.verb_id {
+            query = query.filter(xapi_statements::Column::VerbId.eq(verb_id.as_str()));
+        }
+        if let Some(activity_id) = &filter.activity_id {
+            query = query
+                .filter(xapi_statements::Column::ObjectType.eq("Activity"))
+                .filter(xapi_statements::Column::ObjectId.eq(activity_id.as_str()));
+        }
+        if let Some(registration) = &filter.registration {
+            query = query.filter(xapi_statements::Column::Registration.eq(registration.as_str()));
+        }
+        if let Some(since) = filter.since {
+            query = query.filter(xapi_statements::Column::Stored.gt(since));
+        }
+        if let Some(until) = filter.until {
+            query = query.filter(xapi_statements::Column::Stored.lte(until));
+        }
+
+        query = if filter.ascending {
+            query
+                .order_by_asc(xapi_statements::Column::Stored)
+                .order_by_asc(xapi_statements::Column::Id)
+        } else {
+            query
+                .order_by_desc(xapi_statements::Column::Stored)
+                .order_by_desc(xapi_statements::Column::Id)
+        };
+
+        // Fetch one extra row to know whether there is a next page
+        let mut rows = query.offset(offset).limit(limit + 1).all(db).await?;
+        let more = rows.len() as u64 > limit;
+        rows.truncate(limit as usize);
+
+        Ok((rows.into_iter().map(|row| row.statement).collect(), more))
+    }
+
+    // Load a school's attachment payloads referenced by the given hashes
+    pub async fn find_attachments(
+        db: &DatabaseConnection,
+        tenant_id: i32,
+        hashes: Vec<String>,
+    ) -> Result<Vec<AttachmentPart>, DbErr> {
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rows = XapiAttachments::scoped(tenant_id)
+            .filter(xapi_attachments::Column::Sha2.is_in(hashes))
+            .all(db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AttachmentPart {
+                sha2: row.sha2,
+                content_type: row.content_type,
+                content: row.content,
+            })
+            .collect())
+    }
+
+    // Timestamp up to which query results are complete
+    pub async fn consistent_through(db: &DatabaseConnection, tenant_id: i32) -> Result<Option<NaiveDateTime>, DbErr> {
+        let latest = XapiStatements::scoped(tenant_id)
+            .order_by_desc(xapi_statements::Column::Stored)
+            .one(db)
+            .await?;
+
+        Ok(latest.map(|statement| statement.stored))
+    }
+}
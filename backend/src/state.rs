@@ -0,0 +1,36 @@
+use axum::extract::FromRef;
+use sea_orm::DatabaseConnection;
+
+use crate::application::directory::LdapDirectory;
+use crate::application::encryption::Keyring;
+use crate::application::library::LibraryPolicy;
+use crate::application::login_guard::LoginGuard;
+use crate::application::lti::ToolKey;
+use crate::application::oidc::OidcClient;
+use crate::application::password::PasswordPolicy;
+use crate::config::Config;
+use crate::infrastructure::jwks::JwksCache;
+use crate::infrastructure::mailer::SharedMailer;
+use crate::infrastructure::notifications::NotificationHub;
+use crate::infrastructure::payments::PaymentGateway;
+
+// Shared application state. Handlers can still extract `State<DatabaseConnection>`
+// (or any other field) directly thanks to `FromRef`.
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub db: DatabaseConnection,
+    pub config: Config,
+    pub http: reqwest::Client,
+    pub jwks: JwksCache,
+    pub lti_key: Option<ToolKey>,
+    pub oidc: Option<OidcClient>,
+    pub ldap: Option<LdapDirectory>,
+    pub password_policy: PasswordPolicy,
+    pub mailer: SharedMailer,
+    pub login_guard: LoginGuard,
+    pub keyring: Keyring,
+    // Online fee payments; None when no provider is configured
+    pub payments: Option<PaymentGateway>,
+    pub library_policy: LibraryPolicy,
+    pub notifications: NotificationHub,
+}